@@ -7,17 +7,14 @@ use micro_rdk::{
     common::{
         config::ConfigType,
         registry::{ComponentRegistry, Dependency, RegistryError},
-        sensor::{
-            GenericReadingsResult, Readings, Sensor, SensorError, SensorResult, SensorT,
-            SensorType, TypedReadingsResult,
-        },
+        sensor::{Sensor, SensorError, SensorT, SensorType, TypedReadingsResult},
         status::{Status, StatusError},
     },
     esp32::esp_idf_svc::sys::esp_get_free_heap_size,
-    DoCommand,
+    DoCommand, SensorReadings,
 };
 
-#[derive(DoCommand)]
+#[derive(DoCommand, SensorReadings)]
 pub struct FreeHeapSensor;
 
 pub fn register_models(registry: &mut ComponentRegistry) -> Result<(), RegistryError> {
@@ -37,16 +34,6 @@ impl FreeHeapSensor {
 }
 
 impl Sensor for FreeHeapSensor {}
-impl Readings for FreeHeapSensor {
-    fn get_generic_readings(&mut self) -> Result<GenericReadingsResult, SensorError> {
-        Ok(self
-            .get_readings()?
-            .into_iter()
-            .map(|v| (v.0, SensorResult::<f64> { value: v.1 }.into()))
-            .collect())
-    }
-}
-
 impl SensorT<f64> for FreeHeapSensor {
     fn get_readings(&self) -> Result<TypedReadingsResult<f64>, SensorError> {
         log::debug!("free-heap sensor - get readings called");
@@ -9,14 +9,11 @@ use micro_rdk::{
         board::Board,
         config::ConfigType,
         registry::{get_board_from_dependencies, ComponentRegistry, Dependency, RegistryError},
-        sensor::{
-            GenericReadingsResult, Readings, Sensor, SensorError, SensorResult, SensorT,
-            SensorType, TypedReadingsResult,
-        },
+        sensor::{Sensor, SensorError, SensorT, SensorType, TypedReadingsResult},
         status::{Status, StatusError},
     },
     google::protobuf,
-    DoCommand,
+    DoCommand, SensorReadings,
 };
 
 pub fn register_models(registry: &mut ComponentRegistry) -> Result<(), RegistryError> {
@@ -24,7 +21,7 @@ pub fn register_models(registry: &mut ComponentRegistry) -> Result<(), RegistryE
     Ok(())
 }
 
-#[derive(DoCommand)]
+#[derive(DoCommand, SensorReadings)]
 pub struct MoistureSensor {
     reader: AnalogReaderType<u16>,
 }
@@ -48,16 +45,6 @@ impl MoistureSensor {
 
 impl Sensor for MoistureSensor {}
 
-impl Readings for MoistureSensor {
-    fn get_generic_readings(&mut self) -> Result<GenericReadingsResult, SensorError> {
-        Ok(self
-            .get_readings()?
-            .into_iter()
-            .map(|v| (v.0, SensorResult::<f64> { value: v.1 }.into()))
-            .collect())
-    }
-}
-
 impl SensorT<f64> for MoistureSensor {
     fn get_readings(&self) -> Result<TypedReadingsResult<f64>, SensorError> {
         let reading = self.reader.lock().unwrap().read()?;
@@ -112,3 +112,37 @@ impl Status for WaterPump {
         }))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use micro_rdk::common::{
+        config::Kind as AttributeKind,
+        generic::GenericError,
+        test_utils::{build_resource, command, do_command, fake_board_dependency},
+    };
+    use micro_rdk::google::protobuf::value::Kind;
+
+    #[test]
+    fn test_water_pump_do_command() {
+        let mut pump = build_resource(
+            WaterPump::from_config,
+            [
+                ("pin", AttributeKind::NumberValue(12.0)),
+                ("led", AttributeKind::NumberValue(2.0)),
+            ],
+            vec![fake_board_dependency()],
+        )
+        .unwrap();
+        // the derived DoCommand doesn't handle any command
+        assert!(matches!(
+            do_command(&mut pump, command([("set_power", Kind::NumberValue(0.5))])),
+            Err(GenericError::MethodUnimplemented(_))
+        ));
+
+        pump.set_power(0.5).unwrap();
+        assert!(pump.is_moving().unwrap());
+        pump.stop().unwrap();
+        assert!(!pump.is_moving().unwrap());
+    }
+}
@@ -7,17 +7,14 @@ use micro_rdk::{
     common::{
         config::ConfigType,
         registry::{ComponentRegistry, Dependency, RegistryError},
-        sensor::{
-            GenericReadingsResult, Readings, Sensor, SensorError, SensorResult, SensorT,
-            SensorType, TypedReadingsResult,
-        },
+        sensor::{Sensor, SensorError, SensorT, SensorType, TypedReadingsResult},
         status::{Status, StatusError},
     },
     esp32::esp_idf_svc::sys::{esp, esp_wifi_sta_get_ap_info, wifi_ap_record_t},
-    DoCommand,
+    DoCommand, SensorReadings,
 };
 
-#[derive(DoCommand)]
+#[derive(DoCommand, SensorReadings)]
 pub struct WifiRSSISensor;
 
 pub fn register_models(registry: &mut ComponentRegistry) -> Result<(), RegistryError> {
@@ -38,16 +35,6 @@ impl WifiRSSISensor {
 
 impl Sensor for WifiRSSISensor {}
 
-impl Readings for WifiRSSISensor {
-    fn get_generic_readings(&mut self) -> Result<GenericReadingsResult, SensorError> {
-        Ok(self
-            .get_readings()?
-            .into_iter()
-            .map(|v| (v.0, SensorResult::<f64> { value: v.1 }.into()))
-            .collect())
-    }
-}
-
 impl SensorT<f64> for WifiRSSISensor {
     fn get_readings(&self) -> Result<TypedReadingsResult<f64>, SensorError> {
         log::debug!("wifi-rssi sensor - get readings called");
@@ -22,6 +22,15 @@ use super::{
     sensor::{generic_c_sensor, generic_c_sensor_config},
 };
 
+const PROVISIONING_MANUFACTURER: &str = match option_env!("MICRO_RDK_PROVISIONING_MANUFACTURER") {
+    Some(manufacturer) => manufacturer,
+    None => "viam",
+};
+const PROVISIONING_MODEL: &str = match option_env!("MICRO_RDK_PROVISIONING_MODEL") {
+    Some(model) => model,
+    None => "ffi-provisioning",
+};
+
 #[allow(non_camel_case_types)]
 pub struct viam_server_context {
     registry: Box<ComponentRegistry>,
@@ -43,8 +52,8 @@ extern "C" {
 pub extern "C" fn init_viam_server_context() -> *mut viam_server_context {
     let registry = Box::<ComponentRegistry>::default();
     let mut provisioning_info = ProvisioningInfo::default();
-    provisioning_info.set_manufacturer("viam".to_owned());
-    provisioning_info.set_model("ffi-provisioning".to_owned());
+    provisioning_info.set_manufacturer(PROVISIONING_MANUFACTURER.to_owned());
+    provisioning_info.set_model(PROVISIONING_MODEL.to_owned());
     Box::into_raw(Box::new(viam_server_context {
         registry,
         provisioning_info,
@@ -14,6 +14,18 @@ pub enum Error {
     FileError(std::io::Error),
     #[error("Binary Retrieval Error: {0}")]
     BinaryRetrievalError(RequestError),
+    #[error("Binary Retrieval Error after {0} attempt(s): {1}")]
+    BinaryDownloadError(u32, RequestError),
+    #[error("Checksum Mismatch: expected {0}, computed {1}")]
+    ChecksumMismatch(String, String),
+    #[error("Checksum Format Error: {0}")]
+    ChecksumFormatError(String),
+    #[error("Checksum Missing: {0}")]
+    ChecksumMissing(String),
+    #[error("Unsupported Chip: no micro-RDK release is published for {0}")]
+    UnsupportedChip(String),
+    #[error("Release Asset Not Found: {0}")]
+    ReleaseAssetNotFound(String),
     #[error("Binary Too Small, File Size: {0}")]
     BinaryEditError(u64),
     #[error("Binary Too Large, File Size: {0}")]
@@ -28,6 +40,8 @@ pub enum Error {
     AsyncError(std::io::Error),
     #[error("Missing Config Info: {0}")]
     MissingConfigInfo(String),
+    #[error("Invalid App Address: {0}")]
+    InvalidAppAddress(String),
     #[error("Config Request Error: {0}")]
     ConfigRequestError(String),
     #[error("Certificate Request Error: {0}")]
@@ -38,6 +52,8 @@ pub enum Error {
     FlashConnect,
     #[error("EspFlash Flash error: {0}")]
     EspFlashError(EspFlashError),
+    #[error("Flash write failed after {0} attempts: {1}")]
+    FlashWriteRetriesExhausted(u32, EspFlashError),
     #[error("Monitor serial error: {0}")]
     MonitorError(String),
     #[error("Unimplemented command: {0}")]
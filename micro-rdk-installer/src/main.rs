@@ -3,6 +3,7 @@ use std::{
     fs::{self, File, OpenOptions},
     io::{Read, Seek, SeekFrom, Write},
     path::PathBuf,
+    time::Duration,
 };
 
 #[cfg(target_family = "unix")]
@@ -13,20 +14,23 @@ use std::os::windows::fs::FileExt;
 use clap::{arg, command, Args, Parser, Subcommand};
 use dialoguer::{theme::ColorfulTheme, Input, Password};
 use esp_idf_part::PartitionTable;
-use espflash::cli::{
-    config::Config, connect, monitor::monitor, serial_monitor, ConnectArgs, EspflashProgress,
-    FlashArgs, MonitorArgs,
+use espflash::{
+    cli::{
+        config::Config, connect, monitor::monitor, serial_monitor, ConnectArgs, EspflashProgress,
+        FlashArgs, MonitorArgs,
+    },
+    flasher::Flasher,
 };
 use micro_rdk_installer::{
     error::Error,
     nvs::{
-        data::{ViamFlashStorageData, WifiCredentials},
+        data::{ViamFlashStorageData, WifiCredentials, MAX_WIFI_NETWORKS},
         metadata::read_nvs_metadata,
-        partition::{NVSPartition, NVSPartitionData},
-        request::download_micro_rdk_release,
+        partition::{read_nvs_strings, NVSPartition, NVSPartitionData},
+        request::{download_micro_rdk_release, DownloadOptions, DEFAULT_CHIP},
     },
 };
-use secrecy::Secret;
+use secrecy::{ExposeSecret, Secret};
 use serde::Deserialize;
 use tokio::runtime::Runtime;
 
@@ -34,10 +38,15 @@ const PARTITION_TABLE_ADDR: u32 = 0x8000;
 const PARTITION_TABLE_SIZE: u32 = 0xc00;
 const EMPTY_BYTE: u8 = 0xFF;
 const APP_IMAGE_PARTITION_NAME: &str = "factory";
+const NVS_PARTITION_NAME: &str = "nvs";
 // taken from `espflash::cli::ReadFlashArgs` default values
 const DEFAULT_BLOCK_SIZE: u32 = 0x1000;
 const DEFAULT_MAX_IN_FLIGHT: u32 = 64;
 const DEFAULT_BAUD: u32 = 115_200;
+// the serial link can end up in a bad state (EspError(263)) mid flash, in which case connecting
+// or writing again usually succeeds
+const FLASH_ATTEMPTS: u32 = 3;
+const FLASH_RETRY_BACKOFF: Duration = Duration::from_secs(1);
 
 #[derive(Deserialize, Debug)]
 struct AppCloudConfig {
@@ -58,6 +67,8 @@ enum Commands {
     WriteCredentials(WriteCredentialsArgs),
     CreateNvsPartition(Box<CreateNVSPartitionArgs>),
     Monitor(MonitorArgs),
+    EraseFlash(Box<EraseFlashArgs>),
+    ReadNvs(Box<ReadNvsArgs>),
 }
 
 /// Flash a new micro-RDK app image directly to an ESP32's `factory` partition
@@ -76,8 +87,36 @@ struct AppImageArgs {
 
     /// Version of the compiled micro-RDK server to download.
     /// See https://github.com/viamrobotics/micro-rdk/releases for the version options
+    /// Releases are only built for the esp32, other values of `--chip` need `--binary-path`
     #[arg(long = "version", value_parser = validate_version)]
     version: Option<String>,
+    #[clap(flatten)]
+    download_args: DownloadArgs,
+}
+
+/// Timeout and retries of the release download
+#[derive(Args, Clone)]
+struct DownloadArgs {
+    /// Timeout in seconds of a download attempt
+    #[arg(long = "download-timeout", default_value = "120")]
+    download_timeout: u64,
+    /// Number of times a failed download is retried
+    #[arg(long = "download-retries", default_value = "3")]
+    download_retries: u32,
+    /// Flash the release even when its checksum isn't published (releases are otherwise verified
+    /// against their sha256sums.txt)
+    #[arg(long = "skip-checksum")]
+    skip_checksum: bool,
+}
+
+impl From<&DownloadArgs> for DownloadOptions {
+    fn from(args: &DownloadArgs) -> Self {
+        Self {
+            timeout: Duration::from_secs(args.download_timeout),
+            retries: args.download_retries,
+            skip_checksum: args.skip_checksum,
+        }
+    }
 }
 
 /// Write Wi-Fi and robot credentials to the NVS storage portion of a pre-compiled
@@ -91,14 +130,18 @@ struct WriteCredentialsArgs {
     /// data partition will be edited with Wi-Fi and robot credentials
     #[arg(long = "binary-path")]
     binary_path: String,
-    /// Wi-Fi SSID to write to NVS partition of binary. If not provided, user will be
-    /// prompted for it
-    #[arg(long = "wifi-ssid", required = true)]
+    /// Wi-Fi SSID to write to NVS partition of binary, as the highest priority network.
+    /// Required unless networks are provided with `--wifi-network`
+    #[arg(long = "wifi-ssid", required_unless_present = "wifi_networks")]
     wifi_ssid: Option<String>,
     /// Wi-Fi password to write to NVS partition of binary. If not provided, user will be
     /// prompted for it
     #[arg(long = "wifi-password")]
     wifi_password: Option<Secret<String>>,
+    /// Additional Wi-Fi network formatted as SSID:PASSWORD, repeat the flag to add several.
+    /// Networks are tried in the order given, after the one of `--wifi-ssid` if provided
+    #[arg(long = "wifi-network", value_parser = parse_wifi_network)]
+    wifi_networks: Vec<WifiCredentials>,
 }
 
 /// Flash a pre-compiled binary with the micro-RDK, the robot config, and wifi info
@@ -122,16 +165,51 @@ struct WriteFlashArgs {
     config: Option<String>,
     /// Version of the compiled micro-RDK server to download (ex. v0.2.3, latest).
     /// See https://github.com/viamrobotics/micro-rdk/releases for available versions
+    /// Releases are only built for the esp32, other values of `--chip` need `--binary-path`
     #[arg(long = "version", value_parser = validate_version)]
     version: Option<String>,
-    /// Wi-Fi SSID to write to NVS partition of binary. If not provided, user will be
-    /// prompted for it
-    #[arg(long = "wifi-ssid", required = true)]
+    #[clap(flatten)]
+    download_args: DownloadArgs,
+    /// Wi-Fi SSID to write to NVS partition of binary, as the highest priority network.
+    /// Required unless networks are provided with `--wifi-network`
+    #[arg(long = "wifi-ssid", required_unless_present = "wifi_networks")]
     wifi_ssid: Option<String>,
     /// Wi-Fi password to write to NVS partition of binary. If not provided, user will be
     /// prompted for it
     #[arg(long = "wifi-password")]
     wifi_password: Option<Secret<String>>,
+    /// Additional Wi-Fi network formatted as SSID:PASSWORD, repeat the flag to add several.
+    /// Networks are tried in the order given, after the one of `--wifi-ssid` if provided
+    #[arg(long = "wifi-network", value_parser = parse_wifi_network)]
+    wifi_networks: Vec<WifiCredentials>,
+}
+
+/// Erase the flash of an ESP32 connected to your computer via data cable, or only one of
+/// its partitions
+#[derive(Args)]
+struct EraseFlashArgs {
+    /// from espflash: baud, port
+    #[clap(flatten)]
+    connect_args: ConnectArgs,
+    /// Name of the partition to erase (ex. nvs), as found in the partition table of the
+    /// connected ESP32. The whole flash is erased when not provided
+    #[arg(long = "partition")]
+    partition: Option<String>,
+    /// Erase without asking for confirmation
+    #[arg(long = "yes", short = 'y')]
+    yes: bool,
+}
+
+/// Read the robot credentials and Wi-Fi networks stored in the NVS partition of an ESP32
+/// connected to your computer via data cable
+#[derive(Args)]
+struct ReadNvsArgs {
+    /// from espflash: baud, port
+    #[clap(flatten)]
+    connect_args: ConnectArgs,
+    /// Print the robot secret and Wi-Fi passwords, which are hidden otherwise
+    #[arg(long = "show-secrets")]
+    show_secrets: bool,
 }
 
 /// Generate a binary of a complete NVS data partition that conatins Wi-Fi and security
@@ -147,14 +225,18 @@ struct CreateNVSPartitionArgs {
     // declared in micro-rdk-server/esp32/partitions.csv (0x8000, or 32768)
     #[arg(long = "size", default_value = "32768")]
     size: usize,
-    /// Wi-Fi SSID to write to NVS partition of binary. If not provided, user will be
-    /// prompted for it
+    /// Wi-Fi SSID to write to NVS partition of binary, as the highest priority network. If
+    /// neither this nor `--wifi-network` are provided, user will be prompted for it
     #[arg(long = "wifi-ssid")]
     wifi_ssid: Option<String>,
     /// Wi-Fi password to write to NVS partition of binary. If not provided, user will be
     /// prompted for it
     #[arg(long = "wifi-password")]
     wifi_password: Option<Secret<String>>,
+    /// Additional Wi-Fi network formatted as SSID:PASSWORD, repeat the flag to add several.
+    /// Networks are tried in the order given, after the one of `--wifi-ssid` if provided
+    #[arg(long = "wifi-network", value_parser = parse_wifi_network)]
+    wifi_networks: Vec<WifiCredentials>,
 }
 
 #[derive(Parser)]
@@ -185,6 +267,47 @@ fn validate_version(version: &str) -> Result<String, String> {
     Ok(version.to_owned())
 }
 
+// Secrets are never printed, errors only refer to the SSID
+fn parse_wifi_network(network: &str) -> Result<WifiCredentials, String> {
+    let (ssid, password) = network
+        .split_once(':')
+        .ok_or("Wi-Fi networks should be formatted as SSID:PASSWORD".to_string())?;
+    if ssid.is_empty() {
+        return Err("Wi-Fi network SSID cannot be empty".to_string());
+    }
+    if password.len() > 64 {
+        return Err(format!(
+            "password of Wi-Fi network {:?} is limited to 64 characters or less",
+            ssid
+        ));
+    }
+    Ok(WifiCredentials {
+        ssid: ssid.to_string(),
+        password: Secret::new(password.to_string()),
+    })
+}
+
+/// The Wi-Fi networks in order of priority: the one of `wifi_ssid`, if provided or if there
+/// are no other `wifi_networks` in which case the user is prompted for it, then `wifi_networks`
+fn request_wifi_networks(
+    wifi_ssid: Option<String>,
+    wifi_password: Option<Secret<String>>,
+    wifi_networks: Vec<WifiCredentials>,
+) -> Result<Vec<WifiCredentials>, Error> {
+    let mut networks = Vec::with_capacity(wifi_networks.len() + 1);
+    if wifi_ssid.is_some() || wifi_networks.is_empty() {
+        networks.push(request_wifi(wifi_ssid, wifi_password)?);
+    }
+    networks.extend(wifi_networks);
+    if networks.len() > MAX_WIFI_NETWORKS {
+        return Err(Error::NVSDataProcessingError(format!(
+            "at most {} wifi networks can be stored",
+            MAX_WIFI_NETWORKS
+        )));
+    }
+    Ok(networks)
+}
+
 fn request_wifi(
     wifi_ssid: Option<String>,
     wifi_password: Option<Secret<String>>,
@@ -218,33 +341,50 @@ fn request_wifi(
     Ok(WifiCredentials { ssid, password })
 }
 
+// The device refuses app addresses that aren't absolute http(s) URIs, reject them before
+// they are flashed
+fn validate_app_address(address: &str) -> Result<String, Error> {
+    let url = reqwest::Url::parse(address)
+        .map_err(|e| Error::InvalidAppAddress(format!("{:?} ({})", address, e)))?;
+    if !matches!(url.scheme(), "http" | "https") || url.host_str().is_none() {
+        return Err(Error::InvalidAppAddress(format!(
+            "{:?} should look like https://app.viam.com:443",
+            address
+        )));
+    }
+    Ok(address.to_string())
+}
+
 fn create_nvs_partition_binary(
     config_path: String,
     size: usize,
     wifi_ssid: Option<String>,
     wifi_password: Option<Secret<String>>,
+    wifi_networks: Vec<WifiCredentials>,
 ) -> Result<Vec<u8>, Error> {
     let mut storage_data = ViamFlashStorageData::default();
     let config_str = fs::read_to_string(config_path).map_err(Error::FileError)?;
     let app_config: AppConfig = serde_json::from_str(&config_str)?;
     storage_data.robot_credentials.robot_id = Some(app_config.cloud.r#id.to_string());
-    storage_data.robot_credentials.app_address = Some(app_config.cloud.app_address.to_string());
+    storage_data.robot_credentials.app_address =
+        Some(validate_app_address(&app_config.cloud.app_address)?);
     storage_data.robot_credentials.robot_secret = Some(app_config.cloud.secret);
-    let wifi_cred = request_wifi(wifi_ssid, wifi_password)?;
-    storage_data.wifi = Some(wifi_cred);
+    let mut networks = request_wifi_networks(wifi_ssid, wifi_password, wifi_networks)?;
     log::info!(
-        "Creating NVS partition with robot id: {:?}, wifi ssid: {:?}.",
+        "Creating NVS partition with robot id: {:?}, wifi ssids: {:?}.",
         storage_data
             .robot_credentials
             .robot_id
             .clone()
             .unwrap_or(String::from("none")),
-        storage_data
-            .wifi
-            .clone()
-            .ok_or(Error::NVSDataProcessingError("no wifi".to_string()))?
-            .ssid
+        networks
+            .iter()
+            .map(|network| network.ssid.as_str())
+            .collect::<Vec<_>>()
     );
+    // the first network has the highest priority
+    storage_data.wifi = Some(networks.remove(0));
+    storage_data.additional_wifi = networks;
     let part = &mut NVSPartition::from_storage_data(storage_data, size)?;
     Ok(NVSPartitionData::try_from(part)?.to_bytes())
 }
@@ -274,6 +414,52 @@ fn write_credentials_to_app_binary(
     Ok(())
 }
 
+/// Run `f` up to `FLASH_ATTEMPTS` times with a growing backoff between attempts, returning the
+/// error of the last attempt if none succeeded
+fn with_retries<T, E: std::fmt::Display>(
+    action: &str,
+    f: impl FnMut() -> Result<T, E>,
+) -> Result<T, E> {
+    retry_sleeping(action, std::thread::sleep, f)
+}
+
+// `with_retries` waiting for each backoff with `sleep`
+fn retry_sleeping<T, E: std::fmt::Display>(
+    action: &str,
+    mut sleep: impl FnMut(Duration),
+    mut f: impl FnMut() -> Result<T, E>,
+) -> Result<T, E> {
+    let mut attempt = 1;
+    loop {
+        match f() {
+            Ok(res) => return Ok(res),
+            Err(err) if attempt < FLASH_ATTEMPTS => {
+                let backoff = FLASH_RETRY_BACKOFF * attempt;
+                log::warn!(
+                    "{} failed (attempt {}/{}): {}, retrying in {:?}",
+                    action,
+                    attempt,
+                    FLASH_ATTEMPTS,
+                    err,
+                    backoff
+                );
+                sleep(backoff);
+                attempt += 1;
+            }
+            Err(err) => {
+                log::error!(
+                    "{} failed (attempt {}/{}): {}",
+                    action,
+                    attempt,
+                    FLASH_ATTEMPTS,
+                    err
+                );
+                return Err(err);
+            }
+        }
+    }
+}
+
 fn flash(
     flash_args: FlashArgs,
     connect_args: ConnectArgs,
@@ -281,12 +467,14 @@ fn flash(
     app_path: PathBuf,
 ) -> Result<(), Error> {
     log::info!("Connecting...");
-    let mut flasher = connect(
-        &connect_args,
-        config,
-        flash_args.no_verify,
-        flash_args.no_skip,
-    )
+    let mut flasher = with_retries("connecting", || {
+        connect(
+            &connect_args,
+            config,
+            flash_args.no_verify,
+            flash_args.no_skip,
+        )
+    })
     .map_err(|_| Error::FlashConnect)?;
     let mut f = File::open(app_path).map_err(Error::FileError)?;
     let size = f.metadata().map_err(Error::FileError)?.len();
@@ -296,9 +484,10 @@ fn flash(
     );
     f.read_to_end(&mut buffer).map_err(Error::FileError)?;
     log::info!("Connected. Writing to flash...");
-    flasher
-        .write_bin_to_flash(0x00, &buffer, Some(&mut EspflashProgress::default()))
-        .map_err(Error::EspFlashError)?;
+    with_retries("writing to flash", || {
+        flasher.write_bin_to_flash(0x00, &buffer, Some(&mut EspflashProgress::default()))
+    })
+    .map_err(|err| Error::FlashWriteRetriesExhausted(FLASH_ATTEMPTS, err))?;
     log::info!("Flashing completed.");
     if flash_args.monitor {
         monitor_message();
@@ -347,6 +536,7 @@ fn main() -> Result<(), Error> {
                 nvs_metadata.size as usize,
                 args.wifi_ssid.clone(),
                 args.wifi_password.clone(),
+                args.wifi_networks.clone(),
             )?;
             write_credentials_to_app_binary(
                 app_path,
@@ -375,7 +565,12 @@ fn main() -> Result<(), Error> {
                     }
 
                     let rt = Runtime::new().map_err(Error::AsyncError)?;
-                    rt.block_on(download_micro_rdk_release(&tmp_path, args.version.clone()))?
+                    rt.block_on(download_micro_rdk_release(
+                        &tmp_path,
+                        args.version.clone(),
+                        args.connect_args.chip.unwrap_or(DEFAULT_CHIP),
+                        &(&args.download_args).into(),
+                    ))?
                 }
             };
             let config = Config::load().map_err(|err| Error::SerialConfigError(err.to_string()))?;
@@ -387,6 +582,7 @@ fn main() -> Result<(), Error> {
                     nvs_metadata.size as usize,
                     args.wifi_ssid.clone(),
                     args.wifi_password.clone(),
+                    args.wifi_networks.clone(),
                 )?;
                 write_credentials_to_app_binary(
                     app_path.clone(),
@@ -409,6 +605,7 @@ fn main() -> Result<(), Error> {
                 args.size,
                 args.wifi_ssid.clone(),
                 args.wifi_password.clone(),
+                args.wifi_networks.clone(),
             )?)
             .map_err(Error::FileError)?;
         }
@@ -417,6 +614,8 @@ fn main() -> Result<(), Error> {
             let config = Config::load().map_err(|err| Error::SerialConfigError(err.to_string()))?;
             serial_monitor(args, &config).map_err(|err| Error::MonitorError(err.to_string()))?
         }
+        Some(Commands::EraseFlash(args)) => erase_flash(args)?,
+        Some(Commands::ReadNvs(args)) => read_nvs(args)?,
         None => return Err(Error::NoCommandError),
     };
     Ok(())
@@ -425,24 +624,10 @@ fn main() -> Result<(), Error> {
 fn update_app_image(args: &AppImageArgs) -> Result<(), Error> {
     let config = Config::load().map_err(|err| Error::SerialConfigError(err.to_string()))?;
 
-    let tmp_old = tempfile::NamedTempFile::new().map_err(Error::FileError)?;
-
     log::info!("Retrieving running partition table");
     let mut flasher =
         connect(&args.connect_args, &config, false, false).map_err(|_| Error::FlashConnect)?;
-    flasher
-        .read_flash(
-            PARTITION_TABLE_ADDR,
-            PARTITION_TABLE_SIZE,
-            DEFAULT_BLOCK_SIZE,
-            DEFAULT_MAX_IN_FLIGHT,
-            tmp_old.path().to_path_buf(),
-        )
-        .map_err(|_| Error::FlashConnect)?;
-
-    let old_ptable_buf = fs::read(tmp_old).map_err(Error::FileError)?;
-    let old_ptable = PartitionTable::try_from_bytes(old_ptable_buf.clone())
-        .map_err(|e| Error::PartitionTableError(e.to_string()))?;
+    let old_ptable = read_partition_table(&mut flasher)?;
 
     log::info!("Retrieving new image");
     let tmp_new = tempfile::NamedTempFile::new()
@@ -453,7 +638,12 @@ fn update_app_image(args: &AppImageArgs) -> Result<(), Error> {
         Some(path) => PathBuf::from(path),
         None => {
             let rt = Runtime::new().map_err(Error::AsyncError)?;
-            rt.block_on(download_micro_rdk_release(&tmp_new, args.version.clone()))?
+            rt.block_on(download_micro_rdk_release(
+                &tmp_new,
+                args.version.clone(),
+                args.connect_args.chip.unwrap_or(DEFAULT_CHIP),
+                &(&args.download_args).into(),
+            ))?
         }
     };
 
@@ -543,6 +733,267 @@ fn update_app_image(args: &AppImageArgs) -> Result<(), Error> {
     Ok(())
 }
 
+fn read_partition_table(flasher: &mut Flasher) -> Result<PartitionTable, Error> {
+    let tmp = tempfile::NamedTempFile::new().map_err(Error::FileError)?;
+    flasher
+        .read_flash(
+            PARTITION_TABLE_ADDR,
+            PARTITION_TABLE_SIZE,
+            DEFAULT_BLOCK_SIZE,
+            DEFAULT_MAX_IN_FLIGHT,
+            tmp.path().to_path_buf(),
+        )
+        .map_err(|_| Error::FlashConnect)?;
+    let ptable_buf = fs::read(tmp).map_err(Error::FileError)?;
+    PartitionTable::try_from_bytes(ptable_buf)
+        .map_err(|e| Error::PartitionTableError(e.to_string()))
+}
+
+fn erase_flash(args: &EraseFlashArgs) -> Result<(), Error> {
+    let config = Config::load().map_err(|err| Error::SerialConfigError(err.to_string()))?;
+    log::info!("Connecting...");
+    let mut flasher =
+        connect(&args.connect_args, &config, false, false).map_err(|_| Error::FlashConnect)?;
+
+    let region = match &args.partition {
+        Some(name) => {
+            log::info!("Retrieving running partition table");
+            let ptable = read_partition_table(&mut flasher)?;
+            let partition = ptable.find(name).ok_or_else(|| {
+                Error::PartitionTableError(format!("failed to find `{}` partition", name))
+            })?;
+            Some((name, partition.offset(), partition.size()))
+        }
+        None => None,
+    };
+
+    if !args.yes {
+        let prompt = match region {
+            Some((name, offset, size)) => format!(
+                "Erase the `{}` partition ({:#x} bytes at {:#x})?",
+                name, size, offset
+            ),
+            None => "Erase the whole flash? The device will need to be reflashed".to_string(),
+        };
+        let erase = dialoguer::Confirm::new()
+            .with_prompt(prompt)
+            .default(false)
+            .interact()
+            .unwrap();
+        if !erase {
+            return Ok(());
+        }
+    }
+
+    match region {
+        Some((name, offset, size)) => {
+            log::info!("Erasing `{}` partition...", name);
+            flasher
+                .erase_region(offset, size)
+                .map_err(Error::EspFlashError)?;
+        }
+        None => {
+            log::info!("Erasing flash...");
+            flasher.erase_flash().map_err(Error::EspFlashError)?;
+        }
+    }
+    log::info!("Erase completed.");
+    Ok(())
+}
+
+fn read_nvs(args: &ReadNvsArgs) -> Result<(), Error> {
+    let config = Config::load().map_err(|err| Error::SerialConfigError(err.to_string()))?;
+    log::info!("Connecting...");
+    let mut flasher =
+        connect(&args.connect_args, &config, false, false).map_err(|_| Error::FlashConnect)?;
+
+    log::info!("Retrieving running partition table");
+    let ptable = read_partition_table(&mut flasher)?;
+    let nvs_partition = ptable
+        .find(NVS_PARTITION_NAME)
+        .ok_or(Error::NVSMissingError)?;
+    log::info!("Reading `{}` partition", NVS_PARTITION_NAME);
+    let tmp = tempfile::NamedTempFile::new().map_err(Error::FileError)?;
+    flasher
+        .read_flash(
+            nvs_partition.offset(),
+            nvs_partition.size(),
+            DEFAULT_BLOCK_SIZE,
+            DEFAULT_MAX_IN_FLIGHT,
+            tmp.path().to_path_buf(),
+        )
+        .map_err(|_| Error::FlashConnect)?;
+    let nvs_data = fs::read(tmp).map_err(Error::FileError)?;
+    let storage_data = ViamFlashStorageData::from_nvs_strings(read_nvs_strings(&nvs_data)?);
+
+    let secret = |secret: &Secret<String>| {
+        if args.show_secrets {
+            secret.expose_secret().to_string()
+        } else {
+            "<hidden>".to_string()
+        }
+    };
+    let credentials = &storage_data.robot_credentials;
+    log::info!(
+        "robot id: {}",
+        credentials.robot_id.as_deref().unwrap_or("none")
+    );
+    log::info!(
+        "robot secret: {}",
+        credentials
+            .robot_secret
+            .as_ref()
+            .map_or("none".to_string(), secret)
+    );
+    log::info!(
+        "app address: {}",
+        credentials.app_address.as_deref().unwrap_or("none")
+    );
+    let networks: Vec<_> = storage_data
+        .wifi
+        .iter()
+        .chain(storage_data.additional_wifi.iter())
+        .collect();
+    if networks.is_empty() {
+        log::info!("wifi networks: none");
+    }
+    for (priority, network) in networks.into_iter().enumerate() {
+        log::info!(
+            "wifi network {}: ssid {:?}, password: {}",
+            priority,
+            network.ssid,
+            secret(&network.password)
+        );
+    }
+    Ok(())
+}
+
 fn monitor_message() {
     log::info!("In order to begin monitoring (viewing the log output), you must run the command to reset the chip (e.g. Ctrl-R)");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        parse_wifi_network, request_wifi_networks, retry_sleeping, validate_app_address,
+        FLASH_ATTEMPTS, FLASH_RETRY_BACKOFF,
+    };
+    use micro_rdk_installer::nvs::data::MAX_WIFI_NETWORKS;
+    use secrecy::{ExposeSecret, Secret};
+    use std::time::Duration;
+
+    #[test]
+    fn test_parse_wifi_network() {
+        let network = parse_wifi_network("staging:pass:word").unwrap();
+        assert_eq!(network.ssid, "staging");
+        // only the first colon separates the SSID from the password
+        assert_eq!(network.password.expose_secret(), "pass:word");
+        let network = parse_wifi_network("open:").unwrap();
+        assert_eq!(network.password.expose_secret(), "");
+
+        assert!(parse_wifi_network("staging").is_err());
+        assert!(parse_wifi_network(":password").is_err());
+        let err = parse_wifi_network(&format!("staging:{}", "p".repeat(65))).unwrap_err();
+        assert!(!err.contains("ppp"), "{}", err);
+    }
+
+    #[test]
+    fn test_request_wifi_networks() {
+        let fallbacks = vec![
+            parse_wifi_network("staging:pwd1").unwrap(),
+            parse_wifi_network("backup:pwd2").unwrap(),
+        ];
+        // the network of --wifi-ssid comes first
+        let networks = request_wifi_networks(
+            Some("production".to_string()),
+            Some(Secret::new("pwd0".to_string())),
+            fallbacks.clone(),
+        )
+        .unwrap();
+        let ssids: Vec<_> = networks.iter().map(|n| n.ssid.as_str()).collect();
+        assert_eq!(ssids, ["production", "staging", "backup"]);
+        assert_eq!(networks[0].password.expose_secret(), "pwd0");
+
+        // no prompt when only --wifi-network is given
+        let networks = request_wifi_networks(None, None, fallbacks).unwrap();
+        let ssids: Vec<_> = networks.iter().map(|n| n.ssid.as_str()).collect();
+        assert_eq!(ssids, ["staging", "backup"]);
+
+        let too_many = vec![parse_wifi_network("fallback:pwd").unwrap(); MAX_WIFI_NETWORKS];
+        assert!(request_wifi_networks(
+            Some("production".to_string()),
+            Some(Secret::new("pwd0".to_string())),
+            too_many.clone(),
+        )
+        .is_err());
+        assert_eq!(
+            request_wifi_networks(None, None, too_many).unwrap().len(),
+            MAX_WIFI_NETWORKS
+        );
+    }
+
+    #[test]
+    fn test_validate_app_address() {
+        for address in [
+            "https://app.viam.com:443",
+            "https://app.viam.com",
+            "http://localhost:8080",
+        ] {
+            assert_eq!(validate_app_address(address).unwrap(), address);
+        }
+        // the scheme is required, the device wouldn't know how to connect
+        for address in [
+            "app.viam.com:443",
+            "app.viam.com",
+            "ftp://app.viam.com",
+            "https://",
+            "https://app viam.com",
+            "",
+        ] {
+            assert!(validate_app_address(address).is_err(), "{:?}", address);
+        }
+    }
+
+    #[test]
+    fn test_retry_succeeds() {
+        let mut backoffs = vec![];
+        let mut attempts = 0;
+        let res = retry_sleeping(
+            "flashing",
+            |backoff| backoffs.push(backoff),
+            || {
+                attempts += 1;
+                if attempts < 2 {
+                    Err("port busy")
+                } else {
+                    Ok(attempts)
+                }
+            },
+        );
+        assert_eq!(res, Ok(2));
+        assert_eq!(backoffs, vec![FLASH_RETRY_BACKOFF]);
+    }
+
+    #[test]
+    fn test_retry_exhausted() {
+        let mut backoffs: Vec<Duration> = vec![];
+        let mut attempts = 0;
+        let res: Result<(), _> = retry_sleeping(
+            "flashing",
+            |backoff| backoffs.push(backoff),
+            || {
+                attempts += 1;
+                Err(format!("attempt {} failed", attempts))
+            },
+        );
+        assert_eq!(res, Err(format!("attempt {} failed", FLASH_ATTEMPTS)));
+        assert_eq!(attempts, FLASH_ATTEMPTS);
+        // the backoff grows with each attempt and there's none after the last one
+        assert_eq!(
+            backoffs,
+            (1..FLASH_ATTEMPTS)
+                .map(|attempt| FLASH_RETRY_BACKOFF * attempt)
+                .collect::<Vec<_>>()
+        );
+    }
+}
@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use secrecy::{ExposeSecret, Secret};
 
 use super::super::error::Error;
@@ -26,19 +28,63 @@ pub struct RobotCredentials {
     pub app_address: Option<String>,
 }
 
+/// Maximum number of Wi-Fi networks stored, the keys of the additional networks are suffixed
+/// with their priority and NVS keys are limited to 15 characters (`WIFI_PASSWORD_9`). The
+/// firmware reads as many networks, see `MAX_WIFI_NETWORKS` in micro-rdk
+pub const MAX_WIFI_NETWORKS: usize = 10;
+
 #[derive(Default, Debug)]
 pub struct ViamFlashStorageData {
+    /// Highest priority network, stored under `WIFI_SSID` and `WIFI_PASSWORD`
     pub wifi: Option<WifiCredentials>,
+    /// Networks to fall back on in order of priority, stored under `WIFI_SSID_<n>` and
+    /// `WIFI_PASSWORD_<n>` starting from 1
+    pub additional_wifi: Vec<WifiCredentials>,
     pub robot_credentials: RobotCredentials,
 }
 
 impl ViamFlashStorageData {
-    fn to_nvs_key_value_pairs(&self, namespace_idx: u8) -> Result<[NVSKeyValuePair; 5], Error> {
+    /// Rebuild the storage data from the string values read back from an NVS partition
+    pub fn from_nvs_strings(mut values: HashMap<String, String>) -> Self {
+        let mut wifi_credentials = |ssid_key: &str, password_key: &str| {
+            values.remove(ssid_key).map(|ssid| WifiCredentials {
+                ssid,
+                password: Secret::new(values.remove(password_key).unwrap_or_default()),
+            })
+        };
+        let wifi = wifi_credentials("WIFI_SSID", "WIFI_PASSWORD");
+        let additional_wifi = (1..MAX_WIFI_NETWORKS)
+            .map_while(|priority| {
+                wifi_credentials(
+                    &format!("WIFI_SSID_{}", priority),
+                    &format!("WIFI_PASSWORD_{}", priority),
+                )
+            })
+            .collect();
+        Self {
+            wifi,
+            additional_wifi,
+            robot_credentials: RobotCredentials {
+                robot_id: values.remove("ROBOT_ID"),
+                robot_secret: values.remove("ROBOT_SECRET").map(Secret::new),
+                robot_name: None,
+                app_address: values.remove("ROBOT_APP_ADDR"),
+            },
+        }
+    }
+
+    fn to_nvs_key_value_pairs(&self, namespace_idx: u8) -> Result<Vec<NVSKeyValuePair>, Error> {
         let wifi_cred = self
             .wifi
             .clone()
             .ok_or(Error::NVSDataProcessingError("no wifi".to_string()))?;
-        Ok([
+        if self.additional_wifi.len() >= MAX_WIFI_NETWORKS {
+            return Err(Error::NVSDataProcessingError(format!(
+                "at most {} wifi networks can be stored",
+                MAX_WIFI_NETWORKS
+            )));
+        }
+        let mut pairs = vec![
             NVSKeyValuePair {
                 key: "WIFI_SSID".to_string(),
                 value: NVSValue::String(wifi_cred.ssid),
@@ -77,7 +123,20 @@ impl ViamFlashStorageData {
                 )?),
                 namespace_idx,
             },
-        ])
+        ];
+        for (priority, wifi_cred) in self.additional_wifi.iter().enumerate() {
+            pairs.push(NVSKeyValuePair {
+                key: format!("WIFI_SSID_{}", priority + 1),
+                value: NVSValue::String(wifi_cred.ssid.clone()),
+                namespace_idx,
+            });
+            pairs.push(NVSKeyValuePair {
+                key: format!("WIFI_PASSWORD_{}", priority + 1),
+                value: NVSValue::String(wifi_cred.password.expose_secret().to_string()),
+                namespace_idx,
+            });
+        }
+        Ok(pairs)
     }
 
     pub fn to_entries(&self, namespace_idx: u8) -> Result<Vec<NVSEntry>, Error> {
@@ -111,3 +170,67 @@ impl ViamFlashStorageData {
             .ok_or(Error::MissingConfigInfo("app address not set".to_string()))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{RobotCredentials, ViamFlashStorageData, WifiCredentials, MAX_WIFI_NETWORKS};
+    use crate::nvs::partition::NVSValue;
+    use secrecy::Secret;
+
+    fn wifi(ssid: &str, password: &str) -> WifiCredentials {
+        WifiCredentials {
+            ssid: ssid.to_string(),
+            password: Secret::new(password.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_wifi_networks_key_value_pairs() {
+        let mut data = ViamFlashStorageData {
+            wifi: Some(wifi("production", "pwd0")),
+            additional_wifi: vec![wifi("staging", "pwd1"), wifi("backup", "pwd2")],
+            robot_credentials: RobotCredentials {
+                robot_id: Some("robot".to_string()),
+                robot_secret: Some(Secret::new("secret".to_string())),
+                robot_name: None,
+                app_address: Some("https://app.viam.com:443".to_string()),
+            },
+        };
+        let pairs: Vec<(String, String)> = data
+            .to_nvs_key_value_pairs(1)
+            .unwrap()
+            .into_iter()
+            .map(|pair| {
+                assert_eq!(pair.namespace_idx, 1);
+                match pair.value {
+                    NVSValue::String(value) => (pair.key, value),
+                    NVSValue::Bytes(_) => panic!("{} isn't stored as a string", pair.key),
+                }
+            })
+            .collect();
+        let expected = [
+            ("WIFI_SSID", "production"),
+            ("WIFI_PASSWORD", "pwd0"),
+            ("ROBOT_ID", "robot"),
+            ("ROBOT_SECRET", "secret"),
+            ("ROBOT_APP_ADDR", "https://app.viam.com:443"),
+            ("WIFI_SSID_1", "staging"),
+            ("WIFI_PASSWORD_1", "pwd1"),
+            ("WIFI_SSID_2", "backup"),
+            ("WIFI_PASSWORD_2", "pwd2"),
+        ];
+        assert_eq!(
+            pairs,
+            expected
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect::<Vec<_>>()
+        );
+
+        data.additional_wifi = vec![wifi("fallback", "pwd"); MAX_WIFI_NETWORKS];
+        assert!(data.to_nvs_key_value_pairs(1).is_err());
+        data.wifi = None;
+        data.additional_wifi.clear();
+        assert!(data.to_nvs_key_value_pairs(1).is_err());
+    }
+}
@@ -7,11 +7,14 @@
 //! by data split into however many entries are required based on length. Since the above credentials data
 //! consists only of string or binary values, only those two entry formats have been implemented
 //!
+//! [read_nvs_strings] goes the other way and reads back the string values of a partition, for
+//! example one dumped from a device, to check what it was provisioned with
+//!
 //! More information on the structure of NVS and its API can be found in Espressif's online documentation
 //! (https://docs.espressif.com/projects/esp-idf/en/release-v4.4/esp32/api-reference/storage/nvs_flash.html)
 
 use super::super::error::Error;
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 
 use crc32fast::Hasher;
 
@@ -27,6 +30,14 @@ const PAGE_VERSION: u8 = 0xFE; // Version 2
 
 const DEFAULT_BLOB_CHUNK_IDX: u8 = 0xFF;
 
+const PAGE_SIZE: usize = 4096;
+const ENTRY_SIZE: usize = 32;
+const ENTRIES_PER_PAGE: usize = 126;
+// the page header and the entry state bitmap come before the entries
+const FIRST_ENTRY_OFFSET: usize = 64;
+const PAGE_STATE_UNINITIALIZED: u32 = 0xFFFFFFFF;
+const ENTRY_STATE_WRITTEN: u8 = 0b10;
+
 // computes the checksum of the contents of the header and stores it at index 4
 // as a 32-bit integer (see the link above for more information)
 fn set_header_crc(header: &mut Vec<u8>) {
@@ -452,3 +463,146 @@ impl TryFrom<&mut NVSPartition> for NVSPartitionData {
         Ok(nvs_inst)
     }
 }
+
+/// Read back the string values stored in the Viam namespace of an NVS partition, binary values
+/// are skipped. Pages are read in order of sequence number so that the most recently written
+/// value of a key is returned
+pub fn read_nvs_strings(partition: &[u8]) -> Result<HashMap<String, String>, Error> {
+    if partition.len() % PAGE_SIZE != 0 {
+        return Err(Error::NVSDataProcessingError(format!(
+            "NVS partition size {:#x} is not a multiple of the page size",
+            partition.len()
+        )));
+    }
+    let mut pages: Vec<(u32, &[u8])> = partition
+        .chunks(PAGE_SIZE)
+        .filter(|page| {
+            u32::from_le_bytes(page[0..4].try_into().unwrap()) != PAGE_STATE_UNINITIALIZED
+        })
+        .map(|page| (u32::from_le_bytes(page[4..8].try_into().unwrap()), page))
+        .collect();
+    pages.sort_by_key(|(sequence_number, _)| *sequence_number);
+
+    // (namespace index, format, key, value) of every written entry
+    let mut entries = vec![];
+    for (_, page) in pages {
+        let bitmap = &page[32..FIRST_ENTRY_OFFSET];
+        let mut idx = 0;
+        while idx < ENTRIES_PER_PAGE {
+            let state = (bitmap[idx / 4] >> ((idx % 4) * 2)) & 0b11;
+            let header_pos = FIRST_ENTRY_OFFSET + idx * ENTRY_SIZE;
+            let header = &page[header_pos..header_pos + ENTRY_SIZE];
+            let span = match header[2] as usize {
+                span if state == ENTRY_STATE_WRITTEN
+                    && span > 0
+                    && idx + span <= ENTRIES_PER_PAGE =>
+                {
+                    span
+                }
+                _ => 1,
+            };
+            if state == ENTRY_STATE_WRITTEN {
+                let key_len = header[8..24].iter().position(|b| *b == 0).unwrap_or(16);
+                let key = String::from_utf8_lossy(&header[8..8 + key_len]).to_string();
+                let value = if header[1] == STRING_VALUE_FORMAT {
+                    let len = u16::from_le_bytes([header[24], header[25]]) as usize;
+                    let data = &page[header_pos + ENTRY_SIZE..header_pos + span * ENTRY_SIZE];
+                    data.get(..len).map(|data| data.to_vec())
+                } else {
+                    Some(header[24..32].to_vec())
+                };
+                if let Some(value) = value {
+                    entries.push((header[0], header[1], key, value));
+                }
+            }
+            idx += span;
+        }
+    }
+
+    let namespace_idx = entries
+        .iter()
+        .find(|(ns, format, key, _)| {
+            *ns == 0 && *format == NAMESPACE_FORMAT && key == VIAM_NAMESPACE
+        })
+        .map(|(_, _, _, value)| value[0])
+        .ok_or(Error::NVSDataProcessingError(format!(
+            "no {} namespace in NVS partition",
+            VIAM_NAMESPACE
+        )))?;
+    Ok(entries
+        .into_iter()
+        .filter(|(ns, format, _, _)| *ns == namespace_idx && *format == STRING_VALUE_FORMAT)
+        .map(|(_, _, key, mut value)| {
+            // strings are stored null terminated
+            if value.last() == Some(&0) {
+                value.pop();
+            }
+            (key, String::from_utf8_lossy(&value).to_string())
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_nvs_strings, NVSPartition, NVSPartitionData, PAGE_SIZE};
+    use crate::nvs::data::{RobotCredentials, ViamFlashStorageData, WifiCredentials};
+    use secrecy::{ExposeSecret, Secret};
+
+    fn wifi(ssid: &str, password: &str) -> WifiCredentials {
+        WifiCredentials {
+            ssid: ssid.to_string(),
+            password: Secret::new(password.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_nvs_strings_round_trip() {
+        // long enough to span several entries
+        let secret = "s3cr3t".repeat(20);
+        let data = ViamFlashStorageData {
+            wifi: Some(wifi("production", "pwd0")),
+            additional_wifi: vec![wifi("staging", "pwd1")],
+            robot_credentials: RobotCredentials {
+                robot_id: Some("robot-id".to_string()),
+                robot_secret: Some(Secret::new(secret.clone())),
+                robot_name: None,
+                app_address: Some("https://app.viam.com:443".to_string()),
+            },
+        };
+        let mut partition = NVSPartition::from_storage_data(data, 8 * PAGE_SIZE).unwrap();
+        let bytes = NVSPartitionData::try_from(&mut partition)
+            .unwrap()
+            .to_bytes();
+        assert_eq!(bytes.len(), 8 * PAGE_SIZE);
+
+        let strings = read_nvs_strings(&bytes).unwrap();
+        assert_eq!(strings.len(), 7);
+        assert_eq!(strings["ROBOT_SECRET"], secret);
+        assert_eq!(strings["WIFI_SSID_1"], "staging");
+
+        let data = ViamFlashStorageData::from_nvs_strings(strings);
+        let wifi = data.wifi.unwrap();
+        assert_eq!(wifi.ssid, "production");
+        assert_eq!(wifi.password.expose_secret(), "pwd0");
+        assert_eq!(data.additional_wifi.len(), 1);
+        assert_eq!(data.additional_wifi[0].ssid, "staging");
+        assert_eq!(data.additional_wifi[0].password.expose_secret(), "pwd1");
+        assert_eq!(data.robot_credentials.robot_id.as_deref(), Some("robot-id"));
+        assert_eq!(
+            data.robot_credentials
+                .robot_secret
+                .unwrap()
+                .expose_secret()
+                .as_str(),
+            secret
+        );
+        assert_eq!(
+            data.robot_credentials.app_address.as_deref(),
+            Some("https://app.viam.com:443")
+        );
+
+        assert!(read_nvs_strings(&bytes[..PAGE_SIZE + 1]).is_err());
+        // a blank partition has no Viam namespace
+        assert!(read_nvs_strings(&[0xff; 2 * PAGE_SIZE]).is_err());
+    }
+}
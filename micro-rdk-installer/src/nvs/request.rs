@@ -1,7 +1,11 @@
 use super::super::error::Error;
+use espflash::targets::Chip;
+use sha2::{Digest, Sha256};
 use std::fs::File;
+use std::future::Future;
 use std::io::Write;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 /*
 This module contains the logic for acquiring the security credentials for a robot from
@@ -9,39 +13,262 @@ the Viam App and preparing for flash storage
 */
 
 const RELEASES_BASE_URL: &str = "https://github.com/viamrobotics/micro-rdk/releases";
-const BINARY_NAME: &str = "micro-rdk-server-esp32.bin";
+/// Chip targeted when none is given
+pub const DEFAULT_CHIP: Chip = Chip::Esp32;
+// the SHA256 of the release assets are published with them in a single file, in the `sha256sum`
+// format
+const CHECKSUMS_FILE: &str = "sha256sums.txt";
+const INITIAL_RETRY_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Timeout and retries of a release download
+#[derive(Clone, Debug)]
+pub struct DownloadOptions {
+    /// Timeout of a single download attempt, from connecting to receiving the whole binary
+    pub timeout: Duration,
+    /// Number of attempts made after the first one failed, with an exponential backoff
+    pub retries: u32,
+    /// Flash the binary without verifying it against the published checksums, which otherwise
+    /// fails the download when they are missing
+    pub skip_checksum: bool,
+}
+
+impl Default for DownloadOptions {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(120),
+            retries: 3,
+            skip_checksum: false,
+        }
+    }
+}
+
+/// Name of the release binary built for `chip`, errors if micro-RDK isn't released for it
+pub fn release_binary_name(chip: Chip) -> Result<&'static str, Error> {
+    match chip {
+        Chip::Esp32 => Ok("micro-rdk-server-esp32.bin"),
+        chip => Err(Error::UnsupportedChip(chip.to_string())),
+    }
+}
 
 pub async fn download_micro_rdk_release(
     path: &Path,
     version: Option<String>,
+    chip: Chip,
+    options: &DownloadOptions,
 ) -> Result<PathBuf, Error> {
+    let binary_name = release_binary_name(chip)?;
     let release_url = if version.is_none() || version.clone().unwrap() == "latest" {
-        format!(
-            "{}/{}/{}",
-            RELEASES_BASE_URL, "latest/download", BINARY_NAME
-        )
+        format!("{}/{}", RELEASES_BASE_URL, "latest/download")
+    } else {
+        format!("{}/download/{}", RELEASES_BASE_URL, version.unwrap())
+    };
+    let binary_url = format!("{}/{}", release_url, binary_name);
+
+    let client = reqwest::Client::builder()
+        .timeout(options.timeout)
+        .build()
+        .map_err(Error::BinaryRetrievalError)?;
+    let expected = if options.skip_checksum {
+        log::warn!("Skipping the verification of the release checksum");
+        None
     } else {
-        format!(
-            "{}/download/{}/{}",
-            RELEASES_BASE_URL,
-            version.unwrap(),
-            BINARY_NAME
-        )
+        let checksums_url = format!("{}/{}", release_url, CHECKSUMS_FILE);
+        let checksums = retry_download(options, CHECKSUMS_FILE, &checksums_url, || {
+            download_text(&client, &checksums_url)
+        })
+        .await?;
+        Some(find_checksum(&checksums, binary_name)?.ok_or_else(|| {
+            Error::ChecksumMissing(format!(
+                "{} isn't listed in {}, use --skip-checksum to flash it anyway",
+                binary_name, checksums_url
+            ))
+        })?)
     };
 
-    log::info!("Downloading micro-RDK release from {:?}", release_url);
+    log::info!("Downloading micro-RDK release from {:?}", binary_url);
     let fname = path.to_path_buf();
-    let mut dest = File::create(fname.clone()).map_err(Error::FileError)?;
-    let response = reqwest::get(release_url)
+    let digest = retry_download(options, binary_name, &binary_url, || {
+        download_to_file(&client, &binary_url, &fname)
+    })
+    .await?;
+    if let Some(expected) = expected {
+        if expected != digest {
+            return Err(Error::ChecksumMismatch(expected, digest));
+        }
+        log::info!("Verified release checksum {}", digest);
+    }
+    Ok(fname)
+}
+
+// Run `download` of the release asset `name` at `url` until it succeeds, retrying transient
+// failures (timeouts, connection and server errors) `options.retries` times with an exponential
+// backoff
+async fn retry_download<T, F, Fut>(
+    options: &DownloadOptions,
+    name: &str,
+    url: &str,
+    mut download: F,
+) -> Result<T, Error>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, Error>>,
+{
+    let mut backoff = INITIAL_RETRY_BACKOFF;
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        let err = match download().await {
+            Ok(res) => return Ok(res),
+            Err(Error::BinaryRetrievalError(err)) => err,
+            Err(err) => return Err(err),
+        };
+        // a missing release won't appear by retrying
+        if err.status() == Some(reqwest::StatusCode::NOT_FOUND) {
+            return Err(Error::ReleaseAssetNotFound(format!(
+                "{} isn't published for this release ({})",
+                name, url
+            )));
+        }
+        if err.status().is_some_and(|status| status.is_client_error()) || attempt > options.retries
+        {
+            return Err(Error::BinaryDownloadError(attempt, err));
+        }
+        log::warn!(
+            "Download attempt {} of {} failed ({}), retrying in {:?}",
+            attempt,
+            name,
+            err,
+            backoff
+        );
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff * 2).min(MAX_RETRY_BACKOFF);
+    }
+}
+
+// Find the checksum of `name` in the content of a `sha256sum` output, as lowercase hex. Lines are
+// `<hash>  <name>`, or `<hash> *<name>` for files hashed in binary mode.
+fn find_checksum(checksums: &str, name: &str) -> Result<Option<String>, Error> {
+    for line in checksums.lines().filter(|line| !line.trim().is_empty()) {
+        let (hash, file) = line
+            .trim()
+            .split_once(char::is_whitespace)
+            .ok_or_else(|| Error::ChecksumFormatError(line.to_owned()))?;
+        if hash.len() != 64 || !hash.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(Error::ChecksumFormatError(line.to_owned()));
+        }
+        let file = file.trim_start();
+        if file.strip_prefix('*').unwrap_or(file) == name {
+            return Ok(Some(hash.to_ascii_lowercase()));
+        }
+    }
+    Ok(None)
+}
+
+async fn download_text(client: &reqwest::Client, url: &str) -> Result<String, Error> {
+    client
+        .get(url)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(Error::BinaryRetrievalError)?
+        .text()
+        .await
+        .map_err(Error::BinaryRetrievalError)
+}
+
+// download `url` to `fname`, logging the progress every 10%, and return the SHA256 of the
+// content as lowercase hex
+async fn download_to_file(
+    client: &reqwest::Client,
+    url: &str,
+    fname: &Path,
+) -> Result<String, Error> {
+    let mut response = client
+        .get(url)
+        .send()
         .await
         .map_err(Error::BinaryRetrievalError)?;
     response
         .error_for_status_ref()
         .map_err(Error::BinaryRetrievalError)?;
-    let content = response
-        .bytes()
+    let total = response.content_length();
+    let mut dest = File::create(fname).map_err(Error::FileError)?;
+    let mut hasher = Sha256::new();
+    let mut received: u64 = 0;
+    let mut last_decile = 0;
+    while let Some(chunk) = response
+        .chunk()
         .await
-        .map_err(Error::BinaryRetrievalError)?;
-    dest.write_all(&content).map_err(Error::FileError)?;
-    Ok(fname)
+        .map_err(Error::BinaryRetrievalError)?
+    {
+        dest.write_all(&chunk).map_err(Error::FileError)?;
+        hasher.update(&chunk);
+        received += chunk.len() as u64;
+        if let Some(total) = total.filter(|total| *total > 0) {
+            let decile = received * 10 / total;
+            if decile > last_decile {
+                last_decile = decile;
+                log::info!("Downloaded {}% ({}/{} bytes)", decile * 10, received, total);
+            }
+        }
+    }
+    if total.is_none() {
+        log::info!("Downloaded {} bytes", received);
+    }
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_checksum, release_binary_name, Error};
+    use espflash::targets::Chip;
+
+    const ESP32_HASH: &str = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08";
+
+    #[test]
+    fn test_find_checksum() {
+        let checksums = format!(
+            "{}  micro-rdk-installer-macos\n{} *micro-rdk-server-esp32.bin\n\n",
+            "60303ae22b998861bce3b28f33eec1be758a213c86c93c076dbe9f558c11c752",
+            ESP32_HASH.to_ascii_uppercase()
+        );
+        assert_eq!(
+            find_checksum(&checksums, "micro-rdk-server-esp32.bin").unwrap(),
+            Some(ESP32_HASH.to_owned())
+        );
+        assert_eq!(
+            find_checksum(&checksums, "micro-rdk-server-esp32s3.bin").unwrap(),
+            None
+        );
+        assert!(matches!(
+            find_checksum(
+                "not-a-hash  micro-rdk-server-esp32.bin",
+                "micro-rdk-server-esp32.bin"
+            ),
+            Err(Error::ChecksumFormatError(_))
+        ));
+        assert!(matches!(
+            find_checksum(ESP32_HASH, "micro-rdk-server-esp32.bin"),
+            Err(Error::ChecksumFormatError(_))
+        ));
+    }
+
+    #[test]
+    fn test_release_binary_name() {
+        assert_eq!(
+            release_binary_name(Chip::Esp32).unwrap(),
+            "micro-rdk-server-esp32.bin"
+        );
+        for chip in [Chip::Esp32s3, Chip::Esp32c3] {
+            assert!(matches!(
+                release_binary_name(chip),
+                Err(Error::UnsupportedChip(_))
+            ));
+        }
+    }
 }
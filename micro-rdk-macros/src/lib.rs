@@ -14,6 +14,10 @@
 //! of the PowerSensor trait. `get_generic_readings` will return a struct containing the voltage (in volts),
 //! current (in amperes), power (in watts), and whether or not the power supply is AC.
 //!
+//! SensorReadings - provides a default implementation of the Readings trait for implementers
+//! of the SensorT<f64> trait. `get_generic_readings` will return the map returned by `get_readings`
+//! with every value wrapped as a number.
+//!
 //! # Example using `MovementSensorReadings`
 //!
 //! ```
@@ -127,3 +131,21 @@ pub fn impl_readings_for_power_sensor(input: TokenStream) -> TokenStream {
 
     gen.into()
 }
+
+#[proc_macro_derive(SensorReadings)]
+pub fn impl_readings_for_sensor(input: TokenStream) -> TokenStream {
+    let ast: syn::DeriveInput = syn::parse(input).unwrap();
+    let name = &ast.ident;
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+
+    let crate_ident = get_micro_rdk_crate_ident();
+    let gen = quote! {
+        impl #impl_generics #crate_ident::common::sensor::Readings for #name #ty_generics #where_clause {
+            fn get_generic_readings(&mut self) -> Result<#crate_ident::common::sensor::GenericReadingsResult,#crate_ident::common::sensor::SensorError> {
+                #crate_ident::common::sensor::get_sensor_generic_readings(&*self)
+            }
+        }
+    };
+
+    gen.into()
+}
@@ -3,10 +3,10 @@ use micro_rdk::common::movement_sensor::{
     GeoPosition, MovementSensor, MovementSensorSupportedMethods,
 };
 use micro_rdk::common::power_sensor::{Current, PowerSensor, PowerSupplyType, Voltage};
-use micro_rdk::common::sensor::{Readings, SensorError};
+use micro_rdk::common::sensor::{Readings, Sensor, SensorError, SensorT, TypedReadingsResult};
 use micro_rdk::common::status::{Status, StatusError};
 use micro_rdk::google::protobuf::value::Kind;
-use micro_rdk_macros::{DoCommand, MovementSensorReadings, PowerSensorReadings};
+use micro_rdk_macros::{DoCommand, MovementSensorReadings, PowerSensorReadings, SensorReadings};
 use std::collections::HashMap;
 
 #[derive(DoCommand)]
@@ -98,6 +98,36 @@ impl Status for TestPowerSensor {
     }
 }
 
+#[derive(DoCommand, SensorReadings)]
+struct TestSensor<T>
+where
+    T: Into<f64> + Copy,
+{
+    value: T,
+}
+
+impl<T> Sensor for TestSensor<T> where T: Into<f64> + Copy {}
+
+impl<T> SensorT<f64> for TestSensor<T>
+where
+    T: Into<f64> + Copy,
+{
+    fn get_readings(&self) -> Result<TypedReadingsResult<f64>, SensorError> {
+        Ok(HashMap::from([("value".to_string(), self.value.into())]))
+    }
+}
+
+impl<T> Status for TestSensor<T>
+where
+    T: Into<f64> + Copy,
+{
+    fn get_status(&self) -> Result<Option<micro_rdk::google::protobuf::Struct>, StatusError> {
+        Ok(Some(micro_rdk::google::protobuf::Struct {
+            fields: HashMap::new(),
+        }))
+    }
+}
+
 #[test]
 fn do_command_derive() {
     use micro_rdk::common::generic::DoCommand;
@@ -174,3 +204,16 @@ fn power_sensor_readings_derive() {
         assert!(is_ac)
     }
 }
+
+#[test]
+fn sensor_readings_derive() {
+    let mut a = TestSensor { value: 42_u8 };
+    let res = a.get_generic_readings();
+    assert!(res.is_ok());
+    let res = res.unwrap();
+    assert_eq!(res.len(), 1);
+
+    let value = res.get("value");
+    assert!(value.is_some());
+    assert_eq!(value.unwrap().kind, Some(Kind::NumberValue(42.0)));
+}
@@ -5,15 +5,24 @@ mod esp32 {
     const ROBOT_ID: Option<&str> = option_env!("MICRO_RDK_ROBOT_ID");
     const ROBOT_SECRET: Option<&str> = option_env!("MICRO_RDK_ROBOT_SECRET");
     const ROBOT_APP_ADDRESS: Option<&str> = option_env!("MICRO_RDK_ROBOT_APP_ADDRESS");
+    const PROVISIONING_MANUFACTURER: &str = match option_env!("MICRO_RDK_PROVISIONING_MANUFACTURER")
+    {
+        Some(manufacturer) => manufacturer,
+        None => "viam",
+    };
+    const PROVISIONING_MODEL: &str = match option_env!("MICRO_RDK_PROVISIONING_MODEL") {
+        Some(model) => model,
+        None => "test-esp32",
+    };
+    const DNS_SERVERS: Option<&str> = option_env!("MICRO_RDK_DNS_SERVERS");
 
-    use std::rc::Rc;
-
+    #[cfg(not(feature = "qemu"))]
+    use micro_rdk::common::conn::network::parse_dns_servers;
     use micro_rdk::common::conn::server::WebRtcConfiguration;
     use micro_rdk::common::conn::viam::ViamServerBuilder;
     #[cfg(feature = "qemu")]
     use micro_rdk::common::credentials_storage::RAMStorage;
     use micro_rdk::common::exec::Executor;
-    use micro_rdk::common::webrtc::certificate::Certificate;
     use micro_rdk::esp32::certificate::GeneratedWebRtcCertificateBuilder;
     use micro_rdk::esp32::conn::mdns::Esp32Mdns;
     #[cfg(not(feature = "qemu"))]
@@ -117,25 +126,35 @@ mod esp32 {
         }
 
         let mut info = ProvisioningInfo::default();
-        info.set_manufacturer("viam".to_owned());
-        info.set_model("test-esp32".to_owned());
-
-        let webrtc_certs = GeneratedWebRtcCertificateBuilder::default()
-            .build()
-            .unwrap();
-        let webrtc_certs = Rc::new(Box::new(webrtc_certs) as Box<dyn Certificate>);
-        let dtls = Box::new(Esp32DtlsBuilder::new(webrtc_certs.clone()));
-        let webrtc_config = WebRtcConfiguration::new(webrtc_certs, dtls);
+        info.set_manufacturer(PROVISIONING_MANUFACTURER.to_owned());
+        info.set_model(PROVISIONING_MODEL.to_owned());
 
         let mut builder = ViamServerBuilder::new(storage);
         builder
             .with_provisioning_info(info)
-            .with_webrtc_configuration(webrtc_config)
+            .with_cached_webrtc_certificate(
+                Box::new(|| {
+                    GeneratedWebRtcCertificateBuilder::default()
+                        .build_cached()
+                        .inspect_err(|e| log::error!("couldn't generate WebRTC certificate: {}", e))
+                        .ok()
+                }),
+                Box::new(|cert| {
+                    let dtls = Box::new(Esp32DtlsBuilder::new(cert.clone()));
+                    WebRtcConfiguration::new(cert, dtls)
+                }),
+            )
             .with_http2_server(Esp32H2Connector::default(), 12346)
             .with_default_tasks()
             .with_component_registry(registry);
         #[cfg(not(feature = "qemu"))]
-        let builder = { builder.with_wifi_manager(Box::new(Esp32WifiNetwork::new().unwrap())) };
+        let builder = {
+            let mut network = Esp32WifiNetwork::new().unwrap();
+            if let Some(dns_servers) = DNS_SERVERS {
+                network.set_dns_servers(parse_dns_servers(dns_servers));
+            }
+            builder.with_wifi_manager(Box::new(network))
+        };
         let mdns = Esp32Mdns::new("".to_owned()).unwrap();
         #[cfg(feature = "qemu")]
         let mut server = {
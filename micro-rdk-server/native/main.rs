@@ -3,6 +3,15 @@ mod native {
     const ROBOT_ID: Option<&str> = option_env!("MICRO_RDK_ROBOT_ID");
     const ROBOT_SECRET: Option<&str> = option_env!("MICRO_RDK_ROBOT_SECRET");
     const ROBOT_APP_ADDRESS: Option<&str> = option_env!("MICRO_RDK_ROBOT_APP_ADDRESS");
+    const PROVISIONING_MANUFACTURER: &str = match option_env!("MICRO_RDK_PROVISIONING_MANUFACTURER")
+    {
+        Some(manufacturer) => manufacturer,
+        None => "viam",
+    };
+    const PROVISIONING_MODEL: &str = match option_env!("MICRO_RDK_PROVISIONING_MODEL") {
+        Some(model) => model,
+        None => "test-esp32",
+    };
 
     use std::rc::Rc;
 
@@ -61,8 +70,8 @@ mod native {
         }
 
         let mut info = ProvisioningInfo::default();
-        info.set_manufacturer("viam".to_owned());
-        info.set_model("test-esp32".to_owned());
+        info.set_manufacturer(PROVISIONING_MANUFACTURER.to_owned());
+        info.set_model(PROVISIONING_MODEL.to_owned());
 
         let webrtc_certs = Rc::new(Box::new(WebRtcCertificate::new()) as Box<dyn Certificate>);
         let dtls = Box::new(NativeDtls::new(webrtc_certs.clone()));
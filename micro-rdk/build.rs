@@ -2,6 +2,7 @@ use regex::Regex;
 
 fn main() {
     println!("cargo::rustc-check-cfg=cfg(esp32)");
+    build_info();
     if Regex::new(r"\w+-esp3?2?s?\d?-espidf")
         .unwrap()
         .is_match(&std::env::var("TARGET").unwrap())
@@ -15,3 +16,32 @@ fn main() {
         link_args.propagate();
     }
 }
+
+// embed the build time and revision, exposed by common::build_info
+fn build_info() {
+    // honor SOURCE_DATE_EPOCH for reproducible builds
+    let timestamp = std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.parse::<u64>().ok())
+        .unwrap_or_else(|| {
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default()
+        });
+    println!("cargo::rustc-env=MICRO_RDK_BUILD_TIMESTAMP={}", timestamp);
+
+    let revision = std::process::Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|revision| revision.trim().to_owned())
+        .unwrap_or_else(|| "unknown".to_owned());
+    println!("cargo::rustc-env=MICRO_RDK_GIT_REVISION={}", revision);
+    println!(
+        "cargo::rustc-env=MICRO_RDK_BUILD_TARGET={}",
+        std::env::var("TARGET").unwrap()
+    );
+}
@@ -7,7 +7,7 @@ use crate::google;
 
 use super::board::Board;
 use super::config::ConfigType;
-use super::i2c::I2CHandle;
+use super::i2c::{i2c_address_from_config, I2CHandle};
 use super::movement_sensor::MovementSensorType;
 use super::registry::{get_board_from_dependencies, ComponentRegistry, Dependency};
 use super::sensor::SensorError;
@@ -64,6 +64,10 @@ impl ADXL345 {
         } else {
             return Err(SensorError::ConfigError("ADXL-345 missing i2c_bus"));
         };
+        // an explicit `i2c_address` takes precedence over `use_alt_i2c_address`
+        if let Some(i2c_address) = i2c_address_from_config(&cfg)? {
+            return Ok(Arc::new(Mutex::new(ADXL345::new(i2c_handle, i2c_address)?)));
+        }
         if let Ok(use_alt_address) = cfg.get_attribute::<bool>("use_alt_i2c_address") {
             if use_alt_address {
                 return Ok(Arc::new(Mutex::new(ADXL345::new(i2c_handle, 29)?)));
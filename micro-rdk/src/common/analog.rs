@@ -8,6 +8,10 @@ use thiserror::Error;
 pub enum AnalogError {
     #[error("analog read error {0}")]
     AnalogReadError(i32),
+    #[error("analog write error {0}")]
+    AnalogWriteError(i32),
+    #[error("analog value {0} exceeds the maximum of {1}")]
+    AnalogValueOutOfRange(u16, u16),
 }
 
 pub struct FakeAnalogReader {
@@ -40,6 +44,32 @@ impl AnalogReader<u16> for FakeAnalogReader {
     }
 }
 
+pub struct FakeAnalogWriter {
+    name: String,
+    value: u16,
+}
+
+impl FakeAnalogWriter {
+    pub fn new(name: String) -> Self {
+        Self { name, value: 0 }
+    }
+    /// Returns the last value written
+    pub fn value(&self) -> u16 {
+        self.value
+    }
+}
+
+impl AnalogWriter<u16> for FakeAnalogWriter {
+    type Error = AnalogError;
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+    fn write(&mut self, value: u16) -> Result<(), Self::Error> {
+        self.value = value;
+        Ok(())
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct AnalogResolution {
     pub min_range: f32,
@@ -73,6 +103,27 @@ where
     }
 }
 
+/// An analog output, such as a DAC channel
+pub trait AnalogWriter<Word> {
+    type Error;
+    /// Output `value`, its scale depends on the implementer (for example 0-255 for an 8 bits DAC)
+    fn write(&mut self, value: Word) -> Result<(), Self::Error>;
+    fn name(&self) -> String;
+}
+
+impl<A, Word> AnalogWriter<Word> for Arc<Mutex<A>>
+where
+    A: ?Sized + AnalogWriter<Word>,
+{
+    type Error = A::Error;
+    fn write(&mut self, value: Word) -> Result<(), Self::Error> {
+        self.lock().unwrap().write(value)
+    }
+    fn name(&self) -> String {
+        self.lock().unwrap().name()
+    }
+}
+
 pub(crate) struct AnalogReaderConfig {
     pub(crate) name: String,
     pub(crate) pin: i32,
@@ -95,6 +146,11 @@ impl TryFrom<&Kind> for AnalogReaderConfig {
 
 pub type AnalogReaderType<W, E = AnalogError> = Arc<Mutex<dyn AnalogReader<W, Error = E>>>;
 
+/// Analog writers are configured like readers, with a `name` and a `pin`
+pub(crate) type AnalogWriterConfig = AnalogReaderConfig;
+
+pub type AnalogWriterType<W, E = AnalogError> = Arc<Mutex<dyn AnalogWriter<W, Error = E>>>;
+
 #[cfg(test)]
 mod tests {
     use std::collections::HashMap;
@@ -2,6 +2,7 @@
 use crate::proto::app::v1::CertificateRequest;
 use crate::proto::app::v1::CertificateResponse;
 use crate::proto::{
+    app::agent::v1::{DeviceAgentConfigRequest, DeviceAgentConfigResponse, HostInfo},
     app::v1::{
         AgentInfo, ConfigRequest, ConfigResponse, LogRequest, NeedsRestartRequest,
         NeedsRestartResponse,
@@ -21,7 +22,7 @@ use http_body_util::StreamBody;
 use hyper::{body::Frame, http::HeaderValue};
 use prost::{DecodeError, EncodeError, Message};
 use std::{
-    net::Ipv4Addr,
+    net::{Ipv4Addr, SocketAddr},
     pin::Pin,
     rc::Rc,
     time::{Duration, SystemTime},
@@ -70,14 +71,23 @@ pub enum AppClientError {
     AppClientEmptyBody,
     #[error(transparent)]
     AppClientIoError(#[from] std::io::Error),
+    #[error("couldn't resolve {0}: {1}")]
+    AppDnsError(String, std::io::Error),
+    #[error("couldn't open a TCP connection to {0}: {1}")]
+    AppTcpConnectError(SocketAddr, std::io::Error),
+    #[error("TLS handshake with {0} failed: {1}")]
+    AppTlsError(String, std::io::Error),
 }
 
 impl AppClientError {
     pub fn is_io_error(&self) -> bool {
-        if let AppClientError::AppClientIoError(_) = self {
-            return true;
-        }
-        false
+        matches!(
+            self,
+            AppClientError::AppClientIoError(_)
+                | AppClientError::AppDnsError(..)
+                | AppClientError::AppTcpConnectError(..)
+                | AppClientError::AppTlsError(..)
+        )
     }
     pub fn is_permission_denied(&self) -> bool {
         if let AppClientError::AppGrpcClientError(GrpcClientError::GrpcError { code, .. }) = self {
@@ -405,6 +415,39 @@ impl AppClient {
         })
     }
 
+    /// Obtains the interval at which the agent config of the robot asks to be polled, None if it
+    /// doesn't set one.
+    pub async fn get_agent_config_check_interval(
+        &self,
+    ) -> Result<Option<Duration>, AppClientError> {
+        let req = DeviceAgentConfigRequest {
+            id: self.robot_credentials.robot_id.clone(),
+            host_info: Some(HostInfo {
+                platform: "esp32".to_string(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let body = encode_request(req)?;
+        let r = self
+            .grpc_client
+            .build_request(
+                "/viam.app.agent.v1.AgentDeviceService/DeviceAgentConfig",
+                Some(&self.jwt),
+                "",
+                BodyExt::boxed(Full::new(body).map_err(|never| match never {})),
+            )
+            .map_err(AppClientError::AppGrpcClientError)?;
+        let (mut response, _) = self.grpc_client.send_request(r).await?;
+        if response.is_empty() {
+            return Err(AppClientError::AppClientEmptyBody);
+        }
+        let response = DeviceAgentConfigResponse::decode(response.split_off(5))?;
+        Ok(response
+            .check_interval
+            .and_then(|d| Duration::try_from(d).ok()))
+    }
+
     /// Returns the strong count of the AppClient's internal
     /// gRPC client, serving as a rough proxy for the number of
     /// concurrent tasks using this app client.
@@ -2,10 +2,16 @@
 #[cfg(feature = "builtin-components")]
 use {super::actuator::ActuatorError, crate::google, log::*, std::collections::HashMap};
 
-use super::{config::AttributeError, generic::DoCommand, motor::MotorError};
+use super::{
+    config::{AttributeError, Kind},
+    generic::DoCommand,
+    motor::MotorError,
+};
 use crate::common::actuator::Actuator;
 use crate::common::status::Status;
-use crate::proto::common::v1::Vector3;
+use crate::proto::common::v1::{
+    geometry::GeometryType, Capsule, Geometry, Pose, RectangularPrism, Sphere, Vector3,
+};
 use std::sync::{Arc, Mutex};
 use thiserror::Error;
 
@@ -13,6 +19,21 @@ pub static COMPONENT_NAME: &str = "base";
 
 pub trait Base: Status + Actuator + DoCommand {
     fn set_power(&mut self, lin: &Vector3, ang: &Vector3) -> Result<(), BaseError>;
+    /// The shapes enclosing the base in its own frame, used by motion planning to avoid
+    /// collisions
+    fn get_geometries(&self) -> Result<Vec<Geometry>, BaseError> {
+        Ok(vec![])
+    }
+    /// Spins the base in place by `angle_deg` (counterclockwise when positive) at `degs_per_sec`.
+    /// The spin carries on after this returns, `is_moving` reports whether it is done.
+    fn spin(&mut self, _angle_deg: f64, _degs_per_sec: f64) -> Result<(), BaseError> {
+        Err(BaseError::BaseMethodUnimplemented("spin"))
+    }
+    /// Moves the base straight by `distance_mm` (backwards when negative) at `mm_per_sec`.
+    /// The move carries on after this returns, `is_moving` reports whether it is done.
+    fn move_straight(&mut self, _distance_mm: f64, _mm_per_sec: f64) -> Result<(), BaseError> {
+        Err(BaseError::BaseMethodUnimplemented("move_straight"))
+    }
 }
 
 pub type BaseType = Arc<Mutex<dyn Base>>;
@@ -25,6 +46,99 @@ pub enum BaseError {
     BaseConfigAttributeError(#[from] AttributeError),
     #[error("config error: {0}")]
     BaseConfigError(&'static str),
+    #[error("invalid argument: {0}")]
+    BaseInvalidArgument(&'static str),
+    #[error("unimplemented: {0}")]
+    BaseMethodUnimplemented(&'static str),
+}
+
+/// A box centered on the base's origin
+pub fn box_geometry(x_mm: f64, y_mm: f64, z_mm: f64) -> Geometry {
+    Geometry {
+        center: Some(Pose {
+            o_z: 1.0,
+            ..Default::default()
+        }),
+        geometry_type: Some(GeometryType::Box(RectangularPrism {
+            dims_mm: Some(Vector3 {
+                x: x_mm,
+                y: y_mm,
+                z: z_mm,
+            }),
+        })),
+        ..Default::default()
+    }
+}
+
+/// The `geometry` attribute of a base, shaped like the geometry of a frame in RDK configs: a
+/// `type` of `box` (with `x`, `y` and `z`), `sphere` (with `r`) or `capsule` (with `r` and `l`),
+/// all in mm, an optional `translation` of its center and an optional `label`
+pub(crate) struct GeometryConfig(pub(crate) Geometry);
+
+impl TryFrom<&Kind> for GeometryConfig {
+    type Error = AttributeError;
+    fn try_from(value: &Kind) -> Result<Self, Self::Error> {
+        // missing keys are reported as invalid so they aren't mistaken for a missing geometry
+        let missing =
+            |key: &str| AttributeError::ValidationError(format!("geometry is missing {}", key));
+        let dimension = |key: &str| -> Result<f64, AttributeError> {
+            let dim: f64 = value.get(key)?.ok_or_else(|| missing(key))?.try_into()?;
+            if dim > 0.0 {
+                Ok(dim)
+            } else {
+                Err(AttributeError::ValidationError(format!(
+                    "geometry {} should be positive",
+                    key
+                )))
+            }
+        };
+        let r#type: String = value
+            .get("type")?
+            .ok_or_else(|| missing("type"))?
+            .try_into()?;
+        let geometry_type = match r#type.as_str() {
+            "box" => GeometryType::Box(RectangularPrism {
+                dims_mm: Some(Vector3 {
+                    x: dimension("x")?,
+                    y: dimension("y")?,
+                    z: dimension("z")?,
+                }),
+            }),
+            "sphere" => GeometryType::Sphere(Sphere {
+                radius_mm: dimension("r")?,
+            }),
+            "capsule" => GeometryType::Capsule(Capsule {
+                radius_mm: dimension("r")?,
+                length_mm: dimension("l")?,
+            }),
+            _ => {
+                return Err(AttributeError::ValidationError(
+                    "geometry type should be box, sphere or capsule".to_string(),
+                ))
+            }
+        };
+        let mut center = Pose {
+            o_z: 1.0,
+            ..Default::default()
+        };
+        if let Some(translation) = value.get("translation")? {
+            let coordinate = |key: &str| -> Result<f64, AttributeError> {
+                translation.get(key)?.map_or(Ok(0.0), |v| v.try_into())
+            };
+            center.x = coordinate("x")?;
+            center.y = coordinate("y")?;
+            center.z = coordinate("z")?;
+        }
+        let label = match value.get("label")? {
+            Some(label) => label.try_into()?,
+            None => String::new(),
+        };
+        Ok(GeometryConfig(Geometry {
+            center: Some(center),
+            label,
+            geometry_type: Some(geometry_type),
+        }))
+    }
 }
 
 // TODO(RSDK-5648) - Store power from set_power call on struct and register as "fake" model
@@ -52,6 +166,17 @@ where
     fn set_power(&mut self, lin: &Vector3, ang: &Vector3) -> Result<(), BaseError> {
         self.get_mut().unwrap().set_power(lin, ang)
     }
+    fn get_geometries(&self) -> Result<Vec<Geometry>, BaseError> {
+        self.lock().unwrap().get_geometries()
+    }
+    fn spin(&mut self, angle_deg: f64, degs_per_sec: f64) -> Result<(), BaseError> {
+        self.get_mut().unwrap().spin(angle_deg, degs_per_sec)
+    }
+    fn move_straight(&mut self, distance_mm: f64, mm_per_sec: f64) -> Result<(), BaseError> {
+        self.get_mut()
+            .unwrap()
+            .move_straight(distance_mm, mm_per_sec)
+    }
 }
 
 impl<L> Base for Arc<Mutex<L>>
@@ -61,6 +186,15 @@ where
     fn set_power(&mut self, lin: &Vector3, ang: &Vector3) -> Result<(), BaseError> {
         self.lock().unwrap().set_power(lin, ang)
     }
+    fn get_geometries(&self) -> Result<Vec<Geometry>, BaseError> {
+        self.lock().unwrap().get_geometries()
+    }
+    fn spin(&mut self, angle_deg: f64, degs_per_sec: f64) -> Result<(), BaseError> {
+        self.lock().unwrap().spin(angle_deg, degs_per_sec)
+    }
+    fn move_straight(&mut self, distance_mm: f64, mm_per_sec: f64) -> Result<(), BaseError> {
+        self.lock().unwrap().move_straight(distance_mm, mm_per_sec)
+    }
 }
 
 #[cfg(feature = "builtin-components")]
@@ -100,3 +234,47 @@ impl Status for FakeBase {
         Ok(Some(google::protobuf::Struct { fields: hm }))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::actuator::ActuatorError;
+    use crate::common::test_utils::delegation_test;
+
+    delegation_test!(
+        test_base_delegation,
+        RecordingBase as dyn Base,
+        wrappers: [Mutex::new, |base| Arc::new(Mutex::new(base))],
+        impl Base {
+            fn set_power(&mut self, _: &Vector3, _: &Vector3) -> Result<(), BaseError> {
+                Ok(())
+            }
+            fn get_geometries(&self) -> Result<Vec<Geometry>, BaseError> {
+                Ok(vec![])
+            }
+            fn spin(&mut self, _: f64, _: f64) -> Result<(), BaseError> {
+                Ok(())
+            }
+            fn move_straight(&mut self, _: f64, _: f64) -> Result<(), BaseError> {
+                Ok(())
+            }
+        }
+        impl Actuator {
+            fn is_moving(&mut self) -> Result<bool, ActuatorError> {
+                Ok(false)
+            }
+            fn stop(&mut self) -> Result<(), ActuatorError> {
+                Ok(())
+            }
+        }
+        calls: |base| {
+            base.set_power(&Vector3::default(), &Vector3::default())
+                .unwrap();
+            base.get_geometries().unwrap();
+            base.spin(90.0, 45.0).unwrap();
+            base.move_straight(100.0, 50.0).unwrap();
+            base.is_moving().unwrap();
+            base.stop().unwrap();
+        }
+    );
+}
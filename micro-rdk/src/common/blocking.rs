@@ -0,0 +1,435 @@
+//! Offloading of blocking driver calls to a small pool of threads.
+//!
+//! Everything served by micro-rdk runs cooperatively on a single executor, so a slow device (an
+//! I2C sensor stretching the clock, a sensor waiting on a conversion) stalls gRPC and WebRTC
+//! handling while it is read. A [`BlockingPool`] runs such calls on its own threads, and
+//! [`BackgroundReadings`] uses it to refresh the readings of a sensor in the background so that
+//! `get_readings` always returns immediately with the most recent readings. A pool given to
+//! `ViamServerBuilder::with_blocking_pool` also runs the blocking calls of the server itself, and
+//! the I2C sensors (VEML7700, BME280) configured with `"read_in_background": true` are read on
+//! it.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+
+use async_channel::TrySendError;
+use thiserror::Error;
+
+use super::config::{AttributeError, ConfigType};
+use super::exec::TaskConfig;
+use super::generic::{DoCommand, DoCommandFuture, GenericError};
+use super::sensor::{GenericReadingsResult, Readings, Sensor, SensorError, SensorType};
+use super::status::{Status, StatusError};
+use crate::google;
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Default number of jobs a [`BlockingPool`] queues while all its threads are busy
+pub const DEFAULT_BLOCKING_QUEUE_SIZE: usize = 8;
+
+#[derive(Debug, Error)]
+pub enum BlockingPoolError {
+    #[error("blocking pool queue is full")]
+    QueueFull,
+    #[error("blocking pool is closed")]
+    Closed,
+}
+
+/// A fixed number of threads executing blocking calls, jobs submitted while every thread is busy
+/// wait in a bounded queue and are refused once it is full
+pub struct BlockingPool {
+    jobs: async_channel::Sender<Job>,
+}
+
+impl BlockingPool {
+    /// Start a pool of `threads` threads configured by `config`, queueing at most `queue_size`
+    /// jobs. The threads exit once the pool is dropped
+    pub fn new(threads: usize, queue_size: usize, config: &TaskConfig) -> std::io::Result<Self> {
+        let (jobs, queue) = async_channel::bounded::<Job>(queue_size.max(1));
+        for _ in 0..threads.max(1) {
+            let queue = queue.clone();
+            let _ = config.spawn_thread(move || {
+                while let Ok(job) = queue.recv_blocking() {
+                    // a panicking job must not take the thread down with it
+                    if catch_unwind(AssertUnwindSafe(job)).is_err() {
+                        log::error!("blocking pool job panicked");
+                    }
+                }
+            })?;
+        }
+        Ok(Self { jobs })
+    }
+
+    /// Run `f` on the pool without waiting for it to complete
+    pub fn execute(&self, f: impl FnOnce() + Send + 'static) -> Result<(), BlockingPoolError> {
+        self.jobs.try_send(Box::new(f)).map_err(|e| match e {
+            TrySendError::Full(_) => BlockingPoolError::QueueFull,
+            TrySendError::Closed(_) => BlockingPoolError::Closed,
+        })
+    }
+
+    /// Run `f` on the pool, the returned future resolves to its output or None if it panicked
+    pub fn spawn_blocking<T: Send + 'static>(
+        &self,
+        f: impl FnOnce() -> T + Send + 'static,
+    ) -> Result<impl Future<Output = Option<T>>, BlockingPoolError> {
+        let (tx, rx) = async_channel::bounded(1);
+        self.execute(move || {
+            let _ = tx.send_blocking(f());
+        })?;
+        Ok(async move { rx.recv().await.ok() })
+    }
+}
+
+// The pool sensors configured with `read_in_background` are read on
+static SENSOR_READ_POOL: Mutex<Option<Arc<BlockingPool>>> = Mutex::new(None);
+
+pub(crate) fn set_sensor_read_pool(pool: Arc<BlockingPool>) {
+    let _ = SENSOR_READ_POOL
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .replace(pool);
+}
+
+/// Wrap `sensor` in [`BackgroundReadings`] when its config sets the `read_in_background`
+/// attribute, it is then read on the pool given to `ViamServerBuilder::with_blocking_pool`
+pub(crate) fn sensor_from_config<S>(cfg: &ConfigType, sensor: S) -> Result<SensorType, SensorError>
+where
+    S: Sensor + Send + 'static,
+{
+    match cfg.get_attribute::<bool>("read_in_background") {
+        Ok(false) | Err(AttributeError::KeyNotFound(_)) => Ok(Arc::new(Mutex::new(sensor))),
+        Ok(true) => {
+            let pool = SENSOR_READ_POOL
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .clone()
+                .ok_or(SensorError::ConfigError(
+                    "read_in_background needs the server to have a blocking pool",
+                ))?;
+            Ok(Arc::new(Mutex::new(BackgroundReadings::new(
+                Arc::new(Mutex::new(sensor)),
+                pool,
+            ))))
+        }
+        Err(_) => Err(SensorError::ConfigError(
+            "read_in_background should be a boolean",
+        )),
+    }
+}
+
+type LatestReadings = Option<Result<GenericReadingsResult, String>>;
+
+/// Wraps a sensor so that it is read on a [`BlockingPool`]. Each call to `get_readings` returns
+/// the readings of the last completed read and starts a new one if none is in flight, the first
+/// call returns an error since no read completed yet.
+pub struct BackgroundReadings<S> {
+    sensor: Arc<Mutex<S>>,
+    pool: Arc<BlockingPool>,
+    latest: Arc<Mutex<LatestReadings>>,
+    reading: Arc<AtomicBool>,
+}
+
+impl<S> BackgroundReadings<S>
+where
+    S: Readings + Send + 'static,
+{
+    pub fn new(sensor: Arc<Mutex<S>>, pool: Arc<BlockingPool>) -> Self {
+        Self {
+            sensor,
+            pool,
+            latest: Arc::new(Mutex::new(None)),
+            reading: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    fn start_read(&self) {
+        if self.reading.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        let sensor = self.sensor.clone();
+        let latest = self.latest.clone();
+        let reading = self.reading.clone();
+        let queued = self.pool.execute(move || {
+            // a sensor panicking during a read leaves its mutex poisoned, its next read is tried
+            // anyway
+            let readings = catch_unwind(AssertUnwindSafe(|| {
+                sensor
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .get_generic_readings()
+                    .map_err(|e| e.to_string())
+            }))
+            .unwrap_or_else(|_| Err("sensor panicked during a background read".to_owned()));
+            let _ = latest
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .replace(readings);
+            reading.store(false, Ordering::Release);
+        });
+        if let Err(e) = queued {
+            log::warn!("couldn't start a background read: {}", e);
+            self.reading.store(false, Ordering::Release);
+        }
+    }
+}
+
+impl<S> Readings for BackgroundReadings<S>
+where
+    S: Readings + Send + 'static,
+{
+    fn get_generic_readings(&mut self) -> Result<GenericReadingsResult, SensorError> {
+        self.start_read();
+        match self
+            .latest
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .as_ref()
+        {
+            Some(Ok(readings)) => Ok(readings.clone()),
+            Some(Err(e)) => Err(SensorError::SensorBackgroundReadError(e.clone())),
+            None => Err(SensorError::SensorGenericError(
+                "no readings available yet, a background read is in progress",
+            )),
+        }
+    }
+}
+
+impl<S> Status for BackgroundReadings<S> {
+    fn get_status(&self) -> Result<Option<google::protobuf::Struct>, StatusError> {
+        Ok(Some(google::protobuf::Struct {
+            fields: HashMap::new(),
+        }))
+    }
+}
+
+impl<S> DoCommand for BackgroundReadings<S>
+where
+    S: DoCommand,
+{
+    fn do_command(
+        &mut self,
+        command_struct: Option<google::protobuf::Struct>,
+    ) -> Result<Option<google::protobuf::Struct>, GenericError> {
+        self.sensor.lock().unwrap().do_command(command_struct)
+    }
+    fn do_command_async(
+        &mut self,
+        command_struct: Option<google::protobuf::Struct>,
+    ) -> DoCommandFuture {
+        self.sensor.lock().unwrap().do_command_async(command_struct)
+    }
+}
+
+impl<S> Sensor for BackgroundReadings<S> where S: Sensor + Send + 'static {}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    use super::{
+        sensor_from_config, set_sensor_read_pool, BackgroundReadings, BlockingPool,
+        BlockingPoolError,
+    };
+    use crate::common::config::{self, ConfigType};
+    use crate::common::exec::{Executor, TaskConfig};
+    use crate::common::generic::DoCommand;
+    use crate::common::sensor::{GenericReadingsResult, Readings, Sensor, SensorError};
+    use crate::common::status::{Status, StatusError};
+    use crate::common::test_utils::component_config;
+    use crate::google::protobuf::{value::Kind, Struct, Value};
+
+    // Sensor whose reads block until the test releases them through `gate`
+    struct GatedSensor {
+        gate: async_channel::Receiver<()>,
+        reads: Arc<AtomicUsize>,
+    }
+
+    impl Readings for GatedSensor {
+        fn get_generic_readings(&mut self) -> Result<GenericReadingsResult, SensorError> {
+            self.gate.recv_blocking().unwrap();
+            let reads = self.reads.fetch_add(1, Ordering::SeqCst) + 1;
+            Ok(HashMap::from([(
+                "reads".to_string(),
+                Value {
+                    kind: Some(Kind::NumberValue(reads as f64)),
+                },
+            )]))
+        }
+    }
+
+    struct FastSensor;
+
+    impl Readings for FastSensor {
+        fn get_generic_readings(&mut self) -> Result<GenericReadingsResult, SensorError> {
+            Ok(HashMap::new())
+        }
+    }
+
+    impl Status for FastSensor {
+        fn get_status(&self) -> Result<Option<Struct>, StatusError> {
+            Ok(None)
+        }
+    }
+
+    impl DoCommand for FastSensor {}
+
+    impl Sensor for FastSensor {}
+
+    struct PanickingSensor;
+
+    impl Readings for PanickingSensor {
+        fn get_generic_readings(&mut self) -> Result<GenericReadingsResult, SensorError> {
+            panic!("sensor failure")
+        }
+    }
+
+    // wait for the jobs queued before it on a single thread pool to be done
+    fn drain(exec: &Executor, pool: &BlockingPool) {
+        assert_eq!(exec.block_on(pool.spawn_blocking(|| ()).unwrap()), Some(()));
+    }
+
+    #[test_log::test]
+    fn test_slow_sensor_does_not_block() {
+        let exec = Executor::new();
+        let pool = Arc::new(BlockingPool::new(1, 4, &TaskConfig::default()).unwrap());
+        let (release, gate) = async_channel::unbounded();
+        let reads = Arc::new(AtomicUsize::new(0));
+        let mut slow = BackgroundReadings::new(
+            Arc::new(Mutex::new(GatedSensor {
+                gate,
+                reads: reads.clone(),
+            })),
+            pool.clone(),
+        );
+
+        // the slow read is in flight and stays blocked until released, the fast sensor and
+        // further calls to the slow one are answered meanwhile
+        assert!(slow.get_generic_readings().is_err());
+        assert!(FastSensor.get_generic_readings().is_ok());
+        assert!(slow.get_generic_readings().is_err());
+        assert_eq!(reads.load(Ordering::SeqCst), 0);
+
+        release.send_blocking(()).unwrap();
+        drain(&exec, &pool);
+        assert_eq!(reads.load(Ordering::SeqCst), 1);
+        // returns the completed read and starts the next one
+        let readings = slow.get_generic_readings().unwrap();
+        assert_eq!(
+            readings.get("reads").unwrap().kind,
+            Some(Kind::NumberValue(1.0))
+        );
+        release.send_blocking(()).unwrap();
+        drain(&exec, &pool);
+        assert_eq!(reads.load(Ordering::SeqCst), 2);
+    }
+
+    #[test_log::test]
+    fn test_read_in_background_config() {
+        let sensor = |attributes: Vec<(&str, config::Kind)>| {
+            sensor_from_config(
+                &ConfigType::Dynamic(&component_config(attributes)),
+                FastSensor,
+            )
+        };
+        // read on the executor unless configured otherwise
+        let direct = sensor(vec![]).unwrap();
+        assert!(direct.lock().unwrap().get_generic_readings().is_ok());
+        let direct = sensor(vec![("read_in_background", config::Kind::BoolValue(false))]).unwrap();
+        assert!(direct.lock().unwrap().get_generic_readings().is_ok());
+        assert!(matches!(
+            sensor(vec![(
+                "read_in_background",
+                config::Kind::StringValue("yes".to_owned())
+            )]),
+            Err(SensorError::ConfigError(_))
+        ));
+        // no server pool was given yet
+        assert!(matches!(
+            sensor(vec![("read_in_background", config::Kind::BoolValue(true))]),
+            Err(SensorError::ConfigError(_))
+        ));
+
+        let exec = Executor::new();
+        let pool = Arc::new(BlockingPool::new(1, 4, &TaskConfig::default()).unwrap());
+        set_sensor_read_pool(pool.clone());
+        let background =
+            sensor(vec![("read_in_background", config::Kind::BoolValue(true))]).unwrap();
+        // the first read is made on the pool
+        assert!(background.lock().unwrap().get_generic_readings().is_err());
+        drain(&exec, &pool);
+        assert!(background.lock().unwrap().get_generic_readings().is_ok());
+    }
+
+    #[test_log::test]
+    fn test_spawn_blocking() {
+        let exec = Executor::new();
+        let pool = BlockingPool::new(2, 4, &TaskConfig::default()).unwrap();
+        let (release, gate) = async_channel::bounded::<()>(1);
+        let slow = pool
+            .spawn_blocking(move || {
+                gate.recv_blocking().unwrap();
+                1
+            })
+            .unwrap();
+        // answered by the other thread while the first one is still blocked
+        let fast = pool.spawn_blocking(|| 2).unwrap();
+        assert_eq!(exec.block_on(fast), Some(2));
+        release.send_blocking(()).unwrap();
+        assert_eq!(exec.block_on(slow), Some(1));
+    }
+
+    #[test_log::test]
+    fn test_panicking_job() {
+        let exec = Executor::new();
+        let pool = Arc::new(BlockingPool::new(1, 4, &TaskConfig::default()).unwrap());
+        let job = pool.spawn_blocking(|| panic!("job failure")).unwrap();
+        assert_eq!(exec.block_on(job), None::<()>);
+        // the thread survived the panic
+        assert_eq!(exec.block_on(pool.spawn_blocking(|| 3).unwrap()), Some(3));
+
+        let mut sensor =
+            BackgroundReadings::new(Arc::new(Mutex::new(PanickingSensor)), pool.clone());
+        assert!(sensor.get_generic_readings().is_err());
+        drain(&exec, &pool);
+        assert!(matches!(
+            sensor.get_generic_readings(),
+            Err(SensorError::SensorBackgroundReadError(_))
+        ));
+        // the failed read didn't leave a read in flight, the next call started another one
+        drain(&exec, &pool);
+        assert!(matches!(
+            sensor.get_generic_readings(),
+            Err(SensorError::SensorBackgroundReadError(_))
+        ));
+    }
+
+    #[test_log::test]
+    fn test_bounded_queue() {
+        let exec = Executor::new();
+        let pool = BlockingPool::new(1, 1, &TaskConfig::default()).unwrap();
+        let (started_tx, started) = async_channel::bounded::<()>(1);
+        let (release, gate) = async_channel::bounded::<()>(1);
+        let running = pool
+            .spawn_blocking(move || {
+                started_tx.send_blocking(()).unwrap();
+                gate.recv_blocking().unwrap();
+            })
+            .unwrap();
+        // once the first job occupies the thread the queue holds a single job
+        started.recv_blocking().unwrap();
+        let queued = pool.spawn_blocking(|| 1).unwrap();
+        assert!(matches!(
+            pool.spawn_blocking(|| 2),
+            Err(BlockingPoolError::QueueFull)
+        ));
+        release.send_blocking(()).unwrap();
+        assert_eq!(exec.block_on(running), Some(()));
+        assert_eq!(exec.block_on(queued), Some(1));
+    }
+}
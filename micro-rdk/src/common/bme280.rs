@@ -0,0 +1,416 @@
+//! Driver for the Bosch BME280 (temperature, pressure and humidity) and BMP280 (temperature and
+//! pressure) sensors over I2C.
+//! BME280 datasheet: https://www.bosch-sensortec.com/media/boschsensortec/downloads/datasheets/bst-bme280-ds002.pdf
+//! BMP280 datasheet: https://www.bosch-sensortec.com/media/boschsensortec/downloads/datasheets/bst-bmp280-ds001.pdf
+//!
+//! The model is detected from the chip id. The sensor runs in normal mode, measuring once a
+//! second, and the raw measurements are compensated with the calibration table programmed in
+//! the chip at the factory.
+//!
+//! Readings are `temperature_celsius`, `pressure_pa`, `altitude_m` (derived from the pressure
+//! and `sea_level_pressure_pa`, 101325 by default) and `relative_humidity_pct` on the BME280.
+//! The sensor is found at 0x76 when SDO is wired to ground or 0x77 when wired to VDDIO, set
+//! with the `i2c_address` attribute. A slow bus can be kept off the executor with
+//! `read_in_background`, see [`blocking`](super::blocking).
+
+use std::collections::HashMap;
+
+use super::blocking::sensor_from_config;
+use super::board::Board;
+use super::config::{AttributeError, ConfigType};
+use super::i2c::{i2c_address_from_config, I2CHandle, I2cHandleType};
+use super::registry::{get_board_from_dependencies, ComponentRegistry, Dependency};
+use super::sensor::{
+    GenericReadingsResult, Readings, Sensor, SensorError, SensorResult, SensorType,
+    TypedReadingsResult,
+};
+use super::status::{Status, StatusError};
+use crate::google;
+
+const DEFAULT_I2C_ADDRESS: u8 = 0x76;
+const ALT_I2C_ADDRESS: u8 = 0x77;
+const DEFAULT_SEA_LEVEL_PRESSURE_PA: f64 = 101325.0;
+
+const CHIP_ID_REGISTER: u8 = 0xD0;
+const BME280_CHIP_ID: u8 = 0x60;
+// production BMP280 and its two sample revisions
+const BMP280_CHIP_IDS: [u8; 3] = [0x58, 0x56, 0x57];
+const RESET_REGISTER: u8 = 0xE0;
+const RESET_COMMAND: u8 = 0xB6;
+const CALIBRATION_REGISTER: u8 = 0x88;
+const CALIBRATION_LEN: usize = 26;
+const HUMIDITY_CALIBRATION_REGISTER: u8 = 0xE1;
+const HUMIDITY_CALIBRATION_LEN: usize = 7;
+const CTRL_HUM_REGISTER: u8 = 0xF2;
+const CTRL_MEAS_REGISTER: u8 = 0xF4;
+const CONFIG_REGISTER: u8 = 0xF5;
+const DATA_REGISTER: u8 = 0xF7;
+// humidity oversampling x1, only applied once ctrl_meas is written
+const CTRL_HUM: u8 = 0b001;
+// temperature and pressure oversampling x1, normal mode
+const CTRL_MEAS: u8 = 0b001_001_11;
+// 1000ms between measurements, filter off
+const CONFIG: u8 = 0b101_000_00;
+
+pub(crate) fn register_models(registry: &mut ComponentRegistry) {
+    if registry
+        .register_sensor("bme280", &Bme280::from_config)
+        .is_err()
+    {
+        log::error!("bme280 model is already registered")
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Model {
+    Bme280,
+    Bmp280,
+}
+
+/// Compensation parameters read from the chip, named after the datasheet
+#[derive(Clone, Debug, Default, PartialEq)]
+struct Calibration {
+    t1: u16,
+    t2: i16,
+    t3: i16,
+    p1: u16,
+    p2: i16,
+    p3: i16,
+    p4: i16,
+    p5: i16,
+    p6: i16,
+    p7: i16,
+    p8: i16,
+    p9: i16,
+    h1: u8,
+    h2: i16,
+    h3: u8,
+    h4: i16,
+    h5: i16,
+    h6: i8,
+}
+
+impl Calibration {
+    /// From the 26 bytes starting at 0x88 and, on the BME280, the 7 bytes starting at 0xE1
+    fn from_registers(
+        table: &[u8; CALIBRATION_LEN],
+        humidity_table: Option<&[u8; HUMIDITY_CALIBRATION_LEN]>,
+    ) -> Self {
+        let u16_at = |i: usize| u16::from_le_bytes([table[i], table[i + 1]]);
+        let i16_at = |i: usize| i16::from_le_bytes([table[i], table[i + 1]]);
+        let mut calibration = Self {
+            t1: u16_at(0),
+            t2: i16_at(2),
+            t3: i16_at(4),
+            p1: u16_at(6),
+            p2: i16_at(8),
+            p3: i16_at(10),
+            p4: i16_at(12),
+            p5: i16_at(14),
+            p6: i16_at(16),
+            p7: i16_at(18),
+            p8: i16_at(20),
+            p9: i16_at(22),
+            ..Default::default()
+        };
+        if let Some(h) = humidity_table {
+            calibration.h1 = table[25];
+            calibration.h2 = i16::from_le_bytes([h[0], h[1]]);
+            calibration.h3 = h[2];
+            // h4 and h5 are 12 bits values sharing the nibbles of 0xE5
+            calibration.h4 = ((h[3] as i8 as i16) << 4) | (h[4] & 0x0F) as i16;
+            calibration.h5 = ((h[5] as i8 as i16) << 4) | (h[4] >> 4) as i16;
+            calibration.h6 = h[6] as i8;
+        }
+        calibration
+    }
+
+    /// Temperature in degrees Celsius along with the fine temperature used to compensate the
+    /// other measurements
+    fn temperature(&self, adc_t: i32) -> (f64, f64) {
+        let adc_t = adc_t as f64;
+        let t1 = self.t1 as f64;
+        let var1 = (adc_t / 16384.0 - t1 / 1024.0) * self.t2 as f64;
+        let var2 = (adc_t / 131072.0 - t1 / 8192.0).powi(2) * self.t3 as f64;
+        let t_fine = var1 + var2;
+        (t_fine / 5120.0, t_fine)
+    }
+
+    /// Pressure in Pascal
+    fn pressure(&self, adc_p: i32, t_fine: f64) -> f64 {
+        let mut var1 = t_fine / 2.0 - 64000.0;
+        let mut var2 = var1 * var1 * self.p6 as f64 / 32768.0;
+        var2 += var1 * self.p5 as f64 * 2.0;
+        var2 = var2 / 4.0 + self.p4 as f64 * 65536.0;
+        var1 = (self.p3 as f64 * var1 * var1 / 524288.0 + self.p2 as f64 * var1) / 524288.0;
+        var1 = (1.0 + var1 / 32768.0) * self.p1 as f64;
+        if var1 == 0.0 {
+            // avoids a division by zero with an unprogrammed table
+            return 0.0;
+        }
+        let mut p = 1048576.0 - adc_p as f64;
+        p = (p - var2 / 4096.0) * 6250.0 / var1;
+        var1 = self.p9 as f64 * p * p / 2147483648.0;
+        var2 = p * self.p8 as f64 / 32768.0;
+        p + (var1 + var2 + self.p7 as f64) / 16.0
+    }
+
+    /// Relative humidity in percent
+    fn humidity(&self, adc_h: i32, t_fine: f64) -> f64 {
+        let var_h = t_fine - 76800.0;
+        let var_h = (adc_h as f64 - (self.h4 as f64 * 64.0 + self.h5 as f64 / 16384.0 * var_h))
+            * (self.h2 as f64 / 65536.0
+                * (1.0
+                    + self.h6 as f64 / 67108864.0
+                        * var_h
+                        * (1.0 + self.h3 as f64 / 67108864.0 * var_h)));
+        let var_h = var_h * (1.0 - self.h1 as f64 * var_h / 524288.0);
+        var_h.clamp(0.0, 100.0)
+    }
+}
+
+/// Altitude in meters at which the pressure is `pressure_pa` given the pressure at sea level,
+/// with the international barometric formula
+pub fn altitude_m(pressure_pa: f64, sea_level_pressure_pa: f64) -> f64 {
+    44330.0 * (1.0 - (pressure_pa / sea_level_pressure_pa).powf(1.0 / 5.255))
+}
+
+#[derive(DoCommand)]
+pub struct Bme280<H: I2CHandle> {
+    i2c_handle: H,
+    i2c_address: u8,
+    model: Model,
+    calibration: Calibration,
+    sea_level_pressure_pa: f64,
+}
+
+impl Bme280<I2cHandleType> {
+    pub(crate) fn from_config(
+        cfg: ConfigType,
+        dependencies: Vec<Dependency>,
+    ) -> Result<SensorType, SensorError> {
+        let board = get_board_from_dependencies(dependencies)
+            .ok_or(SensorError::ConfigError("bme280 missing board attribute"))?;
+        let i2c_name = cfg
+            .get_attribute::<String>("i2c_bus")
+            .map_err(|_| SensorError::ConfigError("bme280 missing i2c_bus attribute"))?;
+        let i2c_address = i2c_address_from_config(&cfg)?.unwrap_or(DEFAULT_I2C_ADDRESS);
+        if i2c_address != DEFAULT_I2C_ADDRESS && i2c_address != ALT_I2C_ADDRESS {
+            return Err(SensorError::ConfigError(
+                "bme280 i2c_address should be 0x76 (118) or 0x77 (119)",
+            ));
+        }
+        let sea_level_pressure_pa = match cfg.get_attribute::<f64>("sea_level_pressure_pa") {
+            Ok(pressure) if pressure > 0.0 => pressure,
+            Err(AttributeError::KeyNotFound(_)) => DEFAULT_SEA_LEVEL_PRESSURE_PA,
+            _ => {
+                return Err(SensorError::ConfigError(
+                    "bme280 sea_level_pressure_pa should be a positive number",
+                ))
+            }
+        };
+        let i2c_handle = board.get_i2c_by_name(i2c_name)?;
+        sensor_from_config(
+            &cfg,
+            Bme280::new(i2c_handle, i2c_address)?.with_sea_level_pressure(sea_level_pressure_pa),
+        )
+    }
+}
+
+impl<H: I2CHandle> Bme280<H> {
+    /// Reset the sensor, read its calibration and start measuring
+    pub fn new(mut i2c_handle: H, i2c_address: u8) -> Result<Self, SensorError> {
+        let mut chip_id = [0];
+        i2c_handle.write_read_i2c(i2c_address, &[CHIP_ID_REGISTER], &mut chip_id)?;
+        let model = match chip_id[0] {
+            BME280_CHIP_ID => Model::Bme280,
+            id if BMP280_CHIP_IDS.contains(&id) => Model::Bmp280,
+            _ => {
+                return Err(SensorError::SensorGenericError(
+                    "bme280 unexpected chip id, is this a BME280 or BMP280?",
+                ))
+            }
+        };
+        i2c_handle.write_i2c(i2c_address, &[RESET_REGISTER, RESET_COMMAND])?;
+        // the calibration is copied from the NVM in 2ms after a reset
+        std::thread::sleep(std::time::Duration::from_millis(2));
+
+        let mut table = [0; CALIBRATION_LEN];
+        i2c_handle.write_read_i2c(i2c_address, &[CALIBRATION_REGISTER], &mut table)?;
+        let calibration = if model == Model::Bme280 {
+            let mut humidity_table = [0; HUMIDITY_CALIBRATION_LEN];
+            i2c_handle.write_read_i2c(
+                i2c_address,
+                &[HUMIDITY_CALIBRATION_REGISTER],
+                &mut humidity_table,
+            )?;
+            i2c_handle.write_i2c(i2c_address, &[CTRL_HUM_REGISTER, CTRL_HUM])?;
+            Calibration::from_registers(&table, Some(&humidity_table))
+        } else {
+            Calibration::from_registers(&table, None)
+        };
+        i2c_handle.write_i2c(i2c_address, &[CONFIG_REGISTER, CONFIG])?;
+        i2c_handle.write_i2c(i2c_address, &[CTRL_MEAS_REGISTER, CTRL_MEAS])?;
+
+        Ok(Self {
+            i2c_handle,
+            i2c_address,
+            model,
+            calibration,
+            sea_level_pressure_pa: DEFAULT_SEA_LEVEL_PRESSURE_PA,
+        })
+    }
+
+    /// Pressure at sea level in Pascal the altitude is computed from, defaults to the standard
+    /// atmosphere (101325Pa), use the local pressure adjusted to sea level for an accurate
+    /// altitude
+    pub fn with_sea_level_pressure(mut self, sea_level_pressure_pa: f64) -> Self {
+        self.sea_level_pressure_pa = sea_level_pressure_pa;
+        self
+    }
+
+    pub fn model(&self) -> Model {
+        self.model
+    }
+
+    fn measure(&mut self) -> Result<TypedReadingsResult<f64>, SensorError> {
+        // pressure, temperature and humidity are read in one burst so they belong to the same
+        // measurement
+        let mut data = [0; 8];
+        self.i2c_handle
+            .write_read_i2c(self.i2c_address, &[DATA_REGISTER], &mut data)?;
+        let adc_p = ((data[0] as i32) << 12) | ((data[1] as i32) << 4) | ((data[2] as i32) >> 4);
+        let adc_t = ((data[3] as i32) << 12) | ((data[4] as i32) << 4) | ((data[5] as i32) >> 4);
+        let adc_h = ((data[6] as i32) << 8) | data[7] as i32;
+
+        let (temperature, t_fine) = self.calibration.temperature(adc_t);
+        let pressure = self.calibration.pressure(adc_p, t_fine);
+        let mut readings = HashMap::from([
+            ("temperature_celsius".to_string(), temperature),
+            ("pressure_pa".to_string(), pressure),
+            (
+                "altitude_m".to_string(),
+                altitude_m(pressure, self.sea_level_pressure_pa),
+            ),
+        ]);
+        if self.model == Model::Bme280 {
+            readings.insert(
+                "relative_humidity_pct".to_string(),
+                self.calibration.humidity(adc_h, t_fine),
+            );
+        }
+        Ok(readings)
+    }
+}
+
+impl<H: I2CHandle> Sensor for Bme280<H> {}
+
+impl<H: I2CHandle> Readings for Bme280<H> {
+    fn get_generic_readings(&mut self) -> Result<GenericReadingsResult, SensorError> {
+        Ok(self
+            .measure()?
+            .into_iter()
+            .map(|v| (v.0, SensorResult::<f64> { value: v.1 }.into()))
+            .collect())
+    }
+}
+
+impl<H: I2CHandle> Status for Bme280<H> {
+    fn get_status(&self) -> Result<Option<google::protobuf::Struct>, StatusError> {
+        Ok(Some(google::protobuf::Struct {
+            fields: HashMap::new(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::i2c::I2CErrors;
+    use std::{cell::RefCell, rc::Rc};
+
+    // register map of a device, reads start at the register selected by the last write
+    #[derive(Clone)]
+    struct RegisterMap(Rc<RefCell<[u8; 256]>>);
+
+    impl I2CHandle for RegisterMap {
+        fn name(&self) -> String {
+            "registers".to_owned()
+        }
+        fn write_i2c(&mut self, _address: u8, bytes: &[u8]) -> Result<(), I2CErrors> {
+            let start = bytes[0] as usize;
+            self.0.borrow_mut()[start..start + bytes.len() - 1].copy_from_slice(&bytes[1..]);
+            Ok(())
+        }
+        fn write_read_i2c(
+            &mut self,
+            _address: u8,
+            bytes: &[u8],
+            buffer: &mut [u8],
+        ) -> Result<(), I2CErrors> {
+            let start = bytes[0] as usize;
+            buffer.copy_from_slice(&self.0.borrow()[start..start + buffer.len()]);
+            Ok(())
+        }
+    }
+
+    // calibration and measurement from the compensation example of the BMP280 datasheet
+    fn datasheet_registers(chip_id: u8) -> [u8; 256] {
+        let mut registers = [0; 256];
+        registers[CHIP_ID_REGISTER as usize] = chip_id;
+        let table: [i32; 12] = [
+            27504, 26435, -1000, 36477, -10685, 3024, 2855, 140, -7, 15500, -14600, 6000,
+        ];
+        for (i, value) in table.iter().enumerate() {
+            let offset = CALIBRATION_REGISTER as usize + 2 * i;
+            registers[offset..offset + 2].copy_from_slice(&(*value as u16).to_le_bytes());
+        }
+        // humidity calibration of a BME280: h1 75, h2 362, h3 0, h4 324, h5 0, h6 30
+        registers[0xA1] = 75;
+        registers[0xE1..0xE8].copy_from_slice(&[0x6A, 0x01, 0x00, 0x14, 0x04, 0x00, 0x1E]);
+        // adc_P 415148, adc_T 519888, adc_H 30000
+        registers[0xF7..0xFF].copy_from_slice(&[0x65, 0x5A, 0xC0, 0x7E, 0xED, 0x00, 0x75, 0x30]);
+        registers
+    }
+
+    #[test_log::test]
+    fn test_bme280_compensation() {
+        let registers = RegisterMap(Rc::new(RefCell::new(datasheet_registers(
+            BMP280_CHIP_IDS[0],
+        ))));
+        let mut sensor = Bme280::new(registers.clone(), DEFAULT_I2C_ADDRESS).unwrap();
+        assert_eq!(sensor.model(), Model::Bmp280);
+        assert_eq!(sensor.calibration.t3, -1000);
+        assert_eq!(sensor.calibration.p9, 6000);
+
+        let readings = sensor.measure().unwrap();
+        assert!((readings["temperature_celsius"] - 25.08).abs() < 0.01);
+        assert!((readings["pressure_pa"] - 100653.27).abs() < 0.1);
+        assert!((readings["altitude_m"] - 56.0).abs() < 1.0);
+        assert!(!readings.contains_key("relative_humidity_pct"));
+
+        // the sensor was started in normal mode
+        assert_eq!(registers.0.borrow()[CTRL_MEAS_REGISTER as usize], CTRL_MEAS);
+    }
+
+    #[test_log::test]
+    fn test_bme280_humidity() {
+        let registers = RegisterMap(Rc::new(RefCell::new(datasheet_registers(BME280_CHIP_ID))));
+        let mut sensor = Bme280::new(registers.clone(), DEFAULT_I2C_ADDRESS)
+            .unwrap()
+            .with_sea_level_pressure(100653.27);
+        assert_eq!(sensor.model(), Model::Bme280);
+        assert_eq!(sensor.calibration.h2, 362);
+        assert_eq!(sensor.calibration.h4, 324);
+        assert_eq!(sensor.calibration.h6, 30);
+
+        let readings = sensor.measure().unwrap();
+        let humidity = readings["relative_humidity_pct"];
+        assert!(humidity > 0.0 && humidity < 100.0);
+        assert!(readings["altitude_m"].abs() < 0.1);
+        assert_eq!(registers.0.borrow()[CTRL_HUM_REGISTER as usize], CTRL_HUM);
+
+        let registers = RegisterMap(Rc::new(RefCell::new(datasheet_registers(0x42))));
+        assert!(Bme280::new(registers.clone(), DEFAULT_I2C_ADDRESS).is_err());
+    }
+}
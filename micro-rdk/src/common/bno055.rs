@@ -0,0 +1,482 @@
+//! Movement sensor driver for the Bosch BNO055 9-axis IMU over I2C.
+//! Datasheet: https://www.bosch-sensortec.com/media/boschsensortec/downloads/datasheets/bst-bno055-ds000.pdf
+//!
+//! The chip fuses its accelerometer, gyroscope and magnetometer into an absolute orientation. It
+//! runs in the NDOF fusion mode by default, `mode` can be set to `imu` to leave the magnetometer
+//! out (the heading is then relative to the orientation at start up and no compass heading is
+//! reported).
+//!
+//! Readings are the angular velocity (degrees/s), the linear acceleration (m/s², including
+//! gravity), the compass heading (degrees clockwise from the magnetic north) and the
+//! `euler_angles` (`roll`, `pitch` and `yaw` in degrees). Like for any movement sensor, the
+//! `heading_offset_deg` and `declination_deg` attributes correct the compass heading for the
+//! install, see [`HeadingCorrection`](super::movement_sensor::HeadingCorrection).
+//! The sensor is found at 0x28 when COM3 is wired to ground or 0x29 when wired to VDDIO, set
+//! with the `i2c_address` attribute.
+//!
+//! The fusion is only accurate once the sensors are calibrated, which happens in the background
+//! as the device is moved around. `{"get_calibration_status": true}` returns the calibration
+//! level (0 to 3) of the `system`, `gyroscope`, `accelerometer` and `magnetometer` along with
+//! `fully_calibrated`.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use super::board::Board;
+use super::config::{AttributeError, ConfigType};
+use super::generic::{DoCommand, GenericError};
+use super::i2c::{i2c_address_from_config, I2CErrors, I2CHandle, I2cHandleType};
+use super::math_utils::Vector3;
+use super::movement_sensor::{
+    get_movement_sensor_generic_readings, GeoPosition, MovementSensor,
+    MovementSensorSupportedMethods, MovementSensorType,
+};
+use super::registry::{get_board_from_dependencies, ComponentRegistry, Dependency};
+use super::sensor::{GenericReadingsResult, Readings, SensorError};
+use super::status::{Status, StatusError};
+use crate::google::protobuf::{value::Kind, Struct, Value};
+
+const DEFAULT_I2C_ADDRESS: u8 = 0x28;
+const ALT_I2C_ADDRESS: u8 = 0x29;
+
+const CHIP_ID_REGISTER: u8 = 0x00;
+const CHIP_ID: u8 = 0xA0;
+const PAGE_ID_REGISTER: u8 = 0x07;
+const ACC_DATA_REGISTER: u8 = 0x08;
+const GYR_DATA_REGISTER: u8 = 0x14;
+const EUL_DATA_REGISTER: u8 = 0x1A;
+const CALIB_STAT_REGISTER: u8 = 0x35;
+const UNIT_SEL_REGISTER: u8 = 0x3B;
+const OPR_MODE_REGISTER: u8 = 0x3D;
+const PWR_MODE_REGISTER: u8 = 0x3E;
+
+const PWR_MODE_NORMAL: u8 = 0x00;
+// m/s², degrees/s, degrees, Celsius and the Windows orientation convention (pitch increasing
+// when the nose goes down)
+const UNIT_SEL: u8 = 0x00;
+
+// LSB per unit of the data registers
+const ACC_LSB_PER_MS2: f64 = 100.0;
+const GYR_LSB_PER_DPS: f64 = 16.0;
+const EUL_LSB_PER_DEG: f64 = 16.0;
+
+// switching from the configuration mode takes 7ms, switching to it 19ms
+const TO_FUSION_MODE_DELAY: Duration = Duration::from_millis(7);
+const TO_CONFIG_MODE_DELAY: Duration = Duration::from_millis(19);
+
+pub(crate) fn register_models(registry: &mut ComponentRegistry) {
+    if registry
+        .register_movement_sensor("bno055", &Bno055::from_config)
+        .is_err()
+    {
+        log::error!("bno055 model is already registered")
+    }
+}
+
+/// Operation modes of the chip, the sensor is set up in `Config` and then runs in a fusion mode
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OperationMode {
+    Config = 0x00,
+    /// Accelerometer and gyroscope, the orientation is relative
+    Imu = 0x08,
+    /// Accelerometer, gyroscope and magnetometer, the orientation is absolute
+    #[default]
+    Ndof = 0x0C,
+}
+
+impl TryFrom<&str> for OperationMode {
+    type Error = SensorError;
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "ndof" => Ok(Self::Ndof),
+            "imu" => Ok(Self::Imu),
+            _ => Err(SensorError::ConfigError(
+                "bno055 mode should be one of ndof or imu",
+            )),
+        }
+    }
+}
+
+/// Calibration levels, from 0 (not calibrated) to 3 (fully calibrated)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CalibrationStatus {
+    pub system: u8,
+    pub gyroscope: u8,
+    pub accelerometer: u8,
+    pub magnetometer: u8,
+}
+
+impl CalibrationStatus {
+    fn from_register(calib_stat: u8) -> Self {
+        Self {
+            system: (calib_stat >> 6) & 0x03,
+            gyroscope: (calib_stat >> 4) & 0x03,
+            accelerometer: (calib_stat >> 2) & 0x03,
+            magnetometer: calib_stat & 0x03,
+        }
+    }
+
+    pub fn is_fully_calibrated(&self) -> bool {
+        self.system == 3 && self.gyroscope == 3 && self.accelerometer == 3 && self.magnetometer == 3
+    }
+}
+
+impl From<CalibrationStatus> for Struct {
+    fn from(value: CalibrationStatus) -> Self {
+        let level = |level: u8| Value {
+            kind: Some(Kind::NumberValue(level as f64)),
+        };
+        Struct {
+            fields: HashMap::from([
+                ("system".to_string(), level(value.system)),
+                ("gyroscope".to_string(), level(value.gyroscope)),
+                ("accelerometer".to_string(), level(value.accelerometer)),
+                ("magnetometer".to_string(), level(value.magnetometer)),
+                (
+                    "fully_calibrated".to_string(),
+                    Value {
+                        kind: Some(Kind::BoolValue(value.is_fully_calibrated())),
+                    },
+                ),
+            ]),
+        }
+    }
+}
+
+/// Orientation in degrees, the yaw being the heading
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct EulerAngles {
+    pub roll: f64,
+    pub pitch: f64,
+    pub yaw: f64,
+}
+
+impl From<EulerAngles> for Value {
+    fn from(value: EulerAngles) -> Self {
+        let angle = |angle: f64| Value {
+            kind: Some(Kind::NumberValue(angle)),
+        };
+        Value {
+            kind: Some(Kind::StructValue(Struct {
+                fields: HashMap::from([
+                    ("roll".to_string(), angle(value.roll)),
+                    ("pitch".to_string(), angle(value.pitch)),
+                    ("yaw".to_string(), angle(value.yaw)),
+                ]),
+            })),
+        }
+    }
+}
+
+// three little endian i16 scaled down by `lsb_per_unit`
+fn vector_from_reading(reading: &[u8; 6], lsb_per_unit: f64) -> [f64; 3] {
+    let axis = |i: usize| i16::from_le_bytes([reading[i], reading[i + 1]]) as f64 / lsb_per_unit;
+    [axis(0), axis(2), axis(4)]
+}
+
+pub struct Bno055<H> {
+    i2c_handle: H,
+    i2c_address: u8,
+    mode: OperationMode,
+}
+
+impl Bno055<I2cHandleType> {
+    pub(crate) fn from_config(
+        cfg: ConfigType,
+        dependencies: Vec<Dependency>,
+    ) -> Result<MovementSensorType, SensorError> {
+        let board = get_board_from_dependencies(dependencies)
+            .ok_or(SensorError::ConfigError("bno055 missing board attribute"))?;
+        let i2c_name = cfg
+            .get_attribute::<String>("i2c_bus")
+            .map_err(|_| SensorError::ConfigError("bno055 missing i2c_bus attribute"))?;
+        let i2c_address = i2c_address_from_config(&cfg)?.unwrap_or(DEFAULT_I2C_ADDRESS);
+        if i2c_address != DEFAULT_I2C_ADDRESS && i2c_address != ALT_I2C_ADDRESS {
+            return Err(SensorError::ConfigError(
+                "bno055 i2c_address should be 0x28 (40) or 0x29 (41)",
+            ));
+        }
+        let mode = match cfg.get_attribute::<String>("mode") {
+            Ok(mode) => mode.as_str().try_into()?,
+            Err(AttributeError::KeyNotFound(_)) => OperationMode::default(),
+            Err(_) => {
+                return Err(SensorError::ConfigError(
+                    "bno055 mode should be one of ndof or imu",
+                ))
+            }
+        };
+        let i2c_handle = board.get_i2c_by_name(i2c_name)?;
+        Ok(Arc::new(Mutex::new(Bno055::new(
+            i2c_handle,
+            i2c_address,
+            mode,
+        )?)))
+    }
+}
+
+impl<H: I2CHandle> Bno055<H> {
+    /// Check the chip id and start the fusion in `mode`
+    pub fn new(
+        mut i2c_handle: H,
+        i2c_address: u8,
+        mode: OperationMode,
+    ) -> Result<Self, SensorError> {
+        let mut chip_id = [0];
+        i2c_handle.write_read_i2c(i2c_address, &[CHIP_ID_REGISTER], &mut chip_id)?;
+        if chip_id[0] != CHIP_ID {
+            return Err(SensorError::SensorGenericError(
+                "bno055 unexpected chip id, is this a BNO055?",
+            ));
+        }
+        let mut sensor = Self {
+            i2c_handle,
+            i2c_address,
+            mode: OperationMode::Config,
+        };
+        sensor.set_mode(OperationMode::Config)?;
+        sensor.write_register(PAGE_ID_REGISTER, 0)?;
+        sensor.write_register(PWR_MODE_REGISTER, PWR_MODE_NORMAL)?;
+        sensor.write_register(UNIT_SEL_REGISTER, UNIT_SEL)?;
+        sensor.set_mode(mode)?;
+        Ok(sensor)
+    }
+
+    pub fn mode(&self) -> OperationMode {
+        self.mode
+    }
+
+    fn write_register(&mut self, register: u8, value: u8) -> Result<(), I2CErrors> {
+        self.i2c_handle
+            .write_i2c(self.i2c_address, &[register, value])
+    }
+
+    fn read_registers<const N: usize>(&mut self, register: u8) -> Result<[u8; N], I2CErrors> {
+        let mut buffer = [0; N];
+        self.i2c_handle
+            .write_read_i2c(self.i2c_address, &[register], &mut buffer)?;
+        Ok(buffer)
+    }
+
+    fn set_mode(&mut self, mode: OperationMode) -> Result<(), I2CErrors> {
+        self.write_register(OPR_MODE_REGISTER, mode as u8)?;
+        std::thread::sleep(if mode == OperationMode::Config {
+            TO_CONFIG_MODE_DELAY
+        } else {
+            TO_FUSION_MODE_DELAY
+        });
+        self.mode = mode;
+        Ok(())
+    }
+
+    pub fn get_calibration_status(&mut self) -> Result<CalibrationStatus, SensorError> {
+        let [calib_stat] = self.read_registers::<1>(CALIB_STAT_REGISTER)?;
+        Ok(CalibrationStatus::from_register(calib_stat))
+    }
+
+    pub fn get_euler_angles(&mut self) -> Result<EulerAngles, SensorError> {
+        let [yaw, roll, pitch] =
+            vector_from_reading(&self.read_registers(EUL_DATA_REGISTER)?, EUL_LSB_PER_DEG);
+        Ok(EulerAngles { roll, pitch, yaw })
+    }
+}
+
+impl<H: I2CHandle> MovementSensor for Bno055<H> {
+    fn get_properties(&self) -> MovementSensorSupportedMethods {
+        MovementSensorSupportedMethods {
+            position_supported: false,
+            linear_velocity_supported: false,
+            angular_velocity_supported: true,
+            linear_acceleration_supported: true,
+            compass_heading_supported: self.mode == OperationMode::Ndof,
+        }
+    }
+
+    fn get_angular_velocity(&mut self) -> Result<Vector3, SensorError> {
+        let [x, y, z] =
+            vector_from_reading(&self.read_registers(GYR_DATA_REGISTER)?, GYR_LSB_PER_DPS);
+        Ok(Vector3 { x, y, z })
+    }
+
+    fn get_linear_acceleration(&mut self) -> Result<Vector3, SensorError> {
+        let [x, y, z] =
+            vector_from_reading(&self.read_registers(ACC_DATA_REGISTER)?, ACC_LSB_PER_MS2);
+        Ok(Vector3 { x, y, z })
+    }
+
+    fn get_compass_heading(&mut self) -> Result<f64, SensorError> {
+        if self.mode != OperationMode::Ndof {
+            return Err(SensorError::SensorMethodUnimplemented(
+                "get_compass_heading",
+            ));
+        }
+        Ok(self.get_euler_angles()?.yaw)
+    }
+
+    fn get_position(&mut self) -> Result<GeoPosition, SensorError> {
+        Err(SensorError::SensorMethodUnimplemented("get_position"))
+    }
+
+    fn get_linear_velocity(&mut self) -> Result<Vector3, SensorError> {
+        Err(SensorError::SensorMethodUnimplemented(
+            "get_linear_velocity",
+        ))
+    }
+}
+
+impl<H: I2CHandle> Readings for Bno055<H> {
+    fn get_generic_readings(&mut self) -> Result<GenericReadingsResult, SensorError> {
+        let mut readings = get_movement_sensor_generic_readings(self)?;
+        readings.insert("euler_angles".to_string(), self.get_euler_angles()?.into());
+        Ok(readings)
+    }
+}
+
+impl<H: I2CHandle> DoCommand for Bno055<H> {
+    fn do_command(
+        &mut self,
+        command_struct: Option<Struct>,
+    ) -> Result<Option<Struct>, GenericError> {
+        let Some(command_struct) = command_struct else {
+            return Ok(None);
+        };
+        if command_struct.fields.contains_key("get_calibration_status") {
+            let status = self
+                .get_calibration_status()
+                .map_err(|e| GenericError::Other(e.into()))?;
+            return Ok(Some(status.into()));
+        }
+        Err(GenericError::Other(
+            format!(
+                "unknown bno055 command {:?}",
+                command_struct.fields.keys().collect::<Vec<_>>()
+            )
+            .into(),
+        ))
+    }
+}
+
+impl<H> Status for Bno055<H> {
+    fn get_status(&self) -> Result<Option<Struct>, StatusError> {
+        Ok(Some(Struct {
+            fields: HashMap::new(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::test_utils::{command, do_command};
+    use std::{cell::RefCell, rc::Rc};
+
+    // register map of a device, reads start at the register selected by the last write
+    #[derive(Clone)]
+    struct RegisterMap(Rc<RefCell<[u8; 128]>>);
+
+    impl I2CHandle for RegisterMap {
+        fn name(&self) -> String {
+            "registers".to_owned()
+        }
+        fn write_i2c(&mut self, _address: u8, bytes: &[u8]) -> Result<(), I2CErrors> {
+            let start = bytes[0] as usize;
+            self.0.borrow_mut()[start..start + bytes.len() - 1].copy_from_slice(&bytes[1..]);
+            Ok(())
+        }
+        fn write_read_i2c(
+            &mut self,
+            _address: u8,
+            bytes: &[u8],
+            buffer: &mut [u8],
+        ) -> Result<(), I2CErrors> {
+            let start = bytes[0] as usize;
+            buffer.copy_from_slice(&self.0.borrow()[start..start + buffer.len()]);
+            Ok(())
+        }
+    }
+
+    fn set_vector(registers: &RegisterMap, register: u8, values: [i16; 3]) {
+        let start = register as usize;
+        for (i, value) in values.iter().enumerate() {
+            registers.0.borrow_mut()[start + 2 * i..start + 2 * i + 2]
+                .copy_from_slice(&value.to_le_bytes());
+        }
+    }
+
+    #[test_log::test]
+    fn test_bno055() {
+        let registers = RegisterMap(Rc::new(RefCell::new([0; 128])));
+        assert!(Bno055::new(registers.clone(), DEFAULT_I2C_ADDRESS, OperationMode::Ndof).is_err());
+
+        registers.0.borrow_mut()[CHIP_ID_REGISTER as usize] = CHIP_ID;
+        let mut sensor =
+            Bno055::new(registers.clone(), DEFAULT_I2C_ADDRESS, OperationMode::Ndof).unwrap();
+        assert_eq!(registers.0.borrow()[OPR_MODE_REGISTER as usize], 0x0C);
+
+        set_vector(&registers, ACC_DATA_REGISTER, [981, -50, 0]);
+        set_vector(&registers, GYR_DATA_REGISTER, [160, 0, -32]);
+        // heading 90°, roll -10°, pitch 2.5°
+        set_vector(&registers, EUL_DATA_REGISTER, [1440, -160, 40]);
+
+        let acc = sensor.get_linear_acceleration().unwrap();
+        assert_eq!((acc.x, acc.y, acc.z), (9.81, -0.5, 0.0));
+        let vel = sensor.get_angular_velocity().unwrap();
+        assert_eq!((vel.x, vel.y, vel.z), (10.0, 0.0, -2.0));
+        assert_eq!(sensor.get_compass_heading().unwrap(), 90.0);
+
+        let readings = sensor.get_generic_readings().unwrap();
+        assert!(readings.contains_key("compass_heading"));
+        let Some(Kind::StructValue(euler)) = &readings["euler_angles"].kind else {
+            panic!("euler_angles should be a struct")
+        };
+        assert_eq!(euler.fields["roll"].kind, Some(Kind::NumberValue(-10.0)));
+        assert_eq!(euler.fields["pitch"].kind, Some(Kind::NumberValue(2.5)));
+        assert_eq!(euler.fields["yaw"].kind, Some(Kind::NumberValue(90.0)));
+
+        // without the magnetometer the heading isn't absolute
+        let mut sensor =
+            Bno055::new(registers.clone(), DEFAULT_I2C_ADDRESS, OperationMode::Imu).unwrap();
+        assert_eq!(registers.0.borrow()[OPR_MODE_REGISTER as usize], 0x08);
+        assert!(sensor.get_compass_heading().is_err());
+        let readings = sensor.get_generic_readings().unwrap();
+        assert!(!readings.contains_key("compass_heading"));
+        assert!(readings.contains_key("euler_angles"));
+    }
+
+    #[test_log::test]
+    fn test_bno055_calibration_status() {
+        let registers = RegisterMap(Rc::new(RefCell::new([0; 128])));
+        registers.0.borrow_mut()[CHIP_ID_REGISTER as usize] = CHIP_ID;
+        let mut sensor =
+            Bno055::new(registers.clone(), DEFAULT_I2C_ADDRESS, OperationMode::Ndof).unwrap();
+
+        // system 3, gyroscope 3, accelerometer 1, magnetometer 2
+        registers.0.borrow_mut()[CALIB_STAT_REGISTER as usize] = 0b11_11_01_10;
+        let status = do_command(
+            &mut sensor,
+            command([("get_calibration_status", Kind::BoolValue(true))]),
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(status.fields["system"].kind, Some(Kind::NumberValue(3.0)));
+        assert_eq!(
+            status.fields["accelerometer"].kind,
+            Some(Kind::NumberValue(1.0))
+        );
+        assert_eq!(
+            status.fields["magnetometer"].kind,
+            Some(Kind::NumberValue(2.0))
+        );
+        assert_eq!(
+            status.fields["fully_calibrated"].kind,
+            Some(Kind::BoolValue(false))
+        );
+
+        registers.0.borrow_mut()[CALIB_STAT_REGISTER as usize] = 0xFF;
+        assert!(sensor
+            .get_calibration_status()
+            .unwrap()
+            .is_fully_calibrated());
+        assert!(do_command(&mut sensor, command([("calibrate", Kind::BoolValue(true))])).is_err());
+    }
+}
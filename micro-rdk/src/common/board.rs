@@ -8,14 +8,23 @@ use crate::{
     proto::component,
 };
 
+use async_executor::Task;
 use log::*;
-use std::{collections::HashMap, sync::Arc, sync::Mutex, time::Duration};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, Weak},
+    time::Duration,
+};
 
 use super::{
-    analog::{AnalogReaderType, FakeAnalogReader},
-    config::ConfigType,
-    generic::DoCommand,
-    i2c::{FakeI2CHandle, FakeI2cConfig, I2CErrors, I2CHandle, I2cHandleType},
+    analog::{
+        AnalogError, AnalogReaderType, AnalogWriter, AnalogWriterConfig, AnalogWriterType,
+        FakeAnalogReader, FakeAnalogWriter,
+    },
+    config::{AttributeError, ConfigType},
+    exec::Executor,
+    generic::{DoCommand, GenericError},
+    i2c::{add_i2c_muxes, FakeI2CHandle, FakeI2cConfig, I2CErrors, I2CHandle, I2cHandleType},
     registry::ComponentRegistry,
 };
 #[cfg(feature = "esp32")]
@@ -33,6 +42,10 @@ pub enum BoardError {
     InvalidGpioNumber(u32),
     #[error("analog reader {0} not found")]
     AnalogReaderNotFound(String),
+    #[error("analog writer {0} not found")]
+    AnalogWriterNotFound(String),
+    #[error(transparent)]
+    AnalogWriteError(#[from] AnalogError),
     #[error("board unsupported argument {0} ")]
     BoardUnsupportedArgument(&'static str),
     #[error("i2c bus {0} not found")]
@@ -46,6 +59,8 @@ pub enum BoardError {
     #[error(transparent)]
     #[cfg(feature = "esp32")]
     EspError(#[from] EspError),
+    #[error("pwm frequency {0}Hz is unsupported")]
+    UnsupportedPwmFrequency(u64),
     #[error("construction error test")]
     TestError,
 }
@@ -102,37 +117,199 @@ pub trait Board: Status + DoCommand {
     /// Set the pin to the given PWM frequency (in Hz).
     /// When frequency is 0, the board will unregister the pin and PWM channel from
     /// the timer and removes the PWM signal.
-    fn set_pwm_frequency(&mut self, pin: i32, frequency_hz: u64) -> Result<(), BoardError>;
+    fn set_pwm_frequency(&mut self, _pin: i32, _frequency_hz: u64) -> Result<(), BoardError> {
+        Err(BoardError::BoardMethodNotSupported("set_pwm_frequency"))
+    }
+
+    /// Output `value` on the analog writer (e.g. a DAC channel) named `name`
+    fn write_analog(&mut self, _name: &str, _value: u16) -> Result<(), BoardError> {
+        Err(BoardError::BoardMethodNotSupported("write_analog"))
+    }
+
+    /// Register `pin` as an input so its level can be read with `get_gpio_level`, the pin is
+    /// never driven and pulled up when `pull_up` is set (for open-drain lines)
+    fn configure_input_pin(&mut self, _pin: i32, _pull_up: bool) -> Result<(), BoardError> {
+        Err(BoardError::BoardMethodNotSupported("configure_input_pin"))
+    }
+}
+
+/// Handles the board commands sent through DoCommand, currently only
+/// `{"set_pwm_frequency": {"pin": 12, "frequency_hz": 50}}`
+pub fn board_do_command<B: Board + ?Sized>(
+    board: &mut B,
+    command_struct: Option<google::protobuf::Struct>,
+) -> Result<Option<google::protobuf::Struct>, GenericError> {
+    let Some(command_struct) = command_struct else {
+        return Ok(None);
+    };
+    for (key, val) in &command_struct.fields {
+        match key.as_str() {
+            "set_pwm_frequency" => {
+                let args = match &val.kind {
+                    Some(google::protobuf::value::Kind::StructValue(args)) => args,
+                    _ => {
+                        return Err(GenericError::Other(
+                            "set_pwm_frequency expects a `pin` and a `frequency_hz`".into(),
+                        ))
+                    }
+                };
+                let number = |name: &str| match args.fields.get(name).and_then(|v| v.kind.as_ref())
+                {
+                    Some(google::protobuf::value::Kind::NumberValue(n))
+                        if *n >= 0.0 && n.fract() == 0.0 =>
+                    {
+                        Ok(*n)
+                    }
+                    _ => Err(GenericError::Other(
+                        format!(
+                            "set_pwm_frequency expects `{}` to be a positive integer",
+                            name
+                        )
+                        .into(),
+                    )),
+                };
+                let pin = number("pin")? as i32;
+                let frequency_hz = number("frequency_hz")? as u64;
+                board
+                    .set_pwm_frequency(pin, frequency_hz)
+                    .map_err(|e| GenericError::Other(e.into()))?;
+            }
+            _ => {
+                return Err(GenericError::Other(
+                    format!("unknown board command {}", key).into(),
+                ))
+            }
+        }
+    }
+    Ok(None)
 }
 
 /// An alias for a thread-safe handle to a struct that implements the [Board] trait
 pub type BoardType = Arc<Mutex<dyn Board>>;
 
+/// Default period at which an external watchdog is fed
+pub const DEFAULT_EXTERNAL_WATCHDOG_INTERVAL: Duration = Duration::from_secs(1);
+
+/// An external hardware watchdog fed by toggling a GPIO, configured with the optional
+/// `external_watchdog` board attribute, for example `{"pin": 13, "interval_ms": 500}`
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExternalWatchdogConfig {
+    pub pin: i32,
+    pub interval: Duration,
+}
+
+impl ExternalWatchdogConfig {
+    pub(crate) fn from_config(cfg: &ConfigType) -> Result<Option<Self>, BoardError> {
+        let watchdog = match cfg.get_attribute::<HashMap<&str, i32>>("external_watchdog") {
+            Ok(watchdog) => watchdog,
+            Err(AttributeError::KeyNotFound(_)) => return Ok(None),
+            Err(_) => {
+                return Err(BoardError::BoardUnsupportedArgument(
+                    "external_watchdog should map pin and interval_ms to numbers",
+                ))
+            }
+        };
+        let pin = *watchdog
+            .get("pin")
+            .ok_or(BoardError::BoardUnsupportedArgument(
+                "external_watchdog requires a pin",
+            ))?;
+        let interval = match watchdog.get("interval_ms") {
+            None => DEFAULT_EXTERNAL_WATCHDOG_INTERVAL,
+            Some(ms) if *ms > 0 => Duration::from_millis(*ms as u64),
+            Some(_) => {
+                return Err(BoardError::BoardUnsupportedArgument(
+                    "external_watchdog interval_ms should be positive",
+                ))
+            }
+        };
+        Ok(Some(Self { pin, interval }))
+    }
+}
+
+/// Parse the optional `default_pin_levels` board attribute, mapping pin numbers to `true` (high)
+/// or `false` (low), e.g. `{"12": false}`. Pins are returned in ascending order.
+pub(crate) fn default_pin_levels(cfg: &ConfigType) -> Result<Vec<(i32, bool)>, BoardError> {
+    let levels = match cfg.get_attribute::<HashMap<&str, bool>>("default_pin_levels") {
+        Ok(levels) => levels,
+        Err(AttributeError::KeyNotFound(_)) => return Ok(vec![]),
+        Err(_) => {
+            return Err(BoardError::BoardUnsupportedArgument(
+                "default_pin_levels should map pin numbers to booleans",
+            ))
+        }
+    };
+    let mut levels = levels
+        .into_iter()
+        .map(|(pin, is_high)| {
+            pin.parse::<i32>().map(|pin| (pin, is_high)).map_err(|_| {
+                BoardError::BoardUnsupportedArgument(
+                    "default_pin_levels keys should be pin numbers",
+                )
+            })
+        })
+        .collect::<Result<Vec<_>, BoardError>>()?;
+    levels.sort_unstable();
+    Ok(levels)
+}
+
+/// Toggle the watchdog pin of `board` every `config.interval` on the local executor, until the
+/// board is dropped, at which point the returned task completes. Since feeding relies on the
+/// executor making progress, a hung firmware stops feeding the watchdog which then resets the
+/// device.
+pub fn feed_external_watchdog<B>(board: &Arc<Mutex<B>>, config: ExternalWatchdogConfig) -> Task<()>
+where
+    B: Board + ?Sized + 'static,
+{
+    let board: Weak<Mutex<B>> = Arc::downgrade(board);
+    Executor::new().spawn(async move {
+        let mut level = false;
+        while let Some(board) = board.upgrade() {
+            level = !level;
+            if let Err(err) = board.lock().unwrap().set_gpio_pin_level(config.pin, level) {
+                log::error!("failed to feed the external watchdog: {}", err);
+            }
+            drop(board);
+            async_io::Timer::after(config.interval).await;
+        }
+    })
+}
+
 #[doc(hidden)]
 /// A test implementation of a generic compute board
-#[derive(DoCommand)]
 pub struct FakeBoard {
     analogs: Vec<AnalogReaderType<u16>>,
-    i2cs: HashMap<String, Arc<Mutex<FakeI2CHandle>>>,
+    analog_writers: Vec<AnalogWriterType<u16>>,
+    i2cs: HashMap<String, I2cHandleType>,
     pin_pwms: HashMap<i32, f64>,
     pin_pwm_freq: HashMap<i32, u64>,
+    // levels set through set_gpio_pin_level, pins never set read high
+    pin_levels: HashMap<i32, bool>,
 }
 
 impl FakeBoard {
     pub fn new(analogs: Vec<AnalogReaderType<u16>>) -> Self {
-        let mut i2cs: HashMap<String, Arc<Mutex<FakeI2CHandle>>> = HashMap::new();
+        let mut i2cs: HashMap<String, I2cHandleType> = HashMap::new();
         let i2c0 = Arc::new(Mutex::new(FakeI2CHandle::new("i2c0".to_string())));
         i2cs.insert(i2c0.name(), i2c0);
         let i2c1 = Arc::new(Mutex::new(FakeI2CHandle::new("i2c1".to_string())));
         i2cs.insert(i2c1.name(), i2c1);
         FakeBoard {
             analogs,
+            analog_writers: vec![],
             i2cs,
             pin_pwms: HashMap::new(),
             pin_pwm_freq: HashMap::new(),
+            pin_levels: HashMap::new(),
         }
     }
 
+    /// Add `writers` to the analog writers of the board
+    pub fn with_analog_writers(mut self, writers: Vec<AnalogWriterType<u16>>) -> Self {
+        self.analog_writers.extend(writers);
+        self
+    }
+
     pub(crate) fn from_config(cfg: ConfigType) -> Result<BoardType, BoardError> {
         if cfg.get_attribute::<bool>("fail_new").unwrap_or(false) {
             return Err(BoardError::TestError);
@@ -151,38 +328,63 @@ impl FakeBoard {
             vec![]
         };
 
-        let i2cs = if let Ok(i2c_confs) = cfg.get_attribute::<Vec<FakeI2cConfig>>("i2cs") {
+        let analog_writers = cfg
+            .get_attribute::<Vec<AnalogWriterConfig>>("analog_writers")
+            .unwrap_or_default()
+            .into_iter()
+            .map(|conf| {
+                let writer: AnalogWriterType<u16> =
+                    Arc::new(Mutex::new(FakeAnalogWriter::new(conf.name)));
+                writer
+            })
+            .collect();
+
+        let mut i2cs = if let Ok(i2c_confs) = cfg.get_attribute::<Vec<FakeI2cConfig>>("i2cs") {
             let name_to_i2c = i2c_confs.iter().map(|v| {
                 let name = v.name.to_string();
                 let value: [u8; 3] = [v.value_1, v.value_2, v.value_3];
-                (
-                    name.to_string(),
-                    Arc::new(Mutex::new(FakeI2CHandle::new_with_value(name, value))),
-                )
+                let i2c: I2cHandleType = Arc::new(Mutex::new(FakeI2CHandle::new_with_value(
+                    name.clone(),
+                    value,
+                )));
+                (name, i2c)
             });
             HashMap::from_iter(name_to_i2c)
         } else {
             HashMap::new()
         };
+        add_i2c_muxes(&cfg, &mut i2cs)?;
 
-        Ok(Arc::new(Mutex::new(FakeBoard {
+        let board = Arc::new(Mutex::new(FakeBoard {
             analogs,
+            analog_writers,
             i2cs,
             pin_pwms: HashMap::new(),
             pin_pwm_freq: HashMap::new(),
-        })))
+            pin_levels: HashMap::new(),
+        }));
+        if let Some(watchdog) = ExternalWatchdogConfig::from_config(&cfg)? {
+            feed_external_watchdog(&board, watchdog).detach();
+        }
+        Ok(board)
     }
 }
 
 impl Board for FakeBoard {
     fn set_gpio_pin_level(&mut self, pin: i32, is_high: bool) -> Result<(), BoardError> {
         info!("set pin {} to {}", pin, is_high);
+        let _ = self.pin_levels.insert(pin, is_high);
         Ok(())
     }
 
     fn get_gpio_level(&self, pin: i32) -> Result<bool, BoardError> {
         info!("get pin {}", pin);
-        Ok(true)
+        Ok(*self.pin_levels.get(&pin).unwrap_or(&true))
+    }
+
+    fn configure_input_pin(&mut self, pin: i32, pull_up: bool) -> Result<(), BoardError> {
+        info!("configure pin {} as an input (pull up: {})", pin, pull_up);
+        Ok(())
     }
 
     fn get_analog_reader_by_name(&self, name: String) -> Result<AnalogReaderType<u16>, BoardError> {
@@ -232,6 +434,22 @@ impl Board for FakeBoard {
         self.pin_pwm_freq.insert(pin, frequency_hz);
         Ok(())
     }
+
+    fn write_analog(&mut self, name: &str, value: u16) -> Result<(), BoardError> {
+        match self.analog_writers.iter_mut().find(|w| w.name() == name) {
+            Some(writer) => Ok(writer.write(value)?),
+            None => Err(BoardError::AnalogWriterNotFound(name.to_owned())),
+        }
+    }
+}
+
+impl DoCommand for FakeBoard {
+    fn do_command(
+        &mut self,
+        command_struct: Option<google::protobuf::Struct>,
+    ) -> Result<Option<google::protobuf::Struct>, GenericError> {
+        board_do_command(self, command_struct)
+    }
 }
 
 impl Status for FakeBoard {
@@ -275,6 +493,10 @@ where
         self.lock().unwrap().set_gpio_pin_level(pin, is_high)
     }
 
+    fn configure_input_pin(&mut self, pin: i32, pull_up: bool) -> Result<(), BoardError> {
+        self.lock().unwrap().configure_input_pin(pin, pull_up)
+    }
+
     fn get_analog_reader_by_name(&self, name: String) -> Result<AnalogReaderType<u16>, BoardError> {
         self.lock().unwrap().get_analog_reader_by_name(name)
     }
@@ -310,4 +532,255 @@ where
     fn set_pwm_frequency(&mut self, pin: i32, frequency_hz: u64) -> Result<(), BoardError> {
         self.lock().unwrap().set_pwm_frequency(pin, frequency_hz)
     }
+
+    fn write_analog(&mut self, name: &str, value: u16) -> Result<(), BoardError> {
+        self.lock().unwrap().write_analog(name, value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        default_pin_levels, feed_external_watchdog, AnalogReaderType, AnalogWriterType, Board,
+        BoardError, ExternalWatchdogConfig, FakeBoard, I2cHandleType,
+    };
+    use crate::common::analog::FakeAnalogWriter;
+    use crate::common::config::{ConfigType, Kind as ConfigKind};
+    use crate::common::exec::Executor;
+    use crate::common::test_utils::{command, component_config, delegation_test, do_command};
+    use crate::google::protobuf::value::Kind;
+    use crate::proto::component::board::v1::PowerMode;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    delegation_test!(
+        test_board_delegation,
+        RecordingBoard as dyn Board,
+        wrappers: [|board| Arc::new(Mutex::new(board))],
+        impl Board {
+            fn set_gpio_pin_level(&mut self, _: i32, _: bool) -> Result<(), BoardError> {
+                Ok(())
+            }
+            fn get_gpio_level(&self, _: i32) -> Result<bool, BoardError> {
+                Ok(true)
+            }
+            fn get_analog_reader_by_name(
+                &self,
+                _: String,
+            ) -> Result<AnalogReaderType<u16>, BoardError> {
+                Err(BoardError::BoardMethodNotSupported(
+                    "get_analog_reader_by_name",
+                ))
+            }
+            fn set_power_mode(&self, _: PowerMode, _: Option<Duration>) -> Result<(), BoardError> {
+                Ok(())
+            }
+            fn get_i2c_by_name(&self, _: String) -> Result<I2cHandleType, BoardError> {
+                Err(BoardError::BoardMethodNotSupported("get_i2c_by_name"))
+            }
+            fn get_digital_interrupt_value(&self, _: i32) -> Result<u32, BoardError> {
+                Ok(0)
+            }
+            fn get_pwm_duty(&self, _: i32) -> f64 {
+                0.0
+            }
+            fn set_pwm_duty(&mut self, _: i32, _: f64) -> Result<(), BoardError> {
+                Ok(())
+            }
+            fn get_pwm_frequency(&self, _: i32) -> Result<u64, BoardError> {
+                Ok(0)
+            }
+            fn set_pwm_frequency(&mut self, _: i32, _: u64) -> Result<(), BoardError> {
+                Ok(())
+            }
+            fn write_analog(&mut self, _: &str, _: u16) -> Result<(), BoardError> {
+                Ok(())
+            }
+            fn configure_input_pin(&mut self, _: i32, _: bool) -> Result<(), BoardError> {
+                Ok(())
+            }
+        }
+        calls: |board| {
+            board.set_gpio_pin_level(1, true).unwrap();
+            board.get_gpio_level(1).unwrap();
+            assert!(board.get_analog_reader_by_name("a".to_owned()).is_err());
+            board.set_power_mode(PowerMode::Normal, None).unwrap();
+            assert!(board.get_i2c_by_name("i2c".to_owned()).is_err());
+            board.get_digital_interrupt_value(1).unwrap();
+            board.get_pwm_duty(1);
+            board.set_pwm_duty(1, 0.5).unwrap();
+            board.get_pwm_frequency(1).unwrap();
+            board.set_pwm_frequency(1, 1000).unwrap();
+            board.write_analog("dac", 128).unwrap();
+            board.configure_input_pin(1, true).unwrap();
+        }
+    );
+
+    #[test_log::test]
+    fn test_write_analog() {
+        let config = component_config([(
+            "analog_writers",
+            ConfigKind::VecValue(vec![ConfigKind::StructValue(HashMap::from([
+                (
+                    "name".to_owned(),
+                    ConfigKind::StringValue("valve".to_owned()),
+                ),
+                ("pin".to_owned(), ConfigKind::NumberValue(25.0)),
+            ]))]),
+        )]);
+        let board = FakeBoard::from_config(ConfigType::Dynamic(&config)).unwrap();
+        assert!(board.lock().unwrap().write_analog("valve", 200).is_ok());
+        assert!(matches!(
+            board.lock().unwrap().write_analog("pump", 200),
+            Err(BoardError::AnalogWriterNotFound(_))
+        ));
+
+        let valve = Arc::new(Mutex::new(FakeAnalogWriter::new("valve".to_owned())));
+        let writer: AnalogWriterType<u16> = valve.clone();
+        let mut board = FakeBoard::new(vec![]).with_analog_writers(vec![writer]);
+        board.write_analog("valve", 200).unwrap();
+        assert_eq!(valve.lock().unwrap().value(), 200);
+        board.write_analog("valve", 0).unwrap();
+        assert_eq!(valve.lock().unwrap().value(), 0);
+    }
+
+    #[test_log::test]
+    fn test_default_pin_levels() {
+        let levels = |attributes: Vec<(&str, ConfigKind)>| {
+            let config = component_config(attributes);
+            default_pin_levels(&ConfigType::Dynamic(&config))
+        };
+        assert_eq!(levels(vec![]).unwrap(), vec![]);
+        assert_eq!(
+            levels(vec![(
+                "default_pin_levels",
+                ConfigKind::StructValue(HashMap::from([
+                    ("15".to_owned(), ConfigKind::BoolValue(true)),
+                    ("12".to_owned(), ConfigKind::BoolValue(false)),
+                ])),
+            )])
+            .unwrap(),
+            vec![(12, false), (15, true)]
+        );
+        assert!(matches!(
+            levels(vec![(
+                "default_pin_levels",
+                ConfigKind::StructValue(HashMap::from([(
+                    "relay".to_owned(),
+                    ConfigKind::BoolValue(true)
+                )])),
+            )]),
+            Err(BoardError::BoardUnsupportedArgument(_))
+        ));
+        for malformed in [
+            ConfigKind::BoolValue(true),
+            ConfigKind::StructValue(HashMap::from([(
+                "12".to_owned(),
+                ConfigKind::StringValue("high".to_owned()),
+            )])),
+        ] {
+            assert!(matches!(
+                levels(vec![("default_pin_levels", malformed)]),
+                Err(BoardError::BoardUnsupportedArgument(_))
+            ));
+        }
+    }
+
+    #[test_log::test]
+    fn test_external_watchdog_config() {
+        let watchdog = |attributes: Vec<(&str, ConfigKind)>| {
+            let config = component_config(attributes);
+            ExternalWatchdogConfig::from_config(&ConfigType::Dynamic(&config))
+        };
+        let attribute = |fields: Vec<(&str, ConfigKind)>| {
+            vec![(
+                "external_watchdog",
+                ConfigKind::StructValue(
+                    fields.into_iter().map(|(k, v)| (k.to_owned(), v)).collect(),
+                ),
+            )]
+        };
+        assert_eq!(watchdog(vec![]).unwrap(), None);
+        assert_eq!(
+            watchdog(attribute(vec![("pin", ConfigKind::NumberValue(13.0))])).unwrap(),
+            Some(ExternalWatchdogConfig {
+                pin: 13,
+                interval: Duration::from_secs(1),
+            })
+        );
+        assert_eq!(
+            watchdog(attribute(vec![
+                ("pin", ConfigKind::NumberValue(13.0)),
+                ("interval_ms", ConfigKind::NumberValue(500.0)),
+            ]))
+            .unwrap()
+            .unwrap()
+            .interval,
+            Duration::from_millis(500)
+        );
+        for malformed in [
+            vec![("external_watchdog", ConfigKind::NumberValue(13.0))],
+            attribute(vec![(
+                "pin",
+                ConfigKind::StringValue("thirteen".to_owned()),
+            )]),
+            attribute(vec![("interval_ms", ConfigKind::NumberValue(500.0))]),
+            attribute(vec![
+                ("pin", ConfigKind::NumberValue(13.0)),
+                ("interval_ms", ConfigKind::NumberValue(0.0)),
+            ]),
+        ] {
+            assert!(matches!(
+                watchdog(malformed),
+                Err(BoardError::BoardUnsupportedArgument(_))
+            ));
+        }
+    }
+
+    #[test_log::test]
+    fn test_set_pwm_frequency_command() {
+        let set_frequency = |pin: f64, frequency_hz: f64| {
+            command([(
+                "set_pwm_frequency",
+                Kind::StructValue(command([
+                    ("pin", Kind::NumberValue(pin)),
+                    ("frequency_hz", Kind::NumberValue(frequency_hz)),
+                ])),
+            )])
+        };
+        let mut board = FakeBoard::new(vec![]);
+        assert!(do_command(&mut board, set_frequency(12.0, 50.0)).is_ok());
+        assert_eq!(board.get_pwm_frequency(12).unwrap(), 50);
+
+        assert!(do_command(&mut board, set_frequency(12.0, -1.0)).is_err());
+        assert!(do_command(&mut board, set_frequency(12.5, 50.0)).is_err());
+        assert!(do_command(
+            &mut board,
+            command([("set_pwm_frequency", Kind::NumberValue(50.0))])
+        )
+        .is_err());
+        assert!(do_command(&mut board, command([("set_pwm_duty", Kind::NullValue(0))])).is_err());
+        assert_eq!(board.get_pwm_frequency(12).unwrap(), 50);
+    }
+
+    #[test_log::test]
+    fn test_external_watchdog_stops_with_board() {
+        let board = Arc::new(Mutex::new(FakeBoard::new(vec![])));
+        let task = feed_external_watchdog(
+            &board,
+            ExternalWatchdogConfig {
+                pin: 13,
+                interval: Duration::from_millis(10),
+            },
+        );
+        let exec = Executor::new();
+        exec.block_on(async_io::Timer::after(Duration::from_millis(30)));
+        // the feeding task only holds a weak reference to the board
+        assert_eq!(Arc::strong_count(&board), 1);
+        assert_eq!(Arc::weak_count(&board), 1);
+        assert!(!task.is_finished());
+        drop(board);
+        exec.block_on(task);
+    }
 }
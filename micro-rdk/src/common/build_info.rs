@@ -0,0 +1,42 @@
+//! Version and build information of the firmware, embedded at compile time by the build script
+//!
+//! The build time is taken from `SOURCE_DATE_EPOCH` when set, for reproducible builds.
+
+use chrono::{DateTime, Utc};
+
+/// Version of the micro-rdk crate
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+/// Build time in seconds since the UNIX epoch
+pub const BUILD_TIMESTAMP: &str = env!("MICRO_RDK_BUILD_TIMESTAMP");
+/// Short hash of the git commit the firmware was built from, `unknown` outside of a checkout
+pub const GIT_REVISION: &str = env!("MICRO_RDK_GIT_REVISION");
+/// Target triple the firmware was built for
+pub const TARGET: &str = env!("MICRO_RDK_BUILD_TARGET");
+
+/// Build time of the firmware
+pub fn build_time() -> Option<DateTime<Utc>> {
+    DateTime::from_timestamp(BUILD_TIMESTAMP.parse().ok()?, 0)
+}
+
+/// Build information as `(key, value)` pairs: `version`, `built` (RFC 3339), `revision` and
+/// `target`
+pub fn build_info() -> Vec<(&'static str, String)> {
+    vec![
+        ("version", VERSION.to_owned()),
+        (
+            "built",
+            build_time().map(|t| t.to_rfc3339()).unwrap_or_default(),
+        ),
+        ("revision", GIT_REVISION.to_owned()),
+        ("target", TARGET.to_owned()),
+    ]
+}
+
+/// mDNS TXT records advertising the firmware version and revision, to be passed to
+/// [`ViamServerBuilder::with_mdns_txt_records`](crate::common::conn::viam::ViamServerBuilder::with_mdns_txt_records)
+pub fn mdns_txt_records() -> Vec<(String, String)> {
+    vec![
+        ("version".to_owned(), VERSION.to_owned()),
+        ("revision".to_owned(), GIT_REVISION.to_owned()),
+    ]
+}
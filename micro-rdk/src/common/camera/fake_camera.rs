@@ -1,20 +1,35 @@
 use crate::{
     common::{
-        camera::{Camera, CameraError, CameraType},
+        camera::{
+            encode_pcd, Camera, CameraError, CameraType, MimeType, MIME_TYPE_JPEG,
+            MIME_TYPE_VIAM_RGBA,
+        },
         status::{Status, StatusError},
     },
     google,
 };
-use bytes::Bytes;
+use bytes::{BufMut, Bytes, BytesMut};
 use std::{
     collections::HashMap,
     sync::{Arc, Mutex},
+    time::{Duration, Instant},
 };
 
 use crate::common::{config::ConfigType, registry::ComponentRegistry, registry::Dependency};
 
 static FAKE_JPEG: &[u8] = include_bytes!("./fake_image.jpg");
 
+const RGBA_HEADER: &[u8; 4] = b"RGBA";
+
+const DEFAULT_PATTERN_WIDTH: u32 = 320;
+const DEFAULT_PATTERN_HEIGHT: u32 = 240;
+const DEFAULT_PATTERN_FPS: f64 = 10.0;
+const MAX_PATTERN_DIMENSION: u32 = 1920;
+
+// the point cloud is a sphere of FAKE_POINT_CLOUD_RADIUS meters centered 1m in front of the camera
+const FAKE_POINT_CLOUD_POINTS: usize = 500;
+const FAKE_POINT_CLOUD_RADIUS: f32 = 0.25;
+
 pub(crate) fn register_models(registry: &mut ComponentRegistry) {
     if registry
         .register_camera("fake", &FakeCamera::from_config)
@@ -24,18 +39,199 @@ pub(crate) fn register_models(registry: &mut ComponentRegistry) {
     }
 }
 
+/// Test patterns the fake camera can generate
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TestPattern {
+    /// Vertical SMPTE-like color bars
+    ColorBars,
+    /// Horizontal gradient scrolling by a few pixels every frame
+    MovingGradient,
+    /// Index of the frame drawn in white on a black background
+    FrameCounter,
+}
+
+impl TryFrom<&str> for TestPattern {
+    type Error = CameraError;
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "color_bars" => Ok(Self::ColorBars),
+            "moving_gradient" => Ok(Self::MovingGradient),
+            "frame_counter" => Ok(Self::FrameCounter),
+            _ => Err(CameraError::ConfigError(
+                "pattern should be one of color_bars, moving_gradient or frame_counter",
+            )),
+        }
+    }
+}
+
+// Generates frames of a test pattern, the pattern advances at `frame_period` regardless of how
+// often frames are requested
+struct PatternGenerator {
+    pattern: TestPattern,
+    width: u32,
+    height: u32,
+    frame_period: Duration,
+    start: Instant,
+}
+
+impl PatternGenerator {
+    fn frame_index(&self) -> u64 {
+        (self.start.elapsed().as_nanos() / self.frame_period.as_nanos().max(1)) as u64
+    }
+
+    fn render(&self, frame: u64) -> Bytes {
+        let mut buf = BytesMut::with_capacity(
+            RGBA_HEADER.len() + 8 + (self.width * self.height * 4) as usize,
+        );
+        buf.put_slice(RGBA_HEADER);
+        buf.put_u32(self.width);
+        buf.put_u32(self.height);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let [r, g, b] = self.pixel(frame, x, y);
+                buf.put_slice(&[r, g, b, 0xFF]);
+            }
+        }
+        buf.freeze()
+    }
+
+    fn pixel(&self, frame: u64, x: u32, y: u32) -> [u8; 3] {
+        match self.pattern {
+            TestPattern::ColorBars => {
+                const BARS: [[u8; 3]; 8] = [
+                    [0xFF, 0xFF, 0xFF],
+                    [0xFF, 0xFF, 0x00],
+                    [0x00, 0xFF, 0xFF],
+                    [0x00, 0xFF, 0x00],
+                    [0xFF, 0x00, 0xFF],
+                    [0xFF, 0x00, 0x00],
+                    [0x00, 0x00, 0xFF],
+                    [0x00, 0x00, 0x00],
+                ];
+                BARS[(x * BARS.len() as u32 / self.width) as usize]
+            }
+            TestPattern::MovingGradient => {
+                let offset = (frame * 4 % self.width as u64) as u32;
+                let level = ((x + offset) % self.width * 0xFF / self.width) as u8;
+                [level, (y * 0xFF / self.height) as u8, 0xFF - level]
+            }
+            TestPattern::FrameCounter => {
+                if frame_counter_pixel(frame, x, y, self.width, self.height) {
+                    [0xFF, 0xFF, 0xFF]
+                } else {
+                    [0x00, 0x00, 0x00]
+                }
+            }
+        }
+    }
+}
+
+// 3x5 bitmaps of the digits, one row per 3 bits starting from the top
+const DIGITS: [[u8; 5]; 10] = [
+    [0b111, 0b101, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b111, 0b001, 0b111, 0b100, 0b111],
+    [0b111, 0b001, 0b111, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b111, 0b001, 0b111],
+    [0b111, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b010, 0b010, 0b010],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001, 0b111],
+];
+
+// whether (x, y) is lit when `frame` is drawn as 8 digits centered in a width x height image
+fn frame_counter_pixel(frame: u64, x: u32, y: u32, width: u32, height: u32) -> bool {
+    const COUNT: u32 = 8;
+    // each digit is 3 cells wide plus one cell of spacing
+    let cell = (width / (COUNT * 4)).min(height / 5).max(1);
+    let (left, top) = (
+        width.saturating_sub(COUNT * 4 * cell) / 2,
+        height.saturating_sub(5 * cell) / 2,
+    );
+    if x < left || y < top {
+        return false;
+    }
+    let (col, row) = ((x - left) / cell, (y - top) / cell);
+    if col >= COUNT * 4 || row >= 5 || col % 4 == 3 {
+        return false;
+    }
+    let digit = (frame / 10u64.pow(COUNT - 1 - col / 4) % 10) as usize;
+    DIGITS[digit][row as usize] & (0b100 >> (col % 4)) != 0
+}
+
+// points evenly spread on a sphere (Fibonacci lattice)
+fn fake_point_cloud() -> Vec<[f32; 3]> {
+    let golden_angle = std::f32::consts::PI * (3.0 - 5.0_f32.sqrt());
+    (0..FAKE_POINT_CLOUD_POINTS)
+        .map(|i| {
+            let y = 1.0 - 2.0 * (i as f32 + 0.5) / FAKE_POINT_CLOUD_POINTS as f32;
+            let r = (1.0 - y * y).sqrt();
+            let theta = golden_angle * i as f32;
+            [
+                FAKE_POINT_CLOUD_RADIUS * r * theta.cos(),
+                FAKE_POINT_CLOUD_RADIUS * y,
+                1.0 + FAKE_POINT_CLOUD_RADIUS * r * theta.sin(),
+            ]
+        })
+        .collect()
+}
+
+/// A camera returning either a fixed JPEG image or, when the `pattern` attribute is set, a test
+/// pattern (`color_bars`, `moving_gradient` or `frame_counter`) as uncompressed Viam RGBA images
+/// of `width` x `height` pixels advancing at `fps` frames per second. Its point cloud is a sphere
+/// 1m in front of the camera.
 #[derive(DoCommand)]
-pub struct FakeCamera {}
+pub struct FakeCamera {
+    pattern: Option<PatternGenerator>,
+}
 
 impl FakeCamera {
     pub fn new() -> Self {
-        FakeCamera {}
+        FakeCamera { pattern: None }
+    }
+    pub fn with_pattern(
+        pattern: TestPattern,
+        width: u32,
+        height: u32,
+        fps: f64,
+    ) -> Result<Self, CameraError> {
+        if !(1..=MAX_PATTERN_DIMENSION).contains(&width)
+            || !(1..=MAX_PATTERN_DIMENSION).contains(&height)
+        {
+            return Err(CameraError::ConfigError(
+                "width and height should be between 1 and 1920",
+            ));
+        }
+        if !(fps.is_finite() && fps > 0.0) {
+            return Err(CameraError::ConfigError("fps should be greater than 0"));
+        }
+        Ok(FakeCamera {
+            pattern: Some(PatternGenerator {
+                pattern,
+                width,
+                height,
+                frame_period: Duration::from_secs_f64(1.0 / fps),
+                start: Instant::now(),
+            }),
+        })
     }
     pub(crate) fn from_config(
-        _cfg: ConfigType,
+        cfg: ConfigType,
         _: Vec<Dependency>,
     ) -> Result<CameraType, CameraError> {
-        Ok(Arc::new(Mutex::new(FakeCamera::new())))
+        let Ok(pattern) = cfg.get_attribute::<String>("pattern") else {
+            return Ok(Arc::new(Mutex::new(FakeCamera::new())));
+        };
+        Ok(Arc::new(Mutex::new(FakeCamera::with_pattern(
+            pattern.as_str().try_into()?,
+            cfg.get_attribute::<u32>("width")
+                .unwrap_or(DEFAULT_PATTERN_WIDTH),
+            cfg.get_attribute::<u32>("height")
+                .unwrap_or(DEFAULT_PATTERN_HEIGHT),
+            cfg.get_attribute::<f64>("fps")
+                .unwrap_or(DEFAULT_PATTERN_FPS),
+        )?)))
     }
 }
 
@@ -46,8 +242,26 @@ impl Default for FakeCamera {
 }
 
 impl Camera for FakeCamera {
-    fn get_image(&mut self) -> Result<Bytes, CameraError> {
-        Ok(FAKE_JPEG.into())
+    fn get_image(&mut self, mime_type: Option<MimeType>) -> Result<Bytes, CameraError> {
+        // test patterns are only rendered as raw RGBA frames and the default image is a JPEG
+        match (self.pattern.as_ref(), mime_type) {
+            (Some(pattern), None | Some(MimeType::ViamRgba)) => {
+                Ok(pattern.render(pattern.frame_index()))
+            }
+            (None, None | Some(MimeType::Jpeg)) => Ok(FAKE_JPEG.into()),
+            _ => Err(CameraError::CameraGenericError(
+                "fake camera can't produce the requested image type",
+            )),
+        }
+    }
+    fn image_mime_type(&self) -> &'static str {
+        match self.pattern {
+            Some(_) => MIME_TYPE_VIAM_RGBA,
+            None => MIME_TYPE_JPEG,
+        }
+    }
+    fn get_point_cloud(&mut self) -> Result<Bytes, CameraError> {
+        Ok(encode_pcd(&fake_point_cloud()))
     }
 }
 
@@ -70,7 +284,8 @@ mod tests {
 
     use async_io::Timer;
 
-    use super::FAKE_JPEG;
+    use super::{FakeCamera, TestPattern, FAKE_JPEG, MIME_TYPE_VIAM_RGBA};
+    use crate::common::camera::{Camera, CameraError, MimeType};
     use crate::{
         common::{
             app_client::encode_request,
@@ -254,6 +469,82 @@ mod tests {
         Ok(())
     }
 
+    #[test_log::test]
+    fn test_fake_point_cloud() {
+        let pcd = FakeCamera::new().get_point_cloud().unwrap();
+        let header_end = pcd.windows(12).position(|w| w == b"DATA binary\n").unwrap() + 12;
+        let header = std::str::from_utf8(&pcd[..header_end]).unwrap();
+        assert!(header.starts_with("VERSION .7\nFIELDS x y z\n"));
+        assert!(header.contains("\nPOINTS 500\n"));
+        assert!(header.contains("\nWIDTH 500\nHEIGHT 1\n"));
+
+        let points = &pcd[header_end..];
+        assert_eq!(points.len(), 500 * 12);
+        for point in points.chunks(12) {
+            let coordinate =
+                |i: usize| f32::from_le_bytes(point[i * 4..i * 4 + 4].try_into().unwrap());
+            let distance =
+                (coordinate(0).powi(2) + coordinate(1).powi(2) + (coordinate(2) - 1.0).powi(2))
+                    .sqrt();
+            assert!((distance - 0.25).abs() < 1e-4);
+        }
+    }
+
+    #[test_log::test]
+    fn test_test_patterns() {
+        let pixel = |image: &bytes::Bytes, width: usize, x: usize, y: usize| {
+            let start = 12 + (y * width + x) * 4;
+            image[start..start + 4].to_vec()
+        };
+
+        let mut camera = FakeCamera::with_pattern(TestPattern::ColorBars, 64, 8, 10.0).unwrap();
+        assert_eq!(camera.image_mime_type(), MIME_TYPE_VIAM_RGBA);
+        let image = camera.get_image(None).unwrap();
+        assert_eq!(image.len(), 12 + 64 * 8 * 4);
+        assert_eq!(
+            camera.get_image(Some(MimeType::ViamRgba)).unwrap().len(),
+            image.len()
+        );
+        assert!(matches!(
+            camera.get_image(Some(MimeType::Jpeg)),
+            Err(CameraError::CameraGenericError(_))
+        ));
+        let mut jpeg_camera = FakeCamera::new();
+        assert_eq!(
+            jpeg_camera.get_image(Some(MimeType::Jpeg)).unwrap(),
+            FAKE_JPEG
+        );
+        assert!(matches!(
+            jpeg_camera.get_image(Some(MimeType::ViamRgba)),
+            Err(CameraError::CameraGenericError(_))
+        ));
+        assert_eq!(&image[..4], b"RGBA");
+        assert_eq!(u32::from_be_bytes(image[4..8].try_into().unwrap()), 64);
+        assert_eq!(u32::from_be_bytes(image[8..12].try_into().unwrap()), 8);
+        assert_eq!(pixel(&image, 64, 0, 0), [0xFF, 0xFF, 0xFF, 0xFF]);
+        assert_eq!(pixel(&image, 64, 63, 7), [0x00, 0x00, 0x00, 0xFF]);
+
+        let camera = FakeCamera::with_pattern(TestPattern::MovingGradient, 64, 8, 10.0).unwrap();
+        let generator = camera.pattern.as_ref().unwrap();
+        assert_ne!(generator.render(0), generator.render(1));
+
+        let camera = FakeCamera::with_pattern(TestPattern::FrameCounter, 64, 10, 10.0).unwrap();
+        let generator = camera.pattern.as_ref().unwrap();
+        // with 2x2 cells the last digit starts at x = 7 * 4 * 2, its top row is lit for 0 but
+        // only the middle cell for 1
+        let zero = generator.render(0);
+        let one = generator.render(1);
+        assert_eq!(pixel(&zero, 64, 56, 0), [0xFF, 0xFF, 0xFF, 0xFF]);
+        assert_eq!(pixel(&one, 64, 56, 0), [0x00, 0x00, 0x00, 0xFF]);
+        assert_eq!(pixel(&one, 64, 58, 0), [0xFF, 0xFF, 0xFF, 0xFF]);
+        // digits before the last one are the same
+        assert_eq!(pixel(&zero, 64, 48, 0), pixel(&one, 64, 48, 0));
+
+        assert!(FakeCamera::with_pattern(TestPattern::ColorBars, 0, 8, 10.0).is_err());
+        assert!(FakeCamera::with_pattern(TestPattern::ColorBars, 8, 8, 0.0).is_err());
+        assert!(TestPattern::try_from("checkerboard").is_err());
+    }
+
     #[test_log::test]
     fn test_fake_camera() {
         let exec = Executor::default();
@@ -1,6 +1,12 @@
-use super::{generic::DoCommand, registry::ComponentRegistry, status::Status};
-use bytes::Bytes;
+use super::{
+    config::{AttributeError, ConfigType},
+    generic::DoCommand,
+    registry::ComponentRegistry,
+    status::Status,
+};
+use bytes::{BufMut, Bytes, BytesMut};
 use prost::EncodeError;
+use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use thiserror::Error;
 
@@ -39,15 +45,251 @@ pub enum CameraError {
     MessageEncodeError(#[from] EncodeError),
 }
 
+// Allocations kept by a `FrameBufferPool`, when all of them are still used by frames the oldest
+// one is let go (it is freed once its frames are dropped)
+const MAX_POOLED_FRAME_BUFFERS: usize = 2;
+
+/// Reusable buffers for camera frames. Frames are copied one after the other into the same
+/// allocation and handed out as `Bytes` sharing it; once all the frames copied into an
+/// allocation have been sent and dropped it is reclaimed from its start for the next ones rather
+/// than allocating a new buffer per frame, which avoids fragmenting the heap. A new allocation,
+/// only as large as the frame, is made when no kept allocation has room for it. The number of
+/// allocations made is reported by `allocations`, to compare with the number of frames copied.
+pub struct FrameBufferPool {
+    // the buffers with the size of their allocation
+    buffers: Vec<(BytesMut, usize)>,
+    capacity: usize,
+    allocations: usize,
+    frames: usize,
+}
+
+impl FrameBufferPool {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            buffers: vec![(BytesMut::with_capacity(capacity), capacity)],
+            capacity,
+            allocations: 1,
+            frames: 0,
+        }
+    }
+
+    // buffer with room for `len` more bytes, without allocating when possible. A whole
+    // allocation no frame uses anymore is reclaimed first, so it is filled again from its start
+    fn buffer_for(&mut self, len: usize) -> &mut BytesMut {
+        let reusable = self.buffers.iter_mut().position(|(buffer, allocated)| {
+            buffer.try_reclaim(*allocated) || buffer.capacity() >= len
+        });
+        let idx = match reusable {
+            Some(idx) => idx,
+            None => {
+                if self.buffers.len() == MAX_POOLED_FRAME_BUFFERS {
+                    let _ = self.buffers.remove(0);
+                }
+                let buffer = BytesMut::with_capacity(len);
+                let allocated = buffer.capacity();
+                self.buffers.push((buffer, allocated));
+                self.allocations += 1;
+                self.buffers.len() - 1
+            }
+        };
+        self.frames += 1;
+        &mut self.buffers[idx].0
+    }
+
+    /// Largest frame the pool accepts
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Number of buffers allocated since the pool was created
+    pub fn allocations(&self) -> usize {
+        self.allocations
+    }
+
+    /// Number of frames copied since the pool was created
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+
+    pub fn copy_frame(&mut self, frame: &[u8]) -> Result<Bytes, CameraError> {
+        if frame.len() > self.capacity {
+            return Err(CameraError::ImageTooBig(frame.len(), self.capacity));
+        }
+        let buffer = self.buffer_for(frame.len());
+        buffer.extend_from_slice(frame);
+        Ok(buffer.split().freeze())
+    }
+}
+
+/// Whether the `rotation` attribute of a camera asks for its frames to be turned by 180 degrees,
+/// which sensors do by flipping both axes. Only 0 and 180 are accepted: turning frames by 90 or
+/// 270 degrees means decoding and encoding them again, which is too slow and memory hungry for a
+/// microcontroller.
+pub fn rotation_from_config(cfg: &ConfigType) -> Result<bool, CameraError> {
+    match cfg.get_attribute::<u32>("rotation") {
+        Ok(0) | Err(AttributeError::KeyNotFound(_)) => Ok(false),
+        Ok(180) => Ok(true),
+        Ok(90 | 270) => Err(CameraError::ConfigError(
+            "frames can't be rotated by 90 or 270 degrees, only 0 and 180 are supported",
+        )),
+        _ => Err(CameraError::ConfigError("rotation should be 0 or 180")),
+    }
+}
+
+pub const MIME_TYPE_JPEG: &str = "image/jpeg";
+/// MIME type of uncompressed Viam RGBA images: the "RGBA" magic, the width and height as big
+/// endian u32 and then 4 bytes per pixel, row by row
+pub const MIME_TYPE_VIAM_RGBA: &str = "image/vnd.viam.rgba";
+
+/// Image formats that can be requested from [`Camera::get_image`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MimeType {
+    Jpeg,
+    /// Raw RGB frames, see [`MIME_TYPE_VIAM_RGBA`]
+    ViamRgba,
+}
+
+impl MimeType {
+    /// The format asked for by a client, ignoring the `+lazy` suffix clients may add. Formats
+    /// that are empty or not supported are `None`, leaving the choice to the camera
+    pub fn from_request(mime_type: &str) -> Option<Self> {
+        match mime_type.trim_end_matches("+lazy") {
+            MIME_TYPE_JPEG => Some(Self::Jpeg),
+            MIME_TYPE_VIAM_RGBA => Some(Self::ViamRgba),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Jpeg => MIME_TYPE_JPEG,
+            Self::ViamRgba => MIME_TYPE_VIAM_RGBA,
+        }
+    }
+}
+
+/// MIME type of the point clouds returned by [`Camera::get_point_cloud`]
+pub const MIME_TYPE_PCD: &str = "pointcloud/pcd";
+
+/// Encode points, as x, y, z coordinates in meters, to a binary PCD (v0.7) point cloud
+pub fn encode_pcd(points: &[[f32; 3]]) -> Bytes {
+    let header = format!(
+        "VERSION .7\nFIELDS x y z\nSIZE 4 4 4\nTYPE F F F\nCOUNT 1 1 1\nWIDTH {0}\nHEIGHT 1\n\
+         VIEWPOINT 0 0 0 1 0 0 0\nPOINTS {0}\nDATA binary\n",
+        points.len()
+    );
+    let mut buf = BytesMut::with_capacity(header.len() + points.len() * 12);
+    buf.put_slice(header.as_bytes());
+    for point in points {
+        for coordinate in point {
+            buf.put_f32_le(*coordinate);
+        }
+    }
+    buf.freeze()
+}
+
+/// Intrinsics of a depth camera, used to project its depth frames to point clouds with
+/// [`encode_depth_pcd`]. Configured with the `depth` attribute, for example
+/// `{"fx": 70.0, "fy": 70.0, "ppx": 40.0, "ppy": 30.0, "depth_scale": 0.001}`
+#[derive(Clone, Debug, PartialEq)]
+pub struct DepthIntrinsics {
+    /// Focal lengths in pixels
+    pub fx: f32,
+    pub fy: f32,
+    /// Principal point in pixels, the center of the frame when not set
+    pub ppx: Option<f32>,
+    pub ppy: Option<f32>,
+    /// Meters per unit of depth, 0.001 (millimeters) when not set
+    pub depth_scale: f32,
+}
+
+impl DepthIntrinsics {
+    pub fn from_config(cfg: &ConfigType) -> Result<Option<Self>, CameraError> {
+        let depth = match cfg.get_attribute::<HashMap<&str, f64>>("depth") {
+            Ok(depth) => depth,
+            Err(AttributeError::KeyNotFound(_)) => return Ok(None),
+            Err(_) => {
+                return Err(CameraError::ConfigError(
+                    "depth should map fx, fy, ppx, ppy and depth_scale to numbers",
+                ))
+            }
+        };
+        let focal_length = |key: &str| match depth.get(key) {
+            Some(f) if *f > 0.0 => Ok(*f as f32),
+            _ => Err(CameraError::ConfigError(
+                "depth requires positive fx and fy focal lengths, in pixels",
+            )),
+        };
+        let depth_scale = match depth.get("depth_scale") {
+            None => 0.001,
+            Some(scale) if *scale > 0.0 => *scale as f32,
+            Some(_) => {
+                return Err(CameraError::ConfigError(
+                    "depth_scale should be the positive number of meters per unit of depth",
+                ))
+            }
+        };
+        Ok(Some(Self {
+            fx: focal_length("fx")?,
+            fy: focal_length("fy")?,
+            ppx: depth.get("ppx").map(|ppx| *ppx as f32),
+            ppy: depth.get("ppy").map(|ppy| *ppy as f32),
+            depth_scale,
+        }))
+    }
+}
+
+/// Project a depth frame to a binary PCD point cloud, see [`encode_pcd`]. The frame holds
+/// `width` x `height` little endian u16 depths, row by row starting from the top left pixel, in
+/// units of `depth_scale` meters. Pixels without a measurement have a depth of 0 and are skipped
+pub fn encode_depth_pcd(
+    intrinsics: &DepthIntrinsics,
+    width: usize,
+    height: usize,
+    frame: &[u8],
+) -> Result<Bytes, CameraError> {
+    if frame.len() != width * height * 2 {
+        return Err(CameraError::ConfigError(
+            "depth frames should hold width x height little endian u16 depths, row major",
+        ));
+    }
+    let ppx = intrinsics.ppx.unwrap_or(width as f32 / 2.0);
+    let ppy = intrinsics.ppy.unwrap_or(height as f32 / 2.0);
+    let points: Vec<[f32; 3]> = frame
+        .chunks_exact(2)
+        .enumerate()
+        .filter_map(|(idx, depth)| {
+            let depth = u16::from_le_bytes([depth[0], depth[1]]);
+            if depth == 0 {
+                return None;
+            }
+            let z = depth as f32 * intrinsics.depth_scale;
+            let (u, v) = ((idx % width) as f32, (idx / width) as f32);
+            Some([
+                (u - ppx) * z / intrinsics.fx,
+                (v - ppy) * z / intrinsics.fy,
+                z,
+            ])
+        })
+        .collect();
+    Ok(encode_pcd(&points))
+}
+
 pub trait Camera: Status + DoCommand {
-    /// Returns a structured image response from a camera of the underlying robot.
-    /// A specific MIME type can be requested but may not necessarily be the same one returned
-    fn get_image(&mut self) -> Result<Bytes, CameraError> {
+    /// Returns an image of the requested `mime_type`, or of `image_mime_type` when none is
+    /// requested. Fails with [`CameraError::CameraGenericError`] when the camera can't produce
+    /// the requested type
+    fn get_image(&mut self, _mime_type: Option<MimeType>) -> Result<Bytes, CameraError> {
         Err(CameraError::CameraMethodUnimplemented("get_image"))
     }
+    /// MIME type of the images returned by `get_image` when no type is requested
+    fn image_mime_type(&self) -> &'static str {
+        MIME_TYPE_JPEG
+    }
     fn get_images(&mut self) -> Result<Bytes, CameraError> {
         Err(CameraError::CameraMethodUnimplemented("get_images"))
     }
+    /// Returns a point cloud in the binary PCD format, see [`encode_pcd`]
     fn get_point_cloud(&mut self) -> Result<Bytes, CameraError> {
         Err(CameraError::CameraMethodUnimplemented("get_point_cloud"))
     }
@@ -61,8 +303,11 @@ impl<L> Camera for Mutex<L>
 where
     L: ?Sized + Camera,
 {
-    fn get_image(&mut self) -> Result<Bytes, CameraError> {
-        self.get_mut().unwrap().get_image()
+    fn get_image(&mut self, mime_type: Option<MimeType>) -> Result<Bytes, CameraError> {
+        self.get_mut().unwrap().get_image(mime_type)
+    }
+    fn image_mime_type(&self) -> &'static str {
+        self.lock().unwrap().image_mime_type()
     }
     fn get_images(&mut self) -> Result<Bytes, CameraError> {
         self.get_mut().unwrap().get_images()
@@ -79,8 +324,11 @@ impl<L> Camera for Arc<Mutex<L>>
 where
     L: ?Sized + Camera,
 {
-    fn get_image(&mut self) -> Result<Bytes, CameraError> {
-        self.lock().unwrap().get_image()
+    fn get_image(&mut self, mime_type: Option<MimeType>) -> Result<Bytes, CameraError> {
+        self.lock().unwrap().get_image(mime_type)
+    }
+    fn image_mime_type(&self) -> &'static str {
+        self.lock().unwrap().image_mime_type()
     }
     fn get_images(&mut self) -> Result<Bytes, CameraError> {
         self.lock().unwrap().get_images()
@@ -92,3 +340,198 @@ where
         self.lock().unwrap().get_properties()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::test_utils::delegation_test;
+
+    #[test_log::test]
+    fn test_frame_buffer_pool() {
+        let mut pool = FrameBufferPool::new(16);
+        let first = pool.copy_frame(b"abcd").unwrap();
+        let start = first.as_ptr();
+        // frames share the allocation while it has room left
+        let second = pool.copy_frame(b"efgh").unwrap();
+        assert_eq!(second.as_ptr(), start.wrapping_add(4));
+        assert_eq!(&first[..], b"abcd");
+        assert_eq!(&second[..], b"efgh");
+
+        // reclaimed once all its frames are dropped
+        drop(first);
+        drop(second);
+        let third = pool.copy_frame(b"ijklmnop").unwrap();
+        assert_eq!(third.as_ptr(), start);
+        let fourth = pool.copy_frame(b"qrstuvwx").unwrap();
+        assert_eq!(fourth.as_ptr(), start.wrapping_add(8));
+
+        // no room while those are alive, a frame sized allocation is made and kept
+        assert_eq!(pool.allocations(), 1);
+        let fifth = pool.copy_frame(b"yz").unwrap();
+        assert_ne!(fifth.as_ptr(), start);
+        assert_eq!(&fifth[..], b"yz");
+        assert_eq!(pool.buffers.len(), 2);
+        assert_eq!(pool.buffers[1].0.capacity(), 0);
+        let fifth_start = fifth.as_ptr();
+        drop(fifth);
+        let sixth = pool.copy_frame(b"01").unwrap();
+        assert_eq!(sixth.as_ptr(), fifth_start);
+        assert_eq!(pool.buffers.len(), 2);
+        drop((third, fourth, sixth));
+        assert_eq!(pool.copy_frame(b"23").unwrap().as_ptr(), start);
+        // 7 frames for 2 allocations
+        assert_eq!(pool.frames(), 7);
+        assert_eq!(pool.allocations(), 2);
+
+        assert!(matches!(
+            pool.copy_frame(&[0; 17]),
+            Err(CameraError::ImageTooBig(17, 16))
+        ));
+    }
+
+    #[test_log::test]
+    fn test_rotation_from_config() {
+        use crate::common::config::Kind;
+        use crate::common::test_utils::component_config;
+        let rotation = |attributes: Vec<(&str, Kind)>| {
+            rotation_from_config(&ConfigType::Dynamic(&component_config(attributes)))
+        };
+        assert!(!rotation(vec![]).unwrap());
+        assert!(!rotation(vec![("rotation", Kind::NumberValue(0.0))]).unwrap());
+        assert!(rotation(vec![("rotation", Kind::NumberValue(180.0))]).unwrap());
+        for invalid in [90.0, 270.0, 45.0] {
+            assert!(matches!(
+                rotation(vec![("rotation", Kind::NumberValue(invalid))]),
+                Err(CameraError::ConfigError(_))
+            ));
+        }
+        assert!(rotation(vec![("rotation", Kind::StringValue("180".to_owned()))]).is_err());
+    }
+
+    #[test_log::test]
+    fn test_mime_type_request() {
+        assert_eq!(MimeType::from_request("image/jpeg"), Some(MimeType::Jpeg));
+        assert_eq!(
+            MimeType::from_request("image/jpeg+lazy"),
+            Some(MimeType::Jpeg)
+        );
+        assert_eq!(
+            MimeType::from_request(MIME_TYPE_VIAM_RGBA),
+            Some(MimeType::ViamRgba)
+        );
+        assert_eq!(MimeType::from_request(""), None);
+        assert_eq!(MimeType::from_request("image/png"), None);
+        assert_eq!(MimeType::ViamRgba.as_str(), MIME_TYPE_VIAM_RGBA);
+    }
+
+    #[test_log::test]
+    fn test_depth_pcd() {
+        use crate::common::config::Kind;
+        use crate::common::test_utils::component_config;
+
+        let depth = |attributes: Vec<(&str, f64)>| {
+            let cfg = component_config([(
+                "depth",
+                Kind::StructValue(
+                    attributes
+                        .into_iter()
+                        .map(|(k, v)| (k.to_owned(), Kind::NumberValue(v)))
+                        .collect(),
+                ),
+            )]);
+            DepthIntrinsics::from_config(&ConfigType::Dynamic(&cfg))
+        };
+        let intrinsics = depth(vec![("fx", 2.0), ("fy", 4.0)]).unwrap().unwrap();
+        assert_eq!(
+            intrinsics,
+            DepthIntrinsics {
+                fx: 2.0,
+                fy: 4.0,
+                ppx: None,
+                ppy: None,
+                depth_scale: 0.001,
+            }
+        );
+        assert!(
+            DepthIntrinsics::from_config(&ConfigType::Dynamic(&component_config([])))
+                .unwrap()
+                .is_none()
+        );
+        assert!(matches!(
+            depth(vec![("fx", 2.0)]),
+            Err(CameraError::ConfigError(_))
+        ));
+        assert!(matches!(
+            depth(vec![("fx", 2.0), ("fy", 2.0), ("depth_scale", 0.0)]),
+            Err(CameraError::ConfigError(_))
+        ));
+        for malformed in [
+            Kind::BoolValue(true),
+            Kind::StructValue(HashMap::from([
+                ("fx".to_owned(), Kind::NumberValue(2.0)),
+                ("fy".to_owned(), Kind::BoolValue(true)),
+            ])),
+        ] {
+            let cfg = component_config([("depth", malformed)]);
+            assert!(matches!(
+                DepthIntrinsics::from_config(&ConfigType::Dynamic(&cfg)),
+                Err(CameraError::ConfigError(_))
+            ));
+        }
+
+        // 2x2 frame whose bottom left pixel has no measurement
+        let frame: Vec<u8> = [1000_u16, 2000, 0, 4000]
+            .iter()
+            .flat_map(|depth| depth.to_le_bytes())
+            .collect();
+        let pcd = encode_depth_pcd(&intrinsics, 2, 2, &frame).unwrap();
+        let header_len = pcd.len() - 3 * 12;
+        assert!(std::str::from_utf8(&pcd[..header_len])
+            .unwrap()
+            .contains("POINTS 3\n"));
+        let points: Vec<f32> = pcd[header_len..]
+            .chunks_exact(4)
+            .map(|f| f32::from_le_bytes(f.try_into().unwrap()))
+            .collect();
+        // the principal point is the center of the frame, (1, 1)
+        assert_eq!(
+            points,
+            vec![-0.5, -0.25, 1.0, 0.0, -0.5, 2.0, 0.0, 0.0, 4.0]
+        );
+
+        assert!(matches!(
+            encode_depth_pcd(&intrinsics, 3, 2, &frame),
+            Err(CameraError::ConfigError(_))
+        ));
+    }
+
+    delegation_test!(
+        test_camera_delegation,
+        RecordingCamera as dyn Camera,
+        wrappers: [Mutex::new, |camera| Arc::new(Mutex::new(camera))],
+        impl Camera {
+            fn get_image(&mut self, _: Option<MimeType>) -> Result<Bytes, CameraError> {
+                Ok(Bytes::new())
+            }
+            fn image_mime_type(&self) -> &'static str {
+                "image/png"
+            }
+            fn get_images(&mut self) -> Result<Bytes, CameraError> {
+                Ok(Bytes::new())
+            }
+            fn get_point_cloud(&mut self) -> Result<Bytes, CameraError> {
+                Ok(Bytes::new())
+            }
+            fn get_properties(&mut self) -> Result<Bytes, CameraError> {
+                Ok(Bytes::new())
+            }
+        }
+        calls: |camera| {
+            camera.get_image(None).unwrap();
+            assert_eq!(camera.image_mime_type(), "image/png");
+            camera.get_images().unwrap();
+            camera.get_point_cloud().unwrap();
+            camera.get_properties().unwrap();
+        }
+    );
+}
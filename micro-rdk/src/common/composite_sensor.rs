@@ -0,0 +1,192 @@
+//! A sensor merging the readings of several sensors into a single map, so that physically
+//! separate sensors (temperature, humidity, pressure...) can be presented as one.
+//!
+//! Sensors are listed in the `sensors` attribute, each with the `name` of the sensor and an
+//! optional `prefix` (defaulting to the name), a reading `key` of that sensor being reported as
+//! `<prefix>_<key>`. If a sensor fails, the readings of the other sensors are still returned
+//! along with a `<prefix>_error` string describing the failure.
+//!
+//! ```json
+//! "attributes": {
+//!     "sensors": [
+//!         { "name": "bme", "prefix": "outside" },
+//!         { "name": "ina" }
+//!     ]
+//! }
+//! ```
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use super::config::ConfigType;
+use super::registry::{ComponentRegistry, Dependency, ResourceKey};
+use super::robot::Resource;
+use super::sensor::{
+    GenericReadingsResult, Readings, Sensor, SensorError, SensorType,
+    COMPONENT_NAME as SensorCompName,
+};
+use super::status::{Status, StatusError};
+use crate::google;
+use crate::google::protobuf::{value::Kind, Value};
+
+pub(crate) fn register_models(registry: &mut ComponentRegistry) {
+    if registry
+        .register_sensor("composite_sensor", &CompositeSensor::from_config)
+        .is_err()
+    {
+        log::error!("composite_sensor model is already registered")
+    }
+    if registry
+        .register_dependency_getter(
+            SensorCompName,
+            "composite_sensor",
+            &CompositeSensor::dependencies_from_config,
+        )
+        .is_err()
+    {
+        log::error!("failed to register dependency getter for composite_sensor model")
+    }
+}
+
+#[derive(DoCommand)]
+pub struct CompositeSensor {
+    sensors: Vec<(String, SensorType)>,
+}
+
+impl CompositeSensor {
+    /// Merge the readings of `sensors`, each paired with the prefix of its keys
+    pub fn new(sensors: Vec<(String, SensorType)>) -> Self {
+        Self { sensors }
+    }
+
+    // (name, prefix) of each configured sensor
+    fn sensors_from_config(cfg: &ConfigType) -> Result<Vec<(String, String)>, SensorError> {
+        cfg.get_attribute::<Vec<HashMap<&str, &str>>>("sensors")
+            .map_err(|_| SensorError::ConfigError("composite_sensor requires a list of `sensors`"))?
+            .iter()
+            .map(|sensor| {
+                let name = sensor.get("name").ok_or(SensorError::ConfigError(
+                    "composite_sensor entries need a `name`",
+                ))?;
+                let prefix = sensor.get("prefix").unwrap_or(name);
+                Ok((name.to_string(), prefix.to_string()))
+            })
+            .collect()
+    }
+
+    pub(crate) fn from_config(
+        cfg: ConfigType,
+        deps: Vec<Dependency>,
+    ) -> Result<SensorType, SensorError> {
+        let mut sensors = Vec::new();
+        for (name, prefix) in Self::sensors_from_config(&cfg)? {
+            let sensor = deps
+                .iter()
+                .find_map(|Dependency(key, res)| match res {
+                    Resource::Sensor(sensor) if key.1 == name => Some(sensor.clone()),
+                    _ => None,
+                })
+                .ok_or(SensorError::ConfigError(
+                    "composite_sensor sensor couldn't be found",
+                ))?;
+            sensors.push((prefix, sensor));
+        }
+        Ok(Arc::new(Mutex::new(CompositeSensor::new(sensors))))
+    }
+
+    pub(crate) fn dependencies_from_config(cfg: ConfigType) -> Vec<ResourceKey> {
+        Self::sensors_from_config(&cfg)
+            .map(|sensors| {
+                sensors
+                    .into_iter()
+                    .map(|(name, _)| ResourceKey::new(SensorCompName, name))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+impl Sensor for CompositeSensor {}
+
+impl Readings for CompositeSensor {
+    fn get_generic_readings(&mut self) -> Result<GenericReadingsResult, SensorError> {
+        let mut readings = HashMap::new();
+        for (prefix, sensor) in self.sensors.iter_mut() {
+            match sensor.get_generic_readings() {
+                Ok(child) => readings.extend(
+                    child
+                        .into_iter()
+                        .map(|(key, value)| (format!("{}_{}", prefix, key), value)),
+                ),
+                Err(err) => {
+                    log::warn!("composite_sensor failed to read {}: {}", prefix, err);
+                    readings.insert(
+                        format!("{}_error", prefix),
+                        Value {
+                            kind: Some(Kind::StringValue(err.to_string())),
+                        },
+                    );
+                }
+            }
+        }
+        Ok(readings)
+    }
+}
+
+impl Status for CompositeSensor {
+    fn get_status(&self) -> Result<Option<google::protobuf::Struct>, StatusError> {
+        Ok(Some(google::protobuf::Struct {
+            fields: HashMap::new(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::CompositeSensor;
+    use crate::common::sensor::{
+        FakeSensor, GenericReadingsResult, Readings, Sensor, SensorError, SensorType,
+    };
+    use crate::common::status::{Status, StatusError};
+    use crate::google;
+    use crate::google::protobuf::value::Kind;
+
+    #[derive(DoCommand)]
+    struct FailingSensor;
+    impl Sensor for FailingSensor {}
+    impl Readings for FailingSensor {
+        fn get_generic_readings(&mut self) -> Result<GenericReadingsResult, SensorError> {
+            Err(SensorError::SensorGenericError("unplugged"))
+        }
+    }
+    impl Status for FailingSensor {
+        fn get_status(&self) -> Result<Option<google::protobuf::Struct>, StatusError> {
+            Ok(None)
+        }
+    }
+
+    #[test_log::test]
+    fn test_composite_sensor_readings() {
+        let sensors: Vec<(String, SensorType)> = vec![
+            (
+                "outside".to_owned(),
+                Arc::new(Mutex::new(FakeSensor::new())),
+            ),
+            ("inside".to_owned(), Arc::new(Mutex::new(FailingSensor))),
+        ];
+        let readings = CompositeSensor::new(sensors)
+            .get_generic_readings()
+            .unwrap();
+        assert_eq!(readings.len(), 2);
+        assert_eq!(
+            readings["outside_fake_sensor"].kind,
+            Some(Kind::NumberValue(42.42))
+        );
+        assert_eq!(
+            readings["inside_error"].kind,
+            Some(Kind::StringValue("unplugged".to_owned()))
+        );
+    }
+}
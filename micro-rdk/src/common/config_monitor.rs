@@ -8,14 +8,26 @@ use crate::{
 };
 use async_io::Timer;
 use futures_lite::{Future, FutureExt};
+use std::cell::Cell;
 use std::fmt::Debug;
 use std::pin::Pin;
 use std::time::Duration;
 
+/// How often the robot config is polled unless changed with [`ConfigMonitor::with_period`] or
+/// the agent config of the robot
+pub const DEFAULT_CONFIG_POLL_INTERVAL: Duration = Duration::from_secs(10);
+/// Shortest poll interval accepted, from the agent config or [`ConfigMonitor::with_period`]
+pub const MIN_CONFIG_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Polls app for the robot config and restarts when it changed. The first poll also looks for a
+/// `check_interval` in the agent config of the robot which, when set, replaces the configured
+/// period.
 pub struct ConfigMonitor<'a, Storage> {
     curr_config: Box<RobotConfig>, //config for robot gotten from last robot startup, aka inputted from entry
     storage: Storage,
     restart_hook: Box<dyn Fn() + 'a>,
+    period: Cell<Duration>,
+    agent_config_checked: Cell<bool>,
 }
 
 impl<'a, Storage> ConfigMonitor<'a, Storage>
@@ -33,9 +45,17 @@ where
             curr_config,
             storage,
             restart_hook: Box::new(restart_hook),
+            period: Cell::new(DEFAULT_CONFIG_POLL_INTERVAL),
+            agent_config_checked: Cell::new(false),
         }
     }
 
+    /// Poll every `period`, raised to [`MIN_CONFIG_POLL_INTERVAL`] if shorter
+    pub fn with_period(self, period: Duration) -> Self {
+        self.period.set(period.max(MIN_CONFIG_POLL_INTERVAL));
+        self
+    }
+
     fn restart(&self) -> ! {
         log::warn!("Robot configuration change detected restarting micro-rdk");
         (self.restart_hook)();
@@ -53,7 +73,7 @@ where
     }
 
     fn get_default_period(&self) -> Duration {
-        Duration::from_secs(10)
+        self.period.get()
     }
 
     // TODO(RSDK-8160): Update "restart on config" to compare config version instead of deep
@@ -85,7 +105,54 @@ where
                 }
             }
 
+            if !self.agent_config_checked.replace(true) {
+                match app_client
+                    .get_agent_config_check_interval()
+                    .or(async {
+                        let _ = Timer::after(Duration::from_secs(60)).await;
+                        Err(AppClientError::AppClientRequestTimeout)
+                    })
+                    .await
+                {
+                    Ok(Some(interval)) => {
+                        let interval = interval.max(MIN_CONFIG_POLL_INTERVAL);
+                        log::info!(
+                            "agent config sets the config poll interval to {:?}",
+                            interval
+                        );
+                        self.period.set(interval);
+                    }
+                    Ok(None) => {}
+                    Err(e) => log::debug!("couldn't get the agent config reason {:?}", e),
+                }
+            }
+
             Ok(Some(self.get_default_period()))
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{ConfigMonitor, DEFAULT_CONFIG_POLL_INTERVAL, MIN_CONFIG_POLL_INTERVAL};
+    use crate::common::app_client::PeriodicAppClientTask;
+    use crate::common::credentials_storage::RAMStorage;
+    use std::time::Duration;
+
+    #[test_log::test]
+    fn test_config_monitor_period() {
+        let monitor = || ConfigMonitor::new(Box::default(), RAMStorage::new(), || {});
+        assert_eq!(monitor().get_default_period(), DEFAULT_CONFIG_POLL_INTERVAL);
+        assert_eq!(
+            monitor()
+                .with_period(Duration::from_secs(60))
+                .get_default_period(),
+            Duration::from_secs(60)
+        );
+        // a null period would poll app in a busy loop
+        assert_eq!(
+            monitor().with_period(Duration::ZERO).get_default_period(),
+            MIN_CONFIG_POLL_INTERVAL
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use crate::{google::protobuf::value::Kind, proto::app::v1::RobotConfig};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -8,6 +9,99 @@ pub enum MdnsError {
     MdnsRemoveServiceError(String),
     #[error("couldn't init mdns")]
     MdnsInitServiceError(String),
+    #[error("invalid mdns txt record {0}: {1}")]
+    MdnsInvalidTxtRecord(String, &'static str),
+}
+
+/// TXT entries advertised with every robot service
+pub const DEFAULT_TXT_RECORDS: [(&str, &str); 2] = [("grpc", ""), ("webrtc", "")];
+/// Largest size in bytes of a single `key=value` TXT string
+pub const MAX_TXT_RECORD_LEN: usize = 255;
+/// Largest size in bytes of all the TXT strings of a service, so that it fits in a single packet
+/// (RFC 6763 section 6.2)
+pub const MAX_TXT_RECORDS_LEN: usize = 1300;
+
+/// Check that `records`, advertised in addition to [`DEFAULT_TXT_RECORDS`], respect the mDNS
+/// limits: keys are non empty printable ASCII without `=` and unique, each `key=value` string
+/// fits in [`MAX_TXT_RECORD_LEN`] and all of them in [`MAX_TXT_RECORDS_LEN`]
+pub fn validate_txt_records(records: &[(String, String)]) -> Result<(), MdnsError> {
+    let record_len = |key: &str, value: &str| key.len() + 1 + value.len();
+    // each string is prefixed by its length
+    let mut total: usize = DEFAULT_TXT_RECORDS
+        .iter()
+        .map(|(k, v)| 1 + record_len(k, v))
+        .sum();
+    for (i, (key, value)) in records.iter().enumerate() {
+        let invalid = |reason| Err(MdnsError::MdnsInvalidTxtRecord(key.clone(), reason));
+        if key.is_empty() || !key.bytes().all(|b| (0x20..=0x7e).contains(&b) && b != b'=') {
+            return invalid("keys should be printable ASCII without '='");
+        }
+        if DEFAULT_TXT_RECORDS
+            .iter()
+            .map(|(k, _)| *k)
+            .chain(records[..i].iter().map(|(k, _)| k.as_str()))
+            .any(|k| k.eq_ignore_ascii_case(key))
+        {
+            return invalid("duplicated key");
+        }
+        if record_len(key, value) > MAX_TXT_RECORD_LEN {
+            return invalid("longer than 255 bytes");
+        }
+        total += 1 + record_len(key, value);
+    }
+    if total > MAX_TXT_RECORDS_LEN {
+        return Err(MdnsError::MdnsInvalidTxtRecord(
+            "*".to_owned(),
+            "records are longer than 1300 bytes",
+        ));
+    }
+    Ok(())
+}
+
+/// Model of the robot config service listing the extra TXT entries advertised by the robot in
+/// its `txt_records` attribute, e.g. `{"name": "mdns", "api": "rdk:service:generic", "model":
+/// "rdk:builtin:mdns", "attributes": {"txt_records": {"site": "lab-3"}}}`
+pub const MDNS_SERVICE_MODEL: &str = "rdk:builtin:mdns";
+
+/// Extra TXT entries set in `config` through the [`MDNS_SERVICE_MODEL`] service, sorted by key
+/// and validated with [`validate_txt_records`]. Returns None when the config has no such service.
+pub fn txt_records_from_config(
+    config: &RobotConfig,
+) -> Result<Option<Vec<(String, String)>>, MdnsError> {
+    let Some(service) = config
+        .services
+        .iter()
+        .find(|service| service.model == MDNS_SERVICE_MODEL)
+    else {
+        return Ok(None);
+    };
+    let Some(records) = service
+        .attributes
+        .as_ref()
+        .and_then(|attributes| attributes.fields.get("txt_records"))
+    else {
+        return Ok(Some(vec![]));
+    };
+    let Some(Kind::StructValue(records)) = records.kind.as_ref() else {
+        return Err(MdnsError::MdnsInvalidTxtRecord(
+            "txt_records".to_owned(),
+            "should map keys to string values",
+        ));
+    };
+    let mut records = records
+        .fields
+        .iter()
+        .map(|(key, value)| match value.kind.as_ref() {
+            Some(Kind::StringValue(value)) => Ok((key.clone(), value.clone())),
+            _ => Err(MdnsError::MdnsInvalidTxtRecord(
+                key.clone(),
+                "values should be strings",
+            )),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    records.sort_unstable();
+    validate_txt_records(&records)?;
+    Ok(Some(records))
 }
 
 pub struct NoMdns;
@@ -56,3 +150,93 @@ pub trait Mdns {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{txt_records_from_config, validate_txt_records, MDNS_SERVICE_MODEL};
+    use crate::google::protobuf::{value::Kind, Struct, Value};
+    use crate::proto::app::v1::{RobotConfig, ServiceConfig};
+    use std::collections::HashMap;
+
+    #[test_log::test]
+    fn test_validate_txt_records() {
+        let record = |k: &str, v: &str| (k.to_owned(), v.to_owned());
+        assert!(validate_txt_records(&[]).is_ok());
+        assert!(validate_txt_records(&[record("site", "lab-3"), record("fw", "1.2")]).is_ok());
+        assert!(validate_txt_records(&[record("", "x")]).is_err());
+        assert!(validate_txt_records(&[record("a=b", "x")]).is_err());
+        assert!(validate_txt_records(&[record("GRPC", "x")]).is_err());
+        assert!(validate_txt_records(&[record("site", "a"), record("site", "b")]).is_err());
+        assert!(validate_txt_records(&[record("site", &"x".repeat(250))]).is_err());
+        let records: Vec<_> = (0..6)
+            .map(|i| record(&format!("k{}", i), &"x".repeat(240)))
+            .collect();
+        assert!(validate_txt_records(&records).is_err());
+    }
+
+    #[test_log::test]
+    fn test_txt_records_from_config() {
+        let config = |txt_records: Option<Kind>| RobotConfig {
+            services: vec![ServiceConfig {
+                name: "mdns".to_owned(),
+                model: MDNS_SERVICE_MODEL.to_owned(),
+                attributes: Some(Struct {
+                    fields: txt_records
+                        .into_iter()
+                        .map(|kind| ("txt_records".to_owned(), Value { kind: Some(kind) }))
+                        .collect(),
+                }),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let records = |records: &[(&str, Kind)]| {
+            Kind::StructValue(Struct {
+                fields: records
+                    .iter()
+                    .map(|(k, v)| {
+                        (
+                            k.to_string(),
+                            Value {
+                                kind: Some(v.clone()),
+                            },
+                        )
+                    })
+                    .collect::<HashMap<_, _>>(),
+            })
+        };
+
+        assert_eq!(
+            txt_records_from_config(&RobotConfig::default()).unwrap(),
+            None
+        );
+        assert_eq!(
+            txt_records_from_config(&config(None)).unwrap(),
+            Some(vec![])
+        );
+        assert_eq!(
+            txt_records_from_config(&config(Some(records(&[
+                ("site", Kind::StringValue("lab-3".to_owned())),
+                ("fw", Kind::StringValue("1.2".to_owned())),
+            ]))))
+            .unwrap(),
+            Some(vec![
+                ("fw".to_owned(), "1.2".to_owned()),
+                ("site".to_owned(), "lab-3".to_owned())
+            ])
+        );
+        assert!(txt_records_from_config(&config(Some(records(&[(
+            "site",
+            Kind::NumberValue(3.0)
+        )]))))
+        .is_err());
+        assert!(txt_records_from_config(&config(Some(records(&[(
+            "webrtc",
+            Kind::StringValue("off".to_owned())
+        )]))))
+        .is_err());
+        assert!(
+            txt_records_from_config(&config(Some(Kind::StringValue("site".to_owned())))).is_err()
+        );
+    }
+}
@@ -1,4 +1,11 @@
-use std::net::Ipv4Addr;
+use std::{
+    cell::{Cell, RefCell},
+    net::Ipv4Addr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -14,6 +21,27 @@ pub enum NetworkError {
     NoIpConfigured,
 }
 
+/// Parse a comma separated list of IPv4 addresses of DNS servers (e.g. `1.1.1.1,8.8.8.8`).
+/// A malformed list is logged and ignored, so the DNS servers provided by DHCP are used rather
+/// than failing at boot.
+pub fn parse_dns_servers(list: &str) -> Vec<Ipv4Addr> {
+    match list
+        .split(',')
+        .map(|server| server.trim().parse())
+        .collect::<Result<Vec<Ipv4Addr>, _>>()
+    {
+        Ok(servers) => servers,
+        Err(e) => {
+            log::error!(
+                "ignoring DNS servers {:?}, expected comma separated IPv4 addresses: {}",
+                list,
+                e
+            );
+            vec![]
+        }
+    }
+}
+
 /// Reflects the representation of a network's status.
 pub trait Network {
     /// Get the current IP address of the network interface.
@@ -22,6 +50,22 @@ pub trait Network {
     /// Returns whether the underlying network interface is connected, *not* if
     /// internet access is available
     fn is_connected(&self) -> Result<bool, NetworkError>;
+
+    /// SSID of the access point the interface is associated with, None when not on Wi-Fi or
+    /// unknown
+    fn get_ssid(&self) -> Option<String> {
+        None
+    }
+
+    /// Gateway of the network interface, None when unknown
+    fn get_gateway(&self) -> Option<Ipv4Addr> {
+        None
+    }
+
+    /// Subnet mask of the network interface, None when unknown
+    fn get_netmask(&self) -> Option<Ipv4Addr> {
+        None
+    }
 }
 
 impl<T: Network + ?Sized> Network for Box<T> {
@@ -31,6 +75,37 @@ impl<T: Network + ?Sized> Network for Box<T> {
     fn is_connected(&self) -> Result<bool, NetworkError> {
         (**self).is_connected()
     }
+    fn get_ssid(&self) -> Option<String> {
+        (**self).get_ssid()
+    }
+    fn get_gateway(&self) -> Option<Ipv4Addr> {
+        (**self).get_gateway()
+    }
+    fn get_netmask(&self) -> Option<Ipv4Addr> {
+        (**self).get_netmask()
+    }
+}
+
+/// Snapshot of the state of a network, see [`network_details`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NetworkDetails {
+    pub connected: bool,
+    pub ip: Ipv4Addr,
+    pub ssid: Option<String>,
+    pub gateway: Option<Ipv4Addr>,
+    pub netmask: Option<Ipv4Addr>,
+}
+
+impl NetworkDetails {
+    pub fn new(network: &dyn Network) -> Self {
+        Self {
+            connected: network.is_connected().unwrap_or(false),
+            ip: network.get_ip(),
+            ssid: network.get_ssid(),
+            gateway: network.get_gateway(),
+            netmask: network.get_netmask(),
+        }
+    }
 }
 
 /// For networks managed outside of micro-rdk (for example, using micro-rdk as an ESP-IDF
@@ -58,3 +133,155 @@ impl Network for ExternallyManagedNetwork {
         Ok(true)
     }
 }
+
+/// Connectivity of the network shared with the resources, so that drivers and the data manager
+/// can adapt when offline (e.g. capture less data to save power and flash). The server updates
+/// [`CONNECTIVITY`] periodically, read it with [`is_network_connected`].
+pub struct Connectivity {
+    connected: AtomicBool,
+    details: Mutex<Option<NetworkDetails>>,
+}
+
+/// Connectivity of the network the server runs on
+pub static CONNECTIVITY: Connectivity = Connectivity::new();
+
+/// Whether the network the server runs on was connected at the last check, the network is
+/// assumed connected until checked
+pub fn is_network_connected() -> bool {
+    CONNECTIVITY.is_connected()
+}
+
+/// Details of the network the server runs on as of the last check, None until checked
+pub fn network_details() -> Option<NetworkDetails> {
+    CONNECTIVITY.details()
+}
+
+impl Connectivity {
+    pub const fn new() -> Self {
+        Self {
+            connected: AtomicBool::new(true),
+            details: Mutex::new(None),
+        }
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.connected.load(Ordering::Relaxed)
+    }
+
+    pub fn details(&self) -> Option<NetworkDetails> {
+        self.details.lock().unwrap().clone()
+    }
+
+    /// Check whether `network` is connected, a failed check counting as disconnected, and
+    /// return the new state
+    pub fn update(&self, network: &dyn Network) -> bool {
+        let details = NetworkDetails::new(network);
+        let connected = details.connected;
+        let _ = self.details.lock().unwrap().replace(details);
+        if self.connected.swap(connected, Ordering::Relaxed) != connected {
+            log::info!(
+                "network {}",
+                if connected {
+                    "connected"
+                } else {
+                    "disconnected"
+                }
+            );
+        }
+        connected
+    }
+}
+
+impl Default for Connectivity {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[doc(hidden)]
+/// A test network whose connectivity can be changed
+pub struct FakeNetwork {
+    ip: Ipv4Addr,
+    connected: Cell<bool>,
+    ssid: RefCell<Option<String>>,
+}
+
+impl FakeNetwork {
+    pub fn new(ip: Ipv4Addr) -> Self {
+        Self {
+            ip,
+            connected: Cell::new(true),
+            ssid: RefCell::new(None),
+        }
+    }
+    pub fn set_connected(&self, connected: bool) {
+        self.connected.set(connected);
+    }
+    pub fn set_ssid(&self, ssid: Option<String>) {
+        self.ssid.replace(ssid);
+    }
+}
+
+impl Network for FakeNetwork {
+    fn get_ip(&self) -> Ipv4Addr {
+        self.ip
+    }
+    fn is_connected(&self) -> Result<bool, NetworkError> {
+        Ok(self.connected.get())
+    }
+    fn get_ssid(&self) -> Option<String> {
+        self.ssid.borrow().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_dns_servers, Connectivity, FakeNetwork, NetworkDetails};
+    use std::net::Ipv4Addr;
+
+    #[test_log::test]
+    fn test_parse_dns_servers() {
+        assert_eq!(
+            parse_dns_servers("1.1.1.1, 8.8.8.8"),
+            vec![Ipv4Addr::new(1, 1, 1, 1), Ipv4Addr::new(8, 8, 8, 8)]
+        );
+        assert_eq!(parse_dns_servers("1.1.1.1,dns.google"), vec![]);
+        assert_eq!(parse_dns_servers(""), vec![]);
+    }
+
+    #[test_log::test]
+    fn test_connectivity_follows_network() {
+        let network = FakeNetwork::new(Ipv4Addr::LOCALHOST);
+        let connectivity = Connectivity::new();
+        assert!(connectivity.is_connected());
+        network.set_connected(false);
+        assert!(connectivity.is_connected());
+        assert!(!connectivity.update(&network));
+        assert!(!connectivity.is_connected());
+        network.set_connected(true);
+        assert!(connectivity.update(&network));
+        assert!(connectivity.is_connected());
+    }
+
+    #[test_log::test]
+    fn test_connectivity_records_details() {
+        let network = FakeNetwork::new(Ipv4Addr::new(192, 168, 1, 12));
+        let connectivity = Connectivity::new();
+        assert_eq!(connectivity.details(), None);
+        network.set_ssid(Some("office".to_owned()));
+        connectivity.update(&network);
+        assert_eq!(
+            connectivity.details(),
+            Some(NetworkDetails {
+                connected: true,
+                ip: Ipv4Addr::new(192, 168, 1, 12),
+                ssid: Some("office".to_owned()),
+                gateway: None,
+                netmask: None,
+            })
+        );
+        network.set_connected(false);
+        connectivity.update(&network);
+        assert!(!connectivity.details().unwrap().connected);
+    }
+}
@@ -6,6 +6,7 @@ use crate::common::{
         certificate::Certificate,
         dtls::DtlsBuilder,
         grpc::{WebRtcGrpcBody, WebRtcGrpcServer},
+        ice::{IceError, StunServer},
         io::WebRtcTransport,
         sctp::SctpHandle,
     },
@@ -16,16 +17,103 @@ use async_io::Timer;
 use futures_lite::prelude::*;
 
 use async_executor::Task;
-use std::{rc::Rc, time::Duration};
+use std::{
+    rc::Rc,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+use thiserror::Error;
+
+// Limits of the running server, read and changed through `connection_limit` and
+// `set_connection_limit`
+static CONNECTION_LIMITS: ConnectionLimits = ConnectionLimits::new();
+
+#[derive(Debug, Error)]
+pub enum ConnectionLimitError {
+    #[error("connection limit {0} is outside of the allowed range 1..={1}")]
+    OutOfRange(usize, usize),
+    #[error("no server is running")]
+    NotRunning,
+}
+
+// Limit and ceiling of a server, and the limit last requested at runtime (0 when none was)
+pub(crate) struct ConnectionLimits {
+    limit: AtomicUsize,
+    ceiling: AtomicUsize,
+    requested: AtomicUsize,
+}
+
+impl ConnectionLimits {
+    pub(crate) const fn new() -> Self {
+        Self {
+            limit: AtomicUsize::new(0),
+            ceiling: AtomicUsize::new(0),
+            requested: AtomicUsize::new(0),
+        }
+    }
+
+    fn get(&self) -> Option<(usize, usize)> {
+        let ceiling = self.ceiling.load(Ordering::Relaxed);
+        if ceiling == 0 {
+            return None;
+        }
+        let limit = match self.requested.load(Ordering::Relaxed) {
+            0 => self.limit.load(Ordering::Relaxed),
+            requested => requested,
+        };
+        Some((limit, ceiling))
+    }
+
+    fn set(&self, limit: usize) -> Result<(), ConnectionLimitError> {
+        let ceiling = self.ceiling.load(Ordering::Relaxed);
+        if ceiling == 0 {
+            return Err(ConnectionLimitError::NotRunning);
+        }
+        if limit == 0 || limit > ceiling {
+            return Err(ConnectionLimitError::OutOfRange(limit, ceiling));
+        }
+        self.requested.store(limit, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+/// Returns the maximum number of concurrent connections of the running server and the ceiling it
+/// can be raised to with [`set_connection_limit`], None when no server is running
+pub fn connection_limit() -> Option<(usize, usize)> {
+    CONNECTION_LIMITS.get()
+}
+
+/// Change the maximum number of concurrent connections of the running server, the new limit is
+/// applied on the next incoming connection and kept when the server restarts. When lowered,
+/// connections above the limit are not closed but their slot is dropped once they end.
+pub fn set_connection_limit(limit: usize) -> Result<(), ConnectionLimitError> {
+    CONNECTION_LIMITS.set(limit)
+}
 
 pub struct WebRtcConfiguration {
     pub(crate) dtls: Box<dyn DtlsBuilder>,
     pub(crate) cert: Rc<Box<dyn Certificate>>,
+    pub(crate) stun_servers: Vec<StunServer>,
 }
 
 impl WebRtcConfiguration {
     pub fn new(cert: Rc<Box<dyn Certificate>>, dtls: Box<dyn DtlsBuilder>) -> Self {
-        Self { cert, dtls }
+        Self {
+            cert,
+            dtls,
+            stun_servers: vec![StunServer::default()],
+        }
+    }
+
+    /// Replace the STUN servers used to gather server reflexive candidates, they are tried in
+    /// order until one answers. URIs are of the form `stun:host[:port]`, an invalid URI is
+    /// reported and the configuration is left untouched.
+    pub fn with_stun_servers<S: AsRef<str>>(mut self, uris: &[S]) -> Result<Self, IceError> {
+        self.stun_servers = uris
+            .iter()
+            .map(|uri| uri.as_ref().parse())
+            .collect::<Result<Vec<StunServer>, IceError>>()?;
+        Ok(self)
     }
 }
 
@@ -108,33 +196,108 @@ impl IncomingConnectionTask {
 
 pub(crate) struct IncomingConnectionManager {
     connections: Vec<IncomingConnectionTask>,
+    limit: usize,
+    limits: Option<&'static ConnectionLimits>,
 }
 
 impl IncomingConnectionManager {
     pub(crate) fn new(size: usize) -> Self {
         let mut connections = Vec::with_capacity(size);
         connections.resize_with(size, Default::default);
-        Self { connections }
+        Self {
+            connections,
+            limit: size,
+            limits: None,
+        }
+    }
+
+    // Create a manager whose limit can be changed at runtime with `set_connection_limit`, up to
+    // `ceiling`
+    pub(crate) fn with_runtime_limit(size: usize, ceiling: usize) -> Self {
+        Self::with_limits(size, ceiling, &CONNECTION_LIMITS)
+    }
+
+    // Create a manager following the limit requested through `limits`, up to `ceiling`
+    fn with_limits(size: usize, ceiling: usize, limits: &'static ConnectionLimits) -> Self {
+        let ceiling = ceiling.max(size);
+        let size = match limits.requested.load(Ordering::Relaxed) {
+            0 => size,
+            requested => requested.min(ceiling),
+        };
+        limits.limit.store(size, Ordering::Relaxed);
+        limits.ceiling.store(ceiling, Ordering::Relaxed);
+        Self {
+            limits: Some(limits),
+            ..Self::new(size)
+        }
+    }
+
+    // follow a limit requested at runtime, slots still in use above a lowered limit are dropped
+    // as their connection ends
+    fn apply_requested_limit(&mut self) {
+        let Some(limits) = self.limits else {
+            return;
+        };
+        let requested = limits.requested.load(Ordering::Relaxed);
+        if requested != 0 && requested != self.limit {
+            self.limit = requested.min(limits.ceiling.load(Ordering::Relaxed));
+            limits.limit.store(self.limit, Ordering::Relaxed);
+            log::info!("connection limit set to {}", self.limit);
+        }
+        if self.connections.len() < self.limit {
+            self.connections.resize_with(self.limit, Default::default);
+        }
+        while self.connections.len() > self.limit {
+            match self.connections.iter().position(|c| c.is_finished()) {
+                Some(idx) => {
+                    let _ = self.connections.swap_remove(idx);
+                }
+                None => break,
+            }
+        }
     }
 
     pub(crate) fn max_connections(&self) -> usize {
-        self.connections.len()
+        self.limit
     }
 
-    // return the lowest priority of active webrtc tasks or 0
-    pub(crate) fn get_lowest_prio(&self) -> u32 {
+    // slot to reuse for a new connection: a free one if any, otherwise the one holding the lowest
+    // priority connection
+    fn lowest_slot(&mut self) -> Option<&mut IncomingConnectionTask> {
         self.connections
-            .iter()
-            .min_by(|a, b| a.get_prio().cmp(&b.get_prio()))
-            .map_or(0, |c| c.get_prio())
+            .iter_mut()
+            .min_by_key(|c| (!c.is_finished(), c.get_prio()))
+    }
+
+    // Whether a connection of priority `prio` can be accepted. It can when a slot is free or when
+    // the lowest priority connection is strictly lower than `prio`. On equal priority the existing
+    // connection is kept, so two clients of the same priority can't keep taking the slot from each
+    // other (and a new HTTP2 connection never replaces another one).
+    pub(crate) fn can_accept(&mut self, prio: u32) -> bool {
+        self.apply_requested_limit();
+        self.lowest_slot()
+            .is_some_and(|slot| slot.is_finished() || slot.get_prio() < prio)
+    }
+
+    // Make room for a connection of priority `prio` before anything is allocated for it, so there
+    // are never more than `max_connections` connections set up at once. Returns true when a slot
+    // is free or was freed by cancelling the lowest priority connection, and false when the
+    // connection should be refused (see `can_accept`). The slot is then left free for
+    // `insert_new_conn`.
+    pub(crate) async fn make_room(&mut self, prio: u32) -> bool {
+        if !self.can_accept(prio) {
+            return false;
+        }
+        let slot = self.lowest_slot().unwrap();
+        if let Some(last_error) = slot.cancel().await {
+            log::info!("last_error {:?}", last_error);
+        }
+        true
     }
-    // function will never fail and the lowest priority will always be replaced
+
+    // Store the task of a connection accepted by `make_room` in the slot it freed
     pub(crate) async fn insert_new_conn(&mut self, task: Task<Result<(), ServerError>>, prio: u32) {
-        if let Some(slot) = self
-            .connections
-            .iter_mut()
-            .min_by(|a, b| a.get_prio().cmp(&b.get_prio()))
-        {
+        if let Some(slot) = self.lowest_slot() {
             if let Some(last_error) = slot.cancel().await {
                 log::info!("last_error {:?}", last_error);
             }
@@ -142,3 +305,114 @@ impl IncomingConnectionManager {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::exec::Executor;
+    use futures_lite::future;
+
+    // lowest priority of the active connections or 0
+    fn lowest_prio(manager: &IncomingConnectionManager) -> u32 {
+        manager
+            .connections
+            .iter()
+            .map(|c| c.get_prio())
+            .min()
+            .unwrap_or(0)
+    }
+
+    fn pending_conn(exec: &Executor) -> Task<Result<(), ServerError>> {
+        exec.spawn(future::pending())
+    }
+
+    #[test_log::test]
+    fn test_connection_priority() {
+        let exec = Executor::new();
+        let mut manager = IncomingConnectionManager::new(1);
+        assert!(exec.block_on(manager.make_room(0)));
+        exec.block_on(manager.insert_new_conn(pending_conn(&exec), 5));
+        assert_eq!(lowest_prio(&manager), 5);
+
+        // refused: lower or equal priority offers leave the established connection alone
+        assert!(!manager.can_accept(4));
+        assert!(!exec.block_on(manager.make_room(5)));
+        assert_eq!(lowest_prio(&manager), 5);
+        assert!(!manager.connections[0].is_finished());
+
+        // evicted: the lowest priority connection is cancelled before the new one is set up, so
+        // the limit is never exceeded
+        assert!(manager.can_accept(6));
+        assert!(exec.block_on(manager.make_room(6)));
+        assert!(manager.connections[0].is_finished());
+        exec.block_on(manager.insert_new_conn(pending_conn(&exec), 6));
+        assert_eq!(lowest_prio(&manager), 6);
+
+        // a slot freed by a connection ending is reused whatever the priority
+        let done = exec.spawn(async { Ok(()) });
+        let mut manager = IncomingConnectionManager::new(2);
+        exec.block_on(manager.insert_new_conn(pending_conn(&exec), 3));
+        exec.block_on(manager.insert_new_conn(done, 9));
+        exec.block_on(future::yield_now());
+        assert!(exec.block_on(manager.make_room(0)));
+        assert_eq!(
+            manager
+                .connections
+                .iter()
+                .filter(|c| c.is_finished())
+                .count(),
+            1
+        );
+        exec.block_on(manager.insert_new_conn(pending_conn(&exec), 1));
+        assert_eq!(lowest_prio(&manager), 1);
+        assert!(!manager.can_accept(1));
+    }
+
+    #[test_log::test]
+    fn test_runtime_connection_limit() {
+        let exec = Executor::new();
+        let limits: &'static ConnectionLimits = Box::leak(Box::new(ConnectionLimits::new()));
+        assert!(limits.get().is_none());
+        assert!(matches!(
+            limits.set(2),
+            Err(ConnectionLimitError::NotRunning)
+        ));
+
+        let mut manager = IncomingConnectionManager::with_limits(2, 4, limits);
+        assert_eq!(limits.get(), Some((2, 4)));
+        assert!(matches!(
+            limits.set(0),
+            Err(ConnectionLimitError::OutOfRange(0, 4))
+        ));
+        assert!(matches!(
+            limits.set(5),
+            Err(ConnectionLimitError::OutOfRange(5, 4))
+        ));
+        assert_eq!(limits.get(), Some((2, 4)));
+
+        // raised: new slots are available on the next connection
+        limits.set(3).unwrap();
+        assert_eq!(limits.get(), Some((3, 4)));
+        for prio in 0..3 {
+            assert!(exec.block_on(manager.make_room(prio + 1)));
+            exec.block_on(manager.insert_new_conn(pending_conn(&exec), prio + 1));
+        }
+        assert_eq!(manager.max_connections(), 3);
+        assert_eq!(manager.connections.len(), 3);
+        assert!(!manager.can_accept(1));
+
+        // lowered: running connections are kept until they end
+        limits.set(1).unwrap();
+        assert!(!manager.can_accept(1));
+        assert_eq!(manager.max_connections(), 1);
+        assert_eq!(manager.connections.len(), 3);
+        let _ = exec.block_on(manager.connections[0].cancel());
+        assert!(!manager.can_accept(1));
+        assert_eq!(manager.connections.len(), 2);
+
+        // the requested limit is kept when the server restarts
+        let manager = IncomingConnectionManager::with_limits(2, 4, limits);
+        assert_eq!(manager.max_connections(), 1);
+        assert_eq!(limits.get(), Some((1, 4)));
+    }
+}
@@ -1,7 +1,6 @@
 use async_channel::Receiver;
 use async_executor::Task;
 use async_io::{Async, Timer};
-use either::Either;
 
 use futures_lite::{FutureExt, StreamExt};
 use futures_util::stream::FuturesUnordered;
@@ -13,46 +12,224 @@ use std::future::Future;
 use crate::common::app_client::{
     AppClient, AppClientBuilder, AppClientError, PeriodicAppClientTask,
 };
-use crate::common::credentials_storage::{StorageDiagnostic, TlsCertificate};
+use crate::common::blocking::BlockingPool;
+use crate::common::credentials_storage::{
+    CachedWebRtcCertificate, StorageDiagnostic, TlsCertificate, WebRtcCertificateStorage,
+};
 use crate::common::webrtc::signaling_server::SignalingServer;
 use std::marker::PhantomData;
-use std::net::{SocketAddr, TcpListener};
+use std::net::{SocketAddr, TcpListener, ToSocketAddrs};
 use std::pin::Pin;
 use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
+
+use once_cell::sync::Lazy;
 use std::task::Poll;
 use std::time::Duration;
 use std::{fmt::Debug, net::TcpStream};
 
-use crate::common::config_monitor::ConfigMonitor;
+use crate::common::config_monitor::{
+    ConfigMonitor, DEFAULT_CONFIG_POLL_INTERVAL, MIN_CONFIG_POLL_INTERVAL,
+};
 use crate::common::grpc::{GrpcBody, GrpcServer, ServerError};
 use crate::common::grpc_client::GrpcClient;
 use crate::common::log::LogUploadTask;
+use crate::common::metrics::{self, ConnectionGuard};
 use crate::common::provisioning::server::{
-    serve_provisioning_async, ProvisioningInfo, WifiApConfiguration, WifiManager,
+    serve_provisioning_async, ProvisioningInfo, WifiApConfiguration, WifiApConfigurationError,
+    WifiManager,
 };
 use crate::common::registry::ComponentRegistry;
 use crate::common::restart_monitor::RestartMonitor;
 use crate::common::robot::LocalRobot;
+use crate::common::startup_report::{ConfigSource, StartupReport};
 use crate::common::webrtc::api::{SignalingTask, WebRtcApi, WebRtcError, WebRtcSignalingChannel};
 use crate::common::webrtc::certificate::Certificate;
 use crate::common::webrtc::dtls::DtlsBuilder;
+use crate::common::webrtc::ice::LocalIpWatch;
 use crate::common::{
     credentials_storage::{RobotConfigurationStorage, WifiCredentialStorage},
-    exec::Executor,
+    exec::{Executor, TaskConfig},
 };
 use crate::proto;
 use crate::proto::app::v1::RobotConfig;
 
 use super::errors;
-use super::mdns::Mdns;
-use super::network::Network;
+use super::mdns::{
+    txt_records_from_config, validate_txt_records, Mdns, MdnsError, DEFAULT_TXT_RECORDS,
+};
+use super::network::{Network, CONNECTIVITY};
 use super::server::{IncomingConnectionManager, WebRtcConfiguration};
 use crate::common::provisioning::server::AsNetwork;
 
 #[cfg(feature = "ota")]
 use crate::common::{credentials_storage::OtaMetadataStorage, ota};
 
+static FACTORY_RESET: Lazy<FactoryReset> = Lazy::new(FactoryReset::new);
+// How often the running server looks for a factory reset request
+const FACTORY_RESET_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+// Default delay before attempting to reconnect to app, doubled after each failed attempt up to
+// APP_RECONNECT_MAX_BACKOFF and reset once connected
+const APP_RECONNECT_BACKOFF: Duration = Duration::from_secs(1);
+const APP_RECONNECT_MAX_BACKOFF: Duration = Duration::from_secs(60);
+// Shortest delay accepted, a null delay would never grow and reconnect in a busy loop
+const APP_RECONNECT_MIN_BACKOFF: Duration = Duration::from_millis(10);
+
+// Delay before the next attempt to reconnect to app
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct AppReconnectBackoff {
+    initial: Duration,
+    max: Duration,
+    current: Duration,
+}
+
+impl AppReconnectBackoff {
+    // `initial` is raised to APP_RECONNECT_MIN_BACKOFF and `max` to `initial` if shorter
+    fn new(initial: Duration, max: Duration) -> Self {
+        let (requested_initial, requested_max) = (initial, max);
+        let initial = initial.max(APP_RECONNECT_MIN_BACKOFF);
+        let max = max.max(initial);
+        if (initial, max) != (requested_initial, requested_max) {
+            log::warn!(
+                "app reconnect backoff from {:?} up to {:?} adjusted to {:?} up to {:?}",
+                requested_initial,
+                requested_max,
+                initial,
+                max
+            );
+        }
+        Self {
+            initial,
+            max,
+            current: initial,
+        }
+    }
+
+    // the delay once another attempt failed
+    fn after_failure(&self) -> Duration {
+        (self.current * 2).min(self.max)
+    }
+
+    fn update(&mut self, connected: bool) {
+        self.current = if connected {
+            self.initial
+        } else {
+            self.after_failure()
+        };
+    }
+}
+
+// How long the reachability check waits for a TCP connection to app
+const APP_REACHABILITY_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Resolve the app host then, for TLS addresses, open a TCP connection to it before the TLS and
+// gRPC handshakes, so that a DNS failure, an unreachable app and a failed TLS handshake are told
+// apart in the logs. Plain http addresses (local development) are only resolved.
+async fn check_app_reachability(
+    app_uri: &Uri,
+    pool: Option<&BlockingPool>,
+    task_config: &TaskConfig,
+    executor: &Executor,
+) -> Result<(), AppClientError> {
+    let host = app_uri.host().unwrap_or_default();
+    let tls = app_uri.scheme_str() != Some("http");
+    let port = app_uri.port_u16().unwrap_or(if tls { 443 } else { 80 });
+    let addrs = resolve_app_host(host, port, pool, task_config, executor)
+        .await
+        .map_err(|e| AppClientError::AppDnsError(host.to_owned(), e))?;
+    if addrs.is_empty() {
+        return Err(AppClientError::AppDnsError(
+            host.to_owned(),
+            std::io::Error::new(std::io::ErrorKind::NotFound, "no address found"),
+        ));
+    }
+    if !tls {
+        return Ok(());
+    }
+    let mut last_error = None;
+    for addr in addrs {
+        match Async::<TcpStream>::connect(addr)
+            .or(async {
+                Timer::after(APP_REACHABILITY_TIMEOUT).await;
+                Err(std::io::ErrorKind::TimedOut.into())
+            })
+            .await
+        {
+            Ok(_) => return Ok(()),
+            Err(e) => last_error = Some(AppClientError::AppTcpConnectError(addr, e)),
+        }
+    }
+    Err(last_error.unwrap())
+}
+
+// getaddrinfo blocks until the DNS server answers, the lookup is made on `pool` when there is one
+// and on a thread of its own configured by `task_config` otherwise so the executor keeps running
+// meanwhile
+async fn resolve_app_host(
+    host: &str,
+    port: u16,
+    pool: Option<&BlockingPool>,
+    task_config: &TaskConfig,
+    executor: &Executor,
+) -> std::io::Result<Vec<SocketAddr>> {
+    let lookup = {
+        let host = host.to_owned();
+        move || {
+            (host.as_str(), port)
+                .to_socket_addrs()
+                .map(Iterator::collect)
+        }
+    };
+    match pool {
+        Some(pool) => pool
+            .spawn_blocking(lookup)
+            .map_err(std::io::Error::other)?
+            .await
+            .unwrap_or_else(|| Err(std::io::Error::other("address lookup panicked"))),
+        None => executor
+            .spawn_with_config(task_config, move || async move { lookup() })?
+            .await
+            .unwrap_or_else(|| Err(std::io::Error::other("address lookup panicked"))),
+    }
+}
+
+/// Flag through which a factory reset of a running [`ViamServer`] is requested, see
+/// [`ViamServerBuilder::with_factory_reset`]. Servers use the process-wide one returned by
+/// [`FactoryReset::global`] unless given another one.
+#[derive(Clone, Default)]
+pub struct FactoryReset(Arc<AtomicBool>);
+
+impl FactoryReset {
+    /// A flag independent from the process-wide one
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The flag shared by the process, set by [`request_factory_reset`]
+    pub fn global() -> Self {
+        FACTORY_RESET.clone()
+    }
+
+    /// Ask the server watching this flag to erase the robot credentials, configuration and TLS
+    /// certificate then restart, the device will boot into provisioning mode so it can be
+    /// assigned to another robot. WiFi credentials are kept.
+    pub fn request(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub(crate) fn take(&self) -> bool {
+        self.0.swap(false, Ordering::Relaxed)
+    }
+}
+
+/// Request a factory reset of the running [`ViamServer`] through the process-wide
+/// [`FactoryReset`], see [`FactoryReset::request`]
+pub fn request_factory_reset() {
+    FactoryReset::global().request();
+}
+
 pub struct RobotCloudConfig {
     local_fqdn: String,
     name: String,
@@ -91,12 +268,22 @@ impl From<&proto::app::v1::CloudConfig> for RobotCloudConfig {
 
 #[cfg(not(feature = "ota"))]
 pub trait ViamServerStorage:
-    RobotConfigurationStorage + WifiCredentialStorage + StorageDiagnostic + Clone + 'static
+    RobotConfigurationStorage
+    + WifiCredentialStorage
+    + WebRtcCertificateStorage
+    + StorageDiagnostic
+    + Clone
+    + 'static
 {
 }
 #[cfg(not(feature = "ota"))]
 impl<T> ViamServerStorage for T where
-    T: RobotConfigurationStorage + WifiCredentialStorage + StorageDiagnostic + Clone + 'static
+    T: RobotConfigurationStorage
+        + WifiCredentialStorage
+        + WebRtcCertificateStorage
+        + StorageDiagnostic
+        + Clone
+        + 'static
 {
 }
 
@@ -104,6 +291,7 @@ impl<T> ViamServerStorage for T where
 pub trait ViamServerStorage:
     RobotConfigurationStorage
     + WifiCredentialStorage
+    + WebRtcCertificateStorage
     + OtaMetadataStorage
     + StorageDiagnostic
     + Clone
@@ -114,6 +302,7 @@ pub trait ViamServerStorage:
 impl<T> ViamServerStorage for T where
     T: RobotConfigurationStorage
         + WifiCredentialStorage
+        + WebRtcCertificateStorage
         + OtaMetadataStorage
         + StorageDiagnostic
         + Clone
@@ -137,8 +326,16 @@ impl HTTP2Server {
     }
 }
 
+/// Generates a new WebRTC certificate, `None` if it couldn't be generated
+pub type WebRtcCertificateGenerator = Box<dyn FnOnce() -> Option<CachedWebRtcCertificate>>;
+/// Makes the WebRTC configuration (DTLS stack, STUN servers...) serving a certificate
+pub type WebRtcConfigurationFactory =
+    Box<dyn FnOnce(Rc<Box<dyn Certificate>>) -> WebRtcConfiguration>;
+
 pub(crate) enum WebRtcListener {
     WebRtc(WebRtcConfiguration),
+    // resolved once storage is read, at the start of ViamServer::run
+    Cached(WebRtcCertificateGenerator, WebRtcConfigurationFactory),
     Empty,
 }
 
@@ -182,6 +379,35 @@ pub trait IntoHttp2Stream: Future<Output = Result<Box<dyn HTTP2Stream>, std::io:
 
 impl<T> HTTP2Stream for T where T: rt::Read + rt::Write + Unpin {}
 
+/// Configuration used when running without any connection to app, see
+/// [`ViamServerBuilder::local_only`]
+pub(crate) struct LocalOnlyConfiguration {
+    config: Box<RobotConfig>,
+    certificate: TlsCertificate,
+}
+
+/// Durations of the cycle set by [`ViamServerBuilder::with_deep_sleep_cycle`]
+#[cfg(feature = "esp32")]
+#[derive(Clone, Copy, Debug)]
+struct DeepSleepCycle {
+    awake: Duration,
+    asleep: Duration,
+}
+
+#[cfg(feature = "esp32")]
+impl DeepSleepCycle {
+    async fn run(self) -> Result<(), errors::ServerError> {
+        Timer::after(self.awake).await;
+        log::warn!("entering deep sleep for {:?}", self.asleep);
+        unsafe {
+            crate::esp32::esp_idf_svc::sys::esp_sleep_enable_timer_wakeup(
+                self.asleep.as_micros() as u64
+            );
+            crate::esp32::esp_idf_svc::sys::esp_deep_sleep_start();
+        }
+    }
+}
+
 pub struct WantsNetwork;
 pub struct HasNetwork;
 pub struct ViamServerBuilder<Storage, State> {
@@ -189,15 +415,49 @@ pub struct ViamServerBuilder<Storage, State> {
     http2_server: HTTP2Server,
     webrtc_configuration: WebRtcListener,
     provisioning_info: ProvisioningInfo,
+    provisioning_ap: Option<WifiApConfiguration>,
     wifi_manager: Option<Box<dyn WifiManager>>,
     component_registry: Box<ComponentRegistry>,
     http2_server_port: u16,
     http2_server_insecure: bool,
     app_client_tasks: Vec<Box<dyn PeriodicAppClientTask>>,
     max_concurrent_connections: usize,
+    local_only: Option<LocalOnlyConfiguration>,
+    local_api_key: Option<String>,
+    tcp_keepalive: Option<TcpKeepalive>,
+    mdns_txt_records: Vec<(String, String)>,
+    config_poll_interval: Duration,
+    pin_cached_config: bool,
+    initial_config_timeout: Duration,
+    app_reconnect_backoff: AppReconnectBackoff,
+    sensor_only: bool,
+    blocking_pool: Option<Arc<BlockingPool>>,
+    task_config: TaskConfig,
+    factory_reset: FactoryReset,
+    restart_hook: Rc<dyn Fn()>,
+    #[cfg(feature = "native")]
+    metrics_address: Option<SocketAddr>,
+    #[cfg(feature = "esp32")]
+    watchdog_startup_grace: Duration,
+    #[cfg(feature = "esp32")]
+    deep_sleep_cycle: Option<DeepSleepCycle>,
     _state: PhantomData<State>,
 }
 
+// Whether the device runs without SPIRAM
+#[cfg(target_os = "espidf")]
+fn lacks_spiram() -> bool {
+    extern "C" {
+        pub static g_spiram_ok: bool;
+    }
+    unsafe { !g_spiram_ok }
+}
+
+#[cfg(not(target_os = "espidf"))]
+fn lacks_spiram() -> bool {
+    false
+}
+
 impl<Storage> ViamServerBuilder<Storage, WantsNetwork>
 where
     Storage: ViamServerStorage,
@@ -212,7 +472,6 @@ where
         // if it needs a coordinated adjustment.
 
         // By default, we get three, everywhere.
-        #[allow(unused_mut)]
         let mut max = 3;
 
         // If local signaling is enabled, grant two more
@@ -222,33 +481,60 @@ where
         }
 
         // But on an esp32 lacking SPIRAM, assume only one connection can be realized
-        #[cfg(target_os = "espidf")]
-        {
-            extern "C" {
-                pub static g_spiram_ok: bool;
-            }
-            unsafe {
-                if !g_spiram_ok {
-                    max = 1;
-                }
-            }
+        if lacks_spiram() {
+            max = 1;
         }
 
         max
     }
 
+    /// Returns the highest limit on the number of concurrent connections that can be set at
+    /// runtime: twice the default, except on an esp32 lacking SPIRAM which can't realize more
+    /// than one connection.
+    pub fn get_max_runtime_concurrent_connections() -> usize {
+        let default = Self::get_default_max_concurrent_connections();
+        if lacks_spiram() {
+            default
+        } else {
+            2 * default
+        }
+    }
+
     pub fn new(storage: Storage) -> Self {
         ViamServerBuilder {
             storage,
             http2_server: HTTP2Server::Empty,
             webrtc_configuration: WebRtcListener::Empty,
             provisioning_info: Default::default(),
+            provisioning_ap: None,
             wifi_manager: None,
             component_registry: Default::default(),
             http2_server_port: 12346,
             http2_server_insecure: false,
             app_client_tasks: Default::default(),
             max_concurrent_connections: Self::get_default_max_concurrent_connections(),
+            local_only: None,
+            local_api_key: None,
+            tcp_keepalive: Some(Default::default()),
+            mdns_txt_records: Vec::new(),
+            config_poll_interval: DEFAULT_CONFIG_POLL_INTERVAL,
+            pin_cached_config: false,
+            initial_config_timeout: DEFAULT_INITIAL_CONFIG_TIMEOUT,
+            app_reconnect_backoff: AppReconnectBackoff::new(
+                APP_RECONNECT_BACKOFF,
+                APP_RECONNECT_MAX_BACKOFF,
+            ),
+            sensor_only: false,
+            blocking_pool: None,
+            task_config: TaskConfig::default(),
+            factory_reset: FactoryReset::global(),
+            restart_hook: Rc::new(|| std::process::exit(0)),
+            #[cfg(feature = "native")]
+            metrics_address: None,
+            #[cfg(feature = "esp32")]
+            watchdog_startup_grace: Duration::ZERO,
+            #[cfg(feature = "esp32")]
+            deep_sleep_cycle: None,
             _state: PhantomData,
         }
     }
@@ -261,11 +547,31 @@ where
             http2_server: self.http2_server,
             webrtc_configuration: self.webrtc_configuration,
             provisioning_info: self.provisioning_info,
+            provisioning_ap: self.provisioning_ap,
             component_registry: self.component_registry,
             http2_server_port: self.http2_server_port,
             http2_server_insecure: self.http2_server_insecure,
             app_client_tasks: self.app_client_tasks,
             max_concurrent_connections: self.max_concurrent_connections,
+            local_only: self.local_only,
+            local_api_key: self.local_api_key,
+            tcp_keepalive: self.tcp_keepalive,
+            mdns_txt_records: self.mdns_txt_records,
+            config_poll_interval: self.config_poll_interval,
+            pin_cached_config: self.pin_cached_config,
+            initial_config_timeout: self.initial_config_timeout,
+            app_reconnect_backoff: self.app_reconnect_backoff,
+            sensor_only: self.sensor_only,
+            blocking_pool: self.blocking_pool,
+            task_config: self.task_config,
+            factory_reset: self.factory_reset,
+            restart_hook: self.restart_hook,
+            #[cfg(feature = "native")]
+            metrics_address: self.metrics_address,
+            #[cfg(feature = "esp32")]
+            watchdog_startup_grace: self.watchdog_startup_grace,
+            #[cfg(feature = "esp32")]
+            deep_sleep_cycle: self.deep_sleep_cycle,
             wifi_manager: Some(wifi_manager),
             _state: PhantomData::<HasNetwork>,
         }
@@ -291,6 +597,17 @@ where
         self
     }
 
+    /// SSID and password of the AP started while provisioning instead of the
+    /// [`WifiApConfiguration`] defaults, errors if they don't fit the Wi-Fi limits
+    pub fn with_provisioning_ap(
+        &mut self,
+        ap: WifiApConfiguration,
+    ) -> Result<&mut Self, WifiApConfigurationError> {
+        ap.validate()?;
+        self.provisioning_ap = Some(ap);
+        Ok(self)
+    }
+
     pub fn with_http2_server<H>(&mut self, http2_connector: H, port: u16) -> &mut Self
     where
         H: ViamH2Connector + 'static,
@@ -313,6 +630,18 @@ where
         self
     }
 
+    /// Serve WebRTC with a certificate kept in storage rather than a new one each boot.
+    /// `generate` is only called when storage holds no certificate or an expired one, the
+    /// generated certificate is then stored for the following boots.
+    pub fn with_cached_webrtc_certificate(
+        &mut self,
+        generate: WebRtcCertificateGenerator,
+        configure: WebRtcConfigurationFactory,
+    ) -> &mut Self {
+        self.webrtc_configuration = WebRtcListener::Cached(generate, configure);
+        self
+    }
+
     pub fn with_component_registry(
         &mut self,
         component_registry: Box<ComponentRegistry>,
@@ -326,6 +655,150 @@ where
         self
     }
 
+    /// Never attempt to reach app. The robot is built from `config` (for example decoded from a
+    /// file) and the HTTP2 server uses `certificate` rather than one issued by app. App client
+    /// tasks, including WebRTC signaling through app, are not run while local connections are
+    /// served as usual.
+    pub fn local_only(&mut self, config: RobotConfig, certificate: TlsCertificate) -> &mut Self {
+        self.local_only = Some(LocalOnlyConfiguration {
+            config: Box::new(config),
+            certificate,
+        });
+        self
+    }
+
+    /// Pre-shared api key protecting the local gRPC server in local only mode. Clients must
+    /// authenticate with it through `AuthService/Authenticate` and send the returned token as
+    /// `authorization: Bearer <token>`, other requests are rejected as unauthenticated.
+    pub fn with_local_api_key(&mut self, api_key: String) -> &mut Self {
+        self.local_api_key = Some(api_key);
+        self
+    }
+
+    /// Configure the TCP keepalive probes sent on accepted HTTP2 connections so that sockets of
+    /// dead peers are closed and their connection slot freed. Enabled with the
+    /// [`TcpKeepalive`] defaults, `None` disables it.
+    pub fn with_tcp_keepalive(&mut self, keepalive: Option<TcpKeepalive>) -> &mut Self {
+        self.tcp_keepalive = keepalive;
+        self
+    }
+
+    /// Extra TXT entries (e.g. a site identifier) advertised by the mDNS services of the robot
+    /// along with the `grpc` and `webrtc` ones, errors if they exceed the mDNS limits (see
+    /// [`validate_txt_records`]). Records set in the robot config with a
+    /// [`MDNS_SERVICE_MODEL`](super::mdns::MDNS_SERVICE_MODEL) service replace these.
+    pub fn with_mdns_txt_records(
+        &mut self,
+        records: Vec<(String, String)>,
+    ) -> Result<&mut Self, MdnsError> {
+        validate_txt_records(&records)?;
+        self.mdns_txt_records = records;
+        Ok(self)
+    }
+
+    /// How often app is polled for a new robot config (which restarts the robot), defaults to
+    /// [`DEFAULT_CONFIG_POLL_INTERVAL`]. A `check_interval` set in the agent config of the robot
+    /// takes precedence. Restart requests are polled independently at the interval app asks for
+    /// (5 seconds by default, see [`RestartMonitor`]) so a longer config poll interval doesn't
+    /// delay them. Intervals shorter than [`MIN_CONFIG_POLL_INTERVAL`] are raised to it.
+    pub fn with_config_poll_interval(&mut self, interval: Duration) -> &mut Self {
+        self.config_poll_interval = interval.max(MIN_CONFIG_POLL_INTERVAL);
+        self
+    }
+
+    /// Keep running the robot config cached in storage and ignore the changes made in app, app is
+    /// still contacted for authentication, certificates, logs, data and restart requests. The
+    /// config received from app is only used (and cached) when none is in storage, for example
+    /// after provisioning or a factory reset.
+    pub fn with_pinned_config(&mut self, pin: bool) -> &mut Self {
+        self.pin_cached_config = pin;
+        self
+    }
+
+    /// How long to wait for the robot config from app at startup before falling back on the
+    /// cached one, defaults to [`DEFAULT_INITIAL_CONFIG_TIMEOUT`]
+    pub fn with_initial_config_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.initial_config_timeout = timeout;
+        self
+    }
+
+    /// Delay before reconnecting to app once the connection is lost, doubled after each failed
+    /// attempt up to `max`. Defaults to 1 second and 1 minute. `initial` is raised to 10ms and
+    /// `max` to `initial` when shorter.
+    pub fn with_app_reconnect_backoff(&mut self, initial: Duration, max: Duration) -> &mut Self {
+        self.app_reconnect_backoff = AppReconnectBackoff::new(initial, max);
+        self
+    }
+
+    /// Serve the process metrics (see [`crate::common::metrics`]) in the Prometheus text format
+    /// over HTTP at `http://<address>/metrics`
+    #[cfg(feature = "native")]
+    pub fn with_metrics_endpoint(&mut self, address: SocketAddr) -> &mut Self {
+        self.metrics_address = Some(address);
+        self
+    }
+
+    /// Delay the registration of the server task on the task watchdog by `grace` after startup,
+    /// so that a slow first configuration (camera, many I2C sensors...) doesn't trip it
+    #[cfg(feature = "esp32")]
+    pub fn with_watchdog_startup_grace(&mut self, grace: Duration) -> &mut Self {
+        self.watchdog_startup_grace = grace;
+        self
+    }
+
+    /// Only run the app client tasks (log upload, data sync, config and restart monitoring...)
+    /// along with the robot, for battery powered sensor nodes uploading data on a schedule. No
+    /// HTTP2 or WebRTC server is started, no WebRTC certificate is loaded, signaling isn't done
+    /// and nothing is advertised over mDNS, the robot can't be reached. The network is still
+    /// monitored. Ignored in local only mode. See
+    /// [`with_deep_sleep_cycle`](Self::with_deep_sleep_cycle) to power the device down between
+    /// captures.
+    pub fn sensor_only(&mut self) -> &mut Self {
+        self.sensor_only = true;
+        self
+    }
+
+    /// Put the device in deep sleep for `asleep` once it has been running for `awake`, the
+    /// device reboots when it wakes up. `awake` has to leave enough time to connect to app,
+    /// capture data and upload it, data not synced yet is lost unless it's stored in flash.
+    #[cfg(feature = "esp32")]
+    pub fn with_deep_sleep_cycle(&mut self, awake: Duration, asleep: Duration) -> &mut Self {
+        self.deep_sleep_cycle = Some(DeepSleepCycle { awake, asleep });
+        self
+    }
+
+    /// Run the blocking calls made by the server (resolving the app address) on `pool` rather
+    /// than on the executor, the pool can be shared with components offloading their reads to it
+    /// (see [`BackgroundReadings`](crate::common::blocking::BackgroundReadings)). Sensors
+    /// configured with `"read_in_background": true` are read on it.
+    pub fn with_blocking_pool(&mut self, pool: Arc<BlockingPool>) -> &mut Self {
+        crate::common::blocking::set_sensor_read_pool(pool.clone());
+        self.blocking_pool = Some(pool);
+        self
+    }
+
+    /// Stack size and priority of the threads the server spawns for the blocking calls it makes
+    /// without a blocking pool (see [`with_blocking_pool`](Self::with_blocking_pool)), defaults
+    /// to [`TaskConfig::default`]
+    pub fn with_task_config(&mut self, config: TaskConfig) -> &mut Self {
+        self.task_config = config;
+        self
+    }
+
+    /// Watch `factory_reset` for factory reset requests instead of the process-wide
+    /// [`FactoryReset::global`]
+    pub fn with_factory_reset(&mut self, factory_reset: FactoryReset) -> &mut Self {
+        self.factory_reset = factory_reset;
+        self
+    }
+
+    /// Called to restart the device once a factory reset is done, exits the process by default
+    /// so that it is restarted by the system (or the device rebooted on ESP32)
+    pub fn with_restart_hook(&mut self, restart_hook: impl Fn() + 'static) -> &mut Self {
+        self.restart_hook = Rc::new(restart_hook);
+        self
+    }
+
     pub fn with_default_tasks(&mut self) -> &mut Self {
         let restart_monitor = Box::new(RestartMonitor::new(|| std::process::exit(0)));
         let log_upload = Box::new(LogUploadTask);
@@ -360,6 +833,7 @@ where
             mdns: RefCell::new(mdns),
             component_registry: self.component_registry,
             provisioning_info: self.provisioning_info,
+            provisioning_ap: self.provisioning_ap,
             http2_server_insecure: self.http2_server_insecure,
             http2_server_port: self.http2_server_port,
             wifi_manager: self.wifi_manager.into(),
@@ -367,6 +841,25 @@ where
             #[cfg(feature = "ota")]
             ota_service_task: Default::default(),
             max_concurrent_connections: self.max_concurrent_connections,
+            local_only: self.local_only,
+            local_api_key: self.local_api_key,
+            tcp_keepalive: self.tcp_keepalive,
+            mdns_txt_records: self.mdns_txt_records,
+            config_poll_interval: self.config_poll_interval,
+            pin_cached_config: self.pin_cached_config,
+            initial_config_timeout: self.initial_config_timeout,
+            app_reconnect_backoff: self.app_reconnect_backoff,
+            sensor_only: self.sensor_only,
+            blocking_pool: self.blocking_pool,
+            task_config: self.task_config,
+            factory_reset: self.factory_reset,
+            restart_hook: self.restart_hook,
+            #[cfg(feature = "native")]
+            metrics_address: self.metrics_address,
+            #[cfg(feature = "esp32")]
+            watchdog_startup_grace: self.watchdog_startup_grace,
+            #[cfg(feature = "esp32")]
+            deep_sleep_cycle: self.deep_sleep_cycle,
             network: Some(network),
         }
     }
@@ -396,6 +889,7 @@ where
             mdns: RefCell::new(mdns),
             component_registry: self.component_registry,
             provisioning_info: self.provisioning_info,
+            provisioning_ap: self.provisioning_ap,
             http2_server_insecure: self.http2_server_insecure,
             http2_server_port: self.http2_server_port,
             wifi_manager: Rc::new(self.wifi_manager),
@@ -403,6 +897,25 @@ where
             #[cfg(feature = "ota")]
             ota_service_task: None,
             max_concurrent_connections: self.max_concurrent_connections,
+            local_only: self.local_only,
+            local_api_key: self.local_api_key,
+            tcp_keepalive: self.tcp_keepalive,
+            mdns_txt_records: self.mdns_txt_records,
+            config_poll_interval: self.config_poll_interval,
+            pin_cached_config: self.pin_cached_config,
+            initial_config_timeout: self.initial_config_timeout,
+            app_reconnect_backoff: self.app_reconnect_backoff,
+            sensor_only: self.sensor_only,
+            blocking_pool: self.blocking_pool,
+            task_config: self.task_config,
+            factory_reset: self.factory_reset,
+            restart_hook: self.restart_hook,
+            #[cfg(feature = "native")]
+            metrics_address: self.metrics_address,
+            #[cfg(feature = "esp32")]
+            watchdog_startup_grace: self.watchdog_startup_grace,
+            #[cfg(feature = "esp32")]
+            deep_sleep_cycle: self.deep_sleep_cycle,
             network: None,
         }
     }
@@ -414,6 +927,7 @@ pub struct ViamServer<Storage, C, M> {
     webrtc_configuration: WebRtcListener,
     http2_connector: C,
     provisioning_info: ProvisioningInfo,
+    provisioning_ap: Option<WifiApConfiguration>,
     mdns: RefCell<M>,
     component_registry: Box<ComponentRegistry>,
     http2_server_insecure: bool,
@@ -423,6 +937,25 @@ pub struct ViamServer<Storage, C, M> {
     #[cfg(feature = "ota")]
     ota_service_task: Option<Task<()>>,
     max_concurrent_connections: usize,
+    local_only: Option<LocalOnlyConfiguration>,
+    local_api_key: Option<String>,
+    tcp_keepalive: Option<TcpKeepalive>,
+    mdns_txt_records: Vec<(String, String)>,
+    config_poll_interval: Duration,
+    pin_cached_config: bool,
+    initial_config_timeout: Duration,
+    app_reconnect_backoff: AppReconnectBackoff,
+    sensor_only: bool,
+    blocking_pool: Option<Arc<BlockingPool>>,
+    task_config: TaskConfig,
+    factory_reset: FactoryReset,
+    restart_hook: Rc<dyn Fn()>,
+    #[cfg(feature = "native")]
+    metrics_address: Option<SocketAddr>,
+    #[cfg(feature = "esp32")]
+    watchdog_startup_grace: Duration,
+    #[cfg(feature = "esp32")]
+    deep_sleep_cycle: Option<DeepSleepCycle>,
     network: Option<Box<dyn Network>>,
 }
 impl<Storage, C, M> ViamServer<Storage, C, M>
@@ -434,6 +967,7 @@ where
     M: Mdns,
 {
     pub fn run_forever(&mut self) -> ! {
+        crate::common::log::install_panic_hook();
         #[cfg(feature = "esp32")]
         {
             // set the TWDT to expire after 3 minutes
@@ -442,16 +976,27 @@ where
             })
             .unwrap();
 
-            // Register the current task on the TWDT. The TWDT runs in the IDLE Task.
-            crate::esp32::esp_idf_svc::sys::esp!(unsafe {
-                crate::esp32::esp_idf_svc::sys::esp_task_wdt_add(
-                    crate::esp32::esp_idf_svc::sys::xTaskGetCurrentTaskHandle(),
-                )
-            })
-            .unwrap();
+            if let Err(err) = crate::esp32::utils::set_alloc_failure_handler(
+                crate::esp32::utils::log_alloc_failure,
+            ) {
+                log::warn!(
+                    "couldn't register the allocation failure handler: {:?}",
+                    err
+                );
+            }
 
+            let grace = self.watchdog_startup_grace;
+            if grace.is_zero() {
+                Self::add_to_task_watchdog();
+            }
             self.executor
-                .spawn(async {
+                .spawn(async move {
+                    if !grace.is_zero() {
+                        log::info!("task watchdog enabled in {:?}", grace);
+                        Timer::after(grace).await;
+                        // executor tasks run on the task calling run_forever
+                        Self::add_to_task_watchdog();
+                    }
                     loop {
                         Timer::after(Duration::from_secs(90)).await;
                         unsafe { crate::esp32::esp_idf_svc::sys::esp_task_wdt_reset() };
@@ -463,8 +1008,83 @@ where
         exec.block_on(Box::pin(self.run()));
     }
 
+    // Register the current task on the TWDT. The TWDT runs in the IDLE Task.
+    #[cfg(feature = "esp32")]
+    fn add_to_task_watchdog() {
+        crate::esp32::esp_idf_svc::sys::esp!(unsafe {
+            crate::esp32::esp_idf_svc::sys::esp_task_wdt_add(
+                crate::esp32::esp_idf_svc::sys::xTaskGetCurrentTaskHandle(),
+            )
+        })
+        .unwrap();
+    }
+
+    // Swaps a cached WebRTC listener for a configuration serving the stored certificate, or a
+    // newly generated one when storage doesn't hold a valid certificate
+    fn load_webrtc_configuration(&mut self) {
+        let WebRtcListener::Cached(generate, configure) =
+            std::mem::replace(&mut self.webrtc_configuration, WebRtcListener::Empty)
+        else {
+            return;
+        };
+        let stored = if self.storage.has_webrtc_certificate() {
+            self.storage
+                .get_webrtc_certificate()
+                .inspect_err(|e| log::warn!("couldn't read the stored WebRTC certificate: {:?}", e))
+                .ok()
+                .filter(|cert| {
+                    let expired = cert.is_expired();
+                    if expired {
+                        log::info!("the stored WebRTC certificate expired, replacing it");
+                    }
+                    !expired
+                })
+        } else {
+            None
+        };
+        let cert = match stored {
+            Some(cert) => cert,
+            None => {
+                let Some(cert) = generate() else {
+                    log::error!("couldn't generate a WebRTC certificate, WebRTC is disabled");
+                    return;
+                };
+                if let Err(e) = self.storage.store_webrtc_certificate(cert.clone()) {
+                    log::warn!("couldn't store the WebRTC certificate: {:?}", e);
+                }
+                cert
+            }
+        };
+        self.webrtc_configuration =
+            WebRtcListener::WebRtc(configure(Rc::new(Box::new(cert) as Box<dyn Certificate>)));
+    }
+
     pub(crate) async fn run(&mut self) -> ! {
         self.storage.log_space_diagnostic();
+        crate::common::log::report_previous_panic();
+        let local_only = self.local_only.take();
+        let sensor_only = self.sensor_only && local_only.is_none();
+        if self.sensor_only && !sensor_only {
+            log::warn!("sensor only mode can't be used in local only mode, ignoring it");
+        }
+        if sensor_only {
+            // the robot server still runs to watch the network, it just has nothing to accept
+            log::info!("running in sensor only mode, local connections will not be served");
+            self.http2_server = HTTP2Server::Empty;
+            self.webrtc_configuration = WebRtcListener::Empty;
+        } else {
+            self.load_webrtc_configuration();
+        }
+        #[cfg(feature = "native")]
+        if let Some(address) = self.metrics_address.take() {
+            match Async::<TcpListener>::bind(address) {
+                Ok(listener) => self
+                    .executor
+                    .spawn(crate::common::metrics::serve_metrics(listener))
+                    .detach(),
+                Err(e) => log::error!("couldn't serve metrics on {}: {:?}", address, e),
+            }
+        }
         // The first step is to check whether or not credentials are populated in
         // storage. If not, we should go straight to provisioning.
         //
@@ -476,7 +1096,8 @@ where
         //| true             | false            | true             |
         //| true             | true             | false            |
         //+------------------+------------------+------------------+
-        if !self.storage.has_robot_credentials()
+        // In local only mode robot credentials are not needed
+        if (local_only.is_none() && !self.storage.has_robot_credentials())
             || self
                 .wifi_manager
                 .as_ref()
@@ -487,14 +1108,30 @@ where
         }
 
         // Since provisioning was run and completed, credentials are properly populated
-        // if wifi manager is configured loop forever until wifi is connected
+        // if wifi manager is configured loop forever until wifi is connected, trying the
+        // stored networks in order of priority
         if let Some(wifi) = self.wifi_manager.as_ref().as_ref() {
-            while let Err(err) = wifi
-                .set_sta_mode(self.storage.get_wifi_credentials().unwrap())
-                .await
-            {
-                log::error!("couldn't connect to wifi reason {:?}", err);
-                let _ = Timer::after(Duration::from_secs(2)).await;
+            let mut networks = vec![self.storage.get_wifi_credentials().unwrap()];
+            networks.extend(
+                self.storage
+                    .get_fallback_wifi_credentials()
+                    .inspect_err(|err| {
+                        log::error!("couldn't read the fallback wifi networks reason {:?}", err)
+                    })
+                    .unwrap_or_default(),
+            );
+            for network in networks.iter().cycle() {
+                match wifi.set_sta_mode(network.clone()).await {
+                    Ok(()) => break,
+                    Err(err) => {
+                        log::error!(
+                            "couldn't connect to wifi {} reason {:?}",
+                            network.wifi_ssid(),
+                            err
+                        );
+                        let _ = Timer::after(Duration::from_secs(2)).await;
+                    }
+                }
             }
         }
 
@@ -503,82 +1140,124 @@ where
             |network| network.as_network(),
         );
 
-        let robot_creds = self.storage.get_robot_credentials().unwrap();
-        let app_address = self
-            .storage
-            .get_app_address()
-            .or_else(|_| {
-                let _ = self.storage.store_app_address("https://app.viam.com:443");
-                self.storage.get_app_address()
-            })
-            .unwrap();
+        let robot_creds = if local_only.is_some() {
+            self.storage.get_robot_credentials().unwrap_or_default()
+        } else {
+            self.storage.get_robot_credentials().unwrap()
+        };
 
         // attempt to instantiate an app client
         // if we have an unauthenticated or permission denied error, we erase the creds
         // and restart
         // otherwise we assume some network layer error and attempt to start the robot from cached
         // data
+        let app_client = if local_only.is_some() {
+            log::info!("running in local only mode, app will not be contacted");
+            None
+        } else {
+            let app_address = self
+                .storage
+                .get_app_address()
+                .or_else(|_| {
+                    let _ = self.storage.store_app_address("https://app.viam.com:443");
+                    self.storage.get_app_address()
+                })
+                .unwrap();
 
-        let app_client = self
-            .connect_to_app()
-            .await
-            .inspect_err(|error| {
-                if error.is_permission_denied() || error.is_unauthenticated() {
-                    let _ = self.storage.reset_robot_credentials().inspect_err(|err| {
-                        log::error!("error {:?} while erasing credentials", err)
-                    });
-                    let _ = self.storage.reset_robot_configuration().inspect_err(|err| {
-                        log::error!("error {:?} while erasing configuration", err)
-                    });
-                    #[cfg(not(test))]
-                    panic!("erased credentials restart robot"); // TODO bubble up error and go back in provisioning
-                }
-                log::error!("couldn't connect to {} reason {:?}", app_address, error);
-            })
-            .ok();
+            self.connect_to_app()
+                .await
+                .inspect_err(|error| {
+                    if error.is_permission_denied() || error.is_unauthenticated() {
+                        let _ = self.storage.reset_robot_credentials().inspect_err(|err| {
+                            log::error!("error {:?} while erasing credentials", err)
+                        });
+                        let _ = self.storage.reset_robot_configuration().inspect_err(|err| {
+                            log::error!("error {:?} while erasing configuration", err)
+                        });
+                        #[cfg(not(test))]
+                        panic!("erased credentials restart robot"); // TODO bubble up error and go back in provisioning
+                    }
+                    log::error!("couldn't connect to {} reason {:?}", app_address, error);
+                })
+                .ok()
+        };
 
         // The next step is to build the robot based on the config retrieved online or from storage. Defaulting to an empty
         // robot if neither are available
         // If we are offline viam server will not start webrtc listening (AppClient wil not be constructed)
         // However we are still able to connect locally (H2) and we should cache data if the data manager exists.
         // is_connected only tells us whether or not we are on a network
-        let config = match app_client.as_ref() {
-            Some(app) => app
-                .get_app_config(Some(network.get_ip()))
-                .await
-                .inspect_err(|err| {
-                    log::error!(
-                        "couldn't get config, will default to cached config reason {:?}",
-                        err
-                    )
-                })
-                .ok(),
-            None => None,
-        };
+        let (config, build_time, config_source) = if let Some(local_only) = local_only.as_ref() {
+            (local_only.config.clone(), None, ConfigSource::LocalOnly)
+        } else {
+            let config = match app_client.as_ref() {
+                Some(app) => app
+                    .get_app_config(Some(network.get_ip()))
+                    .or(async {
+                        Timer::after(self.initial_config_timeout).await;
+                        Err(AppClientError::AppClientRequestTimeout)
+                    })
+                    .await
+                    .inspect_err(|err| {
+                        log::error!(
+                            "couldn't get config, will default to cached config reason {:?}",
+                            err
+                        )
+                    })
+                    .ok(),
+                None => None,
+            };
+
+            let pinned_config = if self.pin_cached_config {
+                self.storage.get_robot_configuration().ok()
+            } else {
+                None
+            };
 
-        let (config, build_time) = config.map_or_else(
-            || {
+            if let Some(pinned_config) = pinned_config {
+                log::warn!("running the pinned cached config, changes made in app are ignored");
                 (
-                    self.storage
-                        .get_robot_configuration()
-                        .ok() //can inspect and report empty robot will be constructed
-                        .map_or(Box::default(), Box::new),
-                    None,
+                    Box::new(pinned_config),
+                    config.and_then(|resp| resp.1),
+                    ConfigSource::Pinned,
                 )
-            },
-            |resp| (resp.0.config.map_or(Box::default(), Box::new), resp.1),
-        );
+            } else {
+                let (config, build_time, config_source) = config.map_or_else(
+                    || {
+                        // empty robot will be constructed without a cached config
+                        self.storage
+                            .get_robot_configuration()
+                            .ok()
+                            .map_or((Box::default(), None, ConfigSource::Empty), |config| {
+                                (Box::new(config), None, ConfigSource::Cache)
+                            })
+                    },
+                    |resp| {
+                        (
+                            resp.0.config.map_or(Box::default(), Box::new),
+                            resp.1,
+                            ConfigSource::App,
+                        )
+                    },
+                );
 
-        if let Err(err) = self.storage.store_robot_configuration(&config) {
-            log::error!("couldn't store the robot configuration reason {:?}", err);
-        }
+                if let Err(err) = self.storage.store_robot_configuration(&config) {
+                    log::error!("couldn't store the robot configuration reason {:?}", err);
+                }
+                (config, build_time, config_source)
+            }
+        };
 
-        let config_monitor_task = Box::new(ConfigMonitor::new(
-            config.clone(),
-            self.storage.clone(),
-            || std::process::exit(0),
-        ));
-        self.app_client_tasks.push(config_monitor_task);
+        // with a pinned config, config changes are never looked for
+        if local_only.is_none() && !self.pin_cached_config {
+            let config_monitor_task = Box::new(
+                ConfigMonitor::new(config.clone(), self.storage.clone(), || {
+                    std::process::exit(0)
+                })
+                .with_period(self.config_poll_interval),
+            );
+            self.app_client_tasks.push(config_monitor_task);
+        }
 
         #[cfg(feature = "ota")]
         {
@@ -615,15 +1294,33 @@ where
             }
         }
 
-        let mut robot = LocalRobot::from_cloud_config(
+        let (mut robot, build_error) = match LocalRobot::from_cloud_config(
             self.executor.clone(),
             robot_creds.robot_id.clone(),
             &config,
             &mut self.component_registry,
             build_time,
-        )
-        .inspect_err(|err| log::error!("couldn't build the robot reason {:?}", err))
-        .unwrap_or_default();
+        ) {
+            Ok(robot) => (robot, None),
+            Err(err) => {
+                log::error!("couldn't build the robot reason {:?}", err);
+                (LocalRobot::default(), Some(err.to_string()))
+            }
+        };
+
+        #[cfg(feature = "ota")]
+        if let Some(service) = config
+            .services
+            .iter()
+            .find(|&service| service.model == *ota::OTA_MODEL_TRIPLET)
+        {
+            robot.insert_generic_component(
+                service.name.clone(),
+                Arc::new(Mutex::new(ota::OtaCommands::new(self.storage.clone()))),
+            );
+        }
+
+        StartupReport::new(&robot, network, config_source, build_error).log();
 
         self.app_client_tasks
             .append(&mut robot.get_periodic_app_client_tasks());
@@ -631,37 +1328,38 @@ where
         let robot = Arc::new(Mutex::new(robot));
 
         if self.http2_server.has_http2_server() && !self.http2_server_insecure {
-            // Try to obtain and store a fresh TLS certificate. If this fails or we cannot reach
-            // app, then we'll end up falling back on whatever TLS certificate was cached. Note:
-            // we're using "if let Some(...) = ..." over calling map on option because a
-            // future cannot be awaited inside a closure (and trying to use OptionFuture or block_on felt messier)
-            let certs = if let Some(app) = app_client.as_ref() {
-                app.get_certificates()
-                    .await
-                    .map(|cert_resp| {
-                        let cert: TlsCertificate = cert_resp.into();
-                        match self.storage.store_tls_certificate(cert.clone()) {
-                            Ok(_) => {
-                                log::debug!("stored TLS certificate received by app");
-                            }
-                            Err(err) => {
-                                log::error!("error storing TLS cert: {:?}", err);
-                            }
-                        }
-                        // even if we fail to store the certificate, proceed
-                        // with the valid certificate obtained by app
-                        Some(cert)
-                    })
-                    .ok()
+            // Try to obtain and store a fresh TLS certificate, retrying a few times. If this fails
+            // or we cannot reach app, then we'll end up falling back on whatever TLS certificate
+            // was cached.
+            let (certs, reason) = if let Some(local_only) = local_only.as_ref() {
+                (Some(local_only.certificate.clone()), None)
             } else {
-                log::info!("Failed to obtain certificates from app, will attempt to load any stored certificates");
-                Some(self.storage.get_tls_certificate().ok())
-            }
-            .flatten();
-
-            match certs {
-                None => {
-                    log::error!("no TLS certificates found in storage or after contacting app, disabling HTTP2 server");
+                let fetched = match app_client.as_ref() {
+                    Some(app) => self
+                        .fetch_certificates(
+                            app,
+                            CERTIFICATE_FETCH_ATTEMPTS,
+                            CERTIFICATE_FETCH_BACKOFF,
+                        )
+                        .await
+                        .map_err(|err| err.to_string()),
+                    None => Err("app is not connected".to_owned()),
+                };
+                match fetched {
+                    Ok(cert) => (Some(cert), None),
+                    Err(err) => {
+                        log::info!("Failed to obtain certificates from app, will attempt to load any stored certificates");
+                        (self.storage.get_tls_certificate().ok(), Some(err))
+                    }
+                }
+            };
+
+            match certs {
+                None => {
+                    log::error!(
+                        "HTTP2 SERVER DISABLED: no TLS certificate could be obtained from app ({}) and none is stored, the local gRPC API is unavailable and only WebRTC connections will be served",
+                        reason.unwrap_or_default()
+                    );
                     // At no point were we ever able to obtain a valid TLS certificate, so we disable HTTP2
                     let _ = std::mem::replace(&mut self.http2_server, HTTP2Server::Empty);
                 }
@@ -678,6 +1376,15 @@ where
 
         self.storage.log_space_diagnostic();
 
+        let api_key = match (local_only.as_ref(), self.local_api_key.as_ref()) {
+            (Some(_), Some(key)) => Some(Arc::from(key.as_str())),
+            (None, Some(_)) => {
+                log::warn!("local api key is only enforced in local only mode, ignoring it");
+                None
+            }
+            _ => None,
+        };
+
         let (tx, rx) = async_channel::bounded(1);
 
         let mut inner = RobotServer {
@@ -688,11 +1395,17 @@ where
             mdns: &self.mdns,
             webrtc_signaling: rx,
             webrtc_config: &self.webrtc_configuration,
+            local_ip: LocalIpWatch::new(network.get_ip()),
             network,
-            incomming_connection_manager: IncomingConnectionManager::new(
+            incomming_connection_manager: IncomingConnectionManager::with_runtime_limit(
                 self.max_concurrent_connections,
+                ViamServerBuilder::<Storage, WantsNetwork>::get_max_runtime_concurrent_connections(
+                ),
             ),
             robot_config: &config,
+            api_key,
+            tcp_keepalive: self.tcp_keepalive.as_ref(),
+            mdns_txt_records: &self.mdns_txt_records,
             #[cfg(feature = "local-signaling")]
             local_signaling_server: Some(Arc::new(SignalingServer::new(
                 self.executor.clone(),
@@ -702,18 +1415,30 @@ where
             local_signaling_server: None,
         };
 
-        if let Some(cfg) = config.cloud.as_ref() {
-            self.app_client_tasks
-                .push(Box::new(SignalingTask::new(tx.clone(), cfg.fqdn.clone())));
-            self.app_client_tasks
-                .push(Box::new(SignalingTask::new(tx.clone(), cfg.fqdn.clone())));
+        // in sensor only mode WebRTC connections aren't signaled
+        if local_only.is_none() && !sensor_only {
+            if let Some(cfg) = config.cloud.as_ref() {
+                self.app_client_tasks
+                    .push(Box::new(SignalingTask::new(tx.clone(), cfg.fqdn.clone())));
+                self.app_client_tasks
+                    .push(Box::new(SignalingTask::new(tx.clone(), cfg.fqdn.clone())));
+            }
         }
 
-        let mut tasks: FuturesUnordered<_> = FuturesUnordered::new();
-        tasks.push(Either::Right(Box::pin(
-            self.run_app_client_tasks(app_client),
+        let mut tasks: FuturesUnordered<
+            Pin<Box<dyn Future<Output = Result<(), errors::ServerError>> + '_>>,
+        > = FuturesUnordered::new();
+        if local_only.is_none() {
+            tasks.push(Box::pin(self.run_app_client_tasks(app_client)));
+        }
+        tasks.push(Box::pin(futures_lite::future::or(
+            inner.run(),
+            self.wait_for_factory_reset(),
         )));
-        tasks.push(Either::Left(Box::pin(inner.run())));
+        #[cfg(feature = "esp32")]
+        if let Some(cycle) = self.deep_sleep_cycle {
+            tasks.push(Box::pin(cycle.run()));
+        }
 
         while let Some(ret) = tasks.next().await {
             log::error!("task ran returned {:?}", ret);
@@ -722,18 +1447,98 @@ where
         unreachable!()
     }
 
+    // Wait for a factory reset request (see `FactoryReset`), erase what ties the device to its
+    // robot and restart into provisioning
+    async fn wait_for_factory_reset(&self) -> Result<(), errors::ServerError> {
+        while !self.factory_reset.take() {
+            Timer::after(FACTORY_RESET_CHECK_INTERVAL).await;
+        }
+        log::warn!("factory reset requested, erasing robot credentials and configuration");
+        let _ = self
+            .storage
+            .reset_robot_credentials()
+            .inspect_err(|err| log::error!("error {:?} while erasing credentials", err));
+        let _ = self
+            .storage
+            .reset_robot_configuration()
+            .inspect_err(|err| log::error!("error {:?} while erasing configuration", err));
+        let _ = self
+            .storage
+            .reset_tls_certificate()
+            .inspect_err(|err| log::error!("error {:?} while erasing tls certificate", err));
+        // give the response to the DoCommand a chance to reach the client
+        Timer::after(FACTORY_RESET_CHECK_INTERVAL).await;
+        log::warn!("restarting into provisioning mode");
+        (self.restart_hook)();
+        Ok(())
+    }
+
+    // Get a TLS certificate from app and store it, making up to `attempts` attempts separated by
+    // `backoff` (doubled after each attempt)
+    async fn fetch_certificates(
+        &self,
+        app: &AppClient,
+        attempts: u32,
+        mut backoff: Duration,
+    ) -> Result<TlsCertificate, AppClientError> {
+        let mut attempt = 1;
+        let cert_resp = loop {
+            match app.get_certificates().await {
+                Ok(cert_resp) => break cert_resp,
+                Err(err) if attempt < attempts => {
+                    log::warn!(
+                        "couldn't get TLS certificates from app (attempt {}/{}), retrying in {:?} reason {:?}",
+                        attempt,
+                        attempts,
+                        backoff,
+                        err
+                    );
+                    Timer::after(backoff).await;
+                    backoff *= 2;
+                    attempt += 1;
+                }
+                Err(err) => return Err(err),
+            }
+        };
+        let cert: TlsCertificate = cert_resp.into();
+        match self.storage.store_tls_certificate(cert.clone()) {
+            Ok(_) => {
+                log::debug!("stored TLS certificate received by app");
+            }
+            Err(err) => {
+                log::error!("error storing TLS cert: {:?}", err);
+            }
+        }
+        // even if we fail to store the certificate, proceed
+        // with the valid certificate obtained by app
+        Ok(cert)
+    }
+
     async fn connect_to_app(&self) -> Result<AppClient, AppClientError> {
         let robot_creds = self.storage.get_robot_credentials().unwrap();
         let app_uri = self
             .storage
             .get_app_address()
             .unwrap_or("https://app.viam.com:443".parse::<Uri>().unwrap());
+        check_app_reachability(
+            &app_uri,
+            self.blocking_pool.as_deref(),
+            &self.task_config,
+            &self.executor,
+        )
+        .await?;
         let app_client_io = self
             .http2_connector
             .connect_to(&app_uri)
             .map_err(AppClientError::AppClientIoError)?
             .await
-            .map_err(AppClientError::AppClientIoError)?;
+            .map_err(|e| {
+                if app_uri.scheme_str() == Some("http") {
+                    AppClientError::AppClientIoError(e)
+                } else {
+                    AppClientError::AppTlsError(app_uri.host().unwrap_or_default().to_owned(), e)
+                }
+            })?;
         let grpc_client = GrpcClient::new(app_client_io, self.executor.clone(), app_uri)
             .await
             .map_err(AppClientError::AppGrpcClientError)?;
@@ -749,7 +1554,7 @@ where
         &self,
         mut app_client: Option<AppClient>,
     ) -> Result<(), errors::ServerError> {
-        let wait = Duration::from_secs(1); // should do exponential back off
+        let mut backoff = self.app_reconnect_backoff;
         loop {
             if let Some(app_client) = app_client {
                 let mut app_client_tasks: FuturesUnordered<AppClientTaskRunner> =
@@ -773,7 +1578,7 @@ where
             // the only way to reach here is either we had a None passed (app_client wasn't connected at boot)
             // or an error was reported by an underlying task which means that app client
             // is considered gone
-            let _ = Timer::after(wait).await;
+            let _ = Timer::after(backoff.current).await;
             app_client = self
                 .connect_to_app()
                 .await
@@ -788,9 +1593,14 @@ where
                         #[cfg(not(test))]
                         panic!("erased credentials restart robot"); // TODO bubble up error and go back in provisioning
                     }
-                    log::error!("couldn't connect to signaling server, reason {:?}", error);
+                    log::error!(
+                        "couldn't connect to signaling server, reason {:?}, retrying in {:?}",
+                        error,
+                        backoff.after_failure()
+                    );
                 })
                 .ok();
+            backoff.update(app_client.is_some());
         }
     }
     // I am adding provisioning in the main flow of viamserver
@@ -799,8 +1609,10 @@ where
     // case.
     async fn provision(&self) {
         let mut last_error = None;
+        let ap = self.provisioning_ap.clone().unwrap_or_default();
         if let Some(wifi) = self.wifi_manager.as_ref() {
-            while let Err(err) = wifi.set_ap_sta_mode(WifiApConfiguration::default()).await {
+            log::info!("Provisioning SSID: {}", ap.ssid);
+            while let Err(err) = wifi.set_ap_sta_mode(ap.clone()).await {
                 log::error!("couldn't start AP mode reason {:?}", err);
                 let _ = Timer::after(Duration::from_secs(2)).await;
             }
@@ -811,6 +1623,7 @@ where
             self.storage.clone(),
             last_error.take(),
             self.wifi_manager.clone(),
+            ap.captive_portal,
             &self.mdns,
         )
         .await
@@ -836,17 +1649,113 @@ struct RobotServer<'a, M> {
     http2_server_port: u16,
     webrtc_signaling: Receiver<Box<WebRtcSignalingChannel>>,
     network: &'a dyn Network,
+    local_ip: LocalIpWatch,
     incomming_connection_manager: IncomingConnectionManager,
     robot_config: &'a RobotConfig,
+    api_key: Option<Arc<str>>,
+    tcp_keepalive: Option<&'a TcpKeepalive>,
+    mdns_txt_records: &'a [(String, String)],
     #[allow(dead_code)]
     local_signaling_server: Option<Arc<SignalingServer>>,
 }
 
+/// TCP keepalive settings of accepted connections: a peer is considered gone after `idle`
+/// without traffic followed by `retries` unanswered probes sent every `interval`
+#[derive(Clone, Debug)]
+pub struct TcpKeepalive {
+    pub idle: Duration,
+    pub interval: Duration,
+    pub retries: u32,
+}
+
+impl Default for TcpKeepalive {
+    fn default() -> Self {
+        Self {
+            idle: Duration::from_secs(30),
+            interval: Duration::from_secs(5),
+            retries: 3,
+        }
+    }
+}
+
+impl TcpKeepalive {
+    pub fn with_idle(mut self, idle: Duration) -> Self {
+        self.idle = idle;
+        self
+    }
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+    pub fn with_retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    #[cfg(not(target_os = "espidf"))]
+    fn apply(&self, stream: &TcpStream) -> std::io::Result<()> {
+        let keepalive = socket2::TcpKeepalive::new()
+            .with_time(self.idle)
+            .with_interval(self.interval)
+            .with_retries(self.retries);
+        socket2::SockRef::from(stream).set_tcp_keepalive(&keepalive)
+    }
+
+    // lwip options are set directly, socket2 doesn't expose them all on ESP-IDF
+    #[cfg(target_os = "espidf")]
+    fn apply(&self, stream: &TcpStream) -> std::io::Result<()> {
+        use crate::esp32::esp_idf_svc::sys::{
+            setsockopt, IPPROTO_TCP, SOL_SOCKET, SO_KEEPALIVE, TCP_KEEPCNT, TCP_KEEPIDLE,
+            TCP_KEEPINTVL,
+        };
+        use std::os::fd::AsRawFd;
+        let fd = stream.as_raw_fd();
+        let set = |level: u32, option: u32, value: u32| {
+            let value = value as std::ffi::c_int;
+            let ret = unsafe {
+                setsockopt(
+                    fd,
+                    level as _,
+                    option as _,
+                    &value as *const _ as *const std::ffi::c_void,
+                    std::mem::size_of_val(&value) as _,
+                )
+            };
+            if ret != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            Ok(())
+        };
+        set(SOL_SOCKET, SO_KEEPALIVE, 1)?;
+        set(IPPROTO_TCP, TCP_KEEPIDLE, self.idle.as_secs().max(1) as u32)?;
+        set(
+            IPPROTO_TCP,
+            TCP_KEEPINTVL,
+            self.interval.as_secs().max(1) as u32,
+        )?;
+        set(IPPROTO_TCP, TCP_KEEPCNT, self.retries)
+    }
+}
+
 pub(crate) enum IncomingConnection {
     HTTP2Connection(std::io::Result<(Async<TcpStream>, SocketAddr)>),
     WebRTCConnection(Result<Box<WebRtcSignalingChannel>, WebRtcError>),
+    // Not a connection, periodically wakes up the server to look for a change of IP address
+    NetworkCheck,
 }
 
+/// Default time the robot config from app is waited for at startup, see
+/// [`ViamServerBuilder::with_initial_config_timeout`]
+pub const DEFAULT_INITIAL_CONFIG_TIMEOUT: Duration = Duration::from_secs(30);
+
+// Number of attempts made to get TLS certificates from app at startup, and delay before the
+// first retry (doubled after each attempt)
+const CERTIFICATE_FETCH_ATTEMPTS: u32 = 3;
+const CERTIFICATE_FETCH_BACKOFF: Duration = Duration::from_secs(1);
+
+// How often the local IP address is compared against the one reported by the network
+const NETWORK_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
 impl<'a, M> RobotServer<'a, M>
 where
     M: Mdns,
@@ -857,6 +1766,7 @@ where
     ) -> Task<Result<(), errors::ServerError>> {
         let exec = self.executor.clone();
         let robot = self.robot.clone();
+        let api_key = self.api_key.clone();
 
         // If the connection manager has a low limit on the number of
         // concurrent connections, don't enable local signaling. This
@@ -875,9 +1785,13 @@ where
                 None
             });
 
+        let connection = ConnectionGuard::new();
         self.executor.spawn(async move {
-            #[allow(unused_mut)]
+            let _connection = connection;
             let mut srv = GrpcServer::new(robot, GrpcBody::new());
+            if let Some(api_key) = api_key {
+                srv.require_api_key(api_key);
+            }
             #[cfg(feature = "local-signaling")]
             if let Some(ss) = ss {
                 srv.register_signaling_server(ss);
@@ -899,33 +1813,62 @@ where
         match incoming {
             IncomingConnection::HTTP2Connection(conn) => {
                 if let HTTP2Server::HTTP2Connector(h) = self.http2_server {
-                    if self.incomming_connection_manager.get_lowest_prio() < u32::MAX {
-                        let stream = conn?;
-                        // we will have to wait for the tls context to be established before moving forward
-                        let io = h.accept_connection(stream.0)?.await?;
-                        let task = self.serve_http2_connection(io);
-                        self.incomming_connection_manager
-                            .insert_new_conn(task, u32::MAX)
-                            .await;
+                    let stream = conn?;
+                    if let Some(keepalive) = self.tcp_keepalive {
+                        if let Err(e) = keepalive.apply(stream.0.get_ref()) {
+                            log::warn!("failed to enable TCP keepalive on {}: {}", stream.1, e);
+                        }
+                    }
+                    // refuse the connection by closing it before the TLS handshake when all the
+                    // slots are taken by connections it can't replace
+                    if !self.incomming_connection_manager.make_room(u32::MAX).await {
+                        metrics::connection_refused();
+                        log::warn!(
+                            "refusing connection from {}: too many active connections",
+                            stream.1
+                        );
+                        return Ok(());
                     }
+                    // we will have to wait for the tls context to be established before moving forward
+                    let io = h.accept_connection(stream.0)?.await?;
+                    let task = self.serve_http2_connection(io);
+                    self.incomming_connection_manager
+                        .insert_new_conn(task, u32::MAX)
+                        .await;
                 }
             }
 
             IncomingConnection::WebRTCConnection(conn) => {
-                let sig = conn.map_err(|e| errors::ServerError::Other(e.into()))?;
-                let ip = self.network.get_ip();
+                let mut sig = conn.map_err(|e| errors::ServerError::Other(e.into()))?;
+                let _ = self.local_ip.set(self.network.get_ip());
                 if let WebRtcListener::WebRtc(conf) = self.webrtc_config {
+                    // a slot is freed (or the offer refused) before the DTLS context and the
+                    // connection state are allocated
+                    if !self
+                        .incomming_connection_manager
+                        .make_room(sig.offer().priority())
+                        .await
+                    {
+                        metrics::connection_refused();
+                        sig.refuse_offer().await?;
+                        return Err(WebRtcError::NoConnectionAvailable().into());
+                    }
                     let mut api = WebRtcApi::new(
                         self.executor.clone(),
                         sig,
                         conf.cert.clone(),
-                        ip,
+                        self.local_ip.clone(),
+                        conf.stun_servers.clone(),
                         conf.dtls.make()?,
                     );
                     let (answer, prio) = api.answer(0).await?;
                     let robot = self.robot.clone();
 
+                    let connection = ConnectionGuard::new();
+                    // the handshake runs in the connection task so other connections and network
+                    // checks keep being handled meanwhile
                     let task = self.executor.spawn(async move {
+                        let _connection = connection;
                         let mut conn = api.connect(answer, robot).await?;
                         conn.run().await
                     });
@@ -934,6 +1877,14 @@ where
                         .await;
                 }
             }
+            IncomingConnection::NetworkCheck => {
+                CONNECTIVITY.update(self.network);
+                let ip = self.network.get_ip();
+                // active WebRTC connections will notice the change and restart ICE
+                if self.local_ip.set(ip) {
+                    log::info!("local ip address changed to {}", ip);
+                }
+            }
         }
         Ok(())
     }
@@ -943,6 +1894,21 @@ where
             if let Some(cfg) = self.robot_config.cloud.as_ref() {
                 let mut mdns = self.mdns.borrow_mut();
                 let cfg: RobotCloudConfig = cfg.into();
+                // records set in the robot config replace the ones given to the builder
+                let config_records = txt_records_from_config(self.robot_config)
+                    .inspect_err(|e| log::error!("ignoring the configured mdns txt records: {}", e))
+                    .ok()
+                    .flatten();
+                let txt_records: Vec<(&str, &str)> = DEFAULT_TXT_RECORDS
+                    .into_iter()
+                    .chain(
+                        config_records
+                            .as_deref()
+                            .unwrap_or(self.mdns_txt_records)
+                            .iter()
+                            .map(|(k, v)| (k.as_str(), v.as_str())),
+                    )
+                    .collect();
                 mdns.set_hostname(&cfg.name)
                     .map_err(|e| errors::ServerError::Other(e.into()))?;
                 mdns.add_service(
@@ -950,7 +1916,7 @@ where
                     "_rpc",
                     "_tcp",
                     self.http2_server_port,
-                    &[("grpc", ""), ("webrtc", "")],
+                    &txt_records,
                 )
                 .map_err(|e| errors::ServerError::Other(e.into()))?;
                 mdns.add_service(
@@ -958,7 +1924,7 @@ where
                     "_rpc",
                     "_tcp",
                     self.http2_server_port,
-                    &[("grpc", ""), ("webrtc", "")],
+                    &txt_records,
                 )
                 .map_err(|e| errors::ServerError::Other(e.into()))?;
             }
@@ -1001,7 +1967,17 @@ where
                     })
                 };
 
-            let incoming = Box::pin(futures_lite::future::or(h2_conn, webrtc_conn)).await;
+            let network_check: Pin<Box<dyn Future<Output = IncomingConnection>>> =
+                Box::pin(async {
+                    Timer::after(NETWORK_CHECK_INTERVAL).await;
+                    IncomingConnection::NetworkCheck
+                });
+
+            let incoming = Box::pin(futures_lite::future::or(
+                futures_lite::future::or(h2_conn, webrtc_conn),
+                network_check,
+            ))
+            .await;
             if let Err(e) = self.serve_incoming_connection(incoming).await {
                 log::error!("failed to server incoming connection reason {:?}", e)
             }
@@ -1090,12 +2066,13 @@ impl Future for AppClientTaskRunner<'_> {
 #[cfg(test)]
 mod tests {
     use std::{
+        cell::RefCell,
         future::Future,
         net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream},
         pin::Pin,
         rc::Rc,
         sync::{
-            atomic::{AtomicI32, Ordering},
+            atomic::{AtomicBool, AtomicI32, Ordering},
             Arc,
         },
         time::Duration,
@@ -1105,11 +2082,15 @@ mod tests {
         common::{
             app_client::encode_request,
             conn::{
+                mdns::{Mdns, MdnsError},
                 network::{ExternallyManagedNetwork, Network},
                 server::WebRtcConfiguration,
-                viam::ViamServerBuilder,
+                viam::{FactoryReset, ViamServerBuilder},
+            },
+            credentials_storage::{
+                CachedWebRtcCertificate, RAMStorage, RobotConfigurationStorage, TlsCertificate,
+                WebRtcCertificateStorage,
             },
-            credentials_storage::{RAMStorage, RobotConfigurationStorage},
             exec::Executor,
             grpc::{GrpcBody, GrpcError, GrpcResponse, ServerError},
             log::LogUploadTask,
@@ -1160,6 +2141,13 @@ mod tests {
         config_fn: Option<Rc<Box<dyn Fn() -> RobotConfig>>>,
         log_fn: Option<&'static dyn Fn()>,
         auth_fn: Option<Rc<Box<dyn Fn(&AuthenticateRequest) -> bool>>>,
+        // authentication fails as if app was unavailable, after calling auth_fn
+        auth_unavailable: bool,
+        // called for each certificate request, which fails as if app was unavailable when it
+        // returns false
+        certificate_fn: Option<Rc<Box<dyn Fn() -> bool>>>,
+        // how long the config requests are held before being answered
+        config_delay: Option<Duration>,
     }
 
     impl AppServerInsecure {
@@ -1170,6 +2158,9 @@ mod tests {
                     return Err(ServerError::new(GrpcError::RpcPermissionDenied, None));
                 }
             }
+            if self.auth_unavailable {
+                return Err(ServerError::new(GrpcError::RpcUnavailable, None));
+            }
             let resp = AuthenticateResponse {
                 access_token: "fake".to_string(),
             };
@@ -1208,7 +2199,12 @@ mod tests {
             resp.encode(&mut buffer).unwrap();
             buffer.freeze()
         }
-        fn certificates(&self, _body: Bytes) -> Bytes {
+        fn certificates(&self, _body: Bytes) -> Result<Bytes, ServerError> {
+            if let Some(certificate_fn) = &self.certificate_fn {
+                if !certificate_fn() {
+                    return Err(ServerError::new(GrpcError::RpcUnavailable, None));
+                }
+            }
             let self_signed =
                 rcgen::generate_simple_self_signed(["localhost".to_string()]).unwrap();
             let tls_certificate = self_signed.serialize_pem().unwrap();
@@ -1223,7 +2219,7 @@ mod tests {
             buffer.put_u8(0);
             buffer.put_u32(len.try_into().unwrap());
             resp.encode(&mut buffer).unwrap();
-            buffer.freeze()
+            Ok(buffer.freeze())
         }
         fn get_config(&self) -> Bytes {
             let cfg = self
@@ -1250,10 +2246,18 @@ mod tests {
                 .to_bytes();
             let out = match parts.uri.path() {
                 "/proto.rpc.v1.AuthService/Authenticate" => self.authenticate(body.split_off(5))?,
-                "/viam.app.v1.RobotService/Certificate" => self.certificates(body.split_off(5)),
+                "/viam.app.v1.RobotService/Certificate" => self.certificates(body.split_off(5))?,
                 "/viam.app.v1.RobotService/Log" => self.log(body.split_off(5)),
                 "/viam.app.v1.RobotService/NeedsRestart" => self.needs_restart(body.split_off(5)),
-                "/viam.app.v1.RobotService/Config" => self.get_config(),
+                "/viam.app.v1.RobotService/Config" => {
+                    if let Some(delay) = self.config_delay {
+                        Timer::after(delay).await;
+                    }
+                    self.get_config()
+                }
+                "/viam.app.agent.v1.AgentDeviceService/DeviceAgentConfig" => {
+                    return Err(ServerError::new(GrpcError::RpcUnimplemented, None))
+                }
                 _ => panic!("unsupported uri {:?}", parts.uri.path()),
             };
             Ok(out)
@@ -1362,6 +2366,313 @@ mod tests {
         });
     }
 
+    #[test_log::test]
+    fn test_cached_webrtc_certificate() {
+        let _unused = global_network_test_lock();
+        let ram_storage = RAMStorage::new();
+        let generated = Rc::new(RefCell::new(0));
+
+        let load = |storage: RAMStorage, generated: Rc<RefCell<usize>>| {
+            let network = match local_ip_address::local_ip().expect("error parsing local IP") {
+                std::net::IpAddr::V4(ip) => ExternallyManagedNetwork::new(ip),
+                _ => panic!("oops expected ipv4"),
+            };
+            let mdns = NativeMdns::new("".to_owned(), network.get_ip()).unwrap();
+            let mut viam_server = ViamServerBuilder::new(storage);
+            viam_server.with_cached_webrtc_certificate(
+                Box::new(move || {
+                    *generated.borrow_mut() += 1;
+                    Some(CachedWebRtcCertificate::new(
+                        &WebRtcCertificate::new(),
+                        u64::MAX,
+                    ))
+                }),
+                Box::new(|cert| {
+                    let dtls = Box::new(NativeDtls::new(cert.clone()));
+                    WebRtcConfiguration::new(cert, dtls)
+                }),
+            );
+            let mut viam_server = viam_server.build(
+                NativeH2Connector::default(),
+                Executor::new(),
+                mdns,
+                Box::new(network),
+            );
+            viam_server.load_webrtc_configuration();
+            match &viam_server.webrtc_configuration {
+                super::WebRtcListener::WebRtc(conf) => conf.cert.get_fingerprint().to_string(),
+                _ => panic!("WebRTC should be configured"),
+            }
+        };
+
+        // first boot generates and stores a certificate
+        let fingerprint = load(ram_storage.clone(), generated.clone());
+        assert_eq!(*generated.borrow(), 1);
+        assert!(ram_storage.has_webrtc_certificate());
+        assert_eq!(
+            ram_storage
+                .get_webrtc_certificate()
+                .unwrap()
+                .get_fingerprint()
+                .to_string(),
+            fingerprint
+        );
+
+        // following boots reuse it
+        assert_eq!(load(ram_storage.clone(), generated.clone()), fingerprint);
+        assert_eq!(*generated.borrow(), 1);
+
+        // an expired certificate is replaced
+        let mut expired = ram_storage.get_webrtc_certificate().unwrap();
+        expired.not_after = 0;
+        ram_storage.store_webrtc_certificate(expired).unwrap();
+        assert_ne!(load(ram_storage.clone(), generated.clone()), fingerprint);
+        assert_eq!(*generated.borrow(), 2);
+        assert!(!ram_storage.get_webrtc_certificate().unwrap().is_expired());
+    }
+
+    #[test_log::test]
+    fn test_fetch_certificates_retries() {
+        let _unused = global_network_test_lock();
+        let ram_storage = RAMStorage::new();
+        let _ = ram_storage.store_app_address(LOCALHOST_URI);
+        let network = match local_ip_address::local_ip().expect("error parsing local IP") {
+            std::net::IpAddr::V4(ip) => ExternallyManagedNetwork::new(ip),
+            _ => panic!("oops expected ipv4"),
+        };
+        let creds = CloudConfig {
+            id: "test-certificates".to_string(),
+            secret: "".to_string(),
+            app_address: LOCALHOST_URI.to_owned(),
+        };
+        assert!(ram_storage.store_robot_credentials(creds).is_ok());
+        let mdns = NativeMdns::new("".to_owned(), network.get_ip()).unwrap();
+
+        let exec = Executor::new();
+        let viam_server = ViamServerBuilder::new(ram_storage.clone()).build(
+            NativeH2Connector::default(),
+            exec.clone(),
+            mdns,
+            Box::new(network),
+        );
+
+        // the first two certificate requests fail
+        let requests = Rc::new(AtomicI32::new(0));
+        let cloned_requests = requests.clone();
+        let app = AppServerInsecure {
+            certificate_fn: Some(Rc::new(Box::new(move || {
+                cloned_requests.fetch_add(1, Ordering::AcqRel) >= 2
+            }))),
+            ..Default::default()
+        };
+        let cloned_exec = exec.clone();
+        exec.block_on(async move {
+            let other_clone = cloned_exec.clone();
+            let _fake_server_task =
+                cloned_exec.spawn(async move { run_fake_app_server(other_clone, app).await });
+            // let the fake app start listening
+            futures_lite::future::yield_now().await;
+            let app = viam_server.connect_to_app().await.unwrap();
+
+            // gives up once all the attempts failed
+            assert!(viam_server
+                .fetch_certificates(&app, 2, Duration::from_millis(1))
+                .await
+                .is_err());
+            assert_eq!(requests.load(Ordering::Acquire), 2);
+            assert!(!ram_storage.has_tls_certificate());
+
+            // retries until a certificate is received, which is stored
+            requests.store(1, Ordering::Release);
+            let cert = viam_server
+                .fetch_certificates(&app, 3, Duration::from_millis(1))
+                .await
+                .unwrap();
+            assert_eq!(requests.load(Ordering::Acquire), 3);
+            assert_eq!(
+                ram_storage.get_tls_certificate().unwrap().certificate,
+                cert.certificate
+            );
+        });
+    }
+
+    #[test_log::test]
+    fn test_initial_config_timeout() {
+        let _unused = global_network_test_lock();
+        let ram_storage = RAMStorage::new();
+        let _ = ram_storage.store_app_address(LOCALHOST_URI);
+        let network = match local_ip_address::local_ip().expect("error parsing local IP") {
+            std::net::IpAddr::V4(ip) => ExternallyManagedNetwork::new(ip),
+            _ => panic!("oops expected ipv4"),
+        };
+        let creds = CloudConfig {
+            id: "test-config-timeout".to_string(),
+            secret: "".to_string(),
+            app_address: LOCALHOST_URI.to_owned(),
+        };
+        assert!(ram_storage.store_robot_credentials(creds).is_ok());
+        let cached_config = RobotConfig {
+            revision: "cached".to_owned(),
+            ..make_sample_config()
+        };
+        assert!(ram_storage
+            .store_robot_configuration(&cached_config)
+            .is_ok());
+        let mdns = NativeMdns::new("".to_owned(), network.get_ip()).unwrap();
+
+        let mut viam_server = ViamServerBuilder::new(ram_storage.clone());
+        viam_server
+            .with_http2_server(NativeH2Connector::default(), 12346)
+            .with_initial_config_timeout(Duration::from_millis(100));
+        let exec = Executor::new();
+        let mut viam_server = viam_server.build(
+            NativeH2Connector::default(),
+            exec.clone(),
+            mdns,
+            Box::new(network),
+        );
+
+        // app takes far longer than the timeout to send the config, the certificates requested
+        // once the robot is built show that the server went on without it
+        let certificate_requested = Rc::new(AtomicBool::new(false));
+        let cloned_certificate_requested = certificate_requested.clone();
+        let app = AppServerInsecure {
+            config_fn: Some(Rc::new(Box::new(|| RobotConfig {
+                revision: "app".to_owned(),
+                ..make_sample_config()
+            }))),
+            config_delay: Some(Duration::from_secs(60)),
+            certificate_fn: Some(Rc::new(Box::new(move || {
+                cloned_certificate_requested.store(true, Ordering::Release);
+                true
+            }))),
+            ..Default::default()
+        };
+        let cloned_exec = exec.clone();
+        exec.block_on(async move {
+            let other_clone = cloned_exec.clone();
+            let _fake_server_task =
+                cloned_exec.spawn(async move { run_fake_app_server(other_clone, app).await });
+            let _task = cloned_exec.spawn(async move {
+                viam_server.run().await;
+            });
+            async {
+                while !certificate_requested.load(Ordering::Acquire) {
+                    Timer::after(Duration::from_millis(50)).await;
+                }
+            }
+            .or(async {
+                Timer::after(Duration::from_secs(30)).await;
+                panic!("the server kept waiting for the config");
+            })
+            .await;
+            assert_eq!(
+                ram_storage.get_robot_configuration().unwrap().revision,
+                "cached"
+            );
+        });
+    }
+
+    #[test_log::test]
+    fn test_app_reconnect_backoff() {
+        let _unused = global_network_test_lock();
+        let ram_storage = RAMStorage::new();
+        let _ = ram_storage.store_app_address(LOCALHOST_URI);
+        let network = match local_ip_address::local_ip().expect("error parsing local IP") {
+            std::net::IpAddr::V4(ip) => ExternallyManagedNetwork::new(ip),
+            _ => panic!("oops expected ipv4"),
+        };
+
+        let creds = CloudConfig {
+            id: "test-backoff".to_string(),
+            secret: "".to_string(),
+            app_address: LOCALHOST_URI.to_owned(),
+        };
+        assert!(ram_storage.store_robot_credentials(creds).is_ok());
+
+        let mdns = NativeMdns::new("".to_owned(), network.get_ip());
+        assert!(mdns.is_ok());
+        let mdns = mdns.unwrap();
+        let cloned_ram_storage = ram_storage.clone();
+        let mut viam_server = ViamServerBuilder::new(ram_storage);
+        viam_server
+            .with_http2_server(NativeH2Connector::default(), 12346)
+            .with_max_concurrent_connection(2)
+            .with_http2_server_insecure(true)
+            .with_default_tasks()
+            .with_app_reconnect_backoff(Duration::from_millis(50), Duration::from_millis(200));
+
+        let exec = Executor::new();
+
+        let mut viam_server = viam_server.build(
+            NativeH2Connector::default(),
+            exec.clone(),
+            mdns,
+            Box::new(network),
+        );
+        let cloned_exec = exec.clone();
+
+        // every connection attempt is refused as if app was unavailable
+        let (attempts_tx, attempts_rx) = async_channel::unbounded();
+        let mut app = AppServerInsecure {
+            auth_unavailable: true,
+            ..Default::default()
+        };
+        app.auth_fn = Some(Rc::new(Box::new(move |_| {
+            let _ = attempts_tx.try_send(std::time::Instant::now());
+            true
+        })));
+        exec.block_on(async move {
+            let other_clone = cloned_exec.clone();
+            let _fake_server_task =
+                cloned_exec.spawn(async move { run_fake_app_server(other_clone, app).await });
+            let _task = cloned_exec.spawn(async move {
+                viam_server.run().await;
+            });
+            // the attempt at startup then the reconnections, each waiting twice as long as the
+            // previous one up to the maximum
+            let mut attempts = vec![];
+            for _ in 0..4 {
+                attempts.push(attempts_rx.recv().await.unwrap());
+            }
+            let delays: Vec<Duration> = attempts.windows(2).map(|w| w[1] - w[0]).collect();
+            for (delay, min) in delays.iter().zip([50, 100, 200]) {
+                assert!(*delay >= Duration::from_millis(min), "{:?}", delays);
+            }
+            // a transient failure keeps the credentials
+            assert!(cloned_ram_storage.has_robot_credentials());
+        });
+    }
+
+    #[test_log::test]
+    fn test_app_reconnect_backoff_delays() {
+        use super::AppReconnectBackoff;
+
+        let mut backoff = AppReconnectBackoff::new(Duration::from_secs(1), Duration::from_secs(5));
+        let mut delays = vec![backoff.current];
+        for _ in 0..4 {
+            backoff.update(false);
+            delays.push(backoff.current);
+        }
+        assert_eq!(delays, [1, 2, 4, 5, 5].map(Duration::from_secs).to_vec());
+        // a successful reconnection starts over from the initial delay
+        backoff.update(true);
+        assert_eq!(
+            backoff,
+            AppReconnectBackoff::new(Duration::from_secs(1), Duration::from_secs(5))
+        );
+
+        // a null delay would stay null, an inverted range never backs off
+        let mut backoff = AppReconnectBackoff::new(Duration::ZERO, Duration::ZERO);
+        assert_eq!(backoff.current, super::APP_RECONNECT_MIN_BACKOFF);
+        backoff.update(false);
+        assert_eq!(backoff.current, super::APP_RECONNECT_MIN_BACKOFF);
+        let mut backoff = AppReconnectBackoff::new(Duration::from_secs(5), Duration::from_secs(1));
+        assert_eq!(backoff.max, Duration::from_secs(5));
+        backoff.update(false);
+        assert_eq!(backoff.current, Duration::from_secs(5));
+    }
+
     #[test_log::test]
     // The goal of the test is to confirm that transient failure of the app client caused
     // by network issues (and not permission issues)
@@ -1471,6 +2782,214 @@ mod tests {
         });
     }
 
+    // Records the services added to it
+    #[derive(Clone, Default)]
+    struct RecordingMdns(Rc<RefCell<Vec<String>>>);
+
+    impl Mdns for RecordingMdns {
+        fn add_service(
+            &mut self,
+            instance_name: &str,
+            _: impl AsRef<str>,
+            _: impl AsRef<str>,
+            _: u16,
+            _: &[(&str, &str)],
+        ) -> Result<(), MdnsError> {
+            self.0.borrow_mut().push(instance_name.to_owned());
+            Ok(())
+        }
+        fn remove_service(
+            &mut self,
+            _: &str,
+            _: impl AsRef<str>,
+            _: impl AsRef<str>,
+        ) -> Result<(), MdnsError> {
+            Ok(())
+        }
+    }
+
+    #[test_log::test]
+    fn test_config_poll_interval() {
+        use crate::common::config_monitor::MIN_CONFIG_POLL_INTERVAL;
+
+        let mut builder = ViamServerBuilder::new(RAMStorage::new());
+        builder.with_config_poll_interval(Duration::from_secs(30));
+        assert_eq!(builder.config_poll_interval, Duration::from_secs(30));
+        // a null interval would poll app in a busy loop
+        builder.with_config_poll_interval(Duration::ZERO);
+        assert_eq!(builder.config_poll_interval, MIN_CONFIG_POLL_INTERVAL);
+    }
+
+    #[test_log::test]
+    fn test_sensor_only() {
+        let _unused = global_network_test_lock();
+        let ram_storage = RAMStorage::new();
+        let _ = ram_storage.store_app_address(LOCALHOST_URI);
+
+        let network = match local_ip_address::local_ip().expect("error parsing local IP") {
+            std::net::IpAddr::V4(ip) => ExternallyManagedNetwork::new(ip),
+            _ => panic!("oops expected ipv4"),
+        };
+
+        let creds = CloudConfig {
+            id: "test-sensor-only".to_string(),
+            secret: "".to_string(),
+            app_address: LOCALHOST_URI.to_owned(),
+        };
+        assert!(ram_storage.store_robot_credentials(creds).is_ok());
+
+        let mdns = RecordingMdns::default();
+        let services = mdns.0.clone();
+
+        // a pending factory reset is handled by the tasks started once the robot is built,
+        // after the local servers would have been advertised and bound
+        let factory_reset = FactoryReset::new();
+        factory_reset.request();
+        let (restarted_tx, restarted_rx) = async_channel::bounded(1);
+        let generated = Rc::new(AtomicI32::new(0));
+        let generated_cloned = generated.clone();
+
+        let mut viam_server = ViamServerBuilder::new(ram_storage.clone());
+        viam_server
+            .with_http2_server(NativeH2Connector::default(), 12346)
+            .with_cached_webrtc_certificate(
+                Box::new(move || {
+                    let _ = generated_cloned.fetch_add(1, Ordering::AcqRel);
+                    Some(CachedWebRtcCertificate::new(
+                        &WebRtcCertificate::new(),
+                        u64::MAX,
+                    ))
+                }),
+                Box::new(|cert| {
+                    let dtls = Box::new(NativeDtls::new(cert.clone()));
+                    WebRtcConfiguration::new(cert, dtls)
+                }),
+            )
+            .with_default_tasks()
+            .with_factory_reset(factory_reset)
+            .with_restart_hook(move || {
+                let _ = restarted_tx.try_send(());
+            })
+            .sensor_only();
+
+        let exec = Executor::new();
+
+        let mut viam_server = viam_server.build(
+            NativeH2Connector::default(),
+            exec.clone(),
+            mdns,
+            Box::new(network),
+        );
+        let cloned_exec = exec.clone();
+
+        let mut app = AppServerInsecure::default();
+        let shared_auth_counter = Rc::new(AtomicI32::new(0));
+        let shared_auth_counter_cloned = shared_auth_counter.clone();
+        app.auth_fn = Some(Rc::new(Box::new(move |req| {
+            assert!(req.entity.contains("test-sensor-only"));
+            let _ = shared_auth_counter_cloned.fetch_add(1, Ordering::AcqRel);
+            true
+        })));
+        app.config_fn = Some(Rc::new(Box::new(|| {
+            let mut cfg = make_sample_config();
+            if let Some(cloud) = cfg.cloud.as_mut() {
+                cloud.fqdn = "test-sensor.xxds65ui.viam.cloud".to_owned();
+                cloud.local_fqdn = "test-sensor.xxds65ui.viam.local.cloud".to_owned();
+            }
+            cfg
+        })));
+
+        exec.block_on(async move {
+            let other_clone = cloned_exec.clone();
+            let _fake_server_task =
+                cloned_exec.spawn(async move { run_fake_app_server(other_clone, app).await });
+            let _task = cloned_exec.spawn(async move {
+                viam_server.run().await;
+            });
+            restarted_rx.recv().await.unwrap();
+
+            // the robot is neither advertised nor served
+            assert!(services.borrow().is_empty());
+            let addr = SocketAddr::new(std::net::IpAddr::V4(Ipv4Addr::LOCALHOST), 12346);
+            assert!(Async::<TcpStream>::connect(addr).await.is_err());
+            // no WebRTC certificate was loaded or generated
+            assert_eq!(generated.load(Ordering::Acquire), 0);
+            assert!(!ram_storage.has_webrtc_certificate());
+
+            // while app is still contacted
+            assert_eq!(shared_auth_counter.load(Ordering::Acquire), 1);
+        });
+    }
+
+    #[test_log::test]
+    fn test_factory_reset() {
+        let _unused = global_network_test_lock();
+        let ram_storage = RAMStorage::new();
+        let _ = ram_storage.store_app_address(LOCALHOST_URI);
+
+        let network = match local_ip_address::local_ip().expect("error parsing local IP") {
+            std::net::IpAddr::V4(ip) => ExternallyManagedNetwork::new(ip),
+            _ => panic!("oops expected ipv4"),
+        };
+
+        let creds = CloudConfig {
+            id: "test-factory-reset".to_string(),
+            secret: "".to_string(),
+            app_address: LOCALHOST_URI.to_owned(),
+        };
+        assert!(ram_storage.store_robot_credentials(creds).is_ok());
+
+        let mdns = NativeMdns::new("".to_owned(), network.get_ip()).unwrap();
+
+        let factory_reset = FactoryReset::new();
+        let restarted = Rc::new(AtomicBool::new(false));
+        let restarted_cloned = restarted.clone();
+        let mut viam_server = ViamServerBuilder::new(ram_storage.clone());
+        viam_server
+            .with_http2_server(NativeH2Connector::default(), 12346)
+            .with_factory_reset(factory_reset.clone())
+            .with_restart_hook(move || restarted_cloned.store(true, Ordering::Release))
+            .sensor_only();
+
+        let exec = Executor::new();
+        let mut viam_server = viam_server.build(
+            NativeH2Connector::default(),
+            exec.clone(),
+            mdns,
+            Box::new(network),
+        );
+        let cloned_exec = exec.clone();
+        let mut app = AppServerInsecure::default();
+        app.config_fn = Some(Rc::new(Box::new(make_sample_config)));
+
+        exec.block_on(async move {
+            let other_clone = cloned_exec.clone();
+            let _fake_server_task =
+                cloned_exec.spawn(async move { run_fake_app_server(other_clone, app).await });
+            let _task = cloned_exec.spawn(async move {
+                viam_server.run().await;
+            });
+            // nothing is erased until a reset is requested
+            Timer::after(Duration::from_millis(200)).await;
+            assert!(ram_storage.has_robot_credentials());
+            assert!(!restarted.load(Ordering::Acquire));
+
+            factory_reset.request();
+            async {
+                while !restarted.load(Ordering::Acquire) {
+                    Timer::after(Duration::from_millis(50)).await;
+                }
+            }
+            .or(async {
+                Timer::after(Duration::from_secs(10)).await;
+                panic!("the server didn't restart after the factory reset");
+            })
+            .await;
+            assert!(!ram_storage.has_robot_credentials());
+            assert!(!ram_storage.has_robot_configuration());
+        });
+    }
+
     #[test_log::test]
     /// Runs viam server exposing HTTP2 connections, since each HTTP2 connection gets a
     /// max_prio assigned we can't test preemption
@@ -1559,6 +3078,74 @@ mod tests {
             assert!(t4.is_err());
         });
     }
+    #[test_log::test]
+    /// In local only mode the server must serve HTTP2 connections with the provided
+    /// certificate without ever reaching app (no fake app server is running)
+    fn test_local_only() {
+        let _unused = global_network_test_lock();
+        let ram_storage = RAMStorage::new();
+
+        let network = match local_ip_address::local_ip().expect("error parsing local IP") {
+            std::net::IpAddr::V4(ip) => ExternallyManagedNetwork::new(ip),
+            _ => panic!("oops expected ipv4"),
+        };
+
+        let mdns = NativeMdns::new("".to_owned(), network.get_ip());
+        assert!(mdns.is_ok());
+        let mdns = mdns.unwrap();
+
+        let mut cfg = make_sample_config();
+        if let Some(cloud) = cfg.cloud.as_mut() {
+            cloud.fqdn = "test-local.xxds65ui.viam.cloud".to_owned();
+            cloud.local_fqdn = "test-local.xxds65ui.viam.local.cloud".to_owned();
+        }
+        let self_signed = rcgen::generate_simple_self_signed(["localhost".to_string()]).unwrap();
+        let certificate = TlsCertificate::new(
+            self_signed.serialize_pem().unwrap().into_bytes(),
+            self_signed.serialize_private_key_pem().into_bytes(),
+        );
+
+        let mut viam_server = ViamServerBuilder::new(ram_storage);
+        viam_server
+            .with_http2_server(NativeH2Connector::default(), 12346)
+            .with_max_concurrent_connection(3)
+            .local_only(cfg, certificate);
+
+        let exec = Executor::new();
+
+        let mut viam_server = viam_server.build(
+            NativeH2Connector::default(),
+            exec.clone(),
+            mdns,
+            Box::new(network),
+        );
+
+        let cloned_exec = exec.clone();
+        exec.block_on(async move {
+            let _task = cloned_exec.spawn(async move {
+                viam_server.run().await;
+            });
+            let record = look_for_an_mdns_record("_rpc._tcp.local.", "grpc", "test-local")
+                .or(async {
+                    let _ = Timer::after(Duration::from_secs(1)).await;
+                    Err("timeout".into())
+                })
+                .await;
+
+            assert!(record.is_ok());
+            let record = record.unwrap();
+
+            let addr = record.get_addresses_v4().into_iter().take(1).next();
+            assert!(addr.is_some());
+            let addr = addr.unwrap();
+            let port = record.get_port();
+            let addr = SocketAddr::new(std::net::IpAddr::V4(*addr), port);
+
+            let t1 = test_connect_to(addr, cloned_exec.clone()).await;
+            assert!(t1.is_ok());
+        });
+    }
+
     async fn test_connect_to(
         addr: SocketAddr,
         exec: Executor,
@@ -1802,6 +3389,80 @@ mod tests {
         Ok(())
     }
 
+    #[test_log::test]
+    fn test_tcp_keepalive() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let _client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (stream, _) = listener.accept().unwrap();
+        let sock = socket2::SockRef::from(&stream);
+        assert!(!sock.keepalive().unwrap());
+
+        super::TcpKeepalive::default()
+            .with_idle(Duration::from_secs(10))
+            .with_retries(2)
+            .apply(&stream)
+            .unwrap();
+        assert!(sock.keepalive().unwrap());
+    }
+
+    #[test_log::test]
+    fn test_app_reachability() {
+        use super::check_app_reachability;
+        use crate::common::app_client::AppClientError;
+        use crate::common::blocking::BlockingPool;
+        use crate::common::exec::TaskConfig;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let exec = Executor::new();
+        // lookups without a pool run on a thread configured by the server
+        let config = TaskConfig::default().with_stack_size(32 * 1024);
+        exec.block_on(async {
+            let uri = format!("https://127.0.0.1:{}", port).parse().unwrap();
+            assert!(check_app_reachability(&uri, None, &config, &exec)
+                .await
+                .is_ok());
+            drop(listener);
+            let err = check_app_reachability(&uri, None, &config, &exec)
+                .await
+                .unwrap_err();
+            assert!(matches!(err, AppClientError::AppTcpConnectError(..)));
+            // reachability failures are retried like any other io error
+            assert!(err.is_io_error());
+            // plain http addresses are only resolved
+            let uri = format!("http://127.0.0.1:{}", port).parse().unwrap();
+            assert!(check_app_reachability(&uri, None, &config, &exec)
+                .await
+                .is_ok());
+
+            let uri = "https://app.invalid:443".parse().unwrap();
+            let err = check_app_reachability(&uri, None, &config, &exec)
+                .await
+                .unwrap_err();
+            assert!(matches!(err, AppClientError::AppDnsError(..)));
+            assert!(err.is_io_error());
+
+            // the lookup is made on the blocking pool when there is one
+            let pool = BlockingPool::new(1, 1, &Default::default()).unwrap();
+            let uri = format!("http://127.0.0.1:{}", port).parse().unwrap();
+            assert!(check_app_reachability(&uri, Some(&pool), &config, &exec)
+                .await
+                .is_ok());
+            let uri = "https://app.invalid:443".parse().unwrap();
+            assert!(matches!(
+                check_app_reachability(&uri, Some(&pool), &config, &exec).await,
+                Err(AppClientError::AppDnsError(..))
+            ));
+        });
+
+        let err = AppClientError::AppTlsError(
+            "app.viam.com".to_owned(),
+            std::io::ErrorKind::ConnectionReset.into(),
+        );
+        assert!(err.is_io_error());
+        assert!(!AppClientError::AppClientEmptyBody.is_io_error());
+    }
+
     #[ignore]
     #[test_log::test]
     fn test_viam_builder() {
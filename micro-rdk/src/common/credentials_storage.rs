@@ -1,10 +1,18 @@
 #![allow(dead_code)]
 use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
 use std::{convert::Infallible, error::Error, fmt::Debug, rc::Rc, sync::Mutex};
 
-use hyper::Uri;
+use hyper::{http::uri::InvalidUri, Uri};
+use thiserror::Error;
 
-use crate::{common::grpc::ServerError, proto::app::v1::RobotConfig};
+use crate::{
+    common::{
+        grpc::{GrpcError, ServerError},
+        webrtc::certificate::{Certificate, Fingerprint},
+    },
+    proto::app::v1::RobotConfig,
+};
 
 use crate::proto::{
     app::v1::CertificateResponse,
@@ -56,6 +64,49 @@ impl TryFrom<CloudConfig> for RobotCredentials {
     }
 }
 
+#[derive(Error, Debug)]
+pub enum AppAddressError {
+    #[error("app address {0:?} is not a valid URI: {1}")]
+    InvalidUri(String, InvalidUri),
+    #[error("app address {0:?} should start with http:// or https://")]
+    InvalidScheme(String),
+    #[error("app address {0:?} has no host")]
+    MissingHost(String),
+}
+
+impl From<AppAddressError> for ServerError {
+    fn from(value: AppAddressError) -> Self {
+        Self::new(GrpcError::RpcInvalidArgument, Some(value.into()))
+    }
+}
+
+/// Parses the address of app, an absolute http(s) URI such as `https://app.viam.com:443`.
+/// Storages validate addresses with it when they are stored so a typo is reported at
+/// provisioning time rather than when connecting to app.
+pub fn parse_app_address(address: &str) -> Result<Uri, AppAddressError> {
+    let uri = address
+        .parse::<Uri>()
+        .map_err(|e| AppAddressError::InvalidUri(address.to_owned(), e))?;
+    if !matches!(uri.scheme_str(), Some("http") | Some("https")) {
+        return Err(AppAddressError::InvalidScheme(address.to_owned()));
+    }
+    if uri.host().is_none() {
+        return Err(AppAddressError::MissingHost(address.to_owned()));
+    }
+    Ok(uri)
+}
+
+/// Parses an app address read back from storage. Addresses stored before they were validated
+/// may lack a scheme (`app.viam.com:443`), those are read as https rather than rejected.
+pub fn parse_stored_app_address(address: &str) -> Result<Uri, AppAddressError> {
+    match parse_app_address(address) {
+        Err(AppAddressError::InvalidScheme(_)) if !address.contains("://") => {
+            parse_app_address(&format!("https://{}", address))
+        }
+        result => result,
+    }
+}
+
 impl From<RobotCredentials> for CloudConfig {
     fn from(value: RobotCredentials) -> Self {
         Self {
@@ -90,6 +141,15 @@ pub struct TlsCertificate {
     pub(crate) private_key: Vec<u8>,
 }
 
+impl TlsCertificate {
+    pub fn new(certificate: Vec<u8>, private_key: Vec<u8>) -> Self {
+        Self {
+            certificate,
+            private_key,
+        }
+    }
+}
+
 impl From<CertificateResponse> for TlsCertificate {
     fn from(resp: CertificateResponse) -> Self {
         Self {
@@ -99,12 +159,63 @@ impl From<CertificateResponse> for TlsCertificate {
     }
 }
 
+/// A self-signed WebRTC certificate kept in storage so the same one is served across reboots
+#[derive(Clone, Default)]
+pub struct CachedWebRtcCertificate {
+    pub(crate) certificate: Vec<u8>,
+    pub(crate) private_key: Vec<u8>,
+    pub(crate) fingerprint: Fingerprint,
+    /// end of validity of the certificate, in seconds since the UNIX epoch
+    pub(crate) not_after: u64,
+}
+
+impl CachedWebRtcCertificate {
+    pub fn new<C: Certificate + ?Sized>(cert: &C, not_after: u64) -> Self {
+        Self {
+            certificate: cert.get_der_certificate().to_vec(),
+            private_key: cert.get_der_keypair().to_vec(),
+            fingerprint: cert.get_fingerprint().clone(),
+            not_after,
+        }
+    }
+    pub fn not_after(&self) -> u64 {
+        self.not_after
+    }
+    /// A device that hasn't synchronized its clock yet sees every certificate as valid
+    pub fn is_expired(&self) -> bool {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .is_ok_and(|now| now.as_secs() >= self.not_after)
+    }
+}
+
+impl Certificate for CachedWebRtcCertificate {
+    fn get_der_certificate(&self) -> &'_ [u8] {
+        &self.certificate
+    }
+    fn get_der_keypair(&self) -> &'_ [u8] {
+        &self.private_key
+    }
+    fn get_fingerprint(&self) -> &'_ Fingerprint {
+        &self.fingerprint
+    }
+}
+
+/// Maximum number of WiFi networks kept in storage, counting the one of
+/// [`WifiCredentialStorage::get_wifi_credentials`] and its fallbacks
+pub const MAX_WIFI_NETWORKS: usize = 10;
+
 pub trait WifiCredentialStorage {
     type Error: Error + Debug + Into<ServerError>;
     fn has_wifi_credentials(&self) -> bool;
     fn store_wifi_credentials(&self, creds: WifiCredentials) -> Result<(), Self::Error>;
     fn get_wifi_credentials(&self) -> Result<WifiCredentials, Self::Error>;
     fn reset_wifi_credentials(&self) -> Result<(), Self::Error>;
+    /// Networks to fall back on, in order of priority, when the one of `get_wifi_credentials`
+    /// can't be joined
+    fn get_fallback_wifi_credentials(&self) -> Result<Vec<WifiCredentials>, Self::Error> {
+        Ok(vec![])
+    }
 }
 
 pub trait RobotConfigurationStorage {
@@ -139,16 +250,88 @@ pub trait OtaMetadataStorage {
     fn reset_ota_metadata(&self) -> Result<(), Self::Error>;
 }
 
+pub trait WebRtcCertificateStorage {
+    type Error: Error + Debug + Into<ServerError>;
+    fn has_webrtc_certificate(&self) -> bool;
+    fn store_webrtc_certificate(&self, cert: CachedWebRtcCertificate) -> Result<(), Self::Error>;
+    fn get_webrtc_certificate(&self) -> Result<CachedWebRtcCertificate, Self::Error>;
+    fn reset_webrtc_certificate(&self) -> Result<(), Self::Error>;
+}
+
 pub trait StorageDiagnostic {
     fn log_space_diagnostic(&self);
 }
 
+/// An item kept in storage
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StorageItem {
+    RobotCredentials,
+    AppAddress,
+    RobotConfiguration,
+    TlsCertificate,
+    WifiCredentials,
+}
+
+/// Items present in `storage` that can't be read back, for example after the underlying flash
+/// got corrupted
+pub fn find_unreadable_items<S>(storage: &S) -> Vec<StorageItem>
+where
+    S: RobotConfigurationStorage + WifiCredentialStorage,
+{
+    let mut unreadable = vec![];
+    if storage.has_robot_credentials() && storage.get_robot_credentials().is_err() {
+        unreadable.push(StorageItem::RobotCredentials);
+    }
+    if storage.has_app_address() && storage.get_app_address().is_err() {
+        unreadable.push(StorageItem::AppAddress);
+    }
+    if storage.has_robot_configuration() && storage.get_robot_configuration().is_err() {
+        unreadable.push(StorageItem::RobotConfiguration);
+    }
+    if storage.has_tls_certificate() && storage.get_tls_certificate().is_err() {
+        unreadable.push(StorageItem::TlsCertificate);
+    }
+    if storage.has_wifi_credentials() && storage.get_wifi_credentials().is_err() {
+        unreadable.push(StorageItem::WifiCredentials);
+    }
+    unreadable
+}
+
+/// Erase `items` from `storage`, leaving the other items untouched. Errors are logged and the
+/// remaining items erased regardless.
+pub fn reset_items<S>(storage: &S, items: &[StorageItem])
+where
+    S: RobotConfigurationStorage + WifiCredentialStorage,
+{
+    for item in items {
+        let result = match item {
+            StorageItem::RobotCredentials => {
+                storage.reset_robot_credentials().map_err(|e| e.to_string())
+            }
+            StorageItem::AppAddress => storage.reset_app_address().map_err(|e| e.to_string()),
+            StorageItem::RobotConfiguration => storage
+                .reset_robot_configuration()
+                .map_err(|e| e.to_string()),
+            StorageItem::TlsCertificate => {
+                storage.reset_tls_certificate().map_err(|e| e.to_string())
+            }
+            StorageItem::WifiCredentials => {
+                storage.reset_wifi_credentials().map_err(|e| e.to_string())
+            }
+        };
+        if let Err(e) = result {
+            log::error!("couldn't erase {:?} from storage: {}", item, e);
+        }
+    }
+}
+
 #[derive(Default)]
 struct RAMCredentialStorageInner {
     robot_creds: Option<RobotCredentials>,
     robot_config: Option<RobotConfig>,
     wifi_creds: Option<WifiCredentials>,
     tls_cert: Option<TlsCertificate>,
+    webrtc_cert: Option<CachedWebRtcCertificate>,
     app_address: Option<String>,
     #[cfg(feature = "ota")]
     ota_metadata: Option<OtaMetadata>,
@@ -165,6 +348,7 @@ impl RAMStorage {
             robot_config: None,
             wifi_creds: None,
             tls_cert: None,
+            webrtc_cert: None,
             app_address: None,
             #[cfg(feature = "ota")]
             ota_metadata: None,
@@ -195,13 +379,16 @@ impl OtaMetadataStorage for RAMStorage {
 }
 
 impl RobotConfigurationStorage for RAMStorage {
-    type Error = <RobotCredentials as TryFrom<CloudConfig>>::Error;
+    type Error = AppAddressError;
     fn has_robot_credentials(&self) -> bool {
         let inner_ref = self.0.lock().unwrap();
         inner_ref.robot_creds.is_some()
     }
     fn store_robot_credentials(&self, cfg: CloudConfig) -> Result<(), Self::Error> {
-        let creds: RobotCredentials = cfg.try_into()?;
+        if !cfg.app_address.is_empty() {
+            parse_app_address(&cfg.app_address)?;
+        }
+        let creds = RobotCredentials::new(cfg.id, cfg.secret);
         let mut inner_ref = self.0.lock().unwrap();
         let _ = inner_ref.robot_creds.insert(creds);
         Ok(())
@@ -258,17 +445,14 @@ impl RobotConfigurationStorage for RAMStorage {
         Ok(())
     }
     fn store_app_address(&self, uri: &str) -> Result<(), Self::Error> {
+        parse_app_address(uri)?;
         let mut inner_ref = self.0.lock().unwrap();
         let _ = inner_ref.app_address.insert(uri.to_string());
         Ok(())
     }
     fn get_app_address(&self) -> Result<Uri, Self::Error> {
         let inner_ref = self.0.lock().unwrap();
-        inner_ref
-            .app_address
-            .clone()
-            .unwrap_or_default()
-            .parse::<Uri>()
+        parse_stored_app_address(&inner_ref.app_address.clone().unwrap_or_default())
     }
     fn reset_app_address(&self) -> Result<(), Self::Error> {
         let mut inner_ref = self.0.lock().unwrap();
@@ -304,6 +488,27 @@ impl WifiCredentialStorage for RAMStorage {
     }
 }
 
+impl WebRtcCertificateStorage for RAMStorage {
+    type Error = Infallible;
+    fn has_webrtc_certificate(&self) -> bool {
+        let inner_ref = self.0.lock().unwrap();
+        inner_ref.webrtc_cert.is_some()
+    }
+    fn store_webrtc_certificate(&self, cert: CachedWebRtcCertificate) -> Result<(), Self::Error> {
+        let mut inner_ref = self.0.lock().unwrap();
+        let _ = inner_ref.webrtc_cert.insert(cert);
+        Ok(())
+    }
+    fn get_webrtc_certificate(&self) -> Result<CachedWebRtcCertificate, Self::Error> {
+        let inner_ref = self.0.lock().unwrap();
+        Ok(inner_ref.webrtc_cert.clone().unwrap_or_default())
+    }
+    fn reset_webrtc_certificate(&self) -> Result<(), Self::Error> {
+        let _ = self.0.lock().unwrap().webrtc_cert.take();
+        Ok(())
+    }
+}
+
 impl StorageDiagnostic for RAMStorage {
     fn log_space_diagnostic(&self) {}
 }
@@ -313,3 +518,239 @@ impl From<Infallible> for ServerError {
         unreachable!()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prost::Message;
+    use std::cell::RefCell;
+
+    #[derive(Error, Debug)]
+    enum BlobStorageError {
+        #[error(transparent)]
+        AppAddress(#[from] AppAddressError),
+        #[error(transparent)]
+        Decode(#[from] prost::DecodeError),
+    }
+
+    impl From<BlobStorageError> for ServerError {
+        fn from(value: BlobStorageError) -> Self {
+            ServerError::new(GrpcError::RpcInternal, Some(value.into()))
+        }
+    }
+
+    // Keeps the robot configuration encoded like flash backed storages do
+    #[derive(Default)]
+    struct BlobStorage {
+        ram: RAMStorage,
+        config: RefCell<Option<Vec<u8>>>,
+    }
+
+    impl RobotConfigurationStorage for BlobStorage {
+        type Error = BlobStorageError;
+        fn has_robot_credentials(&self) -> bool {
+            self.ram.has_robot_credentials()
+        }
+        fn store_robot_credentials(&self, cfg: CloudConfig) -> Result<(), Self::Error> {
+            Ok(self.ram.store_robot_credentials(cfg)?)
+        }
+        fn get_robot_credentials(&self) -> Result<RobotCredentials, Self::Error> {
+            Ok(self.ram.get_robot_credentials()?)
+        }
+        fn reset_robot_credentials(&self) -> Result<(), Self::Error> {
+            Ok(self.ram.reset_robot_credentials()?)
+        }
+        fn has_app_address(&self) -> bool {
+            self.ram.has_app_address()
+        }
+        fn store_app_address(&self, uri: &str) -> Result<(), Self::Error> {
+            Ok(self.ram.store_app_address(uri)?)
+        }
+        fn get_app_address(&self) -> Result<Uri, Self::Error> {
+            Ok(self.ram.get_app_address()?)
+        }
+        fn reset_app_address(&self) -> Result<(), Self::Error> {
+            Ok(self.ram.reset_app_address()?)
+        }
+        fn has_robot_configuration(&self) -> bool {
+            self.config.borrow().is_some()
+        }
+        fn store_robot_configuration(&self, cfg: &RobotConfig) -> Result<(), Self::Error> {
+            let _ = self.config.borrow_mut().insert(cfg.encode_to_vec());
+            Ok(())
+        }
+        fn get_robot_configuration(&self) -> Result<RobotConfig, Self::Error> {
+            let config = self.config.borrow();
+            Ok(RobotConfig::decode(config.as_deref().unwrap_or_default())?)
+        }
+        fn reset_robot_configuration(&self) -> Result<(), Self::Error> {
+            let _ = self.config.borrow_mut().take();
+            Ok(())
+        }
+        fn has_tls_certificate(&self) -> bool {
+            self.ram.has_tls_certificate()
+        }
+        fn store_tls_certificate(&self, creds: TlsCertificate) -> Result<(), Self::Error> {
+            Ok(self.ram.store_tls_certificate(creds)?)
+        }
+        fn get_tls_certificate(&self) -> Result<TlsCertificate, Self::Error> {
+            Ok(self.ram.get_tls_certificate()?)
+        }
+        fn reset_tls_certificate(&self) -> Result<(), Self::Error> {
+            Ok(self.ram.reset_tls_certificate()?)
+        }
+    }
+
+    impl WifiCredentialStorage for BlobStorage {
+        type Error = Infallible;
+        fn has_wifi_credentials(&self) -> bool {
+            self.ram.has_wifi_credentials()
+        }
+        fn store_wifi_credentials(&self, creds: WifiCredentials) -> Result<(), Self::Error> {
+            self.ram.store_wifi_credentials(creds)
+        }
+        fn get_wifi_credentials(&self) -> Result<WifiCredentials, Self::Error> {
+            self.ram.get_wifi_credentials()
+        }
+        fn reset_wifi_credentials(&self) -> Result<(), Self::Error> {
+            self.ram.reset_wifi_credentials()
+        }
+    }
+
+    #[test_log::test]
+    fn test_unreadable_items_are_reset() {
+        let storage = RAMStorage::new();
+        storage
+            .store_robot_credentials(CloudConfig {
+                id: "robot".to_owned(),
+                secret: "secret".to_owned(),
+                app_address: "".to_owned(),
+            })
+            .unwrap();
+        storage
+            .store_app_address("https://app.viam.com:443")
+            .unwrap();
+        storage
+            .store_wifi_credentials(WifiCredentials::new("ssid".to_owned(), "pwd".to_owned()))
+            .unwrap();
+        storage
+            .store_robot_configuration(&RobotConfig::default())
+            .unwrap();
+        assert!(find_unreadable_items(&storage).is_empty());
+
+        // store_app_address validates the address, write around it like a corrupted flash would
+        let _ = storage
+            .0
+            .lock()
+            .unwrap()
+            .app_address
+            .insert("not a uri".to_owned());
+        let unreadable = find_unreadable_items(&storage);
+        assert_eq!(unreadable, vec![StorageItem::AppAddress]);
+
+        reset_items(&storage, &unreadable);
+        assert!(find_unreadable_items(&storage).is_empty());
+        assert!(!storage.has_app_address());
+        assert!(storage.has_robot_credentials());
+        assert!(storage.has_robot_configuration());
+        assert!(storage.has_wifi_credentials());
+        assert_eq!(storage.get_robot_credentials().unwrap().robot_id, "robot");
+    }
+
+    #[test_log::test]
+    fn test_corrupted_configuration_is_reset() {
+        let storage = BlobStorage::default();
+        storage
+            .store_robot_credentials(CloudConfig {
+                id: "robot".to_owned(),
+                secret: "secret".to_owned(),
+                app_address: "".to_owned(),
+            })
+            .unwrap();
+        storage
+            .store_robot_configuration(&RobotConfig::default())
+            .unwrap();
+        assert!(find_unreadable_items(&storage).is_empty());
+
+        // a truncated length delimited field can't be decoded
+        let _ = storage.config.borrow_mut().insert(vec![0x12, 0xff, 0x01]);
+        let unreadable = find_unreadable_items(&storage);
+        assert_eq!(unreadable, vec![StorageItem::RobotConfiguration]);
+
+        reset_items(&storage, &unreadable);
+        assert!(find_unreadable_items(&storage).is_empty());
+        assert!(!storage.has_robot_configuration());
+        assert!(storage.has_robot_credentials());
+    }
+
+    #[test_log::test]
+    fn test_app_address_validation() {
+        let uri = parse_app_address("https://app.viam.com:443").unwrap();
+        assert_eq!(uri.host(), Some("app.viam.com"));
+        assert_eq!(uri.port_u16(), Some(443));
+        assert!(parse_app_address("http://localhost:56432").is_ok());
+
+        assert!(matches!(
+            parse_app_address("https://app viam.com"),
+            Err(AppAddressError::InvalidUri(..))
+        ));
+        assert!(matches!(
+            parse_app_address(""),
+            Err(AppAddressError::InvalidUri(..))
+        ));
+        assert!(matches!(
+            parse_app_address("app.viam.com:443"),
+            Err(AppAddressError::InvalidScheme(_))
+        ));
+        assert!(matches!(
+            parse_app_address("htps://app.viam.com"),
+            Err(AppAddressError::InvalidScheme(_))
+        ));
+        assert!(matches!(
+            parse_app_address("/app"),
+            Err(AppAddressError::InvalidScheme(_))
+        ));
+
+        let storage = RAMStorage::new();
+        assert!(storage.store_app_address("app.viam.com:443").is_err());
+        assert!(!storage.has_app_address());
+        storage
+            .store_app_address("https://app.viam.com:443")
+            .unwrap();
+        assert_eq!(
+            storage.get_app_address().unwrap(),
+            "https://app.viam.com:443"
+        );
+    }
+
+    #[test_log::test]
+    fn test_stored_app_address() {
+        assert_eq!(
+            parse_stored_app_address("app.viam.com:443").unwrap(),
+            "https://app.viam.com:443"
+        );
+        assert_eq!(
+            parse_stored_app_address("http://localhost:56432").unwrap(),
+            "http://localhost:56432"
+        );
+        assert!(matches!(
+            parse_stored_app_address("htps://app.viam.com"),
+            Err(AppAddressError::InvalidScheme(_))
+        ));
+        assert!(parse_stored_app_address("not a uri").is_err());
+
+        // an address stored before validation is readable and isn't reset
+        let storage = RAMStorage::new();
+        let _ = storage
+            .0
+            .lock()
+            .unwrap()
+            .app_address
+            .insert("app.viam.com:443".to_owned());
+        assert!(find_unreadable_items(&storage).is_empty());
+        assert_eq!(
+            storage.get_app_address().unwrap().host(),
+            Some("app.viam.com")
+        );
+    }
+}
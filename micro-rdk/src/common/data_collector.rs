@@ -1,14 +1,15 @@
+use std::collections::HashMap;
 use std::fmt::Display;
 use std::time::{Duration, Instant};
 
-use crate::google::protobuf::Timestamp;
-use crate::proto::app::data_sync::v1::{SensorData, SensorMetadata};
+use crate::google::protobuf::{value, Struct, Timestamp, Value};
+use crate::proto::app::data_sync::v1::{sensor_data::Data, SensorData, SensorMetadata};
 
 use super::{
     config::{AttributeError, Kind},
     movement_sensor::MovementSensor,
     robot::ResourceType,
-    sensor::{Readings, SensorError},
+    sensor::{GenericReadingsResult, Readings, SensorError},
 };
 
 use thiserror::Error;
@@ -26,6 +27,15 @@ pub struct DataCollectorConfig {
     pub capture_frequency_hz: f32,
     pub capacity: usize,
     pub disabled: bool,
+    /// Capture on wall-clock boundaries (e.g. on the minute for a 1/60Hz frequency) once the
+    /// clock is set, see [`DataCollector::with_clock_alignment`]
+    pub align_to_clock: bool,
+    /// How readings captured over `aggregation_window_s` are combined before being stored,
+    /// see [`DataCollector::with_aggregation`]
+    pub method_aggregation: MethodAggregation,
+    pub aggregation_window_s: Option<f32>,
+    /// Overrides the `sync_interval_mins` of the data manager for this collector
+    pub sync_interval: Option<Duration>,
 }
 
 impl TryFrom<&Kind> for DataCollectorConfig {
@@ -38,6 +48,11 @@ impl TryFrom<&Kind> for DataCollectorConfig {
             },
             Err(_) => false,
         };
+        let align_to_clock: bool = value
+            .get("align_to_clock")?
+            .map(|kind| kind.try_into())
+            .transpose()?
+            .unwrap_or(false);
         let method_str: String = value
             .get("method")?
             .ok_or(AttributeError::KeyNotFound("method".to_string()))?
@@ -68,11 +83,58 @@ impl TryFrom<&Kind> for DataCollectorConfig {
                 return Err(AttributeError::ConversionImpossibleError);
             }
         };
+        let method_aggregation = match value.get("method_aggregation")? {
+            Some(kind) => {
+                let aggregation: String = kind.try_into()?;
+                MethodAggregation::try_from(aggregation.as_str())?
+            }
+            None => MethodAggregation::None,
+        };
+        let aggregation_window_s: Option<f32> = value
+            .get("aggregation_window_s")?
+            .map(|kind| kind.try_into())
+            .transpose()?;
+        if method_aggregation != MethodAggregation::None {
+            if method != CollectionMethod::Readings {
+                return Err(AttributeError::ValidationError(
+                    "method_aggregation is only supported for the Readings method".to_string(),
+                ));
+            }
+            match aggregation_window_s {
+                Some(window) if window * capture_frequency_hz >= 1.0 => {}
+                Some(_) => {
+                    return Err(AttributeError::ValidationError(
+                        "aggregation_window_s must span at least one capture".to_string(),
+                    ))
+                }
+                None => {
+                    return Err(AttributeError::KeyNotFound(
+                        "aggregation_window_s".to_string(),
+                    ))
+                }
+            }
+        }
+        let sync_interval = match value.get("sync_interval_minutes")? {
+            Some(kind) => {
+                let minutes: f64 = kind.try_into()?;
+                if minutes <= 0.0 {
+                    return Err(AttributeError::ValidationError(
+                        "sync_interval_minutes must be positive".to_string(),
+                    ));
+                }
+                Some(Duration::from_secs_f64(minutes * 60.0))
+            }
+            None => None,
+        };
         Ok(DataCollectorConfig {
             method,
             capture_frequency_hz,
             capacity,
             disabled,
+            align_to_clock,
+            method_aggregation,
+            aggregation_window_s,
+            sync_interval,
         })
     }
 }
@@ -105,6 +167,137 @@ impl Display for CollectionMethod {
     }
 }
 
+/// How the numeric readings captured over an aggregation window are combined into the
+/// single datum stored for that window, numbers nested in struct readings included. Non-numeric
+/// readings are stored as last captured.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MethodAggregation {
+    #[default]
+    None,
+    Mean,
+    Max,
+    Min,
+}
+
+impl TryFrom<&str> for MethodAggregation {
+    type Error = AttributeError;
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "none" => Ok(Self::None),
+            "mean" => Ok(Self::Mean),
+            "max" => Ok(Self::Max),
+            "min" => Ok(Self::Min),
+            _ => Err(AttributeError::ValidationError(format!(
+                "unknown method_aggregation {}",
+                value
+            ))),
+        }
+    }
+}
+
+// A reading aggregated over the current window, structs (e.g. the {x, y, z} of a linear
+// acceleration) being aggregated field by field
+enum AggregatedValue {
+    // number of samples accumulated, used for the mean
+    Number(f64, usize),
+    Struct(HashMap<String, AggregatedValue>),
+    // non-numeric readings are kept as last captured
+    Last(Value),
+}
+
+impl AggregatedValue {
+    fn new(value: Value) -> Self {
+        match value.kind {
+            Some(value::Kind::NumberValue(number)) => Self::Number(number, 1),
+            Some(value::Kind::StructValue(fields)) => Self::Struct(
+                fields
+                    .fields
+                    .into_iter()
+                    .map(|(key, value)| (key, Self::new(value)))
+                    .collect(),
+            ),
+            kind => Self::Last(Value { kind }),
+        }
+    }
+
+    fn add(&mut self, aggregation: MethodAggregation, value: Value) {
+        match (self, value.kind) {
+            (Self::Number(current, count), Some(value::Kind::NumberValue(new))) => {
+                *current = match aggregation {
+                    MethodAggregation::Max => current.max(new),
+                    MethodAggregation::Min => current.min(new),
+                    _ => *current + new,
+                };
+                *count += 1;
+            }
+            (Self::Struct(fields), Some(value::Kind::StructValue(new))) => {
+                add_fields(fields, aggregation, new.fields)
+            }
+            (current, kind) => *current = Self::new(Value { kind }),
+        }
+    }
+
+    fn finish(self, aggregation: MethodAggregation) -> Value {
+        let kind = match self {
+            Self::Number(sum, count) if aggregation == MethodAggregation::Mean => {
+                value::Kind::NumberValue(sum / count as f64)
+            }
+            Self::Number(number, _) => value::Kind::NumberValue(number),
+            Self::Struct(fields) => value::Kind::StructValue(Struct {
+                fields: finish_fields(fields, aggregation),
+            }),
+            Self::Last(value) => return value,
+        };
+        Value { kind: Some(kind) }
+    }
+}
+
+fn add_fields(
+    aggregated: &mut HashMap<String, AggregatedValue>,
+    aggregation: MethodAggregation,
+    fields: HashMap<String, Value>,
+) {
+    for (key, value) in fields {
+        match aggregated.get_mut(&key) {
+            Some(current) => current.add(aggregation, value),
+            None => {
+                aggregated.insert(key, AggregatedValue::new(value));
+            }
+        }
+    }
+}
+
+fn finish_fields(
+    aggregated: HashMap<String, AggregatedValue>,
+    aggregation: MethodAggregation,
+) -> HashMap<String, Value> {
+    aggregated
+        .into_iter()
+        .map(|(key, value)| (key, value.finish(aggregation)))
+        .collect()
+}
+
+/// Readings buffered over the current aggregation window
+#[derive(Default)]
+struct AggregationWindow {
+    readings: HashMap<String, AggregatedValue>,
+    captures: usize,
+    first_requested: Option<Duration>,
+}
+
+impl AggregationWindow {
+    fn add(&mut self, aggregation: MethodAggregation, readings: GenericReadingsResult) {
+        add_fields(&mut self.readings, aggregation, readings);
+        self.captures += 1;
+    }
+
+    fn finish(&mut self, aggregation: MethodAggregation) -> GenericReadingsResult {
+        let readings = finish_fields(std::mem::take(&mut self.readings), aggregation);
+        *self = Default::default();
+        readings
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct ResourceMethodKey {
     pub r_name: String,
@@ -130,6 +323,8 @@ pub enum DataCollectionError {
     NoSupportedMethods,
     #[error("capture frequency cannot be 0.0")]
     UnsupportedCaptureFrequency,
+    #[error("method_aggregation unsupported for method {0}")]
+    UnsupportedAggregation(CollectionMethod),
     #[error(transparent)]
     SensorCollectionError(#[from] SensorError),
 }
@@ -144,6 +339,11 @@ pub struct DataCollector {
     method: CollectionMethod,
     time_interval: Duration,
     capacity: usize,
+    align_to_clock: bool,
+    aggregation: MethodAggregation,
+    captures_per_window: usize,
+    window: AggregationWindow,
+    sync_interval: Option<Duration>,
 }
 
 fn resource_method_pair_is_valid(resource: &ResourceType, method: &CollectionMethod) -> bool {
@@ -187,21 +387,67 @@ impl DataCollector {
             method,
             time_interval,
             capacity,
+            align_to_clock: false,
+            aggregation: MethodAggregation::None,
+            captures_per_window: 1,
+            window: Default::default(),
+            sync_interval: None,
         })
     }
 
+    /// Sync the data of this collector at `sync_interval` rather than at the interval of the
+    /// data manager, None uses the latter
+    pub fn with_sync_interval(mut self, sync_interval: Option<Duration>) -> Self {
+        self.sync_interval = sync_interval;
+        self
+    }
+
+    /// Buffer the readings captured over `window` and store a single datum combining them
+    /// with `aggregation` once the window is over. Only applies to the Readings method.
+    pub fn with_aggregation(
+        mut self,
+        aggregation: MethodAggregation,
+        window: Duration,
+    ) -> Result<Self, DataCollectionError> {
+        if aggregation != MethodAggregation::None && self.method != CollectionMethod::Readings {
+            return Err(DataCollectionError::UnsupportedAggregation(
+                self.method.clone(),
+            ));
+        }
+        self.aggregation = aggregation;
+        self.captures_per_window =
+            ((window.as_secs_f64() / self.time_interval.as_secs_f64()).round() as usize).max(1);
+        Ok(self)
+    }
+
+    /// Capture when the wall-clock time is a multiple of the capture interval rather than
+    /// relative to boot, so that devices capturing at the same frequency line up. Captures are
+    /// relative to boot until the clock is set.
+    pub fn with_clock_alignment(mut self, align_to_clock: bool) -> Self {
+        self.align_to_clock = align_to_clock;
+        self
+    }
+
     pub fn from_config(
         name: String,
         resource: ResourceType,
         conf: &DataCollectorConfig,
     ) -> Result<Self, DataCollectionError> {
-        Self::new(
+        let coll = Self::new(
             name,
             resource,
             conf.method.clone(),
             conf.capture_frequency_hz,
             conf.capacity,
-        )
+        )?
+        .with_clock_alignment(conf.align_to_clock)
+        .with_sync_interval(conf.sync_interval);
+        match conf.aggregation_window_s {
+            Some(window) if conf.method_aggregation != MethodAggregation::None => {
+                coll.with_aggregation(conf.method_aggregation, Duration::from_secs_f32(window))
+            }
+            _ => Ok(coll),
+        }
     }
 
     pub fn name(&self) -> String {
@@ -224,15 +470,30 @@ impl DataCollector {
         self.capacity
     }
 
-    /// calls the method associated with the collector and returns the resulting data
+    pub fn aligned_to_clock(&self) -> bool {
+        self.align_to_clock
+    }
+
+    pub fn sync_interval(&self) -> Option<Duration> {
+        self.sync_interval
+    }
+
+    /// calls the method associated with the collector and returns the resulting data, when
+    /// aggregating readings data is only returned once the aggregation window is over
     pub(crate) fn call_method(
         &mut self,
         robot_start_time: Instant,
-    ) -> Result<SensorData, DataCollectionError> {
-        let reading_requested_ts = robot_start_time.elapsed();
+    ) -> Result<Option<SensorData>, DataCollectionError> {
+        let mut reading_requested_ts = robot_start_time.elapsed();
         let data = match &mut self.resource {
             ResourceType::Sensor(ref mut res) => match self.method {
-                CollectionMethod::Readings => res.get_generic_readings()?.into(),
+                CollectionMethod::Readings => {
+                    let readings = res.get_generic_readings()?;
+                    match self.aggregate(readings, &mut reading_requested_ts) {
+                        Some(data) => data,
+                        None => return Ok(None),
+                    }
+                }
                 _ => {
                     return Err(DataCollectionError::UnsupportedMethod(
                         self.method.clone(),
@@ -241,7 +502,13 @@ impl DataCollector {
                 }
             },
             ResourceType::MovementSensor(ref mut res) => match self.method {
-                CollectionMethod::Readings => res.get_generic_readings()?.into(),
+                CollectionMethod::Readings => {
+                    let readings = res.get_generic_readings()?;
+                    match self.aggregate(readings, &mut reading_requested_ts) {
+                        Some(data) => data,
+                        None => return Ok(None),
+                    }
+                }
                 CollectionMethod::AngularVelocity => res
                     .get_angular_velocity()?
                     .to_data_struct("angular_velocity"),
@@ -263,7 +530,7 @@ impl DataCollector {
             _ => return Err(DataCollectionError::NoSupportedMethods),
         };
         let reading_received_ts = robot_start_time.elapsed();
-        Ok(SensorData {
+        Ok(Some(SensorData {
             metadata: Some(SensorMetadata {
                 time_received: Some(Timestamp {
                     seconds: reading_received_ts.as_secs() as i64,
@@ -275,7 +542,26 @@ impl DataCollector {
                 }),
             }),
             data: Some(data),
-        })
+        }))
+    }
+
+    // buffers readings until the aggregation window is over, at which point the aggregated
+    // readings are returned and `requested_ts` is moved back to the start of the window
+    fn aggregate(
+        &mut self,
+        readings: GenericReadingsResult,
+        requested_ts: &mut Duration,
+    ) -> Option<Data> {
+        if self.aggregation == MethodAggregation::None {
+            return Some(readings.into());
+        }
+        self.window.add(self.aggregation, readings);
+        let first_requested = *self.window.first_requested.get_or_insert(*requested_ts);
+        if self.window.captures < self.captures_per_window {
+            return None;
+        }
+        *requested_ts = first_requested;
+        Some(self.window.finish(self.aggregation).into())
     }
 
     pub fn resource_method_key(&self) -> ResourceMethodKey {
@@ -294,15 +580,149 @@ mod tests {
     use std::time::{Duration, Instant};
 
     use super::{
-        CollectionMethod, DataCollectionError, DataCollector, DataCollectorConfig,
-        DEFAULT_CACHE_SIZE_KB,
+        AggregationWindow, CollectionMethod, DataCollectionError, DataCollector,
+        DataCollectorConfig, MethodAggregation, DEFAULT_CACHE_SIZE_KB,
     };
     use crate::common::config::{AttributeError, Kind};
+    use crate::common::generic::DoCommand;
     use crate::common::robot::ResourceType;
-    use crate::common::sensor::FakeSensor;
+    use crate::common::sensor::{FakeSensor, GenericReadingsResult, Readings, Sensor, SensorError};
+    use crate::common::status::{Status, StatusError};
     use crate::google;
     use crate::proto::app::data_sync::v1::sensor_data::Data;
 
+    #[derive(DoCommand)]
+    struct CountingSensor {
+        count: f64,
+    }
+
+    impl Sensor for CountingSensor {}
+
+    impl Readings for CountingSensor {
+        fn get_generic_readings(&mut self) -> Result<GenericReadingsResult, SensorError> {
+            self.count += 1.0;
+            Ok(HashMap::from([
+                (
+                    "count".to_string(),
+                    google::protobuf::Value {
+                        kind: Some(google::protobuf::value::Kind::NumberValue(self.count)),
+                    },
+                ),
+                (
+                    "label".to_string(),
+                    google::protobuf::Value {
+                        kind: Some(google::protobuf::value::Kind::StringValue(format!(
+                            "capture {}",
+                            self.count
+                        ))),
+                    },
+                ),
+            ]))
+        }
+    }
+
+    impl Status for CountingSensor {
+        fn get_status(&self) -> Result<Option<google::protobuf::Struct>, StatusError> {
+            Ok(None)
+        }
+    }
+
+    fn aggregated_readings(data: Option<Data>) -> (f64, String) {
+        let readings = match data {
+            Some(Data::Struct(mut d)) => match d.fields.remove("readings").and_then(|v| v.kind) {
+                Some(google::protobuf::value::Kind::StructValue(s)) => s,
+                _ => panic!("readings was not a struct"),
+            },
+            _ => panic!("expected struct data"),
+        };
+        let count = match readings.fields.get("count").and_then(|v| v.kind.clone()) {
+            Some(google::protobuf::value::Kind::NumberValue(count)) => count,
+            _ => panic!("count was not a number"),
+        };
+        let label = match readings.fields.get("label").and_then(|v| v.kind.clone()) {
+            Some(google::protobuf::value::Kind::StringValue(label)) => label,
+            _ => panic!("label was not a string"),
+        };
+        (count, label)
+    }
+
+    #[test_log::test]
+    fn test_aggregate_struct_readings() {
+        let number = |number: f64| google::protobuf::Value {
+            kind: Some(google::protobuf::value::Kind::NumberValue(number)),
+        };
+        let vector = |x: f64, y: f64, z: f64| google::protobuf::Value {
+            kind: Some(google::protobuf::value::Kind::StructValue(
+                google::protobuf::Struct {
+                    fields: HashMap::from([
+                        ("x".to_string(), number(x)),
+                        ("y".to_string(), number(y)),
+                        ("z".to_string(), number(z)),
+                    ]),
+                },
+            )),
+        };
+        let field = |readings: &GenericReadingsResult, key: &str, axis: &str| match readings
+            .get(key)
+            .and_then(|v| v.kind.as_ref())
+        {
+            Some(google::protobuf::value::Kind::StructValue(s)) => s.fields[axis].clone(),
+            _ => panic!("{} was not a struct", key),
+        };
+
+        for (aggregation, expected) in [
+            (MethodAggregation::Mean, [2.0, -4.0, 9.5]),
+            (MethodAggregation::Max, [3.0, -2.0, 9.8]),
+            (MethodAggregation::Min, [1.0, -6.0, 9.2]),
+        ] {
+            let mut window = AggregationWindow::default();
+            for (x, y, z) in [(1.0, -2.0, 9.8), (3.0, -6.0, 9.2)] {
+                window.add(
+                    aggregation,
+                    HashMap::from([("linear_acceleration".to_string(), vector(x, y, z))]),
+                );
+            }
+            assert_eq!(window.captures, 2);
+            let readings = window.finish(aggregation);
+            for (axis, expected) in ["x", "y", "z"].into_iter().zip(expected) {
+                assert_eq!(
+                    field(&readings, "linear_acceleration", axis),
+                    number(expected)
+                );
+            }
+            assert_eq!(window.captures, 0);
+        }
+
+        // a field changing kind starts over from its latest value
+        let mut window = AggregationWindow::default();
+        window.add(
+            MethodAggregation::Mean,
+            HashMap::from([("linear_acceleration".to_string(), vector(1.0, 1.0, 1.0))]),
+        );
+        let mut changed = vector(5.0, 3.0, 3.0);
+        if let Some(google::protobuf::value::Kind::StructValue(s)) = changed.kind.as_mut() {
+            s.fields.insert(
+                "x".to_string(),
+                google::protobuf::Value {
+                    kind: Some(google::protobuf::value::Kind::StringValue(
+                        "n/a".to_string(),
+                    )),
+                },
+            );
+        }
+        window.add(
+            MethodAggregation::Mean,
+            HashMap::from([("linear_acceleration".to_string(), changed)]),
+        );
+        window.add(
+            MethodAggregation::Mean,
+            HashMap::from([("linear_acceleration".to_string(), vector(5.0, 5.0, 5.0))]),
+        );
+        let readings = window.finish(MethodAggregation::Mean);
+        assert_eq!(field(&readings, "linear_acceleration", "x"), number(5.0));
+        assert_eq!(field(&readings, "linear_acceleration", "y"), number(3.0));
+    }
+
     #[test_log::test]
     fn test_collector_config() -> Result<(), AttributeError> {
         let kind_map = HashMap::from([
@@ -318,6 +738,8 @@ mod tests {
         assert_eq!(conf.capture_frequency_hz, 100.0);
         assert_eq!(conf.capacity, (DEFAULT_CACHE_SIZE_KB * 1000.0) as usize);
         assert_eq!(conf.disabled, false);
+        assert_eq!(conf.align_to_clock, false);
+        assert_eq!(conf.sync_interval, None);
 
         let kind_map = HashMap::from([
             (
@@ -327,6 +749,8 @@ mod tests {
             ("capture_frequency_hz".to_string(), Kind::NumberValue(100.0)),
             ("cache_size_kb".to_string(), Kind::NumberValue(2.0)),
             ("disabled".to_string(), Kind::BoolValue(true)),
+            ("align_to_clock".to_string(), Kind::BoolValue(true)),
+            ("sync_interval_minutes".to_string(), Kind::NumberValue(0.5)),
         ]);
         let conf_kind = Kind::StructValue(kind_map);
         let conf: DataCollectorConfig = (&conf_kind).try_into()?;
@@ -334,6 +758,22 @@ mod tests {
         assert_eq!(conf.capture_frequency_hz, 100.0);
         assert_eq!(conf.capacity, 2000);
         assert_eq!(conf.disabled, true);
+        assert_eq!(conf.align_to_clock, true);
+        assert_eq!(conf.sync_interval, Some(Duration::from_secs(30)));
+
+        let kind_map = HashMap::from([
+            (
+                "method".to_string(),
+                Kind::StringValue("Readings".to_string()),
+            ),
+            ("capture_frequency_hz".to_string(), Kind::NumberValue(100.0)),
+            (
+                "align_to_clock".to_string(),
+                Kind::StringValue("true".to_string()),
+            ),
+        ]);
+        let conf_kind = Kind::StructValue(kind_map);
+        assert!(DataCollectorConfig::try_from(&conf_kind).is_err());
 
         let kind_map = HashMap::from([
             (
@@ -425,7 +865,7 @@ mod tests {
             DataCollectorConfig::try_from(&conf_kind).expect("data collector config parse failed");
         let mut coll = DataCollector::from_config("fake".to_string(), resource, &conf)?;
         assert_eq!(coll.time_interval(), Duration::from_millis(10));
-        let data = coll.call_method(robot_start_time)?.data;
+        let data = coll.call_method(robot_start_time)?.unwrap().data;
         assert!(data.is_some());
         let data = data.unwrap();
         match data {
@@ -456,4 +896,92 @@ mod tests {
         };
         Ok(())
     }
+
+    #[test_log::test]
+    fn test_collect_aggregated_data() -> Result<(), DataCollectionError> {
+        let robot_start_time = Instant::now();
+        let kind_map = HashMap::from([
+            (
+                "method".to_string(),
+                Kind::StringValue("Readings".to_string()),
+            ),
+            ("capture_frequency_hz".to_string(), Kind::NumberValue(10.0)),
+            (
+                "method_aggregation".to_string(),
+                Kind::StringValue("mean".to_string()),
+            ),
+            ("aggregation_window_s".to_string(), Kind::NumberValue(0.4)),
+        ]);
+        let conf_kind = Kind::StructValue(kind_map);
+        let conf =
+            DataCollectorConfig::try_from(&conf_kind).expect("data collector config parse failed");
+        assert_eq!(conf.method_aggregation, MethodAggregation::Mean);
+        assert_eq!(conf.aggregation_window_s, Some(0.4));
+
+        let resource = ResourceType::Sensor(Arc::new(Mutex::new(CountingSensor { count: 0.0 })));
+        let mut coll = DataCollector::from_config("counting".to_string(), resource, &conf)?;
+        for _ in 0..3 {
+            assert!(coll.call_method(robot_start_time)?.is_none());
+        }
+        let data = coll.call_method(robot_start_time)?.unwrap();
+        let (count, label) = aggregated_readings(data.data);
+        assert_eq!(count, 2.5);
+        assert_eq!(label, "capture 4");
+
+        // the next window starts over
+        for _ in 0..3 {
+            assert!(coll.call_method(robot_start_time)?.is_none());
+        }
+        let (count, _) = aggregated_readings(coll.call_method(robot_start_time)?.unwrap().data);
+        assert_eq!(count, 6.5);
+
+        for (aggregation, expected) in
+            [(MethodAggregation::Max, 3.0), (MethodAggregation::Min, 1.0)]
+        {
+            let resource =
+                ResourceType::Sensor(Arc::new(Mutex::new(CountingSensor { count: 0.0 })));
+            let mut coll = DataCollector::new(
+                "counting".to_string(),
+                resource,
+                CollectionMethod::Readings,
+                10.0,
+                1000,
+            )?
+            .with_aggregation(aggregation, Duration::from_millis(300))?;
+            assert!(coll.call_method(robot_start_time)?.is_none());
+            assert!(coll.call_method(robot_start_time)?.is_none());
+            let (count, label) =
+                aggregated_readings(coll.call_method(robot_start_time)?.unwrap().data);
+            assert_eq!(count, expected);
+            assert_eq!(label, "capture 3");
+        }
+
+        let resource = ResourceType::Sensor(Arc::new(Mutex::new(FakeSensor::new())));
+        let mut coll = DataCollector::new(
+            "fake".to_string(),
+            resource,
+            CollectionMethod::Readings,
+            10.0,
+            1000,
+        )?
+        .with_aggregation(MethodAggregation::None, Duration::from_secs(1))?;
+        assert!(coll.call_method(robot_start_time)?.is_some());
+
+        let kind_map = HashMap::from([
+            (
+                "method".to_string(),
+                Kind::StringValue("Readings".to_string()),
+            ),
+            ("capture_frequency_hz".to_string(), Kind::NumberValue(10.0)),
+            (
+                "method_aggregation".to_string(),
+                Kind::StringValue("max".to_string()),
+            ),
+        ]);
+        assert!(matches!(
+            DataCollectorConfig::try_from(&Kind::StructValue(kind_map)),
+            Err(AttributeError::KeyNotFound(_))
+        ));
+        Ok(())
+    }
 }
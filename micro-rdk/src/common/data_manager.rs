@@ -1,3 +1,5 @@
+use std::cell::Cell;
+use std::collections::HashMap;
 use std::pin::Pin;
 use std::rc::Rc;
 use std::time::{Duration, Instant};
@@ -5,21 +7,26 @@ use std::time::{Duration, Instant};
 use crate::common::data_collector::{DataCollectionError, DataCollector};
 use crate::common::data_store::DataStore;
 use crate::google::protobuf::value::Kind;
-use crate::google::protobuf::{Struct, Timestamp};
+use crate::google::protobuf::{Struct, Timestamp, Value};
 use crate::proto::app::data_sync::v1::{
     DataCaptureUploadRequest, DataType, SensorData, UploadMetadata,
 };
 use crate::proto::app::v1::{RobotConfig, ServiceConfig};
 
 use super::app_client::{AppClient, AppClientError, PeriodicAppClientTask, VIAM_FOUNDING_YEAR};
+use super::conn::network::{Connectivity, CONNECTIVITY};
 use super::data_collector::ResourceMethodKey;
 use super::data_store::{DataStoreError, DataStoreReader, WriteMode};
+use super::generic::{DoCommand, DoCommandFuture, GenericComponent, GenericError};
+use super::metrics;
 use super::robot::{LocalRobot, RobotError};
+use super::status::{Status, StatusError};
+use async_channel::{Receiver, Sender};
 use async_io::Timer;
 use bytes::BytesMut;
 use chrono::offset::Local;
 use chrono::Datelike;
-use futures_lite::prelude::Future;
+use futures_lite::{future, prelude::Future};
 use futures_util::lock::Mutex as AsyncMutex;
 use prost::Message;
 use thiserror::Error;
@@ -29,6 +36,10 @@ use thiserror::Error;
 // the smaller amount of available RAM, we've halved it
 static MAX_SENSOR_CONTENTS_SIZE: usize = 32000;
 
+// How long a `sync_now` command waits for the sync task, which only runs while connected to app,
+// short enough not to hold the caller of the DoCommand while the machine is offline
+const SYNC_NOW_TIMEOUT: Duration = Duration::from_secs(10);
+
 type CollectedReadings = Vec<(ResourceMethodKey, Result<SensorData, DataCollectionError>)>;
 
 /// Allow for a C project using micro-RDK as a library to implement a callback to be run
@@ -66,7 +77,33 @@ pub enum DataManagerError {
     InitializationRobotError(#[from] RobotError),
 }
 
-fn get_data_service_config(
+/// Seconds since the epoch at the start of the year Viam was founded, earlier stored timestamps
+/// are offsets from the start of the robot rather than wall-clock times
+pub(crate) const WALL_CLOCK_MIN_SECS: i64 = 1_577_836_800;
+
+/// Whether a stored timestamp is already a wall-clock time, as written by stores outliving the
+/// robot such as the native disk spool, and needs no correction before upload
+pub(crate) fn is_wall_clock_timestamp(timestamp: &Timestamp) -> bool {
+    timestamp.seconds >= WALL_CLOCK_MIN_SECS
+}
+
+/// Milliseconds since the epoch, None until the clock has been set
+fn wall_clock_ms() -> Option<u64> {
+    let now = Local::now();
+    // a date before Viam was founded means settimeofday was never called
+    (now.year() >= VIAM_FOUNDING_YEAR).then(|| now.timestamp_millis() as u64)
+}
+
+// Whether collectors capturing every `ticks` min intervals are due at `loop_counter` relative to
+// boot and at `clock_tick` (min intervals since the epoch) when aligned to the clock. Before the
+// clock is set collectors aligned to it are captured relative to boot.
+fn collection_due(ticks: u64, loop_counter: u64, clock_tick: Option<u64>) -> (bool, bool) {
+    let relative_due = loop_counter % ticks == 0;
+    let aligned_due = clock_tick.map_or(relative_due, |tick| tick % ticks == 0);
+    (relative_due, aligned_due)
+}
+
+pub(crate) fn get_data_service_config(
     robot_config: &RobotConfig,
 ) -> Result<Option<ServiceConfig>, DataManagerError> {
     let num_configs_detected = robot_config
@@ -84,6 +121,16 @@ fn get_data_service_config(
         .cloned())
 }
 
+fn get_pause_capture_when_offline(attrs: &Struct) -> Result<bool, DataManagerError> {
+    match attrs.fields.get("pause_capture_when_offline") {
+        None => Ok(false),
+        Some(Value {
+            kind: Some(Kind::BoolValue(pause)),
+        }) => Ok(*pause),
+        Some(_) => Err(DataManagerError::ConfigError),
+    }
+}
+
 fn get_data_sync_interval(attrs: &Struct) -> Result<Option<Duration>, DataManagerError> {
     Ok(
         // If cloud sync is disabled, we'll communicate this by having the sync interval be None
@@ -117,6 +164,8 @@ pub struct DataManager<StoreType> {
     sync_interval: Option<Duration>,
     min_interval: Duration,
     robot_part_id: String,
+    pause_capture_when_offline: bool,
+    connectivity: &'static Connectivity,
 }
 
 impl<StoreType> DataManager<StoreType>
@@ -137,9 +186,30 @@ where
             sync_interval,
             min_interval,
             robot_part_id,
+            pause_capture_when_offline: false,
+            connectivity: &CONNECTIVITY,
         })
     }
 
+    /// Stop capturing data while the network is disconnected (see
+    /// [`is_network_connected`](super::conn::network::is_network_connected)), saving power and
+    /// storage when data can't be synced anyway
+    pub fn with_pause_capture_when_offline(mut self, pause: bool) -> Self {
+        self.pause_capture_when_offline = pause;
+        self
+    }
+
+    // Follow `connectivity` rather than the connectivity of the server's network
+    #[cfg(test)]
+    fn with_connectivity(mut self, connectivity: &'static Connectivity) -> Self {
+        self.connectivity = connectivity;
+        self
+    }
+
+    fn capture_paused(&self) -> bool {
+        self.pause_capture_when_offline && !self.connectivity.is_connected()
+    }
+
     pub fn from_robot_and_config(
         robot: &LocalRobot,
         cfg: &RobotConfig,
@@ -169,9 +239,11 @@ where
                     .iter()
                     .map(|c| (c.resource_method_key(), c.capacity()))
                     .collect();
-                let store = StoreType::from_resource_method_settings(collector_settings)?;
+                let store = StoreType::from_service_attributes(collector_settings, &attrs)?;
+                let pause_capture_when_offline = get_pause_capture_when_offline(&attrs)?;
                 let data_manager_svc =
-                    DataManager::new(collectors, store, sync_interval, robot.part_id.clone())?;
+                    DataManager::new(collectors, store, sync_interval, robot.part_id.clone())?
+                        .with_pause_capture_when_offline(pause_capture_when_offline);
                 Ok(Some(data_manager_svc))
             }
         } else {
@@ -205,20 +277,44 @@ where
         intervals
     }
 
+    // Time until the next capture, collectors aligned to the clock are captured on multiples of
+    // the min interval since the epoch once the clock is set
+    fn next_capture_delay(&self) -> Duration {
+        match wall_clock_ms() {
+            Some(now_ms) if self.collectors.iter().any(|c| c.aligned_to_clock()) => {
+                let min_interval_ms = self.min_interval_ms();
+                Duration::from_millis(min_interval_ms - now_ms % min_interval_ms)
+            }
+            _ => self.min_interval,
+        }
+    }
+
     pub async fn data_collection_task(&mut self, robot_start_time: Instant) -> ! {
         let mut loop_counter: u64 = 0;
+        let mut paused = false;
         loop {
-            if let Err(e) = self
-                .collect_data_inner(loop_counter, robot_start_time)
-                .await
-            {
+            if paused != self.capture_paused() {
+                paused = !paused;
+                if paused {
+                    log::info!("network disconnected, pausing data capture");
+                } else {
+                    log::info!("network connected, resuming data capture");
+                }
+            }
+            let res = if paused {
+                Ok(())
+            } else {
+                self.collect_data_inner(loop_counter, robot_start_time)
+                    .await
+            };
+            if let Err(e) = res {
                 log::error!(
                     "data manager error {:?}, will attempt to continue collecting",
                     e
                 );
             }
             loop_counter += 1;
-            Timer::after(self.min_interval).await;
+            Timer::after(self.next_capture_delay()).await;
         }
     }
 
@@ -227,11 +323,22 @@ where
         loop_counter: u64,
         robot_start_time: Instant,
     ) -> Result<(), DataManagerError> {
+        // rounded as the task may wake up slightly after the boundary
+        let clock_tick = wall_clock_ms()
+            .map(|now_ms| (now_ms + self.min_interval_ms() / 2) / self.min_interval_ms());
         let min_interval_ms = self.min_interval_ms();
         for interval in self.collection_intervals() {
-            if loop_counter % (interval / min_interval_ms) == 0 {
-                self.collect_and_store_readings(interval, robot_start_time)
-                    .await?;
+            let (relative_due, aligned_due) =
+                collection_due(interval / min_interval_ms, loop_counter, clock_tick);
+            if relative_due || aligned_due {
+                self.collect_and_store_readings(interval, robot_start_time, |coll| {
+                    if coll.aligned_to_clock() {
+                        aligned_due
+                    } else {
+                        relative_due
+                    }
+                })
+                .await?;
             }
         }
         Ok(())
@@ -241,25 +348,33 @@ where
         &mut self,
         time_interval_ms: u64,
         robot_start_time: Instant,
+        due: impl Fn(&DataCollector) -> bool,
     ) -> Result<(), DataManagerError> {
-        let readings = self.collect_readings_for_interval(time_interval_ms, robot_start_time)?;
+        let readings =
+            self.collect_readings_for_interval(time_interval_ms, robot_start_time, due)?;
         let mut store_guard = self.store.lock().await;
         for (collector_key, reading) in readings {
             match reading {
-                Err(e) => log::error!(
-                    "collector {} failed to collect data reason {:?}",
-                    &collector_key,
-                    e
-                ),
+                Err(e) => {
+                    metrics::data_capture_failed();
+                    log::error!(
+                        "collector {} failed to collect data reason {:?}",
+                        &collector_key,
+                        e
+                    )
+                }
                 Ok(data) => {
                     if let Err(e) =
                         store_guard.write_message(&collector_key, data, WriteMode::OverwriteOldest)
                     {
+                        metrics::data_store_failed();
                         log::error!(
                             "couldn't store data for collector {:?} error : {:?}",
                             collector_key,
                             e
                         );
+                    } else {
+                        metrics::data_captured();
                     }
                 }
             }
@@ -269,11 +384,12 @@ where
 
     // Here, time_interval_ms is required to be a multiple of the minimum time_interval among the collectors.
     // This function then collects readings from collectors whose time_interval is greater than time_interval_ms but
-    // less than the next largest multiple of self.min_interval_ms
+    // less than the next largest multiple of self.min_interval_ms, among those `due` returns true for
     fn collect_readings_for_interval(
         &mut self,
         time_interval_ms: u64,
         robot_start_time: Instant,
+        due: impl Fn(&DataCollector) -> bool,
     ) -> Result<CollectedReadings, DataManagerError> {
         let min_interval_ms = self.min_interval_ms();
         if time_interval_ms % min_interval_ms != 0 {
@@ -287,29 +403,36 @@ where
             .filter(|coll| {
                 (coll.time_interval().as_millis() as u64 / min_interval_ms)
                     == (time_interval_ms / min_interval_ms)
+                    && due(coll)
             })
-            .map(|coll| {
-                Ok((
-                    coll.resource_method_key(),
-                    coll.call_method(robot_start_time),
-                ))
+            .filter_map(|coll| {
+                // aggregating collectors only produce data at the end of their window
+                coll.call_method(robot_start_time)
+                    .transpose()
+                    .map(|data| Ok((coll.resource_method_key(), data)))
             })
             .collect()
     }
 
     pub fn get_sync_task(&self, robot_start_time: Instant) -> Option<DataSyncTask<StoreType>> {
         if let Some(sync_interval) = self.sync_interval {
-            let resource_method_keys: Vec<ResourceMethodKey> = self
+            // the data of every collector is synced as soon as the task starts running
+            let collectors: Vec<CollectorSync> = self
                 .collectors
                 .iter()
-                .map(|coll| coll.resource_method_key())
+                .map(|coll| CollectorSync {
+                    key: coll.resource_method_key(),
+                    sync_interval: coll.sync_interval().unwrap_or(sync_interval),
+                    next_sync: Cell::new(Instant::now()),
+                })
                 .collect();
             Some(DataSyncTask {
                 store: self.store.clone(),
-                resource_method_keys,
+                collectors,
                 sync_interval,
                 part_id: self.part_id(),
                 robot_start_time,
+                sync_requests: async_channel::bounded(1),
             })
         } else {
             None
@@ -327,22 +450,132 @@ pub enum DataSyncError {
     TimeOutOfBoundsError,
     #[error("current time unset")]
     NoCurrentTime,
+    #[error("data sync didn't complete in time, is the machine connected to app?")]
+    SyncUnavailable,
+}
+
+/// Number of datapoints uploaded, or lost while attempting to, by a sync
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SyncCounts {
+    pub synced: usize,
+    pub failed: usize,
+}
+
+impl From<SyncCounts> for Struct {
+    fn from(value: SyncCounts) -> Self {
+        let number = |n: usize| Value {
+            kind: Some(Kind::NumberValue(n as f64)),
+        };
+        Struct {
+            fields: HashMap::from([
+                ("synced".to_string(), number(value.synced)),
+                ("failed".to_string(), number(value.failed)),
+            ]),
+        }
+    }
+}
+
+type SyncReply = Sender<SyncCounts>;
+
+// Uploads captured data to app, the sync logic being tested against a fake
+trait DataUploader {
+    fn upload_data(
+        &self,
+        data_req: DataCaptureUploadRequest,
+    ) -> Pin<Box<dyn Future<Output = Result<(), AppClientError>> + '_>>;
+}
+
+impl DataUploader for AppClient {
+    fn upload_data(
+        &self,
+        data_req: DataCaptureUploadRequest,
+    ) -> Pin<Box<dyn Future<Output = Result<(), AppClientError>> + '_>> {
+        Box::pin(AppClient::upload_data(self, data_req))
+    }
+}
+
+/// Generic component, named after the data manager service, answering `{"sync_now": true}` by
+/// syncing the data of every collector right away and returning the [`SyncCounts`] of the sync.
+/// An error is returned when the sync didn't complete within 10 seconds, a sync still in progress
+/// carries on.
+pub struct DataSyncTrigger {
+    sync_requests: Sender<SyncReply>,
+    timeout: Duration,
+}
+
+impl DoCommand for DataSyncTrigger {
+    fn do_command_async(&mut self, command_struct: Option<Struct>) -> DoCommandFuture {
+        let sync_now = command_struct
+            .as_ref()
+            .and_then(|cmd| cmd.fields.get("sync_now"))
+            .is_some_and(|v| matches!(v.kind, Some(Kind::BoolValue(true))));
+        if !sync_now {
+            return Box::pin(std::future::ready(Err(GenericError::MethodUnimplemented(
+                "do_command",
+            ))));
+        }
+        let sync_requests = self.sync_requests.clone();
+        let timeout = self.timeout;
+        Box::pin(async move {
+            let (reply, counts) = async_channel::bounded(1);
+            // the request is dropped along with `counts` once timed out, sending waits for the
+            // sync task to pick up a pending request so they don't pile up while it isn't running
+            future::or(
+                async {
+                    sync_requests.send(reply).await.ok()?;
+                    counts.recv().await.ok()
+                },
+                async {
+                    Timer::after(timeout).await;
+                    None
+                },
+            )
+            .await
+            .map(|counts| Some(counts.into()))
+            .ok_or(GenericError::Other(Box::new(
+                DataSyncError::SyncUnavailable,
+            )))
+        })
+    }
+}
+
+impl Status for DataSyncTrigger {
+    fn get_status(&self) -> Result<Option<Struct>, StatusError> {
+        Ok(Some(Struct::default()))
+    }
+}
+
+impl GenericComponent for DataSyncTrigger {}
+
+struct CollectorSync {
+    key: ResourceMethodKey,
+    sync_interval: Duration,
+    next_sync: Cell<Instant>,
 }
 
 pub struct DataSyncTask<StoreType> {
     store: Rc<AsyncMutex<StoreType>>,
-    resource_method_keys: Vec<ResourceMethodKey>,
+    collectors: Vec<CollectorSync>,
     sync_interval: Duration,
     part_id: String,
     // used for time correcting stored data before upload, see DataSyncTask::run
     // and create_time_corrected_reading below
     robot_start_time: Instant,
+    sync_requests: (Sender<SyncReply>, Receiver<SyncReply>),
 }
 
 impl<StoreType> DataSyncTask<StoreType>
 where
     StoreType: DataStore,
 {
+    /// A generic component triggering a sync of all collectors on demand, see [`DataSyncTrigger`]
+    pub fn sync_trigger(&self) -> DataSyncTrigger {
+        DataSyncTrigger {
+            sync_requests: self.sync_requests.0.clone(),
+            timeout: SYNC_NOW_TIMEOUT,
+        }
+    }
+
     #[cfg(test)]
     async fn get_store_lock(&mut self) -> futures_util::lock::MutexGuard<StoreType> {
         self.store.lock().await
@@ -353,7 +586,11 @@ where
         stored_time: Timestamp,
     ) -> Result<chrono::Duration, DataSyncError> {
         let stored_time_dur = Duration::new(stored_time.seconds as u64, stored_time.nanos as u32);
-        let time_to_subtract = self.robot_start_time.elapsed() - stored_time_dur;
+        let time_to_subtract = self
+            .robot_start_time
+            .elapsed()
+            .checked_sub(stored_time_dur)
+            .ok_or(DataSyncError::TimeOutOfBoundsError)?;
         let time_to_subtract = chrono::Duration::new(
             time_to_subtract.as_secs() as i64,
             time_to_subtract.subsec_nanos(),
@@ -366,7 +603,8 @@ where
         let mut msg = SensorData::decode(raw_msg)?;
         // the timestamps of the stored data are measured as offsets from a starting
         // instant (robot_start_time, acquired from DataSyncTask), so we adjust the
-        // timestamps on the parsed message based on the current time (if it is now available)
+        // timestamps on the parsed message based on the current time (if it is now available).
+        // Timestamps already stored as wall-clock times are left as is.
         if let Some(metadata) = msg.metadata.as_mut() {
             let current_dt = Local::now().fixed_offset();
             // Viam was founded in 2020, so if the current time is set to any time before that
@@ -374,7 +612,11 @@ where
             if current_dt.year() < VIAM_FOUNDING_YEAR {
                 return Err(DataSyncError::NoCurrentTime);
             }
-            if let Some(time_received) = metadata.time_received.clone() {
+            if let Some(time_received) = metadata
+                .time_received
+                .clone()
+                .filter(|t| !is_wall_clock_timestamp(t))
+            {
                 let time_to_subtract = self.get_time_to_subtract(time_received)?;
                 let time_received = current_dt - time_to_subtract;
                 metadata.time_received = Some(Timestamp {
@@ -382,7 +624,11 @@ where
                     nanos: time_received.timestamp_subsec_nanos() as i32,
                 });
             }
-            if let Some(time_requested) = metadata.time_requested.clone() {
+            if let Some(time_requested) = metadata
+                .time_requested
+                .clone()
+                .filter(|t| !is_wall_clock_timestamp(t))
+            {
                 let time_to_subtract = self.get_time_to_subtract(time_requested)?;
                 let time_requested = current_dt - time_to_subtract;
                 metadata.time_requested = Some(Timestamp {
@@ -394,8 +640,18 @@ where
         Ok(msg)
     }
 
-    async fn run<'b>(&self, app_client: &'b AppClient) -> Result<(), AppClientError> {
-        for collector_key in self.resource_method_keys.iter() {
+    // syncs the collectors `due` returns true for, counting the datapoints synced or lost in `counts`
+    async fn run(
+        &self,
+        app_client: &impl DataUploader,
+        due: impl Fn(&CollectorSync) -> bool,
+        counts: &mut SyncCounts,
+    ) -> Result<(), AppClientError> {
+        'collectors: for collector in self.collectors.iter().filter(|c| due(c)) {
+            collector
+                .next_sync
+                .set(Instant::now() + collector.sync_interval);
+            let collector_key = &collector.key;
             // Since a write may occur in between uploading consecutive chunks of data, we want to make
             // sure only to process the messages initially present in this region of the store.
             let total_messages = {
@@ -422,12 +678,13 @@ where
                 continue;
             }
             let max_messages_per_chunk = std::cmp::max(10, total_messages.div_ceil(10));
-            let mut messages_processed = 0;
+            let mut messages_read = 0;
+            // the message that didn't fit in the previous chunk, already flushed from the store
+            let mut next_chunk_first_message: Option<BytesMut> = None;
 
-            let mut current_chunk: Vec<BytesMut> = vec![];
             // we process the data for this region of the store in chunks, each iteration of this loop
             // should represent the processing and uploading of a single chunk of data
-            loop {
+            while messages_read < total_messages || next_chunk_first_message.is_some() {
                 let store_lock = self.store.lock().await;
                 let mut reader = match store_lock.get_reader(collector_key) {
                     Ok(reader) => reader,
@@ -440,50 +697,24 @@ where
                         break;
                     }
                 };
-                let next_message = match reader.read_next_message() {
-                    Ok(msg) => {
-                        // this can occur when the last message in the store was the last message
-                        // in the previously uploaded chunk
-                        if msg.is_empty() {
-                            break;
-                        }
-                        messages_processed += 1;
-                        msg
-                    }
-                    Err(err) => {
-                        log::error!(
-                            "error reading message from store for collector key ({:?}): {:?}",
-                            collector_key,
-                            err
-                        );
-
-                        // we don't want to panic, and creating an AppClientError variant for this case
-                        // feels too specific, so we'll move on to the next collector
-                        break;
-                    }
-                };
-
-                if next_message.len() > MAX_SENSOR_CONTENTS_SIZE {
-                    log::error!(
-                        "message encountered that was too large (>32K bytes) for collector {:?}",
-                        collector_key
-                    );
-                } else {
-                    current_chunk.push(next_message);
-                }
+                let carried_over = usize::from(next_chunk_first_message.is_some());
+                let mut current_chunk: Vec<BytesMut> =
+                    next_chunk_first_message.take().into_iter().collect();
+                let mut current_chunk_size: usize = current_chunk.iter().map(|c| c.len()).sum();
 
                 // We want to fill current_chunk until its size reaches just under
                 // MAX_SENSOR_CONTENTS_SIZE and then upload the data. Since we will have
                 // needed to pull the first message of the next chunk to realize that
-                // we have reached capacity, we return that message as well to be placed
-                // at the beginning of the now empty current_chunk once we have successfully
-                // uploaded it
-                let (upload_data, next_chunk_first_message) = loop {
+                // we have reached capacity, that message is kept to be placed at the
+                // beginning of the next chunk
+                while messages_read < total_messages {
                     let next_message = match reader.read_next_message() {
-                        Ok(msg) => {
-                            messages_processed += 1;
-                            msg
+                        // this can occur when data was dropped from the store since it was counted
+                        Ok(msg) if msg.is_empty() => {
+                            messages_read = total_messages;
+                            break;
                         }
+                        Ok(msg) => msg,
                         Err(err) => {
                             log::error!(
                                 "error reading message from store for collector key ({:?}): {:?}",
@@ -494,9 +725,11 @@ where
                             // we don't want to panic, and creating an AppClientError variant for this case
                             // feels too specific, so we'll move on to the next collector without flushing
                             // this region of the store
-                            break (vec![], None);
+                            counts.failed += carried_over;
+                            continue 'collectors;
                         }
                     };
+                    messages_read += 1;
 
                     // skip this message if it's too big
                     if next_message.len() > MAX_SENSOR_CONTENTS_SIZE {
@@ -504,39 +737,40 @@ where
                             "message encountered that was too large (>32K bytes) for collector {:?}",
                             collector_key
                         );
+                        counts.failed += 1;
                         continue;
                     }
-                    let current_chunk_size: usize = current_chunk.iter().map(|c| c.len()).sum();
-                    if (messages_processed >= total_messages)
-                        || ((next_message.len() + current_chunk_size) > MAX_SENSOR_CONTENTS_SIZE)
-                        || ((current_chunk.len() + 1) > max_messages_per_chunk)
-                        || (next_message.is_empty())
+                    if (next_message.len() + current_chunk_size > MAX_SENSOR_CONTENTS_SIZE)
+                        || (current_chunk.len() + 1 > max_messages_per_chunk)
                     {
-                        let data: Result<Vec<SensorData>, DataSyncError> = current_chunk
-                            .drain(..)
-                            .map(|msg| self.get_time_corrected_reading(msg))
-                            .collect();
-                        let data = match data {
-                            Ok(data) => data,
-                            Err(DataSyncError::NoCurrentTime) => {
-                                log::error!("Could not calculate data timestamps, returning without flushing store");
-                                return Ok(());
-                            }
-                            Err(err) => {
-                                log::error!(
-                                    "error decoding readings for collector key ({:?}): {:?}",
-                                    collector_key,
-                                    err
-                                );
-                                vec![]
-                            }
-                        };
-                        if next_message.is_empty() {
-                            break (data, None);
-                        }
-                        break (data, Some(next_message));
-                    } else {
-                        current_chunk.push(next_message);
+                        next_chunk_first_message = Some(next_message);
+                        break;
+                    }
+                    current_chunk_size += next_message.len();
+                    current_chunk.push(next_message);
+                }
+
+                let chunk_len = current_chunk.len();
+                let upload_data: Result<Vec<SensorData>, DataSyncError> = current_chunk
+                    .into_iter()
+                    .map(|msg| self.get_time_corrected_reading(msg))
+                    .collect();
+                let upload_data = match upload_data {
+                    Ok(data) => data,
+                    Err(DataSyncError::NoCurrentTime) => {
+                        log::error!(
+                            "Could not calculate data timestamps, returning without flushing store"
+                        );
+                        return Ok(());
+                    }
+                    Err(err) => {
+                        log::error!(
+                            "error decoding readings for collector key ({:?}): {:?}",
+                            collector_key,
+                            err
+                        );
+                        counts.failed += chunk_len;
+                        vec![]
                     }
                 };
 
@@ -566,26 +800,20 @@ where
                     };
                     match app_client.upload_data(upload_request).await {
                         Ok(_) => {
-                            if let Some(next_message) = next_chunk_first_message {
-                                current_chunk = vec![next_message];
-                            }
+                            counts.synced += data_len;
                             #[cfg(feature = "data-upload-hook-unstable")]
                             unsafe {
                                 micro_rdk_data_manager_post_upload_hook();
                             }
                         }
                         Err(err) => {
-                            log::error!(
-                                "error uploading data, data lost ({:?} messages)",
-                                data_len + 1
-                            );
+                            let lost = data_len + usize::from(next_chunk_first_message.is_some());
+                            log::error!("error uploading data, data lost ({:?} messages)", lost);
+                            counts.failed += lost;
                             return Err(err);
                         }
                     };
                 }
-                if messages_processed >= total_messages {
-                    break;
-                }
             }
         }
         Ok(())
@@ -602,11 +830,60 @@ where
     fn get_default_period(&self) -> Duration {
         self.sync_interval
     }
+    // Waits for the next collector to be due itself rather than relying on the period so that a
+    // sync request (see `DataSyncTrigger`) can wake it up, hence asks to be invoked again right away
     fn invoke<'b, 'a: 'b>(
         &'a self,
         app_client: &'b AppClient,
     ) -> Pin<Box<dyn Future<Output = Result<Option<Duration>, AppClientError>> + 'b>> {
-        Box::pin(async move { self.run(app_client).await.map(|_| None) })
+        Box::pin(self.sync(app_client))
+    }
+}
+
+impl<StoreType> DataSyncTask<StoreType>
+where
+    StoreType: DataStore,
+{
+    // syncs the collectors once due, or all of them when requested
+    async fn sync(
+        &self,
+        app_client: &impl DataUploader,
+    ) -> Result<Option<Duration>, AppClientError> {
+        let next_sync = self
+            .collectors
+            .iter()
+            .map(|c| c.next_sync.get())
+            .min()
+            .unwrap_or_else(|| Instant::now() + self.sync_interval);
+        let reply = future::or(
+            async {
+                Timer::at(next_sync).await;
+                None
+            },
+            async {
+                loop {
+                    let reply = self.sync_requests.1.recv().await.ok()?;
+                    // nobody waits for the counts of a request that timed out
+                    if !reply.is_closed() {
+                        break Some(reply);
+                    }
+                }
+            },
+        )
+        .await;
+        let now = Instant::now();
+        let mut counts = SyncCounts::default();
+        let res = self
+            .run(
+                app_client,
+                |c| reply.is_some() || c.next_sync.get() <= now,
+                &mut counts,
+            )
+            .await;
+        if let Some(reply) = reply {
+            let _ = reply.try_send(counts);
+        }
+        res.map(|_| Some(Duration::ZERO))
     }
 }
 
@@ -614,19 +891,29 @@ where
 mod tests {
     use std::cell::RefCell;
     use std::collections::HashMap;
+    use std::future::Future;
     use std::mem::MaybeUninit;
+    use std::pin::Pin;
     use std::rc::Rc;
     use std::sync::{Arc, Mutex};
     use std::time::{Duration, Instant};
 
     use bytes::BytesMut;
+    use futures_lite::future::zip;
     use prost::Message;
     use ringbuf::{LocalRb, Rb};
 
-    use super::DataManager;
+    use super::{
+        collection_due, get_pause_capture_when_offline, DataManager, DataManagerError,
+        DataSyncError, DataSyncTask, DataUploader, SyncCounts,
+    };
+    use crate::common::app_client::AppClientError;
+    use crate::common::conn::network::{Connectivity, FakeNetwork};
     use crate::common::data_collector::DataCollectionError;
-    use crate::common::data_store::{DataStoreReader, WriteMode};
+    use crate::common::data_store::{DataStoreReader, DefaultDataStore, WriteMode};
     use crate::common::encoder::EncoderError;
+    use crate::common::exec::Executor;
+    use crate::common::generic::{DoCommand, GenericError};
     use crate::common::{
         data_collector::{
             CollectionMethod, DataCollector, ResourceMethodKey, DEFAULT_CACHE_SIZE_KB,
@@ -640,8 +927,10 @@ mod tests {
         status::{Status, StatusError},
     };
     use crate::google::protobuf::value::Kind;
-    use crate::google::protobuf::Struct;
-    use crate::proto::app::data_sync::v1::{sensor_data::Data, SensorData};
+    use crate::google::protobuf::{Struct, Timestamp};
+    use crate::proto::app::data_sync::v1::{
+        sensor_data::Data, DataCaptureUploadRequest, SensorData, SensorMetadata,
+    };
 
     #[derive(DoCommand)]
     struct TestSensorFailure {}
@@ -785,6 +1074,71 @@ mod tests {
         );
     }
 
+    #[test_log::test]
+    fn test_pause_capture_when_offline() {
+        let attrs = |value: Option<Kind>| Struct {
+            fields: value
+                .map(|kind| {
+                    (
+                        "pause_capture_when_offline".to_owned(),
+                        crate::google::protobuf::Value { kind: Some(kind) },
+                    )
+                })
+                .into_iter()
+                .collect(),
+        };
+        assert!(!get_pause_capture_when_offline(&attrs(None)).unwrap());
+        assert!(get_pause_capture_when_offline(&attrs(Some(Kind::BoolValue(true)))).unwrap());
+        assert!(matches!(
+            get_pause_capture_when_offline(&attrs(Some(Kind::StringValue("true".to_owned())))),
+            Err(DataManagerError::ConfigError)
+        ));
+
+        static CONNECTIVITY: Connectivity = Connectivity::new();
+        let network = FakeNetwork::new(std::net::Ipv4Addr::LOCALHOST);
+        let collector = DataCollector::new(
+            "r1".to_string(),
+            ResourceType::Sensor(Arc::new(Mutex::new(TestSensor {}))),
+            CollectionMethod::Readings,
+            100.0,
+            (DEFAULT_CACHE_SIZE_KB * 1000.0) as usize,
+        )
+        .unwrap();
+        let key = collector.resource_method_key();
+        let store =
+            DefaultDataStore::from_resource_method_settings(vec![(key.clone(), 1000)]).unwrap();
+        let mut data_manager = DataManager::new(vec![collector], store, None, "1".to_string())
+            .unwrap()
+            .with_pause_capture_when_offline(true)
+            .with_connectivity(&CONNECTIVITY);
+
+        let exec = Executor::new();
+        let start = Instant::now();
+        let mut capture_for = |duration: Duration| {
+            exec.block_on(futures_lite::future::or(
+                async {
+                    data_manager.data_collection_task(start).await;
+                },
+                async {
+                    async_io::Timer::after(duration).await;
+                },
+            ));
+            exec.block_on(data_manager.get_store_lock())
+                .get_reader(&key)
+                .unwrap()
+                .messages_remaining()
+                .unwrap()
+        };
+
+        network.set_connected(false);
+        CONNECTIVITY.update(&network);
+        assert_eq!(capture_for(Duration::from_millis(100)), 0);
+
+        network.set_connected(true);
+        CONNECTIVITY.update(&network);
+        assert!(capture_for(Duration::from_millis(100)) > 0);
+    }
+
     #[test_log::test]
     fn test_collect_readings_for_interval() {
         let robot_start_time = Instant::now();
@@ -835,7 +1189,8 @@ mod tests {
         assert!(data_manager.is_ok());
         let mut data_manager = data_manager.unwrap();
 
-        let sensor_data = data_manager.collect_readings_for_interval(100, robot_start_time);
+        let sensor_data =
+            data_manager.collect_readings_for_interval(100, robot_start_time, |_| true);
         assert!(sensor_data.is_ok());
         let sensor_data = sensor_data.unwrap();
         let sensor_data: Vec<(ResourceMethodKey, SensorData)> = sensor_data
@@ -911,6 +1266,42 @@ mod tests {
         };
     }
 
+    #[test_log::test]
+    fn test_clock_aligned_collection() {
+        assert_eq!(collection_due(3, 0, None), (true, true));
+        assert_eq!(collection_due(3, 1, None), (false, false));
+        assert_eq!(collection_due(3, 1, Some(6)), (false, true));
+        assert_eq!(collection_due(3, 3, Some(7)), (true, false));
+
+        let robot_start_time = Instant::now();
+        let aligned = DataCollector::new(
+            "aligned".to_string(),
+            ResourceType::Sensor(Arc::new(Mutex::new(TestSensor {}))),
+            CollectionMethod::Readings,
+            10.0,
+            (DEFAULT_CACHE_SIZE_KB * 1000.0) as usize,
+        )
+        .unwrap()
+        .with_clock_alignment(true);
+        assert!(aligned.aligned_to_clock());
+        let relative = DataCollector::new(
+            "relative".to_string(),
+            ResourceType::Sensor(Arc::new(Mutex::new(TestSensor {}))),
+            CollectionMethod::Readings,
+            10.0,
+            (DEFAULT_CACHE_SIZE_KB * 1000.0) as usize,
+        )
+        .unwrap();
+        let mut data_manager =
+            DataManager::new(vec![aligned, relative], NoOpStore {}, None, "1".to_string()).unwrap();
+
+        let readings = data_manager
+            .collect_readings_for_interval(100, robot_start_time, |coll| coll.aligned_to_clock())
+            .unwrap();
+        assert_eq!(readings.len(), 1);
+        assert_eq!(readings[0].0.r_name, "aligned");
+    }
+
     #[test_log::test]
     fn test_collect_readings_for_interval_failure() {
         let robot_start_time = Instant::now();
@@ -947,7 +1338,7 @@ mod tests {
         let mut data_manager = data_manager.unwrap();
 
         let readings = data_manager
-            .collect_readings_for_interval(100, robot_start_time)
+            .collect_readings_for_interval(100, robot_start_time, |_| true)
             .unwrap();
         let readings: Result<Vec<(ResourceMethodKey, SensorData)>, DataCollectionError> =
             readings.into_iter().try_fold(vec![], |mut out, val| {
@@ -1170,4 +1561,256 @@ mod tests {
             assert_eq!(read_data, expected_data);
         });
     }
+
+    #[test_log::test]
+    fn test_time_corrected_reading() {
+        let coll = DataCollector::new(
+            "r1".to_string(),
+            ResourceType::Sensor(Arc::new(Mutex::new(TestSensor {}))),
+            CollectionMethod::Readings,
+            10.0,
+            (DEFAULT_CACHE_SIZE_KB * 1000.0) as usize,
+        )
+        .unwrap();
+        let manager = DataManager::new(
+            vec![coll],
+            NoOpStore {},
+            Some(Duration::from_secs(600)),
+            "1".to_string(),
+        )
+        .unwrap();
+        let robot_start_time = Instant::now() - Duration::from_secs(20);
+        let task = manager.get_sync_task(robot_start_time).unwrap();
+        let timed = |seconds| {
+            let timestamp = Some(Timestamp { seconds, nanos: 0 });
+            SensorData {
+                metadata: Some(SensorMetadata {
+                    time_requested: timestamp.clone(),
+                    time_received: timestamp,
+                }),
+                data: None,
+            }
+        };
+        let corrected = |msg: SensorData| {
+            task.get_time_corrected_reading(BytesMut::from(msg.encode_to_vec().as_slice()))
+        };
+
+        // stored 5s after the robot started, i.e. 15s ago
+        let now = chrono::Local::now().timestamp();
+        let received = corrected(timed(5))
+            .unwrap()
+            .metadata
+            .unwrap()
+            .time_received
+            .unwrap()
+            .seconds;
+        assert!((now - 16..=now - 14).contains(&received));
+
+        // wall-clock times are left as is
+        assert_eq!(
+            corrected(timed(1_700_000_000)).unwrap(),
+            timed(1_700_000_000)
+        );
+
+        // an offset past the current uptime can't be corrected
+        assert!(matches!(
+            corrected(timed(1_000)),
+            Err(DataSyncError::TimeOutOfBoundsError)
+        ));
+    }
+
+    struct FakeUploader {
+        uploads: RefCell<Vec<(String, usize)>>,
+        fail: bool,
+    }
+
+    impl FakeUploader {
+        fn new(fail: bool) -> Self {
+            Self {
+                uploads: RefCell::new(vec![]),
+                fail,
+            }
+        }
+    }
+
+    impl DataUploader for FakeUploader {
+        fn upload_data(
+            &self,
+            data_req: DataCaptureUploadRequest,
+        ) -> Pin<Box<dyn Future<Output = Result<(), AppClientError>> + '_>> {
+            let res = if self.fail {
+                Err(AppClientError::AppClientEmptyBody)
+            } else {
+                self.uploads.borrow_mut().push((
+                    data_req.metadata.unwrap().component_name,
+                    data_req.sensor_contents.len(),
+                ));
+                Ok(())
+            };
+            Box::pin(std::future::ready(res))
+        }
+    }
+
+    fn sync_task_for_test() -> (DataSyncTask<DefaultDataStore>, Vec<ResourceMethodKey>) {
+        let coll_1 = DataCollector::new(
+            "r1".to_string(),
+            ResourceType::Sensor(Arc::new(Mutex::new(TestSensor {}))),
+            CollectionMethod::Readings,
+            10.0,
+            (DEFAULT_CACHE_SIZE_KB * 1000.0) as usize,
+        )
+        .unwrap();
+        let coll_2 = DataCollector::new(
+            "r2".to_string(),
+            ResourceType::Sensor(Arc::new(Mutex::new(TestSensor {}))),
+            CollectionMethod::Readings,
+            10.0,
+            (DEFAULT_CACHE_SIZE_KB * 1000.0) as usize,
+        )
+        .unwrap()
+        .with_sync_interval(Some(Duration::from_secs(30)));
+        let keys = vec![coll_1.resource_method_key(), coll_2.resource_method_key()];
+        let store =
+            DefaultDataStore::new(keys.iter().map(|k| (k.clone(), 8000)).collect()).unwrap();
+        let manager = DataManager::new(
+            vec![coll_1, coll_2],
+            store,
+            Some(Duration::from_secs(600)),
+            "1".to_string(),
+        )
+        .unwrap();
+        (manager.get_sync_task(Instant::now()).unwrap(), keys)
+    }
+
+    fn write_readings(task: &DataSyncTask<DefaultDataStore>, key: &ResourceMethodKey, n: usize) {
+        let mut store = task.store.try_lock().unwrap();
+        for _ in 0..n {
+            let reading = SensorData {
+                metadata: Some(SensorMetadata {
+                    time_received: Some(Timestamp::default()),
+                    time_requested: Some(Timestamp::default()),
+                    ..Default::default()
+                }),
+                data: Some(Data::Struct(Struct::default())),
+            };
+            assert!(store
+                .write_message(key, reading, WriteMode::PreserveOrFail)
+                .is_ok());
+        }
+    }
+
+    #[test_log::test]
+    fn test_sync_task() {
+        let (task, keys) = sync_task_for_test();
+        let sync_intervals: Vec<Duration> =
+            task.collectors.iter().map(|c| c.sync_interval).collect();
+        assert_eq!(
+            sync_intervals,
+            vec![Duration::from_secs(600), Duration::from_secs(30)]
+        );
+        write_readings(&task, &keys[0], 2);
+        write_readings(&task, &keys[1], 1);
+        let uploader = FakeUploader::new(false);
+
+        async_io::block_on(async {
+            // every collector is due right away
+            let start = Instant::now();
+            assert_eq!(task.sync(&uploader).await.unwrap(), Some(Duration::ZERO));
+            assert_eq!(
+                *uploader.uploads.borrow(),
+                vec![("r1".to_string(), 2), ("r2".to_string(), 1)]
+            );
+            let next_syncs: Vec<Duration> = task
+                .collectors
+                .iter()
+                .map(|c| c.next_sync.get() - start)
+                .collect();
+            assert!(next_syncs[0] >= Duration::from_secs(600));
+            assert!(next_syncs[0] < Duration::from_secs(601));
+            assert!(next_syncs[1] >= Duration::from_secs(30));
+            assert!(next_syncs[1] < Duration::from_secs(31));
+
+            // only the collector that is due again is synced
+            uploader.uploads.borrow_mut().clear();
+            write_readings(&task, &keys[0], 1);
+            write_readings(&task, &keys[1], 3);
+            task.collectors[1].next_sync.set(Instant::now());
+            assert!(task.sync(&uploader).await.is_ok());
+            assert_eq!(*uploader.uploads.borrow(), vec![("r2".to_string(), 3)]);
+            assert!(task.collectors[1].next_sync.get() - start >= Duration::from_secs(30));
+        });
+    }
+
+    #[test_log::test]
+    fn test_sync_now() {
+        let (task, keys) = sync_task_for_test();
+        let mut trigger = task.sync_trigger();
+        let sync_now = Struct {
+            fields: HashMap::from([(
+                "sync_now".to_string(),
+                crate::google::protobuf::Value {
+                    kind: Some(Kind::BoolValue(true)),
+                },
+            )]),
+        };
+        let counts = |synced, failed| -> Struct { SyncCounts { synced, failed }.into() };
+
+        async_io::block_on(async {
+            assert!(trigger.do_command_async(None).await.is_err());
+
+            // no collector is due, the request syncs them all
+            task.collectors
+                .iter()
+                .for_each(|c| c.next_sync.set(Instant::now() + Duration::from_secs(30)));
+            write_readings(&task, &keys[0], 2);
+            write_readings(&task, &keys[1], 1);
+            let uploader = FakeUploader::new(false);
+            let (res, sync) = zip(
+                trigger.do_command_async(Some(sync_now.clone())),
+                task.sync(&uploader),
+            )
+            .await;
+            assert!(sync.is_ok());
+            assert_eq!(res.unwrap(), Some(counts(3, 0)));
+
+            // the datapoints lost to a failed upload are counted
+            write_readings(&task, &keys[0], 2);
+            let uploader = FakeUploader::new(true);
+            let (res, sync) = zip(
+                trigger.do_command_async(Some(sync_now.clone())),
+                task.sync(&uploader),
+            )
+            .await;
+            assert!(sync.is_err());
+            assert_eq!(res.unwrap(), Some(counts(0, 2)));
+
+            // a request nobody waits for anymore doesn't trigger a sync
+            let (stale, stale_counts) = async_channel::bounded(1);
+            drop(stale_counts);
+            assert!(task.sync_requests.0.try_send(stale).is_ok());
+            write_readings(&task, &keys[1], 1);
+            let uploader = FakeUploader::new(false);
+            let (res, sync) = zip(
+                trigger.do_command_async(Some(sync_now.clone())),
+                task.sync(&uploader),
+            )
+            .await;
+            assert!(sync.is_ok());
+            assert_eq!(res.unwrap(), Some(counts(1, 0)));
+            assert!(task.sync_requests.1.is_empty());
+
+            // the caller isn't held while the sync task doesn't run
+            trigger.timeout = Duration::from_millis(10);
+            let start = Instant::now();
+            let Err(GenericError::Other(err)) = trigger.do_command_async(Some(sync_now)).await
+            else {
+                panic!("the sync should have timed out");
+            };
+            assert!(matches!(
+                err.downcast_ref::<DataSyncError>(),
+                Some(DataSyncError::SyncUnavailable)
+            ));
+            assert!(start.elapsed() < Duration::from_secs(1));
+        });
+    }
 }
@@ -2,6 +2,7 @@
 //! Implementers of the trait are meant to be written to by DataCollectors (RSDK-6992, RSDK-6994)
 //! and read from by a task that uploads the data to app (RSDK-6995)
 
+use crate::google::protobuf::Struct;
 use crate::proto::app::data_sync::v1::SensorData;
 use bytes::{Buf, BufMut, BytesMut};
 use prost::{encoding::decode_varint, length_delimiter_len, DecodeError, EncodeError, Message};
@@ -52,6 +53,8 @@ pub enum DataStoreError {
     BufferInUse(ResourceMethodKey),
     #[error("unimplemented")]
     Unimplemented,
+    #[error("spooling error: {0}")]
+    SpoolError(String),
 }
 
 /// A trait for an entity that is capable of reading from a store region without consuming
@@ -84,6 +87,18 @@ pub trait DataStore {
     where
         Self: std::marker::Sized;
 
+    /// Initializes from resource-method keys and the attributes of the data manager service
+    /// config, for stores that can be configured there.
+    fn from_service_attributes(
+        settings: Vec<(ResourceMethodKey, usize)>,
+        _attributes: &Struct,
+    ) -> Result<Self, DataStoreError>
+    where
+        Self: std::marker::Sized,
+    {
+        Self::from_resource_method_settings(settings)
+    }
+
     // Gets a reader that should implement `DataStoreReader`
     fn get_reader(&self, collector_key: &ResourceMethodKey)
         -> Result<Self::Reader, DataStoreError>;
@@ -0,0 +1,300 @@
+//! Diagnostics generic component, exposes the state of the running server through DoCommand
+//!
+//! Supported commands:
+//! - `{"get_connection_limit": null}` returns the current connection limit, the ceiling it can be
+//!   raised to and the number of active connections
+//! - `{"set_connection_limit": n}` changes the connection limit, `n` has to be between 1 and the
+//!   ceiling
+//! - `{"get_build_info": null}` returns the firmware `version`, build time (`built`), git
+//!   `revision` and `target`, see [`build_info`](super::build_info)
+//! - `{"get_network": null}` returns whether the network is `connected`, its `ip`, `ssid`,
+//!   `gateway` and `netmask` as of the last network check, unknown values are omitted, see
+//!   [`network_details`]
+//! - `{"factory_reset": "confirm"}` erases the robot credentials and configuration then restarts
+//!   the device into provisioning mode so it can be moved to another robot, the value has to be
+//!   `"confirm"` to prevent accidental resets, see [`FactoryReset`]
+//!
+//! Robot-wide commands such as `{"any_moving": null}` are sent to the generic resource named
+//! [`ROBOT_COMMAND_RESOURCE_NAME`] instead, see [`LocalRobot::do_command`].
+//!
+//! [`LocalRobot::do_command`]: crate::common::robot::LocalRobot::do_command
+//! [`ROBOT_COMMAND_RESOURCE_NAME`]: crate::common::robot::ROBOT_COMMAND_RESOURCE_NAME
+//! [`FactoryReset`]: crate::common::conn::viam::FactoryReset
+//! [`network_details`]: crate::common::conn::network::network_details
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use super::{
+    build_info::build_info,
+    config::ConfigType,
+    conn::{
+        network::{network_details, NetworkDetails},
+        server::{connection_limit, set_connection_limit},
+        viam::FactoryReset,
+    },
+    generic::{DoCommand, GenericComponent, GenericComponentType, GenericError},
+    metrics,
+    registry::{ComponentRegistry, Dependency},
+    status::{Status, StatusError},
+};
+use crate::google::protobuf::{value::Kind, Struct, Value};
+
+// value the `factory_reset` command has to be given
+const FACTORY_RESET_CONFIRMATION: &str = "confirm";
+
+pub(crate) fn register_models(registry: &mut ComponentRegistry) {
+    if registry
+        .register_generic_component("diagnostics", &Diagnostics::from_config)
+        .is_err()
+    {
+        log::error!("model diagnostics is already registered")
+    }
+}
+
+pub struct Diagnostics {
+    factory_reset: FactoryReset,
+}
+
+impl Diagnostics {
+    /// Diagnostics requesting factory resets through `factory_reset`
+    pub fn new(factory_reset: FactoryReset) -> Self {
+        Self { factory_reset }
+    }
+
+    pub(crate) fn from_config(
+        _: ConfigType,
+        _: Vec<Dependency>,
+    ) -> Result<GenericComponentType, GenericError> {
+        Ok(Arc::new(Mutex::new(Diagnostics::new(
+            FactoryReset::global(),
+        ))))
+    }
+
+    fn connection_limit(res: &mut HashMap<String, Value>) -> Result<(), GenericError> {
+        let (limit, ceiling) =
+            connection_limit().ok_or_else(|| GenericError::Other("no server is running".into()))?;
+        for (key, value) in [
+            ("connection_limit", limit as f64),
+            ("connection_limit_ceiling", ceiling as f64),
+            ("active_connections", metrics::active_connections() as f64),
+        ] {
+            res.insert(
+                key.to_owned(),
+                Value {
+                    kind: Some(Kind::NumberValue(value)),
+                },
+            );
+        }
+        Ok(())
+    }
+
+    fn network(
+        details: Option<NetworkDetails>,
+        res: &mut HashMap<String, Value>,
+    ) -> Result<(), GenericError> {
+        let details =
+            details.ok_or_else(|| GenericError::Other("the network wasn't checked yet".into()))?;
+        res.insert(
+            "connected".to_owned(),
+            Value {
+                kind: Some(Kind::BoolValue(details.connected)),
+            },
+        );
+        for (key, value) in [
+            ("ip", Some(details.ip.to_string())),
+            ("ssid", details.ssid),
+            ("gateway", details.gateway.map(|ip| ip.to_string())),
+            ("netmask", details.netmask.map(|ip| ip.to_string())),
+        ] {
+            if let Some(value) = value {
+                res.insert(
+                    key.to_owned(),
+                    Value {
+                        kind: Some(Kind::StringValue(value)),
+                    },
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+impl GenericComponent for Diagnostics {}
+
+impl DoCommand for Diagnostics {
+    fn do_command(
+        &mut self,
+        command_struct: Option<Struct>,
+    ) -> Result<Option<Struct>, GenericError> {
+        let mut res = HashMap::new();
+        if let Some(command_struct) = command_struct.as_ref() {
+            for (key, val) in &command_struct.fields {
+                match key.as_str() {
+                    "get_connection_limit" => Self::connection_limit(&mut res)?,
+                    "get_network" => Self::network(network_details(), &mut res)?,
+                    "get_build_info" => res.extend(build_info().into_iter().map(|(key, value)| {
+                        (
+                            key.to_owned(),
+                            Value {
+                                kind: Some(Kind::StringValue(value)),
+                            },
+                        )
+                    })),
+                    "set_connection_limit" => {
+                        let limit = match val.kind {
+                            Some(Kind::NumberValue(limit)) if limit >= 0.0 => limit as usize,
+                            _ => {
+                                return Err(GenericError::Other(
+                                    "set_connection_limit expects a positive number".into(),
+                                ))
+                            }
+                        };
+                        set_connection_limit(limit).map_err(|e| GenericError::Other(e.into()))?;
+                        Self::connection_limit(&mut res)?;
+                    }
+                    "factory_reset" => {
+                        match &val.kind {
+                            Some(Kind::StringValue(confirm))
+                                if confirm == FACTORY_RESET_CONFIRMATION => {}
+                            _ => {
+                                return Err(GenericError::Other(
+                                    format!(
+                                        "factory_reset has to be confirmed with \"{}\"",
+                                        FACTORY_RESET_CONFIRMATION
+                                    )
+                                    .into(),
+                                ))
+                            }
+                        }
+                        log::warn!("factory reset requested through diagnostics");
+                        self.factory_reset.request();
+                        res.insert(
+                            "factory_reset".to_owned(),
+                            Value {
+                                kind: Some(Kind::BoolValue(true)),
+                            },
+                        );
+                    }
+                    _ => {
+                        return Err(GenericError::Other(
+                            format!("unknown diagnostics command {}", key).into(),
+                        ))
+                    }
+                };
+            }
+        }
+        Ok(Some(Struct { fields: res }))
+    }
+}
+
+impl Status for Diagnostics {
+    fn get_status(&self) -> Result<Option<Struct>, StatusError> {
+        Ok(Some(Struct {
+            fields: HashMap::new(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::conn::network::FakeNetwork;
+    use std::net::Ipv4Addr;
+
+    #[test_log::test]
+    fn test_diagnostics_build_info() {
+        let res = Diagnostics::new(FactoryReset::new())
+            .do_command(Some(Struct {
+                fields: HashMap::from([(
+                    "get_build_info".to_owned(),
+                    Value {
+                        kind: Some(Kind::NullValue(0)),
+                    },
+                )]),
+            }))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            res.fields["version"].kind,
+            Some(Kind::StringValue(env!("CARGO_PKG_VERSION").to_owned()))
+        );
+        assert!(crate::common::build_info::build_time().is_some());
+    }
+
+    #[test_log::test]
+    fn test_diagnostics_invalid_commands() {
+        let factory_reset = FactoryReset::new();
+        let mut diagnostics = Diagnostics::new(factory_reset.clone());
+        let command = |key: &str, kind: Kind| {
+            Some(Struct {
+                fields: HashMap::from([(key.to_owned(), Value { kind: Some(kind) })]),
+            })
+        };
+        assert!(diagnostics
+            .do_command(command("reboot", Kind::NullValue(0)))
+            .is_err());
+        assert!(diagnostics
+            .do_command(command(
+                "set_connection_limit",
+                Kind::StringValue("4".to_owned())
+            ))
+            .is_err());
+        assert!(diagnostics
+            .do_command(command("set_connection_limit", Kind::NumberValue(-1.0)))
+            .is_err());
+        assert!(diagnostics
+            .do_command(command("set_connection_limit", Kind::NumberValue(0.0)))
+            .is_err());
+        assert!(diagnostics
+            .do_command(command("factory_reset", Kind::NullValue(0)))
+            .is_err());
+        assert!(diagnostics
+            .do_command(command(
+                "factory_reset",
+                Kind::StringValue("yes".to_owned())
+            ))
+            .is_err());
+        assert!(!factory_reset.take());
+    }
+
+    #[test_log::test]
+    fn test_diagnostics_network() {
+        let mut res = HashMap::new();
+        assert!(Diagnostics::network(None, &mut res).is_err());
+
+        let network = FakeNetwork::new(Ipv4Addr::new(10, 0, 0, 7));
+        network.set_ssid(Some("lab".to_owned()));
+        Diagnostics::network(Some(NetworkDetails::new(&network)), &mut res).unwrap();
+        assert_eq!(res["connected"].kind, Some(Kind::BoolValue(true)));
+        assert_eq!(
+            res["ip"].kind,
+            Some(Kind::StringValue("10.0.0.7".to_owned()))
+        );
+        assert_eq!(res["ssid"].kind, Some(Kind::StringValue("lab".to_owned())));
+        assert!(!res.contains_key("gateway"));
+    }
+
+    #[test_log::test]
+    fn test_diagnostics_factory_reset() {
+        let factory_reset = FactoryReset::new();
+        let res = Diagnostics::new(factory_reset.clone())
+            .do_command(Some(Struct {
+                fields: HashMap::from([(
+                    "factory_reset".to_owned(),
+                    Value {
+                        kind: Some(Kind::StringValue(FACTORY_RESET_CONFIRMATION.to_owned())),
+                    },
+                )]),
+            }))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            res.fields["factory_reset"].kind,
+            Some(Kind::BoolValue(true))
+        );
+        assert!(factory_reset.take());
+    }
+}
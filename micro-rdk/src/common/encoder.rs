@@ -347,3 +347,47 @@ where
         self.lock().unwrap().get_direction()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::test_utils::delegation_test;
+
+    delegation_test!(
+        test_encoder_delegation,
+        RecordingEncoder as dyn SingleEncoder,
+        wrappers: [Mutex::new, |encoder| Arc::new(Mutex::new(encoder))],
+        impl Encoder {
+            fn get_properties(&mut self) -> EncoderSupportedRepresentations {
+                EncoderSupportedRepresentations {
+                    ticks_count_supported: true,
+                    angle_degrees_supported: false,
+                }
+            }
+            fn get_position(
+                &self,
+                position_type: EncoderPositionType,
+            ) -> Result<EncoderPosition, EncoderError> {
+                Ok(position_type.wrap_value(0.0))
+            }
+            fn reset_position(&mut self) -> Result<(), EncoderError> {
+                Ok(())
+            }
+        }
+        impl SingleEncoder {
+            fn set_direction(&mut self, _: Direction) -> Result<(), EncoderError> {
+                Ok(())
+            }
+            fn get_direction(&self) -> Result<Direction, EncoderError> {
+                Ok(Direction::Forwards)
+            }
+        }
+        calls: |encoder| {
+            encoder.get_properties();
+            encoder.get_position(EncoderPositionType::TICKS).unwrap();
+            encoder.reset_position().unwrap();
+            encoder.set_direction(Direction::Backwards).unwrap();
+            encoder.get_direction().unwrap();
+        }
+    );
+}
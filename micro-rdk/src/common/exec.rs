@@ -1,4 +1,21 @@
 //! The exec module exposes helpers to execute futures
+//!
+//! Futures spawned with [`Executor::spawn`] run on the thread owning the executor and share its
+//! stack (on ESP32 the main task, sized by `CONFIG_ESP_MAIN_TASK_STACK_SIZE`). Work needing a
+//! bigger stack or a different priority can be moved to a dedicated thread with
+//! [`Executor::spawn_with_config`], the [`TaskConfig`] defaults being
+//! [`DEFAULT_TASK_STACK_SIZE`] and [`DEFAULT_TASK_PRIORITY`].
+//!
+//! Because futures only give control back to the executor when they await something pending,
+//! a driver doing a long stretch of synchronous work (many large I2C transfers, parsing a big
+//! payload) starves every other future: connections aren't serviced and on ESP32 the idle task
+//! never runs, so the task watchdog resets the device. Such loops should await [`yield_now`]
+//! every few milliseconds of work, or hold a [`CooperativeYield`] and await
+//! [`CooperativeYield::tick`] on every iteration to yield once [`DEFAULT_YIELD_INTERVAL`]
+//! elapsed. Work that blocks on a single long call can't be split and belongs on another thread
+//! instead, see [`BlockingPool`](super::blocking::BlockingPool).
+use std::time::{Duration, Instant};
+
 use async_executor::{LocalExecutor, Task};
 use futures_lite::{
     future::{self, block_on},
@@ -7,6 +24,67 @@ use futures_lite::{
 
 use crate::common::{provisioning::server::ProvisioningExecutor, webrtc::exec::WebRtcExecutor};
 
+/// How long a [`CooperativeYield`] lets a loop run before yielding, well under the 5 seconds
+/// of the ESP-IDF task watchdog and short enough to keep connections responsive
+pub const DEFAULT_YIELD_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Default stack size in bytes of a thread spawned by [`Executor::spawn_with_config`]
+pub const DEFAULT_TASK_STACK_SIZE: usize = 8 * 1024;
+/// Default FreeRTOS priority of a thread spawned by [`Executor::spawn_with_config`] (ignored on
+/// native), same as the ESP-IDF pthread default
+pub const DEFAULT_TASK_PRIORITY: u8 = 5;
+
+/// Parameters of the thread backing a future spawned with [`Executor::spawn_with_config`]
+#[derive(Clone, Debug)]
+pub struct TaskConfig {
+    pub stack_size: usize,
+    pub priority: u8,
+}
+
+impl Default for TaskConfig {
+    fn default() -> Self {
+        Self {
+            stack_size: DEFAULT_TASK_STACK_SIZE,
+            priority: DEFAULT_TASK_PRIORITY,
+        }
+    }
+}
+
+impl TaskConfig {
+    pub fn with_stack_size(mut self, stack_size: usize) -> Self {
+        self.stack_size = stack_size;
+        self
+    }
+    pub fn with_priority(mut self, priority: u8) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    // spawn a thread running `f` with this configuration
+    pub(crate) fn spawn_thread<F>(&self, f: F) -> std::io::Result<std::thread::JoinHandle<()>>
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        #[cfg(feature = "esp32")]
+        crate::esp32::esp_idf_svc::hal::task::thread::ThreadSpawnConfiguration {
+            stack_size: self.stack_size,
+            priority: self.priority,
+            ..Default::default()
+        }
+        .set()
+        .map_err(std::io::Error::other)?;
+        let spawned = std::thread::Builder::new()
+            .stack_size(self.stack_size)
+            .spawn(f);
+        // later threads get the default configuration back
+        #[cfg(feature = "esp32")]
+        crate::esp32::esp_idf_svc::hal::task::thread::ThreadSpawnConfiguration::default()
+            .set()
+            .map_err(std::io::Error::other)?;
+        spawned
+    }
+}
+
 #[derive(Clone, Debug, Default)]
 /// This executor is local and bounded to the CPU that created it usually you would create it after spwaning a thread on a specific core
 pub struct Executor {}
@@ -30,6 +108,27 @@ impl Executor {
     pub fn block_on<T>(&self, future: impl Future<Output = T>) -> T {
         EX.with(|e| block_on(e.run(future)))
     }
+
+    /// Run the future returned by `make_future` on a new thread configured by `config`, with its
+    /// own local executor. The returned task resolves to the output of the future, or None if the
+    /// thread panicked.
+    pub fn spawn_with_config<T, F, Fut>(
+        &self,
+        config: &TaskConfig,
+        make_future: F,
+    ) -> std::io::Result<Task<Option<T>>>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future<Output = T> + 'static,
+    {
+        let (tx, rx) = async_channel::bounded(1);
+        let _ = config.spawn_thread(move || {
+            let output = Executor::new().block_on(make_future());
+            let _ = tx.send_blocking(output);
+        })?;
+        Ok(self.spawn(async move { rx.recv().await.ok() }))
+    }
 }
 
 /// helper trait for hyper to spwan future onto a local executor
@@ -56,3 +155,104 @@ impl ProvisioningExecutor for Executor {
         self.spawn(future)
     }
 }
+
+/// Give control back to the executor so the other futures can make progress. On ESP32 the
+/// calling task is also delayed by one tick, letting the idle task run and feed the watchdog.
+pub async fn yield_now() {
+    #[cfg(feature = "esp32")]
+    crate::esp32::esp_idf_svc::hal::delay::FreeRtos::delay_ms(1);
+    future::yield_now().await
+}
+
+/// Yields to the executor at most once per interval from a long running loop, calling
+/// [`yield_now`] on every iteration would slow the loop down needlessly
+///
+/// ```ignore
+/// let mut yielder = CooperativeYield::new();
+/// for frame in frames {
+///     parse(frame)?;
+///     yielder.tick().await;
+/// }
+/// ```
+#[derive(Debug)]
+pub struct CooperativeYield {
+    interval: Duration,
+    last_yield: Instant,
+}
+
+impl Default for CooperativeYield {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CooperativeYield {
+    pub fn new() -> Self {
+        Self {
+            interval: DEFAULT_YIELD_INTERVAL,
+            last_yield: Instant::now(),
+        }
+    }
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+    /// Yield if more than the interval elapsed since the last time this yielded
+    pub async fn tick(&mut self) {
+        if self.last_yield.elapsed() >= self.interval {
+            yield_now().await;
+            self.last_yield = Instant::now();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{cell::Cell, rc::Rc};
+
+    #[test_log::test]
+    fn test_cooperative_yield() {
+        let exec = Executor::new();
+        let counter = Rc::new(Cell::new(0));
+        let background = counter.clone();
+        let task = exec.spawn(async move { background.set(background.get() + 1) });
+
+        exec.block_on(async {
+            // the loop doesn't yield before the interval elapsed
+            let mut yielder = CooperativeYield::new().with_interval(Duration::from_secs(60));
+            for _ in 0..10 {
+                yielder.tick().await;
+            }
+            assert_eq!(counter.get(), 0);
+
+            let mut yielder = CooperativeYield::new().with_interval(Duration::ZERO);
+            yielder.tick().await;
+            assert_eq!(counter.get(), 1);
+        });
+        assert!(task.is_finished());
+    }
+
+    #[test_log::test]
+    fn test_spawn_with_config() {
+        let exec = Executor::new();
+        let caller = std::thread::current().id();
+        let config = TaskConfig::default().with_stack_size(64 * 1024);
+        let task = exec
+            .spawn_with_config(&config, move || async move {
+                // the future runs on its own thread and doesn't need to be Send
+                let local = Rc::new(Cell::new(41));
+                local.set(local.get() + 1);
+                (std::thread::current().id(), local.get())
+            })
+            .unwrap();
+        let (thread, value) = exec.block_on(task).unwrap();
+        assert_ne!(thread, caller);
+        assert_eq!(value, 42);
+
+        let task = exec
+            .spawn_with_config(&config, || async { panic!("task failed") })
+            .unwrap();
+        assert!(exec.block_on(task).is_none());
+    }
+}
@@ -1,4 +1,8 @@
-use std::sync::{Arc, Mutex};
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+};
 
 use crate::google::protobuf::Struct;
 
@@ -35,6 +39,10 @@ pub(crate) fn register_models(registry: &mut ComponentRegistry) {
     }
 }
 
+/// A command started by [`DoCommand::do_command_async`]
+pub type DoCommandFuture =
+    Pin<Box<dyn Future<Output = Result<Option<Struct>, GenericError>> + Send + Sync>>;
+
 pub trait DoCommand {
     /// do_command custom commands outside of a strict API. Takes a command struct that can be interpreted
     /// as a map of method name keys to argument values.
@@ -44,6 +52,12 @@ pub trait DoCommand {
     ) -> Result<Option<Struct>, GenericError> {
         Err(GenericError::MethodUnimplemented("do_command"))
     }
+    /// Start a command that the gRPC server awaits rather than blocking the executor on, for
+    /// commands that take a while such as a timed sequence. The future can't borrow the
+    /// resource, whatever it needs is moved or cloned into it. Runs `do_command` by default.
+    fn do_command_async(&mut self, command_struct: Option<Struct>) -> DoCommandFuture {
+        Box::pin(std::future::ready(self.do_command(command_struct)))
+    }
 }
 
 impl<L> DoCommand for Mutex<L>
@@ -56,6 +70,9 @@ where
     ) -> Result<Option<Struct>, GenericError> {
         self.get_mut().unwrap().do_command(command_struct)
     }
+    fn do_command_async(&mut self, command_struct: Option<Struct>) -> DoCommandFuture {
+        self.get_mut().unwrap().do_command_async(command_struct)
+    }
 }
 
 impl<A> DoCommand for Arc<Mutex<A>>
@@ -68,6 +85,9 @@ where
     ) -> Result<Option<Struct>, GenericError> {
         self.lock().unwrap().do_command(command_struct)
     }
+    fn do_command_async(&mut self, command_struct: Option<Struct>) -> DoCommandFuture {
+        self.lock().unwrap().do_command_async(command_struct)
+    }
 }
 
 pub trait GenericComponent: DoCommand + Status {}
@@ -133,3 +153,17 @@ impl Status for FakeGenericComponent {
         }))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::test_utils::delegation_test;
+
+    delegation_test!(
+        test_generic_component_delegation,
+        RecordingGenericComponent as dyn GenericComponent,
+        wrappers: [Mutex::new, |component| Arc::new(Mutex::new(component))],
+        impl GenericComponent {}
+        calls: |_component| {}
+    );
+}
@@ -0,0 +1,260 @@
+//! A sensor reporting whether the position of a movement sensor lies inside a configured area,
+//! either a circle (`center` with `lat` and `lon`, and `radius_m`) or a polygon (`polygon`, a list
+//! of at least 3 vertices with `lat` and `lon`).
+//!
+//! Readings are `inside` (bool) and `distance_to_boundary_m`, the distance in meters to the
+//! closest point of the boundary.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use super::config::ConfigType;
+use super::movement_sensor::{
+    GeoPosition, MovementSensor, MovementSensorType, COMPONENT_NAME as MovementSensorCompName,
+    EARTH_RADIUS_M,
+};
+use super::registry::{ComponentRegistry, Dependency, ResourceKey};
+use super::robot::Resource;
+use super::sensor::{
+    GenericReadingsResult, Readings, Sensor, SensorError, SensorType,
+    COMPONENT_NAME as SensorCompName,
+};
+use super::status::{Status, StatusError};
+use crate::google;
+use crate::google::protobuf::{value::Kind, Value};
+
+pub(crate) fn register_models(registry: &mut ComponentRegistry) {
+    if registry
+        .register_sensor("geofence", &Geofence::from_config)
+        .is_err()
+    {
+        log::error!("geofence model is already registered")
+    }
+    if registry
+        .register_dependency_getter(
+            SensorCompName,
+            "geofence",
+            &Geofence::dependencies_from_config,
+        )
+        .is_err()
+    {
+        log::error!("failed to register dependency getter for geofence model")
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum GeofenceArea {
+    Circle { center: GeoPosition, radius_m: f64 },
+    Polygon(Vec<GeoPosition>),
+}
+
+impl GeofenceArea {
+    /// Whether `position` is inside the area and its distance in meters to the boundary
+    pub fn locate(&self, position: &GeoPosition) -> (bool, f64) {
+        match self {
+            Self::Circle { center, radius_m } => {
+                let distance = position.distance_to(center);
+                (distance <= *radius_m, (distance - radius_m).abs())
+            }
+            Self::Polygon(vertices) => {
+                // vertices are projected on a plane tangent to the earth at `position`, which is
+                // accurate enough for areas spanning a few kilometers
+                let lat_scale = EARTH_RADIUS_M.to_radians();
+                let lon_scale = lat_scale * position.lat.to_radians().cos();
+                let points: Vec<(f64, f64)> = vertices
+                    .iter()
+                    .map(|v| {
+                        (
+                            (v.lon - position.lon) * lon_scale,
+                            (v.lat - position.lat) * lat_scale,
+                        )
+                    })
+                    .collect();
+                let mut inside = false;
+                let mut distance = f64::INFINITY;
+                for (i, a) in points.iter().enumerate() {
+                    let b = &points[(i + 1) % points.len()];
+                    // ray cast along +x from the origin
+                    if (a.1 > 0.0) != (b.1 > 0.0) && a.0 - a.1 * (b.0 - a.0) / (b.1 - a.1) > 0.0 {
+                        inside = !inside;
+                    }
+                    distance = distance.min(distance_to_segment(*a, *b));
+                }
+                (inside, distance)
+            }
+        }
+    }
+}
+
+// distance from the origin to the segment [a, b]
+fn distance_to_segment(a: (f64, f64), b: (f64, f64)) -> f64 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let len_sq = dx * dx + dy * dy;
+    let t = if len_sq == 0.0 {
+        0.0
+    } else {
+        (-(a.0 * dx + a.1 * dy) / len_sq).clamp(0.0, 1.0)
+    };
+    (a.0 + t * dx).hypot(a.1 + t * dy)
+}
+
+fn geo_position_from_attribute(point: &HashMap<&str, f64>) -> Result<GeoPosition, SensorError> {
+    match (point.get("lat"), point.get("lon")) {
+        (Some(lat), Some(lon)) => Ok(GeoPosition {
+            lat: *lat,
+            lon: *lon,
+            alt: 0.0,
+        }),
+        _ => Err(SensorError::ConfigError(
+            "geofence points need both `lat` and `lon`",
+        )),
+    }
+}
+
+#[derive(DoCommand)]
+pub struct Geofence {
+    movement_sensor: MovementSensorType,
+    area: GeofenceArea,
+}
+
+impl Geofence {
+    pub fn new(movement_sensor: MovementSensorType, area: GeofenceArea) -> Self {
+        Self {
+            movement_sensor,
+            area,
+        }
+    }
+
+    pub(crate) fn from_config(
+        cfg: ConfigType,
+        deps: Vec<Dependency>,
+    ) -> Result<SensorType, SensorError> {
+        let name = cfg
+            .get_attribute::<String>("movement_sensor")
+            .map_err(|_| SensorError::ConfigError("geofence requires a `movement_sensor`"))?;
+        let movement_sensor = deps
+            .into_iter()
+            .find_map(|Dependency(key, res)| match res {
+                Resource::MovementSensor(ms) if key.1 == name => Some(ms),
+                _ => None,
+            })
+            .ok_or(SensorError::ConfigError(
+                "geofence movement sensor couldn't be found",
+            ))?;
+
+        let area = if let Ok(polygon) = cfg.get_attribute::<Vec<HashMap<&str, f64>>>("polygon") {
+            if polygon.len() < 3 {
+                return Err(SensorError::ConfigError(
+                    "geofence polygon needs at least 3 points",
+                ));
+            }
+            GeofenceArea::Polygon(
+                polygon
+                    .iter()
+                    .map(geo_position_from_attribute)
+                    .collect::<Result<_, _>>()?,
+            )
+        } else {
+            let center = cfg
+                .get_attribute::<HashMap<&str, f64>>("center")
+                .map_err(|_| {
+                    SensorError::ConfigError("geofence requires either `polygon` or `center`")
+                })?;
+            let radius_m = cfg
+                .get_attribute::<f64>("radius_m")
+                .map_err(|_| SensorError::ConfigError("geofence circle requires `radius_m`"))?;
+            if radius_m <= 0.0 {
+                return Err(SensorError::ConfigError(
+                    "geofence `radius_m` must be positive",
+                ));
+            }
+            GeofenceArea::Circle {
+                center: geo_position_from_attribute(&center)?,
+                radius_m,
+            }
+        };
+        Ok(Arc::new(Mutex::new(Geofence::new(movement_sensor, area))))
+    }
+
+    pub(crate) fn dependencies_from_config(cfg: ConfigType) -> Vec<ResourceKey> {
+        let mut r_keys = Vec::new();
+        if let Ok(name) = cfg.get_attribute::<String>("movement_sensor") {
+            r_keys.push(ResourceKey::new(MovementSensorCompName, name));
+        }
+        r_keys
+    }
+}
+
+impl Sensor for Geofence {}
+
+impl Readings for Geofence {
+    fn get_generic_readings(&mut self) -> Result<GenericReadingsResult, SensorError> {
+        let position = self.movement_sensor.lock().unwrap().get_position()?;
+        let (inside, distance) = self.area.locate(&position);
+        Ok(HashMap::from([
+            (
+                "inside".to_string(),
+                Value {
+                    kind: Some(Kind::BoolValue(inside)),
+                },
+            ),
+            (
+                "distance_to_boundary_m".to_string(),
+                Value {
+                    kind: Some(Kind::NumberValue(distance)),
+                },
+            ),
+        ]))
+    }
+}
+
+impl Status for Geofence {
+    fn get_status(&self) -> Result<Option<google::protobuf::Struct>, StatusError> {
+        Ok(Some(google::protobuf::Struct {
+            fields: HashMap::new(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GeofenceArea;
+    use crate::common::movement_sensor::GeoPosition;
+
+    fn pos(lat: f64, lon: f64) -> GeoPosition {
+        GeoPosition { lat, lon, alt: 0.0 }
+    }
+
+    #[test_log::test]
+    fn test_geofence_circle() {
+        let area = GeofenceArea::Circle {
+            center: pos(40.0, -74.0),
+            radius_m: 1000.0,
+        };
+        // 0.001 degree of latitude is ~111m
+        let (inside, distance) = area.locate(&pos(40.001, -74.0));
+        assert!(inside);
+        assert!((distance - 888.8).abs() < 1.0, "{}", distance);
+        let (inside, distance) = area.locate(&pos(40.01, -74.0));
+        assert!(!inside);
+        assert!((distance - 111.9).abs() < 1.0, "{}", distance);
+    }
+
+    #[test_log::test]
+    fn test_geofence_polygon() {
+        let area = GeofenceArea::Polygon(vec![
+            pos(0.0, 0.0),
+            pos(0.0, 0.01),
+            pos(0.01, 0.01),
+            pos(0.01, 0.0),
+        ]);
+        let (inside, distance) = area.locate(&pos(0.005, 0.002));
+        assert!(inside);
+        assert!((distance - 222.4).abs() < 1.0, "{}", distance);
+        let (inside, distance) = area.locate(&pos(0.005, 0.012));
+        assert!(!inside);
+        assert!((distance - 222.4).abs() < 1.0, "{}", distance);
+        let (inside, _) = area.locate(&pos(-0.001, 0.005));
+        assert!(!inside);
+    }
+}
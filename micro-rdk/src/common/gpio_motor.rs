@@ -36,21 +36,38 @@
 //!
 //! ```
 //!
+//! # Power ramp
+//! When `max_power_ramp_per_sec` is set, the power requested through `set_power` (and `go_for`)
+//! is reached progressively, changing by at most that fraction of full power per second, so
+//! geared drivetrains aren't jerked by sudden power changes. `stop` still stops immediately.
+//!
+//! # Fault pin
+//! The driver's fault output can be wired to the input set by `fault_pin`, its state is then
+//! reported by `get_fault_status` and in the status of the motor (as `fault_error` when it can't
+//! be read). A high level signals a fault unless `fault_active_low` is set, as for the open-drain
+//! nFAULT line of drivers like the DRV8833, which is then pulled up.
+//!
 
 use std::collections::HashMap;
+use std::pin::Pin;
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
+use async_executor::Task;
+use async_io::Timer;
+use futures_lite::Future;
+
 use super::actuator::{Actuator, ActuatorError};
-use super::board::{Board, BoardType};
-use super::config::ConfigType;
+use super::board::{Board, BoardError, BoardType};
+use super::config::{AttributeError, ConfigType};
 use super::encoder::{
-    Encoder, EncoderPositionType, EncoderType, COMPONENT_NAME as EncoderCompName,
+    Encoder, EncoderError, EncoderPositionType, EncoderType, COMPONENT_NAME as EncoderCompName,
 };
+use super::exec::Executor;
 use super::math_utils::go_for_math;
 use super::motor::{
-    Motor, MotorError, MotorPinType, MotorPinsConfig, MotorSupportedProperties, MotorType,
-    COMPONENT_NAME as MotorCompName,
+    Motor, MotorError, MotorFaultStatus, MotorPinType, MotorPinsConfig, MotorSupportedProperties,
+    MotorType, COMPONENT_NAME as MotorCompName,
 };
 use super::registry::{get_board_from_dependencies, ComponentRegistry, Dependency, ResourceKey};
 use super::robot::Resource;
@@ -110,8 +127,32 @@ pub(crate) fn gpio_motor_from_config(
         }
         MotorPinType::AB => AbMotor::<BoardType>::from_config(cfg, board.clone())?.clone(),
     };
+    let motor: MotorType = match cfg.get_attribute::<f64>("max_power_ramp_per_sec") {
+        Ok(max_power_ramp_per_sec) => Arc::new(Mutex::new(RampedMotor::new(
+            motor,
+            max_power_ramp_per_sec,
+            cfg.get_attribute::<f64>("max_rpm").unwrap_or(100.0),
+        )?)),
+        Err(AttributeError::KeyNotFound(_)) => motor,
+        Err(_) => {
+            return Err(MotorError::ConfigError(
+                "RampedMotor, 'max_power_ramp_per_sec' has to be a number",
+            ))
+        }
+    };
     if let Some(enc) = enc {
-        let enc_motor = EncodedMotor::new(motor, enc.clone());
+        let gearing_attribute = |name| match cfg.get_attribute::<f64>(name) {
+            Ok(value) => Ok(value),
+            Err(AttributeError::KeyNotFound(_)) => Ok(1.0),
+            Err(_) => Err(MotorError::ConfigError(
+                "EncodedMotor, 'ticks_per_rotation' and 'gear_ratio' have to be numbers",
+            )),
+        };
+        let ticks_per_rotation = gearing_attribute("ticks_per_rotation")?;
+        let gear_ratio = gearing_attribute("gear_ratio")?;
+        let enc_motor = EncodedMotor::new(motor, enc.clone())
+            .with_gearing(ticks_per_rotation, gear_ratio)?
+            .with_inverted(invert_from_config(&cfg)?);
         return Ok(Arc::new(Mutex::new(enc_motor)));
     }
     Ok(motor)
@@ -123,10 +164,153 @@ pub(crate) fn gpio_motor_from_config(
 // of forcing the user to supply a PWM frequency in the motor config)
 const MOTOR_PWM_FREQUENCY: u64 = 1000;
 
+/// How often the power is updated while ramping
+const RAMP_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Completes once the given interval between two power updates of a ramp is over
+pub type RampTimer = Arc<dyn Fn(Duration) -> Pin<Box<dyn Future<Output = ()>>>>;
+
+fn default_ramp_timer() -> RampTimer {
+    Arc::new(|interval| {
+        Box::pin(async move {
+            Timer::after(interval).await;
+        })
+    })
+}
+
+// Whether the direction of a motor is flipped, `invert` flips both the direction of the motor and
+// the position reported by its encoder while `dir_flip` only flips the direction of the motor
+fn dir_flip_from_config(cfg: &ConfigType) -> Result<bool, MotorError> {
+    let dir_flip = match cfg.get_attribute::<bool>("dir_flip") {
+        Ok(dir_flip) => dir_flip,
+        Err(AttributeError::KeyNotFound(_)) => false,
+        Err(_) => {
+            return Err(MotorError::ConfigError(
+                "Motor, 'dir_flip' has to be a boolean",
+            ))
+        }
+    };
+    Ok(dir_flip != invert_from_config(cfg)?)
+}
+
+fn invert_from_config(cfg: &ConfigType) -> Result<bool, MotorError> {
+    match cfg.get_attribute::<bool>("invert") {
+        Ok(invert) => Ok(invert),
+        Err(AttributeError::KeyNotFound(_)) => Ok(false),
+        Err(_) => Err(MotorError::ConfigError(
+            "Motor, 'invert' has to be a boolean",
+        )),
+    }
+}
+
+/// The input pin on which a motor driver signals a fault
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct FaultPin {
+    pin: i32,
+    // whether a low level means the driver is in a fault state
+    active_low: bool,
+}
+
+impl FaultPin {
+    pub(crate) fn new(pin: i32, active_low: bool) -> Self {
+        Self { pin, active_low }
+    }
+}
+
+// Parse the optional fault pin and register it as an input on the board, so that reading it
+// doesn't fail on boards that only know about configured pins
+fn fault_pin_from_config(
+    cfg: &ConfigType,
+    board: &mut BoardType,
+) -> Result<Option<FaultPin>, MotorError> {
+    let pin = match cfg.get_attribute::<i32>("fault_pin") {
+        Ok(pin) => pin,
+        Err(AttributeError::KeyNotFound(_)) => return Ok(None),
+        Err(_) => return Err(MotorError::ConfigError("fault_pin should be a pin number")),
+    };
+    let active_low = match cfg.get_attribute::<bool>("fault_active_low") {
+        Ok(active_low) => active_low,
+        Err(AttributeError::KeyNotFound(_)) => false,
+        Err(_) => {
+            return Err(MotorError::ConfigError(
+                "fault_active_low should be a boolean",
+            ))
+        }
+    };
+    // an active-low line is usually open-drain, only ever pulled low by the driver
+    board.configure_input_pin(pin, active_low)?;
+    Ok(Some(FaultPin::new(pin, active_low)))
+}
+
+/// Motors whose driver may signal faults on an input pin
+pub(crate) trait WithFaultPin: Sized {
+    fn fault_pin_mut(&mut self) -> &mut Option<FaultPin>;
+
+    /// Report the state of `fault_pin` as the fault status of the motor
+    fn with_fault_pin(mut self, fault_pin: Option<FaultPin>) -> Self {
+        *self.fault_pin_mut() = fault_pin;
+        self
+    }
+}
+
+macro_rules! impl_with_fault_pin {
+    ($($motor:ident),*) => {
+        $(
+            impl<B> WithFaultPin for $motor<B> {
+                fn fault_pin_mut(&mut self) -> &mut Option<FaultPin> {
+                    &mut self.fault_pin
+                }
+            }
+        )*
+    };
+}
+
+impl_with_fault_pin!(PwmABMotor, PwmDirectionMotor, AbMotor);
+
+fn read_fault_pin<B: Board>(
+    board: &B,
+    fault_pin: Option<FaultPin>,
+) -> Result<Option<MotorFaultStatus>, BoardError> {
+    fault_pin
+        .map(|fault_pin| {
+            Ok(MotorFaultStatus {
+                fault: board.get_gpio_level(fault_pin.pin)? != fault_pin.active_low,
+            })
+        })
+        .transpose()
+}
+
+// A fault pin that can't be read is reported in the status rather than failing it, the rest of
+// the status of the motor being still meaningful
+fn insert_fault_status<B: Board>(
+    hm: &mut HashMap<String, google::protobuf::Value>,
+    board: &B,
+    fault_pin: Option<FaultPin>,
+) {
+    let (key, kind) = match read_fault_pin(board, fault_pin) {
+        Ok(None) => return,
+        Ok(Some(status)) => (
+            "fault",
+            google::protobuf::value::Kind::BoolValue(status.fault),
+        ),
+        Err(err) => (
+            "fault_error",
+            google::protobuf::value::Kind::StringValue(err.to_string()),
+        ),
+    };
+    hm.insert(
+        key.to_string(),
+        google::protobuf::Value { kind: Some(kind) },
+    );
+}
+
 #[derive(DoCommand)]
 pub struct EncodedMotor<M, Enc> {
     motor: M,
     enc: Enc,
+    // encoder ticks per revolution of the output shaft
+    ticks_per_output_rotation: f64,
+    invert: bool,
 }
 
 impl<M, Enc> EncodedMotor<M, Enc>
@@ -134,8 +318,51 @@ where
     M: Motor,
     Enc: Encoder,
 {
+    /// An encoded motor counting a revolution per encoder tick, see [`Self::with_gearing`]
     pub fn new(motor: M, enc: Enc) -> Self {
-        Self { motor, enc }
+        Self {
+            motor,
+            enc,
+            ticks_per_output_rotation: 1.0,
+            invert: false,
+        }
+    }
+
+    /// Report the position in revolutions of the output shaft given the encoder `ticks_per_rotation`
+    /// (of the motor shaft) and the `gear_ratio` of the gearbox (motor shaft revolutions per output
+    /// shaft revolution), both have to be positive
+    pub fn with_gearing(
+        mut self,
+        ticks_per_rotation: f64,
+        gear_ratio: f64,
+    ) -> Result<Self, MotorError> {
+        if !(ticks_per_rotation.is_finite() && ticks_per_rotation > 0.0) {
+            return Err(MotorError::ConfigError(
+                "EncodedMotor, 'ticks_per_rotation' has to be positive",
+            ));
+        }
+        if !(gear_ratio.is_finite() && gear_ratio > 0.0) {
+            return Err(MotorError::ConfigError(
+                "EncodedMotor, 'gear_ratio' has to be positive",
+            ));
+        }
+        self.ticks_per_output_rotation = ticks_per_rotation * gear_ratio;
+        Ok(self)
+    }
+
+    /// Flip the sign of the reported position, to be used along with an inverted `motor` so the
+    /// position keeps increasing when the power is positive
+    pub fn with_inverted(mut self, invert: bool) -> Self {
+        self.invert = invert;
+        self
+    }
+
+    fn ticks(&self) -> Result<f64, EncoderError> {
+        let ticks = self
+            .enc
+            .get_position(EncoderPositionType::UNSPECIFIED)?
+            .value as f64;
+        Ok(if self.invert { -ticks } else { ticks })
     }
 }
 
@@ -145,10 +372,10 @@ where
     Enc: Encoder,
 {
     fn get_position(&mut self) -> Result<i32, MotorError> {
-        Ok(self
-            .enc
-            .get_position(EncoderPositionType::UNSPECIFIED)?
-            .value as i32)
+        Ok(self.get_position_revolutions()? as i32)
+    }
+    fn get_position_revolutions(&mut self) -> Result<f64, MotorError> {
+        Ok(self.ticks()? / self.ticks_per_output_rotation)
     }
 
     /// Accepts percentage as a float, e.g. `0.5` equals `50%` power.
@@ -163,6 +390,9 @@ where
             position_reporting: true,
         }
     }
+    fn get_fault_status(&mut self) -> Result<MotorFaultStatus, MotorError> {
+        self.motor.get_fault_status()
+    }
 }
 
 impl<M, Enc> Actuator for EncodedMotor<M, Enc>
@@ -185,20 +415,176 @@ where
 {
     fn get_status(&self) -> Result<Option<google::protobuf::Struct>, StatusError> {
         let mut hm = HashMap::new();
-        let pos = self
-            .enc
-            .get_position(EncoderPositionType::UNSPECIFIED)?
-            .value as f64;
+        let ticks = self.ticks()?;
         hm.insert(
             "position".to_string(),
             google::protobuf::Value {
-                kind: Some(google::protobuf::value::Kind::NumberValue(pos)),
+                kind: Some(google::protobuf::value::Kind::NumberValue(
+                    ticks / self.ticks_per_output_rotation,
+                )),
+            },
+        );
+        hm.insert(
+            "position_ticks".to_string(),
+            google::protobuf::Value {
+                kind: Some(google::protobuf::value::Kind::NumberValue(ticks)),
             },
         );
+        if let Some(mut status) = self.motor.get_status()? {
+            for key in ["fault", "fault_error"] {
+                if let Some(value) = status.fields.remove(key) {
+                    hm.insert(key.to_string(), value);
+                }
+            }
+        }
         Ok(Some(google::protobuf::Struct { fields: hm }))
     }
 }
 
+/// Wraps a motor so that `set_power` approaches the requested power at `max_power_ramp_per_sec`
+/// (fraction of full power per second) instead of applying it at once. The ramp runs as a task on
+/// the local executor, it restarts from the power reached whenever a new power is requested and
+/// is cancelled by `stop`.
+#[derive(DoCommand)]
+pub struct RampedMotor<M> {
+    motor: M,
+    max_power_ramp_per_sec: f64,
+    max_rpm: f64,
+    // power last applied to the motor, updated by the ramp task
+    power: Arc<Mutex<f64>>,
+    ramp_timer: RampTimer,
+    ramp: Option<Task<()>>,
+}
+
+impl<M> RampedMotor<M>
+where
+    M: Motor + Clone + 'static,
+{
+    pub fn new(motor: M, max_power_ramp_per_sec: f64, max_rpm: f64) -> Result<Self, MotorError> {
+        if !(max_power_ramp_per_sec.is_finite() && max_power_ramp_per_sec > 0.0) {
+            return Err(MotorError::ConfigError(
+                "RampedMotor, 'max_power_ramp_per_sec' has to be positive",
+            ));
+        }
+        Ok(Self {
+            motor,
+            max_power_ramp_per_sec,
+            max_rpm,
+            power: Default::default(),
+            ramp_timer: default_ramp_timer(),
+            ramp: None,
+        })
+    }
+
+    /// Time the power updates of ramps with `ramp_timer` rather than the system clock
+    pub fn with_ramp_timer(mut self, ramp_timer: RampTimer) -> Self {
+        self.ramp_timer = ramp_timer;
+        self
+    }
+
+    /// Power last applied to the wrapped motor
+    pub fn current_power(&self) -> f64 {
+        *self.power.lock().unwrap()
+    }
+}
+
+// Move the power of `motor` from `from` to `to` at `rate` per second, updating it every
+// RAMP_INTERVAL as timed by `timer`
+async fn ramp_power<M: Motor>(
+    mut motor: M,
+    from: f64,
+    to: f64,
+    rate: f64,
+    power: Arc<Mutex<f64>>,
+    timer: RampTimer,
+) {
+    let mut elapsed = Duration::ZERO;
+    loop {
+        let step = rate * elapsed.as_secs_f64();
+        let next = if to > from {
+            (from + step).min(to)
+        } else {
+            (from - step).max(to)
+        };
+        if let Err(e) = motor.set_power(next) {
+            log::error!("failed to ramp motor power: {}", e);
+            return;
+        }
+        *power.lock().unwrap() = next;
+        if next == to {
+            return;
+        }
+        timer(RAMP_INTERVAL).await;
+        elapsed += RAMP_INTERVAL;
+    }
+}
+
+impl<M> Motor for RampedMotor<M>
+where
+    M: Motor + Clone + 'static,
+{
+    fn set_power(&mut self, pct: f64) -> Result<(), MotorError> {
+        if !(-1.0..=1.0).contains(&pct) {
+            return Err(MotorError::PowerSetError);
+        }
+        let _ = self.ramp.take();
+        let from = self.current_power();
+        if from == pct {
+            return self.motor.set_power(pct);
+        }
+        self.ramp = Some(Executor::new().spawn(ramp_power(
+            self.motor.clone(),
+            from,
+            pct,
+            self.max_power_ramp_per_sec,
+            self.power.clone(),
+            self.ramp_timer.clone(),
+        )));
+        Ok(())
+    }
+    fn get_position(&mut self) -> Result<i32, MotorError> {
+        self.motor.get_position()
+    }
+    fn get_position_revolutions(&mut self) -> Result<f64, MotorError> {
+        self.motor.get_position_revolutions()
+    }
+    /// The returned duration doesn't account for the time spent ramping
+    fn go_for(&mut self, rpm: f64, revolutions: f64) -> Result<Option<Duration>, MotorError> {
+        let (pwr, dur) = go_for_math(self.max_rpm, rpm, revolutions)?;
+        self.set_power(pwr)?;
+        Ok(dur)
+    }
+    fn get_properties(&mut self) -> MotorSupportedProperties {
+        self.motor.get_properties()
+    }
+    fn get_fault_status(&mut self) -> Result<MotorFaultStatus, MotorError> {
+        self.motor.get_fault_status()
+    }
+}
+
+impl<M> Actuator for RampedMotor<M>
+where
+    M: Motor + Clone + 'static,
+{
+    fn is_moving(&mut self) -> Result<bool, ActuatorError> {
+        self.motor.is_moving()
+    }
+    fn stop(&mut self) -> Result<(), ActuatorError> {
+        let _ = self.ramp.take();
+        *self.power.lock().unwrap() = 0.0;
+        self.motor.stop()
+    }
+}
+
+impl<M> Status for RampedMotor<M>
+where
+    M: Motor + Clone + 'static,
+{
+    fn get_status(&self) -> Result<Option<google::protobuf::Struct>, StatusError> {
+        self.motor.get_status()
+    }
+}
+
 // Represents a motor using a A, B, and PWM pins
 #[derive(DoCommand)]
 pub(crate) struct PwmABMotor<B> {
@@ -208,6 +594,7 @@ pub(crate) struct PwmABMotor<B> {
     pwm_pin: i32,
     max_rpm: f64,
     dir_flip: bool,
+    fault_pin: Option<FaultPin>,
 }
 
 impl<B> PwmABMotor<B>
@@ -229,6 +616,7 @@ where
             pwm_pin,
             max_rpm,
             dir_flip,
+            fault_pin: None,
         };
         // we start with this because we want to reserve a timer and PWM channel early
         // for boards where these are a limited resource
@@ -245,7 +633,10 @@ where
         r_keys
     }
 
-    pub(crate) fn from_config(cfg: ConfigType, board: BoardType) -> Result<MotorType, MotorError> {
+    pub(crate) fn from_config(
+        cfg: ConfigType,
+        mut board: BoardType,
+    ) -> Result<MotorType, MotorError> {
         let pins =
             cfg.get_attribute::<MotorPinsConfig>("pins")
                 .or(Err(MotorError::ConfigError(
@@ -262,11 +653,13 @@ where
             .pwm
             .ok_or(MotorError::ConfigError("PwmABMotor, need 'pwm' pin"))?;
         let max_rpm: f64 = cfg.get_attribute::<f64>("max_rpm").unwrap_or(100.0);
-        let dir_flip: bool = cfg.get_attribute::<bool>("dir_flip").unwrap_or_default();
+        let dir_flip = dir_flip_from_config(&cfg)?;
+        let fault_pin = fault_pin_from_config(&cfg, &mut board)?;
 
-        Ok(Arc::new(Mutex::new(PwmABMotor::new(
-            a_pin, b_pin, pwm_pin, max_rpm, dir_flip, board,
-        )?)))
+        Ok(Arc::new(Mutex::new(
+            PwmABMotor::new(a_pin, b_pin, pwm_pin, max_rpm, dir_flip, board)?
+                .with_fault_pin(fault_pin),
+        )))
     }
 }
 
@@ -278,7 +671,7 @@ where
         if !(-1.0..=1.0).contains(&pct) {
             return Err(MotorError::PowerSetError);
         }
-        let set_forwards = (pct > 0.0) && !self.dir_flip;
+        let set_forwards = (pct > 0.0) != self.dir_flip;
         if set_forwards {
             self.board.set_gpio_pin_level(self.a_pin, false)?;
             self.board.set_gpio_pin_level(self.b_pin, true)?;
@@ -312,6 +705,11 @@ where
             position_reporting: false,
         }
     }
+
+    fn get_fault_status(&mut self) -> Result<MotorFaultStatus, MotorError> {
+        read_fault_pin(&self.board, self.fault_pin)?
+            .ok_or(MotorError::MotorMethodUnimplemented("get_fault_status"))
+    }
 }
 
 impl<B> Status for PwmABMotor<B>
@@ -327,6 +725,7 @@ where
                 kind: Some(google::protobuf::value::Kind::NumberValue(pos)),
             },
         );
+        insert_fault_status(&mut hm, &self.board, self.fault_pin);
         Ok(Some(google::protobuf::Struct { fields: hm }))
     }
 }
@@ -351,6 +750,7 @@ pub(crate) struct PwmDirectionMotor<B> {
     pwm_pin: i32,
     max_rpm: f64,
     dir_flip: bool,
+    fault_pin: Option<FaultPin>,
 }
 
 impl<B> PwmDirectionMotor<B>
@@ -370,6 +770,7 @@ where
             pwm_pin,
             max_rpm,
             dir_flip,
+            fault_pin: None,
         };
         // we start with this because we want to reserve a timer and PWM channel early
         // for boards where these are a limited resource
@@ -377,7 +778,10 @@ where
         Ok(res)
     }
 
-    pub(crate) fn from_config(cfg: ConfigType, board: BoardType) -> Result<MotorType, MotorError> {
+    pub(crate) fn from_config(
+        cfg: ConfigType,
+        mut board: BoardType,
+    ) -> Result<MotorType, MotorError> {
         let pins =
             cfg.get_attribute::<MotorPinsConfig>("pins")
                 .or(Err(MotorError::ConfigError(
@@ -390,10 +794,12 @@ where
             .pwm
             .ok_or(MotorError::ConfigError("PwmDirectionMotor, need 'pwm' pin"))?;
         let max_rpm: f64 = cfg.get_attribute::<f64>("max_rpm").unwrap_or(100.0);
-        let dir_flip: bool = cfg.get_attribute::<bool>("dir_flip").unwrap_or_default();
-        Ok(Arc::new(Mutex::new(PwmDirectionMotor::new(
-            dir_pin, pwm_pin, max_rpm, dir_flip, board,
-        )?)))
+        let dir_flip = dir_flip_from_config(&cfg)?;
+        let fault_pin = fault_pin_from_config(&cfg, &mut board)?;
+        Ok(Arc::new(Mutex::new(
+            PwmDirectionMotor::new(dir_pin, pwm_pin, max_rpm, dir_flip, board)?
+                .with_fault_pin(fault_pin),
+        )))
     }
 }
 
@@ -405,7 +811,7 @@ where
         if !(-1.0..=1.0).contains(&pct) {
             return Err(MotorError::MissingEncoder);
         }
-        let set_high = (pct > 0.0) && !self.dir_flip;
+        let set_high = (pct > 0.0) != self.dir_flip;
         self.board.set_gpio_pin_level(self.dir_pin, set_high)?;
         self.board.set_pwm_duty(self.pwm_pin, pct)?;
         Ok(())
@@ -430,6 +836,11 @@ where
             position_reporting: false,
         }
     }
+
+    fn get_fault_status(&mut self) -> Result<MotorFaultStatus, MotorError> {
+        read_fault_pin(&self.board, self.fault_pin)?
+            .ok_or(MotorError::MotorMethodUnimplemented("get_fault_status"))
+    }
 }
 
 impl<B> Status for PwmDirectionMotor<B>
@@ -445,6 +856,7 @@ where
                 kind: Some(google::protobuf::value::Kind::NumberValue(pos)),
             },
         );
+        insert_fault_status(&mut hm, &self.board, self.fault_pin);
         Ok(Some(google::protobuf::Struct { fields: hm }))
     }
 }
@@ -474,6 +886,7 @@ pub(crate) struct AbMotor<B> {
     dir_flip: bool,
     is_on: bool,
     pwm_pin: i32,
+    fault_pin: Option<FaultPin>,
 }
 
 impl<B> AbMotor<B>
@@ -495,6 +908,7 @@ where
             dir_flip,
             is_on: false,
             pwm_pin: a_pin,
+            fault_pin: None,
         };
         // we start with this because we want to reserve a timer and PWM channel early
         // for boards where these are a limited resource
@@ -503,7 +917,10 @@ where
         Ok(res)
     }
 
-    pub(crate) fn from_config(cfg: ConfigType, board: BoardType) -> Result<MotorType, MotorError> {
+    pub(crate) fn from_config(
+        cfg: ConfigType,
+        mut board: BoardType,
+    ) -> Result<MotorType, MotorError> {
         let pins =
             cfg.get_attribute::<MotorPinsConfig>("pins")
                 .or(Err(MotorError::ConfigError(
@@ -516,10 +933,11 @@ where
             .b
             .ok_or(MotorError::ConfigError("ABMotor, need 'b' pin"))?;
         let max_rpm: f64 = cfg.get_attribute::<f64>("max_rpm").unwrap_or(100.0);
-        let dir_flip: bool = cfg.get_attribute::<bool>("dir_flip").unwrap_or_default();
-        Ok(Arc::new(Mutex::new(AbMotor::new(
-            a_pin, b_pin, max_rpm, dir_flip, board,
-        )?)))
+        let dir_flip = dir_flip_from_config(&cfg)?;
+        let fault_pin = fault_pin_from_config(&cfg, &mut board)?;
+        Ok(Arc::new(Mutex::new(
+            AbMotor::new(a_pin, b_pin, max_rpm, dir_flip, board)?.with_fault_pin(fault_pin),
+        )))
     }
 }
 
@@ -568,6 +986,11 @@ where
             position_reporting: false,
         }
     }
+
+    fn get_fault_status(&mut self) -> Result<MotorFaultStatus, MotorError> {
+        read_fault_pin(&self.board, self.fault_pin)?
+            .ok_or(MotorError::MotorMethodUnimplemented("get_fault_status"))
+    }
 }
 
 impl<B> Status for AbMotor<B>
@@ -583,6 +1006,7 @@ where
                 kind: Some(google::protobuf::value::Kind::NumberValue(pos)),
             },
         );
+        insert_fault_status(&mut hm, &self.board, self.fault_pin);
         Ok(Some(google::protobuf::Struct { fields: hm }))
     }
 }
@@ -603,3 +1027,341 @@ where
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::board::FakeBoard;
+    use crate::common::encoder::FakeIncrementalEncoder;
+    use crate::common::exec::{yield_now, Executor};
+    use crate::common::motor::FakeMotor;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    #[test_log::test]
+    fn test_encoded_motor_gearing() {
+        let mut enc = FakeIncrementalEncoder::new();
+        enc.ticks = 1500.0;
+        let enc = Arc::new(Mutex::new(enc));
+
+        let mut motor = EncodedMotor::new(FakeMotor::new(), enc.clone());
+        assert_eq!(motor.get_position().unwrap(), 1500);
+        assert_eq!(motor.get_position_revolutions().unwrap(), 1500.0);
+
+        let mut motor = EncodedMotor::new(FakeMotor::new(), enc.clone())
+            .with_gearing(200.0, 2.5)
+            .unwrap();
+        assert_eq!(motor.get_position().unwrap(), 3);
+        assert_eq!(motor.get_position_revolutions().unwrap(), 3.0);
+        let mut motor = EncodedMotor::new(FakeMotor::new(), enc.clone())
+            .with_gearing(100.0, 4.0)
+            .unwrap();
+        assert_eq!(motor.get_position().unwrap(), 3);
+        assert_eq!(motor.get_position_revolutions().unwrap(), 3.75);
+        let mut motor = EncodedMotor::new(FakeMotor::new(), enc.clone())
+            .with_gearing(100.0, 5.0)
+            .unwrap();
+        let status = motor.get_status().unwrap().unwrap();
+        assert_eq!(
+            status.fields["position"].kind,
+            Some(google::protobuf::value::Kind::NumberValue(3.0))
+        );
+        assert_eq!(
+            status.fields["position_ticks"].kind,
+            Some(google::protobuf::value::Kind::NumberValue(1500.0))
+        );
+
+        for (ticks_per_rotation, gear_ratio) in [(0.0, 1.0), (100.0, -2.0), (f64::NAN, 1.0)] {
+            assert!(EncodedMotor::new(FakeMotor::new(), enc.clone())
+                .with_gearing(ticks_per_rotation, gear_ratio)
+                .is_err());
+        }
+    }
+
+    #[test_log::test]
+    fn test_encoded_motor_gearing_config() {
+        use crate::common::config::Kind;
+        use crate::common::test_utils::{build_resource, fake_board_dependency};
+
+        let mut enc = FakeIncrementalEncoder::new();
+        enc.ticks = 1000.0;
+        let enc = Arc::new(Mutex::new(enc));
+        let motor = |gearing: Vec<(&'static str, Kind)>| {
+            let pins = Kind::StructValue(HashMap::from([
+                ("pwm".to_owned(), Kind::NumberValue(32.0)),
+                ("dir".to_owned(), Kind::NumberValue(12.0)),
+            ]));
+            build_resource(
+                gpio_motor_from_config,
+                [vec![("pins", pins)], gearing].concat(),
+                vec![
+                    fake_board_dependency(),
+                    Dependency(
+                        ResourceKey::new(EncoderCompName, "enc"),
+                        Resource::Encoder(enc.clone()),
+                    ),
+                ],
+            )
+        };
+
+        let mut geared = motor(vec![
+            ("ticks_per_rotation", Kind::NumberValue(100.0)),
+            ("gear_ratio", Kind::NumberValue(4.0)),
+        ])
+        .unwrap();
+        assert_eq!(geared.get_position_revolutions().unwrap(), 2.5);
+        assert_eq!(geared.get_position().unwrap(), 2);
+        assert_eq!(motor(vec![]).unwrap().get_position().unwrap(), 1000);
+
+        for invalid in [
+            ("ticks_per_rotation", Kind::NumberValue(0.0)),
+            ("ticks_per_rotation", Kind::StringValue("many".to_owned())),
+            ("gear_ratio", Kind::NumberValue(-1.0)),
+            ("gear_ratio", Kind::BoolValue(true)),
+        ] {
+            assert!(matches!(
+                motor(vec![invalid]),
+                Err(MotorError::ConfigError(_))
+            ));
+        }
+    }
+
+    #[test_log::test]
+    fn test_encoded_motor_inverted() {
+        let mut enc = FakeIncrementalEncoder::new();
+        enc.ticks = 200.0;
+        let mut motor = EncodedMotor::new(FakeMotor::new(), Arc::new(Mutex::new(enc)))
+            .with_gearing(100.0, 1.0)
+            .unwrap()
+            .with_inverted(true);
+        assert_eq!(motor.get_position().unwrap(), -2);
+        let status = motor.get_status().unwrap().unwrap();
+        assert_eq!(
+            status.fields["position_ticks"].kind,
+            Some(google::protobuf::value::Kind::NumberValue(-200.0))
+        );
+    }
+
+    #[test_log::test]
+    fn test_motor_dir_flip() -> Result<(), MotorError> {
+        let board = Arc::new(Mutex::new(FakeBoard::new(vec![])));
+        let mut motor = PwmDirectionMotor::new(12, 32, 100.0, false, board.clone())?;
+        motor.set_power(0.5)?;
+        assert!(board.get_gpio_level(12)?);
+        motor.set_power(-0.5)?;
+        assert!(!board.get_gpio_level(12)?);
+
+        let mut motor = PwmDirectionMotor::new(12, 32, 100.0, true, board.clone())?;
+        motor.set_power(0.5)?;
+        assert!(!board.get_gpio_level(12)?);
+        motor.set_power(-0.5)?;
+        assert!(board.get_gpio_level(12)?);
+
+        let mut motor = PwmABMotor::new(12, 13, 32, 100.0, false, board.clone())?;
+        motor.set_power(0.5)?;
+        assert!(!board.get_gpio_level(12)? && board.get_gpio_level(13)?);
+        motor.set_power(-0.5)?;
+        assert!(board.get_gpio_level(12)? && !board.get_gpio_level(13)?);
+
+        let mut motor = PwmABMotor::new(12, 13, 32, 100.0, true, board.clone())?;
+        motor.set_power(0.5)?;
+        assert!(board.get_gpio_level(12)? && !board.get_gpio_level(13)?);
+        motor.set_power(-0.5)?;
+        assert!(!board.get_gpio_level(12)? && board.get_gpio_level(13)?);
+        Ok(())
+    }
+
+    #[test_log::test]
+    fn test_motor_direction_config() {
+        use crate::common::config::Kind;
+        use crate::common::test_utils::build_resource;
+
+        let board = Arc::new(Mutex::new(FakeBoard::new(vec![])));
+        let motor = |direction: Vec<(&'static str, Kind)>| {
+            let pins = Kind::StructValue(HashMap::from([
+                ("pwm".to_owned(), Kind::NumberValue(32.0)),
+                ("dir".to_owned(), Kind::NumberValue(12.0)),
+            ]));
+            build_resource(
+                gpio_motor_from_config,
+                [vec![("pins", pins)], direction].concat(),
+                vec![Dependency(
+                    ResourceKey::new("board", "board"),
+                    Resource::Board(board.clone()),
+                )],
+            )
+        };
+        // the direction pin is high when going forwards unless flipped, `dir_flip` and `invert`
+        // cancel each other out
+        for (direction, forwards_level) in [
+            (vec![], true),
+            (vec![("dir_flip", Kind::BoolValue(true))], false),
+            (vec![("invert", Kind::BoolValue(true))], false),
+            (
+                vec![
+                    ("dir_flip", Kind::BoolValue(true)),
+                    ("invert", Kind::BoolValue(true)),
+                ],
+                true,
+            ),
+        ] {
+            motor(direction).unwrap().set_power(0.5).unwrap();
+            assert_eq!(board.get_gpio_level(12).unwrap(), forwards_level);
+        }
+        for malformed in [
+            ("dir_flip", Kind::StringValue("yes".to_owned())),
+            ("invert", Kind::NumberValue(1.0)),
+        ] {
+            assert!(matches!(
+                motor(vec![malformed]),
+                Err(MotorError::ConfigError(_))
+            ));
+        }
+    }
+
+    #[test_log::test]
+    fn test_ramped_motor_config() {
+        use crate::common::config::Kind;
+        use crate::common::test_utils::{build_resource, fake_board_dependency};
+
+        let motor = |ramp: Vec<(&'static str, Kind)>| {
+            let pins = Kind::StructValue(HashMap::from([
+                ("pwm".to_owned(), Kind::NumberValue(32.0)),
+                ("dir".to_owned(), Kind::NumberValue(12.0)),
+            ]));
+            build_resource(
+                gpio_motor_from_config,
+                [vec![("pins", pins)], ramp].concat(),
+                vec![fake_board_dependency()],
+            )
+        };
+        assert!(motor(vec![]).is_ok());
+        assert!(motor(vec![("max_power_ramp_per_sec", Kind::NumberValue(0.5))]).is_ok());
+        for invalid in [Kind::NumberValue(0.0), Kind::StringValue("fast".to_owned())] {
+            assert!(matches!(
+                motor(vec![("max_power_ramp_per_sec", invalid)]),
+                Err(MotorError::ConfigError(_))
+            ));
+        }
+    }
+
+    #[test_log::test]
+    fn test_ramped_motor() -> Result<(), MotorError> {
+        let board = Arc::new(Mutex::new(FakeBoard::new(vec![])));
+        let inner = Arc::new(Mutex::new(PwmDirectionMotor::new(
+            12,
+            32,
+            100.0,
+            false,
+            board.clone(),
+        )?));
+        assert!(RampedMotor::new(inner.clone(), 0.0, 100.0).is_err());
+
+        // the power is updated whenever the test releases the ramp timer
+        let (tick_tx, tick_rx) = async_channel::unbounded::<()>();
+        let ticks = Rc::new(Cell::new(0));
+        let ticks_cloned = ticks.clone();
+        // full power is reached after 2 seconds, 100 intervals
+        let mut motor =
+            RampedMotor::new(inner, 0.5, 100.0)?.with_ramp_timer(Arc::new(move |interval| {
+                assert_eq!(interval, RAMP_INTERVAL);
+                let (tick_rx, ticks) = (tick_rx.clone(), ticks_cloned.clone());
+                Box::pin(async move {
+                    let _ = tick_rx.recv().await;
+                    ticks.set(ticks.get() + 1);
+                })
+            }));
+        let exec = Executor::new();
+        let tick = |n: usize| {
+            let target = ticks.get() + n;
+            for _ in 0..n {
+                tick_tx.try_send(()).unwrap();
+            }
+            exec.block_on(async {
+                while ticks.get() < target {
+                    yield_now().await;
+                }
+            });
+        };
+        let duty_is = |expected: f64| (board.get_pwm_duty(32) - expected).abs() < 1e-9;
+
+        motor.set_power(1.0)?;
+        let mut trajectory = vec![];
+        for _ in 0..5 {
+            tick(10);
+            trajectory.push(board.get_pwm_duty(32));
+        }
+        // the power increases by the ramp rate over each interval and is still short of the target
+        for (i, duty) in trajectory.iter().enumerate() {
+            assert!(
+                (duty - 0.1 * (i + 1) as f64).abs() < 1e-9,
+                "{:?}",
+                trajectory
+            );
+        }
+        assert_eq!(motor.current_power(), board.get_pwm_duty(32));
+
+        // a new request ramps from the power reached
+        motor.set_power(0.25)?;
+        tick(10);
+        assert!(duty_is(0.4), "{}", board.get_pwm_duty(32));
+        tick(15);
+        assert_eq!(board.get_pwm_duty(32), 0.25);
+        assert_eq!(motor.current_power(), 0.25);
+
+        // stop cancels the ramp
+        motor.set_power(1.0)?;
+        tick(10);
+        assert!(duty_is(0.35), "{}", board.get_pwm_duty(32));
+        motor.stop()?;
+        assert_eq!(board.get_pwm_duty(32), 0.0);
+        assert!(motor.ramp.is_none());
+        assert_eq!(motor.current_power(), 0.0);
+        assert!(motor.set_power(1.5).is_err());
+        Ok(())
+    }
+
+    #[test_log::test]
+    fn test_motor_fault_pin() {
+        let board = Arc::new(Mutex::new(FakeBoard::new(vec![])));
+        let mut motor = PwmDirectionMotor::new(12, 32, 100.0, false, board.clone()).unwrap();
+        assert!(matches!(
+            motor.get_fault_status(),
+            Err(MotorError::MotorMethodUnimplemented(_))
+        ));
+        let status = motor.get_status().unwrap().unwrap();
+        assert!(!status.fields.contains_key("fault"));
+
+        let mut motor = motor.with_fault_pin(Some(FaultPin::new(14, false)));
+        board.lock().unwrap().set_gpio_pin_level(14, false).unwrap();
+        assert_eq!(
+            motor.get_fault_status().unwrap(),
+            MotorFaultStatus { fault: false }
+        );
+        board.lock().unwrap().set_gpio_pin_level(14, true).unwrap();
+        assert_eq!(
+            motor.get_fault_status().unwrap(),
+            MotorFaultStatus { fault: true }
+        );
+        let status = motor.get_status().unwrap().unwrap();
+        assert_eq!(
+            status.fields["fault"].kind,
+            Some(google::protobuf::value::Kind::BoolValue(true))
+        );
+
+        // an active-low nFAULT line signals a fault by being pulled low
+        let mut motor = motor.with_fault_pin(Some(FaultPin::new(14, true)));
+        assert!(!motor.get_fault_status().unwrap().fault);
+        board.lock().unwrap().set_gpio_pin_level(14, false).unwrap();
+        assert!(motor.get_fault_status().unwrap().fault);
+
+        let enc = Arc::new(Mutex::new(FakeIncrementalEncoder::new()));
+        let mut motor = EncodedMotor::new(motor, enc);
+        assert!(motor.get_fault_status().unwrap().fault);
+        let status = motor.get_status().unwrap().unwrap();
+        assert_eq!(
+            status.fields["fault"].kind,
+            Some(google::protobuf::value::Kind::BoolValue(true))
+        );
+    }
+}
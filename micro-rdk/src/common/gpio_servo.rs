@@ -21,12 +21,20 @@
 //! servo.move_to(90).unwrap()
 //!
 //! ```
+//!
+//! # Position feedback
+//!
+//! By default `get_position` reports the last commanded angle. A servo whose potentiometer is
+//! wired to an analog reader of the board can report its measured angle instead: configure
+//! `feedback_analog_reader` with the name of the reader and `feedback_min_value` /
+//! `feedback_max_value` with the raw values read at `min_angle_deg` and `max_angle_deg`.
 
 use crate::common::status::StatusError;
 use std::sync::{Arc, Mutex};
 
 use super::{
     actuator::{Actuator, ActuatorError},
+    analog::{AnalogReader, AnalogReaderType},
     board::{Board, BoardType},
     config::ConfigType,
     registry::{get_board_from_dependencies, ComponentRegistry, Dependency},
@@ -62,11 +70,14 @@ pub(crate) fn from_config(
     )?;
     let servo_settings = GpioServoSettings::from_config(&cfg)?;
     let pin = cfg.get_attribute::<i32>("pin")?;
-    Ok(Arc::new(Mutex::new(GpioServo::<BoardType>::new(
-        board.clone(),
-        pin,
-        servo_settings,
-    )?)))
+    let mut servo = GpioServo::<BoardType>::new(board.clone(), pin, servo_settings)?;
+    if let Ok(reader) = cfg.get_attribute::<String>("feedback_analog_reader") {
+        let reader = board.get_analog_reader_by_name(reader)?;
+        let raw_at_min = cfg.get_attribute::<u16>("feedback_min_value")?;
+        let raw_at_max = cfg.get_attribute::<u16>("feedback_max_value")?;
+        servo = servo.with_feedback(reader, raw_at_min, raw_at_max)?;
+    }
+    Ok(Arc::new(Mutex::new(servo)))
 }
 
 #[derive(Debug)]
@@ -122,6 +133,15 @@ pub struct GpioServo<B> {
     max_period_us: u32,
     frequency: u32,
     pwm_resolution: u32,
+    feedback: Option<ServoFeedback>,
+}
+
+// Analog reading of the servo potentiometer, calibrated with the raw values read at the minimum
+// and maximum angles
+struct ServoFeedback {
+    reader: AnalogReaderType<u16>,
+    raw_at_min: u16,
+    raw_at_max: u16,
 }
 
 impl<B> GpioServo<B>
@@ -143,11 +163,40 @@ where
             max_period_us: settings.max_period_us,
             frequency: settings.frequency,
             pwm_resolution: settings.pwm_resolution,
+            feedback: None,
         };
         res.board.set_pwm_frequency(pin, res.frequency as u64)?;
         Ok(res)
     }
 
+    /// Report the angle measured by `reader` from `get_position`, `raw_at_min` and `raw_at_max`
+    /// being the values read at the minimum and maximum angles (either can be the larger one)
+    pub fn with_feedback(
+        mut self,
+        reader: AnalogReaderType<u16>,
+        raw_at_min: u16,
+        raw_at_max: u16,
+    ) -> Result<Self, ServoError> {
+        if raw_at_min == raw_at_max {
+            return Err(ServoError::ServoConfigurationError(
+                "GpioServo: feedback_min_value and feedback_max_value must differ",
+            ));
+        }
+        self.feedback = Some(ServoFeedback {
+            reader,
+            raw_at_min,
+            raw_at_max,
+        });
+        Ok(self)
+    }
+
+    fn feedback_to_angle(&self, feedback: &ServoFeedback, raw: u16) -> u32 {
+        let ratio = (raw as f64 - feedback.raw_at_min as f64)
+            / (feedback.raw_at_max as f64 - feedback.raw_at_min as f64);
+        let angle_range = (self.max_angle_deg - self.min_angle_deg) as f64;
+        (self.min_angle_deg as f64 + ratio.clamp(0.0, 1.0) * angle_range).round() as u32
+    }
+
     pub fn angle_to_duty_pct(&self, angle_deg: u32) -> f64 {
         let period = 1.0 / (self.frequency as f64);
         let angle_range = (self.max_angle_deg - self.min_angle_deg) as f64;
@@ -189,6 +238,10 @@ where
         Ok(())
     }
     fn get_position(&mut self) -> Result<u32, ServoError> {
+        if let Some(feedback) = self.feedback.as_ref() {
+            let raw = feedback.reader.lock().unwrap().read()?;
+            return Ok(self.feedback_to_angle(feedback, raw));
+        }
         let duty_pct = self.board.get_pwm_duty(self.pin);
         Ok(self.duty_pct_to_angle(duty_pct))
     }
@@ -217,6 +270,7 @@ where
 
 #[cfg(test)]
 mod tests {
+    use crate::common::analog::FakeAnalogReader;
     use crate::common::board::{Board, FakeBoard};
     use crate::common::gpio_servo::{GpioServo, GpioServoSettings};
     use crate::common::servo::{Servo, ServoError};
@@ -276,6 +330,50 @@ mod tests {
         Ok(())
     }
 
+    #[test_log::test]
+    fn test_get_position_with_feedback() -> Result<(), ServoError> {
+        let reader = Arc::new(Mutex::new(FakeAnalogReader::new("pot".to_string(), 2048)));
+        let board = Arc::new(Mutex::new(FakeBoard::new(vec![reader])));
+        let servo_settings = GpioServoSettings {
+            min_angle_deg: 0,
+            max_angle_deg: 180,
+            min_period_us: 500,
+            max_period_us: 2500,
+            frequency: 300,
+            pwm_resolution: 0,
+        };
+        let reader = board.get_analog_reader_by_name("pot".to_string())?;
+
+        // the measured angle is reported rather than the commanded one
+        let mut servo = GpioServo::new(board.clone(), 2, servo_settings)?.with_feedback(
+            reader.clone(),
+            0,
+            4096,
+        )?;
+        servo.move_to(30)?;
+        assert_eq!(servo.get_position()?, 90);
+
+        // inverted potentiometer, out of range readings are clamped
+        let servo_settings = GpioServoSettings {
+            min_angle_deg: 0,
+            max_angle_deg: 180,
+            min_period_us: 500,
+            max_period_us: 2500,
+            frequency: 300,
+            pwm_resolution: 0,
+        };
+        let mut servo =
+            GpioServo::new(board.clone(), 2, servo_settings)?.with_feedback(reader, 2560, 512)?;
+        assert_eq!(servo.get_position()?, 45);
+        let mut servo = servo.with_feedback(
+            Arc::new(Mutex::new(FakeAnalogReader::new("pot".to_string(), 4000))),
+            2560,
+            512,
+        )?;
+        assert_eq!(servo.get_position()?, 0);
+        Ok(())
+    }
+
     #[test_log::test]
     fn test_move_to_with_pwm_resolution() -> Result<(), ServoError> {
         let board = Arc::new(Mutex::new(FakeBoard::new(vec![])));
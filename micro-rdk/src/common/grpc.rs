@@ -1,5 +1,6 @@
 use core::fmt;
 use std::{
+    collections::VecDeque,
     convert::Infallible,
     fmt::Debug,
     marker::PhantomData,
@@ -9,13 +10,19 @@ use std::{
 
 use crate::{
     common::{
-        analog::AnalogReader, board::Board, motor::Motor, robot::LocalRobot,
+        analog::AnalogReader,
+        base::BaseError,
+        board::Board,
+        generic::{DoCommandFuture, GenericError},
+        motor::Motor,
+        robot::{LocalRobot, RobotError, ROBOT_COMMAND_RESOURCE_NAME},
+        sensor::INCLUDE_CAPTURE_TIME_EXTRA,
         webrtc::grpc::WebRtcGrpcService,
     },
     google::rpc::Status,
     proto::{self, component, robot, rpc::webrtc::v1::CallResponse},
 };
-use bytes::BufMut;
+use bytes::{BufMut, BytesMut};
 use futures_lite::{Future, StreamExt};
 use http_body_util::{combinators::BoxBody, BodyExt, StreamBody};
 use hyper::{
@@ -26,6 +33,7 @@ use hyper::{
 };
 use log::*;
 use prost::Message;
+use rand::{distributions::Alphanumeric, Rng};
 use std::{
     pin::Pin,
     task::{Context, Poll},
@@ -118,16 +126,209 @@ pub trait GrpcResponse {
     fn get_data(&mut self) -> Bytes;
 }
 
+const AUTHENTICATE_PATH: &str = "/proto.rpc.v1.AuthService/Authenticate";
+
 #[derive(Clone)]
 pub struct GrpcServer<R> {
     _response: PhantomData<R>,
     robot: Arc<Mutex<LocalRobot>>,
     signaling_server: Option<Arc<SignalingServer>>,
+    auth: Option<Arc<ApiKeyAuth>>,
 }
 
 pub struct GrpcServerInner<'a> {
     robot: &'a Arc<Mutex<LocalRobot>>,
     signaling_server: &'a Option<Arc<SignalingServer>>,
+    auth: &'a Option<Arc<ApiKeyAuth>>,
+}
+
+// compare secrets without leaking how many leading bytes matched
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+// number of session tokens kept valid at once, the oldest one is revoked when a new one is issued
+const MAX_SESSION_TOKENS: usize = 8;
+const SESSION_TOKEN_LEN: usize = 32;
+
+/// Api key expected by `AuthService/Authenticate` and the session tokens handed out in exchange
+pub(crate) struct ApiKeyAuth {
+    api_key: Arc<str>,
+    tokens: Mutex<VecDeque<String>>,
+}
+
+impl ApiKeyAuth {
+    pub(crate) fn new(api_key: Arc<str>) -> Self {
+        Self {
+            api_key,
+            tokens: Mutex::new(VecDeque::with_capacity(MAX_SESSION_TOKENS)),
+        }
+    }
+
+    /// Exchange the api key for a random session token, `None` when `payload` isn't the key
+    fn issue_token(&self, payload: &str) -> Option<String> {
+        if !constant_time_eq(payload.as_bytes(), self.api_key.as_bytes()) {
+            return None;
+        }
+        let token: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(SESSION_TOKEN_LEN)
+            .map(char::from)
+            .collect();
+        let mut tokens = self.tokens.lock().unwrap();
+        if tokens.len() == MAX_SESSION_TOKENS {
+            let _ = tokens.pop_front();
+        }
+        tokens.push_back(token.clone());
+        Some(token)
+    }
+
+    /// Check the value of an `authorization` header (`Bearer <token>`) against issued tokens,
+    /// requests to `AuthService/Authenticate` need no token
+    fn authorize(&self, path: &str, authorization: Option<&str>) -> bool {
+        if path == AUTHENTICATE_PATH {
+            return true;
+        }
+        let Some(token) = authorization.and_then(|value| value.strip_prefix("Bearer ")) else {
+            return false;
+        };
+        // don't short-circuit so the time taken doesn't tell which token matched
+        self.tokens.lock().unwrap().iter().fold(false, |found, t| {
+            constant_time_eq(t.as_bytes(), token.as_bytes()) | found
+        })
+    }
+}
+
+// The `name` every component request starts with
+#[cfg(not(feature = "esp32"))]
+#[derive(Clone, PartialEq, prost::Message)]
+struct ComponentRequestName {
+    #[prost(string, tag = "1")]
+    name: String,
+}
+
+// The resource a call is recorded against in the metrics, e.g. `rdk:component:motor/left`, or
+// `robot` for the robot's own services. `None` when the call names a resource the robot doesn't
+// have so clients can't grow the metrics table by making up names
+#[cfg(not(feature = "esp32"))]
+fn call_metrics_resource(robot: &Mutex<LocalRobot>, path: &str, payload: &[u8]) -> Option<String> {
+    let Some(service) = path.strip_prefix("/viam.component.") else {
+        return Some("robot".to_owned());
+    };
+    // the service of `movement_sensor` is `movementsensor`
+    let service = service.split('.').next()?;
+    let name = ComponentRequestName::decode(payload).ok()?.name;
+    robot
+        .lock()
+        .unwrap()
+        .get_resource_names()
+        .ok()?
+        .into_iter()
+        .find(|r| r.name == name && r.subtype.replace('_', "") == service)
+        .map(|r| format!("{}:{}:{}/{}", r.namespace, r.r#type, r.subtype, r.name))
+}
+
+// call statistics aren't kept on esp32
+#[cfg(feature = "esp32")]
+fn call_metrics_resource(_: &Mutex<LocalRobot>, _: &str, _: &[u8]) -> Option<String> {
+    None
+}
+
+// Calls answered with `RpcUnimplemented`, unknown paths included, aren't recorded either
+fn record_call_metrics<T>(resource: Option<&str>, start: Instant, res: &Result<T, ServerError>) {
+    let Some(resource) = resource else {
+        return;
+    };
+    if !matches!(res, Err(e) if matches!(e.grpc_error, GrpcError::RpcUnimplemented)) {
+        crate::common::metrics::record_call(resource, start.elapsed(), res.is_ok());
+    }
+}
+
+// Arguments refused by a base (a null speed for instance) are the client's mistake
+fn base_error_to_server_error(err: BaseError) -> ServerError {
+    let grpc_error = match err {
+        BaseError::BaseInvalidArgument(_) => GrpcError::RpcInvalidArgument,
+        _ => GrpcError::RpcInternal,
+    };
+    ServerError::new(grpc_error, Some(err.into()))
+}
+
+type ResponseStream =
+    Pin<Box<dyn futures_lite::Stream<Item = Result<Bytes, ServerError>> + Sync + Send>>;
+
+/// Response to a unary rpc that completes asynchronously
+pub type ResponseFuture = Pin<Box<dyn Future<Output = Result<Bytes, ServerError>> + Sync + Send>>;
+
+// Size above which a chunk of a streamed response is sent
+const RESPONSE_CHUNK_SIZE: usize = 1024;
+
+// Encode a gRPC message made of a single repeated field as a stream of chunks of about
+// RESPONSE_CHUNK_SIZE bytes, so the encoded message is never held in memory as a whole. The
+// elements are encoded and measured, tag included, by `encode_item` and `item_len`.
+fn encode_message_stream<T, L, E>(items: Vec<T>, item_len: L, encode_item: E) -> ResponseStream
+where
+    T: Send + Sync + 'static,
+    L: Fn(&T) -> usize,
+    E: Fn(&T, &mut BytesMut) + Send + Sync + 'static,
+{
+    let len: usize = items.iter().map(item_len).sum();
+    // same 5 bytes header as encode_message
+    let mut header = BytesMut::with_capacity(RESPONSE_CHUNK_SIZE);
+    header.put_u8(0);
+    header.put_u32(len.try_into().unwrap());
+    let mut pending = Some(header);
+    let mut items = items.into_iter();
+    Box::pin(futures_lite::stream::iter(std::iter::from_fn(move || {
+        let mut chunk = pending
+            .take()
+            .unwrap_or_else(|| BytesMut::with_capacity(RESPONSE_CHUNK_SIZE));
+        while chunk.len() < RESPONSE_CHUNK_SIZE {
+            match items.next() {
+                Some(item) => encode_item(&item, &mut chunk),
+                None => break,
+            }
+        }
+        (!chunk.is_empty()).then(|| Ok(chunk.freeze()))
+    })))
+}
+
+// GetReadingsResponse.readings, a map<string, Value> with tag 1 whose entries are encoded as
+// messages with the key as field 1 and the value as field 2
+fn encode_readings_stream(
+    readings: impl IntoIterator<Item = (String, crate::google::protobuf::Value)>,
+) -> ResponseStream {
+    use prost::encoding::{
+        encode_key, encode_varint, encoded_len_varint, key_len, message, string, WireType,
+    };
+    fn entry_len((key, value): &(String, crate::google::protobuf::Value)) -> usize {
+        string::encoded_len(1, key) + message::encoded_len(2, value)
+    }
+    encode_message_stream(
+        readings.into_iter().collect(),
+        |entry| {
+            let len = entry_len(entry);
+            key_len(1) + encoded_len_varint(len as u64) + len
+        },
+        |entry, buf| {
+            encode_key(1, WireType::LengthDelimited, buf);
+            encode_varint(entry_len(entry) as u64, buf);
+            string::encode(1, &entry.0, buf);
+            message::encode(2, &entry.1, buf);
+        },
+    )
+}
+
+// whether a GetReadings request asked for the capture time through its extra parameters
+fn includes_capture_time(extra: &Option<crate::google::protobuf::Struct>) -> bool {
+    extra
+        .as_ref()
+        .and_then(|extra| extra.fields.get(INCLUDE_CAPTURE_TIME_EXTRA))
+        .is_some_and(|value| {
+            matches!(
+                value.kind,
+                Some(crate::google::protobuf::value::Kind::BoolValue(true))
+            )
+        })
 }
 
 // TODO(RSDK-9243): The generic parameter R isn't really used here and can probably be removed,
@@ -141,6 +342,7 @@ where
             _response: PhantomData,
             robot,
             signaling_server: None,
+            auth: None,
         }
     }
 
@@ -149,6 +351,19 @@ where
     pub(crate) fn register_signaling_server(&mut self, signaling_server: Arc<SignalingServer>) {
         let _ = self.signaling_server.insert(signaling_server);
     }
+
+    /// Require requests to carry `authorization: Bearer <token>`, the token is obtained by
+    /// calling `AuthService/Authenticate` with the api key as credentials payload. Requests
+    /// without a valid token are rejected with `RpcUnauthenticated`, over HTTP/2 and WebRTC alike
+    pub(crate) fn require_api_key(&mut self, api_key: Arc<str>) {
+        let _ = self.auth.insert(Arc::new(ApiKeyAuth::new(api_key)));
+    }
+
+    fn check_authorization(&self, path: &str, authorization: Option<&str>) -> bool {
+        self.auth
+            .as_ref()
+            .map_or(true, |auth| auth.authorize(path, authorization))
+    }
 }
 
 impl<'a> GrpcServerInner<'a> {
@@ -199,11 +414,10 @@ impl<'a> GrpcServerInner<'a> {
         }
     }
 
-    pub(crate) fn handle_request(
-        self,
-        path: &str,
-        payload: &[u8],
-    ) -> Pin<Box<dyn futures_lite::Stream<Item = Result<Bytes, ServerError>> + Sync + Send>> {
+    pub(crate) fn handle_request(mut self, path: &str, payload: &[u8]) -> ResponseStream {
+        if let Some(response) = self.handle_do_command_request(path, payload) {
+            return Box::pin(futures_lite::stream::once_future(response));
+        }
         // TODO(RSDK-8785): This is currently the only bidi call that Micro-RDK supports in HTTP2
         // mode, so this is a lazy hack to redirect that call and allow all the other existing unary
         // calls to filter through to the existing match in `handle_unary_request`, which is also
@@ -211,17 +425,120 @@ impl<'a> GrpcServerInner<'a> {
         // demand a better system.
         match path {
             "/proto.rpc.webrtc.v1.SignalingService/Call" => self.signaling_service_call(payload),
+            // responses that can grow large are encoded incrementally rather than in one buffer
+            "/viam.robot.v1.RobotService/ResourceNames" => {
+                self.handle_streamed_request(path, payload, |srv| {
+                    // ResourceNamesResponse.resources, tag 1
+                    srv.get_resource_names().map(|names| {
+                        encode_message_stream(
+                            names,
+                            |name| prost::encoding::message::encoded_len(1, name),
+                            |name, buf| prost::encoding::message::encode(1, name, buf),
+                        )
+                    })
+                })
+            }
+            "/viam.component.sensor.v1.SensorService/GetReadings" => {
+                self.handle_streamed_request(path, payload, |srv| {
+                    srv.sensor_readings(payload).map(encode_readings_stream)
+                })
+            }
+            "/viam.component.movementsensor.v1.MovementSensorService/GetReadings" => self
+                .handle_streamed_request(path, payload, |srv| {
+                    srv.movement_sensor_readings(payload)
+                        .map(encode_readings_stream)
+                }),
+            "/viam.component.powersensor.v1.PowerSensorService/GetReadings" => self
+                .handle_streamed_request(path, payload, |srv| {
+                    srv.power_sensor_readings(payload)
+                        .map(encode_readings_stream)
+                }),
             _ => Box::pin(futures_lite::stream::once(
                 self.handle_unary_request(path, payload),
             )),
         }
     }
 
-    pub(crate) fn handle_unary_request(
+    fn handle_streamed_request(
         mut self,
         path: &str,
         payload: &[u8],
+        handler: impl FnOnce(&mut Self) -> Result<ResponseStream, ServerError>,
+    ) -> ResponseStream {
+        let start = Instant::now();
+        let resource = call_metrics_resource(self.robot, path, payload);
+        let res = handler(&mut self);
+        record_call_metrics(resource.as_deref(), start, &res);
+        res.unwrap_or_else(|e| Box::pin(futures_lite::stream::once(Err(e))))
+    }
+
+    pub(crate) fn handle_unary_request(
+        self,
+        path: &str,
+        payload: &[u8],
     ) -> Result<Bytes, ServerError> {
+        let start = Instant::now();
+        let resource = call_metrics_resource(self.robot, path, payload);
+        let res = self.dispatch_unary_request(path, payload);
+        record_call_metrics(resource.as_deref(), start, &res);
+        res
+    }
+
+    /// DoCommand requests are answered by awaiting the resource's `do_command_async`, so a command
+    /// taking a while doesn't block the executor, and so are motor GoFor and GoTo waiting for the
+    /// move to end. `None` when `path` isn't one of them
+    pub(crate) fn handle_do_command_request(
+        &mut self,
+        path: &str,
+        payload: &[u8],
+    ) -> Option<ResponseFuture> {
+        let start = Instant::now();
+        let response = match path {
+            "/viam.component.board.v1.BoardService/DoCommand" => self.board_do_command(payload),
+            "/viam.component.generic.v1.GenericService/DoCommand" => {
+                self.generic_component_do_command(payload)
+            }
+            #[cfg(feature = "camera")]
+            "/viam.component.camera.v1.CameraService/DoCommand" => self.camera_do_command(payload),
+            "/viam.component.motor.v1.MotorService/DoCommand" => self.motor_do_command(payload),
+            "/viam.component.sensor.v1.SensorService/DoCommand" => self.sensor_do_command(payload),
+            "/viam.component.movementsensor.v1.MovementSensorService/DoCommand" => {
+                self.movement_sensor_do_command(payload)
+            }
+            "/viam.component.encoder.v1.EncoderService/DoCommand" => {
+                self.encoder_do_command(payload)
+            }
+            "/viam.component.powersensor.v1.PowerSensorService/DoCommand" => {
+                self.power_sensor_do_command(payload)
+            }
+            "/viam.component.servo.v1.ServoService/DoCommand" => self.servo_do_command(payload),
+            "/viam.component.motor.v1.MotorService/GoFor" => self.motor_go_for(payload),
+            "/viam.component.motor.v1.MotorService/GoTo" => self.motor_go_to(payload),
+            _ => return None,
+        };
+        let resource = call_metrics_resource(self.robot, path, payload);
+        Some(Box::pin(async move {
+            let res = match response {
+                Ok(response) => response.await,
+                Err(e) => Err(e),
+            };
+            record_call_metrics(resource.as_deref(), start, &res);
+            res
+        }))
+    }
+
+    // Encode the result of a pending DoCommand, failures are reported through `map_err`
+    fn do_command_response(
+        command: DoCommandFuture,
+        map_err: fn(GenericError) -> ServerError,
+    ) -> ResponseFuture {
+        Box::pin(async move {
+            let result = command.await.map_err(map_err)?;
+            GrpcServerInner::encode_message(proto::common::v1::DoCommandResponse { result })
+        })
+    }
+
+    fn dispatch_unary_request(mut self, path: &str, payload: &[u8]) -> Result<Bytes, ServerError> {
         match path {
             "/viam.component.base.v1.BaseService/SetPower" => self.base_set_power(payload),
             "/viam.component.base.v1.BaseService/Stop" => self.base_stop(payload),
@@ -229,6 +546,9 @@ impl<'a> GrpcServerInner<'a> {
             "/viam.component.base.v1.BaseService/Spin" => self.base_spin(payload),
             "/viam.component.base.v1.BaseService/SetVelocity" => self.base_set_velocity(payload),
             "/viam.component.base.v1.BaseService/IsMoving" => self.base_is_moving(payload),
+            "/viam.component.base.v1.BaseService/GetGeometries" => {
+                self.base_get_geometries(payload)
+            }
             "/viam.component.board.v1.BoardService/GetDigitalInterruptValue" => {
                 self.board_get_digital_interrupt_value(payload)
             }
@@ -248,10 +568,7 @@ impl<'a> GrpcServerInner<'a> {
             "/viam.component.board.v1.BoardService/SetPowerMode" => {
                 self.board_set_power_mode(payload)
             }
-            "/viam.component.board.v1.BoardService/DoCommand" => self.board_do_command(payload),
-            "/viam.component.generic.v1.GenericService/DoCommand" => {
-                self.generic_component_do_command(payload)
-            }
+            "/viam.component.board.v1.BoardService/WriteAnalog" => self.board_write_analog(payload),
             #[cfg(feature = "camera")]
             "/viam.component.camera.v1.CameraService/GetImage" => self.camera_get_image(payload),
             #[cfg(feature = "camera")]
@@ -259,13 +576,13 @@ impl<'a> GrpcServerInner<'a> {
                 self.camera_render_frame(payload)
             }
             #[cfg(feature = "camera")]
-            "/viam.component.camera.v1.CameraService/DoCommand" => self.camera_do_command(payload),
+            "/viam.component.camera.v1.CameraService/GetPointCloud" => {
+                self.camera_get_point_cloud(payload)
+            }
             "/viam.component.motor.v1.MotorService/GetPosition" => self.motor_get_position(payload),
             "/viam.component.motor.v1.MotorService/GetProperties" => {
                 self.motor_get_properties(payload)
             }
-            "/viam.component.motor.v1.MotorService/GoFor" => self.motor_go_for(payload),
-            "/viam.component.motor.v1.MotorService/GoTo" => self.motor_go_to(payload),
             "/viam.component.motor.v1.MotorService/IsPowered" => self.motor_is_powered(payload),
             "/viam.component.motor.v1.MotorService/IsMoving" => self.motor_is_moving(payload),
             "/viam.component.motor.v1.MotorService/ResetZeroPosition" => {
@@ -274,14 +591,13 @@ impl<'a> GrpcServerInner<'a> {
             "/viam.component.motor.v1.MotorService/SetPower" => self.motor_set_power(payload),
             "/viam.component.motor.v1.MotorService/Stop" => self.motor_stop(payload),
             "/viam.component.motor.v1.MotorService/SetRPM" => self.motor_set_rpm(payload),
-            "/viam.component.motor.v1.MotorService/DoCommand" => self.motor_do_command(payload),
             "/viam.robot.v1.RobotService/GetVersion" => self.get_version(),
             "/viam.robot.v1.RobotService/ResourceNames" => self.resource_names(payload),
             "/viam.robot.v1.RobotService/GetStatus" => self.robot_status(payload),
             "/viam.robot.v1.RobotService/GetOperations" => self.robot_get_operations(payload),
             "/viam.robot.v1.RobotService/Shutdown" => self.robot_shutdown(payload),
             "/viam.robot.v1.RobotService/GetCloudMetadata" => self.robot_get_cloud_metadata(),
-            "/proto.rpc.v1.AuthService/Authenticate" => self.auth_service_authentificate(payload),
+            AUTHENTICATE_PATH => self.auth_service_authentificate(payload),
             "/proto.rpc.webrtc.v1.SignalingService/OptionalWebRTCConfig" => {
                 self.signaling_service_optional_webrtc_config(payload)
             }
@@ -291,7 +607,6 @@ impl<'a> GrpcServerInner<'a> {
             "/viam.component.sensor.v1.SensorService/GetReadings" => {
                 self.sensor_get_readings(payload)
             }
-            "/viam.component.sensor.v1.SensorService/DoCommand" => self.sensor_do_command(payload),
             "/viam.component.movementsensor.v1.MovementSensorService/GetPosition" => {
                 self.movement_sensor_get_position(payload)
             }
@@ -316,9 +631,6 @@ impl<'a> GrpcServerInner<'a> {
             "/viam.component.movementsensor.v1.MovementSensorService/GetAccuracy" => {
                 self.movement_sensor_get_accuracy(payload)
             }
-            "/viam.component.movementsensor.v1.MovementSensorService/DoCommand" => {
-                self.movement_sensor_do_command(payload)
-            }
             "/viam.component.movementsensor.v1.MovementSensorService/GetReadings" => {
                 self.movement_sensor_get_readings(payload)
             }
@@ -331,9 +643,6 @@ impl<'a> GrpcServerInner<'a> {
             "/viam.component.encoder.v1.EncoderService/GetProperties" => {
                 self.encoder_get_properties(payload)
             }
-            "/viam.component.encoder.v1.EncoderService/DoCommand" => {
-                self.encoder_do_command(payload)
-            }
             "/viam.component.powersensor.v1.PowerSensorService/GetVoltage" => {
                 self.power_sensor_get_voltage(payload)
             }
@@ -346,14 +655,10 @@ impl<'a> GrpcServerInner<'a> {
             "/viam.component.powersensor.v1.PowerSensorService/GetReadings" => {
                 self.power_sensor_get_readings(payload)
             }
-            "/viam.component.powersensor.v1.PowerSensorService/DoCommand" => {
-                self.power_sensor_do_command(payload)
-            }
             "/viam.component.servo.v1.ServoService/Move" => self.servo_move(payload),
             "/viam.component.servo.v1.ServoService/GetPosition" => self.servo_get_position(payload),
             "/viam.component.servo.v1.ServoService/IsMoving" => self.servo_is_moving(payload),
             "/viam.component.servo.v1.ServoService/Stop" => self.servo_stop(payload),
-            "/viam.component.servo.v1.ServoService/DoCommand" => self.servo_do_command(payload),
             _ => Err(ServerError::from(GrpcError::RpcUnimplemented)),
         }
     }
@@ -368,11 +673,9 @@ impl<'a> GrpcServerInner<'a> {
         let pos = motor
             .lock()
             .unwrap()
-            .get_position()
+            .get_position_revolutions()
             .map_err(|err| ServerError::new(GrpcError::RpcInternal, Some(err.into())))?;
-        let resp = component::motor::v1::GetPositionResponse {
-            position: pos as f64,
-        };
+        let resp = component::motor::v1::GetPositionResponse { position: pos };
         GrpcServerInner::encode_message(resp)
     }
 
@@ -388,31 +691,46 @@ impl<'a> GrpcServerInner<'a> {
         GrpcServerInner::encode_message(props)
     }
 
-    fn motor_go_for(&mut self, _message: &[u8]) -> Result<Bytes, ServerError> {
-        // TODO: internal go_for can't wait without blocking executor, must be waited from here.
-        // requires refactoring this function (and its callers) to be async
-        /*
+    // answered once the revolutions should have been made, waiting on the executor
+    fn motor_go_for(&mut self, message: &[u8]) -> Result<ResponseFuture, ServerError> {
         let req = component::motor::v1::GoForRequest::decode(message)
             .map_err(|_| ServerError::from(GrpcError::RpcInvalidArgument))?;
         let motor = match self.robot.lock().unwrap().get_motor_by_name(req.name) {
             Some(m) => m,
             None => return Err(ServerError::from(GrpcError::RpcUnavailable)),
         };
-        let mut motor = motor.lock().unwrap();
-
-        if let Some(dur) =  motor.go_for(req.rpm, req.revolutions).map_err(|err| ServerError::new(GrpcError::RpcInternal, Some(err)))? {
-            // async wait for duration
-        }
-        motor.lock().unwrap();
-
-        let resp = component::motor::v1::GoForResponse {};
-        GrpcServerInner::encode_message(resp)
-        */
-        Err(ServerError::from(GrpcError::RpcUnimplemented))
+        let duration = motor
+            .lock()
+            .unwrap()
+            .go_for(req.rpm, req.revolutions)
+            .map_err(|err| ServerError::new(GrpcError::RpcInternal, Some(err.into())))?;
+        Ok(Box::pin(async move {
+            if let Some(duration) = duration {
+                async_io::Timer::after(duration).await;
+            }
+            GrpcServerInner::encode_message(component::motor::v1::GoForResponse {})
+        }))
     }
 
-    fn motor_go_to(&mut self, _message: &[u8]) -> Result<Bytes, ServerError> {
-        Err(ServerError::from(GrpcError::RpcUnimplemented))
+    // answered once the motor should have reached the position, waiting on the executor
+    fn motor_go_to(&mut self, message: &[u8]) -> Result<ResponseFuture, ServerError> {
+        let req = component::motor::v1::GoToRequest::decode(message)
+            .map_err(|_| ServerError::from(GrpcError::RpcInvalidArgument))?;
+        let motor = match self.robot.lock().unwrap().get_motor_by_name(req.name) {
+            Some(m) => m,
+            None => return Err(ServerError::from(GrpcError::RpcUnavailable)),
+        };
+        let duration = motor
+            .lock()
+            .unwrap()
+            .go_to(req.rpm, req.position_revolutions)
+            .map_err(|err| ServerError::new(GrpcError::RpcInternal, Some(err.into())))?;
+        Ok(Box::pin(async move {
+            if let Some(duration) = duration {
+                async_io::Timer::after(duration).await;
+            }
+            GrpcServerInner::encode_message(component::motor::v1::GoToResponse {})
+        }))
     }
 
     fn motor_is_powered(&mut self, _message: &[u8]) -> Result<Bytes, ServerError> {
@@ -440,28 +758,34 @@ impl<'a> GrpcServerInner<'a> {
         Err(ServerError::from(GrpcError::RpcUnimplemented))
     }
 
-    fn motor_do_command(&mut self, message: &[u8]) -> Result<Bytes, ServerError> {
+    fn motor_do_command(&mut self, message: &[u8]) -> Result<ResponseFuture, ServerError> {
         let req = proto::common::v1::DoCommandRequest::decode(message)
             .map_err(|_| ServerError::from(GrpcError::RpcInvalidArgument))?;
         let motor = match self.robot.lock().unwrap().get_motor_by_name(req.name) {
             Some(m) => m,
             None => return Err(ServerError::from(GrpcError::RpcUnavailable)),
         };
-        let res = motor
-            .lock()
-            .unwrap()
-            .do_command(req.command)
-            .map_err(|_| ServerError::from(GrpcError::RpcInvalidArgument))?;
-        let resp = proto::common::v1::DoCommandResponse { result: res };
-        GrpcServerInner::encode_message(resp)
+        let command = motor.lock().unwrap().do_command_async(req.command);
+        Ok(GrpcServerInner::do_command_response(command, |_| {
+            ServerError::from(GrpcError::RpcInvalidArgument)
+        }))
     }
 
     fn auth_service_authentificate(&mut self, message: &[u8]) -> Result<Bytes, ServerError> {
-        let _req = proto::rpc::v1::AuthenticateRequest::decode(message)
-            .map_err(|_| ServerError::from(GrpcError::RpcInvalidArgument))?;
-        let resp = proto::rpc::v1::AuthenticateResponse {
-            access_token: "esp32".to_string(),
+        let req = proto::rpc::v1::AuthenticateRequest::decode(message)
+            .map_err(|_| ServerError::from(GrpcError::RpcInvalidArgument))?;
+        let access_token = match self.auth {
+            Some(auth) => {
+                let payload = req
+                    .credentials
+                    .as_ref()
+                    .map_or("", |creds| creds.payload.as_str());
+                auth.issue_token(payload)
+                    .ok_or(ServerError::from(GrpcError::RpcUnauthenticated))?
+            }
+            None => "esp32".to_string(),
         };
+        let resp = proto::rpc::v1::AuthenticateResponse { access_token };
         GrpcServerInner::encode_message(resp)
     }
 
@@ -576,20 +900,17 @@ impl<'a> GrpcServerInner<'a> {
         GrpcServerInner::encode_message(resp)
     }
 
-    fn servo_do_command(&mut self, message: &[u8]) -> Result<Bytes, ServerError> {
+    fn servo_do_command(&mut self, message: &[u8]) -> Result<ResponseFuture, ServerError> {
         let req = proto::common::v1::DoCommandRequest::decode(message)
             .map_err(|_| ServerError::from(GrpcError::RpcInvalidArgument))?;
         let servo = match self.robot.lock().unwrap().get_servo_by_name(req.name) {
             Some(m) => m,
             None => return Err(ServerError::from(GrpcError::RpcUnavailable)),
         };
-        let res = servo
-            .lock()
-            .unwrap()
-            .do_command(req.command)
-            .map_err(|_| ServerError::from(GrpcError::RpcInvalidArgument))?;
-        let resp = proto::common::v1::DoCommandResponse { result: res };
-        GrpcServerInner::encode_message(resp)
+        let command = servo.lock().unwrap().do_command_async(req.command);
+        Ok(GrpcServerInner::do_command_response(command, |_| {
+            ServerError::from(GrpcError::RpcInvalidArgument)
+        }))
     }
 
     fn board_get_digital_interrupt_value(&mut self, message: &[u8]) -> Result<Bytes, ServerError> {
@@ -727,6 +1048,25 @@ impl<'a> GrpcServerInner<'a> {
         GrpcServerInner::encode_message(resp)
     }
 
+    fn board_write_analog(&mut self, message: &[u8]) -> Result<Bytes, ServerError> {
+        let req = component::board::v1::WriteAnalogRequest::decode(message)
+            .map_err(|_| ServerError::from(GrpcError::RpcInvalidArgument))?;
+        let mut board = match self.robot.lock().unwrap().get_board_by_name(req.name) {
+            Some(b) => b,
+            None => return Err(ServerError::from(GrpcError::RpcUnavailable)),
+        };
+        // the pin of the request names the analog writer
+        let value: u16 = req
+            .value
+            .try_into()
+            .map_err(|_| ServerError::from(GrpcError::RpcInvalidArgument))?;
+        board
+            .write_analog(&req.pin, value)
+            .map_err(|err| ServerError::new(GrpcError::RpcInternal, Some(err.into())))?;
+        let resp = component::board::v1::WriteAnalogResponse {};
+        GrpcServerInner::encode_message(resp)
+    }
+
     fn board_set_power_mode(&mut self, message: &[u8]) -> Result<Bytes, ServerError> {
         let req = component::board::v1::SetPowerModeRequest::decode(message)
             .map_err(|_| ServerError::from(GrpcError::RpcInvalidArgument))?;
@@ -780,44 +1120,70 @@ impl<'a> GrpcServerInner<'a> {
         GrpcServerInner::encode_message(resp)
     }
 
-    fn board_do_command(&mut self, message: &[u8]) -> Result<Bytes, ServerError> {
+    fn board_do_command(&mut self, message: &[u8]) -> Result<ResponseFuture, ServerError> {
         let req = proto::common::v1::DoCommandRequest::decode(message)
             .map_err(|_| ServerError::from(GrpcError::RpcInvalidArgument))?;
         let board = match self.robot.lock().unwrap().get_board_by_name(req.name) {
             Some(m) => m,
             None => return Err(ServerError::from(GrpcError::RpcUnavailable)),
         };
-        let res = board
-            .lock()
-            .unwrap()
-            .do_command(req.command)
-            .map_err(|_| ServerError::from(GrpcError::RpcInvalidArgument))?;
-        let resp = proto::common::v1::DoCommandResponse { result: res };
-        GrpcServerInner::encode_message(resp)
+        let command = board.lock().unwrap().do_command_async(req.command);
+        Ok(GrpcServerInner::do_command_response(command, |_| {
+            ServerError::from(GrpcError::RpcInvalidArgument)
+        }))
     }
 
-    fn generic_component_do_command(&mut self, message: &[u8]) -> Result<Bytes, ServerError> {
+    fn generic_component_do_command(
+        &mut self,
+        message: &[u8],
+    ) -> Result<ResponseFuture, ServerError> {
         let req = proto::common::v1::DoCommandRequest::decode(message)
             .map_err(|_| ServerError::from(GrpcError::RpcInvalidArgument))?;
         let component = match self
             .robot
             .lock()
             .unwrap()
-            .get_generic_component_by_name(req.name)
+            .get_generic_component_by_name(req.name.clone())
         {
             Some(c) => c,
+            // robot-wide commands are sent to a dedicated resource name
+            None if req.name == ROBOT_COMMAND_RESOURCE_NAME => {
+                let command = req.command.unwrap_or_default();
+                let res = self
+                    .robot
+                    .lock()
+                    .unwrap()
+                    .do_command(&command)
+                    .map_err(|err| {
+                        let status = match err {
+                            RobotError::RobotUnknownCommand(_) => GrpcError::RpcInvalidArgument,
+                            _ => GrpcError::RpcInternal,
+                        };
+                        ServerError::new(status, Some(err.into()))
+                    })?;
+                let resp = proto::common::v1::DoCommandResponse { result: Some(res) };
+                return Ok(Box::pin(std::future::ready(
+                    GrpcServerInner::encode_message(resp),
+                )));
+            }
             None => return Err(ServerError::from(GrpcError::RpcUnavailable)),
         };
-        let res = component
-            .lock()
-            .unwrap()
-            .do_command(req.command)
-            .map_err(|err| ServerError::new(GrpcError::RpcInternal, Some(err.into())))?;
-        let resp = proto::common::v1::DoCommandResponse { result: res };
-        GrpcServerInner::encode_message(resp)
+        let command = component.lock().unwrap().do_command_async(req.command);
+        Ok(GrpcServerInner::do_command_response(command, |err| {
+            ServerError::new(GrpcError::RpcInternal, Some(err.into()))
+        }))
     }
 
     fn sensor_get_readings(&mut self, message: &[u8]) -> Result<Bytes, ServerError> {
+        let readings = self.sensor_readings(message)?;
+        let resp = proto::common::v1::GetReadingsResponse { readings };
+        GrpcServerInner::encode_message(resp)
+    }
+
+    fn sensor_readings(
+        &mut self,
+        message: &[u8],
+    ) -> Result<crate::common::sensor::GenericReadingsResult, ServerError> {
         let req = proto::common::v1::GetReadingsRequest::decode(message)
             .map_err(|_| ServerError::from(GrpcError::RpcInvalidArgument))?;
         let sensor = match self.robot.lock().unwrap().get_sensor_by_name(req.name) {
@@ -825,29 +1191,26 @@ impl<'a> GrpcServerInner<'a> {
             None => return Err(ServerError::from(GrpcError::RpcUnavailable)),
         };
 
-        let readings = sensor
-            .lock()
-            .unwrap()
-            .get_generic_readings()
-            .map_err(|err| ServerError::new(GrpcError::RpcInternal, Some(err.into())))?;
-        let resp = proto::common::v1::GetReadingsResponse { readings };
-        GrpcServerInner::encode_message(resp)
+        let readings = if includes_capture_time(&req.extra) {
+            sensor.lock().unwrap().get_timestamped_readings()
+        } else {
+            sensor.lock().unwrap().get_generic_readings()
+        }
+        .map_err(|err| ServerError::new(GrpcError::RpcInternal, Some(err.into())))?;
+        Ok(readings)
     }
 
-    fn sensor_do_command(&mut self, message: &[u8]) -> Result<Bytes, ServerError> {
+    fn sensor_do_command(&mut self, message: &[u8]) -> Result<ResponseFuture, ServerError> {
         let req = proto::common::v1::DoCommandRequest::decode(message)
             .map_err(|_| ServerError::from(GrpcError::RpcInvalidArgument))?;
         let sensor = match self.robot.lock().unwrap().get_sensor_by_name(req.name) {
             Some(m) => m,
             None => return Err(ServerError::from(GrpcError::RpcUnavailable)),
         };
-        let res = sensor
-            .lock()
-            .unwrap()
-            .do_command(req.command)
-            .map_err(|_| ServerError::from(GrpcError::RpcInvalidArgument))?;
-        let resp = proto::common::v1::DoCommandResponse { result: res };
-        GrpcServerInner::encode_message(resp)
+        let command = sensor.lock().unwrap().do_command_async(req.command);
+        Ok(GrpcServerInner::do_command_response(command, |_| {
+            ServerError::from(GrpcError::RpcInvalidArgument)
+        }))
     }
 
     fn movement_sensor_get_position(&mut self, message: &[u8]) -> Result<Bytes, ServerError> {
@@ -1002,6 +1365,20 @@ impl<'a> GrpcServerInner<'a> {
     }
 
     fn movement_sensor_get_readings(&mut self, message: &[u8]) -> Result<Bytes, ServerError> {
+        let readings = self
+            .movement_sensor_readings(message)?
+            .into_iter()
+            .collect();
+        let resp = proto::common::v1::GetReadingsResponse { readings };
+        GrpcServerInner::encode_message(resp)
+    }
+
+    // ordered as MOVEMENT_SENSOR_READINGS_ORDER so the streamed response keeps that order, the
+    // capture time comes last when requested
+    fn movement_sensor_readings(
+        &mut self,
+        message: &[u8],
+    ) -> Result<Vec<(String, crate::google::protobuf::Value)>, ServerError> {
         let req = proto::common::v1::GetReadingsRequest::decode(message)
             .map_err(|_| ServerError::from(GrpcError::RpcInvalidArgument))?;
         let m_sensor = match self
@@ -1014,16 +1391,25 @@ impl<'a> GrpcServerInner<'a> {
             None => return Err(ServerError::from(GrpcError::RpcUnavailable)),
         };
 
-        let readings = m_sensor
+        let mut readings = m_sensor
             .lock()
             .unwrap()
-            .get_generic_readings()
+            .get_ordered_readings()
             .map_err(|err| ServerError::new(GrpcError::RpcInternal, Some(err.into())))?;
-        let resp = proto::common::v1::GetReadingsResponse { readings };
-        GrpcServerInner::encode_message(resp)
+        if includes_capture_time(&req.extra) {
+            let mut capture_time = crate::common::sensor::GenericReadingsResult::new();
+            crate::common::sensor::add_capture_time(&mut capture_time);
+            let mut capture_time: Vec<_> = capture_time.into_iter().collect();
+            capture_time.sort_by(|(a, _), (b, _)| a.cmp(b));
+            readings.extend(capture_time);
+        }
+        Ok(readings)
     }
 
-    fn movement_sensor_do_command(&mut self, message: &[u8]) -> Result<Bytes, ServerError> {
+    fn movement_sensor_do_command(
+        &mut self,
+        message: &[u8],
+    ) -> Result<ResponseFuture, ServerError> {
         let req = proto::common::v1::DoCommandRequest::decode(message)
             .map_err(|_| ServerError::from(GrpcError::RpcInvalidArgument))?;
         let movement_sensor = match self
@@ -1035,21 +1421,45 @@ impl<'a> GrpcServerInner<'a> {
             Some(m) => m,
             None => return Err(ServerError::from(GrpcError::RpcUnavailable)),
         };
-        let res = movement_sensor
+        let command = movement_sensor
             .lock()
             .unwrap()
-            .do_command(req.command)
-            .map_err(|_| ServerError::from(GrpcError::RpcInvalidArgument))?;
-        let resp = proto::common::v1::DoCommandResponse { result: res };
-        GrpcServerInner::encode_message(resp)
+            .do_command_async(req.command);
+        Ok(GrpcServerInner::do_command_response(command, |_| {
+            ServerError::from(GrpcError::RpcInvalidArgument)
+        }))
     }
 
-    fn base_move_straight(&mut self, _message: &[u8]) -> Result<Bytes, ServerError> {
-        Err(ServerError::from(GrpcError::RpcUnimplemented))
+    // the move isn't waited for, clients poll IsMoving to know when it's done
+    fn base_move_straight(&mut self, message: &[u8]) -> Result<Bytes, ServerError> {
+        let req = component::base::v1::MoveStraightRequest::decode(message)
+            .map_err(|_| ServerError::from(GrpcError::RpcInvalidArgument))?;
+        let base = match self.robot.lock().unwrap().get_base_by_name(req.name) {
+            Some(b) => b,
+            None => return Err(ServerError::from(GrpcError::RpcUnavailable)),
+        };
+        base.lock()
+            .unwrap()
+            .move_straight(req.distance_mm as f64, req.mm_per_sec)
+            .map_err(base_error_to_server_error)?;
+        let resp = component::base::v1::MoveStraightResponse {};
+        GrpcServerInner::encode_message(resp)
     }
 
-    fn base_spin(&mut self, _message: &[u8]) -> Result<Bytes, ServerError> {
-        Err(ServerError::from(GrpcError::RpcUnimplemented))
+    // the spin isn't waited for, clients poll IsMoving to know when it's done
+    fn base_spin(&mut self, message: &[u8]) -> Result<Bytes, ServerError> {
+        let req = component::base::v1::SpinRequest::decode(message)
+            .map_err(|_| ServerError::from(GrpcError::RpcInvalidArgument))?;
+        let base = match self.robot.lock().unwrap().get_base_by_name(req.name) {
+            Some(b) => b,
+            None => return Err(ServerError::from(GrpcError::RpcUnavailable)),
+        };
+        base.lock()
+            .unwrap()
+            .spin(req.angle_deg, req.degs_per_sec)
+            .map_err(base_error_to_server_error)?;
+        let resp = component::base::v1::SpinResponse {};
+        GrpcServerInner::encode_message(resp)
     }
 
     fn base_set_velocity(&mut self, _: &[u8]) -> Result<Bytes, ServerError> {
@@ -1073,6 +1483,22 @@ impl<'a> GrpcServerInner<'a> {
         GrpcServerInner::encode_message(resp)
     }
 
+    fn base_get_geometries(&mut self, message: &[u8]) -> Result<Bytes, ServerError> {
+        let req = proto::common::v1::GetGeometriesRequest::decode(message)
+            .map_err(|_| ServerError::from(GrpcError::RpcInvalidArgument))?;
+        let base = match self.robot.lock().unwrap().get_base_by_name(req.name) {
+            Some(b) => b,
+            None => return Err(ServerError::from(GrpcError::RpcUnavailable)),
+        };
+        let geometries = base
+            .lock()
+            .unwrap()
+            .get_geometries()
+            .map_err(|err| ServerError::new(GrpcError::RpcInternal, Some(err.into())))?;
+        let resp = proto::common::v1::GetGeometriesResponse { geometries };
+        GrpcServerInner::encode_message(resp)
+    }
+
     fn base_set_power(&mut self, message: &[u8]) -> Result<Bytes, ServerError> {
         let req = component::base::v1::SetPowerRequest::decode(message)
             .map_err(|_| ServerError::from(GrpcError::RpcInvalidArgument))?;
@@ -1153,20 +1579,17 @@ impl<'a> GrpcServerInner<'a> {
         GrpcServerInner::encode_message(resp)
     }
 
-    fn encoder_do_command(&mut self, message: &[u8]) -> Result<Bytes, ServerError> {
+    fn encoder_do_command(&mut self, message: &[u8]) -> Result<ResponseFuture, ServerError> {
         let req = proto::common::v1::DoCommandRequest::decode(message)
             .map_err(|_| ServerError::from(GrpcError::RpcInvalidArgument))?;
         let encoder = match self.robot.lock().unwrap().get_encoder_by_name(req.name) {
             Some(m) => m,
             None => return Err(ServerError::from(GrpcError::RpcUnavailable)),
         };
-        let res = encoder
-            .lock()
-            .unwrap()
-            .do_command(req.command)
-            .map_err(|_| ServerError::from(GrpcError::RpcInvalidArgument))?;
-        let resp = proto::common::v1::DoCommandResponse { result: res };
-        GrpcServerInner::encode_message(resp)
+        let command = encoder.lock().unwrap().do_command_async(req.command);
+        Ok(GrpcServerInner::do_command_response(command, |_| {
+            ServerError::from(GrpcError::RpcInvalidArgument)
+        }))
     }
 
     fn power_sensor_get_voltage(&mut self, message: &[u8]) -> Result<Bytes, ServerError> {
@@ -1234,6 +1657,15 @@ impl<'a> GrpcServerInner<'a> {
     }
 
     fn power_sensor_get_readings(&mut self, message: &[u8]) -> Result<Bytes, ServerError> {
+        let readings = self.power_sensor_readings(message)?;
+        let resp = proto::common::v1::GetReadingsResponse { readings };
+        GrpcServerInner::encode_message(resp)
+    }
+
+    fn power_sensor_readings(
+        &mut self,
+        message: &[u8],
+    ) -> Result<crate::common::sensor::GenericReadingsResult, ServerError> {
         let req = proto::common::v1::GetReadingsRequest::decode(message)
             .map_err(|_| ServerError::from(GrpcError::RpcInvalidArgument))?;
         let power_sensor = match self
@@ -1246,16 +1678,16 @@ impl<'a> GrpcServerInner<'a> {
             None => return Err(ServerError::from(GrpcError::RpcUnavailable)),
         };
 
-        let readings = power_sensor
-            .lock()
-            .unwrap()
-            .get_generic_readings()
-            .map_err(|err| ServerError::new(GrpcError::RpcInternal, Some(err.into())))?;
-        let resp = proto::common::v1::GetReadingsResponse { readings };
-        GrpcServerInner::encode_message(resp)
+        let readings = if includes_capture_time(&req.extra) {
+            power_sensor.lock().unwrap().get_timestamped_readings()
+        } else {
+            power_sensor.lock().unwrap().get_generic_readings()
+        }
+        .map_err(|err| ServerError::new(GrpcError::RpcInternal, Some(err.into())))?;
+        Ok(readings)
     }
 
-    fn power_sensor_do_command(&mut self, message: &[u8]) -> Result<Bytes, ServerError> {
+    fn power_sensor_do_command(&mut self, message: &[u8]) -> Result<ResponseFuture, ServerError> {
         let req = proto::common::v1::DoCommandRequest::decode(message)
             .map_err(|_| ServerError::from(GrpcError::RpcInvalidArgument))?;
         let power_sensor = match self
@@ -1267,13 +1699,10 @@ impl<'a> GrpcServerInner<'a> {
             Some(m) => m,
             None => return Err(ServerError::from(GrpcError::RpcUnavailable)),
         };
-        let res = power_sensor
-            .lock()
-            .unwrap()
-            .do_command(req.command)
-            .map_err(|_| ServerError::from(GrpcError::RpcInvalidArgument))?;
-        let resp = proto::common::v1::DoCommandResponse { result: res };
-        GrpcServerInner::encode_message(resp)
+        let command = power_sensor.lock().unwrap().do_command_async(req.command);
+        Ok(GrpcServerInner::do_command_response(command, |_| {
+            ServerError::from(GrpcError::RpcInvalidArgument)
+        }))
     }
 
     fn robot_status_stream(
@@ -1356,14 +1785,16 @@ impl<'a> GrpcServerInner<'a> {
             .get_camera_by_name(req.name)
             .ok_or(GrpcError::RpcUnavailable)?;
 
+        let mime_type = crate::common::camera::MimeType::from_request(&req.mime_type);
+        let mut camera = camera.lock().unwrap();
         let image = camera
-            .lock()
-            .unwrap()
-            .get_image()
+            .get_image(mime_type)
             .map_err(|err| ServerError::new(GrpcError::RpcInternal, Some(err.into())))?;
 
         let resp = component::camera::v1::GetImageResponse {
-            mime_type: "image/jpeg".to_string(),
+            mime_type: mime_type
+                .map_or(camera.image_mime_type(), |mime_type| mime_type.as_str())
+                .to_string(),
             image,
         };
         GrpcServerInner::encode_message(resp)
@@ -1381,14 +1812,16 @@ impl<'a> GrpcServerInner<'a> {
             .get_camera_by_name(req.name)
             .ok_or(GrpcError::RpcUnavailable)?;
 
+        let mime_type = crate::common::camera::MimeType::from_request(&req.mime_type);
+        let mut camera = camera.lock().unwrap();
         let image = camera
-            .lock()
-            .unwrap()
-            .get_image()
+            .get_image(mime_type)
             .map_err(|err| ServerError::new(GrpcError::RpcInternal, Some(err.into())))?;
 
         let msg = crate::google::api::HttpBody {
-            content_type: "image/jpeg".to_string(),
+            content_type: mime_type
+                .map_or(camera.image_mime_type(), |mime_type| mime_type.as_str())
+                .to_string(),
             data: image.to_vec(),
             ..Default::default()
         };
@@ -1396,20 +1829,42 @@ impl<'a> GrpcServerInner<'a> {
     }
 
     #[cfg(feature = "camera")]
-    fn camera_do_command(&mut self, message: &[u8]) -> Result<Bytes, ServerError> {
+    fn camera_get_point_cloud(&mut self, message: &[u8]) -> Result<Bytes, ServerError> {
+        let req = component::camera::v1::GetPointCloudRequest::decode(message)
+            .map_err(|_| ServerError::from(GrpcError::RpcInvalidArgument))?;
+
+        let camera = self
+            .robot
+            .lock()
+            .unwrap()
+            .get_camera_by_name(req.name)
+            .ok_or(GrpcError::RpcUnavailable)?;
+
+        let point_cloud = camera
+            .lock()
+            .unwrap()
+            .get_point_cloud()
+            .map_err(|err| ServerError::new(GrpcError::RpcInternal, Some(err.into())))?;
+
+        let resp = component::camera::v1::GetPointCloudResponse {
+            mime_type: crate::common::camera::MIME_TYPE_PCD.to_string(),
+            point_cloud,
+        };
+        GrpcServerInner::encode_message(resp)
+    }
+
+    #[cfg(feature = "camera")]
+    fn camera_do_command(&mut self, message: &[u8]) -> Result<ResponseFuture, ServerError> {
         let req = proto::common::v1::DoCommandRequest::decode(message)
             .map_err(|_| ServerError::from(GrpcError::RpcInvalidArgument))?;
         let camera = match self.robot.lock().unwrap().get_camera_by_name(req.name) {
             Some(m) => m,
             None => return Err(ServerError::from(GrpcError::RpcUnavailable)),
         };
-        let res = camera
-            .lock()
-            .unwrap()
-            .do_command(req.command)
-            .map_err(|_| ServerError::from(GrpcError::RpcInvalidArgument))?;
-        let resp = proto::common::v1::DoCommandResponse { result: res };
-        GrpcServerInner::encode_message(resp)
+        let command = camera.lock().unwrap().do_command_async(req.command);
+        Ok(GrpcServerInner::do_command_response(command, |_| {
+            ServerError::from(GrpcError::RpcInvalidArgument)
+        }))
     }
 
     fn get_version(&mut self) -> Result<Bytes, ServerError> {
@@ -1422,14 +1877,17 @@ impl<'a> GrpcServerInner<'a> {
     }
 
     fn resource_names(&mut self, _unused_message: &[u8]) -> Result<Bytes, ServerError> {
-        let rr = self
-            .robot
+        let rr = self.get_resource_names()?;
+        let rr = robot::v1::ResourceNamesResponse { resources: rr };
+        GrpcServerInner::encode_message(rr)
+    }
+
+    fn get_resource_names(&mut self) -> Result<Vec<proto::common::v1::ResourceName>, ServerError> {
+        self.robot
             .lock()
             .unwrap()
             .get_resource_names()
-            .map_err(|err| ServerError::new(GrpcError::RpcInternal, Some(err.into())))?;
-        let rr = robot::v1::ResourceNamesResponse { resources: rr };
-        GrpcServerInner::encode_message(rr)
+            .map_err(|err| ServerError::new(GrpcError::RpcInternal, Some(err.into())))
     }
 
     fn signaling_service_optional_webrtc_config(
@@ -1508,14 +1966,29 @@ impl<R> WebRtcGrpcService for GrpcServer<R>
 where
     R: GrpcResponse + 'static,
 {
+    fn is_authorized(&self, method: &str, authorization: Option<&str>) -> bool {
+        self.check_authorization(method, authorization)
+    }
     fn unary_rpc(&mut self, method: &str, data: &Bytes) -> Result<Bytes, ServerError> {
         let grpc = GrpcServerInner {
             robot: &self.robot,
             signaling_server: &self.signaling_server,
+            auth: &self.auth,
         };
         grpc.handle_unary_request(method, data)
             .map(|mut b| b.split_off(5))
     }
+    fn async_unary_rpc(&mut self, method: &str, data: &Bytes) -> Option<ResponseFuture> {
+        let mut grpc = GrpcServerInner {
+            robot: &self.robot,
+            signaling_server: &self.signaling_server,
+            auth: &self.auth,
+        };
+        let response = grpc.handle_do_command_request(method, data)?;
+        Some(Box::pin(async move {
+            response.await.map(|mut b| b.split_off(5))
+        }))
+    }
     fn server_stream_rpc(
         &mut self,
         method: &str,
@@ -1525,6 +1998,7 @@ where
         let mut grpc = GrpcServerInner {
             robot: &self.robot,
             signaling_server: &self.signaling_server,
+            auth: &self.auth,
         };
         grpc.handle_rpc_stream(method, data)
             .map(|mut dur| (dur.0.split_off(5), dur.1))
@@ -1547,6 +2021,12 @@ where
         log::debug!("processing {:?}", req);
         Box::pin(async move {
             let (path, body) = req.into_parts();
+            let authorized = svc.check_authorization(
+                path.uri.path(),
+                path.headers
+                    .get(hyper::header::AUTHORIZATION)
+                    .and_then(|value| value.to_str().ok()),
+            );
             let msg = body
                 .collect()
                 .await
@@ -1561,6 +2041,7 @@ where
             let grpc = GrpcServerInner {
                 robot: &svc.robot,
                 signaling_server: &svc.signaling_server,
+                auth: &svc.auth,
             };
 
             type Stream = dyn futures_lite::Stream<Item = Result<Bytes, ServerError>> + Send + Sync;
@@ -1574,6 +2055,9 @@ where
             let state = UnfoldState {
                 trailers,
                 stream: Some(match grpc.validate_rpc(&msg).map_err(ServerError::from) {
+                    Ok(_) if !authorized => Box::pin(futures_lite::stream::once(Err(
+                        ServerError::from(GrpcError::RpcUnauthenticated),
+                    ))),
                     Ok(payload) => grpc.handle_request(path, payload),
                     Err(e) => Box::pin(futures_lite::stream::once(Err(e))),
                 }),
@@ -1712,3 +2196,463 @@ impl fmt::Display for ServerError {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::google::protobuf::{value::Kind, Value};
+    use futures_lite::future::block_on;
+    use std::collections::HashMap;
+
+    fn authenticate(grpc: &mut GrpcServerInner, payload: &str) -> Result<String, ServerError> {
+        let req = proto::rpc::v1::AuthenticateRequest {
+            entity: "".to_owned(),
+            credentials: Some(proto::rpc::v1::Credentials {
+                r#type: "api-key".to_owned(),
+                payload: payload.to_owned(),
+            }),
+        };
+        grpc.auth_service_authentificate(&req.encode_to_vec())
+            .map(|resp| {
+                proto::rpc::v1::AuthenticateResponse::decode(resp.slice(5..))
+                    .unwrap()
+                    .access_token
+            })
+    }
+
+    #[test_log::test]
+    fn test_api_key_auth() {
+        let robot = Arc::new(Mutex::new(LocalRobot::default()));
+        let mut server = GrpcServer::new(robot.clone(), GrpcBody::new());
+        // no api key configured, every request goes through
+        assert!(server.check_authorization("/viam.robot.v1.RobotService/GetStatus", None));
+
+        server.require_api_key(Arc::from("secret"));
+        let path = "/viam.robot.v1.RobotService/GetStatus";
+        assert!(server.check_authorization(AUTHENTICATE_PATH, None));
+        assert!(!server.check_authorization(path, None));
+        // the raw api key isn't a session token
+        assert!(!server.check_authorization(path, Some("Bearer secret")));
+
+        let mut grpc = GrpcServerInner {
+            robot: &robot,
+            signaling_server: &None,
+            auth: &server.auth,
+        };
+        assert!(authenticate(&mut grpc, "not the secret").is_err());
+        let token = authenticate(&mut grpc, "secret").unwrap();
+        assert_ne!(token, "secret");
+        assert!(server.check_authorization(path, Some(&format!("Bearer {}", token))));
+        assert!(!server.check_authorization(path, Some(&token)));
+        assert!(!server.check_authorization(path, Some("Bearer forged")));
+
+        // WebRTC calls go through the same check
+        assert!(WebRtcGrpcService::is_authorized(
+            &server,
+            path,
+            Some(&format!("Bearer {}", token))
+        ));
+        assert!(!WebRtcGrpcService::is_authorized(&server, path, None));
+
+        // only the most recent tokens stay valid
+        for _ in 0..MAX_SESSION_TOKENS {
+            let _ = authenticate(&mut grpc, "secret").unwrap();
+        }
+        assert!(!server.check_authorization(path, Some(&format!("Bearer {}", token))));
+    }
+
+    fn collect(stream: ResponseStream) -> (usize, Vec<u8>) {
+        let chunks: Vec<Bytes> = block_on(stream.try_collect::<_, _, Vec<_>>()).unwrap();
+        (chunks.len(), chunks.concat())
+    }
+
+    #[cfg(feature = "builtin-components")]
+    #[test_log::test]
+    fn test_call_metrics_per_resource() {
+        use crate::common::config::DynamicComponentConfig;
+        let mut robot = LocalRobot::default();
+        robot
+            .process_components(
+                vec![Some(DynamicComponentConfig {
+                    name: "metered".to_owned(),
+                    namespace: "rdk".to_owned(),
+                    r#type: "movement_sensor".to_owned(),
+                    model: "rdk:builtin:fake".to_owned(),
+                    ..Default::default()
+                })],
+                &mut Box::default(),
+            )
+            .unwrap();
+        let robot = Arc::new(Mutex::new(robot));
+        let grpc = || GrpcServerInner {
+            robot: &robot,
+            signaling_server: &None,
+            auth: &None,
+        };
+        let request = |name: &str| {
+            component::movement_sensor::v1::GetPositionRequest {
+                name: name.to_owned(),
+                extra: None,
+            }
+            .encode_to_vec()
+        };
+        let path = "/viam.component.movementsensor.v1.MovementSensorService/GetPosition";
+        assert!(grpc()
+            .handle_unary_request(path, &request("metered"))
+            .is_ok());
+        assert!(grpc()
+            .handle_unary_request(path, &request("made_up_name"))
+            .is_err());
+        assert!(grpc()
+            .handle_unary_request("/viam.robot.v1.RobotService/ResourceNames", &[])
+            .is_ok());
+        assert!(grpc()
+            .handle_unary_request("/made.up.Service/Method", &[])
+            .is_err());
+        let rendered = crate::common::metrics::render_prometheus();
+        assert!(rendered.contains(
+            "micro_rdk_grpc_call_errors_total{resource=\"rdk:component:movement_sensor/metered\"} 0"
+        ));
+        assert!(rendered.contains("resource=\"robot\""));
+        assert!(!rendered.contains("made_up_name"));
+        assert!(!rendered.contains("made.up"));
+    }
+
+    #[cfg(feature = "builtin-components")]
+    #[test_log::test]
+    fn test_motor_go_to() {
+        use crate::common::{
+            actuator::Actuator,
+            config::{DynamicComponentConfig, Kind},
+            exec::Executor,
+        };
+        let mut robot = LocalRobot::default();
+        robot
+            .process_components(
+                vec![
+                    Some(DynamicComponentConfig {
+                        name: "board".to_owned(),
+                        namespace: "rdk".to_owned(),
+                        r#type: "board".to_owned(),
+                        model: "rdk:builtin:fake".to_owned(),
+                        ..Default::default()
+                    }),
+                    Some(DynamicComponentConfig {
+                        name: "stepper".to_owned(),
+                        namespace: "rdk".to_owned(),
+                        r#type: "motor".to_owned(),
+                        model: "rdk:builtin:stepper".to_owned(),
+                        attributes: Some(HashMap::from([
+                            ("board".to_owned(), Kind::StringValue("board".to_owned())),
+                            (
+                                "pins".to_owned(),
+                                Kind::StructValue(HashMap::from([
+                                    ("step".to_owned(), Kind::NumberValue(12.0)),
+                                    ("dir".to_owned(), Kind::NumberValue(13.0)),
+                                ])),
+                            ),
+                        ])),
+                        ..Default::default()
+                    }),
+                ],
+                &mut Box::default(),
+            )
+            .unwrap();
+        let robot = Arc::new(Mutex::new(robot));
+        let mut grpc = GrpcServerInner {
+            robot: &robot,
+            signaling_server: &None,
+            auth: &None,
+        };
+        let req = component::motor::v1::GoToRequest {
+            name: "stepper".to_owned(),
+            rpm: 600.0,
+            position_revolutions: -0.5,
+            extra: None,
+        };
+        let response = grpc
+            .handle_do_command_request(
+                "/viam.component.motor.v1.MotorService/GoTo",
+                &req.encode_to_vec(),
+            )
+            .unwrap();
+        let exec = Executor::new();
+        assert!(exec.block_on(response).is_ok());
+
+        let mut motor = robot
+            .lock()
+            .unwrap()
+            .get_motor_by_name("stepper".to_owned())
+            .unwrap();
+        exec.block_on(async {
+            while motor.is_moving().unwrap() {
+                async_io::Timer::after(Duration::from_millis(5)).await;
+            }
+        });
+        assert_eq!(motor.get_position_revolutions().unwrap(), -0.5);
+
+        let req = component::motor::v1::GoForRequest {
+            name: "stepper".to_owned(),
+            rpm: -600.0,
+            revolutions: 1.0,
+            extra: None,
+        };
+        let response = grpc
+            .handle_do_command_request(
+                "/viam.component.motor.v1.MotorService/GoFor",
+                &req.encode_to_vec(),
+            )
+            .unwrap();
+        assert!(exec.block_on(response).is_ok());
+        exec.block_on(async {
+            while motor.is_moving().unwrap() {
+                async_io::Timer::after(Duration::from_millis(5)).await;
+            }
+        });
+        assert_eq!(motor.get_position_revolutions().unwrap(), -1.5);
+    }
+
+    #[test_log::test]
+    fn test_do_command_request() {
+        use crate::common::config::DynamicComponentConfig;
+        let mut robot = LocalRobot::default();
+        robot
+            .process_components(
+                vec![Some(DynamicComponentConfig {
+                    name: "generic".to_owned(),
+                    namespace: "rdk".to_owned(),
+                    r#type: "generic".to_owned(),
+                    model: "rdk:builtin:fake".to_owned(),
+                    ..Default::default()
+                })],
+                &mut Box::default(),
+            )
+            .unwrap();
+        let robot = Arc::new(Mutex::new(robot));
+        let mut grpc = GrpcServerInner {
+            robot: &robot,
+            signaling_server: &None,
+            auth: &None,
+        };
+
+        let command = Value {
+            kind: Some(Kind::StringValue("hello".to_owned())),
+        };
+        let req = proto::common::v1::DoCommandRequest {
+            name: "generic".to_owned(),
+            command: Some(crate::google::protobuf::Struct {
+                fields: HashMap::from([("echo".to_owned(), command.clone())]),
+            }),
+        };
+        let response = grpc
+            .handle_do_command_request(
+                "/viam.component.generic.v1.GenericService/DoCommand",
+                &req.encode_to_vec(),
+            )
+            .unwrap();
+        let response = block_on(response).unwrap();
+        let response = proto::common::v1::DoCommandResponse::decode(&response[5..]).unwrap();
+        assert_eq!(response.result.unwrap().fields["echoed"], command);
+
+        assert!(grpc
+            .handle_do_command_request("/viam.component.motor.v1.MotorService/Stop", &[])
+            .is_none());
+
+        // robot-wide commands only go to the dedicated resource, components still receive them
+        let any_moving = |name: &str| {
+            let req = proto::common::v1::DoCommandRequest {
+                name: name.to_owned(),
+                command: Some(crate::google::protobuf::Struct {
+                    fields: HashMap::from([(
+                        "any_moving".to_owned(),
+                        Value {
+                            kind: Some(Kind::NullValue(0)),
+                        },
+                    )]),
+                }),
+            };
+            let response = GrpcServerInner {
+                robot: &robot,
+                signaling_server: &None,
+                auth: &None,
+            }
+            .handle_do_command_request(
+                "/viam.component.generic.v1.GenericService/DoCommand",
+                &req.encode_to_vec(),
+            )
+            .unwrap();
+            block_on(response).map(|response| {
+                proto::common::v1::DoCommandResponse::decode(&response[5..])
+                    .unwrap()
+                    .result
+                    .unwrap()
+            })
+        };
+        assert!(any_moving("generic").unwrap().fields.is_empty());
+        assert_eq!(
+            any_moving(ROBOT_COMMAND_RESOURCE_NAME).unwrap().fields["any_moving"].kind,
+            Some(Kind::BoolValue(false))
+        );
+        assert!(any_moving("missing").is_err());
+    }
+
+    #[test_log::test]
+    fn test_do_command_async_override() {
+        use crate::common::{
+            config::DynamicComponentConfig,
+            generic::{DoCommand, GenericComponent},
+            registry::ComponentRegistry,
+            status::{Status, StatusError},
+        };
+
+        // a sequence that only completes once the executor got back control, `do_command` keeps
+        // its default and fails
+        struct TimedSequence;
+        impl DoCommand for TimedSequence {
+            fn do_command_async(
+                &mut self,
+                _: Option<crate::google::protobuf::Struct>,
+            ) -> DoCommandFuture {
+                Box::pin(async {
+                    futures_lite::future::yield_now().await;
+                    Ok(Some(crate::google::protobuf::Struct {
+                        fields: HashMap::from([(
+                            "sequence".to_owned(),
+                            Value {
+                                kind: Some(Kind::StringValue("done".to_owned())),
+                            },
+                        )]),
+                    }))
+                })
+            }
+        }
+        impl Status for TimedSequence {
+            fn get_status(&self) -> Result<Option<crate::google::protobuf::Struct>, StatusError> {
+                Ok(None)
+            }
+        }
+        impl GenericComponent for TimedSequence {}
+
+        let mut registry = Box::<ComponentRegistry>::default();
+        registry
+            .register_generic_component("timed_sequence", &|_, _| {
+                Ok(Arc::new(Mutex::new(TimedSequence)))
+            })
+            .unwrap();
+        let mut robot = LocalRobot::default();
+        robot
+            .process_components(
+                vec![Some(DynamicComponentConfig {
+                    name: "sequence".to_owned(),
+                    namespace: "rdk".to_owned(),
+                    r#type: "generic".to_owned(),
+                    model: "rdk:builtin:timed_sequence".to_owned(),
+                    ..Default::default()
+                })],
+                &mut registry,
+            )
+            .unwrap();
+        let robot = Arc::new(Mutex::new(robot));
+        assert!(robot
+            .lock()
+            .unwrap()
+            .get_generic_component_by_name("sequence".to_owned())
+            .unwrap()
+            .lock()
+            .unwrap()
+            .do_command(None)
+            .is_err());
+
+        let req = proto::common::v1::DoCommandRequest {
+            name: "sequence".to_owned(),
+            command: None,
+        };
+        let mut response = GrpcServerInner {
+            robot: &robot,
+            signaling_server: &None,
+            auth: &None,
+        }
+        .handle_do_command_request(
+            "/viam.component.generic.v1.GenericService/DoCommand",
+            &req.encode_to_vec(),
+        )
+        .unwrap();
+        // the server awaits the override rather than answering with the sync default
+        assert!(block_on(futures_lite::future::poll_once(&mut response)).is_none());
+        let response = block_on(response).unwrap();
+        let response = proto::common::v1::DoCommandResponse::decode(&response[5..]).unwrap();
+        assert_eq!(
+            response.result.unwrap().fields["sequence"].kind,
+            Some(Kind::StringValue("done".to_owned()))
+        );
+    }
+
+    #[test_log::test]
+    fn test_includes_capture_time() {
+        let extra = |kind| {
+            Some(crate::google::protobuf::Struct {
+                fields: HashMap::from([(
+                    INCLUDE_CAPTURE_TIME_EXTRA.to_string(),
+                    Value { kind: Some(kind) },
+                )]),
+            })
+        };
+        assert!(includes_capture_time(&extra(Kind::BoolValue(true))));
+        assert!(!includes_capture_time(&extra(Kind::BoolValue(false))));
+        assert!(!includes_capture_time(&extra(Kind::StringValue(
+            "true".to_string()
+        ))));
+        assert!(!includes_capture_time(&Some(Default::default())));
+        assert!(!includes_capture_time(&None));
+    }
+
+    #[test_log::test]
+    fn test_streamed_readings_match_encode_message() {
+        let readings: crate::common::sensor::GenericReadingsResult = (0..200)
+            .map(|i| {
+                (
+                    format!("reading_{}", i),
+                    Value {
+                        kind: Some(Kind::NumberValue(i as f64)),
+                    },
+                )
+            })
+            .collect();
+        let expected = GrpcServerInner::encode_message(proto::common::v1::GetReadingsResponse {
+            readings: readings.clone(),
+        })
+        .unwrap();
+        let (chunks, streamed) = collect(encode_readings_stream(readings));
+        assert!(chunks > 1);
+        // map ordering may differ, compare the decoded messages
+        assert_eq!(streamed.len(), expected.len());
+        assert_eq!(streamed[..5], expected[..5]);
+        assert_eq!(
+            proto::common::v1::GetReadingsResponse::decode(&streamed[5..]).unwrap(),
+            proto::common::v1::GetReadingsResponse::decode(&expected[5..]).unwrap()
+        );
+
+        let (chunks, streamed) = collect(encode_readings_stream(
+            crate::common::sensor::GenericReadingsResult::new(),
+        ));
+        assert_eq!(chunks, 1);
+        assert_eq!(streamed, [0, 0, 0, 0, 0]);
+
+        // ordered readings, such as the movement sensor ones, are streamed in order
+        use crate::common::movement_sensor::MOVEMENT_SENSOR_READINGS_ORDER;
+        let readings = MOVEMENT_SENSOR_READINGS_ORDER
+            .iter()
+            .map(|key| (key.to_string(), Value::default()));
+        let (_, streamed) = collect(encode_readings_stream(readings));
+        let positions: Vec<_> = MOVEMENT_SENSOR_READINGS_ORDER
+            .iter()
+            .map(|key| {
+                streamed
+                    .windows(key.len())
+                    .position(|w| w == key.as_bytes())
+                    .unwrap()
+            })
+            .collect();
+        assert!(positions.windows(2).all(|w| w[0] < w[1]));
+    }
+}
@@ -1,6 +1,8 @@
 #![allow(dead_code)]
 
-use super::config::{AttributeError, Kind};
+use super::config::{AttributeError, ConfigType, Kind};
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
 use std::sync::{Arc, Mutex};
 
 use thiserror::Error;
@@ -47,6 +49,183 @@ pub trait I2CHandle {
 
 pub type I2cHandleType = Arc<Mutex<dyn I2CHandle + Send>>;
 
+/// Valid 7-bit I2C device addresses, the addresses outside of this range are reserved
+pub const I2C_ADDRESS_RANGE: RangeInclusive<u8> = 0x08..=0x77;
+
+/// The optional `i2c_address` attribute of a driver, for devices found at a non default address
+pub(crate) fn i2c_address_from_config(cfg: &ConfigType) -> Result<Option<u8>, I2CErrors> {
+    match cfg.get_attribute::<u8>("i2c_address") {
+        Ok(address) if I2C_ADDRESS_RANGE.contains(&address) => Ok(Some(address)),
+        Err(AttributeError::KeyNotFound(_)) => Ok(None),
+        _ => Err(I2CErrors::I2CInvalidArgument(
+            "i2c_address should be a 7-bit address between 0x08 and 0x77",
+        )),
+    }
+}
+
+/// Address of a TCA9548A with its A0-A2 pins tied low
+pub const TCA9548A_DEFAULT_ADDRESS: u8 = 0x70;
+/// Number of downstream channels of a TCA9548A
+pub const TCA9548A_CHANNELS: u8 = 8;
+
+/// A TCA9548A I2C multiplexer connecting its parent bus to one of 8 downstream channels, so
+/// devices sharing an address can each sit on their own channel. The channel is selected by
+/// writing a bitmask to the mux's single control register.
+pub struct I2cMux {
+    parent: I2cHandleType,
+    address: u8,
+}
+
+impl I2cMux {
+    pub fn new(parent: I2cHandleType, address: u8) -> Self {
+        Self { parent, address }
+    }
+
+    /// Connect the parent bus to `channel` only
+    pub fn select_channel(&mut self, channel: u8) -> Result<(), I2CErrors> {
+        if channel >= TCA9548A_CHANNELS {
+            return Err(I2CErrors::I2CInvalidArgument(
+                "i2c mux channel should be between 0 and 7",
+            ));
+        }
+        self.parent.write_i2c(self.address, &[1 << channel])
+    }
+
+    fn on_channel<T>(
+        &mut self,
+        channel: u8,
+        transaction: impl FnOnce(&mut I2cHandleType) -> Result<T, I2CErrors>,
+    ) -> Result<T, I2CErrors> {
+        self.select_channel(channel)?;
+        transaction(&mut self.parent)
+    }
+}
+
+/// The bus behind one channel of an [`I2cMux`], the channel is selected before each transaction
+pub struct I2cMuxChannel {
+    name: String,
+    mux: Arc<Mutex<I2cMux>>,
+    channel: u8,
+}
+
+impl I2cMuxChannel {
+    pub fn new(name: String, mux: Arc<Mutex<I2cMux>>, channel: u8) -> Result<Self, I2CErrors> {
+        if channel >= TCA9548A_CHANNELS {
+            return Err(I2CErrors::I2CInvalidArgument(
+                "i2c mux channel should be between 0 and 7",
+            ));
+        }
+        Ok(Self { name, mux, channel })
+    }
+}
+
+impl I2CHandle for I2cMuxChannel {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn read_i2c(&mut self, address: u8, buffer: &mut [u8]) -> Result<(), I2CErrors> {
+        self.mux
+            .lock()
+            .unwrap()
+            .on_channel(self.channel, |bus| bus.read_i2c(address, buffer))
+    }
+
+    fn write_i2c(&mut self, address: u8, bytes: &[u8]) -> Result<(), I2CErrors> {
+        self.mux
+            .lock()
+            .unwrap()
+            .on_channel(self.channel, |bus| bus.write_i2c(address, bytes))
+    }
+
+    fn write_read_i2c(
+        &mut self,
+        address: u8,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), I2CErrors> {
+        self.mux.lock().unwrap().on_channel(self.channel, |bus| {
+            bus.write_read_i2c(address, bytes, buffer)
+        })
+    }
+}
+
+/// An entry of a board's `i2c_muxes`: a TCA9548A on the `i2c_bus` bus of the board
+#[derive(Debug)]
+pub(crate) struct I2cMuxConfig<'a> {
+    pub(crate) name: &'a str,
+    pub(crate) i2c_bus: &'a str,
+    pub(crate) i2c_address: u8,
+}
+
+impl<'a> TryFrom<&'a Kind> for I2cMuxConfig<'a> {
+    type Error = AttributeError;
+    fn try_from(value: &'a Kind) -> Result<Self, Self::Error> {
+        if !value.contains_key("name")? {
+            return Err(AttributeError::KeyNotFound("name".to_string()));
+        }
+        let name = value.get("name")?.unwrap().try_into()?;
+        if !value.contains_key("i2c_bus")? {
+            return Err(AttributeError::KeyNotFound("i2c_bus".to_string()));
+        }
+        let i2c_bus = value.get("i2c_bus")?.unwrap().try_into()?;
+        let i2c_address = match value.get("i2c_address")? {
+            Some(val) => val.try_into()?,
+            None => TCA9548A_DEFAULT_ADDRESS,
+        };
+        Ok(I2cMuxConfig {
+            name,
+            i2c_bus,
+            i2c_address,
+        })
+    }
+}
+
+/// Add the channels of the muxes declared in the board's `i2c_muxes` to its buses, named
+/// `<name>_<channel>`
+pub(crate) fn add_i2c_muxes(
+    cfg: &ConfigType,
+    i2cs: &mut HashMap<String, I2cHandleType>,
+) -> Result<(), I2CErrors> {
+    let confs = match cfg.get_attribute::<Vec<I2cMuxConfig>>("i2c_muxes") {
+        Ok(confs) => confs,
+        Err(AttributeError::KeyNotFound(_)) => return Ok(()),
+        Err(_) => {
+            return Err(I2CErrors::I2CInvalidArgument(
+                "i2c_muxes entries should have a name and an i2c_bus",
+            ))
+        }
+    };
+    for conf in confs {
+        if !I2C_ADDRESS_RANGE.contains(&conf.i2c_address) {
+            return Err(I2CErrors::I2CInvalidArgument(
+                "i2c mux i2c_address should be a 7-bit address between 0x08 and 0x77",
+            ));
+        }
+        let parent = i2cs
+            .get(conf.i2c_bus)
+            .cloned()
+            .ok_or(I2CErrors::I2CInvalidArgument(
+                "i2c mux i2c_bus should be one of the board's i2cs",
+            ))?;
+        // a channel must not replace a bus a component may already be configured with
+        if (0..TCA9548A_CHANNELS)
+            .any(|channel| i2cs.contains_key(&format!("{}_{}", conf.name, channel)))
+        {
+            return Err(I2CErrors::I2CInvalidArgument(
+                "i2c mux channel names <name>_<channel> should not be the name of another i2c",
+            ));
+        }
+        let mux = Arc::new(Mutex::new(I2cMux::new(parent, conf.i2c_address)));
+        for channel in 0..TCA9548A_CHANNELS {
+            let name = format!("{}_{}", conf.name, channel);
+            let handle = I2cMuxChannel::new(name.clone(), mux.clone(), channel)?;
+            i2cs.insert(name, Arc::new(Mutex::new(handle)));
+        }
+    }
+    Ok(())
+}
+
 #[derive(Debug)]
 pub(crate) struct FakeI2cConfig<'a> {
     pub(crate) name: &'a str,
@@ -122,6 +301,16 @@ impl I2CHandle for FakeI2CHandle {
         }
         Ok(())
     }
+
+    fn write_read_i2c(
+        &mut self,
+        address: u8,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), I2CErrors> {
+        self.write_i2c(address, bytes)?;
+        self.read_i2c(address, buffer)
+    }
 }
 
 impl<A> I2CHandle for Arc<Mutex<A>>
@@ -149,3 +338,102 @@ where
         self.lock().unwrap().write_read_i2c(address, bytes, buffer)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::config::DynamicComponentConfig;
+
+    #[test_log::test]
+    fn test_i2c_address_from_config() {
+        let address = |kind: Option<Kind>| {
+            let config = DynamicComponentConfig {
+                attributes: kind.map(|kind| HashMap::from([("i2c_address".to_owned(), kind)])),
+                ..Default::default()
+            };
+            i2c_address_from_config(&ConfigType::Dynamic(&config))
+        };
+        assert_eq!(address(None).unwrap(), None);
+        assert_eq!(address(Some(Kind::NumberValue(65.0))).unwrap(), Some(0x41));
+        assert!(address(Some(Kind::NumberValue(0x78 as f64))).is_err());
+        assert!(address(Some(Kind::NumberValue(3.0))).is_err());
+        assert!(address(Some(Kind::NumberValue(300.0))).is_err());
+        assert!(address(Some(Kind::StringValue("0x40".to_owned()))).is_err());
+    }
+
+    #[test_log::test]
+    fn test_i2c_mux() {
+        let parent = Arc::new(Mutex::new(FakeI2CHandle::new("i2c0".to_owned())));
+        let mut i2cs: HashMap<String, I2cHandleType> =
+            HashMap::from([("i2c0".to_owned(), parent.clone() as I2cHandleType)]);
+        let config = DynamicComponentConfig {
+            attributes: Some(HashMap::from([(
+                "i2c_muxes".to_owned(),
+                Kind::VecValue(vec![Kind::StructValue(HashMap::from([
+                    ("name".to_owned(), Kind::StringValue("mux".to_owned())),
+                    ("i2c_bus".to_owned(), Kind::StringValue("i2c0".to_owned())),
+                ]))]),
+            )])),
+            ..Default::default()
+        };
+        add_i2c_muxes(&ConfigType::Dynamic(&config), &mut i2cs).unwrap();
+        assert_eq!(i2cs.len(), 9);
+
+        // the fake bus answers with the bytes last written, the channel selection comes first
+        let mut channel = i2cs["mux_5"].clone();
+        assert_eq!(channel.name(), "mux_5");
+        let mut buffer = [0; 1];
+        channel.read_i2c(0x68, &mut buffer).unwrap();
+        assert_eq!(buffer, [0b0010_0000]);
+        channel.write_read_i2c(0x68, &[0x3B], &mut buffer).unwrap();
+        assert_eq!(buffer, [0x3B]);
+
+        let mut mux = I2cMux::new(parent.clone(), TCA9548A_DEFAULT_ADDRESS);
+        assert!(mux.select_channel(8).is_err());
+
+        let config = DynamicComponentConfig {
+            attributes: Some(HashMap::from([(
+                "i2c_muxes".to_owned(),
+                Kind::VecValue(vec![Kind::StructValue(HashMap::from([
+                    ("name".to_owned(), Kind::StringValue("mux".to_owned())),
+                    ("i2c_bus".to_owned(), Kind::StringValue("i2c1".to_owned())),
+                ]))]),
+            )])),
+            ..Default::default()
+        };
+        assert!(add_i2c_muxes(&ConfigType::Dynamic(&config), &mut i2cs).is_err());
+
+        // declaring the same mux again would replace its channels
+        let config = DynamicComponentConfig {
+            attributes: Some(HashMap::from([(
+                "i2c_muxes".to_owned(),
+                Kind::VecValue(vec![Kind::StructValue(HashMap::from([
+                    ("name".to_owned(), Kind::StringValue("mux".to_owned())),
+                    ("i2c_bus".to_owned(), Kind::StringValue("i2c0".to_owned())),
+                ]))]),
+            )])),
+            ..Default::default()
+        };
+        assert!(add_i2c_muxes(&ConfigType::Dynamic(&config), &mut i2cs).is_err());
+
+        // nor can a channel take the name of one of the board's buses
+        let bus = Arc::new(Mutex::new(FakeI2CHandle::new("i2c_3".to_owned())));
+        let mut i2cs: HashMap<String, I2cHandleType> = HashMap::from([
+            ("i2c0".to_owned(), parent as I2cHandleType),
+            ("i2c_3".to_owned(), bus as I2cHandleType),
+        ]);
+        let config = DynamicComponentConfig {
+            attributes: Some(HashMap::from([(
+                "i2c_muxes".to_owned(),
+                Kind::VecValue(vec![Kind::StructValue(HashMap::from([
+                    ("name".to_owned(), Kind::StringValue("i2c".to_owned())),
+                    ("i2c_bus".to_owned(), Kind::StringValue("i2c0".to_owned())),
+                ]))]),
+            )])),
+            ..Default::default()
+        };
+        assert!(add_i2c_muxes(&ConfigType::Dynamic(&config), &mut i2cs).is_err());
+        assert_eq!(i2cs.len(), 2);
+        assert_eq!(i2cs["i2c_3"].name(), "i2c_3");
+    }
+}
@@ -0,0 +1,288 @@
+//! Generic component giving raw access to a device on an I2C bus through DoCommand, to poke at
+//! the registers of a sensor during bring-up without writing a driver first.
+//!
+//! Configured with the `i2c_bus` of the board and the `i2c_address` of the device, data is
+//! exchanged as hex strings (`"0a1b"`)
+//!
+//! Supported commands:
+//! - `{"read_register": r}` reads one byte from register `r`, `{"read_register": {"register": r,
+//!   "length": n}}` reads `n` bytes starting at register `r`
+//! - `{"write_register": {"register": r, "data": "0a1b"}}` writes the bytes to register `r`
+//! - `{"read_bytes": n}` reads `n` bytes from the device without selecting a register first
+//!
+//! Reads return the bytes under the `data` key, e.g. `{"data": "60"}`
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use super::{
+    board::Board,
+    config::ConfigType,
+    generic::{DoCommand, GenericComponent, GenericComponentType, GenericError},
+    i2c::{i2c_address_from_config, I2CHandle, I2cHandleType},
+    registry::{get_board_from_dependencies, ComponentRegistry, Dependency},
+    status::{Status, StatusError},
+};
+use crate::google::protobuf::{value::Kind, Struct, Value};
+
+// most registers are a few bytes wide, this only prevents big allocations
+const MAX_TRANSFER_LEN: usize = 64;
+
+pub(crate) fn register_models(registry: &mut ComponentRegistry) {
+    if registry
+        .register_generic_component("i2c_passthrough", &I2cPassthrough::from_config)
+        .is_err()
+    {
+        log::error!("i2c_passthrough model is already registered")
+    }
+}
+
+pub struct I2cPassthrough {
+    i2c_handle: I2cHandleType,
+    i2c_address: u8,
+}
+
+impl I2cPassthrough {
+    pub fn new(i2c_handle: I2cHandleType, i2c_address: u8) -> Self {
+        Self {
+            i2c_handle,
+            i2c_address,
+        }
+    }
+
+    pub(crate) fn from_config(
+        cfg: ConfigType,
+        dependencies: Vec<Dependency>,
+    ) -> Result<GenericComponentType, GenericError> {
+        let board = get_board_from_dependencies(dependencies)
+            .ok_or_else(|| GenericError::Other("i2c_passthrough missing board".into()))?;
+        let i2c_bus = cfg
+            .get_attribute::<String>("i2c_bus")
+            .map_err(|_| GenericError::Other("i2c_passthrough missing i2c_bus".into()))?;
+        let i2c_address = i2c_address_from_config(&cfg)
+            .map_err(|e| GenericError::Other(e.into()))?
+            .ok_or_else(|| GenericError::Other("i2c_passthrough missing i2c_address".into()))?;
+        let i2c_handle = board
+            .get_i2c_by_name(i2c_bus)
+            .map_err(|e| GenericError::Other(e.into()))?;
+        Ok(Arc::new(Mutex::new(Self::new(i2c_handle, i2c_address))))
+    }
+
+    fn read_register(&mut self, register: u8, len: usize) -> Result<Vec<u8>, GenericError> {
+        let mut buffer = vec![0; len];
+        self.i2c_handle
+            .write_read_i2c(self.i2c_address, &[register], &mut buffer)
+            .map_err(|e| GenericError::Other(e.into()))?;
+        Ok(buffer)
+    }
+
+    fn write_register(&mut self, register: u8, data: &[u8]) -> Result<(), GenericError> {
+        let bytes: Vec<u8> = std::iter::once(register)
+            .chain(data.iter().copied())
+            .collect();
+        self.i2c_handle
+            .write_i2c(self.i2c_address, &bytes)
+            .map_err(|e| GenericError::Other(e.into()))
+    }
+
+    fn read_bytes(&mut self, len: usize) -> Result<Vec<u8>, GenericError> {
+        let mut buffer = vec![0; len];
+        self.i2c_handle
+            .read_i2c(self.i2c_address, &mut buffer)
+            .map_err(|e| GenericError::Other(e.into()))?;
+        Ok(buffer)
+    }
+}
+
+fn number_arg<T: TryFrom<u64>>(
+    command: &str,
+    name: &str,
+    value: Option<&Value>,
+) -> Result<T, GenericError> {
+    match value.and_then(|v| v.kind.as_ref()) {
+        Some(Kind::NumberValue(n)) if *n >= 0.0 && n.fract() == 0.0 => T::try_from(*n as u64)
+            .map_err(|_| {
+                GenericError::Other(format!("{} `{}` is out of range", command, name).into())
+            }),
+        _ => Err(GenericError::Other(
+            format!("{} expects `{}` to be a positive integer", command, name).into(),
+        )),
+    }
+}
+
+fn transfer_len(command: &str, value: Option<&Value>) -> Result<usize, GenericError> {
+    match number_arg::<usize>(command, "length", value)? {
+        len @ 1..=MAX_TRANSFER_LEN => Ok(len),
+        _ => Err(GenericError::Other(
+            format!(
+                "{} `length` has to be between 1 and {}",
+                command, MAX_TRANSFER_LEN
+            )
+            .into(),
+        )),
+    }
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    let hex = hex.trim_start_matches("0x");
+    if hex.is_empty() || hex.len() % 2 != 0 {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+impl GenericComponent for I2cPassthrough {}
+
+impl DoCommand for I2cPassthrough {
+    fn do_command(
+        &mut self,
+        command_struct: Option<Struct>,
+    ) -> Result<Option<Struct>, GenericError> {
+        let mut res = HashMap::new();
+        let Some(command_struct) = command_struct else {
+            return Ok(None);
+        };
+        for (key, val) in &command_struct.fields {
+            let data = match key.as_str() {
+                "read_register" => match &val.kind {
+                    Some(Kind::StructValue(args)) => {
+                        let register = number_arg(key, "register", args.fields.get("register"))?;
+                        let len = match args.fields.get("length") {
+                            Some(len) => transfer_len(key, Some(len))?,
+                            None => 1,
+                        };
+                        self.read_register(register, len)?
+                    }
+                    _ => {
+                        let register = number_arg(key, "register", Some(val))?;
+                        self.read_register(register, 1)?
+                    }
+                },
+                "write_register" => {
+                    let Some(Kind::StructValue(args)) = &val.kind else {
+                        return Err(GenericError::Other(
+                            "write_register expects a `register` and hex `data`".into(),
+                        ));
+                    };
+                    let register = number_arg(key, "register", args.fields.get("register"))?;
+                    let data = match args.fields.get("data").and_then(|v| v.kind.as_ref()) {
+                        Some(Kind::StringValue(hex)) => decode_hex(hex),
+                        _ => None,
+                    }
+                    .ok_or_else(|| {
+                        GenericError::Other("write_register `data` should be hex bytes".into())
+                    })?;
+                    self.write_register(register, &data)?;
+                    continue;
+                }
+                "read_bytes" => {
+                    let len = transfer_len(key, Some(val))?;
+                    self.read_bytes(len)?
+                }
+                _ => {
+                    return Err(GenericError::Other(
+                        format!("unknown i2c_passthrough command {}", key).into(),
+                    ))
+                }
+            };
+            res.insert(
+                "data".to_owned(),
+                Value {
+                    kind: Some(Kind::StringValue(encode_hex(&data))),
+                },
+            );
+        }
+        Ok(Some(Struct { fields: res }))
+    }
+}
+
+impl Status for I2cPassthrough {
+    fn get_status(&self) -> Result<Option<Struct>, StatusError> {
+        Ok(Some(Struct {
+            fields: HashMap::new(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::i2c::FakeI2CHandle;
+    use crate::common::test_utils::{assert_do_command, command, do_command};
+
+    #[test_log::test]
+    fn test_i2c_passthrough() {
+        let handle = Arc::new(Mutex::new(FakeI2CHandle::new_with_value(
+            "i2c0".to_owned(),
+            [0x60, 0x1f, 0x02],
+        )));
+        let mut passthrough = I2cPassthrough::new(handle, 0x76);
+        let data = |hex: &str| Some(command([("data", Kind::StringValue(hex.to_owned()))]));
+
+        assert_do_command(
+            &mut passthrough,
+            command([("read_bytes", Kind::NumberValue(2.0))]),
+            data("601f"),
+        );
+        assert_do_command(
+            &mut passthrough,
+            command([(
+                "write_register",
+                Kind::StructValue(command([
+                    ("register", Kind::NumberValue(244.0)),
+                    ("data", Kind::StringValue("0x27".to_owned())),
+                ])),
+            )]),
+            Some(command([])),
+        );
+        // the fake handle stores the register followed by the data
+        assert_do_command(
+            &mut passthrough,
+            command([("read_bytes", Kind::NumberValue(3.0))]),
+            data("f42702"),
+        );
+        // and answers reads with what it stores
+        assert_do_command(
+            &mut passthrough,
+            command([(
+                "read_register",
+                Kind::StructValue(command([
+                    ("register", Kind::NumberValue(208.0)),
+                    ("length", Kind::NumberValue(2.0)),
+                ])),
+            )]),
+            data("d027"),
+        );
+        assert_do_command(
+            &mut passthrough,
+            command([("read_register", Kind::NumberValue(1.0))]),
+            data("01"),
+        );
+
+        for invalid in [
+            command([("read_register", Kind::NumberValue(256.0))]),
+            command([("read_bytes", Kind::NumberValue(0.0))]),
+            command([("read_bytes", Kind::NumberValue(1.5))]),
+            command([(
+                "write_register",
+                Kind::StructValue(command([
+                    ("register", Kind::NumberValue(1.0)),
+                    ("data", Kind::StringValue("abc".to_owned())),
+                ])),
+            )]),
+            command([("scan", Kind::NullValue(0))]),
+        ] {
+            assert!(do_command(&mut passthrough, invalid).is_err());
+        }
+        assert_eq!(decode_hex("0A1b"), Some(vec![0x0a, 0x1b]));
+    }
+}
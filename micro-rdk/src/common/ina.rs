@@ -21,7 +21,7 @@ use std::sync::{Arc, Mutex};
 use super::{
     board::Board,
     config::ConfigType,
-    i2c::{I2CErrors, I2cHandleType},
+    i2c::{i2c_address_from_config, I2CErrors, I2cHandleType},
     power_sensor::{Current, PowerSensor, PowerSensorType, PowerSupplyType, Voltage},
     registry::{get_board_from_dependencies, ComponentRegistry, Dependency},
     sensor::SensorError,
@@ -75,9 +75,7 @@ fn from_config(
     cfg: ConfigType,
     dependencies: Vec<Dependency>,
 ) -> Result<Ina<I2cHandleType>, SensorError> {
-    let i2c_address = cfg
-        .get_attribute::<u8>("i2c_address")
-        .unwrap_or(DEFAULT_I2C_ADDRESS);
+    let i2c_address = i2c_address_from_config(&cfg)?.unwrap_or(DEFAULT_I2C_ADDRESS);
 
     let default_max_current_amperes = match &model {
         Model::Ina219 => 3.2,
@@ -8,7 +8,10 @@ use ringbuf::{LocalRb, Rb};
 use std::{
     collections::HashMap,
     mem::MaybeUninit,
-    sync::OnceLock,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex, OnceLock,
+    },
     time::{Duration, Instant},
 };
 
@@ -131,6 +134,39 @@ impl PeriodicAppClientTask for LogUploadTask {
     }
 }
 
+/// Environment variable selecting the [`LogFormat`] used by [`initialize_logger`]
+pub const LOG_FORMAT_ENV: &str = "MICRO_RDK_LOG_FORMAT";
+
+/// Format of the logs written locally (serial or terminal), logs uploaded to app are unaffected
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Format of the wrapped logger
+    #[default]
+    Plain,
+    /// One JSON object per line with timestamp, level, module and message
+    Json,
+}
+
+impl LogFormat {
+    /// Read the format from [`LOG_FORMAT_ENV`] (`json` or `plain`), defaults to plain
+    pub fn from_env() -> Self {
+        match std::env::var(LOG_FORMAT_ENV) {
+            Ok(format) if format.eq_ignore_ascii_case("json") => Self::Json,
+            _ => Self::Plain,
+        }
+    }
+}
+
+fn format_json_record(record: &::log::Record) -> String {
+    serde_json::json!({
+        "timestamp": Local::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, false),
+        "level": record.level().as_str().to_lowercase(),
+        "module": record.module_path().unwrap_or(record.target()),
+        "message": record.args().to_string(),
+    })
+    .to_string()
+}
+
 pub trait ViamLogAdapter {
     fn before_log_setup(&self);
     fn get_level_filter(&self) -> ::log::LevelFilter;
@@ -139,32 +175,101 @@ pub trait ViamLogAdapter {
 
 // ViamLogger is a wrapper around an existing logger that stores a copy into LOG_BUFFER for later
 // upload to the cloud. The existing logger should satisfy log::Log and the ViamLogAdapter
-// trait and then by initialized using this function at the start of main
+// trait and then by initialized using this function at the start of main. The local output format
+// is read from the environment, see LogFormat::from_env
 pub fn initialize_logger<T: ::log::Log + ViamLogAdapter + 'static>() {
+    initialize_logger_with_format::<T>(LogFormat::from_env())
+}
+
+/// Same as [`initialize_logger`] with an explicit local output format
+pub fn initialize_logger_with_format<T: ::log::Log + ViamLogAdapter + 'static>(format: LogFormat) {
     let inner = T::new();
-    let logger = ViamLogger::new(inner);
+    let logger = ViamLogger::new(inner, format);
     let filter = logger.level_filter();
     logger.before_log_setup();
     let _ = ::log::set_boxed_logger(Box::new(logger));
     ::log::set_max_level(filter)
 }
 
-struct ViamLogger<L>(L);
+// Number of identical consecutive messages written before further repetitions are collapsed,
+// 0 disables the deduplication
+static LOG_REPEAT_THRESHOLD: AtomicUsize = AtomicUsize::new(DEFAULT_LOG_REPEAT_THRESHOLD);
+
+/// Default number of identical consecutive log messages written before they are collapsed
+pub const DEFAULT_LOG_REPEAT_THRESHOLD: usize = 3;
+
+/// Set how many identical consecutive log messages are written before the following ones are
+/// collapsed into a single "...(repeated N times)" line once a different message is logged.
+/// A threshold of 0 disables deduplication.
+pub fn set_log_repeat_threshold(threshold: usize) {
+    LOG_REPEAT_THRESHOLD.store(threshold, Ordering::Relaxed);
+}
+
+// last message seen by the logger and how many times in a row it was logged
+struct RepeatedLog {
+    level: ::log::Level,
+    target: String,
+    message: String,
+    count: usize,
+}
+
+impl RepeatedLog {
+    fn is_same(&self, record: &::log::Record, message: &str) -> bool {
+        self.level == record.level() && self.target == record.target() && self.message == message
+    }
+}
+
+struct ViamLogger<L> {
+    inner: L,
+    format: LogFormat,
+    last: Mutex<Option<RepeatedLog>>,
+}
 
 impl<L> ViamLogger<L>
 where
     L: ::log::Log + ViamLogAdapter,
 {
-    fn new(inner: L) -> Self {
-        Self(inner)
+    fn new(inner: L, format: LogFormat) -> Self {
+        Self {
+            inner,
+            format,
+            last: Mutex::new(None),
+        }
     }
 
     fn before_log_setup(&self) {
-        self.0.before_log_setup()
+        self.inner.before_log_setup()
     }
 
     fn level_filter(&self) -> ::log::LevelFilter {
-        self.0.get_level_filter()
+        self.inner.get_level_filter()
+    }
+
+    fn write(&self, record: &::log::Record) {
+        match self.format {
+            LogFormat::Plain => self.inner.log(record),
+            LogFormat::Json => eprintln!("{}", format_json_record(record)),
+        }
+        let mut buffer = get_log_buffer().lock_blocking();
+        let _ = buffer.push_overwrite(ViamLogEntry::from_record(record));
+    }
+
+    // write a summary of the repetitions that were not written
+    fn write_repeated(&self, repeated: Option<RepeatedLog>) {
+        let threshold = LOG_REPEAT_THRESHOLD.load(Ordering::Relaxed);
+        if let Some(repeated) = repeated.filter(|r| threshold > 0 && r.count > threshold) {
+            self.write(
+                &::log::Record::builder()
+                    .args(format_args!(
+                        "{} ...(repeated {} times)",
+                        repeated.message,
+                        repeated.count - threshold
+                    ))
+                    .level(repeated.level)
+                    .target(&repeated.target)
+                    .build(),
+            );
+        }
     }
 }
 
@@ -173,18 +278,295 @@ where
     L: ::log::Log + ViamLogAdapter,
 {
     fn enabled(&self, metadata: &log::Metadata) -> bool {
-        self.0.enabled(metadata)
+        self.inner.enabled(metadata)
     }
 
     fn flush(&self) {
-        self.0.flush()
+        let repeated = self.last.lock().unwrap().take();
+        self.write_repeated(repeated);
+        self.inner.flush()
     }
 
     fn log(&self, record: &log::Record) {
-        if self.enabled(record.metadata()) {
-            self.0.log(record);
-            let mut buffer = get_log_buffer().lock_blocking();
-            let _ = buffer.push_overwrite(ViamLogEntry::from_record(record));
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let threshold = LOG_REPEAT_THRESHOLD.load(Ordering::Relaxed);
+        if threshold > 0 {
+            let message = record.args().to_string();
+            let mut last = self.last.lock().unwrap();
+            if let Some(repeated) = last
+                .as_mut()
+                .filter(|repeated| repeated.is_same(record, &message))
+            {
+                repeated.count += 1;
+                if repeated.count > threshold {
+                    return;
+                }
+            } else {
+                let previous = last.replace(RepeatedLog {
+                    level: record.level(),
+                    target: record.target().to_owned(),
+                    message,
+                    count: 1,
+                });
+                drop(last);
+                self.write_repeated(previous);
+            }
+        }
+        self.write(record);
+    }
+}
+
+#[cfg(any(target_os = "espidf", test))]
+const PANIC_RECORD_MAGIC: u32 = 0x5649_414d;
+#[cfg(any(target_os = "espidf", test))]
+const PANIC_MESSAGE_MAX_LEN: usize = 256;
+
+// A panic message that is only valid once stored, the memory holding it may contain anything
+// on boot
+#[cfg(any(target_os = "espidf", test))]
+#[repr(C)]
+struct PanicRecord {
+    magic: u32,
+    len: u32,
+    message: [u8; PANIC_MESSAGE_MAX_LEN],
+}
+
+#[cfg(any(target_os = "espidf", test))]
+impl PanicRecord {
+    const fn new() -> Self {
+        Self {
+            magic: 0,
+            len: 0,
+            message: [0; PANIC_MESSAGE_MAX_LEN],
+        }
+    }
+
+    fn store(&mut self, message: &str) {
+        let bytes = message.as_bytes();
+        let len = bytes.len().min(PANIC_MESSAGE_MAX_LEN);
+        self.message[..len].copy_from_slice(&bytes[..len]);
+        self.len = len as u32;
+        self.magic = PANIC_RECORD_MAGIC;
+    }
+
+    fn take(&mut self) -> Option<String> {
+        if self.magic != PANIC_RECORD_MAGIC {
+            return None;
+        }
+        self.magic = 0;
+        let len = (self.len as usize).min(PANIC_MESSAGE_MAX_LEN);
+        Some(String::from_utf8_lossy(&self.message[..len]).into_owned())
+    }
+}
+
+// Last panic message, kept in RTC memory that is not initialized on boot so it survives the
+// software reset following a panic (but not a power cycle)
+#[cfg(target_os = "espidf")]
+mod panic_record {
+    use super::PanicRecord;
+    use std::cell::UnsafeCell;
+
+    struct PanicRecordCell(UnsafeCell<PanicRecord>);
+
+    // only written by the panic hook and read once when the server starts
+    unsafe impl Sync for PanicRecordCell {}
+
+    #[link_section = ".rtc_noinit"]
+    static PANIC_RECORD: PanicRecordCell = PanicRecordCell(UnsafeCell::new(PanicRecord::new()));
+
+    pub(super) fn store(message: &str) {
+        unsafe { &mut *PANIC_RECORD.0.get() }.store(message)
+    }
+
+    pub(super) fn take() -> Option<String> {
+        unsafe { &mut *PANIC_RECORD.0.get() }.take()
+    }
+}
+
+// Write the panic message straight to the console, the panic may have happened while the logger
+// (or anything it relies on) was locked so the logger must not be used here
+#[cfg(target_os = "espidf")]
+fn write_panic_message(message: &str) {
+    const PANIC_CONSOLE_MAX_LEN: usize = 256;
+    // esp_rom_printf needs a nul terminated string, truncate on the stack rather than allocating
+    let mut buf = [0_u8; PANIC_CONSOLE_MAX_LEN + 1];
+    let len = message.len().min(PANIC_CONSOLE_MAX_LEN);
+    buf[..len].copy_from_slice(&message.as_bytes()[..len]);
+    unsafe {
+        crate::esp32::esp_idf_svc::sys::esp_rom_printf(
+            b"panic: %s\n\0".as_ptr() as *const std::ffi::c_char,
+            buf.as_ptr() as *const std::ffi::c_char,
+        );
+    }
+}
+
+#[cfg(not(target_os = "espidf"))]
+fn write_panic_message(message: &str) {
+    use std::io::Write;
+    // stderr is unbuffered and its lock is reentrant, a failed write is ignored rather than
+    // panicking again
+    let _ = writeln!(std::io::stderr(), "panic: {}", message);
+}
+
+/// Install a panic hook writing the panic message and location to the console before the
+/// default hook runs. On ESP32 the message is also kept across the reboot that follows the panic
+/// so [`report_previous_panic`] can upload it to app on next boot.
+pub fn install_panic_hook() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let message = info.to_string();
+        #[cfg(target_os = "espidf")]
+        panic_record::store(&message);
+        write_panic_message(&message);
+        previous(info)
+    }));
+}
+
+/// Log the panic that caused the last reboot, if any, so it gets uploaded to app
+pub fn report_previous_panic() {
+    #[cfg(target_os = "espidf")]
+    if let Some(message) = panic_record::take() {
+        ::log::error!("previous boot ended with a panic: {}", message);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_log::test]
+    fn test_panic_record() {
+        let mut record = PanicRecord::new();
+        assert!(record.take().is_none());
+
+        record.store("panicked at src/common/motor.rs:12:5:\nstalled");
+        assert_eq!(
+            record.take().as_deref(),
+            Some("panicked at src/common/motor.rs:12:5:\nstalled")
+        );
+        // reported once
+        assert!(record.take().is_none());
+
+        // long messages are truncated, a character cut in half isn't an error
+        let message = format!("a{}", "é".repeat(PANIC_MESSAGE_MAX_LEN));
+        record.store(&message);
+        let taken = record.take().unwrap();
+        assert_eq!(
+            taken,
+            format!("a{}\u{FFFD}", "é".repeat(PANIC_MESSAGE_MAX_LEN / 2 - 1))
+        );
+
+        // whatever the memory held on boot is ignored without the magic
+        let mut garbage = PanicRecord {
+            magic: 0xdead_beef,
+            len: u32::MAX,
+            message: [0xff; PANIC_MESSAGE_MAX_LEN],
+        };
+        assert!(garbage.take().is_none());
+    }
+
+    #[test_log::test]
+    fn test_panic_hook() {
+        install_panic_hook();
+        let panicked = std::panic::catch_unwind(|| panic!("hooked"));
+        // back to the default hook so other tests panic as usual
+        let _ = std::panic::take_hook();
+        // the hook itself must not panic, which would abort
+        assert!(panicked.is_err());
+    }
+
+    #[test_log::test]
+    fn test_format_json_record() {
+        let line = format_json_record(
+            &::log::Record::builder()
+                .args(format_args!("motor \"left\" stalled"))
+                .level(::log::Level::Warn)
+                .target("micro_rdk::common::motor")
+                .module_path(Some("micro_rdk::common::motor"))
+                .build(),
+        );
+        assert!(!line.contains('\n'));
+        let json: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(json["level"], "warn");
+        assert_eq!(json["module"], "micro_rdk::common::motor");
+        assert_eq!(json["message"], "motor \"left\" stalled");
+        assert!(chrono::DateTime::parse_from_rfc3339(json["timestamp"].as_str().unwrap()).is_ok());
+    }
+
+    #[derive(Default)]
+    struct RecordingLogger(Mutex<Vec<(::log::Level, String)>>);
+
+    impl ::log::Log for RecordingLogger {
+        fn enabled(&self, _: &::log::Metadata) -> bool {
+            true
+        }
+        fn log(&self, record: &::log::Record) {
+            self.0
+                .lock()
+                .unwrap()
+                .push((record.level(), record.args().to_string()));
+        }
+        fn flush(&self) {}
+    }
+
+    impl ViamLogAdapter for RecordingLogger {
+        fn before_log_setup(&self) {}
+        fn get_level_filter(&self) -> ::log::LevelFilter {
+            ::log::LevelFilter::Trace
+        }
+        fn new() -> Self {
+            Self::default()
+        }
+    }
+
+    fn log_to(logger: &ViamLogger<RecordingLogger>, level: ::log::Level, message: &str) {
+        ::log::Log::log(
+            logger,
+            &::log::Record::builder()
+                .args(format_args!("{}", message))
+                .level(level)
+                .target("test")
+                .build(),
+        );
+    }
+
+    #[test_log::test]
+    fn test_collapse_repeated_logs() {
+        use ::log::Level::{Error, Info};
+        let logger = ViamLogger::new(RecordingLogger::new(), LogFormat::Plain);
+        for _ in 0..5 {
+            log_to(&logger, Info, "a");
+        }
+        // same message at another level isn't a repetition
+        log_to(&logger, Error, "a");
+        log_to(&logger, Info, "b");
+        assert_eq!(
+            logger.inner.0.lock().unwrap().as_slice(),
+            &[
+                (Info, "a".to_owned()),
+                (Info, "a".to_owned()),
+                (Info, "a".to_owned()),
+                (Info, "a ...(repeated 2 times)".to_owned()),
+                (Error, "a".to_owned()),
+                (Info, "b".to_owned()),
+            ]
+        );
+
+        logger.inner.0.lock().unwrap().clear();
+        for _ in 0..7 {
+            log_to(&logger, Info, "b");
         }
+        // pending repetitions are summarized on flush
+        ::log::Log::flush(&logger);
+        assert_eq!(
+            logger.inner.0.lock().unwrap().as_slice(),
+            &[
+                (Info, "b".to_owned()),
+                (Info, "b".to_owned()),
+                (Info, "b ...(repeated 5 times)".to_owned()),
+            ]
+        );
     }
 }
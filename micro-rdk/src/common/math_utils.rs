@@ -3,7 +3,11 @@ use crate::{
     google::protobuf::{value::Kind, Struct, Value},
     proto::common,
 };
-use std::{collections::HashMap, time::Duration};
+use std::{
+    collections::HashMap,
+    ops::{Add, Mul, Neg, Sub},
+    time::Duration,
+};
 use thiserror::Error;
 
 #[cfg(feature = "data")]
@@ -13,7 +17,7 @@ use crate::proto::app::data_sync::v1::sensor_data::Data;
 #[error("invalid argument")]
 pub struct UtilsInvalidArg;
 
-#[derive(Clone, Copy, Debug, Default)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
 pub struct Vector3 {
     pub x: f64,
     pub y: f64,
@@ -28,6 +32,38 @@ impl Vector3 {
             z: 0.0,
         }
     }
+    pub fn from_xyz(x: f64, y: f64, z: f64) -> Self {
+        Vector3 { x, y, z }
+    }
+    pub fn scale(self, factor: f64) -> Self {
+        Vector3 {
+            x: self.x * factor,
+            y: self.y * factor,
+            z: self.z * factor,
+        }
+    }
+    pub fn dot(self, other: Vector3) -> f64 {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+    pub fn cross(self, other: Vector3) -> Self {
+        Vector3 {
+            x: self.y * other.z - self.z * other.y,
+            y: self.z * other.x - self.x * other.z,
+            z: self.x * other.y - self.y * other.x,
+        }
+    }
+    pub fn magnitude(self) -> f64 {
+        self.dot(self).sqrt()
+    }
+    // Returns the unit vector with the same direction, the zero vector has no direction
+    // and cannot be normalized
+    pub fn normalize(self) -> Result<Self, UtilsInvalidArg> {
+        let magnitude = self.magnitude();
+        if magnitude == 0.0 || !magnitude.is_finite() {
+            return Err(UtilsInvalidArg);
+        }
+        Ok(self.scale(1.0 / magnitude))
+    }
     #[cfg(feature = "data")]
     pub fn to_data_struct(self, key: &str) -> Data {
         let data_struct = Struct {
@@ -63,6 +99,38 @@ impl Vector3 {
     }
 }
 
+impl Add for Vector3 {
+    type Output = Vector3;
+    fn add(self, rhs: Vector3) -> Vector3 {
+        Vector3 {
+            x: self.x + rhs.x,
+            y: self.y + rhs.y,
+            z: self.z + rhs.z,
+        }
+    }
+}
+
+impl Sub for Vector3 {
+    type Output = Vector3;
+    fn sub(self, rhs: Vector3) -> Vector3 {
+        self + (-rhs)
+    }
+}
+
+impl Mul<f64> for Vector3 {
+    type Output = Vector3;
+    fn mul(self, rhs: f64) -> Vector3 {
+        self.scale(rhs)
+    }
+}
+
+impl Neg for Vector3 {
+    type Output = Vector3;
+    fn neg(self) -> Vector3 {
+        self.scale(-1.0)
+    }
+}
+
 impl From<Vector3> for common::v1::Vector3 {
     fn from(vector: Vector3) -> Self {
         common::v1::Vector3 {
@@ -101,6 +169,129 @@ impl From<Vector3> for Value {
     }
 }
 
+/// Euler angles in radians following the aerospace convention: the rotation is applied as
+/// yaw around z, then pitch around the new y, then roll around the new x
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct EulerAngles {
+    pub roll: f64,
+    pub pitch: f64,
+    pub yaw: f64,
+}
+
+/// Rotation represented as a quaternion `w + xi + yj + zk`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Quaternion {
+    pub w: f64,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Default for Quaternion {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl Quaternion {
+    pub fn new(w: f64, x: f64, y: f64, z: f64) -> Self {
+        Quaternion { w, x, y, z }
+    }
+    pub fn identity() -> Self {
+        Quaternion::new(1.0, 0.0, 0.0, 0.0)
+    }
+    // Rotation of `angle` radians around `axis`, the axis doesn't need to be normalized
+    pub fn from_axis_angle(axis: Vector3, angle: f64) -> Result<Self, UtilsInvalidArg> {
+        let axis = axis.normalize()?;
+        let (sin, cos) = (angle / 2.0).sin_cos();
+        Ok(Quaternion::new(
+            cos,
+            axis.x * sin,
+            axis.y * sin,
+            axis.z * sin,
+        ))
+    }
+    pub fn from_euler(angles: EulerAngles) -> Self {
+        let (sr, cr) = (angles.roll / 2.0).sin_cos();
+        let (sp, cp) = (angles.pitch / 2.0).sin_cos();
+        let (sy, cy) = (angles.yaw / 2.0).sin_cos();
+        Quaternion {
+            w: cr * cp * cy + sr * sp * sy,
+            x: sr * cp * cy - cr * sp * sy,
+            y: cr * sp * cy + sr * cp * sy,
+            z: cr * cp * sy - sr * sp * cy,
+        }
+    }
+    // At a pitch of +/-90 degrees (gimbal lock) roll and yaw rotate around the same axis,
+    // in that case roll is reported as 0 and the whole rotation is attributed to yaw
+    pub fn to_euler(self) -> EulerAngles {
+        let q = self.normalize().unwrap_or_default();
+        let sin_pitch = 2.0 * (q.w * q.y - q.z * q.x);
+        if sin_pitch.abs() >= 1.0 - 1e-12 {
+            let pitch = std::f64::consts::FRAC_PI_2.copysign(sin_pitch);
+            let yaw = -sin_pitch.signum() * 2.0 * q.x.atan2(q.w);
+            return EulerAngles {
+                roll: 0.0,
+                pitch,
+                yaw: wrap_angle(yaw),
+            };
+        }
+        EulerAngles {
+            roll: (2.0 * (q.w * q.x + q.y * q.z)).atan2(1.0 - 2.0 * (q.x * q.x + q.y * q.y)),
+            pitch: sin_pitch.asin(),
+            yaw: (2.0 * (q.w * q.z + q.x * q.y)).atan2(1.0 - 2.0 * (q.y * q.y + q.z * q.z)),
+        }
+    }
+    pub fn norm(self) -> f64 {
+        (self.w * self.w + self.x * self.x + self.y * self.y + self.z * self.z).sqrt()
+    }
+    pub fn normalize(self) -> Result<Self, UtilsInvalidArg> {
+        let norm = self.norm();
+        if norm == 0.0 || !norm.is_finite() {
+            return Err(UtilsInvalidArg);
+        }
+        Ok(Quaternion::new(
+            self.w / norm,
+            self.x / norm,
+            self.y / norm,
+            self.z / norm,
+        ))
+    }
+    pub fn conjugate(self) -> Self {
+        Quaternion::new(self.w, -self.x, -self.y, -self.z)
+    }
+    // Rotate `vector` by this quaternion, which is expected to be normalized
+    pub fn rotate(self, vector: Vector3) -> Vector3 {
+        let axis = Vector3::from_xyz(self.x, self.y, self.z);
+        let t = axis.cross(vector) * 2.0;
+        vector + t * self.w + axis.cross(t)
+    }
+}
+
+// Hamilton product, `a * b` applies the rotation `b` then `a`
+impl Mul for Quaternion {
+    type Output = Quaternion;
+    fn mul(self, rhs: Quaternion) -> Quaternion {
+        Quaternion {
+            w: self.w * rhs.w - self.x * rhs.x - self.y * rhs.y - self.z * rhs.z,
+            x: self.w * rhs.x + self.x * rhs.w + self.y * rhs.z - self.z * rhs.y,
+            y: self.w * rhs.y - self.x * rhs.z + self.y * rhs.w + self.z * rhs.x,
+            z: self.w * rhs.z + self.x * rhs.y - self.y * rhs.x + self.z * rhs.w,
+        }
+    }
+}
+
+// wraps an angle in radians to (-pi, pi]
+fn wrap_angle(angle: f64) -> f64 {
+    use std::f64::consts::PI;
+    let wrapped = (angle + PI).rem_euclid(2.0 * PI) - PI;
+    if wrapped == -PI {
+        PI
+    } else {
+        wrapped
+    }
+}
+
 // If revolutions is 0, the returned wait duration will be 0 representing that
 // the motor should run indefinitely.
 pub(crate) fn go_for_math(
@@ -114,7 +305,7 @@ pub(crate) fn go_for_math(
     waitDur := time.Duration(math.Abs(revolutions/rpm)*60*1000) * time.Millisecond
     return powerPct, waitDur
         */
-    if max_rpm.is_nan() || rpm.is_nan() || revolutions.is_nan() {
+    if !max_rpm.is_finite() || !rpm.is_finite() || !revolutions.is_finite() {
         return Err(UtilsInvalidArg);
     }
 
@@ -123,6 +314,10 @@ pub(crate) fn go_for_math(
     if revolutions == 0.0 {
         return Ok((rpm / max_rpm, None));
     }
+    // the motor would never complete the revolutions
+    if rpm == 0.0 {
+        return Err(UtilsInvalidArg);
+    }
 
     let dir = rpm * revolutions / (revolutions * rpm).abs();
     let pct = rpm.abs() / max_rpm * dir;
@@ -148,6 +343,11 @@ mod tests {
         assert!(rpm_nan.is_err());
         let rev_nan = go_for_math(max_rpm, rpm, f64::NAN);
         assert!(rev_nan.is_err());
+        let rpm_inf = go_for_math(100.0, f64::INFINITY, 10.0);
+        assert!(rpm_inf.is_err());
+        // a null speed can't complete any revolution
+        let rpm_zero = go_for_math(100.0, 0.0, 10.0);
+        assert!(rpm_zero.is_err());
     }
 
     #[test_log::test]
@@ -218,4 +418,106 @@ mod tests {
         assert_eq!(pwr, -0.5);
         assert_eq!(dur, Some(Duration::from_secs(30)));
     }
+
+    #[test_log::test]
+    fn test_vector3_ops() {
+        let a = Vector3::from_xyz(1.0, 2.0, 3.0);
+        let b = Vector3::from_xyz(-4.0, 5.0, 0.5);
+
+        assert_eq!(a + b, Vector3::from_xyz(-3.0, 7.0, 3.5));
+        assert_eq!(a - b, Vector3::from_xyz(5.0, -3.0, 2.5));
+        assert_eq!(a * 2.0, Vector3::from_xyz(2.0, 4.0, 6.0));
+        assert_eq!(-a, Vector3::from_xyz(-1.0, -2.0, -3.0));
+        assert_eq!(a.dot(b), 7.5);
+        assert_eq!(a.dot(Vector3::new()), 0.0);
+
+        let x = Vector3::from_xyz(1.0, 0.0, 0.0);
+        let y = Vector3::from_xyz(0.0, 1.0, 0.0);
+        assert_eq!(x.cross(y), Vector3::from_xyz(0.0, 0.0, 1.0));
+        assert_eq!(y.cross(x), Vector3::from_xyz(0.0, 0.0, -1.0));
+        // the cross product is orthogonal to both operands
+        let c = a.cross(b);
+        assert_eq!(c.dot(a), 0.0);
+        assert_eq!(c.dot(b), 0.0);
+
+        assert_eq!(Vector3::from_xyz(3.0, 4.0, 12.0).magnitude(), 13.0);
+        let n = Vector3::from_xyz(0.0, 3.0, 4.0).normalize().unwrap();
+        assert!((n - Vector3::from_xyz(0.0, 0.6, 0.8)).magnitude() < 1e-12);
+        assert!((a.normalize().unwrap().magnitude() - 1.0).abs() < 1e-12);
+        assert!(Vector3::new().normalize().is_err());
+        assert!(Vector3::from_xyz(f64::NAN, 0.0, 0.0).normalize().is_err());
+    }
+
+    fn assert_vector_eq(a: Vector3, b: Vector3) {
+        assert!((a - b).magnitude() < 1e-9, "{:?} != {:?}", a, b);
+    }
+
+    #[test_log::test]
+    fn test_quaternion_rotation() {
+        use std::f64::consts::FRAC_PI_2;
+        let x = Vector3::from_xyz(1.0, 0.0, 0.0);
+        let y = Vector3::from_xyz(0.0, 1.0, 0.0);
+        let z = Vector3::from_xyz(0.0, 0.0, 1.0);
+
+        assert_vector_eq(Quaternion::identity().rotate(x), x);
+        let about_z = Quaternion::from_axis_angle(z, FRAC_PI_2).unwrap();
+        assert_vector_eq(about_z.rotate(x), y);
+        let about_y = Quaternion::from_axis_angle(y * 3.0, FRAC_PI_2).unwrap();
+        assert_vector_eq(about_y.rotate(z), x);
+        assert!(Quaternion::from_axis_angle(Vector3::new(), 1.0).is_err());
+
+        // composition applies the right hand side first
+        assert_vector_eq((about_y * about_z).rotate(x), y);
+        assert_vector_eq((about_z * about_y).rotate(z), y);
+        assert_vector_eq(about_z.conjugate().rotate(about_z.rotate(x)), x);
+
+        let yaw = Quaternion::from_euler(EulerAngles {
+            yaw: FRAC_PI_2,
+            ..Default::default()
+        });
+        assert_vector_eq(yaw.rotate(x), y);
+    }
+
+    #[test_log::test]
+    fn test_euler_round_trip() {
+        for (roll, pitch, yaw) in [
+            (0.0, 0.0, 0.0),
+            (0.1, 0.2, 0.3),
+            (-2.5, 1.2, 3.0),
+            (3.0, -1.5, -0.7),
+            (-0.4, 0.0, -3.1),
+        ] {
+            let angles = EulerAngles { roll, pitch, yaw };
+            let converted = Quaternion::from_euler(angles).to_euler();
+            assert!((converted.roll - roll).abs() < 1e-9, "{:?}", converted);
+            assert!((converted.pitch - pitch).abs() < 1e-9, "{:?}", converted);
+            assert!((converted.yaw - yaw).abs() < 1e-9, "{:?}", converted);
+        }
+    }
+
+    #[test_log::test]
+    fn test_euler_gimbal_lock() {
+        use std::f64::consts::FRAC_PI_2;
+        let vectors = [
+            Vector3::from_xyz(1.0, 0.0, 0.0),
+            Vector3::from_xyz(0.0, 1.0, 0.0),
+            Vector3::from_xyz(0.3, -0.2, 0.9),
+        ];
+        for (roll, pitch, yaw) in [
+            (0.3, FRAC_PI_2, 0.5),
+            (0.3, -FRAC_PI_2, 0.5),
+            (-1.0, FRAC_PI_2, 2.5),
+        ] {
+            let q = Quaternion::from_euler(EulerAngles { roll, pitch, yaw });
+            let converted = q.to_euler();
+            assert_eq!(converted.roll, 0.0);
+            assert!((converted.pitch - pitch).abs() < 1e-9);
+            assert!(converted.yaw.is_finite());
+            // roll and yaw can't be told apart, but the rotation must be the same
+            let q2 = Quaternion::from_euler(converted);
+            for v in vectors {
+                assert_vector_eq(q.rotate(v), q2.rotate(v));
+            }
+        }
+    }
 }
@@ -0,0 +1,267 @@
+//! Process wide counters (connections, gRPC call latencies, data capture) rendered in the
+//! Prometheus text exposition format by [`render_prometheus`]. On native they can be served over
+//! HTTP at `/metrics`, see `ViamServerBuilder::with_metrics_endpoint`. Per resource gRPC call
+//! statistics aren't kept on ESP32.
+
+#[cfg(not(feature = "esp32"))]
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+#[cfg(not(feature = "esp32"))]
+use std::sync::Mutex;
+use std::time::Duration;
+
+static CONNECTIONS_ACTIVE: AtomicU64 = AtomicU64::new(0);
+static CONNECTIONS_ACCEPTED: AtomicU64 = AtomicU64::new(0);
+static CONNECTIONS_REFUSED: AtomicU64 = AtomicU64::new(0);
+static DATA_CAPTURED: AtomicU64 = AtomicU64::new(0);
+static DATA_CAPTURE_ERRORS: AtomicU64 = AtomicU64::new(0);
+static DATA_STORE_ERRORS: AtomicU64 = AtomicU64::new(0);
+// keyed by the resource a call addresses (`rdk:component:motor/left`), `robot` for the robot's own
+// services. Only resources the robot has are recorded (see grpc.rs)
+#[cfg(not(feature = "esp32"))]
+static CALLS: Mutex<BTreeMap<String, CallStats>> = Mutex::new(BTreeMap::new());
+
+#[cfg(not(feature = "esp32"))]
+#[derive(Default)]
+struct CallStats {
+    count: u64,
+    errors: u64,
+    seconds: f64,
+}
+
+// Counts a served connection for as long as it is alive
+pub(crate) struct ConnectionGuard;
+
+impl ConnectionGuard {
+    pub(crate) fn new() -> Self {
+        CONNECTIONS_ACTIVE.fetch_add(1, Ordering::Relaxed);
+        CONNECTIONS_ACCEPTED.fetch_add(1, Ordering::Relaxed);
+        Self
+    }
+}
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        CONNECTIONS_ACTIVE.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Number of connections currently being served
+pub fn active_connections() -> u64 {
+    CONNECTIONS_ACTIVE.load(Ordering::Relaxed)
+}
+
+pub(crate) fn connection_refused() {
+    CONNECTIONS_REFUSED.fetch_add(1, Ordering::Relaxed);
+}
+
+#[cfg(feature = "esp32")]
+pub(crate) fn record_call(_resource: &str, _duration: Duration, _success: bool) {}
+
+#[cfg(not(feature = "esp32"))]
+pub(crate) fn record_call(resource: &str, duration: Duration, success: bool) {
+    let mut calls = CALLS.lock().unwrap();
+    if !calls.contains_key(resource) {
+        calls.insert(resource.to_owned(), CallStats::default());
+    }
+    let stats = calls.get_mut(resource).unwrap();
+    stats.count += 1;
+    stats.seconds += duration.as_secs_f64();
+    if !success {
+        stats.errors += 1;
+    }
+}
+
+pub(crate) fn data_captured() {
+    DATA_CAPTURED.fetch_add(1, Ordering::Relaxed);
+}
+
+pub(crate) fn data_capture_failed() {
+    DATA_CAPTURE_ERRORS.fetch_add(1, Ordering::Relaxed);
+}
+
+pub(crate) fn data_store_failed() {
+    DATA_STORE_ERRORS.fetch_add(1, Ordering::Relaxed);
+}
+
+fn write_metric(out: &mut String, name: &str, kind: &str, help: &str, value: u64) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+    let _ = writeln!(out, "{} {}", name, value);
+}
+
+/// Render all the metrics in the Prometheus text format
+pub fn render_prometheus() -> String {
+    let mut out = String::new();
+    write_metric(
+        &mut out,
+        "micro_rdk_connections_active",
+        "gauge",
+        "Connections currently served.",
+        CONNECTIONS_ACTIVE.load(Ordering::Relaxed),
+    );
+    write_metric(
+        &mut out,
+        "micro_rdk_connections_accepted_total",
+        "counter",
+        "Connections accepted since start.",
+        CONNECTIONS_ACCEPTED.load(Ordering::Relaxed),
+    );
+    write_metric(
+        &mut out,
+        "micro_rdk_connections_refused_total",
+        "counter",
+        "Connections refused because no slot was available.",
+        CONNECTIONS_REFUSED.load(Ordering::Relaxed),
+    );
+    write_metric(
+        &mut out,
+        "micro_rdk_data_captured_total",
+        "counter",
+        "Readings captured and stored by the data manager.",
+        DATA_CAPTURED.load(Ordering::Relaxed),
+    );
+    write_metric(
+        &mut out,
+        "micro_rdk_data_capture_errors_total",
+        "counter",
+        "Readings the data manager failed to capture.",
+        DATA_CAPTURE_ERRORS.load(Ordering::Relaxed),
+    );
+    write_metric(
+        &mut out,
+        "micro_rdk_data_store_errors_total",
+        "counter",
+        "Captured readings the data manager failed to store.",
+        DATA_STORE_ERRORS.load(Ordering::Relaxed),
+    );
+    #[cfg(feature = "esp32")]
+    write_metric(
+        &mut out,
+        "micro_rdk_free_heap_bytes",
+        "gauge",
+        "Free heap.",
+        unsafe {
+            crate::esp32::esp_idf_svc::sys::heap_caps_get_free_size(
+                crate::esp32::esp_idf_svc::sys::MALLOC_CAP_8BIT,
+            )
+        } as u64,
+    );
+    #[cfg(not(feature = "esp32"))]
+    write_call_metrics(&mut out);
+    out
+}
+
+#[cfg(not(feature = "esp32"))]
+fn write_call_metrics(out: &mut String) {
+    let calls = CALLS.lock().unwrap();
+    let _ = writeln!(
+        out,
+        "# HELP micro_rdk_grpc_call_duration_seconds Time spent serving unary gRPC calls."
+    );
+    let _ = writeln!(out, "# TYPE micro_rdk_grpc_call_duration_seconds summary");
+    for (resource, stats) in calls.iter() {
+        let _ = writeln!(
+            out,
+            "micro_rdk_grpc_call_duration_seconds_sum{{resource=\"{}\"}} {}",
+            resource, stats.seconds
+        );
+        let _ = writeln!(
+            out,
+            "micro_rdk_grpc_call_duration_seconds_count{{resource=\"{}\"}} {}",
+            resource, stats.count
+        );
+    }
+    let _ = writeln!(
+        out,
+        "# HELP micro_rdk_grpc_call_errors_total Unary gRPC calls that returned an error."
+    );
+    let _ = writeln!(out, "# TYPE micro_rdk_grpc_call_errors_total counter");
+    for (resource, stats) in calls.iter() {
+        let _ = writeln!(
+            out,
+            "micro_rdk_grpc_call_errors_total{{resource=\"{}\"}} {}",
+            resource, stats.errors
+        );
+    }
+}
+
+// Serve the metrics at `/metrics` to one client at a time, a client has a few seconds to send its
+// request
+#[cfg(feature = "native")]
+pub(crate) async fn serve_metrics(listener: async_io::Async<std::net::TcpListener>) {
+    use futures_lite::{AsyncReadExt, AsyncWriteExt, FutureExt};
+
+    loop {
+        let mut stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(e) => {
+                log::error!("metrics endpoint failed to accept connection {:?}", e);
+                continue;
+            }
+        };
+        let served = async {
+            let mut request = Vec::new();
+            let mut buf = [0_u8; 512];
+            while !request.windows(4).any(|w| w == b"\r\n\r\n") && request.len() < 4096 {
+                let read = stream.read(&mut buf).await?;
+                if read == 0 {
+                    break;
+                }
+                request.extend_from_slice(&buf[..read]);
+            }
+            let response = if request.starts_with(b"GET /metrics ") {
+                let body = render_prometheus();
+                format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                )
+            } else {
+                "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                    .to_owned()
+            };
+            stream.write_all(response.as_bytes()).await?;
+            stream.flush().await
+        }
+        .or(async {
+            async_io::Timer::after(Duration::from_secs(5)).await;
+            Err(std::io::ErrorKind::TimedOut.into())
+        })
+        .await;
+        if let Err(e) = served {
+            log::debug!("metrics endpoint failed to serve request {:?}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_log::test]
+    fn test_render_prometheus() {
+        record_call(
+            "rdk:component:test/metrics",
+            Duration::from_millis(500),
+            true,
+        );
+        record_call(
+            "rdk:component:test/metrics",
+            Duration::from_millis(500),
+            false,
+        );
+        let rendered = render_prometheus();
+        assert!(rendered.contains("# TYPE micro_rdk_connections_active gauge"));
+        assert!(rendered.contains(
+            "micro_rdk_grpc_call_duration_seconds_count{resource=\"rdk:component:test/metrics\"} 2"
+        ));
+        assert!(rendered.contains(
+            "micro_rdk_grpc_call_duration_seconds_sum{resource=\"rdk:component:test/metrics\"} 1"
+        ));
+        assert!(rendered.contains(
+            "micro_rdk_grpc_call_errors_total{resource=\"rdk:component:test/metrics\"} 1"
+        ));
+    }
+}
@@ -22,9 +22,16 @@
 //!
 //! General Purpose Drivers
 //! - [adxl345]
+//! - [bme280]
+//! - [bno055]
 //! - [gpio_motor]
+//! - [i2c_passthrough]
 //! - [ina]
 //! - [mpu6050]
+//! - [pulse_rate]
+//! - [ssd1306]
+//! - [stepper_motor]
+//! - [veml7700]
 
 pub mod actuator;
 #[cfg(feature = "builtin-components")]
@@ -32,9 +39,17 @@ pub mod adxl345;
 pub mod analog;
 pub mod app_client;
 pub mod base;
+pub mod blocking;
+#[cfg(feature = "builtin-components")]
+pub mod bme280;
+#[cfg(feature = "builtin-components")]
+pub mod bno055;
 pub mod board;
+pub mod build_info;
 #[cfg(feature = "camera")]
 pub mod camera;
+#[cfg(feature = "builtin-components")]
+pub mod composite_sensor;
 pub mod config;
 pub mod config_monitor;
 pub mod credentials_storage;
@@ -43,6 +58,8 @@ pub mod encoder;
 pub mod exec;
 pub mod generic;
 #[cfg(feature = "builtin-components")]
+pub mod geofence;
+#[cfg(feature = "builtin-components")]
 pub mod gpio_motor;
 #[cfg(feature = "builtin-components")]
 pub mod gpio_servo;
@@ -50,9 +67,12 @@ pub mod grpc;
 pub mod grpc_client;
 pub mod i2c;
 #[cfg(feature = "builtin-components")]
+pub mod i2c_passthrough;
+#[cfg(feature = "builtin-components")]
 pub mod ina;
 pub mod log;
 pub mod math_utils;
+pub mod metrics;
 pub mod motor;
 pub mod movement_sensor;
 #[cfg(feature = "builtin-components")]
@@ -60,13 +80,24 @@ pub mod mpu6050;
 #[cfg(feature = "ota")]
 pub mod ota;
 pub mod power_sensor;
+#[cfg(feature = "builtin-components")]
+pub mod pulse_rate;
 pub mod registry;
 pub mod restart_monitor;
 pub mod robot;
 pub mod sensor;
 pub mod servo;
+#[cfg(feature = "builtin-components")]
+pub mod ssd1306;
+pub mod startup_report;
 pub mod status;
 #[cfg(feature = "builtin-components")]
+pub mod stepper_motor;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_utils;
+#[cfg(feature = "builtin-components")]
+pub mod veml7700;
+#[cfg(feature = "builtin-components")]
 pub mod wheeled_base;
 pub mod webrtc {
     pub mod api;
@@ -94,5 +125,6 @@ pub mod data_collector;
 pub mod data_manager;
 #[cfg(feature = "data")]
 pub mod data_store;
+pub mod diagnostics;
 
 pub mod provisioning;
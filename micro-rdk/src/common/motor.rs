@@ -92,16 +92,32 @@ impl From<MotorSupportedProperties> for GetPropertiesResponse {
     }
 }
 
+/// Fault condition signalled by a motor driver
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MotorFaultStatus {
+    /// Whether the driver reports a fault (e.g. over-current, over-temperature or under-voltage)
+    pub fault: bool,
+}
+
 pub trait Motor: Status + Actuator + DoCommand {
     /// Sets the percentage of the motor's total power that should be employed.
     /// expressed a value between `-1.0` and `1.0` where negative values indicate a backwards
     /// direction and positive values a forward direction.
     fn set_power(&mut self, pct: f64) -> Result<(), MotorError>;
 
-    /// Reports the position of the robot's motor relative to its zero position.
+    /// Reports the position of the robot's motor relative to its zero position, in whole
+    /// revolutions (rounded toward zero).
     /// This method will return an error if position reporting is not supported.
     fn get_position(&mut self) -> Result<i32, MotorError>;
 
+    /// Reports the position of the robot's motor relative to its zero position, in revolutions
+    /// including the fraction of the current one. The default implementation reports the whole
+    /// revolutions of [`Motor::get_position`].
+    /// This method will return an error if position reporting is not supported.
+    fn get_position_revolutions(&mut self) -> Result<f64, MotorError> {
+        self.get_position().map(f64::from)
+    }
+
     /// Instructs the motor to turn at a specified speed, which is expressed in RPM,
     /// for a specified number of rotations relative to its starting position.
     /// This method will return an error if position reporting is not supported.
@@ -117,9 +133,26 @@ pub trait Motor: Status + Actuator + DoCommand {
         Ok(())
     }
 
+    /// Instructs the motor to turn at a specified speed, which is expressed in RPM, to the
+    /// position (in revolutions) `position_revolutions`.
+    /// This method will return an error if position reporting is not supported.
+    fn go_to(
+        &mut self,
+        _rpm: f64,
+        _position_revolutions: f64,
+    ) -> Result<Option<Duration>, MotorError> {
+        Err(MotorError::MotorMethodUnimplemented("go_to"))
+    }
+
     /// Returns an instance of MotorSupportedProperties indicating the optional properties
     /// supported by this motor
     fn get_properties(&mut self) -> MotorSupportedProperties;
+
+    /// Reports whether the motor's driver is signalling a fault.
+    /// This method will return an error if fault reporting is not supported.
+    fn get_fault_status(&mut self) -> Result<MotorFaultStatus, MotorError> {
+        Err(MotorError::MotorMethodUnimplemented("get_fault_status"))
+    }
 }
 
 pub type MotorType = Arc<Mutex<dyn Motor>>;
@@ -153,7 +186,7 @@ impl MotorPinsConfig {
 }
 
 #[cfg(feature = "builtin-components")]
-#[derive(DoCommand)]
+#[derive(DoCommand, Clone)]
 pub struct FakeMotor {
     pos: f64,
     power: f64,
@@ -252,6 +285,9 @@ where
     fn get_position(&mut self) -> Result<i32, MotorError> {
         self.get_mut().unwrap().get_position()
     }
+    fn get_position_revolutions(&mut self) -> Result<f64, MotorError> {
+        self.get_mut().unwrap().get_position_revolutions()
+    }
     fn set_power(&mut self, pct: f64) -> Result<(), MotorError> {
         self.get_mut().unwrap().set_power(pct)
     }
@@ -261,9 +297,19 @@ where
     fn set_rpm(&mut self, rpm: f64) -> Result<(), MotorError> {
         self.get_mut().unwrap().set_rpm(rpm)
     }
+    fn go_to(
+        &mut self,
+        rpm: f64,
+        position_revolutions: f64,
+    ) -> Result<Option<Duration>, MotorError> {
+        self.get_mut().unwrap().go_to(rpm, position_revolutions)
+    }
     fn get_properties(&mut self) -> MotorSupportedProperties {
         self.get_mut().unwrap().get_properties()
     }
+    fn get_fault_status(&mut self) -> Result<MotorFaultStatus, MotorError> {
+        self.get_mut().unwrap().get_fault_status()
+    }
 }
 
 impl<A> Motor for Arc<Mutex<A>>
@@ -273,6 +319,9 @@ where
     fn get_position(&mut self) -> Result<i32, MotorError> {
         self.lock().unwrap().get_position()
     }
+    fn get_position_revolutions(&mut self) -> Result<f64, MotorError> {
+        self.lock().unwrap().get_position_revolutions()
+    }
     fn set_power(&mut self, pct: f64) -> Result<(), MotorError> {
         self.lock().unwrap().set_power(pct)
     }
@@ -282,9 +331,19 @@ where
     fn set_rpm(&mut self, rpm: f64) -> Result<(), MotorError> {
         self.lock().unwrap().set_rpm(rpm)
     }
+    fn go_to(
+        &mut self,
+        rpm: f64,
+        position_revolutions: f64,
+    ) -> Result<Option<Duration>, MotorError> {
+        self.lock().unwrap().go_to(rpm, position_revolutions)
+    }
     fn get_properties(&mut self) -> MotorSupportedProperties {
         self.lock().unwrap().get_properties()
     }
+    fn get_fault_status(&mut self) -> Result<MotorFaultStatus, MotorError> {
+        self.lock().unwrap().get_fault_status()
+    }
 }
 
 #[cfg(feature = "builtin-components")]
@@ -292,6 +351,9 @@ impl Motor for FakeMotor {
     fn get_position(&mut self) -> Result<i32, MotorError> {
         Ok(self.pos as i32)
     }
+    fn get_position_revolutions(&mut self) -> Result<f64, MotorError> {
+        Ok(self.pos)
+    }
     fn set_power(&mut self, pct: f64) -> Result<(), MotorError> {
         log::debug!("setting power to {}", pct);
         self.power = pct;
@@ -391,9 +453,13 @@ impl FakeMotorWithDependency {
 #[cfg(feature = "builtin-components")]
 impl Motor for FakeMotorWithDependency {
     fn get_position(&mut self) -> Result<i32, MotorError> {
+        Ok(self.get_position_revolutions()? as i32)
+    }
+    /// The angle of the encoder converted to revolutions
+    fn get_position_revolutions(&mut self) -> Result<f64, MotorError> {
         match &self.encoder {
-            Some(enc) => Ok(enc.get_position(EncoderPositionType::DEGREES)?.value as i32),
-            None => Ok(0),
+            Some(enc) => Ok(enc.get_position(EncoderPositionType::DEGREES)?.value as f64 / 360.0),
+            None => Ok(0.0),
         }
     }
     fn set_power(&mut self, pct: f64) -> Result<(), MotorError> {
@@ -435,8 +501,9 @@ impl Actuator for FakeMotorWithDependency {
 mod tests {
     use std::collections::HashMap;
 
+    use super::*;
     use crate::common::config::{Component, DynamicComponentConfig, Kind};
-    use crate::common::motor::{ConfigType, FakeMotor, MotorPinType, MotorPinsConfig};
+    use crate::common::test_utils::delegation_test;
     #[test_log::test]
     fn test_motor_config() {
         let robot_config: [Option<DynamicComponentConfig>; 1] = [Some(DynamicComponentConfig {
@@ -591,4 +658,58 @@ mod tests {
         assert!(motor_type_4.is_ok());
         assert!(matches!(motor_type_4.unwrap(), MotorPinType::AB));
     }
+
+    delegation_test!(
+        test_motor_delegation,
+        RecordingMotor as dyn Motor,
+        wrappers: [Mutex::new, |motor| Arc::new(Mutex::new(motor))],
+        impl Motor {
+            fn set_power(&mut self, _: f64) -> Result<(), MotorError> {
+                Ok(())
+            }
+            fn get_position(&mut self) -> Result<i32, MotorError> {
+                Ok(0)
+            }
+            fn get_position_revolutions(&mut self) -> Result<f64, MotorError> {
+                Ok(0.0)
+            }
+            fn go_for(&mut self, _: f64, _: f64) -> Result<Option<Duration>, MotorError> {
+                Ok(None)
+            }
+            fn set_rpm(&mut self, _: f64) -> Result<(), MotorError> {
+                Ok(())
+            }
+            fn go_to(&mut self, _: f64, _: f64) -> Result<Option<Duration>, MotorError> {
+                Ok(None)
+            }
+            fn get_properties(&mut self) -> MotorSupportedProperties {
+                MotorSupportedProperties {
+                    position_reporting: false,
+                }
+            }
+            fn get_fault_status(&mut self) -> Result<MotorFaultStatus, MotorError> {
+                Ok(MotorFaultStatus::default())
+            }
+        }
+        impl Actuator {
+            fn is_moving(&mut self) -> Result<bool, ActuatorError> {
+                Ok(false)
+            }
+            fn stop(&mut self) -> Result<(), ActuatorError> {
+                Ok(())
+            }
+        }
+        calls: |motor| {
+            motor.set_power(0.5).unwrap();
+            motor.get_position().unwrap();
+            motor.get_position_revolutions().unwrap();
+            motor.go_for(10.0, 1.0).unwrap();
+            motor.set_rpm(10.0).unwrap();
+            motor.go_to(10.0, 1.0).unwrap();
+            motor.get_properties();
+            motor.get_fault_status().unwrap();
+            motor.is_moving().unwrap();
+            motor.stop().unwrap();
+        }
+    );
 }
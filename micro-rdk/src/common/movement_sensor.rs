@@ -62,6 +62,20 @@ pub struct GeoPosition {
     pub alt: f32,
 }
 
+/// Mean Earth radius in meters
+pub const EARTH_RADIUS_M: f64 = 6_371_000.0;
+
+impl GeoPosition {
+    /// Great-circle distance in meters to `other` (haversine formula), altitude is ignored
+    pub fn distance_to(&self, other: &GeoPosition) -> f64 {
+        let (lat1, lat2) = (self.lat.to_radians(), other.lat.to_radians());
+        let d_lat = lat2 - lat1;
+        let d_lon = (other.lon - self.lon).to_radians();
+        let a = (d_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (d_lon / 2.0).sin().powi(2);
+        2.0 * EARTH_RADIUS_M * a.sqrt().asin()
+    }
+}
+
 impl From<GeoPosition> for Value {
     fn from(value: GeoPosition) -> Self {
         let mut fields = HashMap::new();
@@ -113,45 +127,238 @@ pub trait MovementSensor: Status + Readings + DoCommand {
     fn get_linear_acceleration(&mut self) -> Result<Vector3, SensorError>;
     fn get_compass_heading(&mut self) -> Result<f64, SensorError>;
     fn get_properties(&self) -> MovementSensorSupportedMethods;
+    /// The readings of the supported methods, ordered as [`MOVEMENT_SENSOR_READINGS_ORDER`]
+    fn get_ordered_readings(&mut self) -> Result<Vec<(String, Value)>, SensorError> {
+        get_movement_sensor_ordered_readings(self)
+    }
 }
 
 pub type MovementSensorType = Arc<Mutex<dyn MovementSensor>>;
 
+/// Keys of the generic readings of a movement sensor, in the order
+/// [`MovementSensor::get_ordered_readings`] returns them
+pub const MOVEMENT_SENSOR_READINGS_ORDER: [&str; 5] = [
+    "position",
+    "linear_velocity",
+    "linear_acceleration",
+    "angular_velocity",
+    "compass_heading",
+];
+
+/// The readings of the methods `ms` supports, ordered as [`MOVEMENT_SENSOR_READINGS_ORDER`] so
+/// consumers needing a stable order (diffing, CSV export) don't depend on the map ordering.
+/// Unsupported methods are left out, as are those returning
+/// [`SensorError::SensorMethodUnimplemented`] despite being reported as supported
+pub fn get_movement_sensor_ordered_readings<M: MovementSensor + ?Sized>(
+    ms: &mut M,
+) -> Result<Vec<(String, Value)>, SensorError> {
+    let supported_methods = ms.get_properties();
+    let mut res = Vec::with_capacity(MOVEMENT_SENSOR_READINGS_ORDER.len());
+    for key in MOVEMENT_SENSOR_READINGS_ORDER {
+        let value = match key {
+            "position" if supported_methods.position_supported => {
+                ms.get_position().map(Value::from)
+            }
+            "linear_velocity" if supported_methods.linear_velocity_supported => {
+                ms.get_linear_velocity().map(Value::from)
+            }
+            "linear_acceleration" if supported_methods.linear_acceleration_supported => {
+                ms.get_linear_acceleration().map(Value::from)
+            }
+            "angular_velocity" if supported_methods.angular_velocity_supported => {
+                ms.get_angular_velocity().map(Value::from)
+            }
+            "compass_heading" if supported_methods.compass_heading_supported => {
+                ms.get_compass_heading().map(|heading| Value {
+                    kind: Some(Kind::NumberValue(heading)),
+                })
+            }
+            _ => continue,
+        };
+        match value {
+            Ok(value) => res.push((key.to_string(), value)),
+            Err(SensorError::SensorMethodUnimplemented(method)) => {
+                log::debug!("{} is reported as supported but unimplemented", method)
+            }
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(res)
+}
+
 pub fn get_movement_sensor_generic_readings(
     ms: &mut dyn MovementSensor,
 ) -> Result<GenericReadingsResult, SensorError> {
-    let mut res = std::collections::HashMap::new();
-    let supported_methods = ms.get_properties();
-    if supported_methods.position_supported {
-        res.insert("position".to_string(), ms.get_position()?.into());
+    Ok(get_movement_sensor_ordered_readings(ms)?
+        .into_iter()
+        .collect())
+}
+
+/// Per-install corrections of the compass heading of a movement sensor
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct HeadingCorrection {
+    /// Added to the heading measured by the sensor to get the magnetic heading, compensating a
+    /// hard-iron bias or a sensor not mounted facing forward
+    pub offset_deg: f64,
+    /// Positive when the magnetic north is east of the true north
+    pub declination_deg: f64,
+}
+
+impl HeadingCorrection {
+    /// The optional `heading_offset_deg` and `declination_deg` attributes of a movement sensor,
+    /// between -180 and 180 degrees, None when neither is set. `magnetic_declination_deg` is still
+    /// accepted in place of `declination_deg`
+    pub(crate) fn from_config(
+        cfg: &super::config::ConfigType,
+    ) -> Result<Option<Self>, SensorError> {
+        let offset_deg = degrees_from_config(cfg, "heading_offset_deg").map_err(|_| {
+            SensorError::ConfigError(
+                "heading_offset_deg should be a number of degrees between -180 and 180",
+            )
+        })?;
+        let declination_deg = degrees_from_config(cfg, "declination_deg").map_err(|_| {
+            SensorError::ConfigError(
+                "declination_deg should be a number of degrees between -180 and 180",
+            )
+        })?;
+        let legacy_declination_deg =
+            degrees_from_config(cfg, "magnetic_declination_deg").map_err(|_| {
+                SensorError::ConfigError(
+                    "magnetic_declination_deg should be a number of degrees between -180 and 180",
+                )
+            })?;
+        let declination_deg = match (declination_deg, legacy_declination_deg) {
+            (Some(_), Some(_)) => {
+                return Err(SensorError::ConfigError(
+                    "magnetic_declination_deg was replaced by declination_deg, only set the latter",
+                ))
+            }
+            (None, Some(declination_deg)) => {
+                log::warn!("magnetic_declination_deg is deprecated, use declination_deg instead");
+                Some(declination_deg)
+            }
+            (declination_deg, None) => declination_deg,
+        };
+        if offset_deg.is_none() && declination_deg.is_none() {
+            return Ok(None);
+        }
+        Ok(Some(Self {
+            offset_deg: offset_deg.unwrap_or_default(),
+            declination_deg: declination_deg.unwrap_or_default(),
+        }))
     }
-    if supported_methods.linear_velocity_supported {
-        res.insert(
-            "linear_velocity".to_string(),
-            ms.get_linear_velocity()?.into(),
-        );
+
+    /// The magnetic heading corrected for the offset, in [0, 360)
+    pub fn magnetic_heading(&self, measured_deg: f64) -> f64 {
+        (measured_deg + self.offset_deg).rem_euclid(360.0)
     }
-    if supported_methods.linear_acceleration_supported {
-        res.insert(
-            "linear_acceleration".to_string(),
-            ms.get_linear_acceleration()?.into(),
-        );
+
+    /// The true heading, in [0, 360)
+    pub fn true_heading(&self, measured_deg: f64) -> f64 {
+        (self.magnetic_heading(measured_deg) + self.declination_deg).rem_euclid(360.0)
     }
-    if supported_methods.angular_velocity_supported {
-        res.insert(
-            "angular_velocity".to_string(),
-            ms.get_angular_velocity()?.into(),
-        );
+}
+
+// an optional attribute between -180 and 180 degrees
+fn degrees_from_config(cfg: &super::config::ConfigType, key: &str) -> Result<Option<f64>, ()> {
+    use super::config::AttributeError;
+    match cfg.get_attribute::<f64>(key) {
+        Ok(degrees) if (-180.0..=180.0).contains(&degrees) => Ok(Some(degrees)),
+        Err(AttributeError::KeyNotFound(_)) => Ok(None),
+        _ => Err(()),
     }
-    if supported_methods.compass_heading_supported {
-        res.insert(
-            "compass_heading".to_string(),
-            Value {
-                kind: Some(Kind::NumberValue(ms.get_compass_heading()?)),
+}
+
+/// Movement sensor correcting the compass heading of another movement sensor with a
+/// [`HeadingCorrection`] so `get_compass_heading` returns the true heading, the magnetic heading
+/// is still reported by the `magnetic_compass_heading` reading
+pub struct DeclinationCorrectedMovementSensor {
+    sensor: MovementSensorType,
+    correction: HeadingCorrection,
+}
+
+impl DeclinationCorrectedMovementSensor {
+    /// `declination_deg` is positive when the magnetic north is east of the true north
+    pub fn new(sensor: MovementSensorType, declination_deg: f64) -> Self {
+        Self {
+            sensor,
+            correction: HeadingCorrection {
+                offset_deg: 0.0,
+                declination_deg,
             },
-        );
+        }
+    }
+
+    pub fn with_correction(sensor: MovementSensorType, correction: HeadingCorrection) -> Self {
+        Self { sensor, correction }
+    }
+
+    pub fn get_magnetic_heading(&mut self) -> Result<f64, SensorError> {
+        Ok(self
+            .correction
+            .magnetic_heading(self.sensor.get_compass_heading()?))
+    }
+}
+
+impl MovementSensor for DeclinationCorrectedMovementSensor {
+    fn get_position(&mut self) -> Result<GeoPosition, SensorError> {
+        self.sensor.get_position()
+    }
+    fn get_linear_velocity(&mut self) -> Result<Vector3, SensorError> {
+        self.sensor.get_linear_velocity()
+    }
+    fn get_angular_velocity(&mut self) -> Result<Vector3, SensorError> {
+        self.sensor.get_angular_velocity()
+    }
+    fn get_linear_acceleration(&mut self) -> Result<Vector3, SensorError> {
+        self.sensor.get_linear_acceleration()
+    }
+    fn get_compass_heading(&mut self) -> Result<f64, SensorError> {
+        Ok(self
+            .correction
+            .true_heading(self.sensor.get_compass_heading()?))
+    }
+    fn get_properties(&self) -> MovementSensorSupportedMethods {
+        self.sensor.get_properties()
+    }
+}
+
+impl Readings for DeclinationCorrectedMovementSensor {
+    fn get_generic_readings(&mut self) -> Result<GenericReadingsResult, SensorError> {
+        let mut readings = get_movement_sensor_generic_readings(self)?;
+        if self.get_properties().compass_heading_supported {
+            readings.insert(
+                "magnetic_compass_heading".to_string(),
+                Value {
+                    kind: Some(Kind::NumberValue(self.get_magnetic_heading()?)),
+                },
+            );
+        }
+        Ok(readings)
+    }
+}
+
+impl Status for DeclinationCorrectedMovementSensor {
+    fn get_status(
+        &self,
+    ) -> Result<Option<google::protobuf::Struct>, crate::common::status::StatusError> {
+        self.sensor.get_status()
+    }
+}
+
+impl DoCommand for DeclinationCorrectedMovementSensor {
+    fn do_command(
+        &mut self,
+        command_struct: Option<google::protobuf::Struct>,
+    ) -> Result<Option<google::protobuf::Struct>, super::generic::GenericError> {
+        self.sensor.do_command(command_struct)
+    }
+    fn do_command_async(
+        &mut self,
+        command_struct: Option<google::protobuf::Struct>,
+    ) -> super::generic::DoCommandFuture {
+        self.sensor.do_command_async(command_struct)
     }
-    Ok(res)
 }
 
 #[cfg(feature = "builtin-components")]
@@ -294,6 +501,10 @@ where
     fn get_properties(&self) -> MovementSensorSupportedMethods {
         self.lock().unwrap().get_properties()
     }
+
+    fn get_ordered_readings(&mut self) -> Result<Vec<(String, Value)>, SensorError> {
+        self.get_mut().unwrap().get_ordered_readings()
+    }
 }
 
 impl<A> MovementSensor for Arc<Mutex<A>>
@@ -323,4 +534,288 @@ where
     fn get_properties(&self) -> MovementSensorSupportedMethods {
         self.lock().unwrap().get_properties()
     }
+
+    fn get_ordered_readings(&mut self) -> Result<Vec<(String, Value)>, SensorError> {
+        self.lock().unwrap().get_ordered_readings()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::config::{ConfigType, DynamicComponentConfig, Kind as ConfigKind};
+    use crate::common::test_utils::delegation_test;
+
+    delegation_test!(
+        test_movement_sensor_delegation,
+        RecordingMovementSensor as dyn MovementSensor,
+        wrappers: [Mutex::new, |sensor| Arc::new(Mutex::new(sensor))],
+        impl MovementSensor {
+            fn get_position(&mut self) -> Result<GeoPosition, SensorError> {
+                Ok(Default::default())
+            }
+            fn get_linear_velocity(&mut self) -> Result<Vector3, SensorError> {
+                Ok(Default::default())
+            }
+            fn get_angular_velocity(&mut self) -> Result<Vector3, SensorError> {
+                Ok(Default::default())
+            }
+            fn get_linear_acceleration(&mut self) -> Result<Vector3, SensorError> {
+                Ok(Default::default())
+            }
+            fn get_compass_heading(&mut self) -> Result<f64, SensorError> {
+                Ok(0.0)
+            }
+            fn get_properties(&self) -> MovementSensorSupportedMethods {
+                MovementSensorSupportedMethods {
+                    position_supported: false,
+                    linear_velocity_supported: false,
+                    angular_velocity_supported: false,
+                    linear_acceleration_supported: false,
+                    compass_heading_supported: false,
+                }
+            }
+            fn get_ordered_readings(&mut self) -> Result<Vec<(String, Value)>, SensorError> {
+                Ok(vec![])
+            }
+        }
+        impl Readings {
+            fn get_generic_readings(&mut self) -> Result<GenericReadingsResult, SensorError> {
+                Ok(HashMap::new())
+            }
+            fn get_timestamped_readings(&mut self) -> Result<GenericReadingsResult, SensorError> {
+                Ok(HashMap::new())
+            }
+            #[cfg(feature = "data")]
+            fn get_readings_data(
+                &mut self,
+            ) -> Result<crate::proto::app::data_sync::v1::SensorData, SensorError> {
+                Ok(Default::default())
+            }
+        }
+        calls: |sensor| {
+            sensor.get_position().unwrap();
+            sensor.get_linear_velocity().unwrap();
+            sensor.get_angular_velocity().unwrap();
+            sensor.get_linear_acceleration().unwrap();
+            sensor.get_compass_heading().unwrap();
+            sensor.get_properties();
+            sensor.get_ordered_readings().unwrap();
+            sensor.get_generic_readings().unwrap();
+            sensor.get_timestamped_readings().unwrap();
+            #[cfg(feature = "data")]
+            sensor.get_readings_data().unwrap();
+        }
+    );
+
+    #[derive(DoCommand, MovementSensorReadings)]
+    struct AllMethodsMovementSensor;
+
+    impl MovementSensor for AllMethodsMovementSensor {
+        fn get_position(&mut self) -> Result<GeoPosition, SensorError> {
+            Ok(Default::default())
+        }
+        fn get_linear_velocity(&mut self) -> Result<Vector3, SensorError> {
+            Ok(Default::default())
+        }
+        fn get_angular_velocity(&mut self) -> Result<Vector3, SensorError> {
+            Ok(Default::default())
+        }
+        fn get_linear_acceleration(&mut self) -> Result<Vector3, SensorError> {
+            Ok(Default::default())
+        }
+        fn get_compass_heading(&mut self) -> Result<f64, SensorError> {
+            Ok(90.0)
+        }
+        fn get_properties(&self) -> MovementSensorSupportedMethods {
+            MovementSensorSupportedMethods {
+                position_supported: true,
+                linear_velocity_supported: true,
+                angular_velocity_supported: true,
+                linear_acceleration_supported: true,
+                compass_heading_supported: true,
+            }
+        }
+    }
+
+    impl Status for AllMethodsMovementSensor {
+        fn get_status(&self) -> Result<Option<Struct>, crate::common::status::StatusError> {
+            Ok(None)
+        }
+    }
+
+    #[test_log::test]
+    fn test_movement_sensor_readings_order() {
+        let keys = |readings: Vec<(String, Value)>| {
+            readings.into_iter().map(|(key, _)| key).collect::<Vec<_>>()
+        };
+        let readings = get_movement_sensor_ordered_readings(&mut AllMethodsMovementSensor).unwrap();
+        assert_eq!(keys(readings), MOVEMENT_SENSOR_READINGS_ORDER);
+
+        let mut sensor: MovementSensorType = Arc::new(Mutex::new(AllMethodsMovementSensor));
+        let readings = sensor.get_ordered_readings().unwrap();
+        assert_eq!(keys(readings), MOVEMENT_SENSOR_READINGS_ORDER);
+
+        let readings =
+            get_movement_sensor_ordered_readings(&mut FakeMovementSensor::new()).unwrap();
+        assert_eq!(keys(readings), ["position", "linear_acceleration"]);
+
+        let readings = AllMethodsMovementSensor.get_generic_readings().unwrap();
+        assert_eq!(readings.len(), MOVEMENT_SENSOR_READINGS_ORDER.len());
+        assert_eq!(
+            readings["compass_heading"].kind,
+            Some(Kind::NumberValue(90.0))
+        );
+    }
+
+    // only the compass heading is implemented, the angular velocity is wrongly reported as
+    // supported and the other methods fail if called
+    #[derive(DoCommand, MovementSensorReadings)]
+    struct CompassOnlyMovementSensor;
+
+    impl MovementSensor for CompassOnlyMovementSensor {
+        fn get_position(&mut self) -> Result<GeoPosition, SensorError> {
+            Err(SensorError::SensorGenericError("no position"))
+        }
+        fn get_linear_velocity(&mut self) -> Result<Vector3, SensorError> {
+            Err(SensorError::SensorGenericError("no linear velocity"))
+        }
+        fn get_angular_velocity(&mut self) -> Result<Vector3, SensorError> {
+            Err(SensorError::SensorMethodUnimplemented(
+                "get_angular_velocity",
+            ))
+        }
+        fn get_linear_acceleration(&mut self) -> Result<Vector3, SensorError> {
+            Err(SensorError::SensorGenericError("no linear acceleration"))
+        }
+        fn get_compass_heading(&mut self) -> Result<f64, SensorError> {
+            Ok(45.0)
+        }
+        fn get_properties(&self) -> MovementSensorSupportedMethods {
+            MovementSensorSupportedMethods {
+                position_supported: false,
+                linear_velocity_supported: false,
+                angular_velocity_supported: true,
+                linear_acceleration_supported: false,
+                compass_heading_supported: true,
+            }
+        }
+    }
+
+    impl Status for CompassOnlyMovementSensor {
+        fn get_status(&self) -> Result<Option<Struct>, crate::common::status::StatusError> {
+            Ok(None)
+        }
+    }
+
+    #[test_log::test]
+    fn test_movement_sensor_readings_skip_unsupported() {
+        let readings = CompassOnlyMovementSensor.get_generic_readings().unwrap();
+        assert_eq!(readings.keys().collect::<Vec<_>>(), vec!["compass_heading"]);
+        assert_eq!(
+            readings["compass_heading"].kind,
+            Some(Kind::NumberValue(45.0))
+        );
+    }
+
+    #[test_log::test]
+    fn test_declination_corrected_heading() {
+        let mut sensor = DeclinationCorrectedMovementSensor::new(
+            Arc::new(Mutex::new(AllMethodsMovementSensor)),
+            -100.0,
+        );
+        assert_eq!(sensor.get_compass_heading().unwrap(), 350.0);
+        assert_eq!(sensor.get_magnetic_heading().unwrap(), 90.0);
+        let readings = sensor.get_generic_readings().unwrap();
+        assert_eq!(
+            readings["compass_heading"].kind,
+            Some(Kind::NumberValue(350.0))
+        );
+        assert_eq!(
+            readings["magnetic_compass_heading"].kind,
+            Some(Kind::NumberValue(90.0))
+        );
+
+        let mut sensor = DeclinationCorrectedMovementSensor::new(
+            Arc::new(Mutex::new(AllMethodsMovementSensor)),
+            12.5,
+        );
+        assert_eq!(sensor.get_compass_heading().unwrap(), 102.5);
+    }
+
+    #[test_log::test]
+    fn test_heading_correction() {
+        let correction = |attributes: Vec<(&str, ConfigKind)>| {
+            let config = DynamicComponentConfig {
+                attributes: Some(
+                    attributes
+                        .into_iter()
+                        .map(|(key, kind)| (key.to_owned(), kind))
+                        .collect(),
+                ),
+                ..Default::default()
+            };
+            HeadingCorrection::from_config(&ConfigType::Dynamic(&config))
+        };
+        assert_eq!(correction(vec![]).unwrap(), None);
+        assert_eq!(
+            correction(vec![("heading_offset_deg", ConfigKind::NumberValue(-30.0))]).unwrap(),
+            Some(HeadingCorrection {
+                offset_deg: -30.0,
+                declination_deg: 0.0
+            })
+        );
+        assert_eq!(
+            correction(vec![("declination_deg", ConfigKind::NumberValue(-8.25))]).unwrap(),
+            Some(HeadingCorrection {
+                offset_deg: 0.0,
+                declination_deg: -8.25
+            })
+        );
+        assert!(correction(vec![("heading_offset_deg", ConfigKind::NumberValue(200.0))]).is_err());
+        assert!(correction(vec![("declination_deg", ConfigKind::NumberValue(190.0))]).is_err());
+        assert!(correction(vec![("declination_deg", ConfigKind::BoolValue(true))]).is_err());
+
+        // configs written before the attribute was renamed keep their declination
+        assert_eq!(
+            correction(vec![(
+                "magnetic_declination_deg",
+                ConfigKind::NumberValue(4.5)
+            )])
+            .unwrap(),
+            Some(HeadingCorrection {
+                offset_deg: 0.0,
+                declination_deg: 4.5
+            })
+        );
+        assert!(correction(vec![
+            ("declination_deg", ConfigKind::NumberValue(4.5)),
+            ("magnetic_declination_deg", ConfigKind::NumberValue(4.5)),
+        ])
+        .is_err());
+        assert!(correction(vec![(
+            "magnetic_declination_deg",
+            ConfigKind::NumberValue(-200.0)
+        )])
+        .is_err());
+
+        // the sensor reads 90°, the offset brings it to 300° and the declination wraps it to 10°
+        let mut sensor = DeclinationCorrectedMovementSensor::with_correction(
+            Arc::new(Mutex::new(AllMethodsMovementSensor)),
+            HeadingCorrection {
+                offset_deg: -150.0,
+                declination_deg: 70.0,
+            },
+        );
+        assert_eq!(sensor.get_magnetic_heading().unwrap(), 300.0);
+        let readings = sensor.get_generic_readings().unwrap();
+        assert_eq!(
+            readings["compass_heading"].kind,
+            Some(Kind::NumberValue(10.0))
+        );
+        assert_eq!(
+            readings["magnetic_compass_heading"].kind,
+            Some(Kind::NumberValue(300.0))
+        );
+    }
 }
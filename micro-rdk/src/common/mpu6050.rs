@@ -14,6 +14,8 @@
 //!   - if AD0 is wired to ground, it uses the default I2C address of 0x68
 //!   - if AD0 is wired to hot, it uses the alternate I2C address of 0x69
 //!
+//! Any other address can be given with the `i2c_address` attribute, for clones of the chip
+//!
 
 use crate::common::i2c::I2cHandleType;
 use crate::common::math_utils::Vector3;
@@ -22,7 +24,7 @@ use crate::google;
 
 use super::board::Board;
 use super::config::ConfigType;
-use super::i2c::I2CHandle;
+use super::i2c::{i2c_address_from_config, I2CHandle};
 use super::movement_sensor::MovementSensorType;
 use super::registry::{get_board_from_dependencies, ComponentRegistry, Dependency};
 use super::sensor::SensorError;
@@ -82,6 +84,10 @@ impl MPU6050 {
                 "MPU6050 missing i2c_bus attribute",
             ));
         };
+        // an explicit `i2c_address` takes precedence over `use_alt_i2c_address`
+        if let Some(i2c_address) = i2c_address_from_config(&cfg)? {
+            return Ok(Arc::new(Mutex::new(MPU6050::new(i2c_handle, i2c_address)?)));
+        }
         if let Ok(use_alt_address) = cfg.get_attribute::<bool>("use_alt_i2c_address") {
             if use_alt_address {
                 return Ok(Arc::new(Mutex::new(MPU6050::new(i2c_handle, 105)?)));
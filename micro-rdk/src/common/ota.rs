@@ -34,26 +34,34 @@
 /// - CONFIG_BOOTLOADER_APP_ROLLBACK_ENABLE=NO
 ///   - after updating the app, bootloader runs a new app with the "ESP_OTA_IMG_PENDING_VERIFY" state set. If the image is not marked as verified, will boot to previous ota slot
 ///
+/// A generic component named after the OTA service (see [`OtaCommands`]) answers
+/// `{"ota_status": true}` and `{"ota_rollback": true}` to check on an update and recover from a
+/// bad one without physical access to the device.
+///
 use crate::{
     common::{
         config::{AttributeError, Kind},
         conn::viam::ViamH2Connector,
         credentials_storage::OtaMetadataStorage,
         exec::Executor,
+        generic::{DoCommand, GenericComponent, GenericError},
         grpc_client::H2Timer,
+        status::{Status, StatusError},
     },
+    google::protobuf::{value, Struct, Value},
     proto::app::v1::ServiceConfig,
 };
 
 #[cfg(feature = "esp32")]
 use crate::esp32::esp_idf_svc::{
-    ota::{EspFirmwareInfoLoader, EspOta},
-    sys::{esp_ota_get_next_update_partition, esp_partition_t},
+    ota::{EspFirmwareInfoLoader, EspOta, SlotState},
+    sys::{esp, esp_ota_get_next_update_partition, esp_ota_set_boot_partition, esp_partition_t},
 };
 use async_io::Timer;
 use http_body_util::{BodyExt, Empty};
 use hyper::{body::Bytes, client::conn::http2, Request};
 use once_cell::sync::Lazy;
+use std::collections::HashMap;
 use std::time::Duration;
 use thiserror::Error;
 #[cfg(not(feature = "esp32"))]
@@ -62,9 +70,12 @@ use {bincode::Decode, futures_lite::AsyncWriteExt};
 const CONN_RETRY_SECS: u64 = 60;
 const SIZEOF_APPDESC: usize = 256;
 const MAX_VER_LEN: usize = 128;
+// leaves time for the response to an `ota_rollback` command to be sent before rebooting
+#[cfg(feature = "esp32")]
+const ROLLBACK_REBOOT_DELAY: Duration = Duration::from_secs(1);
 pub const OTA_MODEL_TYPE: &str = "ota_service";
 pub static OTA_MODEL_TRIPLET: Lazy<String> =
-    Lazy::new(|| format!("rdk:builtin:{}", OTA_MODEL_TYPE));
+    Lazy::new(|| crate::common::robot::OTA_SERVICE_MODEL.to_owned());
 
 /// https://github.com/espressif/esp-idf/blob/ce6085349f8d5a95fc857e28e2d73d73dd3629b5/components/esp_app_format/include/esp_app_desc.h#L42
 /// https://docs.esp-rs.org/esp-idf-sys/esp_idf_sys/struct.esp_app_desc_t.html
@@ -532,3 +543,211 @@ impl<S: OtaMetadataStorage> OtaService<S> {
         Ok(())
     }
 }
+
+/// Generic component, named after the OTA service, answering:
+/// - `{"ota_status": true}` with the version stored by the last update and, on esp32, the
+///   running, boot and update partitions as well as the state of the running partition
+///   (`valid` is false while a new image hasn't been verified yet)
+/// - `{"ota_rollback": true}` by booting from the other OTA partition after a short delay. The
+///   stored version is left unchanged so that the OTA service doesn't download the release that
+///   was rolled back from again
+pub(crate) struct OtaCommands<S: OtaMetadataStorage> {
+    storage: S,
+}
+
+impl<S: OtaMetadataStorage> OtaCommands<S> {
+    pub(crate) fn new(storage: S) -> Self {
+        Self { storage }
+    }
+
+    fn status(&self) -> Result<Struct, OtaError> {
+        let string = |s: &str| Value {
+            kind: Some(value::Kind::StringValue(s.to_string())),
+        };
+        let mut fields = HashMap::new();
+        if self.storage.has_ota_metadata() {
+            let metadata = self
+                .storage
+                .get_ota_metadata()
+                .map_err(|e| OtaError::Other(e.to_string()))?;
+            fields.insert("version".to_string(), string(metadata.version()));
+        }
+        #[cfg(feature = "esp32")]
+        {
+            let ota = EspOta::new().map_err(|e| {
+                OtaError::UpdateError(format!("failed to initiate ota partition handle: {}", e))
+            })?;
+            let slot_err = |e| OtaError::UpdateError(format!("failed to get ota slot: {}", e));
+            let running = ota.get_running_slot().map_err(slot_err)?;
+            let boot = ota.get_boot_slot().map_err(slot_err)?;
+            let update = ota.get_update_slot().map_err(slot_err)?;
+            fields.insert("running_partition".to_string(), string(&running.label));
+            fields.insert("boot_partition".to_string(), string(&boot.label));
+            fields.insert("update_partition".to_string(), string(&update.label));
+            if let Some(firmware) = running.firmware.as_ref() {
+                fields.insert("firmware_version".to_string(), string(&firmware.version));
+            }
+            fields.insert(
+                "running_state".to_string(),
+                string(&format!("{:?}", running.state).to_lowercase()),
+            );
+            fields.insert(
+                "valid".to_string(),
+                Value {
+                    kind: Some(value::Kind::BoolValue(matches!(
+                        running.state,
+                        SlotState::Valid | SlotState::Factory
+                    ))),
+                },
+            );
+        }
+        Ok(Struct { fields })
+    }
+
+    #[cfg(feature = "esp32")]
+    fn rollback(&mut self) -> Result<Struct, OtaError> {
+        let ptr: *const esp_partition_t =
+            unsafe { esp_ota_get_next_update_partition(std::ptr::null()) };
+        if ptr.is_null() {
+            return Err(OtaError::UpdateError(
+                "failed to obtain a handle to the other OTA partition".to_string(),
+            ));
+        }
+        // fails if the partition doesn't hold a valid image
+        esp!(unsafe { esp_ota_set_boot_partition(ptr) })
+            .map_err(|e| OtaError::UpdateError(format!("failed to set boot partition: {}", e)))?;
+        let address = unsafe { (*ptr).address } as usize;
+        log::warn!(
+            "rolling back to firmware at `{:#x}`, rebooting in {:?}",
+            address,
+            ROLLBACK_REBOOT_DELAY
+        );
+        Executor::new()
+            .spawn(async {
+                Timer::after(ROLLBACK_REBOOT_DELAY).await;
+                esp_idf_svc::hal::reset::restart();
+            })
+            .detach();
+        Ok(Struct {
+            fields: HashMap::from([(
+                "ota_rollback".to_string(),
+                Value {
+                    kind: Some(value::Kind::StringValue(format!("{:#x}", address))),
+                },
+            )]),
+        })
+    }
+
+    #[cfg(not(feature = "esp32"))]
+    fn rollback(&mut self) -> Result<Struct, OtaError> {
+        Err(OtaError::Other(
+            "rollback is only supported on esp32".to_string(),
+        ))
+    }
+}
+
+impl<S: OtaMetadataStorage> DoCommand for OtaCommands<S> {
+    fn do_command(
+        &mut self,
+        command_struct: Option<Struct>,
+    ) -> Result<Option<Struct>, GenericError> {
+        let requested = |key: &str| {
+            command_struct
+                .as_ref()
+                .and_then(|cmd| cmd.fields.get(key))
+                .is_some_and(|v| matches!(v.kind, Some(value::Kind::BoolValue(true))))
+        };
+        let res = if requested("ota_status") {
+            self.status()
+        } else if requested("ota_rollback") {
+            self.rollback()
+        } else {
+            return Err(GenericError::MethodUnimplemented("do_command"));
+        };
+        res.map(Some).map_err(|e| GenericError::Other(Box::new(e)))
+    }
+}
+
+impl<S: OtaMetadataStorage> Status for OtaCommands<S> {
+    fn get_status(&self) -> Result<Option<Struct>, StatusError> {
+        Ok(Some(Struct::default()))
+    }
+}
+
+impl<S: OtaMetadataStorage> GenericComponent for OtaCommands<S> {}
+
+#[cfg(test)]
+mod tests {
+    use super::OtaCommands;
+    use crate::common::credentials_storage::{OtaMetadataStorage, RAMStorage};
+    use crate::common::generic::{DoCommand, GenericError};
+    use crate::common::ota::OtaMetadata;
+    use crate::common::status::Status;
+    use crate::google::protobuf::{value::Kind, Struct, Value};
+    use std::collections::HashMap;
+
+    fn command(key: &str, value: bool) -> Option<Struct> {
+        Some(Struct {
+            fields: HashMap::from([(
+                key.to_string(),
+                Value {
+                    kind: Some(Kind::BoolValue(value)),
+                },
+            )]),
+        })
+    }
+
+    #[test_log::test]
+    fn test_ota_status() {
+        let storage = RAMStorage::new();
+        let mut commands = OtaCommands::new(storage.clone());
+        assert_eq!(commands.get_status().unwrap(), Some(Struct::default()));
+
+        // nothing was installed over the air yet
+        let status = commands
+            .do_command(command("ota_status", true))
+            .unwrap()
+            .unwrap();
+        assert!(!status.fields.contains_key("version"));
+
+        storage
+            .store_ota_metadata(OtaMetadata::new("1.2.3".to_string()))
+            .unwrap();
+        let status = commands
+            .do_command(command("ota_status", true))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            status.fields["version"].kind,
+            Some(Kind::StringValue("1.2.3".to_string()))
+        );
+    }
+
+    #[test_log::test]
+    fn test_ota_commands_dispatch() {
+        let storage = RAMStorage::new();
+        storage
+            .store_ota_metadata(OtaMetadata::new("1.2.3".to_string()))
+            .unwrap();
+        let mut commands = OtaCommands::new(storage.clone());
+
+        // rollback needs the esp32 OTA partitions and leaves the stored version alone
+        assert!(matches!(
+            commands.do_command(command("ota_rollback", true)),
+            Err(GenericError::Other(_))
+        ));
+        assert_eq!(storage.get_ota_metadata().unwrap().version(), "1.2.3");
+
+        for unknown in [
+            None,
+            Some(Struct::default()),
+            command("ota_status", false),
+            command("ota_update", true),
+        ] {
+            assert!(matches!(
+                commands.do_command(unknown),
+                Err(GenericError::MethodUnimplemented("do_command"))
+            ));
+        }
+    }
+}
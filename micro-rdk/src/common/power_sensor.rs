@@ -138,3 +138,56 @@ where
         self.lock().unwrap().get_power()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::test_utils::delegation_test;
+    use std::collections::HashMap;
+
+    delegation_test!(
+        test_power_sensor_delegation,
+        RecordingPowerSensor as dyn PowerSensor,
+        wrappers: [Mutex::new, |sensor| Arc::new(Mutex::new(sensor))],
+        impl PowerSensor {
+            fn get_voltage(&mut self) -> Result<Voltage, SensorError> {
+                Ok(Voltage {
+                    volts: 5.0,
+                    power_supply_type: PowerSupplyType::DC,
+                })
+            }
+            fn get_current(&mut self) -> Result<Current, SensorError> {
+                Ok(Current {
+                    amperes: 0.5,
+                    power_supply_type: PowerSupplyType::DC,
+                })
+            }
+            fn get_power(&mut self) -> Result<f64, SensorError> {
+                Ok(2.5)
+            }
+        }
+        impl Readings {
+            fn get_generic_readings(&mut self) -> Result<GenericReadingsResult, SensorError> {
+                Ok(HashMap::new())
+            }
+            fn get_timestamped_readings(&mut self) -> Result<GenericReadingsResult, SensorError> {
+                Ok(HashMap::new())
+            }
+            #[cfg(feature = "data")]
+            fn get_readings_data(
+                &mut self,
+            ) -> Result<crate::proto::app::data_sync::v1::SensorData, SensorError> {
+                Ok(Default::default())
+            }
+        }
+        calls: |sensor| {
+            sensor.get_voltage().unwrap();
+            sensor.get_current().unwrap();
+            sensor.get_power().unwrap();
+            sensor.get_generic_readings().unwrap();
+            sensor.get_timestamped_readings().unwrap();
+            #[cfg(feature = "data")]
+            sensor.get_readings_data().unwrap();
+        }
+    );
+}
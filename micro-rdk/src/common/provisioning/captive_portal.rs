@@ -0,0 +1,156 @@
+//! Captive portal answering the connectivity checks of phones joining the provisioning AP.
+//!
+//! While enabled (see [`WifiApConfiguration::set_captive_portal`]) every DNS query is resolved
+//! to the AP address and any HTTP request made to another host than [`PROVISIONING_HOST`] is
+//! redirected to it, so the phone shows its captive portal sheet pointing at the provisioning
+//! page.
+//!
+//! [`WifiApConfiguration::set_captive_portal`]: super::server::WifiApConfiguration::set_captive_portal
+
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+
+use async_io::{Async, Timer};
+use futures_lite::{AsyncReadExt, AsyncWriteExt, FutureExt};
+
+use super::server::ProvisioningInfo;
+use crate::common::exec::Executor;
+
+/// Host name the provisioning page (and the provisioning gRPC server) is reached at
+pub(crate) const PROVISIONING_HOST: &str = "viam.setup";
+const HTTP_PORT: u16 = 80;
+// requests heads bigger than this are answered without being read further
+const MAX_REQUEST_HEAD_LEN: usize = 1024;
+// how long a client has to send its request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub(crate) async fn serve_captive_portal(exec: Executor, info: ProvisioningInfo) {
+    let listener =
+        match TcpListener::bind(("0.0.0.0", HTTP_PORT)).and_then(Async::<TcpListener>::try_from) {
+            Ok(listener) => listener,
+            Err(e) => {
+                log::error!("couldn't start the captive portal reason {:?}", e);
+                return;
+            }
+        };
+    let page = provisioning_page(&info);
+    loop {
+        let (stream, _) = match listener.accept().await {
+            Ok(incoming) => incoming,
+            Err(e) => {
+                log::error!("captive portal failed to accept a connection {:?}", e);
+                continue;
+            }
+        };
+        let page = page.clone();
+        exec.spawn(async move {
+            if let Err(e) = answer(stream, &page).await {
+                log::debug!("captive portal failed to answer a request {:?}", e);
+            }
+        })
+        .detach();
+    }
+}
+
+async fn answer(mut stream: Async<TcpStream>, page: &str) -> std::io::Result<()> {
+    let mut head = Vec::with_capacity(MAX_REQUEST_HEAD_LEN);
+    let read = async {
+        let mut buf = [0_u8; 256];
+        while head.len() < MAX_REQUEST_HEAD_LEN && !head.windows(4).any(|w| w == b"\r\n\r\n") {
+            let len = stream.read(&mut buf).await?;
+            if len == 0 {
+                break;
+            }
+            head.extend_from_slice(&buf[..len]);
+        }
+        Ok::<_, std::io::Error>(())
+    };
+    read.or(async {
+        Timer::after(REQUEST_TIMEOUT).await;
+        Err(std::io::ErrorKind::TimedOut.into())
+    })
+    .await?;
+    let response = response_for(&String::from_utf8_lossy(&head), page);
+    stream.write_all(response.as_bytes()).await?;
+    stream.flush().await
+}
+
+// The provisioning page when the request was made to `PROVISIONING_HOST`, a redirection to it
+// otherwise
+fn response_for(request_head: &str, page: &str) -> String {
+    let host = request_head
+        .lines()
+        .skip(1)
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("host"))
+        .map(|(_, value)| value.trim());
+    let on_provisioning_host = host.is_some_and(|host| {
+        host.split(':')
+            .next()
+            .is_some_and(|host| host.eq_ignore_ascii_case(PROVISIONING_HOST))
+    });
+    if on_provisioning_host {
+        format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n{}",
+            page.len(),
+            page
+        )
+    } else {
+        format!(
+            "HTTP/1.1 302 Found\r\nLocation: http://{}/\r\nContent-Length: 0\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n",
+            PROVISIONING_HOST
+        )
+    }
+}
+
+fn provisioning_page(info: &ProvisioningInfo) -> String {
+    format!(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><meta name=\"viewport\" content=\"width=device-width, initial-scale=1\"><title>Machine setup</title></head><body><h1>{} {}</h1><p>This machine is waiting to be set up. Open the Viam mobile app and follow the setup instructions, keep this phone connected to this network until setup completes.</p></body></html>",
+        html_escape(info.get_manufacturer()),
+        html_escape(info.get_model())
+    )
+}
+
+fn html_escape(s: &str) -> String {
+    s.chars()
+        .fold(String::with_capacity(s.len()), |mut out, c| {
+            match c {
+                '&' => out.push_str("&amp;"),
+                '<' => out.push_str("&lt;"),
+                '>' => out.push_str("&gt;"),
+                '"' => out.push_str("&quot;"),
+                '\'' => out.push_str("&#39;"),
+                c => out.push(c),
+            }
+            out
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_log::test]
+    fn test_captive_portal_responses() {
+        let mut info = ProvisioningInfo::default();
+        info.set_manufacturer("acme".to_owned());
+        info.set_model("<rover>".to_owned());
+        let page = provisioning_page(&info);
+        assert!(page.contains("acme &lt;rover&gt;"));
+
+        let redirect = response_for(
+            "GET /generate_204 HTTP/1.1\r\nHost: connectivitycheck.gstatic.com\r\n\r\n",
+            &page,
+        );
+        assert!(redirect.starts_with("HTTP/1.1 302"));
+        assert!(redirect.contains("Location: http://viam.setup/\r\n"));
+
+        // requests without a host are redirected too
+        assert!(response_for("GET / HTTP/1.0\r\n\r\n", &page).starts_with("HTTP/1.1 302"));
+
+        let served = response_for("GET / HTTP/1.1\r\nhost: Viam.Setup:80\r\n\r\n", &page);
+        assert!(served.starts_with("HTTP/1.1 200"));
+        assert!(served.ends_with(&page));
+        assert!(served.contains(&format!("Content-Length: {}\r\n", page.len())));
+    }
+}
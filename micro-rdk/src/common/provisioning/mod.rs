@@ -1 +1,2 @@
+pub mod captive_portal;
 pub mod server;
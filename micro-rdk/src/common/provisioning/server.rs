@@ -8,6 +8,7 @@ use std::{
     rc::Rc,
 };
 
+use super::captive_portal::{serve_captive_portal, PROVISIONING_HOST};
 use crate::{
     common::{
         conn::{
@@ -40,7 +41,7 @@ use hyper::{
 use prost::Message;
 use thiserror::Error;
 
-async fn dns_server(ap_ip: Ipv4Addr) {
+async fn dns_server(ap_ip: Ipv4Addr, captive_portal: bool) {
     let socket = async_io::Async::<UdpSocket>::bind(([0, 0, 0, 0], 53)).unwrap();
     loop {
         let mut buf = [0_u8; 512];
@@ -49,10 +50,11 @@ async fn dns_server(ap_ip: Ipv4Addr) {
         let mut ans = dns_message_parser::Dns::decode(buf);
         if let Ok(ref mut msg) = ans {
             if let Some(q) = msg.questions.first() {
-                if q.domain_name.to_string().contains("viam.setup") {
+                if captive_portal || q.domain_name.to_string().contains(PROVISIONING_HOST) {
                     let rr = dns_message_parser::rr::RR::A(dns_message_parser::rr::A {
                         domain_name: q.domain_name.clone(),
-                        ttl: 3600,
+                        // short lived so phones don't keep the AP address once provisioned
+                        ttl: if captive_portal { 60 } else { 3600 },
                         ipv4_addr: ap_ip,
                     });
 
@@ -77,6 +79,7 @@ pub(crate) struct ProvisioningServiceBuilder<Exec> {
     reason: ProvisioningReason,
     last_error: Option<String>,
     wifi_manager: Rc<Option<Box<dyn WifiManager>>>,
+    captive_portal: bool,
     executor: Exec,
 }
 
@@ -88,6 +91,7 @@ impl<Exec> ProvisioningServiceBuilder<Exec> {
             provisioning_info: None,
             reason: ProvisioningReason::Unprovisioned,
             last_error: None,
+            captive_portal: false,
             executor,
         }
     }
@@ -110,6 +114,10 @@ impl<Exec> ProvisioningServiceBuilder<Exec> {
         let _ = self.last_error.insert(error);
         self
     }
+    pub(crate) fn with_captive_portal(mut self, captive_portal: bool) -> Self {
+        self.captive_portal = captive_portal;
+        self
+    }
     pub(crate) fn with_wifi_manager(
         self,
         wifi_manager: Rc<Option<Box<dyn WifiManager>>>,
@@ -120,6 +128,7 @@ impl<Exec> ProvisioningServiceBuilder<Exec> {
             reason: self.reason,
             last_error: self.last_error,
             wifi_manager,
+            captive_portal: self.captive_portal,
             executor: self.executor,
         }
     }
@@ -132,12 +141,11 @@ impl<Exec> ProvisioningServiceBuilder<Exec> {
     {
         // Provisioning relies on DNS query to find the IP of the server. Specifically it will
         // make a request for viam.setup. All other queries are answered failed to express the lack of
-        // internet
-        let dns_task = self
-            .wifi_manager
-            .as_ref()
-            .as_ref()
-            .map(|wifi_manager| self.executor.spawn(dns_server(wifi_manager.get_ap_ip())));
+        // internet, unless the captive portal is enabled
+        let dns_task = self.wifi_manager.as_ref().as_ref().map(|wifi_manager| {
+            self.executor
+                .spawn(dns_server(wifi_manager.get_ap_ip(), self.captive_portal))
+        });
 
         ProvisioningService {
             provisioning_info: Rc::new(self.provisioning_info),
@@ -453,10 +461,32 @@ where
     }
 }
 
+/// Longest SSID allowed by 802.11, in bytes
+pub const MAX_AP_SSID_LEN: usize = 32;
+/// Shortest WPA2 passphrase
+pub const MIN_AP_PASSWORD_LEN: usize = 8;
+/// Longest WPA2 passphrase
+pub const MAX_AP_PASSWORD_LEN: usize = 63;
+
+#[derive(Error, Debug)]
+pub enum WifiApConfigurationError {
+    #[error("provisioning AP SSID has to be 1 to {MAX_AP_SSID_LEN} bytes long, got {0}")]
+    InvalidSsidLength(usize),
+    #[error("provisioning AP password has to be empty or {MIN_AP_PASSWORD_LEN} to {MAX_AP_PASSWORD_LEN} characters long, got {0}")]
+    InvalidPasswordLength(usize),
+    #[error("provisioning AP password can only contain printable ASCII characters")]
+    InvalidPasswordCharacters,
+}
+
+/// Configuration of the SoftAP started while provisioning. The default SSID is
+/// `esp32-micrordk-XXXX` (the end of the MAC address) protected by the `viamsetup` WPA2
+/// password, an empty password starts an open AP. The captive portal is disabled.
+#[derive(Clone, Debug)]
 pub struct WifiApConfiguration {
     pub(crate) ap_ip_addr: Ipv4Addr,
     pub(crate) ssid: String,
     pub(crate) password: String,
+    pub(crate) captive_portal: bool,
 }
 impl Default for WifiApConfiguration {
     fn default() -> Self {
@@ -477,12 +507,11 @@ impl Default for WifiApConfiguration {
 
         let password = "viamsetup".to_string();
 
-        log::info!("Provisioning SSID: {} - Password: {}", ssid, password);
-
         Self {
             ssid,
             password,
             ap_ip_addr: Ipv4Addr::new(10, 42, 0, 1),
+            captive_portal: false,
         }
     }
 }
@@ -499,6 +528,34 @@ impl WifiApConfiguration {
         self.password = password;
         self
     }
+    /// Steer the browser of phones joining the AP to the provisioning page, see
+    /// [`captive_portal`](super::captive_portal). Disabled by default.
+    pub fn set_captive_portal(mut self, captive_portal: bool) -> Self {
+        self.captive_portal = captive_portal;
+        self
+    }
+    /// Check the SSID and password against the Wi-Fi limits
+    pub fn validate(&self) -> Result<(), WifiApConfigurationError> {
+        if self.ssid.is_empty() || self.ssid.len() > MAX_AP_SSID_LEN {
+            return Err(WifiApConfigurationError::InvalidSsidLength(self.ssid.len()));
+        }
+        if self.password.is_empty() {
+            return Ok(());
+        }
+        if !self
+            .password
+            .chars()
+            .all(|c| c.is_ascii() && !c.is_ascii_control())
+        {
+            return Err(WifiApConfigurationError::InvalidPasswordCharacters);
+        }
+        if !(MIN_AP_PASSWORD_LEN..=MAX_AP_PASSWORD_LEN).contains(&self.password.len()) {
+            return Err(WifiApConfigurationError::InvalidPasswordLength(
+                self.password.len(),
+            ));
+        }
+        Ok(())
+    }
 }
 
 #[derive(Error, Debug)]
@@ -584,6 +641,7 @@ pub(crate) async fn serve_provisioning_async<S, M>(
     storage: S,
     last_error: Option<Box<dyn std::error::Error>>,
     wifi_manager: Rc<Option<Box<dyn WifiManager>>>,
+    captive_portal: bool,
     mdns: &RefCell<M>,
 ) -> Result<(), Box<dyn std::error::Error>>
 where
@@ -599,7 +657,14 @@ where
         info.get_manufacturer()
     );
 
-    let srv = ProvisioningServiceBuilder::<_>::new(exec.clone()).with_provisioning_info(info);
+    // the captive portal is only useful to phones joining the provisioning AP
+    let captive_portal = captive_portal && wifi_manager.is_some();
+    let captive_portal_task =
+        captive_portal.then(|| exec.spawn(serve_captive_portal(exec.clone(), info.clone())));
+
+    let srv = ProvisioningServiceBuilder::<_>::new(exec.clone())
+        .with_provisioning_info(info)
+        .with_captive_portal(captive_portal);
     let srv = srv.with_wifi_manager(wifi_manager);
 
     let srv = if let Some(error) = last_error {
@@ -637,6 +702,9 @@ where
     credential_ready.await;
 
     provisioning_server_task.cancel().await;
+    if let Some(task) = captive_portal_task {
+        task.cancel().await;
+    }
     let mut mdns = mdns.borrow_mut();
     if let Err(e) = mdns.remove_service("provisioning", "_rpc", "_tcp") {
         log::error!("provisioning couldn't remove mdns record error {:?}", e);
@@ -681,7 +749,35 @@ mod tests {
     use prost::Message;
     use rand::{distributions::Alphanumeric, Rng};
 
-    use super::ProvisioningService;
+    use super::{ProvisioningService, WifiApConfiguration, WifiApConfigurationError};
+
+    #[test_log::test]
+    fn test_wifi_ap_configuration_validation() {
+        let ap = WifiApConfiguration::default();
+        assert!(ap.validate().is_ok());
+        assert!(ap.clone().set_ap_password("".to_owned()).validate().is_ok());
+        assert!(matches!(
+            ap.clone().set_ap_ssid("".to_owned()).validate(),
+            Err(WifiApConfigurationError::InvalidSsidLength(0))
+        ));
+        assert!(matches!(
+            ap.clone().set_ap_ssid("a".repeat(33)).validate(),
+            Err(WifiApConfigurationError::InvalidSsidLength(33))
+        ));
+        assert!(ap.clone().set_ap_ssid("a".repeat(32)).validate().is_ok());
+        assert!(matches!(
+            ap.clone().set_ap_password("short".to_owned()).validate(),
+            Err(WifiApConfigurationError::InvalidPasswordLength(5))
+        ));
+        assert!(matches!(
+            ap.clone().set_ap_password("p".repeat(64)).validate(),
+            Err(WifiApConfigurationError::InvalidPasswordLength(64))
+        ));
+        assert!(matches!(
+            ap.set_ap_password("pässwörd".to_owned()).validate(),
+            Err(WifiApConfigurationError::InvalidPasswordCharacters)
+        ));
+    }
 
     async fn run_provisioning_server(ex: Executor, srv: ProvisioningService<RAMStorage>) {
         let listen = TcpListener::bind("127.0.0.1:56432");
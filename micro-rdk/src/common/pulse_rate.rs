@@ -0,0 +1,194 @@
+//! A sensor measuring the rate of pulses on a digital interrupt, as output by water or gas
+//! flow meters, anemometers or tachometers.
+//!
+//! The `pin` attribute must be configured as one of the board's `digital_interrupts`. Readings
+//! are the `frequency_hz` of the pulses over the time elapsed since the previous reading and the
+//! total `pulse_count`. When a `calibration_factor` is set, in pulses per second per unit of
+//! flow (7.5 for a YF-S201 to read liters per minute), the `flow_rate` is returned as well.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::board::BoardType;
+use super::config::{AttributeError, ConfigType};
+use super::registry::{get_board_from_dependencies, ComponentRegistry, Dependency};
+use super::sensor::{
+    GenericReadingsResult, Readings, Sensor, SensorError, SensorResult, SensorType,
+    TypedReadingsResult,
+};
+use super::status::{Status, StatusError};
+use crate::google;
+
+// readings closer together than this keep the previous frequency rather than measuring it
+// over a handful of pulses
+const MIN_SAMPLE_PERIOD: Duration = Duration::from_millis(100);
+
+pub(crate) fn register_models(registry: &mut ComponentRegistry) {
+    if registry
+        .register_sensor("pulse_rate", &PulseRateSensor::from_config)
+        .is_err()
+    {
+        log::error!("pulse_rate model is already registered")
+    }
+}
+
+#[derive(DoCommand)]
+pub struct PulseRateSensor {
+    board: BoardType,
+    pin: i32,
+    calibration_factor: Option<f64>,
+    last_sample: (u32, Instant),
+    frequency_hz: f64,
+}
+
+impl PulseRateSensor {
+    pub(crate) fn from_config(
+        cfg: ConfigType,
+        dependencies: Vec<Dependency>,
+    ) -> Result<SensorType, SensorError> {
+        let board = get_board_from_dependencies(dependencies).ok_or(SensorError::ConfigError(
+            "pulse_rate missing board attribute",
+        ))?;
+        let pin = cfg
+            .get_attribute::<i32>("pin")
+            .map_err(|_| SensorError::ConfigError("pulse_rate missing pin attribute"))?;
+        let calibration_factor = match cfg.get_attribute::<f64>("calibration_factor") {
+            Ok(factor) if factor > 0.0 => Some(factor),
+            Err(AttributeError::KeyNotFound(_)) => None,
+            _ => {
+                return Err(SensorError::ConfigError(
+                    "pulse_rate calibration_factor should be a positive number",
+                ))
+            }
+        };
+        Ok(Arc::new(Mutex::new(PulseRateSensor::new(
+            board,
+            pin,
+            calibration_factor,
+        )?)))
+    }
+
+    /// Start measuring the pulse rate on `pin`, which fails if it isn't a digital interrupt
+    pub fn new(
+        board: BoardType,
+        pin: i32,
+        calibration_factor: Option<f64>,
+    ) -> Result<Self, SensorError> {
+        let count = board.lock().unwrap().get_digital_interrupt_value(pin)?;
+        Ok(Self {
+            board,
+            pin,
+            calibration_factor,
+            last_sample: (count, Instant::now()),
+            frequency_hz: 0.0,
+        })
+    }
+
+    fn update(&mut self, count: u32, now: Instant) -> f64 {
+        let (last_count, last_time) = self.last_sample;
+        let elapsed = now.saturating_duration_since(last_time);
+        if elapsed >= MIN_SAMPLE_PERIOD {
+            // the interrupt counter may wrap around on long running boards
+            self.frequency_hz = count.wrapping_sub(last_count) as f64 / elapsed.as_secs_f64();
+            self.last_sample = (count, now);
+        }
+        self.frequency_hz
+    }
+
+    fn measure(&mut self) -> Result<TypedReadingsResult<f64>, SensorError> {
+        let count = self
+            .board
+            .lock()
+            .unwrap()
+            .get_digital_interrupt_value(self.pin)?;
+        let frequency_hz = self.update(count, Instant::now());
+        let mut readings = HashMap::from([
+            ("frequency_hz".to_string(), frequency_hz),
+            ("pulse_count".to_string(), count as f64),
+        ]);
+        if let Some(factor) = self.calibration_factor {
+            readings.insert("flow_rate".to_string(), frequency_hz / factor);
+        }
+        Ok(readings)
+    }
+}
+
+impl Sensor for PulseRateSensor {}
+
+impl Readings for PulseRateSensor {
+    fn get_generic_readings(&mut self) -> Result<GenericReadingsResult, SensorError> {
+        Ok(self
+            .measure()?
+            .into_iter()
+            .map(|v| (v.0, SensorResult::<f64> { value: v.1 }.into()))
+            .collect())
+    }
+}
+
+impl Status for PulseRateSensor {
+    fn get_status(&self) -> Result<Option<google::protobuf::Struct>, StatusError> {
+        Ok(Some(google::protobuf::Struct {
+            fields: HashMap::new(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::board::FakeBoard;
+    use crate::common::config::Kind;
+    use crate::common::test_utils::{build_resource, fake_board_dependency};
+
+    #[test_log::test]
+    fn test_pulse_rate_frequency() {
+        let start = Instant::now();
+        let mut sensor = PulseRateSensor {
+            board: Arc::new(Mutex::new(FakeBoard::new(vec![]))),
+            pin: 4,
+            calibration_factor: Some(7.5),
+            last_sample: (100, start),
+            frequency_hz: 0.0,
+        };
+        assert_eq!(sensor.update(130, start + Duration::from_secs(2)), 15.0);
+        // too soon to measure, the previous frequency is kept
+        assert_eq!(
+            sensor.update(131, start + Duration::from_millis(2050)),
+            15.0
+        );
+        assert_eq!(sensor.update(150, start + Duration::from_secs(3)), 20.0);
+
+        sensor.last_sample = (u32::MAX - 4, start);
+        assert_eq!(sensor.update(5, start + Duration::from_secs(1)), 10.0);
+    }
+
+    #[test_log::test]
+    fn test_pulse_rate_config() {
+        // the fake board has no digital interrupts
+        assert!(matches!(
+            build_resource(
+                PulseRateSensor::from_config,
+                [("pin", Kind::NumberValue(4.0))],
+                vec![fake_board_dependency()],
+            ),
+            Err(SensorError::SensorBoardError(_))
+        ));
+        for invalid in [
+            vec![],
+            vec![
+                ("pin", Kind::NumberValue(4.0)),
+                ("calibration_factor", Kind::NumberValue(0.0)),
+            ],
+        ] {
+            assert!(matches!(
+                build_resource(
+                    PulseRateSensor::from_config,
+                    invalid,
+                    vec![fake_board_dependency()]
+                ),
+                Err(SensorError::ConfigError(_))
+            ));
+        }
+    }
+}
@@ -137,18 +137,28 @@ impl Default for ComponentRegistry {
     fn default() -> Self {
         let mut r = Self::new();
         crate::common::board::register_models(&mut r);
+        crate::common::diagnostics::register_models(&mut r);
         #[cfg(feature = "builtin-components")]
         {
             crate::common::encoder::register_models(&mut r);
             crate::common::motor::register_models(&mut r);
             crate::common::gpio_motor::register_models(&mut r);
             crate::common::gpio_servo::register_models(&mut r);
+            crate::common::stepper_motor::register_models(&mut r);
             crate::common::sensor::register_models(&mut r);
             crate::common::movement_sensor::register_models(&mut r);
             crate::common::mpu6050::register_models(&mut r);
             crate::common::adxl345::register_models(&mut r);
             crate::common::generic::register_models(&mut r);
+            crate::common::geofence::register_models(&mut r);
+            crate::common::composite_sensor::register_models(&mut r);
             crate::common::ina::register_models(&mut r);
+            crate::common::bme280::register_models(&mut r);
+            crate::common::bno055::register_models(&mut r);
+            crate::common::veml7700::register_models(&mut r);
+            crate::common::pulse_rate::register_models(&mut r);
+            crate::common::i2c_passthrough::register_models(&mut r);
+            crate::common::ssd1306::register_models(&mut r);
             crate::common::wheeled_base::register_models(&mut r);
             #[cfg(feature = "camera")]
             crate::common::camera::register_models(&mut r);
@@ -29,8 +29,8 @@ use log::*;
 #[cfg(feature = "data")]
 use super::{
     data_collector::{DataCollectionError, DataCollector, DataCollectorConfig},
-    data_manager::DataManager,
-    data_store::DefaultDataStore,
+    data_manager::{get_data_service_config, DataManager},
+    data_store::{DataStore, DefaultDataStore},
 };
 
 use super::{
@@ -43,12 +43,12 @@ use super::{
     exec::Executor,
     generic::{GenericComponent, GenericComponentType},
     motor::MotorType,
-    movement_sensor::MovementSensorType,
+    movement_sensor::{DeclinationCorrectedMovementSensor, HeadingCorrection, MovementSensorType},
     power_sensor::{PowerSensor, PowerSensorType},
     registry::{
         get_board_from_dependencies, ComponentRegistry, Dependency, RegistryError, ResourceKey,
     },
-    sensor::SensorType,
+    sensor::{ConvertedSensor, SensorType, UnitConversion},
     servo::{Servo, ServoType},
     status::StatusError,
 };
@@ -100,6 +100,10 @@ pub struct CloudMetadata {
     machine_id: String,
 }
 
+/// Name of the generic resource robot-wide DoCommands are sent to, see [`LocalRobot::do_command`].
+/// A generic component configured with this name takes precedence and receives the commands.
+pub const ROBOT_COMMAND_RESOURCE_NAME: &str = "robot";
+
 pub struct LocalRobot {
     pub(crate) part_id: String,
     resources: ResourceMap,
@@ -114,6 +118,8 @@ pub struct LocalRobot {
     // at some point using settimeofday (or something equivalent) and referenced thereof.
     pub(crate) start_time: Instant,
     cloud_metadata: Option<CloudMetadata>,
+    // name and reason of the components that couldn't be built
+    build_failures: Vec<(String, String)>,
 }
 
 #[derive(Error, Debug)]
@@ -140,11 +146,46 @@ pub enum RobotError {
     ResourceNotFound(String, String),
     #[error("missing cloud metadata")]
     RobotMissingCloudMetadata,
+    #[error("unknown robot command {0}")]
+    RobotUnknownCommand(String),
     #[cfg(feature = "data")]
     #[error(transparent)]
     DataCollectorInitError(#[from] DataCollectionError),
 }
 
+/// Model of the OTA service, whose `version` attribute is the firmware the config was produced
+/// for. Also known as [`OTA_MODEL_TRIPLET`](crate::common::ota::OTA_MODEL_TRIPLET) when the
+/// `ota` feature is enabled
+pub(crate) const OTA_SERVICE_MODEL: &str = "rdk:builtin:ota_service";
+
+// `major.minor.patch` of a version such as `v0.5.1-rc1`, missing parts being 0
+fn parse_version(version: &str) -> Option<(u64, u64, u64)> {
+    let version = version.trim().trim_start_matches('v');
+    let version = version.split(['-', '+']).next()?;
+    let mut parts = version.split('.').map(|part| part.parse::<u64>());
+    let major = parts.next()?.ok()?;
+    let minor = parts.next().unwrap_or(Ok(0)).ok()?;
+    let patch = parts.next().unwrap_or(Ok(0)).ok()?;
+    parts.next().is_none().then_some((major, minor, patch))
+}
+
+// Firmware version `config` was produced for when it is newer than this firmware, that is the
+// `version` requested by its OTA service
+fn config_firmware_version_if_newer(config: &RobotConfig) -> Option<String> {
+    let version = config
+        .services
+        .iter()
+        .find(|service| service.model == OTA_SERVICE_MODEL)?
+        .attributes
+        .as_ref()?
+        .fields
+        .get("version")?;
+    let Some(google::protobuf::value::Kind::StringValue(version)) = version.kind.as_ref() else {
+        return None;
+    };
+    (parse_version(version)? > parse_version(super::build_info::VERSION)?).then(|| version.clone())
+}
+
 fn resource_name_from_component_cfg(cfg: &DynamicComponentConfig) -> ResourceName {
     ResourceName {
         namespace: cfg.namespace.to_string(),
@@ -187,6 +228,7 @@ impl LocalRobot {
             data_manager_sync_task: Default::default(),
             #[cfg(feature = "data")]
             data_collector_configs: Default::default(),
+            build_failures: Default::default(),
         }
     }
     // Inserts components in order of dependency. If a component's dependencies are not satisfied it is
@@ -220,11 +262,13 @@ impl LocalRobot {
         let max_iteration = resource_to_build * 2;
         let mut num_iteration = 0;
         let mut iter = (0..resource_to_build).cycle();
+        // last error of each component, reported for the ones that end up not being built
+        let mut last_errors: Vec<Option<String>> = vec![None; components.len()];
         while resource_to_build > 0 && num_iteration < max_iteration {
             num_iteration += 1;
-            let cfg_outer = &mut components[iter.next().unwrap()];
+            let idx = iter.next().unwrap();
+            let cfg_outer = &mut components[idx];
             if let Some(cfg) = cfg_outer.as_ref() {
-                // capture the error and make it available to LocalRobot so it can be pushed in the logs?
                 if let Err(e) = self.build_resource(cfg, board.clone(), board_key.clone(), registry)
                 {
                     log::error!(
@@ -233,6 +277,7 @@ impl LocalRobot {
                         cfg.r#type,
                         e
                     );
+                    last_errors[idx] = Some(e.to_string());
                     continue;
                 }
                 let _ = cfg_outer.take();
@@ -243,11 +288,25 @@ impl LocalRobot {
             log::error!(
                 "These components couldn't be built {:?}. Check for errors, missing or circular dependencies in the config.",
                 components
-                    .into_iter()
+                    .iter()
                     .flatten()
-                    .map(|x| x.name)
-                    .collect::<Vec<String>>()
-            )
+                    .map(|x| x.name.as_str())
+                    .collect::<Vec<&str>>()
+            );
+            self.build_failures
+                .extend(
+                    components
+                        .into_iter()
+                        .zip(last_errors)
+                        .filter_map(|(cfg, error)| {
+                            cfg.map(|cfg| {
+                                (
+                                    cfg.name,
+                                    error.unwrap_or_else(|| "dependencies not built".to_owned()),
+                                )
+                            })
+                        }),
+                );
         }
         Ok(())
     }
@@ -280,44 +339,94 @@ impl LocalRobot {
             data_manager_sync_task: None,
             data_manager_collection_task: None,
             start_time: Instant::now(),
+            build_failures: vec![],
         };
 
-        let components: Result<Vec<Option<DynamicComponentConfig>>, AttributeError> = config
-            .components
-            .iter()
-            .map(|x| x.try_into().map(Option::Some))
-            .collect();
-        robot.process_components(
-            components.map_err(RobotError::RobotParseConfigError)?,
-            registry,
-        )?;
+        // a config produced for a newer firmware may not parse entirely, then what can be is
+        // built, otherwise a config that can't be parsed is an error
+        let newer_version = config_firmware_version_if_newer(config);
+        let mut components = Vec::with_capacity(config.components.len());
+        for cfg in config.components.iter() {
+            match DynamicComponentConfig::try_from(cfg) {
+                Ok(cfg) => components.push(Some(cfg)),
+                Err(err) if newer_version.is_some() => robot
+                    .build_failures
+                    .push((cfg.name.clone(), format!("unsupported config: {}", err))),
+                Err(err) => return Err(RobotError::RobotParseConfigError(err)),
+            }
+        }
+        robot.process_components(components, registry)?;
+        if let Some(version) = newer_version.filter(|_| !robot.build_failures.is_empty()) {
+            log::warn!(
+                "the config was produced for micro-rdk {} and parts of it aren't supported by micro-rdk {}, please update the firmware (OTA)",
+                version,
+                super::build_info::VERSION
+            );
+        }
 
         // TODO: When cfg's on expressions are valid, remove the outer scope.
         #[cfg(feature = "data")]
         {
-            // TODO(RSDK-8125): Support selection of the DataStore trait other than
-            // DefaultDataStore in a way that is configurable
-            match DataManager::<DefaultDataStore>::from_robot_and_config(&robot, config) {
-                Ok(Some(mut data_manager)) => {
-                    if let Some(task) = data_manager.get_sync_task(robot.start_time) {
-                        let _ = robot.data_manager_sync_task.insert(Box::new(task));
+            // native builds spool captured data to disk when a capture directory is configured,
+            // captured data is kept in RAM when the spool can't be set up
+            #[cfg(feature = "native")]
+            if crate::native::data_store::DiskDataStoreConfig::is_configured(config) {
+                match robot.start_data_manager::<crate::native::data_store::DiskDataStore>(config) {
+                    Err(crate::common::data_manager::DataManagerError::StoreError(
+                        crate::common::data_store::DataStoreError::SpoolError(err),
+                    )) => {
+                        log::error!(
+                            "couldn't spool captured data to disk ({}), keeping it in RAM",
+                            err
+                        );
+                    }
+                    res => {
+                        if let Err(err) = res {
+                            log::error!("Error configuring data management: {:?}", err);
+                        }
+                        return Ok(robot);
                     }
-                    let _ = robot
-                        .data_manager_collection_task
-                        .replace(robot.executor.spawn(async move {
-                            data_manager.data_collection_task(robot.start_time).await;
-                        }));
-                }
-                Ok(None) => {}
-                Err(err) => {
-                    log::error!("Error configuring data management: {:?}", err);
                 }
-            };
+            }
+            if let Err(err) = robot.start_data_manager::<DefaultDataStore>(config) {
+                log::error!("Error configuring data management: {:?}", err);
+            }
         }
 
         Ok(robot)
     }
 
+    #[cfg(feature = "data")]
+    fn start_data_manager<StoreType>(
+        &mut self,
+        config: &RobotConfig,
+    ) -> Result<(), crate::common::data_manager::DataManagerError>
+    where
+        StoreType: DataStore + 'static,
+    {
+        if let Some(mut data_manager) =
+            DataManager::<StoreType>::from_robot_and_config(self, config)?
+        {
+            if let Some(task) = data_manager.get_sync_task(self.start_time) {
+                // `sync_now` is sent to a generic component named after the data manager
+                if let Ok(Some(svc)) = get_data_service_config(config) {
+                    self.insert_generic_component(
+                        svc.name,
+                        Arc::new(Mutex::new(task.sync_trigger())),
+                    );
+                }
+                let _ = self.data_manager_sync_task.insert(Box::new(task));
+            }
+            let start_time = self.start_time;
+            let _ = self
+                .data_manager_collection_task
+                .replace(self.executor.spawn(async move {
+                    data_manager.data_collection_task(start_time).await;
+                }));
+        }
+        Ok(())
+    }
+
     fn build_resource(
         &mut self,
         config: &DynamicComponentConfig,
@@ -416,17 +525,33 @@ impl LocalRobot {
                 let ctor = registry
                     .get_sensor_constructor(&model)
                     .map_err(RobotError::RobotRegistryError)?;
-                ResourceType::Sensor(
-                    ctor(cfg, deps).map_err(|e| RobotError::RobotResourceBuildError(e.into()))?,
-                )
+                let conversions = UnitConversion::from_config(&cfg)
+                    .map_err(|e| RobotError::RobotResourceBuildError(e.into()))?;
+                let sensor =
+                    ctor(cfg, deps).map_err(|e| RobotError::RobotResourceBuildError(e.into()))?;
+                ResourceType::Sensor(if conversions.is_empty() {
+                    sensor
+                } else {
+                    Arc::new(Mutex::new(ConvertedSensor::new(sensor, conversions)))
+                })
             }
             "movement_sensor" => {
                 let ctor = registry
                     .get_movement_sensor_constructor(&model)
                     .map_err(RobotError::RobotRegistryError)?;
-                ResourceType::MovementSensor(
-                    ctor(cfg, deps).map_err(|e| RobotError::RobotResourceBuildError(e.into()))?,
-                )
+                let correction = HeadingCorrection::from_config(&cfg)
+                    .map_err(|e| RobotError::RobotResourceBuildError(e.into()))?;
+                let movement_sensor =
+                    ctor(cfg, deps).map_err(|e| RobotError::RobotResourceBuildError(e.into()))?;
+                ResourceType::MovementSensor(match correction {
+                    Some(correction) => Arc::new(Mutex::new(
+                        DeclinationCorrectedMovementSensor::with_correction(
+                            movement_sensor,
+                            correction,
+                        ),
+                    )),
+                    None => movement_sensor,
+                })
             }
             "encoder" => {
                 let ctor = registry
@@ -487,6 +612,25 @@ impl LocalRobot {
         Ok(())
     }
 
+    /// Adds a generic component exposing the commands of a service, such as the data manager or
+    /// OTA, rather than built from the components config. A component of the same name is kept.
+    #[cfg(any(feature = "data", feature = "ota"))]
+    pub(crate) fn insert_generic_component(
+        &mut self,
+        name: String,
+        component: GenericComponentType,
+    ) {
+        let r_name = ResourceName {
+            namespace: "rdk".to_string(),
+            r#type: "component".to_string(),
+            subtype: "generic".to_string(),
+            name,
+        };
+        self.resources
+            .entry(r_name)
+            .or_insert(ResourceType::Generic(component));
+    }
+
     #[cfg(feature = "data")]
     pub fn data_collectors(&self) -> Result<Vec<DataCollector>, RobotError> {
         let mut res = Vec::new();
@@ -705,6 +849,10 @@ impl LocalRobot {
         }
         Ok(vec)
     }
+    /// Name and reason of the components of the config that couldn't be built
+    pub fn build_failures(&self) -> &[(String, String)] {
+        &self.build_failures
+    }
     pub fn get_resource_names(&self) -> Result<Vec<common::v1::ResourceName>, RobotError> {
         let mut name = Vec::with_capacity(self.resources.len());
         for k in self.resources.keys() {
@@ -884,6 +1032,40 @@ impl LocalRobot {
         Ok(())
     }
 
+    /// Whether any actuator (base, motor or servo) of the robot is currently moving
+    pub fn any_moving(&mut self) -> Result<bool, RobotError> {
+        for resource in self.resources.values_mut() {
+            let moving = match resource {
+                ResourceType::Base(b) => b.is_moving()?,
+                ResourceType::Motor(m) => m.is_moving()?,
+                ResourceType::Servo(s) => s.is_moving()?,
+                _ => continue,
+            };
+            if moving {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Answer a robot-wide DoCommand sent to the generic resource named
+    /// [`ROBOT_COMMAND_RESOURCE_NAME`], currently only `{"any_moving": null}` returning
+    /// `{"any_moving": bool}`
+    pub fn do_command(
+        &mut self,
+        command: &google::protobuf::Struct,
+    ) -> Result<google::protobuf::Struct, RobotError> {
+        let mut res = HashMap::new();
+        for key in command.fields.keys() {
+            let value = match key.as_str() {
+                "any_moving" => google::protobuf::value::Kind::BoolValue(self.any_moving()?),
+                _ => return Err(RobotError::RobotUnknownCommand(key.clone())),
+            };
+            res.insert(key.clone(), google::protobuf::Value { kind: Some(value) });
+        }
+        Ok(google::protobuf::Struct { fields: res })
+    }
+
     pub fn get_cloud_metadata(&self) -> Result<robot::v1::GetCloudMetadataResponse, RobotError> {
         self.cloud_metadata
             .as_ref()
@@ -922,11 +1104,11 @@ mod tests {
             i2c::I2CHandle,
             motor::Motor,
             movement_sensor::MovementSensor,
-            robot::LocalRobot,
+            robot::{LocalRobot, RobotError},
             sensor::Readings,
         },
         google::{self, protobuf::Struct},
-        proto::app::v1::{ComponentConfig, RobotConfig},
+        proto::app::v1::{ComponentConfig, RobotConfig, ServiceConfig},
     };
 
     #[cfg(feature = "data")]
@@ -1309,6 +1491,56 @@ mod tests {
         assert!(pos_deg.is_err());
     }
 
+    #[test_log::test]
+    fn test_any_moving() {
+        let mut robot = LocalRobot::default();
+        robot
+            .process_components(
+                vec![Some(DynamicComponentConfig {
+                    name: "motor".to_owned(),
+                    namespace: "rdk".to_owned(),
+                    r#type: "motor".to_owned(),
+                    model: "rdk:builtin:fake".to_owned(),
+                    ..Default::default()
+                })],
+                &mut Box::default(),
+            )
+            .unwrap();
+        let command = |key: &str| Struct {
+            fields: HashMap::from([(
+                key.to_owned(),
+                google::protobuf::Value {
+                    kind: Some(google::protobuf::value::Kind::NullValue(0)),
+                },
+            )]),
+        };
+        let any_moving = |robot: &mut LocalRobot| {
+            robot.do_command(&command("any_moving")).unwrap().fields["any_moving"]
+                .kind
+                .clone()
+        };
+
+        assert!(!robot.any_moving().unwrap());
+        assert_eq!(
+            any_moving(&mut robot),
+            Some(google::protobuf::value::Kind::BoolValue(false))
+        );
+        let mut motor = robot.get_motor_by_name("motor".to_string()).unwrap();
+        motor.set_power(0.5).unwrap();
+        assert!(robot.any_moving().unwrap());
+        assert_eq!(
+            any_moving(&mut robot),
+            Some(google::protobuf::value::Kind::BoolValue(true))
+        );
+        assert!(robot.stop_all().is_ok());
+        assert!(!robot.any_moving().unwrap());
+
+        assert!(matches!(
+            robot.do_command(&command("reboot")),
+            Err(RobotError::RobotUnknownCommand(_))
+        ));
+    }
+
     #[test_log::test]
     fn test_from_cloud_config() {
         let mut component_cfgs = Vec::new();
@@ -1422,21 +1654,138 @@ mod tests {
 
         assert!(m1.is_some());
 
-        let position = m1.unwrap().get_position();
+        let position = m1.unwrap().get_position_revolutions();
 
         assert!(position.is_ok());
 
-        assert_eq!(position.ok().unwrap(), 90);
+        assert_eq!(position.ok().unwrap(), 0.25);
 
         let m2 = robot.get_motor_by_name("m2".to_string());
 
         assert!(m2.is_some());
 
-        let position = m2.unwrap().get_position();
+        let position = m2.unwrap().get_position_revolutions();
 
         assert!(position.is_ok());
 
-        assert_eq!(position.ok().unwrap(), 180);
+        assert_eq!(position.ok().unwrap(), 0.5);
+    }
+
+    #[test_log::test]
+    fn test_cloud_config_unsupported_component() {
+        let component = |name: &str, r#type: &str, kind| ComponentConfig {
+            name: name.to_string(),
+            model: "rdk:builtin:fake".to_string(),
+            r#type: r#type.to_string(),
+            namespace: "rdk".to_string(),
+            attributes: Some(Struct {
+                fields: HashMap::from([(
+                    "fake_value".to_string(),
+                    google::protobuf::Value { kind },
+                )]),
+            }),
+            ..Default::default()
+        };
+        let mut robot_cfg = RobotConfig {
+            components: vec![
+                component(
+                    "s1",
+                    "sensor",
+                    Some(google::protobuf::value::Kind::NumberValue(1.0)),
+                ),
+                // an attribute without value can't be parsed
+                component("s2", "sensor", None),
+                component(
+                    "t1",
+                    "teleporter",
+                    Some(google::protobuf::value::Kind::NumberValue(1.0)),
+                ),
+            ],
+            ..Default::default()
+        };
+
+        // without a newer firmware version, a config that can't be parsed is an error
+        assert!(matches!(
+            LocalRobot::from_cloud_config(
+                Executor::new(),
+                "".to_string(),
+                &robot_cfg,
+                &mut Box::default(),
+                None,
+            ),
+            Err(RobotError::RobotParseConfigError(_))
+        ));
+
+        robot_cfg.services.push(ServiceConfig {
+            name: "ota".to_string(),
+            model: super::OTA_SERVICE_MODEL.to_string(),
+            attributes: Some(Struct {
+                fields: HashMap::from([(
+                    "version".to_string(),
+                    google::protobuf::Value {
+                        kind: Some(google::protobuf::value::Kind::StringValue(
+                            "v999.0.0".to_string(),
+                        )),
+                    },
+                )]),
+            }),
+            ..Default::default()
+        });
+        let robot = LocalRobot::from_cloud_config(
+            Executor::new(),
+            "".to_string(),
+            &robot_cfg,
+            &mut Box::default(),
+            None,
+        )
+        .unwrap();
+
+        assert!(robot.get_sensor_by_name("s1".to_string()).is_some());
+        assert!(robot.get_sensor_by_name("s2".to_string()).is_none());
+        assert_eq!(robot.get_resource_names().unwrap().len(), 1);
+        let failed: Vec<&str> = robot
+            .build_failures()
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect();
+        assert_eq!(failed, vec!["s2", "t1"]);
+    }
+
+    #[test_log::test]
+    fn test_config_firmware_version() {
+        use super::{config_firmware_version_if_newer, parse_version, OTA_SERVICE_MODEL};
+        assert_eq!(parse_version("v0.5.1-rc1"), Some((0, 5, 1)));
+        assert_eq!(parse_version("1.2"), Some((1, 2, 0)));
+        assert_eq!(parse_version("latest"), None);
+        assert_eq!(parse_version("1.2.3.4"), None);
+
+        let config = |version: &str| RobotConfig {
+            services: vec![ServiceConfig {
+                model: OTA_SERVICE_MODEL.to_string(),
+                attributes: Some(Struct {
+                    fields: HashMap::from([(
+                        "version".to_string(),
+                        google::protobuf::Value {
+                            kind: Some(google::protobuf::value::Kind::StringValue(
+                                version.to_string(),
+                            )),
+                        },
+                    )]),
+                }),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        assert_eq!(
+            config_firmware_version_if_newer(&RobotConfig::default()),
+            None
+        );
+        assert_eq!(config_firmware_version_if_newer(&config("0.0.1")), None);
+        assert_eq!(config_firmware_version_if_newer(&config("nightly")), None);
+        assert_eq!(
+            config_firmware_version_if_newer(&config("999.0.0")),
+            Some("999.0.0".to_string())
+        );
     }
 
     #[test_log::test]
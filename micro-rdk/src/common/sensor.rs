@@ -49,6 +49,8 @@ pub enum SensorError {
     SensorBoardError(#[from] BoardError),
     #[error("sensor error code {0}")]
     SensorCodeError(i32),
+    #[error("background read failed: {0}")]
+    SensorBackgroundReadError(String),
 }
 
 #[cfg(feature = "builtin-components")]
@@ -80,10 +82,69 @@ impl From<GenericReadingsResult> for Data {
     }
 }
 
+/// Key of the `extra` parameters of a GetReadings request asking for the capture time to be
+/// added to the readings
+pub const INCLUDE_CAPTURE_TIME_EXTRA: &str = "include_capture_time";
+/// Reading holding the capture time as an RFC 3339 string, only present once the clock is set
+pub const CAPTURE_TIME_READING: &str = "_capture_time";
+/// Reading holding the capture time in milliseconds on a monotonic clock, only comparable
+/// between readings taken since the same boot
+pub const CAPTURE_MONOTONIC_MS_READING: &str = "_capture_monotonic_ms";
+
+fn monotonic_ms() -> f64 {
+    #[cfg(feature = "esp32")]
+    {
+        // microseconds since boot
+        (unsafe { crate::esp32::esp_idf_svc::sys::esp_timer_get_time() }) as f64 / 1000.0
+    }
+    #[cfg(not(feature = "esp32"))]
+    {
+        static START: std::sync::OnceLock<std::time::Instant> = std::sync::OnceLock::new();
+        START
+            .get_or_init(std::time::Instant::now)
+            .elapsed()
+            .as_secs_f64()
+            * 1000.0
+    }
+}
+
+/// Add the capture time to `readings`: always on the monotonic clock and, when the clock has been
+/// set (by SNTP or manually), as an absolute time
+pub fn add_capture_time(readings: &mut GenericReadingsResult) {
+    use crate::common::app_client::VIAM_FOUNDING_YEAR;
+    use chrono::Datelike;
+    use google::protobuf::value::Kind;
+
+    readings.insert(
+        CAPTURE_MONOTONIC_MS_READING.to_string(),
+        google::protobuf::Value {
+            kind: Some(Kind::NumberValue(monotonic_ms())),
+        },
+    );
+    let now = chrono::offset::Local::now().fixed_offset();
+    // a date before Viam was founded means the clock was never set
+    if now.year() >= VIAM_FOUNDING_YEAR {
+        readings.insert(
+            CAPTURE_TIME_READING.to_string(),
+            google::protobuf::Value {
+                kind: Some(Kind::StringValue(
+                    now.to_rfc3339_opts(chrono::SecondsFormat::Millis, false),
+                )),
+            },
+        );
+    }
+}
+
 pub type TypedReadingsResult<T> = ::std::collections::HashMap<String, T>;
 
 pub trait Readings {
     fn get_generic_readings(&mut self) -> Result<GenericReadingsResult, SensorError>;
+    /// Readings along with the time they were captured, see [`add_capture_time`]
+    fn get_timestamped_readings(&mut self) -> Result<GenericReadingsResult, SensorError> {
+        let mut readings = self.get_generic_readings()?;
+        add_capture_time(&mut readings);
+        Ok(readings)
+    }
     #[cfg(feature = "data")]
     fn get_readings_data(&mut self) -> Result<SensorData, SensorError> {
         let reading_requested_dt = chrono::offset::Local::now().fixed_offset();
@@ -127,8 +188,21 @@ impl From<SensorResult<f64>> for google::protobuf::Value {
     }
 }
 
+/// Readings of a [SensorT] with every value wrapped as a number, used by the `SensorReadings`
+/// derive to implement [Readings]
+pub fn get_sensor_generic_readings<S>(sensor: &S) -> Result<GenericReadingsResult, SensorError>
+where
+    S: SensorT<f64> + ?Sized,
+{
+    Ok(sensor
+        .get_readings()?
+        .into_iter()
+        .map(|v| (v.0, SensorResult::<f64> { value: v.1 }.into()))
+        .collect())
+}
+
 #[cfg(feature = "builtin-components")]
-#[derive(DoCommand)]
+#[derive(DoCommand, SensorReadings)]
 pub struct FakeSensor {
     fake_reading: f64,
 }
@@ -161,17 +235,6 @@ impl Default for FakeSensor {
 #[cfg(feature = "builtin-components")]
 impl Sensor for FakeSensor {}
 
-#[cfg(feature = "builtin-components")]
-impl Readings for FakeSensor {
-    fn get_generic_readings(&mut self) -> Result<GenericReadingsResult, SensorError> {
-        Ok(self
-            .get_readings()?
-            .into_iter()
-            .map(|v| (v.0, SensorResult::<f64> { value: v.1 }.into()))
-            .collect())
-    }
-}
-
 #[cfg(feature = "builtin-components")]
 impl SensorT<f64> for FakeSensor {
     fn get_readings(&self) -> Result<TypedReadingsResult<f64>, SensorError> {
@@ -192,6 +255,13 @@ where
     fn get_generic_readings(&mut self) -> Result<GenericReadingsResult, SensorError> {
         self.get_mut().unwrap().get_generic_readings()
     }
+    fn get_timestamped_readings(&mut self) -> Result<GenericReadingsResult, SensorError> {
+        self.get_mut().unwrap().get_timestamped_readings()
+    }
+    #[cfg(feature = "data")]
+    fn get_readings_data(&mut self) -> Result<SensorData, SensorError> {
+        self.get_mut().unwrap().get_readings_data()
+    }
 }
 
 impl<A> Readings for Arc<Mutex<A>>
@@ -201,6 +271,120 @@ where
     fn get_generic_readings(&mut self) -> Result<GenericReadingsResult, SensorError> {
         self.lock().unwrap().get_generic_readings()
     }
+    fn get_timestamped_readings(&mut self) -> Result<GenericReadingsResult, SensorError> {
+        self.lock().unwrap().get_timestamped_readings()
+    }
+    #[cfg(feature = "data")]
+    fn get_readings_data(&mut self) -> Result<SensorData, SensorError> {
+        self.lock().unwrap().get_readings_data()
+    }
+}
+
+/// Linear conversion `value * scale + offset` of a numeric reading, e.g. a `scale` of 3.28084
+/// converts meters to feet
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct UnitConversion {
+    pub scale: f64,
+    pub offset: f64,
+}
+
+impl Default for UnitConversion {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            offset: 0.0,
+        }
+    }
+}
+
+impl UnitConversion {
+    /// Conversions per reading key from the optional `unit_conversions` attribute of a sensor,
+    /// for example `{"distance": {"scale": 3.28084, "offset": 0}}`
+    pub(crate) fn from_config(
+        cfg: &super::config::ConfigType,
+    ) -> Result<HashMap<String, Self>, SensorError> {
+        use super::config::AttributeError;
+        let conversions =
+            match cfg.get_attribute::<HashMap<&str, HashMap<&str, f64>>>("unit_conversions") {
+                Ok(conversions) => conversions,
+                Err(AttributeError::KeyNotFound(_)) => return Ok(HashMap::new()),
+                Err(_) => {
+                    return Err(SensorError::ConfigError(
+                        "unit_conversions should map reading keys to a `scale` and an `offset`",
+                    ))
+                }
+            };
+        Ok(conversions
+            .into_iter()
+            .map(|(key, conversion)| {
+                let default = Self::default();
+                (
+                    key.to_owned(),
+                    Self {
+                        scale: conversion.get("scale").copied().unwrap_or(default.scale),
+                        offset: conversion.get("offset").copied().unwrap_or(default.offset),
+                    },
+                )
+            })
+            .collect())
+    }
+}
+
+/// Sensor applying a [`UnitConversion`] to some of the numeric readings of another sensor, other
+/// readings are passed through unchanged
+pub struct ConvertedSensor {
+    sensor: SensorType,
+    conversions: HashMap<String, UnitConversion>,
+}
+
+impl ConvertedSensor {
+    pub fn new(sensor: SensorType, conversions: HashMap<String, UnitConversion>) -> Self {
+        Self {
+            sensor,
+            conversions,
+        }
+    }
+}
+
+impl Sensor for ConvertedSensor {}
+
+impl Readings for ConvertedSensor {
+    fn get_generic_readings(&mut self) -> Result<GenericReadingsResult, SensorError> {
+        use google::protobuf::value::Kind;
+        let mut readings = self.sensor.get_generic_readings()?;
+        for (key, conversion) in &self.conversions {
+            if let Some(google::protobuf::Value {
+                kind: Some(Kind::NumberValue(value)),
+            }) = readings.get_mut(key)
+            {
+                *value = *value * conversion.scale + conversion.offset;
+            }
+        }
+        Ok(readings)
+    }
+}
+
+impl Status for ConvertedSensor {
+    fn get_status(
+        &self,
+    ) -> Result<Option<google::protobuf::Struct>, crate::common::status::StatusError> {
+        self.sensor.get_status()
+    }
+}
+
+impl DoCommand for ConvertedSensor {
+    fn do_command(
+        &mut self,
+        command_struct: Option<google::protobuf::Struct>,
+    ) -> Result<Option<google::protobuf::Struct>, super::generic::GenericError> {
+        self.sensor.do_command(command_struct)
+    }
+    fn do_command_async(
+        &mut self,
+        command_struct: Option<google::protobuf::Struct>,
+    ) -> super::generic::DoCommandFuture {
+        self.sensor.do_command_async(command_struct)
+    }
 }
 
 #[cfg(feature = "builtin-components")]
@@ -213,3 +397,103 @@ impl Status for FakeSensor {
         }))
     }
 }
+
+#[cfg(test)]
+#[cfg(feature = "builtin-components")]
+mod tests {
+    use super::{
+        ConvertedSensor, FakeSensor, GenericReadingsResult, Readings, Sensor, SensorError,
+        UnitConversion,
+    };
+    use crate::common::test_utils::delegation_test;
+    use crate::google::protobuf::value::Kind;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    delegation_test!(
+        test_sensor_delegation,
+        RecordingSensor as dyn Sensor,
+        wrappers: [Mutex::new, |sensor| Arc::new(Mutex::new(sensor))],
+        impl Readings {
+            fn get_generic_readings(&mut self) -> Result<GenericReadingsResult, SensorError> {
+                Ok(HashMap::new())
+            }
+            fn get_timestamped_readings(&mut self) -> Result<GenericReadingsResult, SensorError> {
+                Ok(HashMap::new())
+            }
+            #[cfg(feature = "data")]
+            fn get_readings_data(&mut self) -> Result<super::SensorData, SensorError> {
+                Ok(Default::default())
+            }
+        }
+        impl Sensor {}
+        calls: |sensor| {
+            sensor.get_generic_readings().unwrap();
+            sensor.get_timestamped_readings().unwrap();
+            #[cfg(feature = "data")]
+            sensor.get_readings_data().unwrap();
+        }
+    );
+
+    #[test_log::test]
+    fn test_add_capture_time() {
+        use super::{add_capture_time, CAPTURE_MONOTONIC_MS_READING, CAPTURE_TIME_READING};
+        use chrono::Datelike;
+
+        let monotonic_ms =
+            |readings: &GenericReadingsResult| match readings[CAPTURE_MONOTONIC_MS_READING].kind {
+                Some(Kind::NumberValue(ms)) => ms,
+                ref kind => panic!("unexpected monotonic time {:?}", kind),
+            };
+        let mut readings = HashMap::from([(
+            "temperature".to_string(),
+            crate::google::protobuf::Value {
+                kind: Some(Kind::NumberValue(21.5)),
+            },
+        )]);
+        add_capture_time(&mut readings);
+        assert_eq!(readings["temperature"].kind, Some(Kind::NumberValue(21.5)));
+        let first = monotonic_ms(&readings);
+        add_capture_time(&mut readings);
+        assert!(monotonic_ms(&readings) >= first);
+
+        // the clock of the machine running the tests is set
+        let before = chrono::offset::Local::now();
+        assert!(before.year() >= crate::common::app_client::VIAM_FOUNDING_YEAR);
+        add_capture_time(&mut readings);
+        let Some(Kind::StringValue(time)) = &readings[CAPTURE_TIME_READING].kind else {
+            panic!(
+                "unexpected capture time {:?}",
+                readings[CAPTURE_TIME_READING]
+            );
+        };
+        let time = chrono::DateTime::parse_from_rfc3339(time)
+            .unwrap()
+            .timestamp_millis();
+        assert!(time >= before.timestamp_millis());
+        assert!(time <= chrono::offset::Local::now().timestamp_millis());
+    }
+
+    #[test_log::test]
+    fn test_converted_sensor() {
+        let mut sensor = ConvertedSensor::new(
+            Arc::new(Mutex::new(FakeSensor::new())),
+            HashMap::from([
+                (
+                    "fake_sensor".to_owned(),
+                    UnitConversion {
+                        scale: 2.0,
+                        offset: 1.0,
+                    },
+                ),
+                ("missing".to_owned(), UnitConversion::default()),
+            ]),
+        );
+        let readings = sensor.get_generic_readings().unwrap();
+        assert_eq!(readings.len(), 1);
+        assert!(matches!(
+            readings["fake_sensor"].kind,
+            Some(Kind::NumberValue(v)) if (v - 85.84).abs() < 1e-9
+        ));
+    }
+}
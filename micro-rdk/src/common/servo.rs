@@ -1,4 +1,7 @@
-use super::{actuator::Actuator, config::AttributeError, generic::DoCommand, status::Status};
+use super::{
+    actuator::Actuator, analog::AnalogError, config::AttributeError, generic::DoCommand,
+    status::Status,
+};
 use crate::common::board::BoardError;
 use std::sync::{Arc, Mutex};
 use thiserror::Error;
@@ -11,6 +14,8 @@ pub enum ServoError {
     ServoConfigurationError(&'static str),
     #[error(transparent)]
     ServoConfigAttributeError(#[from] AttributeError),
+    #[error(transparent)]
+    ServoAnalogError(#[from] AnalogError),
 }
 
 pub trait Servo: Status + Actuator + DoCommand {
@@ -47,3 +52,38 @@ where
         self.lock().unwrap().get_position()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::actuator::ActuatorError;
+    use crate::common::test_utils::delegation_test;
+
+    delegation_test!(
+        test_servo_delegation,
+        RecordingServo as dyn Servo,
+        wrappers: [Mutex::new, |servo| Arc::new(Mutex::new(servo))],
+        impl Servo {
+            fn move_to(&mut self, _: u32) -> Result<(), ServoError> {
+                Ok(())
+            }
+            fn get_position(&mut self) -> Result<u32, ServoError> {
+                Ok(0)
+            }
+        }
+        impl Actuator {
+            fn is_moving(&mut self) -> Result<bool, ActuatorError> {
+                Ok(false)
+            }
+            fn stop(&mut self) -> Result<(), ActuatorError> {
+                Ok(())
+            }
+        }
+        calls: |servo| {
+            servo.move_to(90).unwrap();
+            servo.get_position().unwrap();
+            servo.is_moving().unwrap();
+            servo.stop().unwrap();
+        }
+    );
+}
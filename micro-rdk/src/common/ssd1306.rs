@@ -0,0 +1,423 @@
+//! Generic component driving a 128 pixels wide SSD1306 OLED display over I2C, to show status
+//! text in the field without a serial monitor.
+//!
+//! Configured with the `i2c_bus` of the board, the `i2c_address` of the display (defaults to
+//! 0x3C) and its `height` in pixels (32 or 64, defaults to 64). Text is drawn with a 5x7 font,
+//! giving 21 characters on each of the `height / 8` rows.
+//!
+//! Supported commands:
+//! - `{"write_text": "...", "row": n}` writes the text on row `n` (0 when omitted), the rest of
+//!   the row is cleared, text past the end of the row is dropped and a newline continues on the
+//!   next row
+//! - `{"clear": true}` blanks the whole display
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use super::{
+    board::Board,
+    config::{AttributeError, ConfigType},
+    generic::{DoCommand, GenericComponent, GenericComponentType, GenericError},
+    i2c::{i2c_address_from_config, I2CErrors, I2CHandle, I2cHandleType},
+    registry::{get_board_from_dependencies, ComponentRegistry, Dependency},
+    status::{Status, StatusError},
+};
+use crate::google::protobuf::{value::Kind, Struct};
+
+pub(crate) fn register_models(registry: &mut ComponentRegistry) {
+    if registry
+        .register_generic_component("ssd1306", &Ssd1306::from_config)
+        .is_err()
+    {
+        log::error!("ssd1306 model is already registered")
+    }
+}
+
+pub const DEFAULT_I2C_ADDRESS: u8 = 0x3C;
+const WIDTH: usize = 128;
+const DEFAULT_HEIGHT: u8 = 64;
+const CHAR_WIDTH: usize = 6;
+/// Number of characters fitting on a row
+pub const COLUMNS: usize = WIDTH / CHAR_WIDTH;
+
+// first byte of each transfer, telling whether the following bytes are commands or pixels
+const CONTROL_COMMAND: u8 = 0x00;
+const CONTROL_DATA: u8 = 0x40;
+
+const SET_COLUMN_ADDRESS: u8 = 0x21;
+const SET_PAGE_ADDRESS: u8 = 0x22;
+
+// 5x7 glyphs of the printable ASCII characters (0x20 to 0x7E), one byte per column with the
+// least significant bit at the top
+const FONT: [[u8; 5]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00], // ' '
+    [0x00, 0x00, 0x5F, 0x00, 0x00], // !
+    [0x00, 0x07, 0x00, 0x07, 0x00], // "
+    [0x14, 0x7F, 0x14, 0x7F, 0x14], // #
+    [0x24, 0x2A, 0x7F, 0x2A, 0x12], // $
+    [0x23, 0x13, 0x08, 0x64, 0x62], // %
+    [0x36, 0x49, 0x55, 0x22, 0x50], // &
+    [0x00, 0x05, 0x03, 0x00, 0x00], // '
+    [0x00, 0x1C, 0x22, 0x41, 0x00], // (
+    [0x00, 0x41, 0x22, 0x1C, 0x00], // )
+    [0x08, 0x2A, 0x1C, 0x2A, 0x08], // *
+    [0x08, 0x08, 0x3E, 0x08, 0x08], // +
+    [0x00, 0x50, 0x30, 0x00, 0x00], // ,
+    [0x08, 0x08, 0x08, 0x08, 0x08], // -
+    [0x00, 0x60, 0x60, 0x00, 0x00], // .
+    [0x20, 0x10, 0x08, 0x04, 0x02], // /
+    [0x3E, 0x51, 0x49, 0x45, 0x3E], // 0
+    [0x00, 0x42, 0x7F, 0x40, 0x00], // 1
+    [0x42, 0x61, 0x51, 0x49, 0x46], // 2
+    [0x21, 0x41, 0x45, 0x4B, 0x31], // 3
+    [0x18, 0x14, 0x12, 0x7F, 0x10], // 4
+    [0x27, 0x45, 0x45, 0x45, 0x39], // 5
+    [0x3C, 0x4A, 0x49, 0x49, 0x30], // 6
+    [0x01, 0x71, 0x09, 0x05, 0x03], // 7
+    [0x36, 0x49, 0x49, 0x49, 0x36], // 8
+    [0x06, 0x49, 0x49, 0x29, 0x1E], // 9
+    [0x00, 0x36, 0x36, 0x00, 0x00], // :
+    [0x00, 0x56, 0x36, 0x00, 0x00], // ;
+    [0x08, 0x14, 0x22, 0x41, 0x00], // <
+    [0x14, 0x14, 0x14, 0x14, 0x14], // =
+    [0x00, 0x41, 0x22, 0x14, 0x08], // >
+    [0x02, 0x01, 0x51, 0x09, 0x06], // ?
+    [0x32, 0x49, 0x79, 0x41, 0x3E], // @
+    [0x7E, 0x11, 0x11, 0x11, 0x7E], // A
+    [0x7F, 0x49, 0x49, 0x49, 0x36], // B
+    [0x3E, 0x41, 0x41, 0x41, 0x22], // C
+    [0x7F, 0x41, 0x41, 0x22, 0x1C], // D
+    [0x7F, 0x49, 0x49, 0x49, 0x41], // E
+    [0x7F, 0x09, 0x09, 0x01, 0x01], // F
+    [0x3E, 0x41, 0x41, 0x51, 0x32], // G
+    [0x7F, 0x08, 0x08, 0x08, 0x7F], // H
+    [0x00, 0x41, 0x7F, 0x41, 0x00], // I
+    [0x20, 0x40, 0x41, 0x3F, 0x01], // J
+    [0x7F, 0x08, 0x14, 0x22, 0x41], // K
+    [0x7F, 0x40, 0x40, 0x40, 0x40], // L
+    [0x7F, 0x02, 0x04, 0x02, 0x7F], // M
+    [0x7F, 0x04, 0x08, 0x10, 0x7F], // N
+    [0x3E, 0x41, 0x41, 0x41, 0x3E], // O
+    [0x7F, 0x09, 0x09, 0x09, 0x06], // P
+    [0x3E, 0x41, 0x51, 0x21, 0x5E], // Q
+    [0x7F, 0x09, 0x19, 0x29, 0x46], // R
+    [0x46, 0x49, 0x49, 0x49, 0x31], // S
+    [0x01, 0x01, 0x7F, 0x01, 0x01], // T
+    [0x3F, 0x40, 0x40, 0x40, 0x3F], // U
+    [0x1F, 0x20, 0x40, 0x20, 0x1F], // V
+    [0x7F, 0x20, 0x18, 0x20, 0x7F], // W
+    [0x63, 0x14, 0x08, 0x14, 0x63], // X
+    [0x03, 0x04, 0x78, 0x04, 0x03], // Y
+    [0x61, 0x51, 0x49, 0x45, 0x43], // Z
+    [0x00, 0x7F, 0x41, 0x41, 0x00], // [
+    [0x02, 0x04, 0x08, 0x10, 0x20], // \
+    [0x00, 0x41, 0x41, 0x7F, 0x00], // ]
+    [0x04, 0x02, 0x01, 0x02, 0x04], // ^
+    [0x40, 0x40, 0x40, 0x40, 0x40], // _
+    [0x00, 0x01, 0x02, 0x04, 0x00], // `
+    [0x20, 0x54, 0x54, 0x54, 0x78], // a
+    [0x7F, 0x48, 0x44, 0x44, 0x38], // b
+    [0x38, 0x44, 0x44, 0x44, 0x20], // c
+    [0x38, 0x44, 0x44, 0x48, 0x7F], // d
+    [0x38, 0x54, 0x54, 0x54, 0x18], // e
+    [0x08, 0x7E, 0x09, 0x01, 0x02], // f
+    [0x08, 0x14, 0x54, 0x54, 0x3C], // g
+    [0x7F, 0x08, 0x04, 0x04, 0x78], // h
+    [0x00, 0x44, 0x7D, 0x40, 0x00], // i
+    [0x20, 0x40, 0x44, 0x3D, 0x00], // j
+    [0x00, 0x7F, 0x10, 0x28, 0x44], // k
+    [0x00, 0x41, 0x7F, 0x40, 0x00], // l
+    [0x7C, 0x04, 0x18, 0x04, 0x78], // m
+    [0x7C, 0x08, 0x04, 0x04, 0x78], // n
+    [0x38, 0x44, 0x44, 0x44, 0x38], // o
+    [0x7C, 0x14, 0x14, 0x14, 0x08], // p
+    [0x08, 0x14, 0x14, 0x18, 0x7C], // q
+    [0x7C, 0x08, 0x04, 0x04, 0x08], // r
+    [0x48, 0x54, 0x54, 0x54, 0x20], // s
+    [0x04, 0x3F, 0x44, 0x40, 0x20], // t
+    [0x3C, 0x40, 0x40, 0x20, 0x7C], // u
+    [0x1C, 0x20, 0x40, 0x20, 0x1C], // v
+    [0x3C, 0x40, 0x30, 0x40, 0x3C], // w
+    [0x44, 0x28, 0x10, 0x28, 0x44], // x
+    [0x0C, 0x50, 0x50, 0x50, 0x3C], // y
+    [0x44, 0x64, 0x54, 0x4C, 0x44], // z
+    [0x00, 0x08, 0x36, 0x41, 0x00], // {
+    [0x00, 0x00, 0x7F, 0x00, 0x00], // |
+    [0x00, 0x41, 0x36, 0x08, 0x00], // }
+    [0x10, 0x08, 0x08, 0x10, 0x08], // ~
+];
+
+fn glyph(c: char) -> &'static [u8; 5] {
+    match c {
+        ' '..='~' => &FONT[c as usize - ' ' as usize],
+        _ => &FONT['?' as usize - ' ' as usize],
+    }
+}
+
+pub struct Ssd1306<H> {
+    i2c_handle: H,
+    i2c_address: u8,
+    height: u8,
+}
+
+impl Ssd1306<I2cHandleType> {
+    pub(crate) fn from_config(
+        cfg: ConfigType,
+        dependencies: Vec<Dependency>,
+    ) -> Result<GenericComponentType, GenericError> {
+        let board = get_board_from_dependencies(dependencies)
+            .ok_or_else(|| GenericError::Other("ssd1306 missing board".into()))?;
+        let i2c_bus = cfg
+            .get_attribute::<String>("i2c_bus")
+            .map_err(|_| GenericError::Other("ssd1306 missing i2c_bus".into()))?;
+        let i2c_address = i2c_address_from_config(&cfg)
+            .map_err(|e| GenericError::Other(e.into()))?
+            .unwrap_or(DEFAULT_I2C_ADDRESS);
+        let height = match cfg.get_attribute::<u8>("height") {
+            Ok(height @ (32 | 64)) => height,
+            Err(AttributeError::KeyNotFound(_)) => DEFAULT_HEIGHT,
+            _ => {
+                return Err(GenericError::Other(
+                    "ssd1306 height should be 32 or 64".into(),
+                ))
+            }
+        };
+        let i2c_handle = board
+            .get_i2c_by_name(i2c_bus)
+            .map_err(|e| GenericError::Other(e.into()))?;
+        let display = Ssd1306::new(i2c_handle, i2c_address, height)
+            .map_err(|e| GenericError::Other(e.into()))?;
+        Ok(Arc::new(Mutex::new(display)))
+    }
+}
+
+impl<H: I2CHandle> Ssd1306<H> {
+    /// Initialize the display (charge pump, addressing mode, orientation) and blank it
+    pub fn new(i2c_handle: H, i2c_address: u8, height: u8) -> Result<Self, I2CErrors> {
+        let mut display = Self {
+            i2c_handle,
+            i2c_address,
+            height,
+        };
+        // display off, clock divide ratio, multiplex ratio, no display offset, start line 0
+        display.send_commands(&[0xAE, 0xD5, 0x80, 0xA8, height - 1, 0xD3, 0x00, 0x40])?;
+        // enable the charge pump, horizontal addressing mode, column 127 mapped to SEG0 and
+        // COM outputs scanned from the bottom
+        display.send_commands(&[0x8D, 0x14, 0x20, 0x00, 0xA1, 0xC8])?;
+        // COM pins configuration, contrast, pre-charge period and VCOMH deselect level
+        let com_pins = if height == 64 { 0x12 } else { 0x02 };
+        display.send_commands(&[0xDA, com_pins, 0x81, 0xCF, 0xD9, 0xF1, 0xDB, 0x40])?;
+        // show the RAM content, not inverted
+        display.send_commands(&[0xA4, 0xA6])?;
+        display.clear()?;
+        display.send_commands(&[0xAF])?; // display on
+        Ok(display)
+    }
+
+    /// Number of text rows of the display
+    pub fn rows(&self) -> usize {
+        self.height as usize / 8
+    }
+
+    fn send_commands(&mut self, commands: &[u8]) -> Result<(), I2CErrors> {
+        let bytes: Vec<u8> = std::iter::once(CONTROL_COMMAND)
+            .chain(commands.iter().copied())
+            .collect();
+        self.i2c_handle.write_i2c(self.i2c_address, &bytes)
+    }
+
+    // a row is one page of the display, 8 pixels high
+    fn write_row_pixels(&mut self, row: usize, pixels: &[u8; WIDTH]) -> Result<(), I2CErrors> {
+        self.send_commands(&[
+            SET_COLUMN_ADDRESS,
+            0,
+            (WIDTH - 1) as u8,
+            SET_PAGE_ADDRESS,
+            row as u8,
+            row as u8,
+        ])?;
+        let bytes: Vec<u8> = std::iter::once(CONTROL_DATA)
+            .chain(pixels.iter().copied())
+            .collect();
+        self.i2c_handle.write_i2c(self.i2c_address, &bytes)
+    }
+
+    pub fn clear(&mut self) -> Result<(), I2CErrors> {
+        for row in 0..self.rows() {
+            self.write_row_pixels(row, &[0; WIDTH])?;
+        }
+        Ok(())
+    }
+
+    /// Write `text` starting on `row`, see the module documentation
+    pub fn write_text(&mut self, text: &str, row: usize) -> Result<(), I2CErrors> {
+        if row >= self.rows() {
+            return Err(I2CErrors::I2CInvalidArgument(
+                "ssd1306 row is out of the display",
+            ));
+        }
+        for (row, line) in (row..self.rows()).zip(text.split('\n')) {
+            let mut pixels = [0; WIDTH];
+            for (column, c) in line.chars().take(COLUMNS).enumerate() {
+                let start = column * CHAR_WIDTH;
+                pixels[start..start + 5].copy_from_slice(glyph(c));
+            }
+            self.write_row_pixels(row, &pixels)?;
+        }
+        Ok(())
+    }
+}
+
+impl<H: I2CHandle> GenericComponent for Ssd1306<H> {}
+
+impl<H: I2CHandle> DoCommand for Ssd1306<H> {
+    fn do_command(
+        &mut self,
+        command_struct: Option<Struct>,
+    ) -> Result<Option<Struct>, GenericError> {
+        let Some(command_struct) = command_struct else {
+            return Ok(None);
+        };
+        let fields = &command_struct.fields;
+        if let Some(text) = fields.get("write_text") {
+            let Some(Kind::StringValue(text)) = &text.kind else {
+                return Err(GenericError::Other(
+                    "ssd1306 write_text expects a string".into(),
+                ));
+            };
+            let row = match fields.get("row").and_then(|v| v.kind.as_ref()) {
+                None => 0,
+                Some(Kind::NumberValue(n)) if *n >= 0.0 && n.fract() == 0.0 => *n as usize,
+                _ => {
+                    return Err(GenericError::Other(
+                        "ssd1306 row should be a positive integer".into(),
+                    ))
+                }
+            };
+            self.write_text(text, row)
+                .map_err(|e| GenericError::Other(e.into()))?;
+        } else if let Some(clear) = fields.get("clear") {
+            if matches!(clear.kind, Some(Kind::BoolValue(true))) {
+                self.clear().map_err(|e| GenericError::Other(e.into()))?;
+            }
+        } else {
+            return Err(GenericError::Other(
+                format!(
+                    "unknown ssd1306 command {:?}",
+                    fields.keys().collect::<Vec<_>>()
+                )
+                .into(),
+            ));
+        }
+        Ok(Some(Struct {
+            fields: HashMap::new(),
+        }))
+    }
+}
+
+impl<H> Status for Ssd1306<H> {
+    fn get_status(&self) -> Result<Option<Struct>, StatusError> {
+        Ok(Some(Struct {
+            fields: HashMap::new(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::test_utils::{assert_do_command, command, do_command};
+    use std::{cell::RefCell, rc::Rc};
+
+    // keeps every transfer written to the display
+    #[derive(Clone, Default)]
+    struct Transfers(Rc<RefCell<Vec<Vec<u8>>>>);
+
+    impl I2CHandle for Transfers {
+        fn name(&self) -> String {
+            "transfers".to_owned()
+        }
+        fn write_i2c(&mut self, address: u8, bytes: &[u8]) -> Result<(), I2CErrors> {
+            assert_eq!(address, DEFAULT_I2C_ADDRESS);
+            self.0.borrow_mut().push(bytes.to_vec());
+            Ok(())
+        }
+    }
+
+    impl Transfers {
+        // pixels written to each row
+        fn rows(&self) -> Vec<(u8, Vec<u8>)> {
+            let transfers = self.0.borrow();
+            transfers
+                .windows(2)
+                .filter(|w| w[0][..2] == [CONTROL_COMMAND, SET_COLUMN_ADDRESS])
+                .map(|w| (w[0][5], w[1][1..].to_vec()))
+                .collect()
+        }
+    }
+
+    #[test_log::test]
+    fn test_ssd1306() {
+        let transfers = Transfers::default();
+        let mut display = Ssd1306::new(transfers.clone(), DEFAULT_I2C_ADDRESS, 32).unwrap();
+        {
+            let init = transfers.0.borrow();
+            assert_eq!(init[0][..5], [CONTROL_COMMAND, 0xAE, 0xD5, 0x80, 0xA8]);
+            assert_eq!(init[0][5], 31);
+            assert_eq!(init.last().unwrap(), &[CONTROL_COMMAND, 0xAF]);
+        }
+        // initialization blanks the 4 rows
+        let rows = transfers.rows();
+        assert_eq!(rows.len(), 4);
+        assert!(rows.iter().all(|(_, pixels)| pixels == &[0; WIDTH]));
+        assert_eq!(display.rows(), 4);
+
+        transfers.0.borrow_mut().clear();
+        assert_do_command(
+            &mut display,
+            command([
+                ("write_text", Kind::StringValue("Hi\nok".to_owned())),
+                ("row", Kind::NumberValue(2.0)),
+            ]),
+            Some(command([])),
+        );
+        let rows = transfers.rows();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].0, 2);
+        assert_eq!(rows[0].1[..5], *glyph('H'));
+        assert_eq!(rows[0].1[6..11], *glyph('i'));
+        assert!(rows[0].1[11..].iter().all(|p| *p == 0));
+        assert_eq!(rows[1].0, 3);
+        assert_eq!(rows[1].1[..5], *glyph('o'));
+
+        // text past the end of the row is dropped
+        transfers.0.borrow_mut().clear();
+        display.write_text(&"x".repeat(30), 0).unwrap();
+        let rows = transfers.rows();
+        assert_eq!(rows[0].1.len(), WIDTH);
+        assert_eq!(rows[0].1[(COLUMNS - 1) * CHAR_WIDTH], glyph('x')[0]);
+        assert!(rows[0].1[COLUMNS * CHAR_WIDTH..].iter().all(|p| *p == 0));
+
+        transfers.0.borrow_mut().clear();
+        assert_do_command(
+            &mut display,
+            command([("clear", Kind::BoolValue(true))]),
+            Some(command([])),
+        );
+        assert_eq!(transfers.rows().len(), 4);
+
+        for invalid in [
+            command([
+                ("write_text", Kind::StringValue("row".to_owned())),
+                ("row", Kind::NumberValue(4.0)),
+            ]),
+            command([("write_text", Kind::NumberValue(1.0))]),
+            command([("invert", Kind::BoolValue(true))]),
+        ] {
+            assert!(do_command(&mut display, invalid).is_err());
+        }
+        assert_eq!(glyph('é'), glyph('?'));
+    }
+}
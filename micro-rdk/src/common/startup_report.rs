@@ -0,0 +1,197 @@
+//! Summary of the state of the device logged once the robot is built, the log line is uploaded to
+//! app with the other logs.
+
+use std::fmt::{self, Display};
+use std::net::Ipv4Addr;
+
+use super::{build_info, conn::network::Network, robot::LocalRobot};
+
+/// Where the config the robot was built from came from
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConfigSource {
+    /// Received from app
+    App,
+    /// Cached in storage, app couldn't be reached
+    Cache,
+    /// Cached in storage and pinned, see `ViamServerBuilder::with_pinned_config`
+    Pinned,
+    /// Given to `ViamServerBuilder::local_only`
+    LocalOnly,
+    /// Neither app nor storage had a config, the robot is empty
+    Empty,
+}
+
+impl Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::App => "app",
+            Self::Cache => "cache",
+            Self::Pinned => "pinned cache",
+            Self::LocalOnly => "local only",
+            Self::Empty => "none",
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct StartupReport {
+    pub config_source: ConfigSource,
+    /// Why the robot couldn't be built from the config, an empty robot is then running
+    pub build_error: Option<String>,
+    /// `subtype:name` of the resources that were built
+    pub resources: Vec<String>,
+    /// Name of the resources that failed to build along with the reason
+    pub failed_resources: Vec<(String, String)>,
+    pub network_connected: bool,
+    pub ip: Ipv4Addr,
+    /// Free heap in bytes, None when unknown (native)
+    pub free_heap: Option<usize>,
+}
+
+impl StartupReport {
+    pub(crate) fn new(
+        robot: &LocalRobot,
+        network: &dyn Network,
+        config_source: ConfigSource,
+        build_error: Option<String>,
+    ) -> Self {
+        let mut resources: Vec<String> = robot
+            .get_resource_names()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|name| format!("{}:{}", name.subtype, name.name))
+            .collect();
+        resources.sort();
+        Self {
+            config_source,
+            build_error,
+            resources,
+            failed_resources: robot.build_failures().to_vec(),
+            network_connected: network.is_connected().unwrap_or(false),
+            ip: network.get_ip(),
+            free_heap: free_heap(),
+        }
+    }
+
+    pub fn is_healthy(&self) -> bool {
+        self.build_error.is_none()
+            && self.failed_resources.is_empty()
+            && self.network_connected
+            && self.config_source != ConfigSource::Empty
+    }
+
+    pub(crate) fn log(&self) {
+        if self.is_healthy() {
+            log::info!("{}", self);
+        } else {
+            log::warn!("{}", self);
+        }
+    }
+}
+
+impl Display for StartupReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "startup report ({})",
+            if self.is_healthy() { "ok" } else { "degraded" }
+        )?;
+        writeln!(
+            f,
+            "  firmware: {} ({})",
+            build_info::VERSION,
+            build_info::GIT_REVISION
+        )?;
+        writeln!(f, "  config: {}", self.config_source)?;
+        if let Some(err) = &self.build_error {
+            writeln!(f, "  robot: failed to build ({})", err)?;
+        }
+        writeln!(
+            f,
+            "  network: {}, ip {}",
+            if self.network_connected {
+                "connected"
+            } else {
+                "disconnected"
+            },
+            self.ip
+        )?;
+        match self.free_heap {
+            Some(free) => writeln!(f, "  free heap: {} bytes", free)?,
+            None => writeln!(f, "  free heap: unknown")?,
+        }
+        write!(
+            f,
+            "  resources: {} built [{}], {} failed",
+            self.resources.len(),
+            self.resources.join(", "),
+            self.failed_resources.len()
+        )?;
+        for (name, reason) in &self.failed_resources {
+            write!(f, "\n    `{}`: {}", name, reason)?;
+        }
+        Ok(())
+    }
+}
+
+fn free_heap() -> Option<usize> {
+    #[cfg(feature = "esp32")]
+    {
+        Some(unsafe { crate::esp32::esp_idf_svc::sys::esp_get_free_heap_size() } as usize)
+    }
+    #[cfg(not(feature = "esp32"))]
+    {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_log::test]
+    fn test_startup_report_display() {
+        let mut report = StartupReport {
+            config_source: ConfigSource::App,
+            build_error: None,
+            resources: vec!["board:board".to_owned(), "sensor:temp".to_owned()],
+            failed_resources: vec![],
+            network_connected: true,
+            ip: Ipv4Addr::new(10, 1, 2, 3),
+            free_heap: Some(1234),
+        };
+        assert!(report.is_healthy());
+        let text = report.to_string();
+        assert!(text.starts_with("startup report (ok)\n"));
+        assert!(text.contains("  config: app\n"));
+        assert!(text.contains("  network: connected, ip 10.1.2.3\n"));
+        assert!(text.contains("  free heap: 1234 bytes\n"));
+        assert!(text.ends_with("  resources: 2 built [board:board, sensor:temp], 0 failed"));
+
+        report
+            .failed_resources
+            .push(("motor".to_owned(), "model not registered".to_owned()));
+        report.config_source = ConfigSource::Cache;
+        assert!(!report.is_healthy());
+        let text = report.to_string();
+        assert!(text.starts_with("startup report (degraded)\n"));
+        assert!(text.contains("  config: cache\n"));
+        assert!(text.ends_with("1 failed\n    `motor`: model not registered"));
+
+        // the robot couldn't be built at all, it runs without any resource
+        let report = StartupReport {
+            config_source: ConfigSource::App,
+            build_error: Some("missing board".to_owned()),
+            resources: vec![],
+            failed_resources: vec![],
+            network_connected: true,
+            ip: Ipv4Addr::new(10, 1, 2, 3),
+            free_heap: None,
+        };
+        assert!(!report.is_healthy());
+        let text = report.to_string();
+        assert!(text.starts_with("startup report (degraded)\n"));
+        assert!(text.contains("  config: app\n  robot: failed to build (missing board)\n"));
+        assert!(text.ends_with("  resources: 0 built [], 0 failed"));
+    }
+}
@@ -4,12 +4,18 @@ use crate::google;
 
 use thiserror::Error;
 
+use super::actuator::ActuatorError;
+use super::board::BoardError;
 use super::encoder::EncoderError;
 
 #[derive(Error, Debug)]
 pub enum StatusError {
     #[error(transparent)]
     EncoderError(#[from] EncoderError),
+    #[error(transparent)]
+    BoardError(#[from] BoardError),
+    #[error(transparent)]
+    ActuatorError(#[from] ActuatorError),
 }
 
 pub trait Status {
@@ -0,0 +1,689 @@
+//! A stepper motor driven through the step and direction inputs of a driver board (A4988,
+//! DRV8825, TMC2208...).
+//!
+//! Steps are pulses on the step pin made by a task on the local executor, following a
+//! trapezoidal speed profile (ramping up to the requested speed at `acceleration` steps/s² and
+//! back down before the target). Each time the task wakes up it makes the steps the profile says
+//! are due, so at rates above the timer resolution steps come in short bursts, and the position
+//! is the count of the steps actually made. The fake board tracks the same virtual position.
+//!
+//! # Configuration
+//! ```json
+//! {
+//!     "pins": { "step": 12, "dir": 13, "enable": 14 },
+//!     "steps_per_rev": 200,
+//!     "max_steps_per_sec": 1000,
+//!     "acceleration": 2000
+//! }
+//! ```
+//! The enable pin is optional and active low (as on most driver boards), the driver is enabled
+//! when a move starts and disabled by `stop`. So is the sleep pin, the driver is woken up when a
+//! move starts and put to sleep by `stop`, while the reset pin is held high.
+//!
+//! # Microstepping
+//! `microsteps` (default 1) divides each full step. When the `ms1`, `ms2` (and `ms3`) pins are
+//! configured they are set to select that mode on the `driver` board (`a4988`, the default,
+//! `drv8825` or `tmc2208`), otherwise the mode is assumed to be set by jumpers. `steps_per_rev`,
+//! `max_steps_per_sec` and `acceleration` stay expressed in full steps, the effective number of
+//! steps per revolution being `steps_per_rev * microsteps`.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use async_executor::Task;
+use async_io::Timer;
+
+use super::{
+    actuator::{Actuator, ActuatorError},
+    board::{Board, BoardType},
+    config::{AttributeError, ConfigType, Kind},
+    exec::Executor,
+    motor::{Motor, MotorError, MotorSupportedProperties, MotorType},
+    registry::{get_board_from_dependencies, ComponentRegistry, Dependency},
+    status::{Status, StatusError},
+};
+use crate::google;
+
+/// Default number of full steps per revolution, 1.8° steppers are the most common
+const DEFAULT_STEPS_PER_REV: u32 = 200;
+const DEFAULT_MAX_STEPS_PER_SEC: f64 = 1000.0;
+/// Longest time the step task sleeps between two checks of the steps due
+const STEP_INTERVAL: Duration = Duration::from_millis(10);
+
+pub(crate) fn register_models(registry: &mut ComponentRegistry) {
+    if registry
+        .register_motor("stepper", &StepperMotor::<BoardType>::from_config)
+        .is_err()
+    {
+        log::error!("stepper model is already registered")
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct StepperPinsConfig {
+    pub step: i32,
+    pub dir: i32,
+    pub enable: Option<i32>,
+    pub ms1: Option<i32>,
+    pub ms2: Option<i32>,
+    pub ms3: Option<i32>,
+    pub sleep: Option<i32>,
+    pub reset: Option<i32>,
+}
+
+impl TryFrom<&Kind> for StepperPinsConfig {
+    type Error = AttributeError;
+    fn try_from(value: &Kind) -> Result<Self, Self::Error> {
+        let pin = |name: &str| -> Result<Option<i32>, AttributeError> {
+            match value.get(name) {
+                Ok(Some(val)) => Ok(Some(val.try_into()?)),
+                Ok(None) | Err(AttributeError::KeyNotFound(_)) => Ok(None),
+                Err(err) => Err(err),
+            }
+        };
+        Ok(Self {
+            step: pin("step")?.ok_or(AttributeError::KeyNotFound("step".to_string()))?,
+            dir: pin("dir")?.ok_or(AttributeError::KeyNotFound("dir".to_string()))?,
+            enable: pin("enable")?,
+            ms1: pin("ms1")?,
+            ms2: pin("ms2")?,
+            ms3: pin("ms3")?,
+            sleep: pin("sleep")?,
+            reset: pin("reset")?,
+        })
+    }
+}
+
+/// Driver boards whose microstep mode is selected with the MS pins
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum MicrostepDriver {
+    #[default]
+    A4988,
+    Drv8825,
+    Tmc2208,
+}
+
+impl TryFrom<&str> for MicrostepDriver {
+    type Error = MotorError;
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "a4988" => Ok(Self::A4988),
+            "drv8825" => Ok(Self::Drv8825),
+            "tmc2208" => Ok(Self::Tmc2208),
+            _ => Err(MotorError::ConfigError(
+                "stepper, driver should be one of a4988, drv8825 or tmc2208",
+            )),
+        }
+    }
+}
+
+impl MicrostepDriver {
+    // levels of MS1, MS2 and MS3 (M0, M1 and M2 on the DRV8825) selecting `microsteps`
+    fn pin_levels(&self, microsteps: u32) -> Option<[bool; 3]> {
+        Some(match (self, microsteps) {
+            (Self::A4988, 1) | (Self::Drv8825, 1) => [false, false, false],
+            (Self::A4988, 2) | (Self::Drv8825, 2) | (Self::Tmc2208, 2) => [true, false, false],
+            (Self::A4988, 4) | (Self::Drv8825, 4) | (Self::Tmc2208, 4) => [false, true, false],
+            (Self::A4988, 8) | (Self::Drv8825, 8) => [true, true, false],
+            (Self::A4988, 16) | (Self::Tmc2208, 16) => [true, true, true],
+            (Self::Drv8825, 16) => [false, false, true],
+            (Self::Drv8825, 32) => [true, false, true],
+            (Self::Tmc2208, 8) => [false, false, false],
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct StepperSettings {
+    pub pins: StepperPinsConfig,
+    pub steps_per_rev: u32,
+    /// Fastest step rate the motor is driven at
+    pub max_steps_per_sec: f64,
+    /// Acceleration and deceleration in steps/s², 0 to start and stop at full speed
+    pub acceleration: f64,
+    pub dir_flip: bool,
+    /// Microsteps per full step
+    pub microsteps: u32,
+    pub driver: MicrostepDriver,
+}
+
+impl StepperSettings {
+    pub fn from_config(cfg: &ConfigType) -> Result<Self, MotorError> {
+        let pins = cfg
+            .get_attribute::<StepperPinsConfig>("pins")
+            .map_err(|_| MotorError::ConfigError("stepper, need 'step' and 'dir' pins"))?;
+        Ok(Self {
+            pins,
+            steps_per_rev: cfg
+                .get_attribute::<u32>("steps_per_rev")
+                .unwrap_or(DEFAULT_STEPS_PER_REV),
+            max_steps_per_sec: cfg
+                .get_attribute::<f64>("max_steps_per_sec")
+                .unwrap_or(DEFAULT_MAX_STEPS_PER_SEC),
+            acceleration: cfg.get_attribute::<f64>("acceleration").unwrap_or_default(),
+            dir_flip: cfg.get_attribute::<bool>("dir_flip").unwrap_or_default(),
+            microsteps: cfg.get_attribute::<u32>("microsteps").unwrap_or(1),
+            driver: cfg
+                .get_attribute::<String>("driver")
+                .map_or(Ok(Default::default()), |driver| driver.as_str().try_into())?,
+        })
+    }
+}
+
+// Trapezoidal speed profile of a move of `steps` steps, or of a continuous move when None
+#[derive(Clone, Debug)]
+struct StepProfile {
+    steps: Option<f64>,
+    rate: f64,
+    acceleration: f64,
+    // time spent accelerating (and decelerating), steps made meanwhile
+    ramp_time: f64,
+    ramp_steps: f64,
+}
+
+impl StepProfile {
+    fn new(steps: Option<f64>, rate: f64, acceleration: f64) -> Self {
+        let (mut rate, mut ramp_time, mut ramp_steps) = (rate, 0.0, 0.0);
+        if acceleration > 0.0 {
+            ramp_time = rate / acceleration;
+            ramp_steps = 0.5 * acceleration * ramp_time * ramp_time;
+            // too short to reach full speed, the profile is a triangle
+            if let Some(steps) = steps.filter(|steps| 2.0 * ramp_steps > *steps) {
+                rate = (steps * acceleration).sqrt();
+                ramp_time = rate / acceleration;
+                ramp_steps = steps / 2.0;
+            }
+        }
+        Self {
+            steps,
+            rate,
+            acceleration,
+            ramp_time,
+            ramp_steps,
+        }
+    }
+
+    // time at which deceleration starts
+    fn cruise_end(&self) -> f64 {
+        self.steps.map_or(f64::INFINITY, |steps| {
+            self.ramp_time + (steps - 2.0 * self.ramp_steps) / self.rate
+        })
+    }
+
+    fn duration(&self) -> Option<Duration> {
+        self.steps
+            .map(|_| Duration::from_secs_f64(self.cruise_end() + self.ramp_time))
+    }
+
+    fn steps_at(&self, t: f64) -> f64 {
+        let cruise_end = self.cruise_end();
+        if t < self.ramp_time {
+            0.5 * self.acceleration * t * t
+        } else if t < cruise_end {
+            self.ramp_steps + self.rate * (t - self.ramp_time)
+        } else if t < cruise_end + self.ramp_time {
+            let left = cruise_end + self.ramp_time - t;
+            self.steps.unwrap_or_default() - 0.5 * self.acceleration * left * left
+        } else {
+            self.steps.unwrap_or_default()
+        }
+    }
+
+    fn rate_at(&self, t: f64) -> f64 {
+        let cruise_end = self.cruise_end();
+        if t < self.ramp_time {
+            self.acceleration * t
+        } else if t < cruise_end {
+            self.rate
+        } else if t < cruise_end + self.ramp_time {
+            self.acceleration * (cruise_end + self.ramp_time - t)
+        } else {
+            0.0
+        }
+    }
+}
+
+// Position (in steps made) and direction of the move in progress, shared with the step task
+#[derive(Default)]
+struct StepperState {
+    position: i64,
+    // forward when true, None at rest
+    moving: Option<bool>,
+}
+
+impl StepperState {
+    fn step(&mut self) {
+        if let Some(forward) = self.moving {
+            self.position += if forward { 1 } else { -1 };
+        }
+    }
+    fn is_moving(&self) -> bool {
+        self.moving.is_some()
+    }
+    fn halt(&mut self) {
+        self.moving = None;
+    }
+}
+
+#[derive(DoCommand)]
+pub struct StepperMotor<B> {
+    board: B,
+    settings: StepperSettings,
+    state: Arc<Mutex<StepperState>>,
+    ramp: Option<Task<()>>,
+}
+
+impl StepperMotor<BoardType> {
+    pub(crate) fn from_config(
+        cfg: ConfigType,
+        deps: Vec<Dependency>,
+    ) -> Result<MotorType, MotorError> {
+        let board = get_board_from_dependencies(deps)
+            .ok_or(MotorError::ConfigError("missing board dependency"))?;
+        let settings = StepperSettings::from_config(&cfg)?;
+        Ok(Arc::new(Mutex::new(StepperMotor::new(board, settings)?)))
+    }
+}
+
+impl<B> StepperMotor<B>
+where
+    B: Board + Clone + 'static,
+{
+    pub fn new(mut board: B, settings: StepperSettings) -> Result<Self, MotorError> {
+        if settings.steps_per_rev == 0
+            || settings.microsteps == 0
+            || settings.max_steps_per_sec <= 0.0
+        {
+            return Err(MotorError::ConfigError(
+                "stepper, steps_per_rev, microsteps and max_steps_per_sec must be positive",
+            ));
+        }
+        let ms_pins = [settings.pins.ms1, settings.pins.ms2, settings.pins.ms3];
+        if ms_pins.iter().any(Option::is_some) {
+            let levels =
+                settings
+                    .driver
+                    .pin_levels(settings.microsteps)
+                    .ok_or(MotorError::ConfigError(
+                        "stepper, microsteps not supported by the driver",
+                    ))?;
+            for (pin, level) in ms_pins.into_iter().zip(levels) {
+                if let Some(pin) = pin {
+                    board.set_gpio_pin_level(pin, level)?;
+                }
+            }
+        }
+        if let Some(pin) = settings.pins.reset {
+            // active low
+            board.set_gpio_pin_level(pin, true)?;
+        }
+        board.set_gpio_pin_level(settings.pins.step, false)?;
+        let mut motor = Self {
+            board,
+            settings,
+            state: Default::default(),
+            ramp: None,
+        };
+        motor.set_enabled(false)?;
+        Ok(motor)
+    }
+
+    /// Steps (microsteps) made since creation, backward steps counting negatively
+    pub fn get_position_steps(&self) -> i64 {
+        self.state.lock().unwrap().position
+    }
+
+    /// Effective number of steps per revolution, accounting for microstepping
+    pub fn effective_steps_per_rev(&self) -> u32 {
+        self.settings.steps_per_rev * self.settings.microsteps
+    }
+
+    fn steps_per_rev(&self) -> f64 {
+        self.effective_steps_per_rev() as f64
+    }
+
+    // max_steps_per_sec and acceleration in microsteps
+    fn max_rate(&self) -> f64 {
+        self.settings.max_steps_per_sec * self.settings.microsteps as f64
+    }
+    fn acceleration(&self) -> f64 {
+        self.settings.acceleration * self.settings.microsteps as f64
+    }
+
+    fn set_enabled(&mut self, enabled: bool) -> Result<(), MotorError> {
+        // enable and sleep are both active low
+        if let Some(pin) = self.settings.pins.enable {
+            self.board.set_gpio_pin_level(pin, !enabled)?;
+        }
+        if let Some(pin) = self.settings.pins.sleep {
+            self.board.set_gpio_pin_level(pin, enabled)?;
+        }
+        Ok(())
+    }
+
+    fn halt(&mut self) -> Result<(), MotorError> {
+        let _ = self.ramp.take();
+        self.state.lock().unwrap().halt();
+        self.board
+            .set_gpio_pin_level(self.settings.pins.step, false)?;
+        Ok(())
+    }
+
+    // Start moving by `steps` steps (indefinitely when None) at up to `rpm`, returns how long the
+    // move will take
+    fn start_motion(
+        &mut self,
+        rpm: f64,
+        steps: Option<f64>,
+        forward: bool,
+    ) -> Result<Option<Duration>, MotorError> {
+        self.halt()?;
+        let rate = (rpm.abs() * self.steps_per_rev() / 60.0).min(self.max_rate());
+        if rate == 0.0 || steps == Some(0.0) {
+            return Ok(None);
+        }
+        let profile = StepProfile::new(steps, rate, self.acceleration());
+        let duration = profile.duration();
+
+        self.set_enabled(true)?;
+        self.board
+            .set_gpio_pin_level(self.settings.pins.dir, forward != self.settings.dir_flip)?;
+        let _ = self.state.lock().unwrap().moving.insert(forward);
+        self.ramp = Some(Executor::new().spawn(make_steps(
+            self.board.clone(),
+            self.settings.pins.step,
+            profile,
+            self.state.clone(),
+        )));
+        Ok(duration)
+    }
+}
+
+// Pulse the step pin following the speed profile until the move ends, counting each step made
+async fn make_steps<B: Board>(
+    mut board: B,
+    step_pin: i32,
+    profile: StepProfile,
+    state: Arc<Mutex<StepperState>>,
+) {
+    let start = Instant::now();
+    let mut made = 0_u64;
+    loop {
+        let elapsed = start.elapsed().as_secs_f64();
+        let due = profile.steps_at(elapsed).floor() as u64;
+        while made < due {
+            if let Err(e) = board
+                .set_gpio_pin_level(step_pin, true)
+                .and_then(|_| board.set_gpio_pin_level(step_pin, false))
+            {
+                log::error!("stepper failed to make a step: {}", e);
+                state.lock().unwrap().halt();
+                return;
+            }
+            made += 1;
+            state.lock().unwrap().step();
+        }
+        if profile.steps.is_some_and(|steps| made as f64 >= steps) {
+            state.lock().unwrap().halt();
+            return;
+        }
+        // wake up for the next step, ramps start from one step per second
+        let rate = profile.rate_at(elapsed).max(1.0);
+        Timer::after(Duration::from_secs_f64(1.0 / rate).min(STEP_INTERVAL)).await;
+    }
+}
+
+impl<B> Motor for StepperMotor<B>
+where
+    B: Board + Clone + 'static,
+{
+    fn set_power(&mut self, pct: f64) -> Result<(), MotorError> {
+        if !(-1.0..=1.0).contains(&pct) {
+            return Err(MotorError::PowerSetError);
+        }
+        let rpm = pct * self.max_rate() * 60.0 / self.steps_per_rev();
+        let _ = self.start_motion(rpm, None, pct > 0.0)?;
+        Ok(())
+    }
+
+    /// Whole revolutions made since creation
+    fn get_position(&mut self) -> Result<i32, MotorError> {
+        Ok(self.get_position_revolutions()? as i32)
+    }
+
+    /// Revolutions made since creation, from the steps counted
+    fn get_position_revolutions(&mut self) -> Result<f64, MotorError> {
+        Ok(self.get_position_steps() as f64 / self.steps_per_rev())
+    }
+
+    fn go_for(&mut self, rpm: f64, revolutions: f64) -> Result<Option<Duration>, MotorError> {
+        let forward = (rpm > 0.0) == (revolutions >= 0.0);
+        let steps =
+            (revolutions != 0.0).then(|| (revolutions * self.steps_per_rev()).abs().round());
+        self.start_motion(rpm, steps, forward)
+    }
+
+    /// Moves to `position_revolutions` relative to the position at creation
+    fn go_to(
+        &mut self,
+        rpm: f64,
+        position_revolutions: f64,
+    ) -> Result<Option<Duration>, MotorError> {
+        let target = (position_revolutions * self.steps_per_rev()).round();
+        let delta = target - self.get_position_steps() as f64;
+        self.start_motion(rpm, Some(delta.abs()), delta > 0.0)
+    }
+
+    fn get_properties(&mut self) -> MotorSupportedProperties {
+        MotorSupportedProperties {
+            position_reporting: true,
+        }
+    }
+}
+
+impl<B> Actuator for StepperMotor<B>
+where
+    B: Board + Clone + 'static,
+{
+    fn is_moving(&mut self) -> Result<bool, ActuatorError> {
+        Ok(self.state.lock().unwrap().is_moving())
+    }
+    fn stop(&mut self) -> Result<(), ActuatorError> {
+        self.halt()
+            .and_then(|_| self.set_enabled(false))
+            .map_err(|_| ActuatorError::CouldntStop)
+    }
+}
+
+impl<B> Status for StepperMotor<B>
+where
+    B: Board + Clone + 'static,
+{
+    fn get_status(&self) -> Result<Option<google::protobuf::Struct>, StatusError> {
+        let mut hm = HashMap::new();
+        hm.insert(
+            "position".to_string(),
+            google::protobuf::Value {
+                kind: Some(google::protobuf::value::Kind::NumberValue(
+                    self.get_position_steps() as f64 / self.steps_per_rev(),
+                )),
+            },
+        );
+        hm.insert(
+            "steps_per_rev".to_string(),
+            google::protobuf::Value {
+                kind: Some(google::protobuf::value::Kind::NumberValue(
+                    self.steps_per_rev(),
+                )),
+            },
+        );
+        Ok(Some(google::protobuf::Struct { fields: hm }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use async_io::Timer;
+
+    use super::{MicrostepDriver, StepProfile, StepperMotor, StepperPinsConfig, StepperSettings};
+    use crate::common::{
+        actuator::Actuator,
+        board::{Board, FakeBoard},
+        exec::Executor,
+        motor::{Motor, MotorError},
+    };
+
+    fn settings(acceleration: f64) -> StepperSettings {
+        StepperSettings {
+            pins: StepperPinsConfig {
+                step: 12,
+                dir: 13,
+                enable: Some(14),
+                ..Default::default()
+            },
+            steps_per_rev: 200,
+            max_steps_per_sec: 1000.0,
+            acceleration,
+            dir_flip: false,
+            microsteps: 1,
+            driver: MicrostepDriver::A4988,
+        }
+    }
+
+    #[test_log::test]
+    fn test_step_profile() {
+        // 1000 steps at 500 steps/s accelerating at 1000 steps/s²: 0.5s ramps of 125 steps
+        let profile = StepProfile::new(Some(1000.0), 500.0, 1000.0);
+        assert_eq!(profile.duration().unwrap().as_secs_f64(), 2.5);
+        assert_eq!(profile.steps_at(0.5), 125.0);
+        assert_eq!(profile.rate_at(1.0), 500.0);
+        assert_eq!(profile.steps_at(2.0), 875.0);
+        assert_eq!(profile.rate_at(2.25), 250.0);
+        assert_eq!(profile.steps_at(3.0), 1000.0);
+        assert_eq!(profile.rate_at(3.0), 0.0);
+
+        // too short to reach full speed
+        let profile = StepProfile::new(Some(100.0), 500.0, 1000.0);
+        assert!(profile.rate_at(profile.duration().unwrap().as_secs_f64() / 2.0) < 500.0);
+        assert!((profile.steps_at(profile.duration().unwrap().as_secs_f64()) - 100.0).abs() < 1e-9);
+
+        // no acceleration
+        let profile = StepProfile::new(Some(1000.0), 500.0, 0.0);
+        assert_eq!(profile.duration().unwrap().as_secs_f64(), 2.0);
+        assert_eq!(profile.rate_at(0.0), 500.0);
+
+        // continuous
+        let profile = StepProfile::new(None, 500.0, 1000.0);
+        assert!(profile.duration().is_none());
+        assert_eq!(profile.steps_at(10.0), 125.0 + 500.0 * 9.5);
+    }
+
+    // drive the step task until the move is over
+    fn wait_stopped<B: Board + Clone + 'static>(motor: &mut StepperMotor<B>) {
+        Executor::new().block_on(async {
+            while motor.is_moving().unwrap() {
+                Timer::after(Duration::from_millis(5)).await;
+            }
+        });
+    }
+
+    #[test_log::test]
+    fn test_stepper_motor() -> Result<(), MotorError> {
+        let board = Arc::new(Mutex::new(FakeBoard::new(vec![])));
+        let mut motor = StepperMotor::new(board.clone(), settings(0.0))?;
+        assert!(!motor.is_moving()?);
+
+        // half a revolution backward, the speed is capped by max_steps_per_sec
+        let duration = motor.go_for(-600.0, 0.5)?;
+        assert_eq!(duration.unwrap().as_secs_f64(), 0.1);
+        assert!(motor.is_moving()?);
+        wait_stopped(&mut motor);
+        assert_eq!(motor.get_position_steps(), -100);
+        assert_eq!(motor.get_position()?, 0);
+        assert_eq!(motor.get_position_revolutions()?, -0.5);
+
+        // stopped before the step task ran, no step was made
+        let _ = motor.go_for(60.0, 2.0)?;
+        motor.stop()?;
+        assert!(!motor.is_moving()?);
+        wait_stopped(&mut motor);
+        assert_eq!(motor.get_position_steps(), -100);
+
+        // go_to is relative to the position at creation
+        let duration = motor.go_to(600.0, 0.25)?;
+        assert_eq!(duration.unwrap().as_secs_f64(), 0.15);
+        wait_stopped(&mut motor);
+        assert_eq!(motor.get_position_steps(), 50);
+        assert_eq!(motor.go_to(600.0, 0.25)?, None);
+
+        assert!(motor.set_power(1.5).is_err());
+        Ok(())
+    }
+
+    #[test_log::test]
+    fn test_stepper_ramp_and_power() -> Result<(), MotorError> {
+        let board = Arc::new(Mutex::new(FakeBoard::new(vec![])));
+        let mut motor = StepperMotor::new(board.clone(), settings(20000.0))?;
+        // every step of the ramps is made
+        let _ = motor.go_for(300.0, 1.0)?;
+        wait_stopped(&mut motor);
+        assert_eq!(motor.get_position_steps(), 200);
+
+        // continuous move until stopped
+        motor.set_power(-0.5)?;
+        Executor::new().block_on(async {
+            while motor.get_position_steps() > 190 {
+                Timer::after(Duration::from_millis(5)).await;
+            }
+        });
+        assert!(motor.is_moving()?);
+        motor.stop()?;
+        let position = motor.get_position_steps();
+        Executor::new().block_on(Timer::after(Duration::from_millis(20)));
+        assert_eq!(motor.get_position_steps(), position);
+        Ok(())
+    }
+
+    #[test_log::test]
+    fn test_stepper_microstepping() -> Result<(), MotorError> {
+        let board = Arc::new(Mutex::new(FakeBoard::new(vec![])));
+        let mut settings = settings(0.0);
+        settings.microsteps = 16;
+        settings.pins.ms1 = Some(15);
+        settings.pins.ms2 = Some(16);
+        settings.pins.ms3 = Some(17);
+        let mut motor = StepperMotor::new(board.clone(), settings)?;
+        assert_eq!(motor.effective_steps_per_rev(), 3200);
+
+        // 1 revolution at 60rpm is 3200 microsteps/s, under the 16000 microsteps/s limit
+        let duration = motor.go_for(60.0, 1.0)?;
+        assert_eq!(duration.unwrap().as_secs_f64(), 1.0);
+        motor.stop()?;
+
+        // 32 microsteps are only supported by the DRV8825
+        let mut settings = super::tests::settings(0.0);
+        settings.microsteps = 32;
+        settings.pins.ms1 = Some(15);
+        assert!(StepperMotor::new(board.clone(), settings).is_err());
+        let mut settings = super::tests::settings(0.0);
+        settings.microsteps = 32;
+        settings.pins.ms1 = Some(15);
+        settings.driver = MicrostepDriver::Drv8825;
+        assert!(StepperMotor::new(board.clone(), settings).is_ok());
+        assert_eq!(
+            MicrostepDriver::Drv8825.pin_levels(32),
+            Some([true, false, true])
+        );
+        assert!(MicrostepDriver::Tmc2208.pin_levels(1).is_none());
+        Ok(())
+    }
+}
@@ -0,0 +1,471 @@
+//! Helpers to test drivers without running a server, enabled by the `test-utils` feature so that
+//! modular drivers can use them from their own tests.
+//!
+//! - [`build_resource`] and [`assert_do_command`] exercise the DoCommand handling of a driver
+//! - [`InMemoryGrpcClient`] calls the gRPC API of a robot end to end without sockets
+//!
+//! See the tests of the water pump in the modular drivers example:
+//!
+//! ```ignore
+//! use micro_rdk::common::config::Kind;
+//! use micro_rdk::common::test_utils::{assert_do_command, build_resource, command, fake_board_dependency};
+//! use micro_rdk::google::protobuf::value;
+//!
+//! let mut pump = build_resource(
+//!     WaterPump::from_config,
+//!     [("pin", Kind::NumberValue(12.0))],
+//!     vec![fake_board_dependency()],
+//! )
+//! .unwrap();
+//! assert_do_command(
+//!     &mut pump,
+//!     command([("get_pins", value::Kind::NullValue(0))]),
+//!     Some(command([("pin", value::Kind::NumberValue(12.0))])),
+//! );
+//! ```
+
+use std::{
+    cell::RefCell,
+    collections::{HashMap, VecDeque},
+    pin::Pin,
+    rc::Rc,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+};
+
+use async_executor::Task;
+use bytes::{BufMut, BytesMut};
+use http_body_util::{BodyExt, Full};
+use hyper::{rt, server::conn::http2, Uri};
+use prost::Message;
+
+use super::{
+    board::FakeBoard,
+    config::{ConfigType, DynamicComponentConfig, Kind},
+    exec::Executor,
+    generic::{DoCommand, GenericError},
+    grpc::{GrpcBody, GrpcServer},
+    grpc_client::{GrpcClient, GrpcClientError},
+    registry::{Dependency, ResourceKey},
+    robot::{LocalRobot, Resource},
+};
+use crate::google::protobuf::{value, Struct, Value};
+
+/// A config for a component of model `fake` with the given attributes
+pub fn component_config<'a>(
+    attributes: impl IntoIterator<Item = (&'a str, Kind)>,
+) -> DynamicComponentConfig {
+    DynamicComponentConfig {
+        name: "test".to_owned(),
+        model: "fake".to_owned(),
+        attributes: Some(
+            attributes
+                .into_iter()
+                .map(|(key, value)| (key.to_owned(), value))
+                .collect(),
+        ),
+        ..Default::default()
+    }
+}
+
+/// Build a resource with `constructor` (usually a driver's `from_config`) from the given
+/// attributes and dependencies
+pub fn build_resource<'a, R, E>(
+    constructor: impl FnOnce(ConfigType, Vec<Dependency>) -> Result<R, E>,
+    attributes: impl IntoIterator<Item = (&'a str, Kind)>,
+    dependencies: Vec<Dependency>,
+) -> Result<R, E> {
+    let config = component_config(attributes);
+    constructor(ConfigType::Dynamic(&config), dependencies)
+}
+
+/// A dependency on a [`FakeBoard`] named `board`
+pub fn fake_board_dependency() -> Dependency {
+    Dependency(
+        ResourceKey::new("board", "board"),
+        Resource::Board(Arc::new(Mutex::new(FakeBoard::new(vec![])))),
+    )
+}
+
+/// A DoCommand payload (or response) from key values
+pub fn command<'a>(fields: impl IntoIterator<Item = (&'a str, value::Kind)>) -> Struct {
+    Struct {
+        fields: fields
+            .into_iter()
+            .map(|(key, kind)| (key.to_owned(), Value { kind: Some(kind) }))
+            .collect::<HashMap<_, _>>(),
+    }
+}
+
+/// Run `payload` through the DoCommand of `resource`
+pub fn do_command<R: DoCommand + ?Sized>(
+    resource: &mut R,
+    payload: Struct,
+) -> Result<Option<Struct>, GenericError> {
+    resource.do_command(Some(payload))
+}
+
+/// Run `payload` through the DoCommand of `resource` and assert that it answers `expected`
+#[track_caller]
+pub fn assert_do_command<R: DoCommand + ?Sized>(
+    resource: &mut R,
+    payload: Struct,
+    expected: Option<Struct>,
+) {
+    match do_command(resource, payload.clone()) {
+        Ok(response) => assert_eq!(
+            response, expected,
+            "unexpected response to {:?}",
+            payload.fields
+        ),
+        Err(e) => panic!("do_command {:?} failed: {}", payload.fields, e),
+    }
+}
+
+/// Defines `$recording`, a component recording the name of every method called on it, and the
+/// test `$test` checking that `$wrappers` (e.g. `Mutex::new`) forward every method of the
+/// component to it when called by `calls` through `dyn $component`.
+///
+/// The methods of every trait the component implements are listed, including those with a
+/// default implementation since a wrapper missing one of them would silently fall back to the
+/// default, and called in the listed order. `Status` and `DoCommand` are implemented and checked
+/// for every component.
+#[cfg(test)]
+macro_rules! delegation_test {
+    (
+        $test:ident, $recording:ident as dyn $component:path,
+        wrappers: [$($wrapper:expr),+ $(,)?],
+        $(
+            impl $trait:path {
+                $(
+                    $(#[$attr:meta])*
+                    fn $method:ident $params:tt -> $ret:ty $body:block
+                )*
+            }
+        )*
+        calls: |$handle:ident| $calls:block
+    ) => {
+        struct $recording;
+
+        impl $recording {
+            // calls are logged per thread, every test running on its own
+            fn log(method: Option<&'static str>) -> Vec<&'static str> {
+                thread_local! {
+                    static CALLS: std::cell::RefCell<Vec<&'static str>> =
+                        const { std::cell::RefCell::new(Vec::new()) };
+                }
+                CALLS.with_borrow_mut(|calls| match method {
+                    Some(method) => {
+                        calls.push(method);
+                        Vec::new()
+                    }
+                    None => std::mem::take(calls),
+                })
+            }
+        }
+
+        $(
+            impl $trait for $recording {
+                $(
+                    $(#[$attr])*
+                    fn $method $params -> $ret {
+                        $recording::log(Some(stringify!($method)));
+                        $body
+                    }
+                )*
+            }
+        )*
+
+        impl $crate::common::status::Status for $recording {
+            fn get_status(
+                &self,
+            ) -> Result<Option<$crate::google::protobuf::Struct>, $crate::common::status::StatusError>
+            {
+                $recording::log(Some("get_status"));
+                Ok(None)
+            }
+        }
+
+        impl $crate::common::generic::DoCommand for $recording {
+            fn do_command(
+                &mut self,
+                _: Option<$crate::google::protobuf::Struct>,
+            ) -> Result<Option<$crate::google::protobuf::Struct>, $crate::common::generic::GenericError>
+            {
+                $recording::log(Some("do_command"));
+                Ok(None)
+            }
+            fn do_command_async(
+                &mut self,
+                _: Option<$crate::google::protobuf::Struct>,
+            ) -> $crate::common::generic::DoCommandFuture {
+                $recording::log(Some("do_command_async"));
+                Box::pin(std::future::ready(Ok(None)))
+            }
+        }
+
+        #[test_log::test]
+        fn $test() {
+            fn call($handle: &mut dyn $component) {
+                use $crate::common::{generic::DoCommand, status::Status};
+                $calls
+                Status::get_status(&*$handle).unwrap();
+                DoCommand::do_command($handle, None).unwrap();
+                drop(DoCommand::do_command_async($handle, None));
+            }
+            let mut expected: Vec<&'static str> = Vec::new();
+            $($(
+                $(#[$attr])*
+                expected.push(stringify!($method));
+            )*)*
+            expected.extend(["get_status", "do_command", "do_command_async"]);
+            $(
+                let mut wrapped = ($wrapper)($recording);
+                call(&mut wrapped);
+                assert_eq!($recording::log(None), expected, stringify!($wrapper));
+            )+
+        }
+    };
+}
+
+#[cfg(test)]
+pub(crate) use delegation_test;
+
+#[derive(Default)]
+struct Pipe {
+    buf: VecDeque<u8>,
+    reader: Option<Waker>,
+    closed: bool,
+}
+
+impl Pipe {
+    fn close(&mut self) {
+        self.closed = true;
+        if let Some(reader) = self.reader.take() {
+            reader.wake();
+        }
+    }
+}
+
+/// One end of an in-memory byte stream, see [`duplex`]
+pub struct DuplexStream {
+    read: Rc<RefCell<Pipe>>,
+    write: Rc<RefCell<Pipe>>,
+}
+
+/// Two connected in-memory streams, what is written to one is read from the other
+pub fn duplex() -> (DuplexStream, DuplexStream) {
+    let a: Rc<RefCell<Pipe>> = Default::default();
+    let b: Rc<RefCell<Pipe>> = Default::default();
+    (
+        DuplexStream {
+            read: a.clone(),
+            write: b.clone(),
+        },
+        DuplexStream { read: b, write: a },
+    )
+}
+
+impl rt::Read for DuplexStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        mut buf: rt::ReadBufCursor<'_>,
+    ) -> Poll<Result<(), std::io::Error>> {
+        let mut pipe = self.read.borrow_mut();
+        if pipe.buf.is_empty() {
+            if pipe.closed {
+                return Poll::Ready(Ok(()));
+            }
+            pipe.reader = Some(cx.waker().clone());
+            return Poll::Pending;
+        }
+        let len = buf.remaining().min(pipe.buf.len());
+        let data: Vec<u8> = pipe.buf.drain(..len).collect();
+        buf.put_slice(&data);
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl rt::Write for DuplexStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _: &mut Context<'_>,
+        data: &[u8],
+    ) -> Poll<Result<usize, std::io::Error>> {
+        let mut pipe = self.write.borrow_mut();
+        if pipe.closed {
+            return Poll::Ready(Err(std::io::ErrorKind::BrokenPipe.into()));
+        }
+        pipe.buf.extend(data);
+        if let Some(reader) = pipe.reader.take() {
+            reader.wake();
+        }
+        Poll::Ready(Ok(data.len()))
+    }
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), std::io::Error>> {
+        Poll::Ready(Ok(()))
+    }
+    fn poll_shutdown(
+        self: Pin<&mut Self>,
+        _: &mut Context<'_>,
+    ) -> Poll<Result<(), std::io::Error>> {
+        self.write.borrow_mut().close();
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl Drop for DuplexStream {
+    fn drop(&mut self) {
+        self.write.borrow_mut().close();
+        self.read.borrow_mut().close();
+    }
+}
+
+/// A [`GrpcClient`] connected to a [`GrpcServer`] serving `robot` through an in-memory stream,
+/// to exercise the gRPC API of a robot end to end, deterministically and without sockets.
+/// The server runs on `exec` so calls have to be awaited within [`Executor::block_on`].
+pub struct InMemoryGrpcClient {
+    client: GrpcClient,
+    _server: Task<()>,
+}
+
+impl InMemoryGrpcClient {
+    pub async fn new(
+        exec: Executor,
+        robot: Arc<Mutex<LocalRobot>>,
+    ) -> Result<Self, GrpcClientError> {
+        let (client_io, server_io) = duplex();
+        let server_exec = exec.clone();
+        let server = exec.spawn(async move {
+            let srv = GrpcServer::new(robot, GrpcBody::new());
+            if let Err(e) = http2::Builder::new(server_exec)
+                .serve_connection(server_io, srv)
+                .await
+            {
+                log::debug!("in memory gRPC server stopped {:?}", e);
+            }
+        });
+        let client = GrpcClient::new(client_io, exec, "http://in-memory".parse::<Uri>()?).await?;
+        Ok(Self {
+            client,
+            _server: server,
+        })
+    }
+
+    /// Make a unary call to the method at `path`, for example
+    /// `/viam.component.sensor.v1.SensorService/GetReadings`
+    pub async fn call<Req, Resp>(&self, path: &str, request: Req) -> Result<Resp, GrpcClientError>
+    where
+        Req: Message,
+        Resp: Message + Default,
+    {
+        let mut body = BytesMut::with_capacity(request.encoded_len() + 5);
+        body.put_u8(0);
+        body.put_u32(request.encoded_len().try_into()?);
+        request.encode(&mut body)?;
+        let request = self.client.build_request(
+            path,
+            None,
+            "",
+            Full::new(body.freeze())
+                .map_err(|never| match never {})
+                .boxed(),
+        )?;
+        let (mut response, _) = self.client.send_request(request).await?;
+        if response.len() < 5 {
+            return Err(GrpcClientError::FrameError(format!(
+                "response of {} bytes is too short",
+                response.len()
+            )));
+        }
+        Ok(Resp::decode(response.split_off(5))?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        common::generic::FakeGenericComponent,
+        proto::common::v1::{GetReadingsRequest, GetReadingsResponse},
+    };
+
+    #[test_log::test]
+    fn test_do_command_harness() {
+        let mut generic = build_resource(FakeGenericComponent::from_config, [], vec![]).unwrap();
+        assert_do_command(
+            &mut generic,
+            command([("ping", value::Kind::NullValue(0))]),
+            Some(command([(
+                "ping",
+                value::Kind::StringValue("pinged".to_owned()),
+            )])),
+        );
+
+        let config = component_config([("pin", Kind::NumberValue(4.0))]);
+        assert_eq!(
+            ConfigType::Dynamic(&config)
+                .get_attribute::<i32>("pin")
+                .unwrap(),
+            4
+        );
+
+        let Dependency(key, Resource::Board(_)) = fake_board_dependency() else {
+            panic!("expected a board dependency");
+        };
+        assert_eq!(key, ResourceKey::new("board", "board"));
+    }
+
+    #[test_log::test]
+    fn test_in_memory_grpc_client() {
+        let mut robot = LocalRobot::default();
+        robot
+            .process_components(
+                vec![Some(DynamicComponentConfig {
+                    name: "thermometer".to_owned(),
+                    namespace: "rdk".to_owned(),
+                    r#type: "sensor".to_owned(),
+                    model: "rdk:builtin:fake".to_owned(),
+                    attributes: Some(HashMap::from([(
+                        "fake_value".to_owned(),
+                        Kind::NumberValue(21.5),
+                    )])),
+                    ..Default::default()
+                })],
+                &mut Box::default(),
+            )
+            .unwrap();
+        let robot = Arc::new(Mutex::new(robot));
+
+        let exec = Executor::new();
+        exec.block_on(async {
+            let client = InMemoryGrpcClient::new(exec.clone(), robot).await.unwrap();
+            let response: GetReadingsResponse = client
+                .call(
+                    "/viam.component.sensor.v1.SensorService/GetReadings",
+                    GetReadingsRequest {
+                        name: "thermometer".to_owned(),
+                        extra: None,
+                    },
+                )
+                .await
+                .unwrap();
+            assert_eq!(
+                response.readings["fake_sensor"].kind,
+                Some(value::Kind::NumberValue(21.5))
+            );
+
+            let missing: Result<GetReadingsResponse, _> = client
+                .call(
+                    "/viam.component.sensor.v1.SensorService/GetReadings",
+                    GetReadingsRequest {
+                        name: "hygrometer".to_owned(),
+                        extra: None,
+                    },
+                )
+                .await;
+            assert!(matches!(missing, Err(GrpcClientError::GrpcError { .. })));
+        });
+    }
+}
@@ -0,0 +1,334 @@
+//! Driver for the Vishay VEML7700 ambient light sensor over I2C.
+//! VEML7700 datasheet: https://www.vishay.com/docs/84286/veml7700.pdf
+//!
+//! Readings are `lux` along with the raw ambient light count (`als_count`). The sensitivity is
+//! set with the `gain` attribute (0.125, 0.25, 1 or 2, 0.25 by default) and the
+//! `integration_time_ms` attribute (25, 50, 100, 200, 400 or 800, 100 by default): a higher gain
+//! or a longer integration time resolves dimmer light but saturates sooner, the defaults cover
+//! indoor light up to direct sunlight through a window. A slow bus can be kept off the executor
+//! with `read_in_background`, see [`blocking`](super::blocking).
+
+use std::collections::HashMap;
+
+use super::blocking::sensor_from_config;
+use super::board::Board;
+use super::config::{AttributeError, ConfigType};
+use super::i2c::{i2c_address_from_config, I2CHandle, I2cHandleType};
+use super::registry::{get_board_from_dependencies, ComponentRegistry, Dependency};
+use super::sensor::{
+    GenericReadingsResult, Readings, Sensor, SensorError, SensorResult, SensorType,
+    TypedReadingsResult,
+};
+use super::status::{Status, StatusError};
+use crate::google;
+
+const DEFAULT_I2C_ADDRESS: u8 = 0x10;
+const ALS_CONF_REGISTER: u8 = 0x00;
+const ALS_REGISTER: u8 = 0x04;
+// lux per count at the highest gain and longest integration time
+const MAX_RESOLUTION_LUX: f64 = 0.0042;
+
+pub(crate) fn register_models(registry: &mut ComponentRegistry) {
+    if registry
+        .register_sensor("veml7700", &Veml7700::from_config)
+        .is_err()
+    {
+        log::error!("veml7700 model is already registered")
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Gain {
+    OneEighth,
+    OneQuarter,
+    One,
+    Two,
+}
+
+impl Gain {
+    pub fn from_value(gain: f64) -> Option<Self> {
+        Some(match gain {
+            0.125 => Self::OneEighth,
+            0.25 => Self::OneQuarter,
+            1.0 => Self::One,
+            2.0 => Self::Two,
+            _ => return None,
+        })
+    }
+    fn value(&self) -> f64 {
+        match self {
+            Self::OneEighth => 0.125,
+            Self::OneQuarter => 0.25,
+            Self::One => 1.0,
+            Self::Two => 2.0,
+        }
+    }
+    // ALS_GAIN, bits 12:11 of ALS_CONF
+    fn bits(&self) -> u16 {
+        match self {
+            Self::One => 0b00,
+            Self::Two => 0b01,
+            Self::OneEighth => 0b10,
+            Self::OneQuarter => 0b11,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IntegrationTime {
+    Ms25,
+    Ms50,
+    Ms100,
+    Ms200,
+    Ms400,
+    Ms800,
+}
+
+impl IntegrationTime {
+    pub fn from_ms(ms: u32) -> Option<Self> {
+        Some(match ms {
+            25 => Self::Ms25,
+            50 => Self::Ms50,
+            100 => Self::Ms100,
+            200 => Self::Ms200,
+            400 => Self::Ms400,
+            800 => Self::Ms800,
+            _ => return None,
+        })
+    }
+    fn ms(&self) -> f64 {
+        match self {
+            Self::Ms25 => 25.0,
+            Self::Ms50 => 50.0,
+            Self::Ms100 => 100.0,
+            Self::Ms200 => 200.0,
+            Self::Ms400 => 400.0,
+            Self::Ms800 => 800.0,
+        }
+    }
+    // ALS_IT, bits 9:6 of ALS_CONF
+    fn bits(&self) -> u16 {
+        match self {
+            Self::Ms25 => 0b1100,
+            Self::Ms50 => 0b1000,
+            Self::Ms100 => 0b0000,
+            Self::Ms200 => 0b0001,
+            Self::Ms400 => 0b0010,
+            Self::Ms800 => 0b0011,
+        }
+    }
+}
+
+/// Lux per count of the sensor with these settings
+fn resolution_lux(gain: Gain, integration_time: IntegrationTime) -> f64 {
+    MAX_RESOLUTION_LUX * (2.0 / gain.value()) * (800.0 / integration_time.ms())
+}
+
+/// The `gain` and `integration_time_ms` attributes, defaulting to 0.25 and 100ms
+fn settings_from_config(cfg: &ConfigType) -> Result<(Gain, IntegrationTime), SensorError> {
+    let gain = match cfg.get_attribute::<f64>("gain") {
+        Ok(gain) => Gain::from_value(gain),
+        Err(AttributeError::KeyNotFound(_)) => Some(Gain::OneQuarter),
+        Err(_) => None,
+    }
+    .ok_or(SensorError::ConfigError(
+        "veml7700 gain should be 0.125, 0.25, 1 or 2",
+    ))?;
+    let integration_time = match cfg.get_attribute::<u32>("integration_time_ms") {
+        Ok(ms) => IntegrationTime::from_ms(ms),
+        Err(AttributeError::KeyNotFound(_)) => Some(IntegrationTime::Ms100),
+        Err(_) => None,
+    }
+    .ok_or(SensorError::ConfigError(
+        "veml7700 integration_time_ms should be 25, 50, 100, 200, 400 or 800",
+    ))?;
+    Ok((gain, integration_time))
+}
+
+#[derive(DoCommand)]
+pub struct Veml7700<H: I2CHandle> {
+    i2c_handle: H,
+    i2c_address: u8,
+    resolution_lux: f64,
+}
+
+impl Veml7700<I2cHandleType> {
+    pub(crate) fn from_config(
+        cfg: ConfigType,
+        dependencies: Vec<Dependency>,
+    ) -> Result<SensorType, SensorError> {
+        let board = get_board_from_dependencies(dependencies)
+            .ok_or(SensorError::ConfigError("veml7700 missing board attribute"))?;
+        let i2c_name = cfg
+            .get_attribute::<String>("i2c_bus")
+            .map_err(|_| SensorError::ConfigError("veml7700 missing i2c_bus attribute"))?;
+        let i2c_address = i2c_address_from_config(&cfg)?.unwrap_or(DEFAULT_I2C_ADDRESS);
+        let (gain, integration_time) = settings_from_config(&cfg)?;
+        let i2c_handle = board.get_i2c_by_name(i2c_name)?;
+        sensor_from_config(
+            &cfg,
+            Veml7700::new(i2c_handle, i2c_address, gain, integration_time)?,
+        )
+    }
+}
+
+impl<H: I2CHandle> Veml7700<H> {
+    /// Configure the sensor and power it on, it measures continuously from then on
+    pub fn new(
+        mut i2c_handle: H,
+        i2c_address: u8,
+        gain: Gain,
+        integration_time: IntegrationTime,
+    ) -> Result<Self, SensorError> {
+        // interrupts disabled and ALS_SD cleared to power on
+        let conf = (gain.bits() << 11) | (integration_time.bits() << 6);
+        let [lsb, msb] = conf.to_le_bytes();
+        i2c_handle.write_i2c(i2c_address, &[ALS_CONF_REGISTER, lsb, msb])?;
+        Ok(Self {
+            i2c_handle,
+            i2c_address,
+            resolution_lux: resolution_lux(gain, integration_time),
+        })
+    }
+
+    fn measure(&mut self) -> Result<TypedReadingsResult<f64>, SensorError> {
+        let mut als = [0; 2];
+        self.i2c_handle
+            .write_read_i2c(self.i2c_address, &[ALS_REGISTER], &mut als)?;
+        let count = u16::from_le_bytes(als) as f64;
+        Ok(HashMap::from([
+            ("lux".to_string(), count * self.resolution_lux),
+            ("als_count".to_string(), count),
+        ]))
+    }
+}
+
+impl<H: I2CHandle> Sensor for Veml7700<H> {}
+
+impl<H: I2CHandle> Readings for Veml7700<H> {
+    fn get_generic_readings(&mut self) -> Result<GenericReadingsResult, SensorError> {
+        Ok(self
+            .measure()?
+            .into_iter()
+            .map(|v| (v.0, SensorResult::<f64> { value: v.1 }.into()))
+            .collect())
+    }
+}
+
+impl<H: I2CHandle> Status for Veml7700<H> {
+    fn get_status(&self) -> Result<Option<google::protobuf::Struct>, StatusError> {
+        Ok(Some(google::protobuf::Struct {
+            fields: HashMap::new(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::config::Kind;
+    use crate::common::i2c::I2CErrors;
+    use crate::common::test_utils::component_config;
+    use std::sync::{Arc, Mutex};
+
+    // The 16 bit little endian registers of a VEML7700, written by the driver or seeded by a test
+    #[derive(Default)]
+    struct Veml7700Registers(HashMap<u8, [u8; 2]>);
+
+    impl I2CHandle for Veml7700Registers {
+        fn name(&self) -> String {
+            "i2c0".to_owned()
+        }
+        fn write_i2c(&mut self, address: u8, bytes: &[u8]) -> Result<(), I2CErrors> {
+            assert_eq!(address, DEFAULT_I2C_ADDRESS);
+            let [register, lsb, msb] = bytes else {
+                panic!("unexpected write {:?}", bytes);
+            };
+            self.0.insert(*register, [*lsb, *msb]);
+            Ok(())
+        }
+        fn write_read_i2c(
+            &mut self,
+            address: u8,
+            bytes: &[u8],
+            buffer: &mut [u8],
+        ) -> Result<(), I2CErrors> {
+            assert_eq!(address, DEFAULT_I2C_ADDRESS);
+            let [register] = bytes else {
+                panic!("unexpected register read {:?}", bytes);
+            };
+            buffer.copy_from_slice(&self.0.get(register).copied().unwrap_or_default());
+            Ok(())
+        }
+    }
+
+    #[test_log::test]
+    fn test_veml7700_settings() {
+        let settings = |attributes: Vec<(&str, Kind)>| {
+            settings_from_config(&ConfigType::Dynamic(&component_config(attributes)))
+        };
+        assert_eq!(
+            settings(vec![]).unwrap(),
+            (Gain::OneQuarter, IntegrationTime::Ms100)
+        );
+        assert_eq!(
+            settings(vec![
+                ("gain", Kind::NumberValue(2.0)),
+                ("integration_time_ms", Kind::NumberValue(800.0)),
+            ])
+            .unwrap(),
+            (Gain::Two, IntegrationTime::Ms800)
+        );
+        for invalid in [
+            vec![("gain", Kind::NumberValue(4.0))],
+            vec![("gain", Kind::StringValue("high".to_owned()))],
+            vec![("integration_time_ms", Kind::NumberValue(150.0))],
+            vec![("integration_time_ms", Kind::NumberValue(-100.0))],
+        ] {
+            assert!(matches!(
+                settings(invalid),
+                Err(SensorError::ConfigError(_))
+            ));
+        }
+    }
+
+    #[test_log::test]
+    fn test_veml7700_lux() {
+        assert_eq!(resolution_lux(Gain::Two, IntegrationTime::Ms800), 0.0042);
+        assert_eq!(
+            resolution_lux(Gain::OneEighth, IntegrationTime::Ms25),
+            0.0042 * 16.0 * 32.0
+        );
+
+        let registers = Arc::new(Mutex::new(Veml7700Registers::default()));
+        let mut sensor = Veml7700::new(
+            registers.clone(),
+            DEFAULT_I2C_ADDRESS,
+            Gain::Two,
+            IntegrationTime::Ms800,
+        )
+        .unwrap();
+        // gain x2 is 01 in bits 12:11 and 800ms 0011 in bits 9:6, ALS_SD is cleared
+        assert_eq!(
+            registers.lock().unwrap().0[&ALS_CONF_REGISTER],
+            [0xC0, 0x08]
+        );
+
+        registers
+            .lock()
+            .unwrap()
+            .0
+            .insert(ALS_REGISTER, 1000_u16.to_le_bytes());
+        let readings = sensor.measure().unwrap();
+        assert_eq!(readings["als_count"], 1000.0);
+        assert_eq!(readings["lux"], 1000.0 * 0.0042);
+
+        // the count is read little endian
+        registers
+            .lock()
+            .unwrap()
+            .0
+            .insert(ALS_REGISTER, [0x34, 0x12]);
+        assert_eq!(sensor.measure().unwrap()["als_count"], 0x1234 as f64);
+    }
+}
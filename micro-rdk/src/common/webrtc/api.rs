@@ -2,7 +2,7 @@
 use std::{
     fmt::Debug,
     io::{self, Cursor},
-    net::{Ipv4Addr, UdpSocket},
+    net::UdpSocket,
     pin::Pin,
     rc::Rc,
     sync::{atomic::AtomicBool, Arc, Mutex},
@@ -52,7 +52,7 @@ use super::{
     dtls::DtlsConnector,
     exec::WebRtcExecutor,
     grpc::{WebRtcGrpcBody, WebRtcGrpcServer},
-    ice::{ICEAgent, ICECredentials},
+    ice::{ICEAgent, ICECredentials, LocalIpWatch, StunServer},
     io::WebRtcTransport,
     sctp::{Channel, SctpConnector, SctpHandle},
     signaling_server::LocalSignaling,
@@ -134,6 +134,15 @@ impl WebRtcSdp {
     pub fn new(sdp: SessionDescription, uuid: String) -> Self {
         WebRtcSdp { sdp, uuid }
     }
+    // priority requested by the caller through the x-priority attribute, defaults to u32::MAX
+    pub(crate) fn priority(&self) -> u32 {
+        self.sdp
+            .media_descriptions
+            .first()
+            .and_then(|media| media.attribute("x-priority").flatten())
+            .map_or(Ok(u32::MAX), |a| a.parse::<u32>())
+            .unwrap_or(u32::MAX)
+    }
 }
 
 struct AtomicSyncInner {
@@ -299,6 +308,18 @@ impl PeriodicAppClientTask for SignalingTask {
 }
 
 impl WebRtcSignalingChannel {
+    // answer the offer with a "too many active connections" error
+    pub(crate) async fn refuse_offer(&mut self) -> Result<(), WebRtcError> {
+        self.send_sdp_error_too_many_connections(self.sdp.uuid.clone())
+            .await?;
+
+        // TODO(APP-6381): Without this delay, sdks receive a `ContextCancelled` error instead
+        // of `ResourceExhausted`. It's possible a race condition on the App side is closing
+        // the connection before the error is properly recorded for an sdk to see.
+        async_io::Timer::after(Duration::from_millis(200)).await;
+        Ok(())
+    }
+
     pub(crate) async fn send_sdp_error_too_many_connections(
         &mut self,
         uuid: String,
@@ -538,7 +559,8 @@ pub struct WebRtcApi<C, E> {
     certificate: Rc<C>,
     local_creds: ICECredentials,
     remote_creds: Option<ICECredentials>,
-    local_ip: Ipv4Addr,
+    local_ip: LocalIpWatch,
+    stun_servers: Vec<StunServer>,
     dtls: Option<Box<dyn DtlsConnector>>,
     ice_agent: AtomicSync,
 }
@@ -552,7 +574,8 @@ where
         executor: E,
         signaling: Box<WebRtcSignalingChannel>,
         certificate: Rc<C>,
-        local_ip: Ipv4Addr,
+        local_ip: LocalIpWatch,
+        stun_servers: Vec<StunServer>,
         dtls: Box<dyn DtlsConnector>,
     ) -> Self {
         let udp = Arc::new(async_io::Async::<UdpSocket>::bind(([0, 0, 0, 0], 0)).unwrap());
@@ -567,6 +590,7 @@ where
             remote_creds: None,
             local_creds: Default::default(),
             local_ip,
+            stun_servers,
             dtls: Some(dtls),
             ice_agent: AtomicSync::default(),
         }
@@ -582,8 +606,10 @@ where
             ice_transport,
             self.local_creds.clone(),
             self.remote_creds.as_ref().unwrap().clone(),
-            self.local_ip,
+            self.local_ip.get(),
         );
+        ice_agent.watch_local_ip(self.local_ip.clone());
+        ice_agent.set_stun_servers(self.stun_servers.clone());
 
         self.signaling.send_sdp_answer(answer).await?;
 
@@ -734,23 +760,11 @@ where
             .first()
             .ok_or_else(|| WebRtcError::InvalidSDPOffer("no media description".to_owned()))?;
 
-        let caller_prio = attribute
-            .attribute("x-priority")
-            .flatten()
-            .map_or(Ok(u32::MAX), |a| a.parse::<u32>())
-            .unwrap_or(u32::MAX);
+        let caller_prio = self.signaling.offer().priority();
 
         // TODO use is_some_then when rust min version reach 1.70
         if current_prio >= caller_prio {
-            self.signaling
-                .send_sdp_error_too_many_connections(self.signaling.offer().uuid.clone())
-                .await?;
-
-            // TODO(APP-6381): Without this delay, sdks receive a `ContextCancelled` error instead
-            // of `ResourceExhausted`. It's possible a race condition on the App side is closing
-            // the connection before the error is properly recorded for an sdk to see.
-            async_io::Timer::after(Duration::from_millis(200)).await;
-
+            self.signaling.refuse_offer().await?;
             return Err(WebRtcError::NoConnectionAvailable());
         }
 
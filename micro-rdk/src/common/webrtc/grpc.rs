@@ -10,7 +10,7 @@ use futures_lite::AsyncReadExt;
 use prost::Message;
 
 use crate::{
-    common::grpc::{GrpcResponse, ServerError},
+    common::grpc::{GrpcError, GrpcResponse, ResponseFuture, ServerError},
     google::rpc::Status,
     proto::rpc::webrtc::{
         self,
@@ -80,7 +80,13 @@ pub struct WebRtcGrpcServer<S> {
 }
 
 pub trait WebRtcGrpcService {
+    /// Whether a call to `method` carrying `authorization` (the value of the request's
+    /// `authorization` metadata) may be served
+    fn is_authorized(&self, method: &str, authorization: Option<&str>) -> bool;
     fn unary_rpc(&mut self, method: &str, data: &Bytes) -> Result<Bytes, ServerError>;
+    /// Unary rpcs whose response is awaited rather than computed in place, `None` when `method`
+    /// is answered by `unary_rpc`
+    fn async_unary_rpc(&mut self, method: &str, data: &Bytes) -> Option<ResponseFuture>;
     fn server_stream_rpc(
         &mut self,
         method: &str,
@@ -121,7 +127,18 @@ where
     ) -> Result<(Status, Option<Instant>), WebRtcError> {
         let method = &hdr.method;
         log::debug!("processing req {:?}", method);
-        let ret = if let Some(pkt) = msg.packet_message.as_ref() {
+        let authorization = hdr
+            .metadata
+            .as_ref()
+            .and_then(|md| md.md.get("authorization"))
+            .and_then(|values| values.values.first())
+            .map(String::as_str);
+        let ret = if !self.service.is_authorized(method, authorization) {
+            (
+                ServerError::from(GrpcError::RpcUnauthenticated).to_status(),
+                None,
+            )
+        } else if let Some(pkt) = msg.packet_message.as_ref() {
             if method.contains("Stream") {
                 match self.service.server_stream_rpc(method, &pkt.data) {
                     Ok(data) => {
@@ -137,7 +154,11 @@ where
                     Err(e) => (e.to_status(), None),
                 }
             } else {
-                match self.service.unary_rpc(method, &pkt.data) {
+                let response = match self.service.async_unary_rpc(method, &pkt.data) {
+                    Some(response) => response.await,
+                    None => self.service.unary_rpc(method, &pkt.data),
+                };
+                match response {
                     Ok(data) => {
                         self.send_rpc_response(data, stream).await?;
                         (
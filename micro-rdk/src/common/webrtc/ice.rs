@@ -3,6 +3,11 @@ use std::{
     io,
     net::{Ipv4Addr, SocketAddr, SocketAddrV4, ToSocketAddrs},
     pin::Pin,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
     time::{Duration, Instant},
 };
 
@@ -60,6 +65,10 @@ pub enum IceError {
     IceTransportClosed,
     #[error("server is not ipv4")]
     IceStunServerNotIPV4,
+    #[error("stun server didn't answer")]
+    IceStunServerUnreachable,
+    #[error("invalid stun server uri {0}")]
+    IceInvalidStunServerUri(String),
     #[error("io error from transport")]
     IceIoError,
     #[error("missing xor_mapped address")]
@@ -86,6 +95,84 @@ pub enum IceError {
     IceCandidateError(#[from] CandidateError),
 }
 
+/// STUN server used when none are configured
+pub const DEFAULT_STUN_SERVER: &str = "stun:global.stun.twilio.com:3478";
+const DEFAULT_STUN_PORT: u16 = 3478;
+// number of binding requests sent to a STUN server before moving on to the next one
+const STUN_MAX_ATTEMPTS: u32 = 3;
+
+/// A STUN server address parsed from a URI of the form `stun:host[:port]`, the scheme may be
+/// omitted. Only plain UDP STUN is supported.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StunServer {
+    host: String,
+    port: u16,
+}
+
+impl Default for StunServer {
+    fn default() -> Self {
+        DEFAULT_STUN_SERVER.parse().unwrap()
+    }
+}
+
+impl FromStr for StunServer {
+    type Err = IceError;
+    fn from_str(uri: &str) -> Result<Self, Self::Err> {
+        let invalid = || IceError::IceInvalidStunServerUri(uri.to_owned());
+        let addr = match uri.split_once(':') {
+            Some(("stun", addr)) => addr,
+            Some(("stuns" | "turn" | "turns", _)) => return Err(invalid()),
+            _ => uri,
+        };
+        let (host, port) = match addr.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse::<u16>().map_err(|_| invalid())?),
+            None => (addr, DEFAULT_STUN_PORT),
+        };
+        if host.is_empty()
+            || !host
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-')
+        {
+            return Err(invalid());
+        }
+        Ok(Self {
+            host: host.to_owned(),
+            port,
+        })
+    }
+}
+
+impl std::fmt::Display for StunServer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "stun:{}:{}", self.host, self.port)
+    }
+}
+
+impl ToSocketAddrs for StunServer {
+    type Iter = std::vec::IntoIter<SocketAddr>;
+    fn to_socket_addrs(&self) -> io::Result<Self::Iter> {
+        (self.host.as_str(), self.port).to_socket_addrs()
+    }
+}
+
+/// Shared view of the local IP address. The server updates it when the network reports a new
+/// address and running ICE agents restart when they notice the change.
+#[derive(Clone, Debug)]
+pub(crate) struct LocalIpWatch(Arc<AtomicU32>);
+
+impl LocalIpWatch {
+    pub(crate) fn new(ip: Ipv4Addr) -> Self {
+        Self(Arc::new(AtomicU32::new(ip.into())))
+    }
+    pub(crate) fn get(&self) -> Ipv4Addr {
+        self.0.load(Ordering::Relaxed).into()
+    }
+    /// Store a new address, returns true if it differs from the previous one
+    pub(crate) fn set(&self, ip: Ipv4Addr) -> bool {
+        self.0.swap(ip.into(), Ordering::Relaxed) != u32::from(ip)
+    }
+}
+
 enum IceEvent {
     CandidateReceived(Candidate),
     StunPacketReceived((usize, SocketAddrV4)),
@@ -97,7 +184,8 @@ enum IceEvent {
 /// * Only support ICE-CONTROLLED
 /// * Doesn't resolve local mDNS candidate presented
 /// * Doesn't do a best effort to find a better pair once one was nominated
-/// * Doesn't support Ice Restart
+/// * Ice Restart is local only, credentials are kept and the remote peer learns about our new
+///   address through connectivity checks (peer reflexive candidate)
 /// * Doesn't support freeing candidates
 /// * Can only do trickle ice
 /// * Adding/Removing tracks
@@ -111,6 +199,8 @@ pub struct ICEAgent {
     remote_credentials: ICECredentials,
     state: ICEAgentState,
     local_ip: Ipv4Addr,
+    local_ip_watch: Option<LocalIpWatch>,
+    stun_servers: Vec<StunServer>,
 }
 
 impl Drop for ICEAgent {
@@ -140,14 +230,54 @@ impl ICEAgent {
             transport,
             candidate_pairs: vec![],
             local_ip,
+            local_ip_watch: None,
+            stun_servers: vec![StunServer::default()],
             local_credentials,
             remote_credentials,
             state: ICEAgentState::Checking,
         }
     }
 
+    /// Follow changes of the local IP address, when a change is detected the agent will
+    /// restart ICE
+    pub(crate) fn watch_local_ip(&mut self, watch: LocalIpWatch) {
+        self.local_ip_watch = Some(watch);
+    }
+
+    /// Set the STUN servers used to find our server reflexive candidate
+    pub(crate) fn set_stun_servers(&mut self, stun_servers: Vec<StunServer>) {
+        self.stun_servers = stun_servers;
+    }
+
+    /// Restart ICE with a new local IP address. Local candidates are gathered again and the
+    /// checklist is rebuilt against the remote candidates we already know of. The signaling
+    /// channel is gone at this point so credentials are not renegotiated, the data channel state
+    /// is left untouched.
+    pub(crate) async fn restart(&mut self, local_ip: Ipv4Addr) -> Result<(), IceError> {
+        log::info!("restarting ICE, local ip {} -> {}", self.local_ip, local_ip);
+        self.local_ip = local_ip;
+        self.local_candidates.clear();
+        self.candidate_pairs.clear();
+        self.state = ICEAgentState::Checking;
+
+        let gathered = self
+            .local_candidates()
+            .or(async {
+                Timer::after(Duration::from_secs(5)).await;
+                Err(IceError::IceTimeout)
+            })
+            .await;
+
+        // even if reflexive candidate gathering failed we still have our host candidate
+        for remote_idx in 0..self.remote_candidates.len() {
+            self.form_pairs(remote_idx);
+        }
+        gathered
+    }
+
     /// Gather local candidates, it will only generate one host and one server reflexive,
-    /// relay candidates are not supported yet
+    /// relay candidates are not supported yet. STUN servers are tried in order until one of them
+    /// answers
     pub async fn local_candidates(&mut self) -> Result<(), IceError> {
         if !self.local_candidates.is_empty() {
             return Ok(());
@@ -166,32 +296,41 @@ impl ICEAgent {
 
         log::debug!("local_candidates: looking for srv reflexive candidate");
 
-        let message = stun_codec::Message::<stun_codec::rfc5389::Attribute>::new(
-            stun_codec::MessageClass::Request,
-            stun_codec::rfc5389::methods::BINDING,
-            stun_codec::TransactionId::new(rand::random()),
-        );
-
-        let mut encoder = stun_codec::MessageEncoder::new();
-        let bytes = Bytes::from(encoder.encode_into_bytes(message).unwrap());
-
-        // TODO(RSDK-3063) Twilio address is hard-coded, we should support additional server via WebRTCOptions
-        let mut stun_ip = match "global.stun.twilio.com:3478".to_socket_addrs() {
-            Ok(stun_ip) => stun_ip,
-            Err(err) => {
-                log::warn!("Failed trying to resolve STUN server address; no reflexive candidate will be generated: {}", err);
-                return Ok(());
+        let mut rflx_addr = None;
+        for server in &self.stun_servers {
+            match self.query_stun_server(server).await {
+                Ok(addr) => {
+                    rflx_addr = Some(addr);
+                    break;
+                }
+                Err(IceError::IceIoError) => return Err(IceError::IceIoError),
+                Err(err) => {
+                    log::warn!("STUN server {} failed: {}, trying next one", server, err);
+                }
             }
-        };
+        }
 
-        let stun_ip = match stun_ip.next() {
-            Some(stun_ip) => stun_ip,
+        let rflx_addr = match rflx_addr {
+            Some(addr) => addr,
             None => {
-                log::warn!("STUN server address resolution found no records; no reflexive candidate will be generated");
+                log::warn!("no STUN server answered; no reflexive candidate will be generated");
                 return Ok(());
             }
         };
 
+        let srflx_candidate = Candidate::new_srflx_candidate(rflx_addr, our_ip);
+        self.local_candidates.push(srflx_candidate);
+
+        Ok(())
+    }
+
+    /// Send a binding request to a STUN server and return our server reflexive address
+    async fn query_stun_server(&self, server: &StunServer) -> Result<SocketAddrV4, IceError> {
+        let stun_ip = server
+            .to_socket_addrs()
+            .map_err(|_| IceError::IceStunServerUnreachable)?
+            .find(|addr| addr.is_ipv4())
+            .ok_or(IceError::IceStunServerNotIPV4)?;
         let stun_ip = match stun_ip {
             SocketAddr::V4(v4) => v4,
             _ => {
@@ -199,13 +338,26 @@ impl ICEAgent {
             }
         };
 
+        let message = stun_codec::Message::<stun_codec::rfc5389::Attribute>::new(
+            stun_codec::MessageClass::Request,
+            stun_codec::rfc5389::methods::BINDING,
+            stun_codec::TransactionId::new(rand::random()),
+        );
+
+        let mut encoder = stun_codec::MessageEncoder::new();
+        let bytes = Bytes::from(
+            encoder
+                .encode_into_bytes(message)
+                .map_err(|_| IceError::IceStunEncodingError)?,
+        );
+
         let mut buf = BytesMut::zeroed(256);
+        let mut attempts = 0;
         let (buf_len, _addr) = loop {
-            let _r = self
-                .transport
+            self.transport
                 .send_to(&bytes, stun_ip.into())
                 .await
-                .unwrap();
+                .map_err(|_| IceError::IceIoError)?;
             let response = self
                 .transport
                 .recv_from(&mut buf)
@@ -217,7 +369,13 @@ impl ICEAgent {
 
             match response {
                 Ok(rsp) => break rsp,
-                Err(e) if e.kind() == io::ErrorKind::TimedOut => continue,
+                Err(e) if e.kind() == io::ErrorKind::TimedOut => {
+                    attempts += 1;
+                    if attempts >= STUN_MAX_ATTEMPTS {
+                        return Err(IceError::IceStunServerUnreachable);
+                    }
+                    continue;
+                }
                 Err(_) => return Err(IceError::IceIoError),
             };
         };
@@ -226,7 +384,7 @@ impl ICEAgent {
         let decoded = decoder
             .decode_from_bytes(&buf[..buf_len])
             .map_err(|_| IceError::IceStunDecodingError)?
-            .unwrap();
+            .map_err(|_| IceError::IceStunDecodingError)?;
 
         let xor_mapped_addr =
             match decoded.get_attribute::<stun_codec::rfc5389::attributes::XorMappedAddress>() {
@@ -234,15 +392,10 @@ impl ICEAgent {
                 None => return Err(IceError::IceMissingXorMappedAddress),
             };
 
-        let rflx_addr = match xor_mapped_addr {
-            SocketAddr::V4(v4) => v4,
-            SocketAddr::V6(_) => return Err(IceError::IceXorMappedAddressIsIPV6),
-        };
-
-        let srflx_candidate = Candidate::new_srflx_candidate(rflx_addr, our_ip);
-        self.local_candidates.push(srflx_candidate);
-
-        Ok(())
+        match xor_mapped_addr {
+            SocketAddr::V4(v4) => Ok(v4),
+            SocketAddr::V6(_) => Err(IceError::IceXorMappedAddressIsIPV6),
+        }
     }
 
     /// run the ice agent, processing incoming STUN packet and emitting STUN request
@@ -252,6 +405,16 @@ impl ICEAgent {
 
         let error = loop {
             let stop = stop.clone();
+            if let Some(ip) = self
+                .local_ip_watch
+                .as_ref()
+                .map(|watch| watch.get())
+                .filter(|ip| *ip != self.local_ip)
+            {
+                if let Err(e) = self.restart(ip).await {
+                    log::error!("ICE restart couldn't gather all local candidates {:?}", e);
+                }
+            }
             for pair in &mut self.candidate_pairs {
                 pair.update_pair_status();
                 // TODO(npm) check for nomination flag before we are actually connected
@@ -662,10 +825,10 @@ mod tests {
     use async_executor::Executor;
     use async_io::Async;
     use futures_lite::future::block_on;
-    use std::net::UdpSocket;
+    use std::net::{Ipv4Addr, UdpSocket};
     use std::sync::Arc;
 
-    use crate::common::webrtc::ice::{ICEAgent, ICECredentials};
+    use crate::common::webrtc::ice::{ICEAgent, ICECredentials, LocalIpWatch, StunServer};
 
     use crate::common::webrtc::{candidates::Candidate, io::WebRtcTransport};
 
@@ -717,4 +880,33 @@ mod tests {
 
         Ok(())
     }
+
+    #[test_log::test]
+    fn test_local_ip_watch() {
+        let watch = LocalIpWatch::new(Ipv4Addr::new(10, 1, 2, 3));
+        let cloned = watch.clone();
+        assert!(!cloned.set(Ipv4Addr::new(10, 1, 2, 3)));
+        assert!(cloned.set(Ipv4Addr::new(10, 1, 2, 4)));
+        assert_eq!(watch.get(), Ipv4Addr::new(10, 1, 2, 4));
+    }
+
+    #[test_log::test]
+    fn test_stun_server_uri() {
+        let srv = "stun:stun.l.google.com:19302".parse::<StunServer>();
+        assert!(srv.is_ok());
+        assert_eq!(srv.unwrap().to_string(), "stun:stun.l.google.com:19302");
+        let srv = "stun.example.com".parse::<StunServer>();
+        assert!(srv.is_ok());
+        assert_eq!(srv.unwrap().to_string(), "stun:stun.example.com:3478");
+        assert!("10.1.2.3:3478".parse::<StunServer>().is_ok());
+        assert!("turn:turn.example.com:3478".parse::<StunServer>().is_err());
+        assert!("stun:stun.example.com:notaport"
+            .parse::<StunServer>()
+            .is_err());
+        assert!("stun:".parse::<StunServer>().is_err());
+        assert_eq!(
+            StunServer::default().to_string(),
+            "stun:global.stun.twilio.com:3478"
+        );
+    }
 }
@@ -1,14 +1,22 @@
 use super::actuator::{Actuator, ActuatorError};
-use super::base::{Base, BaseError, BaseType, COMPONENT_NAME as BaseCompName};
-use super::config::ConfigType;
+use super::base::{
+    box_geometry, Base, BaseError, BaseType, GeometryConfig, COMPONENT_NAME as BaseCompName,
+};
+use super::config::{AttributeError, ConfigType};
+use super::exec::Executor;
 use super::motor::{Motor, MotorType, COMPONENT_NAME as MotorCompName};
 use super::registry::{ComponentRegistry, Dependency, ResourceKey};
 use super::robot::Resource;
 use super::status::{Status, StatusError};
 use crate::google;
-use crate::proto::common::v1::Vector3;
+use crate::proto::common::v1::{Geometry, Vector3};
+use async_executor::Task;
+use async_io::Timer;
+use futures_lite::Future;
 use std::collections::HashMap;
+use std::pin::Pin;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 pub(crate) fn register_models(registry: &mut ComponentRegistry) {
     if registry
@@ -32,10 +40,30 @@ pub(crate) fn register_models(registry: &mut ComponentRegistry) {
     }
 }
 
+/// Completes once a spin or straight move lasting the given duration is over
+pub type MoveTimer = Box<dyn Fn(Duration) -> Pin<Box<dyn Future<Output = ()>>>>;
+
+fn default_move_timer() -> MoveTimer {
+    Box::new(|duration| {
+        Box::pin(async move {
+            Timer::after(duration).await;
+        })
+    })
+}
+
 #[derive(DoCommand)]
 pub struct WheeledBase<ML, MR> {
     motor_right: MR,
     motor_left: ML,
+    invert_left: bool,
+    invert_right: bool,
+    geometry: Option<Geometry>,
+    // distance between the wheels and wheel circumference, needed to spin and move straight
+    wheel_dimensions: Option<(f64, f64)>,
+    move_timer: MoveTimer,
+    // a spin or straight move made by running both wheels for a set time, the task stops the
+    // motors once the move timer completes
+    timed_move: Option<Task<()>>,
 }
 
 impl<ML, MR> WheeledBase<ML, MR>
@@ -47,8 +75,94 @@ where
         WheeledBase {
             motor_right,
             motor_left,
+            invert_left: false,
+            invert_right: false,
+            geometry: None,
+            wheel_dimensions: None,
+            move_timer: default_move_timer(),
+            timed_move: None,
+        }
+    }
+
+    /// Flip the direction of the left and/or right wheel, for motors mounted or wired backwards
+    pub fn with_inverted(mut self, invert_left: bool, invert_right: bool) -> Self {
+        self.invert_left = invert_left;
+        self.invert_right = invert_right;
+        self
+    }
+
+    /// Report `geometry` as the shape of the base to motion planning
+    pub fn with_geometry(mut self, geometry: Geometry) -> Self {
+        self.geometry = Some(geometry);
+        self
+    }
+
+    /// Allow spinning and moving straight given the distance between the wheels `width_mm` and
+    /// their `wheel_circumference_mm`, both have to be positive
+    pub fn with_wheel_dimensions(
+        mut self,
+        width_mm: f64,
+        wheel_circumference_mm: f64,
+    ) -> Result<Self, BaseError> {
+        if !(width_mm.is_finite() && width_mm > 0.0)
+            || !(wheel_circumference_mm.is_finite() && wheel_circumference_mm > 0.0)
+        {
+            return Err(BaseError::BaseConfigError(
+                "width_mm and wheel_circumference_mm should be positive numbers",
+            ));
+        }
+        self.wheel_dimensions = Some((width_mm, wheel_circumference_mm));
+        Ok(self)
+    }
+
+    /// Time spins and straight moves with `move_timer` rather than the system clock
+    pub fn with_move_timer(mut self, move_timer: MoveTimer) -> Self {
+        self.move_timer = move_timer;
+        self
+    }
+
+    fn is_timed_move_running(&self) -> bool {
+        self.timed_move
+            .as_ref()
+            .is_some_and(|timed_move| !timed_move.is_finished())
+    }
+
+    /// The explicit `geometry` attribute, otherwise a box as wide as `width_mm` and as long and
+    /// tall as a wheel of `wheel_circumference_mm`
+    fn geometry_from_config(cfg: &ConfigType) -> Result<Option<Geometry>, BaseError> {
+        match cfg.get_attribute::<GeometryConfig>("geometry") {
+            Ok(GeometryConfig(geometry)) => return Ok(Some(geometry)),
+            Err(AttributeError::KeyNotFound(_)) => {}
+            Err(err) => return Err(err.into()),
+        }
+        let width_mm = cfg.get_attribute::<f64>("width_mm");
+        let circumference_mm = cfg.get_attribute::<f64>("wheel_circumference_mm");
+        match (width_mm, circumference_mm) {
+            (Ok(width_mm), Ok(circumference_mm)) if width_mm > 0.0 && circumference_mm > 0.0 => {
+                let wheel_diameter_mm = circumference_mm / std::f64::consts::PI;
+                Ok(Some(box_geometry(
+                    width_mm,
+                    wheel_diameter_mm,
+                    wheel_diameter_mm,
+                )))
+            }
+            (Err(AttributeError::KeyNotFound(_)), _) | (_, Err(AttributeError::KeyNotFound(_))) => {
+                Ok(None)
+            }
+            _ => Err(BaseError::BaseConfigError(
+                "width_mm and wheel_circumference_mm should be positive numbers",
+            )),
         }
     }
+
+    // Powers of the left and right motors for the given linear and angular powers
+    fn wheel_powers(&self, lin: &Vector3, ang: &Vector3) -> (f64, f64) {
+        let (l, r) = self.differential_drive(lin.y, ang.z);
+        (
+            if self.invert_left { -l } else { l },
+            if self.invert_right { -r } else { r },
+        )
+    }
     #[allow(clippy::only_used_in_recursion)]
     fn differential_drive(&self, forward: f64, left: f64) -> (f64, f64) {
         if forward < 0.0 {
@@ -84,9 +198,27 @@ where
                 };
             }
         }
+        let invert_left = cfg.get_attribute::<bool>("invert_left").unwrap_or_default();
+        let invert_right = cfg
+            .get_attribute::<bool>("invert_right")
+            .unwrap_or_default();
+        let geometry = Self::geometry_from_config(&cfg)?;
         if let Some(l_motor) = l_motor {
             if let Some(r_motor) = r_motor {
-                Ok(Arc::new(Mutex::new(WheeledBase::new(r_motor, l_motor))))
+                // deployed bases rely on the motors being passed in this order, the invert
+                // attributes follow the motor they are named after
+                let mut base =
+                    WheeledBase::new(r_motor, l_motor).with_inverted(invert_right, invert_left);
+                if let Some(geometry) = geometry {
+                    base = base.with_geometry(geometry);
+                }
+                if let (Ok(width_mm), Ok(wheel_circumference_mm)) = (
+                    cfg.get_attribute::<f64>("width_mm"),
+                    cfg.get_attribute::<f64>("wheel_circumference_mm"),
+                ) {
+                    base = base.with_wheel_dimensions(width_mm, wheel_circumference_mm)?;
+                }
+                Ok(Arc::new(Mutex::new(base)))
             } else {
                 Err(BaseError::BaseConfigError("right motor couldn't be found"))
             }
@@ -108,17 +240,82 @@ where
         r_keys
     }
 }
+
+impl<ML, MR> WheeledBase<ML, MR>
+where
+    ML: Motor + Clone + 'static,
+    MR: Motor + Clone + 'static,
+{
+    // Turn the left and right wheels by `revolutions` at `left_rpm` and `right_rpm`
+    fn go_for_wheels(
+        &mut self,
+        left_rpm: f64,
+        right_rpm: f64,
+        revolutions: f64,
+    ) -> Result<(), BaseError> {
+        self.timed_move = None;
+        if revolutions == 0.0 {
+            return Ok(());
+        }
+        let left_rpm = if self.invert_left {
+            -left_rpm
+        } else {
+            left_rpm
+        };
+        let right_rpm = if self.invert_right {
+            -right_rpm
+        } else {
+            right_rpm
+        };
+        let left = self.motor_left.go_for(left_rpm, revolutions)?;
+        let right = self.motor_right.go_for(right_rpm, revolutions)?;
+        // motors without position control only tell how long to run for, both are stopped once
+        // the longest of the two moves is done
+        if let Some(duration) = left.max(right) {
+            let timer = (self.move_timer)(duration);
+            let (mut left, mut right) = (self.motor_left.clone(), self.motor_right.clone());
+            self.timed_move = Some(Executor::new().spawn(async move {
+                timer.await;
+                if let Err(e) = left.stop().and_then(|_| right.stop()) {
+                    log::error!("failed to stop base at the end of its move: {}", e);
+                }
+            }));
+        }
+        Ok(())
+    }
+
+    // a move at a null speed would never end
+    fn check_speed(speed: f64) -> Result<(), BaseError> {
+        if !speed.is_finite() || speed == 0.0 {
+            return Err(BaseError::BaseInvalidArgument(
+                "the speed of a spin or straight move should be a non zero number",
+            ));
+        }
+        Ok(())
+    }
+
+    fn get_wheel_dimensions(&self) -> Result<(f64, f64), BaseError> {
+        self.wheel_dimensions.ok_or(BaseError::BaseConfigError(
+            "width_mm and wheel_circumference_mm are needed to spin or move straight",
+        ))
+    }
+}
+
 impl<ML, MR> Status for WheeledBase<ML, MR>
 where
-    ML: Motor,
-    MR: Motor,
+    ML: Motor + Clone,
+    MR: Motor + Clone,
 {
     fn get_status(&self) -> Result<Option<google::protobuf::Struct>, StatusError> {
+        // motors handles are shared, asking a copy whether it runs doesn't need `&mut self`
+        let is_moving = self.is_timed_move_running()
+            || self.motor_left.clone().is_moving()?
+            || self.motor_right.clone().is_moving()?;
         let mut hm = HashMap::new();
         hm.insert(
             "is_moving".to_string(),
             google::protobuf::Value {
-                kind: Some(google::protobuf::value::Kind::BoolValue(false)),
+                kind: Some(google::protobuf::value::Kind::BoolValue(is_moving)),
             },
         );
         Ok(Some(google::protobuf::Struct { fields: hm }))
@@ -130,10 +327,14 @@ where
     ML: Motor,
     MR: Motor,
 {
+    /// A spin or straight move is in progress until its timer completed and both motors stopped
     fn is_moving(&mut self) -> Result<bool, ActuatorError> {
-        Ok(self.motor_left.is_moving()? || self.motor_right.is_moving()?)
+        Ok(self.is_timed_move_running()
+            || self.motor_left.is_moving()?
+            || self.motor_right.is_moving()?)
     }
     fn stop(&mut self) -> Result<(), ActuatorError> {
+        self.timed_move = None;
         self.motor_left.stop()?;
         self.motor_right.stop()?;
         Ok(())
@@ -142,13 +343,225 @@ where
 
 impl<ML, MR> Base for WheeledBase<ML, MR>
 where
-    ML: Motor,
-    MR: Motor,
+    ML: Motor + Clone + 'static,
+    MR: Motor + Clone + 'static,
 {
     fn set_power(&mut self, lin: &Vector3, ang: &Vector3) -> Result<(), BaseError> {
-        let (l, r) = self.differential_drive(lin.y, ang.z);
+        self.timed_move = None;
+        let (l, r) = self.wheel_powers(lin, ang);
         self.motor_left.set_power(l)?;
         self.motor_right.set_power(r)?;
         Ok(())
     }
+    fn get_geometries(&self) -> Result<Vec<Geometry>, BaseError> {
+        Ok(self.geometry.iter().cloned().collect())
+    }
+    fn spin(&mut self, angle_deg: f64, degs_per_sec: f64) -> Result<(), BaseError> {
+        Self::check_speed(degs_per_sec)?;
+        let (width_mm, wheel_circumference_mm) = self.get_wheel_dimensions()?;
+        // the wheels travel in opposite directions along a circle as wide as the base
+        let wheel_revs_per_deg = std::f64::consts::PI * width_mm / 360.0 / wheel_circumference_mm;
+        let rpm = degs_per_sec * wheel_revs_per_deg * 60.0;
+        self.go_for_wheels(-rpm, rpm, angle_deg * wheel_revs_per_deg)
+    }
+    fn move_straight(&mut self, distance_mm: f64, mm_per_sec: f64) -> Result<(), BaseError> {
+        Self::check_speed(mm_per_sec)?;
+        let (_, wheel_circumference_mm) = self.get_wheel_dimensions()?;
+        let rpm = mm_per_sec / wheel_circumference_mm * 60.0;
+        self.go_for_wheels(rpm, rpm, distance_mm / wheel_circumference_mm)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::config::Kind;
+    use crate::common::exec::{yield_now, Executor};
+    use crate::common::motor::FakeMotor;
+    use crate::common::test_utils::component_config;
+    use crate::proto::common::v1::geometry::GeometryType;
+
+    #[test_log::test]
+    fn test_wheeled_base_inverted() {
+        let forward = Vector3 {
+            x: 0.0,
+            y: 0.5,
+            z: 0.0,
+        };
+        let spin = Vector3 {
+            x: 0.0,
+            y: 0.0,
+            z: 0.5,
+        };
+        let base = WheeledBase::new(FakeMotor::new(), FakeMotor::new());
+        let (l, r) = base.wheel_powers(&forward, &Vector3::default());
+        assert!(l > 0.0 && r > 0.0);
+        // turning left spins the left wheel backwards
+        let (l, r) = base.wheel_powers(&Vector3::default(), &spin);
+        assert!(l < 0.0 && r > 0.0);
+
+        let base = WheeledBase::new(FakeMotor::new(), FakeMotor::new()).with_inverted(true, false);
+        let (l, r) = base.wheel_powers(&forward, &Vector3::default());
+        assert!(l < 0.0 && r > 0.0);
+        let (l, r) = base.wheel_powers(&Vector3::default(), &spin);
+        assert!(l > 0.0 && r > 0.0);
+    }
+
+    #[test_log::test]
+    fn test_wheeled_base_geometry() {
+        let geometry = |attributes: Vec<(&str, Kind)>| {
+            WheeledBase::<FakeMotor, FakeMotor>::geometry_from_config(&ConfigType::Dynamic(
+                &component_config(attributes),
+            ))
+        };
+        assert!(geometry(vec![("width_mm", Kind::NumberValue(300.0))])
+            .unwrap()
+            .is_none());
+
+        let computed = geometry(vec![
+            ("width_mm", Kind::NumberValue(300.0)),
+            (
+                "wheel_circumference_mm",
+                Kind::NumberValue(100.0 * std::f64::consts::PI),
+            ),
+        ])
+        .unwrap()
+        .unwrap();
+        assert_eq!(computed, box_geometry(300.0, 100.0, 100.0));
+
+        // an explicit geometry wins over the computed one
+        let explicit = geometry(vec![
+            ("width_mm", Kind::NumberValue(300.0)),
+            ("wheel_circumference_mm", Kind::NumberValue(314.0)),
+            (
+                "geometry",
+                Kind::StructValue(HashMap::from([
+                    ("type".to_owned(), Kind::StringValue("sphere".to_owned())),
+                    ("r".to_owned(), Kind::NumberValue(250.0)),
+                    (
+                        "translation".to_owned(),
+                        Kind::StructValue(HashMap::from([(
+                            "z".to_owned(),
+                            Kind::NumberValue(50.0),
+                        )])),
+                    ),
+                ])),
+            ),
+        ])
+        .unwrap()
+        .unwrap();
+        assert!(matches!(
+            explicit.geometry_type,
+            Some(GeometryType::Sphere(ref sphere)) if sphere.radius_mm == 250.0
+        ));
+        assert_eq!(explicit.center.unwrap().z, 50.0);
+
+        for invalid in [
+            vec![
+                ("width_mm", Kind::NumberValue(-300.0)),
+                ("wheel_circumference_mm", Kind::NumberValue(314.0)),
+            ],
+            vec![(
+                "geometry",
+                Kind::StructValue(HashMap::from([(
+                    "type".to_owned(),
+                    Kind::StringValue("box".to_owned()),
+                )])),
+            )],
+            vec![(
+                "geometry",
+                Kind::StructValue(HashMap::from([
+                    ("type".to_owned(), Kind::StringValue("cone".to_owned())),
+                    ("r".to_owned(), Kind::NumberValue(250.0)),
+                ])),
+            )],
+        ] {
+            assert!(geometry(invalid).is_err());
+        }
+
+        let base = WheeledBase::new(FakeMotor::new(), FakeMotor::new());
+        assert!(base.get_geometries().unwrap().is_empty());
+        let base = base.with_geometry(computed.clone());
+        assert_eq!(base.get_geometries().unwrap(), vec![computed]);
+    }
+
+    #[test_log::test]
+    fn test_wheeled_base_spin() {
+        let left = Arc::new(Mutex::new(FakeMotor::new()));
+        let right = Arc::new(Mutex::new(FakeMotor::new()));
+        let mut base = WheeledBase::new(left.clone(), right.clone());
+        assert!(base.spin(90.0, 300.0).is_err());
+        assert!(base.with_wheel_dimensions(0.0, 100.0).is_err());
+
+        // null or non finite speeds are refused before the motors are touched
+        let mut base = WheeledBase::new(left.clone(), right.clone())
+            .with_wheel_dimensions(100.0, 100.0)
+            .unwrap();
+        for speed in [0.0, f64::NAN, f64::INFINITY] {
+            assert!(matches!(
+                base.spin(90.0, speed),
+                Err(BaseError::BaseInvalidArgument(_))
+            ));
+            assert!(matches!(
+                base.move_straight(100.0, speed),
+                Err(BaseError::BaseInvalidArgument(_))
+            ));
+        }
+        assert!(!base.is_moving().unwrap());
+
+        // motors set running directly are reported as moving
+        right.set_power(0.5).unwrap();
+        assert!(base.is_moving().unwrap());
+        assert!(matches!(
+            base.get_status().unwrap().unwrap().fields["is_moving"].kind,
+            Some(google::protobuf::value::Kind::BoolValue(true))
+        ));
+        base.stop().unwrap();
+        assert!(matches!(
+            base.get_status().unwrap().unwrap().fields["is_moving"].kind,
+            Some(google::protobuf::value::Kind::BoolValue(false))
+        ));
+
+        // the moves last until the test ends them
+        let (durations_tx, durations_rx) = async_channel::unbounded();
+        let (end_tx, end_rx) = async_channel::unbounded::<()>();
+        let mut base = WheeledBase::new(left, right.clone())
+            .with_wheel_dimensions(100.0, 100.0 * std::f64::consts::PI)
+            .unwrap()
+            .with_move_timer(Box::new(move |duration| {
+                durations_tx.try_send(duration).unwrap();
+                let end_rx = end_rx.clone();
+                Box::pin(async move {
+                    let _ = end_rx.recv().await;
+                })
+            }));
+        let exec = Executor::new();
+
+        // a quarter turn is a quarter revolution of each wheel, made at 50rpm in 300ms
+        base.spin(90.0, 300.0).unwrap();
+        let duration = durations_rx.try_recv().unwrap();
+        assert!((duration.as_secs_f64() - 0.3).abs() < 1e-6);
+        exec.block_on(yield_now());
+        assert!(right.is_moving().unwrap());
+        assert!(base.is_moving().unwrap());
+
+        // the motors are stopped once the move is over
+        end_tx.try_send(()).unwrap();
+        exec.block_on(async {
+            while base.is_moving().unwrap() {
+                yield_now().await;
+            }
+        });
+        assert!(!right.is_moving().unwrap());
+
+        // stopping cancels the move
+        base.move_straight(1000.0, 100.0).unwrap();
+        assert!(durations_rx.try_recv().is_ok());
+        assert!(base.is_moving().unwrap());
+        base.stop().unwrap();
+        assert!(!base.is_moving().unwrap());
+        end_tx.try_send(()).unwrap();
+        exec.block_on(yield_now());
+        assert!(!base.is_moving().unwrap());
+    }
 }
@@ -1,5 +1,7 @@
 #![allow(dead_code)]
 use crate::common::analog::{AnalogError, AnalogReader, AnalogResolution};
+#[cfg(esp32)]
+use crate::common::{analog::AnalogWriter, board::BoardError};
 use crate::esp32::esp_idf_svc::hal::adc::{AdcChannelDriver, AdcDriver};
 use crate::esp32::esp_idf_svc::hal::gpio::ADCPin;
 use std::sync::{Arc, Mutex};
@@ -61,3 +63,49 @@ impl<const A: u32, T: ADCPin> AnalogReader<u16> for Esp32AnalogReader<'_, A, T>
         }
     }
 }
+
+/// An analog writer backed by one of the two 8 bits DAC channels of the ESP32 (GPIO25 and
+/// GPIO26), written values range from 0 to 255
+#[cfg(esp32)]
+pub struct Esp32AnalogWriter {
+    name: String,
+    channel: crate::esp32::esp_idf_svc::sys::dac_channel_t,
+}
+
+#[cfg(esp32)]
+impl Esp32AnalogWriter {
+    pub fn new(name: String, pin: i32) -> Result<Self, BoardError> {
+        use crate::esp32::esp_idf_svc::sys::{
+            dac_channel_t_DAC_CHANNEL_1, dac_channel_t_DAC_CHANNEL_2, dac_output_enable, esp,
+        };
+        let channel = match pin {
+            25 => dac_channel_t_DAC_CHANNEL_1,
+            26 => dac_channel_t_DAC_CHANNEL_2,
+            _ => return Err(BoardError::GpioPinError(pin as u32, "Pin is not a DAC pin")),
+        };
+        esp!(unsafe { dac_output_enable(channel) })?;
+        Ok(Self { name, channel })
+    }
+}
+
+#[cfg(esp32)]
+impl AnalogWriter<u16> for Esp32AnalogWriter {
+    type Error = AnalogError;
+    fn write(&mut self, value: u16) -> Result<(), Self::Error> {
+        use crate::esp32::esp_idf_svc::sys::{dac_output_voltage, esp};
+        let value = u8::try_from(value)
+            .map_err(|_| AnalogError::AnalogValueOutOfRange(value, u8::MAX as u16))?;
+        esp!(unsafe { dac_output_voltage(self.channel, value) })
+            .map_err(|e| AnalogError::AnalogWriteError(e.code()))
+    }
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+}
+
+#[cfg(esp32)]
+impl Drop for Esp32AnalogWriter {
+    fn drop(&mut self) {
+        unsafe { crate::esp32::esp_idf_svc::sys::dac_output_disable(self.channel) };
+    }
+}
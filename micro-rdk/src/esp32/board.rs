@@ -8,11 +8,15 @@ use std::{
 
 use crate::{
     common::{
-        analog::{AnalogReader, AnalogReaderType},
-        board::{Board, BoardError, BoardType},
+        analog::{AnalogReader, AnalogReaderType, AnalogWriter, AnalogWriterType},
+        board::{
+            board_do_command, default_pin_levels, feed_external_watchdog, Board, BoardError,
+            BoardType, ExternalWatchdogConfig,
+        },
         config::ConfigType,
         digital_interrupt::DigitalInterruptConfig,
-        i2c::I2cHandleType,
+        generic::{DoCommand, GenericError},
+        i2c::{add_i2c_muxes, I2cHandleType},
         registry::ComponentRegistry,
         status::{Status, StatusError},
     },
@@ -21,15 +25,15 @@ use crate::{
 };
 
 #[cfg(esp32)]
-use crate::common::analog::AnalogReaderConfig;
+use crate::common::analog::{AnalogReaderConfig, AnalogWriterConfig};
 
 use super::{
     i2c::{Esp32I2C, Esp32I2cConfig},
-    pin::Esp32GPIOPin,
+    pin::{Esp32GPIOPin, Esp32InputPin},
 };
 
 #[cfg(esp32)]
-use super::analog::Esp32AnalogReader;
+use super::analog::{Esp32AnalogReader, Esp32AnalogWriter};
 
 #[cfg(esp32)]
 use crate::esp32::esp_idf_svc::hal::adc::{
@@ -37,7 +41,7 @@ use crate::esp32::esp_idf_svc::hal::adc::{
     AdcDriver, ADC1,
 };
 
-use crate::esp32::esp_idf_svc::hal::gpio::InterruptType;
+use crate::esp32::esp_idf_svc::hal::gpio::{InterruptType, Pull};
 
 pub(crate) fn register_models(registry: &mut ComponentRegistry) {
     if registry
@@ -49,10 +53,20 @@ pub(crate) fn register_models(registry: &mut ComponentRegistry) {
 }
 
 /// An ESP32 implementation that wraps esp-idf functionality
-#[derive(DoCommand)]
+///
+/// Output levels to apply as soon as the board is built can be set with the `default_pin_levels`
+/// attribute mapping pin numbers to `true` (high) or `false` (low), e.g. `{"12": false}`.
+///
+/// The PWM frequency of a pin can be changed at runtime through DoCommand with
+/// `{"set_pwm_frequency": {"pin": 12, "frequency_hz": 50}}`.
+///
+/// The DAC channels (GPIO25 and GPIO26) are exposed as analog writers with the
+/// `analog_writers` attribute, e.g. `[{"name": "valve", "pin": 25}]`.
 pub struct EspBoard {
     pins: Vec<Esp32GPIOPin>,
+    input_pins: Vec<Esp32InputPin>,
     analogs: Vec<AnalogReaderType<u16>>,
+    analog_writers: Vec<AnalogWriterType<u16>>,
     i2cs: HashMap<String, I2cHandleType>,
 }
 
@@ -64,7 +78,9 @@ impl EspBoard {
     ) -> Self {
         EspBoard {
             pins,
+            input_pins: vec![],
             analogs,
+            analog_writers: vec![],
             i2cs,
         }
     }
@@ -221,6 +237,23 @@ impl EspBoard {
                 .unwrap_or_default();
             (analogs, pins, i2c_confs)
         };
+        // safe output levels (e.g. relays off) are driven as soon as the pins exist, before the
+        // rest of the board and the components depending on it are built
+        for (pin, is_high) in default_pin_levels(&cfg)? {
+            let idx = match pins.iter().position(|p| p.pin() == pin) {
+                Some(idx) => idx,
+                None => {
+                    pins.push(Esp32GPIOPin::new(pin, None)?);
+                    pins.len() - 1
+                }
+            };
+            let p = &mut pins[idx];
+            if is_high {
+                p.set_high()?;
+            } else {
+                p.set_low()?;
+            }
+        }
         let mut i2cs = HashMap::new();
         for conf in i2c_confs.iter() {
             let name = conf.name.to_string();
@@ -228,6 +261,13 @@ impl EspBoard {
             let i2c_wrapped: I2cHandleType = Arc::new(Mutex::new(i2c));
             i2cs.insert(name.to_string(), i2c_wrapped);
         }
+        add_i2c_muxes(&cfg, &mut i2cs)?;
+        let watchdog = ExternalWatchdogConfig::from_config(&cfg)?;
+        if let Some(watchdog) = watchdog.as_ref() {
+            if !pins.iter().any(|p| p.pin() == watchdog.pin) {
+                pins.push(Esp32GPIOPin::new(watchdog.pin, None)?);
+            }
+        }
         if let Ok(interrupt_confs) =
             cfg.get_attribute::<Vec<DigitalInterruptConfig>>("digital_interrupts")
         {
@@ -244,11 +284,38 @@ impl EspBoard {
                 }
             }
         }
-        Ok(Arc::new(Mutex::new(Self {
+        // the DAC is only available on the original esp32, its pins are checked once every GPIO
+        // pin (default levels, watchdog, interrupts) is known so a DAC output isn't also driven
+        #[cfg(not(esp32))]
+        let analog_writers = vec![];
+        #[cfg(esp32)]
+        let analog_writers = cfg
+            .get_attribute::<Vec<AnalogWriterConfig>>("analog_writers")
+            .unwrap_or_default()
+            .into_iter()
+            .map(|conf| {
+                if pins.iter().any(|p| p.pin() == conf.pin) {
+                    return Err(BoardError::GpioPinError(
+                        conf.pin as u32,
+                        "is configured as a GPIO pin, it can't be an analog writer",
+                    ));
+                }
+                let writer: AnalogWriterType<u16> =
+                    Arc::new(Mutex::new(Esp32AnalogWriter::new(conf.name, conf.pin)?));
+                Ok(writer)
+            })
+            .collect::<Result<Vec<_>, BoardError>>()?;
+        let board = Arc::new(Mutex::new(Self {
             pins,
+            input_pins: vec![],
             analogs,
+            analog_writers,
             i2cs,
-        })))
+        }));
+        if let Some(watchdog) = watchdog {
+            feed_external_watchdog(&board, watchdog).detach();
+        }
+        Ok(board)
     }
 }
 
@@ -271,6 +338,9 @@ impl Board for EspBoard {
         Err(BoardError::GpioPinError(pin as u32, "not an output"))
     }
     fn get_gpio_level(&self, pin: i32) -> Result<bool, BoardError> {
+        if let Some(input) = self.input_pins.iter().find(|p| p.pin() == pin) {
+            return Ok(input.is_high());
+        }
         let pin = self
             .pins
             .iter()
@@ -278,6 +348,26 @@ impl Board for EspBoard {
             .ok_or(BoardError::GpioPinError(pin as u32, "not registered"))?;
         Ok(pin.is_high())
     }
+    fn configure_input_pin(&mut self, pin: i32, pull_up: bool) -> Result<(), BoardError> {
+        if self.input_pins.iter().any(|p| p.pin() == pin) {
+            return Ok(());
+        }
+        // registered pins are driven in input/output mode, they would fight the device driving
+        // the line
+        if let Some(idx) = self.pins.iter().position(|p| p.pin() == pin) {
+            if self.pins[idx].is_interrupt() {
+                return Err(BoardError::GpioPinError(
+                    pin as u32,
+                    "is registered as an interrupt",
+                ));
+            }
+            log::warn!("pin {} is used as an input, it can no longer be set", pin);
+            let _ = self.pins.swap_remove(idx);
+        }
+        let pull = pull_up.then_some(Pull::Up);
+        self.input_pins.push(Esp32InputPin::new(pin, pull)?);
+        Ok(())
+    }
     fn get_pwm_duty(&self, pin: i32) -> f64 {
         match self.pins.iter().find(|p| p.pin() == pin) {
             None => 0.0,
@@ -308,6 +398,12 @@ impl Board for EspBoard {
             .ok_or(BoardError::GpioPinError(pin as u32, "not registered"))?;
         pin.set_pwm_frequency(frequency_hz)
     }
+    fn write_analog(&mut self, name: &str, value: u16) -> Result<(), BoardError> {
+        match self.analog_writers.iter_mut().find(|w| w.name() == name) {
+            Some(writer) => Ok(writer.write(value)?),
+            None => Err(BoardError::AnalogWriterNotFound(name.to_owned())),
+        }
+    }
     fn get_analog_reader_by_name(&self, name: String) -> Result<AnalogReaderType<u16>, BoardError> {
         match self.analogs.iter().find(|a| a.name() == name) {
             Some(reader) => Ok(reader.clone()),
@@ -370,6 +466,15 @@ impl Board for EspBoard {
     }
 }
 
+impl DoCommand for EspBoard {
+    fn do_command(
+        &mut self,
+        command_struct: Option<google::protobuf::Struct>,
+    ) -> Result<Option<google::protobuf::Struct>, GenericError> {
+        board_do_command(self, command_struct)
+    }
+}
+
 impl Status for EspBoard {
     fn get_status(&self) -> Result<Option<google::protobuf::Struct>, StatusError> {
         let mut hm = HashMap::new();
@@ -1,3 +1,20 @@
+//! Camera driver for the sensors supported by the esp32-camera component (OV2640, OV3660...)
+//!
+//! Supported DoCommand commands, each answering the resulting `auto_exposure`, `auto_gain`,
+//! `exposure` and `gain` of the sensor:
+//! - `{"set_auto_exposure": bool}` turns the automatic exposure control (AEC) on or off
+//! - `{"set_auto_gain": bool}` turns the automatic gain control (AGC) on or off
+//! - `{"set_exposure": n}` sets a manual exposure between 0 and 1200, turning AEC off
+//! - `{"set_gain": n}` sets a manual gain between 0 and 30, turning AGC off
+//! - `{"reset_auto": null}` turns AEC and AGC back on
+//! - `{"get_exposure": null}` only returns the current values
+//!
+//! Modules streaming depth over the camera interface are configured with the `depth` attribute
+//! holding their [DepthIntrinsics]. Frames are then captured raw, 2 bytes per pixel, and
+//! projected to point clouds by `get_point_cloud` while `get_image` is unavailable.
+//!
+//! Frames are flipped by the sensor with the `hmirror` and `vflip` attributes. A `rotation` of
+//! 180 degrees flips both axes on the sensor, 90 and 270 degrees aren't supported.
 #![allow(dead_code)]
 use std::{
     collections::HashMap,
@@ -6,8 +23,12 @@ use std::{
 
 use crate::{
     common::{
-        camera::{Camera, CameraError, CameraType},
+        camera::{
+            encode_depth_pcd, rotation_from_config, Camera, CameraError, CameraType,
+            DepthIntrinsics, FrameBufferPool, MimeType,
+        },
         config::ConfigType,
+        generic::{DoCommand, GenericError},
         registry::{ComponentRegistry, Dependency},
         status::{Status, StatusError},
     },
@@ -15,11 +36,15 @@ use crate::{
         camera::{
             camera_config_t, camera_config_t__bindgen_ty_1, camera_config_t__bindgen_ty_2,
             camera_fb_t, esp_camera_deinit, esp_camera_fb_get, esp_camera_fb_return,
-            esp_camera_init,
+            esp_camera_init, esp_camera_sensor_get, sensor_t,
         },
-        esp,
+        esp, heap_caps_get_free_size, heap_caps_get_largest_free_block, MALLOC_CAP_8BIT,
+    },
+    google::{
+        self,
+        api::HttpBody,
+        protobuf::{value::Kind, Struct, Value},
     },
-    google::{self, api::HttpBody},
     proto::component::camera,
 };
 use bytes::{Bytes, BytesMut};
@@ -35,6 +60,7 @@ pub(crate) fn register_models(registry: &mut ComponentRegistry) {
 
 static CAMERA_ALREADY_REGISTERED: Mutex<bool> = Mutex::new(false);
 
+#[derive(Clone, Copy, PartialEq, Eq)]
 enum PixelFormat {
     /// 2BPP
     RGB565 = 0,
@@ -76,11 +102,80 @@ enum FrameSize {
     HVGA = 7,
     /// 640x480, ~62.73KB JPEG
     VGA = 8,
+    /// 800x600
+    SVGA = 9,
+    /// 1024x768
+    XGA = 10,
+    /// 1280x720
+    HD = 11,
+    /// 1280x1024
+    SXGA = 12,
+    /// 1600x1200
+    UXGA = 13,
+}
+
+impl FrameSize {
+    fn from_u32(frame_size: u32) -> Option<Self> {
+        Some(match frame_size {
+            0 => Self::W96XH96,
+            1 => Self::QQVGA,
+            2 => Self::QCIF,
+            3 => Self::HQVGA,
+            4 => Self::W240XH240,
+            5 => Self::QVGA,
+            6 => Self::CIF,
+            7 => Self::HVGA,
+            8 => Self::VGA,
+            9 => Self::SVGA,
+            10 => Self::XGA,
+            11 => Self::HD,
+            12 => Self::SXGA,
+            13 => Self::UXGA,
+            _ => return None,
+        })
+    }
+
+    fn dimensions(&self) -> (usize, usize) {
+        match self {
+            Self::W96XH96 => (96, 96),
+            Self::QQVGA => (160, 120),
+            Self::QCIF => (176, 144),
+            Self::HQVGA => (240, 176),
+            Self::W240XH240 => (240, 240),
+            Self::QVGA => (320, 240),
+            Self::CIF => (400, 296),
+            Self::HVGA => (480, 320),
+            Self::VGA => (640, 480),
+            Self::SVGA => (800, 600),
+            Self::XGA => (1024, 768),
+            Self::HD => (1280, 720),
+            Self::SXGA => (1280, 1024),
+            Self::UXGA => (1600, 1200),
+        }
+    }
+
+    /// Largest JPEG frame the driver can produce, it sizes its frame buffers to a fifth of the
+    /// pixel count in JPEG mode
+    fn max_jpeg_bytes(&self) -> usize {
+        let (width, height) = self.dimensions();
+        width * height / 5
+    }
 }
 
-#[derive(DoCommand)]
+// large enough for a VGA JPEG frame
+const DEFAULT_MAX_FRAME_SIZE: usize = 64 * 1024;
+
+// ranges of the manual exposure and gain accepted by the sensors
+const MAX_EXPOSURE: f64 = 1200.0;
+const MAX_GAIN: f64 = 30.0;
+
+type SensorControl = Option<unsafe extern "C" fn(*mut sensor_t, i32) -> i32>;
+
 pub struct Esp32Camera {
     config: camera_config_t,
+    frames: FrameBufferPool,
+    // set for depth modules, whose frames are projected to point clouds
+    depth: Option<DepthIntrinsics>,
 }
 
 impl Esp32Camera {
@@ -116,6 +211,38 @@ impl Esp32Camera {
         let jpeg_quality = cfg.get_attribute::<i32>("jpeg_quality").unwrap_or(32);
         //  If pin_sccb_sda is -1, use the already configured I2C bus by number
         let sccb_i2c_port = cfg.get_attribute::<i32>("sccb_i2c_port").unwrap_or(-1);
+        // Size of the buffer frames are copied into, larger frames are rejected
+        let max_frame_size = cfg
+            .get_attribute::<usize>("max_frame_size")
+            .unwrap_or(DEFAULT_MAX_FRAME_SIZE);
+        // Orientation of the frames, a rotation by 180 degrees is done by the sensor flipping
+        // both axes
+        let hmirror = cfg.get_attribute::<bool>("hmirror").unwrap_or(false);
+        let vflip = cfg.get_attribute::<bool>("vflip").unwrap_or(false);
+        let rotated = rotation_from_config(&cfg)?;
+        let (hmirror, vflip) = (hmirror != rotated, vflip != rotated);
+        let depth = DepthIntrinsics::from_config(&cfg)?;
+        // depth modules send 16 bit depths, captured as is by the 2 bytes per pixel format
+        let pixel_format = match depth {
+            Some(_) => PixelFormat::RGB565,
+            None => PixelFormat::JPEG,
+        };
+        // fail now rather than later on captures of frames too big for the buffer, depth frames
+        // are projected straight from the driver's frame buffer
+        let frame_bytes = FrameSize::from_u32(frame_size)
+            .ok_or(CameraError::ConfigError(
+                "frame_size should be between 0 (96x96) and 13 (1600x1200)",
+            ))?
+            .max_jpeg_bytes();
+        if depth.is_none() && frame_bytes > max_frame_size {
+            return Err(CameraError::InitError(
+                format!(
+                    "frame_size {} produces frames of up to {} bytes but max_frame_size is {} bytes, use a smaller frame_size or raise max_frame_size to at least {}",
+                    frame_size, frame_bytes, max_frame_size, frame_bytes
+                )
+                .into(),
+            ));
+        }
 
         let config = camera_config_t {
             pin_pwdn,
@@ -137,7 +264,7 @@ impl Esp32Camera {
             xclk_freq_hz,
             ledc_channel,
             ledc_timer,
-            pixel_format: PixelFormat::JPEG as u32,
+            pixel_format: pixel_format as u32,
             frame_size,
             jpeg_quality,
             // Number of frame buffers to be allocated.
@@ -167,15 +294,193 @@ impl Esp32Camera {
         })?;
 
         *registered = true;
+        // dropping the camera takes the lock again
+        drop(registered);
+
+        let camera = Self {
+            config,
+            frames: FrameBufferPool::new(max_frame_size),
+            depth,
+        };
+        // dropping the camera on error deinitializes the driver
+        camera.set_orientation(hmirror, vflip)?;
+
+        Ok(Arc::new(Mutex::new(camera)))
+    }
 
-        Ok(Arc::new(Mutex::new(Self { config })))
+    // sensors start unflipped, only the configured flips are applied
+    fn set_orientation(&self, hmirror: bool, vflip: bool) -> Result<(), CameraError> {
+        if !hmirror && !vflip {
+            return Ok(());
+        }
+        let sensor = Self::sensor()?;
+        if hmirror {
+            let control = unsafe { (*sensor).set_hmirror };
+            if control.is_none() {
+                return Err(CameraError::ConfigError(
+                    "the camera sensor can't mirror frames, remove hmirror or rotation",
+                ));
+            }
+            Self::set_control(sensor, control, 1)?;
+        }
+        if vflip {
+            let control = unsafe { (*sensor).set_vflip };
+            if control.is_none() {
+                return Err(CameraError::ConfigError(
+                    "the camera sensor can't flip frames, remove vflip or rotation",
+                ));
+            }
+            Self::set_control(sensor, control, 1)?;
+        }
+        Ok(())
+    }
+
+    fn sensor() -> Result<*mut sensor_t, CameraError> {
+        let sensor = unsafe { esp_camera_sensor_get() };
+        if sensor.is_null() {
+            return Err(CameraError::CameraGenericError("camera sensor not found"));
+        }
+        Ok(sensor)
+    }
+
+    // the controls a sensor doesn't support are left unset by the driver
+    fn set_control(
+        sensor: *mut sensor_t,
+        control: SensorControl,
+        value: i32,
+    ) -> Result<(), CameraError> {
+        let control = control.ok_or(CameraError::CameraMethodUnimplemented(
+            "control not supported by the camera sensor",
+        ))?;
+        if unsafe { control(sensor, value) } != 0 {
+            return Err(CameraError::CameraGenericError(
+                "camera sensor rejected the setting",
+            ));
+        }
+        Ok(())
+    }
+
+    fn exposure_status(sensor: *mut sensor_t) -> HashMap<String, Value> {
+        let status = unsafe { &(*sensor).status };
+        HashMap::from([
+            (
+                "auto_exposure".to_owned(),
+                Value {
+                    kind: Some(Kind::BoolValue(status.aec != 0)),
+                },
+            ),
+            (
+                "auto_gain".to_owned(),
+                Value {
+                    kind: Some(Kind::BoolValue(status.agc != 0)),
+                },
+            ),
+            (
+                "exposure".to_owned(),
+                Value {
+                    kind: Some(Kind::NumberValue(status.aec_value as f64)),
+                },
+            ),
+            (
+                "gain".to_owned(),
+                Value {
+                    kind: Some(Kind::NumberValue(status.agc_gain as f64)),
+                },
+            ),
+        ])
+    }
+}
+
+fn bool_arg(command: &str, value: &Value) -> Result<bool, GenericError> {
+    match value.kind {
+        Some(Kind::BoolValue(enabled)) => Ok(enabled),
+        _ => Err(GenericError::Other(
+            format!("{} expects a boolean", command).into(),
+        )),
+    }
+}
+
+fn number_arg(command: &str, value: &Value, max: f64) -> Result<i32, GenericError> {
+    match value.kind {
+        Some(Kind::NumberValue(n)) if (0.0..=max).contains(&n) => Ok(n as i32),
+        _ => Err(GenericError::Other(
+            format!("{} expects a number between 0 and {}", command, max).into(),
+        )),
+    }
+}
+
+impl DoCommand for Esp32Camera {
+    fn do_command(
+        &mut self,
+        command_struct: Option<Struct>,
+    ) -> Result<Option<Struct>, GenericError> {
+        let Some(command_struct) = command_struct else {
+            return Ok(None);
+        };
+        let sensor = Self::sensor().map_err(|e| GenericError::Other(e.into()))?;
+        let set = |control, value| {
+            Self::set_control(sensor, control, value).map_err(|e| GenericError::Other(e.into()))
+        };
+        for (key, val) in &command_struct.fields {
+            match key.as_str() {
+                "set_auto_exposure" => set(
+                    unsafe { (*sensor).set_exposure_ctrl },
+                    bool_arg(key, val)? as i32,
+                )?,
+                "set_auto_gain" => set(
+                    unsafe { (*sensor).set_gain_ctrl },
+                    bool_arg(key, val)? as i32,
+                )?,
+                "set_exposure" => {
+                    let exposure = number_arg(key, val, MAX_EXPOSURE)?;
+                    set(unsafe { (*sensor).set_exposure_ctrl }, 0)?;
+                    set(unsafe { (*sensor).set_aec_value }, exposure)?;
+                }
+                "set_gain" => {
+                    let gain = number_arg(key, val, MAX_GAIN)?;
+                    set(unsafe { (*sensor).set_gain_ctrl }, 0)?;
+                    set(unsafe { (*sensor).set_agc_gain }, gain)?;
+                }
+                "reset_auto" => {
+                    set(unsafe { (*sensor).set_exposure_ctrl }, 1)?;
+                    set(unsafe { (*sensor).set_gain_ctrl }, 1)?;
+                }
+                "get_exposure" => {}
+                _ => {
+                    return Err(GenericError::Other(
+                        format!("unknown esp32-camera command {}", key).into(),
+                    ))
+                }
+            }
+        }
+        Ok(Some(Struct {
+            fields: Self::exposure_status(sensor),
+        }))
     }
 }
 
 impl Camera for Esp32Camera {
-    fn get_image(&mut self) -> Result<Bytes, CameraError> {
+    fn get_image(&mut self, mime_type: Option<MimeType>) -> Result<Bytes, CameraError> {
+        if self.depth.is_some() {
+            return Err(CameraError::CameraMethodUnimplemented("get_image"));
+        }
+        // frames are compressed by the sensor, decoding them would need more memory than a
+        // microcontroller can spare
+        if !matches!(mime_type, None | Some(MimeType::Jpeg)) {
+            return Err(CameraError::CameraGenericError(
+                "esp32-camera only produces image/jpeg",
+            ));
+        }
+        let frame = Esp32CameraFrameBuffer::get().ok_or(CameraError::FailedToGetImage)?;
+        self.frames.copy_frame(frame.as_slice())
+    }
+    fn get_point_cloud(&mut self) -> Result<Bytes, CameraError> {
+        let intrinsics = self
+            .depth
+            .as_ref()
+            .ok_or(CameraError::CameraMethodUnimplemented("get_point_cloud"))?;
         let frame = Esp32CameraFrameBuffer::get().ok_or(CameraError::FailedToGetImage)?;
-        Ok(frame.as_bytes())
+        encode_depth_pcd(intrinsics, frame.width(), frame.height(), frame.as_slice())
     }
 }
 
@@ -189,10 +494,29 @@ impl Drop for Esp32Camera {
     }
 }
 
+// Reports how often frames needed an allocation along with the free heap and its largest block,
+// the heap is fragmented when the largest free block is much smaller than the free heap
 impl Status for Esp32Camera {
     fn get_status(&self) -> Result<Option<google::protobuf::Struct>, StatusError> {
+        let (free_heap, largest_free_block) = unsafe {
+            (
+                heap_caps_get_free_size(MALLOC_CAP_8BIT),
+                heap_caps_get_largest_free_block(MALLOC_CAP_8BIT),
+            )
+        };
+        let number = |n: usize| Value {
+            kind: Some(Kind::NumberValue(n as f64)),
+        };
         Ok(Some(google::protobuf::Struct {
-            fields: HashMap::new(),
+            fields: HashMap::from([
+                ("frames".to_owned(), number(self.frames.frames())),
+                (
+                    "frame_allocations".to_owned(),
+                    number(self.frames.allocations()),
+                ),
+                ("free_heap".to_owned(), number(free_heap)),
+                ("largest_free_block".to_owned(), number(largest_free_block)),
+            ]),
         }))
     }
 }
@@ -233,9 +557,8 @@ impl Esp32CameraFrameBuffer {
     fn buf(&self) -> *const u8 {
         unsafe { (*(self.0)).buf }
     }
-    fn as_bytes(self) -> Bytes {
-        let buf = unsafe { core::slice::from_raw_parts(self.buf(), self.len()) };
-        Bytes::from(buf)
+    fn as_slice(&self) -> &[u8] {
+        unsafe { core::slice::from_raw_parts(self.buf(), self.len()) }
     }
 }
 
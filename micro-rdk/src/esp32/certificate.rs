@@ -21,7 +21,10 @@ use esp_idf_svc::sys::{
     SHA_TYPE_SHA2_256,
 };
 
-use crate::common::webrtc::certificate::{Certificate, Fingerprint};
+use crate::common::{
+    credentials_storage::CachedWebRtcCertificate,
+    webrtc::certificate::{Certificate, Fingerprint},
+};
 
 #[derive(Clone)]
 pub struct WebRtcCertificate {
@@ -148,6 +151,12 @@ impl GeneratedWebRtcCertificateBuilder {
         self.not_after = not_after;
         self
     }
+    /// Builds a certificate to be kept in storage, along with its end of validity
+    pub fn build_cached(self) -> Result<CachedWebRtcCertificate, MbedTLSError> {
+        let not_after = self.not_after.and_utc().timestamp().max(0) as u64;
+        let cert = self.build()?;
+        Ok(CachedWebRtcCertificate::new(&cert, not_after))
+    }
     pub fn build(mut self) -> Result<WebRtcCertificate, MbedTLSError> {
         unsafe {
             MbedTLSError::to_unit_result(mbedtls_ctr_drbg_seed(
@@ -18,7 +18,7 @@ use {
     crate::esp32::esp_idf_svc::{
         eventloop::{EspSubscription, EspSystemEventLoop, System},
         handle::RawHandle,
-        netif::EspNetif,
+        netif::{EspNetif, IpEvent},
         sys,
         sys::esp_wifi_set_ps,
         wifi::{EspWifi, WifiEvent},
@@ -31,6 +31,7 @@ use {
 
 use esp_idf_svc::{
     hal::modem::WifiModem,
+    ipv4::IpInfo,
     sys::{
         esp_interface_t_ESP_IF_WIFI_STA, esp_wifi_get_config, esp_wifi_set_config, wifi_config_t,
         wifi_scan_method_t_WIFI_ALL_CHANNEL_SCAN, wifi_sort_method_t_WIFI_CONNECT_AP_BY_SIGNAL,
@@ -49,6 +50,18 @@ use crate::{
 #[cfg(feature = "qemu")]
 use crate::esp32::esp_idf_svc::eth::{BlockingEth, EspEth, OpenEth};
 
+// SSID of the access point the station is associated with
+fn esp32_sta_ssid() -> Option<String> {
+    let mut ap_info = sys::wifi_ap_record_t::default();
+    sys::esp!(unsafe { sys::esp_wifi_sta_get_ap_info(&mut ap_info as *mut _) }).ok()?;
+    let len = ap_info
+        .ssid
+        .iter()
+        .position(|c| *c == 0)
+        .unwrap_or(ap_info.ssid.len());
+    Some(String::from_utf8_lossy(&ap_info.ssid[..len]).into_owned())
+}
+
 pub(crate) fn esp32_get_system_event_loop() -> Result<&'static EspSystemEventLoop, EspError> {
     static INSTANCE: OnceCell<EspSystemEventLoop> = OnceCell::new();
     INSTANCE.get_or_try_init(EspSystemEventLoop::take)
@@ -70,11 +83,46 @@ pub(crate) fn esp32_get_wifi() -> Result<&'static Mutex<AsyncWifi<EspWifi<'stati
     })
 }
 
+// DNS server slots of a netif, in the order configured servers are assigned to them
+const DNS_SERVER_SLOTS: [sys::esp_netif_dns_type_t; 3] = [
+    sys::esp_netif_dns_type_t_ESP_NETIF_DNS_MAIN,
+    sys::esp_netif_dns_type_t_ESP_NETIF_DNS_BACKUP,
+    sys::esp_netif_dns_type_t_ESP_NETIF_DNS_FALLBACK,
+];
+
+fn esp32_set_dns_servers(
+    handle: *mut sys::esp_netif_t,
+    servers: &[Ipv4Addr],
+) -> Result<(), EspError> {
+    for (slot, server) in DNS_SERVER_SLOTS.iter().zip(servers) {
+        let mut dns_config = sys::esp_netif_dns_info_t {
+            ip: sys::esp_ip_addr_t {
+                u_addr: sys::_ip_addr__bindgen_ty_1 {
+                    ip4: sys::esp_ip4_addr {
+                        addr: u32::from_le_bytes(server.octets()),
+                    },
+                },
+                type_: 0, // Ipv4Type
+            },
+        };
+        unsafe {
+            sys::esp!(sys::esp_netif_set_dns_info(
+                handle,
+                *slot,
+                &mut dns_config as *mut _
+            ))
+        }?;
+    }
+    Ok(())
+}
+
 /// A wrapper around the wifi structure available in esp-idf-svc with and adjustment to support
 /// reconnection
 #[derive(Default)]
 pub struct Esp32WifiNetwork {
     _subscription: RefCell<Option<EspSubscription<'static, System>>>,
+    _ip_subscription: RefCell<Option<EspSubscription<'static, System>>>,
+    dns_servers: Vec<Ipv4Addr>,
 }
 
 impl Esp32WifiNetwork {
@@ -84,6 +132,19 @@ impl Esp32WifiNetwork {
             ..Default::default()
         })
     }
+    /// Use `dns_servers` (up to 3, in priority order) instead of the DNS servers provided by
+    /// DHCP, for networks whose DHCP hands out broken DNS servers. The DHCP provided servers are
+    /// used when empty.
+    pub fn set_dns_servers(&mut self, dns_servers: Vec<Ipv4Addr>) {
+        if dns_servers.len() > DNS_SERVER_SLOTS.len() {
+            log::warn!(
+                "only the first {} DNS servers of {:?} will be used",
+                DNS_SERVER_SLOTS.len(),
+                dns_servers
+            );
+        }
+        self.dns_servers = dns_servers;
+    }
     /// Sets the wifi in mixed mode (AP+STA), sta is configured to allow
     /// for scanning nearby networks
     pub(crate) async fn set_ap_sta_mode(
@@ -100,7 +161,11 @@ impl Esp32WifiNetwork {
             channel: 10,
             secondary_channel: None,
             protocols: Protocol::P802D11B | Protocol::P802D11BG | Protocol::P802D11BGN,
-            auth_method: esp_idf_svc::wifi::AuthMethod::WPA2Personal,
+            auth_method: if ap_config.password.is_empty() {
+                AuthMethod::None
+            } else {
+                AuthMethod::WPA2Personal
+            },
             password: ap_config
                 .password
                 .as_str()
@@ -274,8 +339,30 @@ impl Esp32WifiNetwork {
             }
         })?;
         let _ = self._subscription.borrow_mut().replace(subscription);
+
+        if !self.dns_servers.is_empty() {
+            // DHCP overwrites the DNS servers every time a lease is obtained, so they are set
+            // again after each one
+            let dns_servers = self.dns_servers.clone();
+            esp32_set_dns_servers(wifi.wifi().sta_netif().handle(), &dns_servers)?;
+            log::info!("using DNS servers {:?}", dns_servers);
+            let subscription = sl_stack.subscribe::<IpEvent, _>(move |event: IpEvent| {
+                if matches!(event, IpEvent::DhcpIpAssigned(_)) {
+                    let handle =
+                        Esp32NetifHelper::default().netif_hnds[ESP32NetifHandle::Esp32WifiSta];
+                    if let Err(err) = esp32_set_dns_servers(handle, &dns_servers) {
+                        log::error!("couldn't set the DNS servers cause : {:?}", err);
+                    }
+                }
+            })?;
+            let _ = self._ip_subscription.borrow_mut().replace(subscription);
+        }
         Ok(())
     }
+    fn sta_ip_info(&self) -> Option<IpInfo> {
+        let guard = esp32_get_wifi().map_or(None, |wifi| wifi.try_lock())?;
+        guard.wifi().sta_netif().get_ip_info().ok()
+    }
     async fn scan_networks_inner(&self) -> Result<Vec<AccessPointInfo>, WifiManagerError> {
         let mut wifi = esp32_get_wifi()?.lock().await;
         wifi.scan().await.map_err(Into::into)
@@ -368,6 +455,16 @@ impl Network for Esp32WifiNetwork {
         let guard = esp32_get_wifi().map_or(None, |wifi| wifi.try_lock());
         Ok(guard.map_or(Ok(false), |guard| guard.is_connected())?)
     }
+    fn get_ssid(&self) -> Option<String> {
+        esp32_sta_ssid()
+    }
+    fn get_gateway(&self) -> Option<Ipv4Addr> {
+        self.sta_ip_info().map(|ip_info| ip_info.subnet.gateway)
+    }
+    fn get_netmask(&self) -> Option<Ipv4Addr> {
+        self.sta_ip_info()
+            .map(|ip_info| Ipv4Addr::from(ip_info.subnet.mask))
+    }
 }
 
 #[cfg(feature = "qemu")]
@@ -400,6 +497,20 @@ impl Network for Box<BlockingEth<EspEth<'static, OpenEth>>> {
     fn is_connected(&self) -> Result<bool, NetworkError> {
         Ok(BlockingEth::is_connected(self)?)
     }
+    fn get_gateway(&self) -> Option<Ipv4Addr> {
+        self.eth()
+            .netif()
+            .get_ip_info()
+            .ok()
+            .map(|ip_info| ip_info.subnet.gateway)
+    }
+    fn get_netmask(&self) -> Option<Ipv4Addr> {
+        self.eth()
+            .netif()
+            .get_ip_info()
+            .ok()
+            .map(|ip_info| Ipv4Addr::from(ip_info.subnet.mask))
+    }
 }
 
 enum ESP32NetifHandle {
@@ -461,6 +572,9 @@ impl Esp32NetifHelper {
         Self { netif_hnds }
     }
     fn get_ip_addr(&self) -> Result<u32, NetworkError> {
+        self.get_ip_info().map(|ip_info| ip_info.ip.addr)
+    }
+    fn get_ip_info(&self) -> Result<esp_idf_svc::sys::esp_netif_ip_info_t, NetworkError> {
         let mut ip_info: esp_idf_svc::sys::esp_netif_ip_info_t = Default::default();
         if unsafe {
             esp_idf_svc::sys::esp!(esp_idf_svc::sys::esp_netif_get_ip_info(
@@ -470,7 +584,7 @@ impl Esp32NetifHelper {
         }
         .is_ok()
         {
-            return Ok(ip_info.ip.addr);
+            return Ok(ip_info);
         }
         if unsafe {
             esp_idf_svc::sys::esp!(esp_idf_svc::sys::esp_netif_get_ip_info(
@@ -480,7 +594,7 @@ impl Esp32NetifHelper {
         }
         .is_ok()
         {
-            return Ok(ip_info.ip.addr);
+            return Ok(ip_info);
         }
 
         Err(NetworkError::NoIpConfigured)
@@ -505,6 +619,17 @@ impl Network for Esp32ExternallyManagedNetwork {
     fn is_connected(&self) -> Result<bool, NetworkError> {
         Ok(self.inner.connected.load(Ordering::Acquire))
     }
+    fn get_ssid(&self) -> Option<String> {
+        esp32_sta_ssid()
+    }
+    fn get_gateway(&self) -> Option<Ipv4Addr> {
+        let ip_info = Esp32NetifHelper::default().get_ip_info().ok()?;
+        Some(Ipv4Addr::from(ip_info.gw.addr.to_be()))
+    }
+    fn get_netmask(&self) -> Option<Ipv4Addr> {
+        let ip_info = Esp32NetifHelper::default().get_ip_info().ok()?;
+        Some(Ipv4Addr::from(ip_info.netmask.addr.to_be()))
+    }
 }
 
 impl Drop for Esp32ExternallyManagedNetwork {
@@ -18,7 +18,7 @@ use std::collections::HashMap;
 use std::sync::atomic::{AtomicI32, Ordering};
 use std::sync::{Arc, Mutex};
 
-use crate::common::config::ConfigType;
+use crate::common::config::{AttributeError, ConfigType};
 use crate::common::encoder::{
     Encoder, EncoderError, EncoderPosition, EncoderPositionType, EncoderSupportedRepresentations,
     EncoderType,
@@ -55,6 +55,7 @@ pub struct Esp32Encoder<A, B> {
     config: pcnt_config_t,
     a: A,
     b: B,
+    invert: bool,
 }
 
 impl<A, B> Esp32Encoder<A, B>
@@ -84,6 +85,7 @@ where
             },
             a,
             b,
+            invert: false,
         };
         enc.setup_pcnt()?;
         enc.start()?;
@@ -105,7 +107,20 @@ where
             Ok(b) => b,
             Err(err) => return Err(EncoderError::EncoderCodeError(err.code())),
         };
-        Ok(Arc::new(Mutex::new(Esp32Encoder::new(a, b)?)))
+        let invert = match cfg.get_attribute::<bool>("invert") {
+            Ok(invert) => invert,
+            Err(AttributeError::KeyNotFound(_)) => false,
+            Err(err) => return Err(EncoderError::EncoderConfigAttributeError(err)),
+        };
+        Ok(Arc::new(Mutex::new(
+            Esp32Encoder::new(a, b)?.with_inverted(invert),
+        )))
+    }
+
+    /// Flip the sign of the reported position, for encoders counting down when moving forwards
+    pub fn with_inverted(mut self, invert: bool) -> Self {
+        self.invert = invert;
+        self
     }
 
     fn start(&self) -> Result<(), EncoderError> {
@@ -252,6 +267,7 @@ where
         match position_type {
             EncoderPositionType::TICKS | EncoderPositionType::UNSPECIFIED => {
                 let count = self.get_counter_value()?;
+                let count = if self.invert { -count } else { count };
                 Ok(EncoderPositionType::TICKS.wrap_value(count as f32))
             }
             EncoderPositionType::DEGREES => Err(EncoderError::EncoderAngularNotSupported),
@@ -51,13 +51,10 @@ use crate::{
     common::{
         config::{AttributeError, ConfigType},
         registry::{ComponentRegistry, Dependency},
-        sensor::{
-            GenericReadingsResult, Readings, Sensor, SensorError, SensorResult, SensorT,
-            SensorType, TypedReadingsResult,
-        },
+        sensor::{Sensor, SensorError, SensorT, SensorType, TypedReadingsResult},
         status::{Status, StatusError},
     },
-    google, DoCommand,
+    google, DoCommand, SensorReadings,
 };
 
 use crate::esp32::esp_idf_svc::hal::{
@@ -93,7 +90,7 @@ struct IsrSharedState {
     notifier: Arc<Notifier>,
 }
 
-#[derive(DoCommand)]
+#[derive(DoCommand, SensorReadings)]
 pub struct HCSR04Sensor {
     // The PinDriver to control the pin that triggers issuing a pulse.
     //
@@ -257,16 +254,6 @@ impl Drop for HCSR04Sensor {
 
 impl Sensor for HCSR04Sensor {}
 
-impl Readings for HCSR04Sensor {
-    fn get_generic_readings(&mut self) -> Result<GenericReadingsResult, SensorError> {
-        Ok(self
-            .get_readings()?
-            .into_iter()
-            .map(|v| (v.0, SensorResult::<f64> { value: v.1 }.into()))
-            .collect())
-    }
-}
-
 impl SensorT<f64> for HCSR04Sensor {
     fn get_readings(&self) -> Result<TypedReadingsResult<f64>, SensorError> {
         // If the echo pin is already high for some reason, the state machine
@@ -1,6 +1,6 @@
 #![allow(dead_code)]
 use bytes::Bytes;
-use hyper::{http::uri::InvalidUri, Uri};
+use hyper::Uri;
 use prost::{DecodeError, Message};
 use std::{cell::RefCell, ffi::CString, num::NonZeroI32, rc::Rc};
 use thiserror::Error;
@@ -8,14 +8,20 @@ use thiserror::Error;
 use crate::{
     common::{
         credentials_storage::{
-            RobotConfigurationStorage, RobotCredentials, StorageDiagnostic, TlsCertificate,
-            WifiCredentialStorage, WifiCredentials,
+            find_unreadable_items, parse_app_address, parse_stored_app_address, reset_items,
+            AppAddressError, CachedWebRtcCertificate, RobotConfigurationStorage, RobotCredentials,
+            StorageDiagnostic, TlsCertificate, WebRtcCertificateStorage, WifiCredentialStorage,
+            WifiCredentials, MAX_WIFI_NETWORKS,
         },
         grpc::{GrpcError, ServerError},
+        webrtc::certificate::Fingerprint,
     },
     esp32::esp_idf_svc::{
         nvs::{EspCustomNvs, EspCustomNvsPartition, EspNvs},
-        sys::{esp, nvs_get_stats, nvs_stats_t, EspError, ESP_ERR_INVALID_ARG},
+        sys::{
+            esp, nvs_flash_erase_partition, nvs_get_stats, nvs_stats_t, EspError,
+            ESP_ERR_INVALID_ARG, ESP_ERR_NVS_NEW_VERSION_FOUND, ESP_ERR_NVS_NO_FREE_PAGES,
+        },
     },
     proto::{app::v1::RobotConfig, provisioning::v1::CloudConfig},
 };
@@ -33,7 +39,9 @@ pub enum NVSStorageError {
     #[error(transparent)]
     NVSValueDecodeError(#[from] DecodeError),
     #[error(transparent)]
-    NVSUriParseError(#[from] InvalidUri),
+    NVSAppAddressError(#[from] AppAddressError),
+    #[error("nvs key {0} holds an invalid certificate fingerprint")]
+    NVSInvalidFingerprint(String),
 }
 
 #[derive(Clone)]
@@ -46,16 +54,44 @@ pub struct NVSStorage {
 
 impl NVSStorage {
     // taking partition name as argument so we can use another NVS part name if we want to.
+    // A partition that can't be initialized, or holding values that can't be read back, is
+    // erased so that the device boots into provisioning rather than being wedged.
     pub fn new(partition_name: &str) -> Result<Self, NVSStorageError> {
-        let partition: EspCustomNvsPartition = EspCustomNvsPartition::take(partition_name)?;
+        let c_partition_name = CString::new(partition_name)
+            .map_err(|_| EspError::from_non_zero(NonZeroI32::new(ESP_ERR_INVALID_ARG).unwrap()))?;
+        let partition: EspCustomNvsPartition = match EspCustomNvsPartition::take(partition_name) {
+            Err(err)
+                if err.code() == ESP_ERR_NVS_NO_FREE_PAGES as i32
+                    || err.code() == ESP_ERR_NVS_NEW_VERSION_FOUND as i32 =>
+            {
+                log::error!(
+                    "NVS partition {} is unreadable ({}), ERASING IT: the device will have to be provisioned again",
+                    partition_name,
+                    err
+                );
+                esp!(unsafe { nvs_flash_erase_partition(c_partition_name.as_ptr()) })?;
+                EspCustomNvsPartition::take(partition_name)?
+            }
+            partition => partition?,
+        };
         let nvs = EspNvs::new(partition, "VIAM_NS", true)?;
 
-        Ok(Self {
+        let storage = Self {
             nvs: Rc::new(nvs.into()),
-            partition_name: CString::new(partition_name).map_err(|_| {
-                EspError::from_non_zero(NonZeroI32::new(ESP_ERR_INVALID_ARG).unwrap())
-            })?,
-        })
+            partition_name: c_partition_name,
+        };
+
+        let unreadable = find_unreadable_items(&storage);
+        if !unreadable.is_empty() {
+            log::error!(
+                "NVS partition {} holds unreadable values {:?}, erasing them",
+                partition_name,
+                unreadable
+            );
+            reset_items(&storage, &unreadable);
+        }
+
+        Ok(storage)
     }
 
     fn get_string(&self, key: &str) -> Result<String, NVSStorageError> {
@@ -118,6 +154,20 @@ impl NVSStorage {
         Ok(nvs.blob_len(key)?.is_some())
     }
 
+    fn get_u64(&self, key: &str) -> Result<u64, NVSStorageError> {
+        let nvs = self.nvs.borrow();
+        nvs.get_u64(key)?
+            .ok_or(NVSStorageError::NVSKeyAbsent(key.to_string()))
+    }
+
+    fn set_u64(&self, key: &str, value: u64) -> Result<(), NVSStorageError> {
+        if key.len() > MAX_NVS_KEY_SIZE {
+            return Err(NVSStorageError::NVSKeyTooLong(key.to_string(), key.len()));
+        }
+        let mut nvs = self.nvs.borrow_mut();
+        Ok(nvs.set_u64(key, value)?)
+    }
+
     fn has_key(&self, key: &str) -> Result<bool, NVSStorageError> {
         let nvs = self.nvs.borrow();
         Ok(nvs.contains(key)?)
@@ -171,8 +221,20 @@ const NVS_ROBOT_APP_ADDRESS: &str = "ROBOT_APP_ADDR";
 const NVS_ROBOT_CONFIG_KEY: &str = "ROBOT_CONFIG";
 const NVS_WIFI_SSID_KEY: &str = "WIFI_SSID";
 const NVS_WIFI_PASSWORD_KEY: &str = "WIFI_PASSWORD";
+// fallback networks are written by the installer under WIFI_SSID_<n> and WIFI_PASSWORD_<n>,
+// starting from 1
+fn nvs_fallback_wifi_keys(priority: usize) -> (String, String) {
+    (
+        format!("{}_{}", NVS_WIFI_SSID_KEY, priority),
+        format!("{}_{}", NVS_WIFI_PASSWORD_KEY, priority),
+    )
+}
 const NVS_TLS_CERTIFICATE_KEY: &str = "TLS_CERT";
 const NVS_TLS_PRIVATE_KEY_KEY: &str = "TLS_PRIV_KEY";
+const NVS_WEBRTC_CERTIFICATE_KEY: &str = "WEBRTC_CERT";
+const NVS_WEBRTC_PRIVATE_KEY_KEY: &str = "WEBRTC_PRIV_KEY";
+const NVS_WEBRTC_FINGERPRINT_KEY: &str = "WEBRTC_FP";
+const NVS_WEBRTC_NOT_AFTER_KEY: &str = "WEBRTC_EXPIRY";
 
 #[cfg(feature = "ota")]
 const NVS_OTA_VERSION_KEY: &str = "OTA_VERSION";
@@ -214,7 +276,9 @@ impl RobotConfigurationStorage for NVSStorage {
     }
 
     fn get_app_address(&self) -> Result<Uri, Self::Error> {
-        Ok(self.get_string(NVS_ROBOT_APP_ADDRESS)?.parse::<Uri>()?)
+        Ok(parse_stored_app_address(
+            &self.get_string(NVS_ROBOT_APP_ADDRESS)?,
+        )?)
     }
 
     fn has_app_address(&self) -> bool {
@@ -222,6 +286,7 @@ impl RobotConfigurationStorage for NVSStorage {
     }
 
     fn store_app_address(&self, uri: &str) -> Result<(), Self::Error> {
+        parse_app_address(uri)?;
         self.set_string(NVS_ROBOT_APP_ADDRESS, uri)
     }
     fn reset_app_address(&self) -> Result<(), Self::Error> {
@@ -229,9 +294,15 @@ impl RobotConfigurationStorage for NVSStorage {
     }
 
     fn store_robot_credentials(&self, cfg: CloudConfig) -> Result<(), Self::Error> {
+        // an empty address leaves the stored one (or the default) in use
+        if !cfg.app_address.is_empty() {
+            parse_app_address(&cfg.app_address)?;
+        }
         self.set_string(NVS_ROBOT_SECRET_KEY, &cfg.secret)?;
         self.set_string(NVS_ROBOT_ID_KEY, &cfg.id)?;
-        self.set_string(NVS_ROBOT_APP_ADDRESS, &cfg.app_address)?;
+        if !cfg.app_address.is_empty() {
+            self.set_string(NVS_ROBOT_APP_ADDRESS, &cfg.app_address)?;
+        }
         Ok(())
     }
 
@@ -287,6 +358,48 @@ impl RobotConfigurationStorage for NVSStorage {
     }
 }
 
+impl WebRtcCertificateStorage for NVSStorage {
+    type Error = NVSStorageError;
+    fn has_webrtc_certificate(&self) -> bool {
+        self.has_blob(NVS_WEBRTC_CERTIFICATE_KEY).unwrap_or(false)
+            && self.has_blob(NVS_WEBRTC_PRIVATE_KEY_KEY).unwrap_or(false)
+            && self.has_string(NVS_WEBRTC_FINGERPRINT_KEY).unwrap_or(false)
+            && self.has_key(NVS_WEBRTC_NOT_AFTER_KEY).unwrap_or(false)
+    }
+
+    fn get_webrtc_certificate(&self) -> Result<CachedWebRtcCertificate, Self::Error> {
+        let certificate = self.get_blob(NVS_WEBRTC_CERTIFICATE_KEY)?;
+        let private_key = self.get_blob(NVS_WEBRTC_PRIVATE_KEY_KEY)?;
+        let fingerprint = Fingerprint::try_from(
+            self.get_string(NVS_WEBRTC_FINGERPRINT_KEY)?.as_str(),
+        )
+        .map_err(|_| NVSStorageError::NVSInvalidFingerprint(NVS_WEBRTC_FINGERPRINT_KEY.into()))?;
+        let not_after = self.get_u64(NVS_WEBRTC_NOT_AFTER_KEY)?;
+        Ok(CachedWebRtcCertificate {
+            certificate,
+            private_key,
+            fingerprint,
+            not_after,
+        })
+    }
+
+    fn store_webrtc_certificate(&self, cert: CachedWebRtcCertificate) -> Result<(), Self::Error> {
+        self.set_blob(NVS_WEBRTC_CERTIFICATE_KEY, Bytes::from(cert.certificate))?;
+        self.set_blob(NVS_WEBRTC_PRIVATE_KEY_KEY, Bytes::from(cert.private_key))?;
+        self.set_string(NVS_WEBRTC_FINGERPRINT_KEY, &cert.fingerprint.to_string())?;
+        self.set_u64(NVS_WEBRTC_NOT_AFTER_KEY, cert.not_after)?;
+        Ok(())
+    }
+
+    fn reset_webrtc_certificate(&self) -> Result<(), Self::Error> {
+        self.erase_key(NVS_WEBRTC_CERTIFICATE_KEY)?;
+        self.erase_key(NVS_WEBRTC_PRIVATE_KEY_KEY)?;
+        self.erase_key(NVS_WEBRTC_FINGERPRINT_KEY)?;
+        self.erase_key(NVS_WEBRTC_NOT_AFTER_KEY)?;
+        Ok(())
+    }
+}
+
 impl WifiCredentialStorage for NVSStorage {
     type Error = NVSStorageError;
     fn has_wifi_credentials(&self) -> bool {
@@ -309,8 +422,28 @@ impl WifiCredentialStorage for NVSStorage {
     fn reset_wifi_credentials(&self) -> Result<(), Self::Error> {
         self.erase_key(NVS_WIFI_SSID_KEY)?;
         self.erase_key(NVS_WIFI_PASSWORD_KEY)?;
+        for priority in 1..MAX_WIFI_NETWORKS {
+            let (ssid_key, pwd_key) = nvs_fallback_wifi_keys(priority);
+            self.erase_key(&ssid_key)?;
+            self.erase_key(&pwd_key)?;
+        }
         Ok(())
     }
+
+    fn get_fallback_wifi_credentials(&self) -> Result<Vec<WifiCredentials>, Self::Error> {
+        let mut networks = vec![];
+        for priority in 1..MAX_WIFI_NETWORKS {
+            let (ssid_key, pwd_key) = nvs_fallback_wifi_keys(priority);
+            if !self.has_string(&ssid_key)? {
+                break;
+            }
+            networks.push(WifiCredentials {
+                ssid: self.get_string(&ssid_key)?,
+                pwd: self.get_string(&pwd_key)?,
+            });
+        }
+        Ok(networks)
+    }
 }
 
 impl From<NVSStorageError> for ServerError {
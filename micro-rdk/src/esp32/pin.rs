@@ -1,10 +1,10 @@
-use super::pwm::PwmDriver;
+use super::pwm::{PwmDriver, MAX_PWM_FREQUENCY_HZ, MIN_PWM_FREQUENCY_HZ};
 use crate::common::board::BoardError;
 use crate::esp32::esp_idf_svc::hal::gpio::{
-    AnyIOPin, InputOutput, InterruptType, Pin, PinDriver, Pull,
+    AnyIOPin, Input, InputOutput, InterruptType, Pin, PinDriver, Pull,
 };
 use crate::esp32::esp_idf_svc::sys::{
-    esp, gpio_install_isr_service, gpio_isr_handler_add, ESP_INTR_FLAG_IRAM,
+    esp, gpio_install_isr_service, gpio_isr_handler_add, ESP_INTR_FLAG_IRAM, SOC_GPIO_PIN_COUNT,
     SOC_GPIO_VALID_OUTPUT_GPIO_MASK,
 };
 use once_cell::sync::{Lazy, OnceCell};
@@ -49,6 +49,38 @@ fn is_valid_gpio_pin(pin: i32) -> Result<(), BoardError> {
     }
 }
 
+/// A pin that is only ever read, so it never drives a line that another device drives (such
+/// as the fault output of a motor driver)
+pub struct Esp32InputPin {
+    pin: i32,
+    driver: PinDriver<'static, AnyIOPin, Input>,
+}
+
+impl Esp32InputPin {
+    pub fn new(pin: i32, pull: Option<Pull>) -> Result<Self, BoardError> {
+        // input only pins are valid here, unlike in `is_valid_gpio_pin`
+        if !(0..SOC_GPIO_PIN_COUNT as i32).contains(&pin) {
+            return Err(BoardError::InvalidGpioNumber(pin as u32));
+        }
+        let mut driver = PinDriver::input(unsafe { AnyIOPin::new(pin) })
+            .map_err(|e| BoardError::GpioPinOtherError(pin as u32, Box::new(e)))?;
+        if let Some(pull) = pull {
+            driver
+                .set_pull(pull)
+                .map_err(|e| BoardError::GpioPinOtherError(pin as u32, Box::new(e)))?;
+        }
+        Ok(Self { pin, driver })
+    }
+
+    pub fn pin(&self) -> i32 {
+        self.pin
+    }
+
+    pub fn is_high(&self) -> bool {
+        self.driver.is_high()
+    }
+}
+
 /// Esp32GPIOPin is a wrapper for a pin on ESP32 as represented in esp-idf-hal
 /// and esp-idf-sys. This exists so that all micro-RDK drivers can interact
 /// with pins through the board instance and avoid conflicting uses of pins
@@ -159,6 +191,9 @@ impl Esp32GPIOPin {
                 "is not a pwm pin",
             ));
         }
+        if freq != 0 && !(MIN_PWM_FREQUENCY_HZ..=MAX_PWM_FREQUENCY_HZ).contains(&freq) {
+            return Err(BoardError::UnsupportedPwmFrequency(freq));
+        }
         if freq == 0 {
             self.pwm_driver = None
         } else {
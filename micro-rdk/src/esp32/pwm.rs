@@ -16,6 +16,12 @@ use std::fmt::Debug;
 use std::sync::Mutex;
 use thiserror::Error;
 
+// Range of frequencies LEDC timers can produce with the default 8 bits duty resolution, the
+// highest is the 80MHz APB clock divided by 2^8, the lowest uses the 1MHz REF_TICK clock with
+// the largest divider
+pub(crate) const MIN_PWM_FREQUENCY_HZ: u64 = 4;
+pub(crate) const MAX_PWM_FREQUENCY_HZ: u64 = 312_500;
+
 static LEDC_MANAGER: Lazy<Mutex<LedcManager>> = Lazy::new(|| Mutex::new(LedcManager::new()));
 
 #[derive(Debug, Error)]
@@ -4,7 +4,9 @@ use crate::common::actuator::{Actuator, ActuatorError};
 use crate::common::encoder::{
     Direction, Encoder, EncoderPositionType, EncoderSupportedRepresentations, SingleEncoder,
 };
-use crate::common::motor::{Motor, MotorError, MotorSupportedProperties, MotorType};
+use crate::common::motor::{
+    Motor, MotorError, MotorFaultStatus, MotorSupportedProperties, MotorType,
+};
 use crate::common::status::{Status, StatusError};
 use crate::google;
 
@@ -72,6 +74,9 @@ impl Motor for SingleEncodedMotor {
             position_reporting: true,
         }
     }
+    fn get_fault_status(&mut self) -> Result<MotorFaultStatus, MotorError> {
+        self.motor.get_fault_status()
+    }
 }
 
 impl Actuator for SingleEncodedMotor {
@@ -96,6 +101,13 @@ impl Status for SingleEncodedMotor {
                 kind: Some(google::protobuf::value::Kind::NumberValue(pos)),
             },
         );
+        if let Some(fault) = self
+            .motor
+            .get_status()?
+            .and_then(|mut status| status.fields.remove("fault"))
+        {
+            hm.insert("fault".to_string(), fault);
+        }
         Ok(Some(google::protobuf::Struct { fields: hm }))
     }
 }
@@ -55,6 +55,7 @@ pub struct Esp32SingleEncoder {
     pulse_counter: Box<PulseStorage>,
     config: pcnt_config_t,
     dir: Direction,
+    invert: bool,
 }
 
 impl Esp32SingleEncoder {
@@ -81,6 +82,7 @@ impl Esp32SingleEncoder {
                 unit,
             },
             dir: Direction::StoppedForwards,
+            invert: false,
         };
         if dir_flip {
             enc.dir = Direction::StoppedBackwards
@@ -106,9 +108,20 @@ impl Esp32SingleEncoder {
                 }
             },
         };
-        Ok(Arc::new(Mutex::new(Esp32SingleEncoder::new(
-            pin, dir_flip,
-        )?)))
+        let invert = match cfg.get_attribute::<bool>("invert") {
+            Ok(invert) => invert,
+            Err(AttributeError::KeyNotFound(_)) => false,
+            Err(err) => return Err(EncoderError::EncoderConfigAttributeError(err)),
+        };
+        Ok(Arc::new(Mutex::new(
+            Esp32SingleEncoder::new(pin, dir_flip)?.with_inverted(invert),
+        )))
+    }
+
+    /// Flip the sign of the reported position, the direction set by the motor is unchanged
+    pub fn with_inverted(mut self, invert: bool) -> Self {
+        self.invert = invert;
+        self
     }
 
     pub fn start(&self) -> Result<(), EncoderError> {
@@ -258,6 +271,7 @@ impl Encoder for Esp32SingleEncoder {
         match position_type {
             EncoderPositionType::TICKS | EncoderPositionType::UNSPECIFIED => {
                 let count = self.get_counter_value()?;
+                let count = if self.invert { -count } else { count };
                 Ok(EncoderPositionType::TICKS.wrap_value(count as f32))
             }
             EncoderPositionType::DEGREES => Err(EncoderError::EncoderAngularNotSupported),
@@ -62,3 +62,71 @@ macro_rules! esp32_print_stack_high_watermark {
 }
 
 pub(crate) use esp32_print_stack_high_watermark;
+
+/// Details of a heap allocation that could not be satisfied
+#[derive(Clone, Copy, Debug)]
+pub struct AllocFailure {
+    /// Requested size in bytes
+    pub size: usize,
+    /// Capabilities (MALLOC_CAP_*) requested for the allocation
+    pub caps: u32,
+    /// Free heap with the requested capabilities at the time of the failure
+    pub free_heap: usize,
+    /// Largest contiguous block available with the requested capabilities
+    pub largest_free_block: usize,
+}
+
+static ALLOC_FAILURE_HANDLER: std::sync::Mutex<fn(&AllocFailure)> =
+    std::sync::Mutex::new(log_alloc_failure);
+
+/// Default allocation failure handler, prints the failed request along with the heap state to
+/// the ROM console. Handlers run inside the allocator so they must not allocate nor take locks
+/// (which rules out the logger)
+pub fn log_alloc_failure(failure: &AllocFailure) {
+    use std::ffi::{c_char, c_uint};
+    unsafe {
+        crate::esp32::esp_idf_svc::sys::esp_rom_printf(
+            b"failed to allocate %u bytes (caps 0x%x), free heap %u bytes, largest free block %u bytes\n\0"
+                .as_ptr() as *const c_char,
+            failure.size as c_uint,
+            failure.caps as c_uint,
+            failure.free_heap as c_uint,
+            failure.largest_free_block as c_uint,
+        );
+    }
+}
+
+unsafe extern "C" fn alloc_failed_callback(
+    size: usize,
+    caps: u32,
+    _function_name: *const std::ffi::c_char,
+) {
+    use crate::esp32::esp_idf_svc::sys::{
+        heap_caps_get_free_size, heap_caps_get_largest_free_block,
+    };
+    let failure = AllocFailure {
+        size,
+        caps,
+        free_heap: heap_caps_get_free_size(caps),
+        largest_free_block: heap_caps_get_largest_free_block(caps),
+    };
+    // never block here, the failure may come from code holding the lock
+    if let Ok(handler) = ALLOC_FAILURE_HANDLER.try_lock().map(|handler| *handler) {
+        handler(&failure);
+    }
+}
+
+/// Register `handler` to be called whenever a heap allocation fails, before the allocating code
+/// aborts. Use [`log_alloc_failure`] to log the requested size and the current free heap.
+pub fn set_alloc_failure_handler(
+    handler: fn(&AllocFailure),
+) -> Result<(), crate::esp32::esp_idf_svc::sys::EspError> {
+    *ALLOC_FAILURE_HANDLER
+        .lock()
+        .unwrap_or_else(|e| e.into_inner()) = handler;
+    crate::esp32::esp_idf_svc::sys::esp!(unsafe {
+        crate::esp32::esp_idf_svc::sys::heap_caps_register_failed_alloc_callback(Some(
+            alloc_failed_callback,
+        ))
+    })
+}
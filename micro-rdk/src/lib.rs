@@ -28,6 +28,7 @@ extern crate scopeguard;
 pub use micro_rdk_macros::DoCommand;
 pub use micro_rdk_macros::MovementSensorReadings;
 pub use micro_rdk_macros::PowerSensorReadings;
+pub use micro_rdk_macros::SensorReadings;
 
 /// gRPC protobuf utilities, auto-generated
 pub mod google {
@@ -71,6 +72,11 @@ pub mod proto {
         pub mod v1 {
             include!("gen/viam.app.v1.rs");
         }
+        pub mod agent {
+            pub mod v1 {
+                include!("gen/viam.app.agent.v1.rs");
+            }
+        }
         pub mod packages {
             pub mod v1 {
                 include!("gen/viam.app.packages.v1.rs");
@@ -187,7 +193,13 @@ stun_codec::define_attribute_enums!(
     ]
 );
 
-pub struct DurationParseFailure;
+/// Error returned when a protobuf Duration cannot be represented as a [`time::Duration`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, thiserror::Error)]
+#[error("cannot convert negative duration ({seconds}s, {nanos}ns)")]
+pub struct DurationParseFailure {
+    pub seconds: i64,
+    pub nanos: i32,
+}
 
 impl TryFrom<google::protobuf::Duration> for time::Duration {
     type Error = DurationParseFailure;
@@ -198,7 +210,19 @@ impl TryFrom<google::protobuf::Duration> for time::Duration {
                 duration.nanos as u32,
             ))
         } else {
-            Err(DurationParseFailure)
+            Err(DurationParseFailure {
+                seconds: duration.seconds,
+                nanos: duration.nanos,
+            })
+        }
+    }
+}
+
+impl From<time::Duration> for google::protobuf::Duration {
+    fn from(duration: time::Duration) -> Self {
+        Self {
+            seconds: duration.as_secs().try_into().unwrap_or(i64::MAX),
+            nanos: duration.subsec_nanos() as i32,
         }
     }
 }
@@ -214,4 +238,23 @@ mod tests {
         let lock = LOCK.get_or_init(|| Mutex::new(()));
         lock.lock().unwrap()
     }
+
+    #[test_log::test]
+    fn test_duration_conversion() {
+        let duration = std::time::Duration::new(12, 345);
+        let proto: crate::google::protobuf::Duration = duration.into();
+        assert_eq!(proto.seconds, 12);
+        assert_eq!(proto.nanos, 345);
+        assert_eq!(std::time::Duration::try_from(proto), Ok(duration));
+
+        let err = std::time::Duration::try_from(crate::google::protobuf::Duration {
+            seconds: -1,
+            nanos: 0,
+        })
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "cannot convert negative duration (-1s, 0ns)"
+        );
+    }
 }
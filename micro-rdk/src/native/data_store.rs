@@ -0,0 +1,745 @@
+//! A [`DataStore`] spooling captured data to disk rather than keeping it in RAM, so that a
+//! native deployment can keep capturing over long offline periods and sync the data once
+//! connectivity returns.
+//!
+//! Captured data for each collector is appended, as length-delimited `SensorData` messages, to
+//! segment files in its own directory under `<capture_dir>/micro-rdk-spool`. A new segment is
+//! started once the current one reaches `capture_segment_size_kb`, and segments are only deleted
+//! once entirely synced, or when the spool would grow past `capture_dir_max_size_mb` (the oldest
+//! segment is dropped first, shared between collectors in proportion to their `cache_size_kb`).
+//! The synced messages of a partially synced segment are cut from its file, so they aren't
+//! uploaded again by the next run.
+//!
+//! The segments spooled by a previous run are replayed when the store is created, so data
+//! captured offline survives a crash, a reboot or a config reload. Since the timestamps of
+//! captured data are offsets from the start of the robot (see `DataSyncTask`), they are turned
+//! into wall-clock times as the data is spooled. Data spooled before the clock was set can't be
+//! dated once the robot restarted and is dropped on replay, as is a message cut short by a crash.
+
+use std::{
+    cell::RefCell,
+    collections::{HashSet, VecDeque},
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    rc::Rc,
+    time::{Duration, SystemTime},
+};
+
+use bytes::{Buf, BytesMut};
+use prost::{encoding::decode_varint, length_delimiter_len, Message};
+
+use crate::common::{
+    data_collector::ResourceMethodKey,
+    data_manager::{get_data_service_config, is_wall_clock_timestamp, WALL_CLOCK_MIN_SECS},
+    data_store::{DataStore, DataStoreError, DataStoreReader, WriteMode},
+};
+use crate::google::protobuf::{value::Kind, Struct, Timestamp};
+use crate::proto::app::data_sync::v1::SensorData;
+use crate::proto::app::v1::RobotConfig;
+
+pub const DEFAULT_SEGMENT_SIZE_KB: f64 = 64.0;
+pub const DEFAULT_MAX_SIZE_MB: f64 = 100.0;
+const SPOOL_DIR: &str = "micro-rdk-spool";
+
+impl From<std::io::Error> for DataStoreError {
+    fn from(value: std::io::Error) -> Self {
+        DataStoreError::SpoolError(value.to_string())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct DiskDataStoreConfig {
+    pub capture_dir: PathBuf,
+    pub segment_size: usize,
+    pub max_size: u64,
+}
+
+impl Default for DiskDataStoreConfig {
+    fn default() -> Self {
+        Self {
+            capture_dir: std::env::temp_dir(),
+            segment_size: (DEFAULT_SEGMENT_SIZE_KB * 1000.0) as usize,
+            max_size: (DEFAULT_MAX_SIZE_MB * 1_000_000.0) as u64,
+        }
+    }
+}
+
+impl DiskDataStoreConfig {
+    /// Whether the data manager service asks for captured data to be spooled to disk
+    pub fn is_configured(cfg: &RobotConfig) -> bool {
+        get_data_service_config(cfg)
+            .ok()
+            .flatten()
+            .and_then(|svc| svc.attributes)
+            .is_some_and(|attrs| attrs.fields.contains_key("capture_dir"))
+    }
+
+    pub fn from_attributes(attributes: &Struct) -> Result<Self, DataStoreError> {
+        let number = |key: &str, default: f64| match attributes.fields.get(key) {
+            None => Ok(default),
+            Some(v) => match v.kind {
+                Some(Kind::NumberValue(n)) if n > 0.0 => Ok(n),
+                _ => Err(DataStoreError::SpoolError(format!(
+                    "{} should be a positive number",
+                    key
+                ))),
+            },
+        };
+        let mut config = Self::default();
+        if let Some(v) = attributes.fields.get("capture_dir") {
+            match &v.kind {
+                Some(Kind::StringValue(dir)) => config.capture_dir = dir.into(),
+                _ => {
+                    return Err(DataStoreError::SpoolError(
+                        "capture_dir should be a path".to_string(),
+                    ))
+                }
+            }
+        }
+        config.segment_size =
+            (number("capture_segment_size_kb", DEFAULT_SEGMENT_SIZE_KB)? * 1000.0) as usize;
+        config.max_size =
+            (number("capture_dir_max_size_mb", DEFAULT_MAX_SIZE_MB)? * 1_000_000.0) as u64;
+        Ok(config)
+    }
+}
+
+struct Segment {
+    path: PathBuf,
+    len: usize,
+    messages: usize,
+}
+
+fn duration_from_timestamp(timestamp: &Timestamp) -> Duration {
+    Duration::new(
+        timestamp.seconds.max(0) as u64,
+        timestamp.nanos.max(0) as u32,
+    )
+}
+
+// Turns the timestamps of `message`, offsets from the start of the robot, into wall-clock times
+// given the time since the epoch `now`, the message being spooled as it is received. They are
+// left as is while the clock isn't set.
+fn to_wall_clock(message: &mut SensorData, now: Duration) {
+    if now.as_secs() < WALL_CLOCK_MIN_SECS as u64 {
+        return;
+    }
+    let Some(metadata) = message.metadata.as_mut() else {
+        return;
+    };
+    let Some(received) = metadata.time_received.as_ref() else {
+        return;
+    };
+    if is_wall_clock_timestamp(received) {
+        return;
+    }
+    let Some(robot_start) = now.checked_sub(duration_from_timestamp(received)) else {
+        return;
+    };
+    for timestamp in [&mut metadata.time_received, &mut metadata.time_requested]
+        .into_iter()
+        .flatten()
+    {
+        let time = robot_start + duration_from_timestamp(timestamp);
+        *timestamp = Timestamp {
+            seconds: time.as_secs() as i64,
+            nanos: time.subsec_nanos() as i32,
+        };
+    }
+}
+
+// The messages of a segment spooled by a previous run that can still be dated, stopping at a
+// message cut short, and how many there are
+fn replayable_messages(mut contents: &[u8]) -> (Vec<u8>, usize) {
+    let mut kept = Vec::with_capacity(contents.len());
+    let mut messages = 0;
+    while !contents.is_empty() {
+        let remaining = contents;
+        let Ok(message) = SensorData::decode_length_delimited(&mut contents) else {
+            break;
+        };
+        let relative = message.metadata.is_some_and(|metadata| {
+            [metadata.time_received, metadata.time_requested]
+                .iter()
+                .flatten()
+                .any(|timestamp| !is_wall_clock_timestamp(timestamp))
+        });
+        if !relative {
+            kept.extend_from_slice(&remaining[..remaining.len() - contents.len()]);
+            messages += 1;
+        }
+    }
+    (kept, messages)
+}
+
+// The segments spooled in `dir` by a previous run, oldest first, and the number of the next one
+fn replay_segments(dir: &Path) -> Result<(VecDeque<Segment>, u64), DataStoreError> {
+    let mut paths: Vec<(u64, PathBuf)> = fs::read_dir(dir)?
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            let number = path
+                .file_name()?
+                .to_str()?
+                .strip_suffix(".seg")?
+                .parse()
+                .ok()?;
+            Some((number, path))
+        })
+        .collect();
+    paths.sort();
+    let next_segment = paths.last().map_or(0, |(number, _)| number + 1);
+    let mut segments = VecDeque::new();
+    for (_, path) in paths {
+        let contents = fs::read(&path)?;
+        let (kept, messages) = replayable_messages(&contents);
+        if messages == 0 {
+            fs::remove_file(&path)?;
+            continue;
+        }
+        if kept.len() != contents.len() {
+            fs::write(&path, &kept)?;
+        }
+        segments.push_back(Segment {
+            path,
+            len: kept.len(),
+            messages,
+        });
+    }
+    Ok((segments, next_segment))
+}
+
+/// The segments spooled for a collector, oldest first
+struct CollectorSpool {
+    key: ResourceMethodKey,
+    dir: PathBuf,
+    segments: VecDeque<Segment>,
+    next_segment: u64,
+    size: u64,
+    max_size: u64,
+    segment_size: usize,
+    in_use: bool,
+}
+
+impl CollectorSpool {
+    fn drop_oldest_segment(&mut self) -> Result<(), DataStoreError> {
+        if let Some(segment) = self.segments.pop_front() {
+            self.size -= segment.len as u64;
+            fs::remove_file(&segment.path)?;
+        }
+        Ok(())
+    }
+
+    // Removes the first `len` bytes, holding `messages` messages, of the oldest segment. The
+    // remaining messages are written to a temporary file replacing the segment, so a crash leaves
+    // either the whole segment or only its unsynced messages.
+    fn drop_synced_messages(&mut self, len: usize, messages: usize) -> Result<(), DataStoreError> {
+        let Some(segment) = self.segments.front_mut() else {
+            return Ok(());
+        };
+        let contents = fs::read(&segment.path)?;
+        let tmp = segment.path.with_extension("tmp");
+        fs::write(&tmp, &contents[len.min(contents.len())..])?;
+        fs::rename(&tmp, &segment.path)?;
+        segment.len -= len;
+        segment.messages -= messages;
+        self.size -= len as u64;
+        Ok(())
+    }
+
+    fn write(
+        &mut self,
+        mut message: SensorData,
+        write_mode: WriteMode,
+    ) -> Result<(), DataStoreError> {
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default();
+        to_wall_clock(&mut message, now);
+        let encoded = message.encode_length_delimited_to_vec();
+        if encoded.len() > self.segment_size {
+            return Err(DataStoreError::DataTooLarge(
+                self.key.clone(),
+                encoded.len(),
+                self.segment_size,
+            ));
+        }
+        while self.size + encoded.len() as u64 > self.max_size {
+            if !matches!(write_mode, WriteMode::OverwriteOldest) {
+                return Err(DataStoreError::DataBufferFull(self.key.clone()));
+            }
+            self.drop_oldest_segment()?;
+        }
+        if self
+            .segments
+            .back()
+            .map_or(true, |s| s.len + encoded.len() > self.segment_size)
+        {
+            self.segments.push_back(Segment {
+                path: self.dir.join(format!("{:010}.seg", self.next_segment)),
+                len: 0,
+                messages: 0,
+            });
+            self.next_segment += 1;
+        }
+        let segment = self.segments.back_mut().unwrap();
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&segment.path)?
+            .write_all(&encoded)?;
+        segment.len += encoded.len();
+        segment.messages += 1;
+        self.size += encoded.len() as u64;
+        Ok(())
+    }
+}
+
+/// DiskDataStore spools the data of each collector to segment files on disk, see the module
+/// documentation. Like [`crate::common::data_store::DefaultDataStore`] it is not thread-safe.
+pub struct DiskDataStore {
+    spools: Vec<Rc<RefCell<CollectorSpool>>>,
+}
+
+impl DiskDataStore {
+    pub fn new(
+        collector_settings: Vec<(ResourceMethodKey, usize)>,
+        config: DiskDataStoreConfig,
+    ) -> Result<Self, DataStoreError> {
+        if collector_settings.is_empty() {
+            return Err(DataStoreError::NoCollectors);
+        }
+        let spool_dir = config.capture_dir.join(SPOOL_DIR);
+        let total_capacity: usize = collector_settings.iter().map(|(_, c)| c).sum();
+        let mut names = HashSet::new();
+        let spools = collector_settings
+            .into_iter()
+            .enumerate()
+            .map(|(idx, (key, capacity))| {
+                // the directory of a collector is named after it so that its data is found
+                // again by the next run
+                let mut name: String =
+                    format!("{}-{}-{}", key.component_type, key.r_name, key.method)
+                        .chars()
+                        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+                        .collect();
+                if !names.insert(name.clone()) {
+                    name = format!("{}-{}", name, idx);
+                }
+                let dir = spool_dir.join(name);
+                fs::create_dir_all(&dir)?;
+                let (segments, next_segment) = replay_segments(&dir)?;
+                let max_size = (config.max_size as f64 * capacity as f64
+                    / total_capacity.max(1) as f64) as u64;
+                let mut spool = CollectorSpool {
+                    key,
+                    dir,
+                    size: segments.iter().map(|s| s.len as u64).sum(),
+                    segments,
+                    next_segment,
+                    max_size,
+                    segment_size: config.segment_size.min(max_size as usize),
+                    in_use: false,
+                };
+                // the share of the collector may have shrunk since the data was spooled
+                while spool.size > spool.max_size {
+                    spool.drop_oldest_segment()?;
+                }
+                if !spool.segments.is_empty() {
+                    log::info!(
+                        "replaying {} spooled segments of {}",
+                        spool.segments.len(),
+                        spool.key
+                    );
+                }
+                Ok(Rc::new(RefCell::new(spool)))
+            })
+            .collect::<Result<_, DataStoreError>>()?;
+        Ok(Self { spools })
+    }
+
+    fn get_spool(
+        &self,
+        collector_key: &ResourceMethodKey,
+    ) -> Result<&Rc<RefCell<CollectorSpool>>, DataStoreError> {
+        self.spools
+            .iter()
+            .find(|spool| spool.borrow().key == *collector_key)
+            .ok_or(DataStoreError::UnknownCollectorKey(collector_key.clone()))
+    }
+}
+
+impl DataStore for DiskDataStore {
+    type Reader = DiskDataStoreReader;
+
+    fn write_message(
+        &mut self,
+        collector_key: &ResourceMethodKey,
+        message: SensorData,
+        write_mode: WriteMode,
+    ) -> Result<(), DataStoreError> {
+        let mut spool = self.get_spool(collector_key)?.borrow_mut();
+        if spool.in_use {
+            return Err(DataStoreError::BufferInUse(collector_key.clone()));
+        }
+        spool.write(message, write_mode)
+    }
+
+    fn from_resource_method_settings(
+        settings: Vec<(ResourceMethodKey, usize)>,
+    ) -> Result<Self, DataStoreError> {
+        Self::new(settings, Default::default())
+    }
+
+    fn from_service_attributes(
+        settings: Vec<(ResourceMethodKey, usize)>,
+        attributes: &Struct,
+    ) -> Result<Self, DataStoreError> {
+        Self::new(settings, DiskDataStoreConfig::from_attributes(attributes)?)
+    }
+
+    fn get_reader(
+        &self,
+        collector_key: &ResourceMethodKey,
+    ) -> Result<DiskDataStoreReader, DataStoreError> {
+        let spool = self.get_spool(collector_key)?;
+        let mut guard = spool.borrow_mut();
+        if guard.in_use {
+            return Err(DataStoreError::BufferInUse(collector_key.clone()));
+        }
+        guard.in_use = true;
+        Ok(DiskDataStoreReader {
+            spool: spool.clone(),
+            segment: 0,
+            offset: 0,
+            messages_read: 0,
+            contents: None,
+        })
+    }
+}
+
+pub struct DiskDataStoreReader {
+    spool: Rc<RefCell<CollectorSpool>>,
+    // position of the next message to read
+    segment: usize,
+    offset: usize,
+    messages_read: usize,
+    contents: Option<Vec<u8>>,
+}
+
+impl DataStoreReader for DiskDataStoreReader {
+    fn read_next_message(&mut self) -> Result<BytesMut, DataStoreError> {
+        let spool = self.spool.borrow();
+        loop {
+            let Some(segment) = spool.segments.get(self.segment) else {
+                return Ok(BytesMut::with_capacity(0));
+            };
+            if self.offset < segment.len {
+                break;
+            }
+            self.segment += 1;
+            self.offset = 0;
+            self.contents = None;
+        }
+        if self.contents.is_none() {
+            self.contents = Some(fs::read(&spool.segments[self.segment].path)?);
+        }
+        let mut contents = &self.contents.as_ref().unwrap()[self.offset..];
+        let encoded_len = decode_varint(&mut contents)? as usize;
+        if encoded_len > contents.remaining() {
+            return Err(DataStoreError::DataIntegrityError);
+        }
+        self.offset += length_delimiter_len(encoded_len) + encoded_len;
+        self.messages_read += 1;
+        Ok(BytesMut::from(&contents[..encoded_len]))
+    }
+
+    fn messages_remaining(&self) -> Result<usize, DataStoreError> {
+        let spool = self.spool.borrow();
+        let spooled: usize = spool.segments.iter().map(|s| s.messages).sum();
+        Ok(spooled - self.messages_read)
+    }
+
+    fn flush(self) {
+        let mut spool = self.spool.borrow_mut();
+        let mut messages_read = self.messages_read;
+        for _ in 0..self.segment {
+            messages_read -= spool.segments[0].messages;
+            if let Err(err) = spool.drop_oldest_segment() {
+                log::error!("couldn't remove synced segment: {:?}", err);
+            }
+        }
+        if spool
+            .segments
+            .front()
+            .is_some_and(|segment| self.offset >= segment.len)
+        {
+            if let Err(err) = spool.drop_oldest_segment() {
+                log::error!("couldn't remove synced segment: {:?}", err);
+            }
+        } else if self.offset > 0 {
+            if let Err(err) = spool.drop_synced_messages(self.offset, messages_read) {
+                log::error!("couldn't remove synced messages: {:?}", err);
+            }
+        }
+    }
+}
+
+impl Drop for DiskDataStoreReader {
+    fn drop(&mut self) {
+        self.spool.borrow_mut().in_use = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::io::Write;
+    use std::time::Duration;
+
+    use prost::Message;
+
+    use super::{to_wall_clock, DiskDataStore, DiskDataStoreConfig, SPOOL_DIR};
+    use crate::common::data_collector::{CollectionMethod, ResourceMethodKey};
+    use crate::common::data_store::{DataStore, DataStoreError, DataStoreReader, WriteMode};
+    use crate::google::protobuf::{value::Kind, Struct, Timestamp, Value};
+    use crate::proto::app::data_sync::v1::{sensor_data::Data, SensorData, SensorMetadata};
+
+    fn reading(value: f64) -> SensorData {
+        SensorData {
+            metadata: None,
+            data: Some(Data::Struct(Struct {
+                fields: HashMap::from([(
+                    "thing".to_string(),
+                    Value {
+                        kind: Some(Kind::NumberValue(value)),
+                    },
+                )]),
+            })),
+        }
+    }
+
+    fn timed_reading(value: f64, requested_s: i64, received_s: i64) -> SensorData {
+        let timestamp = |seconds| Some(Timestamp { seconds, nanos: 0 });
+        SensorData {
+            metadata: Some(SensorMetadata {
+                time_requested: timestamp(requested_s),
+                time_received: timestamp(received_s),
+            }),
+            ..reading(value)
+        }
+    }
+
+    #[test_log::test]
+    fn test_to_wall_clock() {
+        let now = Duration::from_secs(1_700_000_000);
+        let mut msg = timed_reading(1.0, 9, 10);
+        to_wall_clock(&mut msg, now);
+        assert_eq!(msg, timed_reading(1.0, 1_699_999_999, 1_700_000_000));
+        // already a wall-clock time
+        to_wall_clock(&mut msg, now + Duration::from_secs(60));
+        assert_eq!(msg, timed_reading(1.0, 1_699_999_999, 1_700_000_000));
+
+        // the clock isn't set
+        let mut msg = timed_reading(1.0, 9, 10);
+        to_wall_clock(&mut msg, Duration::from_secs(10));
+        assert_eq!(msg, timed_reading(1.0, 9, 10));
+        let mut msg = reading(1.0);
+        to_wall_clock(&mut msg, now);
+        assert_eq!(msg, reading(1.0));
+    }
+
+    #[test_log::test]
+    fn test_disk_data_store() {
+        let capture_dir =
+            std::env::temp_dir().join(format!("micro-rdk-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&capture_dir);
+        let key = ResourceMethodKey {
+            r_name: "thing".to_string(),
+            component_type: "rdk:component:sensor".to_string(),
+            method: CollectionMethod::Readings,
+        };
+        let msg_len = reading(0.0).encode_length_delimited_to_vec().len();
+        let config = DiskDataStoreConfig {
+            capture_dir: capture_dir.clone(),
+            segment_size: 3 * msg_len,
+            max_size: 9 * msg_len as u64,
+        };
+        let mut store = DiskDataStore::new(vec![(key.clone(), 1000)], config.clone()).unwrap();
+
+        for i in 0..9 {
+            assert!(store
+                .write_message(&key, reading(i as f64), WriteMode::PreserveOrFail)
+                .is_ok());
+        }
+        let spooled = std::fs::read_dir(capture_dir.join(SPOOL_DIR))
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap()
+            .path();
+        assert_eq!(std::fs::read_dir(&spooled).unwrap().count(), 3);
+
+        // the spool is full
+        assert!(matches!(
+            store.write_message(&key, reading(9.0), WriteMode::PreserveOrFail),
+            Err(DataStoreError::DataBufferFull(_))
+        ));
+        // dropping the oldest segment
+        assert!(store
+            .write_message(&key, reading(9.0), WriteMode::OverwriteOldest)
+            .is_ok());
+
+        let mut reader = store.get_reader(&key).unwrap();
+        assert!(matches!(
+            store.write_message(&key, reading(10.0), WriteMode::OverwriteOldest),
+            Err(DataStoreError::BufferInUse(_))
+        ));
+        assert_eq!(reader.messages_remaining().unwrap(), 7);
+        for i in 3..8 {
+            let msg = SensorData::decode(reader.read_next_message().unwrap()).unwrap();
+            assert_eq!(msg, reading(i as f64));
+        }
+        assert_eq!(reader.messages_remaining().unwrap(), 2);
+        reader.flush();
+        // the synced segment was removed
+        assert_eq!(std::fs::read_dir(&spooled).unwrap().count(), 2);
+
+        let mut reader = store.get_reader(&key).unwrap();
+        assert_eq!(reader.messages_remaining().unwrap(), 2);
+        let msg = SensorData::decode(reader.read_next_message().unwrap()).unwrap();
+        assert_eq!(msg, reading(8.0));
+        let msg = SensorData::decode(reader.read_next_message().unwrap()).unwrap();
+        assert_eq!(msg, reading(9.0));
+        assert!(reader.read_next_message().unwrap().is_empty());
+        reader.flush();
+        assert_eq!(std::fs::read_dir(&spooled).unwrap().count(), 0);
+
+        // data left over from a previous run is replayed, timed with the wall clock
+        let received = std::time::SystemTime::now()
+            .duration_since(std::time::SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        assert!(store
+            .write_message(&key, timed_reading(10.0, 4, 5), WriteMode::PreserveOrFail)
+            .is_ok());
+        assert!(store
+            .write_message(&key, reading(11.0), WriteMode::PreserveOrFail)
+            .is_ok());
+        drop(store);
+        let segment = std::fs::read_dir(&spooled)
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap()
+            .path();
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&segment)
+            .unwrap();
+        // data that can't be dated anymore and a message cut short are dropped
+        file.write_all(&timed_reading(12.0, 4, 5).encode_length_delimited_to_vec())
+            .unwrap();
+        file.write_all(&reading(13.0).encode_length_delimited_to_vec()[..4])
+            .unwrap();
+        drop(file);
+
+        let mut store = DiskDataStore::new(vec![(key.clone(), 1000)], config.clone()).unwrap();
+        let mut reader = store.get_reader(&key).unwrap();
+        assert_eq!(reader.messages_remaining().unwrap(), 2);
+        let msg = SensorData::decode(reader.read_next_message().unwrap()).unwrap();
+        let metadata = msg.metadata.unwrap();
+        let received_s = metadata.time_received.unwrap().seconds;
+        assert!((received..received + 5).contains(&received_s));
+        assert_eq!(metadata.time_requested.unwrap().seconds, received_s - 1);
+        let msg = SensorData::decode(reader.read_next_message().unwrap()).unwrap();
+        assert_eq!(msg, reading(11.0));
+        drop(reader);
+
+        // new data goes to a new segment after the replayed ones
+        assert!(store
+            .write_message(&key, reading(14.0), WriteMode::PreserveOrFail)
+            .is_ok());
+        assert_eq!(std::fs::read_dir(&spooled).unwrap().count(), 2);
+        let mut reader = store.get_reader(&key).unwrap();
+        assert_eq!(reader.messages_remaining().unwrap(), 3);
+        for _ in 0..3 {
+            assert!(!reader.read_next_message().unwrap().is_empty());
+        }
+        reader.flush();
+        let store = DiskDataStore::new(vec![(key.clone(), 1000)], config).unwrap();
+        assert_eq!(
+            store
+                .get_reader(&key)
+                .unwrap()
+                .messages_remaining()
+                .unwrap(),
+            0
+        );
+
+        std::fs::remove_dir_all(capture_dir).unwrap();
+    }
+
+    #[test_log::test]
+    fn test_partially_synced_segment() {
+        let capture_dir =
+            std::env::temp_dir().join(format!("micro-rdk-test-partial-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&capture_dir);
+        let key = ResourceMethodKey {
+            r_name: "thing".to_string(),
+            component_type: "rdk:component:sensor".to_string(),
+            method: CollectionMethod::Readings,
+        };
+        let msg_len = reading(0.0).encode_length_delimited_to_vec().len();
+        let config = DiskDataStoreConfig {
+            capture_dir: capture_dir.clone(),
+            segment_size: 3 * msg_len,
+            max_size: 9 * msg_len as u64,
+        };
+        let mut store = DiskDataStore::new(vec![(key.clone(), 1000)], config.clone()).unwrap();
+        for i in 0..3 {
+            assert!(store
+                .write_message(&key, reading(i as f64), WriteMode::PreserveOrFail)
+                .is_ok());
+        }
+        let mut reader = store.get_reader(&key).unwrap();
+        let msg = SensorData::decode(reader.read_next_message().unwrap()).unwrap();
+        assert_eq!(msg, reading(0.0));
+        reader.flush();
+        assert_eq!(
+            store
+                .get_reader(&key)
+                .unwrap()
+                .messages_remaining()
+                .unwrap(),
+            2
+        );
+        drop(store);
+
+        // the synced message isn't uploaded again by the next run
+        let store = DiskDataStore::new(vec![(key.clone(), 1000)], config).unwrap();
+        let mut reader = store.get_reader(&key).unwrap();
+        assert_eq!(reader.messages_remaining().unwrap(), 2);
+        for i in 1..3 {
+            let msg = SensorData::decode(reader.read_next_message().unwrap()).unwrap();
+            assert_eq!(msg, reading(i as f64));
+        }
+        drop(reader);
+        drop(store);
+
+        // a spool that can't be created is reported as such, the robot then keeps captured data
+        // in RAM
+        let not_a_dir = capture_dir.join("file");
+        std::fs::write(&not_a_dir, b"").unwrap();
+        let config = DiskDataStoreConfig {
+            capture_dir: not_a_dir,
+            ..Default::default()
+        };
+        assert!(matches!(
+            DiskDataStore::new(vec![(key, 1000)], config),
+            Err(DataStoreError::SpoolError(_))
+        ));
+
+        std::fs::remove_dir_all(capture_dir).unwrap();
+    }
+}
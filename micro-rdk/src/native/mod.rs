@@ -1,4 +1,6 @@
 pub mod certificate;
+#[cfg(feature = "data")]
+pub mod data_store;
 pub mod dtls;
 pub mod log;
 pub mod tcp;
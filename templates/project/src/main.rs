@@ -3,12 +3,21 @@ const PASS: Option<&str> = option_env!("MICRO_RDK_WIFI_PASSWORD");
 const ROBOT_ID: Option<&str> = option_env!("MICRO_RDK_ROBOT_ID");
 const ROBOT_SECRET: Option<&str> = option_env!("MICRO_RDK_ROBOT_SECRET");
 const ROBOT_APP_ADDRESS: Option<&str> = option_env!("MICRO_RDK_ROBOT_APP_ADDRESS");
+const PROVISIONING_MANUFACTURER: &str = match option_env!("MICRO_RDK_PROVISIONING_MANUFACTURER") {
+    Some(manufacturer) => manufacturer,
+    None => "viam",
+};
+const PROVISIONING_MODEL: &str = match option_env!("MICRO_RDK_PROVISIONING_MODEL") {
+    Some(model) => model,
+    None => "esp32",
+};
+const DNS_SERVERS: Option<&str> = option_env!("MICRO_RDK_DNS_SERVERS");
 
 use std::rc::Rc;
 
 use micro_rdk::{
     common::{
-        conn::{server::WebRtcConfiguration, viam::ViamServerBuilder},
+        conn::{network::parse_dns_servers, server::WebRtcConfiguration, viam::ViamServerBuilder},
         credentials_storage::{
             RobotConfigurationStorage, RobotCredentials, WifiCredentialStorage, WifiCredentials,
         },
@@ -108,8 +117,8 @@ fn main() {
     }
 
     let mut info = ProvisioningInfo::default();
-    info.set_manufacturer("viam".to_owned());
-    info.set_model("esp32".to_owned());
+    info.set_manufacturer(PROVISIONING_MANUFACTURER.to_owned());
+    info.set_model(PROVISIONING_MODEL.to_owned());
 
     let mut registry = Box::<ComponentRegistry>::default();
     if let Err(e) = register_modules(&mut registry) {
@@ -130,7 +139,13 @@ fn main() {
         .with_default_tasks()
         .with_component_registry(registry);
 
-    let builder = { builder.with_wifi_manager(Box::new(Esp32WifiNetwork::new().unwrap())) };
+    let builder = {
+        let mut network = Esp32WifiNetwork::new().unwrap();
+        if let Some(dns_servers) = DNS_SERVERS {
+            network.set_dns_servers(parse_dns_servers(dns_servers));
+        }
+        builder.with_wifi_manager(Box::new(network))
+    };
     let mdns = Esp32Mdns::new("".to_owned()).unwrap();
 
     let mut server = { builder.build(Esp32H2Connector::default(), Executor::new(), mdns) };